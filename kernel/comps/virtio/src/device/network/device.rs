@@ -1,9 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use alloc::{
-    boxed::Box, collections::linked_list::LinkedList, string::ToString, sync::Arc, vec::Vec,
+    boxed::Box, collections::linked_list::LinkedList, format, string::String, sync::Arc, vec::Vec,
+};
+use core::{
+    fmt::Debug,
+    mem::size_of,
+    sync::atomic::{AtomicUsize, Ordering},
 };
-use core::{fmt::Debug, mem::size_of};
 
 use aster_bigtcp::device::{Checksum, DeviceCapabilities, Medium};
 use aster_network::{
@@ -125,13 +129,17 @@ impl NetworkDevice {
             debug!("network device config space change");
         }
 
-        /// Interrupt handlers if network device receives/sends some packet
-        fn handle_send_event(_: &TrapFrame) {
-            aster_network::handle_send_irq(super::DEVICE_NAME);
-        }
-        fn handle_recv_event(_: &TrapFrame) {
-            aster_network::handle_recv_irq(super::DEVICE_NAME);
-        }
+        let name = new_device_name();
+
+        // Interrupt handlers if network device receives/sends some packet
+        let handle_send_event = {
+            let name = name.clone();
+            move |_: &TrapFrame| aster_network::handle_send_irq(&name)
+        };
+        let handle_recv_event = {
+            let name = name.clone();
+            move |_: &TrapFrame| aster_network::handle_recv_irq(&name)
+        };
 
         device
             .transport
@@ -148,10 +156,7 @@ impl NetworkDevice {
 
         device.transport.finish_init();
 
-        aster_network::register_device(
-            super::DEVICE_NAME.to_string(),
-            Arc::new(SpinLock::new(device)),
-        );
+        aster_network::register_device(name, Arc::new(SpinLock::new(device)));
         Ok(())
     }
 
@@ -266,6 +271,14 @@ impl NetworkDevice {
     }
 }
 
+/// Allocates a unique name for a new virtio-net device.
+fn new_device_name() -> String {
+    static NEXT_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+    let index = NEXT_INDEX.fetch_add(1, Ordering::Relaxed);
+    format!("{}{}", super::DEVICE_NAME_PREFIX, index)
+}
+
 fn queue_to_network_error(err: QueueError) -> VirtioNetError {
     match err {
         QueueError::NotReady => VirtioNetError::NotReady,
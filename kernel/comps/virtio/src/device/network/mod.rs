@@ -4,4 +4,8 @@ pub mod config;
 pub mod device;
 pub mod header;
 
-pub static DEVICE_NAME: &str = "Virtio-Net";
+/// The prefix of the names of virtio-net devices.
+///
+/// The devices are named `Virtio-Net0`, `Virtio-Net1`, and so on, in the order in which they are
+/// initialized.
+pub static DEVICE_NAME_PREFIX: &str = "Virtio-Net";
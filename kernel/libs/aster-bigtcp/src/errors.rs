@@ -9,6 +9,17 @@ pub enum BindError {
     InUse,
 }
 
+/// An error describing the reason why the addresses or the routes of an iface cannot be updated.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConfigError {
+    /// The address or the route already exists.
+    Exists,
+    /// The address or the route does not exist.
+    NotFound,
+    /// There is no room for more addresses or routes.
+    Exhausted,
+}
+
 pub mod tcp {
     pub use smoltcp::socket::tcp::{ConnectError, ListenError, RecvError, SendError};
 }
@@ -26,3 +37,49 @@ pub mod udp {
         BufferFull,
     }
 }
+
+pub mod packet {
+    /// An error returned by [`Iface::send_frame`].
+    ///
+    /// [`Iface::send_frame`]: crate::iface::Iface::send_frame
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum SendError {
+        /// The frame is larger than the maximum frame size of the iface.
+        TooLarge,
+        /// The iface does not transmit link-layer frames.
+        Unsupported,
+        /// The transmission queue of the iface is full.
+        BufferFull,
+    }
+
+    /// An error returned by [`PacketSocket::recv`].
+    ///
+    /// [`PacketSocket::recv`]: crate::socket::PacketSocket::recv
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum RecvError {
+        /// There are no frames to receive.
+        Exhausted,
+    }
+}
+
+pub mod raw {
+    /// An error returned by [`RawSocket::send`].
+    ///
+    /// [`RawSocket::send`]: crate::socket::RawSocket::send
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum SendError {
+        /// The packet is larger than the MTU of the iface.
+        TooLarge,
+        /// The send buffer is full.
+        BufferFull,
+    }
+
+    /// An error returned by [`RawSocket::recv`].
+    ///
+    /// [`RawSocket::recv`]: crate::socket::RawSocket::recv
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum RecvError {
+        /// There are no packets to receive.
+        Exhausted,
+    }
+}
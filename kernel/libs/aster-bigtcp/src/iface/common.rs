@@ -4,37 +4,56 @@ use alloc::{
     collections::{
         btree_map::{BTreeMap, Entry},
         btree_set::BTreeSet,
+        vec_deque::VecDeque,
     },
     string::String,
     sync::Arc,
+    vec,
     vec::Vec,
 };
+use core::sync::atomic::{AtomicU32, Ordering};
 
 use keyable_arc::KeyableArc;
 use ostd::sync::{LocalIrqDisabled, SpinLock, SpinLockGuard};
 use smoltcp::{
-    iface::{packet::Packet, Context},
-    phy::Device,
-    wire::{IpAddress, IpEndpoint, Ipv4Address, Ipv4Packet},
+    iface::{packet::Packet, Context, Route},
+    phy::{Device, TxToken},
+    time::Instant,
+    wire::{
+        EthernetAddress, EthernetFrame, EthernetProtocol, HardwareAddress, IpAddress, IpCidr,
+        IpEndpoint, IpListenEndpoint, Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr,
+    },
 };
 
 use super::{
-    poll::{FnHelper, PollContext},
+    flag::{InterfaceFlags, InterfaceType},
+    iface::Ipv4Route,
+    multicast::MulticastGroups,
+    poll::{FnHelper, IpPacket, PollContext},
     port::BindPortConfig,
     time::get_network_timestamp,
     Iface,
 };
 use crate::{
-    errors::BindError,
+    errors::{packet::SendError, BindError, ConfigError},
     ext::Ext,
-    socket::{TcpConnectionBg, TcpListenerBg, UdpSocketBg},
+    socket::{
+        FrameDirection, PacketSocketBg, RawSocketBg, TcpConnectionBg, TcpListenerBg, UdpSocketBg,
+    },
 };
 
 pub struct IfaceCommon<E: Ext> {
+    index: u32,
     name: String,
+    type_: InterfaceType,
+    flags: AtomicU32,
     interface: SpinLock<smoltcp::iface::Interface, LocalIrqDisabled>,
     used_ports: SpinLock<BTreeMap<u16, usize>, LocalIrqDisabled>,
     sockets: SpinLock<SocketSet<E>, LocalIrqDisabled>,
+    packet_sockets: SpinLock<BTreeSet<KeyableArc<PacketSocketBg<E>>>, LocalIrqDisabled>,
+    /// The link-layer frames sent by the user that have not been transmitted.
+    frames_to_send: SpinLock<VecDeque<Vec<u8>>, LocalIrqDisabled>,
+    multicast_groups: MulticastGroups,
     sched_poll: E::ScheduleNextPoll,
 }
 
@@ -42,11 +61,25 @@ pub(super) struct SocketSet<E: Ext> {
     pub(super) tcp_conn: BTreeSet<KeyableArc<TcpConnectionBg<E>>>,
     pub(super) tcp_listen: BTreeSet<KeyableArc<TcpListenerBg<E>>>,
     pub(super) udp: BTreeSet<KeyableArc<UdpSocketBg<E>>>,
+    pub(super) raw: BTreeSet<KeyableArc<RawSocketBg<E>>>,
 }
 
+/// The maximum number of the frames that are sent by the user and have not been transmitted.
+///
+/// This is smaller than the transmission queue length of Linux (i.e., 1000), because the frames
+/// are queued only when the device is busy.
+const MAX_FRAMES_TO_SEND: usize = 64;
+
+/// The index of the next iface.
+///
+/// Like Linux, the indexes of ifaces start from one.
+static NEXT_IFACE_INDEX: AtomicU32 = AtomicU32::new(1);
+
 impl<E: Ext> IfaceCommon<E> {
     pub(super) fn new(
         name: String,
+        type_: InterfaceType,
+        flags: InterfaceFlags,
         interface: smoltcp::iface::Interface,
         sched_poll: E::ScheduleNextPoll,
     ) -> Self {
@@ -54,28 +87,103 @@ impl<E: Ext> IfaceCommon<E> {
             tcp_conn: BTreeSet::new(),
             tcp_listen: BTreeSet::new(),
             udp: BTreeSet::new(),
+            raw: BTreeSet::new(),
         };
 
         Self {
+            index: NEXT_IFACE_INDEX.fetch_add(1, Ordering::Relaxed),
             name,
+            type_,
+            flags: AtomicU32::new(flags.bits()),
             interface: SpinLock::new(interface),
             used_ports: SpinLock::new(BTreeMap::new()),
             sockets: SpinLock::new(sockets),
+            packet_sockets: SpinLock::new(BTreeSet::new()),
+            frames_to_send: SpinLock::new(VecDeque::new()),
+            multicast_groups: MulticastGroups::new(),
             sched_poll,
         }
     }
 
+    pub(super) fn index(&self) -> u32 {
+        self.index
+    }
+
     pub(super) fn name(&self) -> &str {
         &self.name
     }
 
+    pub(super) fn type_(&self) -> InterfaceType {
+        self.type_
+    }
+
+    pub(super) fn flags(&self) -> InterfaceFlags {
+        InterfaceFlags::from_bits_truncate(self.flags.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn set_up(&self, is_up: bool) {
+        let up_flags = InterfaceFlags::UP | InterfaceFlags::RUNNING | InterfaceFlags::LOWER_UP;
+        if is_up {
+            self.flags.fetch_or(up_flags.bits(), Ordering::Relaxed);
+        } else {
+            self.flags.fetch_and(!up_flags.bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn is_up(&self) -> bool {
+        self.flags().contains(InterfaceFlags::UP)
+    }
+
+    pub(super) fn mtu(&self) -> usize {
+        self.interface.lock().context().caps.ip_mtu()
+    }
+
+    pub(super) fn hardware_addr(&self) -> Option<EthernetAddress> {
+        match self.interface.lock().hardware_addr() {
+            HardwareAddress::Ethernet(ether_addr) => Some(ether_addr),
+            HardwareAddress::Ip => None,
+        }
+    }
+
     pub(super) fn ipv4_addr(&self) -> Option<Ipv4Address> {
         self.interface.lock().ipv4_addr()
     }
 
+    pub(super) fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.interface
+            .lock()
+            .ip_addrs()
+            .iter()
+            .filter_map(|cidr| match cidr {
+                IpCidr::Ipv4(ipv4_cidr) => Some(*ipv4_cidr),
+                IpCidr::Ipv6(_) => None,
+            })
+            .collect()
+    }
+
+    pub(super) fn ipv6_addr(&self) -> Option<Ipv6Address> {
+        self.interface.lock().ipv6_addr()
+    }
+
+    pub(super) fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.interface
+            .lock()
+            .ip_addrs()
+            .iter()
+            .filter_map(|cidr| match cidr {
+                IpCidr::Ipv4(_) => None,
+                IpCidr::Ipv6(ipv6_cidr) => Some(*ipv6_cidr),
+            })
+            .collect()
+    }
+
     pub(super) fn sched_poll(&self) -> &E::ScheduleNextPoll {
         &self.sched_poll
     }
+
+    pub(super) fn multicast_groups(&self) -> &MulticastGroups {
+        &self.multicast_groups
+    }
 }
 
 impl<E: Ext> IfaceCommon<E> {
@@ -85,6 +193,125 @@ impl<E: Ext> IfaceCommon<E> {
     }
 }
 
+impl<E: Ext> IfaceCommon<E> {
+    pub(super) fn add_ipv4_cidr(&self, cidr: Ipv4Cidr) -> Result<(), ConfigError> {
+        self.add_ip_cidr(IpCidr::Ipv4(cidr))
+    }
+
+    pub(super) fn remove_ipv4_cidr(&self, addr: Ipv4Address) -> Result<Ipv4Cidr, ConfigError> {
+        match self.remove_ip_cidr(IpAddress::Ipv4(addr))? {
+            IpCidr::Ipv4(cidr) => Ok(cidr),
+            IpCidr::Ipv6(_) => unreachable!("the removed address is an IPv4 address"),
+        }
+    }
+
+    pub(super) fn add_ipv6_cidr(&self, cidr: Ipv6Cidr) -> Result<(), ConfigError> {
+        self.add_ip_cidr(IpCidr::Ipv6(cidr))
+    }
+
+    pub(super) fn remove_ipv6_cidr(&self, addr: Ipv6Address) -> Result<Ipv6Cidr, ConfigError> {
+        match self.remove_ip_cidr(IpAddress::Ipv6(addr))? {
+            IpCidr::Ipv4(_) => unreachable!("the removed address is an IPv6 address"),
+            IpCidr::Ipv6(cidr) => Ok(cidr),
+        }
+    }
+
+    fn add_ip_cidr(&self, cidr: IpCidr) -> Result<(), ConfigError> {
+        let mut result = Ok(());
+
+        self.interface.lock().update_ip_addrs(|ip_addrs| {
+            if ip_addrs
+                .iter()
+                .any(|ip_addr| ip_addr.address() == cidr.address())
+            {
+                result = Err(ConfigError::Exists);
+            } else if ip_addrs.push(cidr).is_err() {
+                result = Err(ConfigError::Exhausted);
+            }
+        });
+
+        result
+    }
+
+    fn remove_ip_cidr(&self, addr: IpAddress) -> Result<IpCidr, ConfigError> {
+        let mut result = Err(ConfigError::NotFound);
+
+        self.interface.lock().update_ip_addrs(|ip_addrs| {
+            if let Some(pos) = ip_addrs
+                .iter()
+                .position(|ip_addr| ip_addr.address() == addr)
+            {
+                result = Ok(ip_addrs.remove(pos));
+            }
+        });
+
+        result
+    }
+
+    pub(super) fn ipv4_routes(&self) -> Vec<Ipv4Route> {
+        let mut routes = Vec::new();
+
+        self.interface.lock().routes_mut().update(|table| {
+            routes.extend(
+                table
+                    .iter()
+                    .filter_map(|route| match (route.cidr, route.via_router) {
+                        (IpCidr::Ipv4(cidr), IpAddress::Ipv4(gateway)) => {
+                            Some(Ipv4Route { cidr, gateway })
+                        }
+                        _ => None,
+                    }),
+            );
+        });
+
+        routes
+    }
+
+    pub(super) fn add_ipv4_route(&self, route: Ipv4Route) -> Result<(), ConfigError> {
+        let mut result = Ok(());
+
+        self.interface.lock().routes_mut().update(|table| {
+            if table
+                .iter()
+                .any(|old_route| old_route.cidr == IpCidr::Ipv4(route.cidr))
+            {
+                result = Err(ConfigError::Exists);
+                return;
+            }
+
+            let new_route = Route {
+                cidr: IpCidr::Ipv4(route.cidr),
+                via_router: IpAddress::Ipv4(route.gateway),
+                preferred_until: None,
+                expires_at: None,
+            };
+            if table.push(new_route).is_err() {
+                result = Err(ConfigError::Exhausted);
+            }
+        });
+
+        result
+    }
+
+    pub(super) fn remove_ipv4_route(&self, cidr: Ipv4Cidr) -> Result<Ipv4Route, ConfigError> {
+        let mut result = Err(ConfigError::NotFound);
+
+        self.interface.lock().routes_mut().update(|table| {
+            if let Some(pos) = table
+                .iter()
+                .position(|route| route.cidr == IpCidr::Ipv4(cidr))
+            {
+                let IpAddress::Ipv4(gateway) = table.remove(pos).via_router else {
+                    unreachable!("the gateway of an IPv4 route is an IPv4 address");
+                };
+                result = Ok(Ipv4Route { cidr, gateway });
+            }
+        });
+
+        result
+    }
+}
+
 const IP_LOCAL_PORT_START: u16 = 32768;
 const IP_LOCAL_PORT_END: u16 = 60999;
 
@@ -92,10 +319,11 @@ impl<E: Ext> IfaceCommon<E> {
     pub(super) fn bind(
         &self,
         iface: Arc<dyn Iface<E>>,
+        addr: Option<IpAddress>,
         config: BindPortConfig,
     ) -> core::result::Result<BoundPort<E>, BindError> {
         let port = self.bind_port(config)?;
-        Ok(BoundPort { iface, port })
+        Ok(BoundPort { iface, addr, port })
     }
 
     /// Allocates an unused ephemeral port.
@@ -188,6 +416,120 @@ impl<E: Ext> IfaceCommon<E> {
         let removed = sockets.udp.remove(socket);
         debug_assert!(removed);
     }
+
+    pub(crate) fn register_raw_socket(&self, socket: KeyableArc<RawSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        let inserted = sockets.raw.insert(socket);
+        debug_assert!(inserted);
+    }
+
+    pub(crate) fn remove_raw_socket(&self, socket: &KeyableArc<RawSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        let removed = sockets.raw.remove(socket);
+        debug_assert!(removed);
+    }
+
+    pub(crate) fn register_packet_socket(&self, socket: KeyableArc<PacketSocketBg<E>>) {
+        let mut packet_sockets = self.packet_sockets.lock();
+        let inserted = packet_sockets.insert(socket);
+        debug_assert!(inserted);
+    }
+
+    pub(crate) fn remove_packet_socket(&self, socket: &KeyableArc<PacketSocketBg<E>>) {
+        let mut packet_sockets = self.packet_sockets.lock();
+        let removed = packet_sockets.remove(socket);
+        debug_assert!(removed);
+    }
+}
+
+impl<E: Ext> IfaceCommon<E> {
+    /// Delivers an Ethernet frame received or sent by the iface to the packet sockets.
+    pub(super) fn process_frame(
+        &self,
+        frame: &[u8],
+        protocol: EthernetProtocol,
+        direction: FrameDirection,
+    ) {
+        let packet_sockets = self.packet_sockets.lock();
+        for socket in packet_sockets.iter() {
+            socket.process(frame, protocol, direction);
+        }
+    }
+
+    pub(super) fn send_frame<F, R>(&self, size: usize, f: F) -> Result<R, SendError>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let max_frame_len = {
+            let interface = self.interface.lock();
+            let caps = &interface.context().caps;
+            match interface.hardware_addr() {
+                HardwareAddress::Ethernet(_) => caps.max_transmission_unit,
+                // Like Linux, the frames on the loopback iface have Ethernet headers.
+                HardwareAddress::Ip if self.type_ == InterfaceType::Loopback => {
+                    EthernetFrame::<&[u8]>::buffer_len(caps.ip_mtu())
+                }
+                _ => return Err(SendError::Unsupported),
+            }
+        };
+        if size > max_frame_len {
+            return Err(SendError::TooLarge);
+        }
+
+        let mut frames_to_send = self.frames_to_send.lock();
+        if frames_to_send.len() >= MAX_FRAMES_TO_SEND {
+            return Err(SendError::BufferFull);
+        }
+
+        let mut frame = vec![0; size];
+        let result = f(&mut frame);
+        frames_to_send.push_back(frame);
+
+        Ok(result)
+    }
+
+    /// Transmits the link-layer frames sent by the user.
+    fn transmit_frames<D: Device + ?Sized>(&self, device: &mut D, now: Instant) {
+        let mut frames_to_send = self.frames_to_send.lock();
+
+        while !frames_to_send.is_empty() {
+            let Some(tx_token) = device.transmit(now) else {
+                break;
+            };
+
+            let frame = frames_to_send.pop_front().unwrap();
+            if self.type_ == InterfaceType::Loopback {
+                self.loop_back_frame(&frame, tx_token);
+                continue;
+            }
+
+            tx_token.consume(frame.len(), |buffer| buffer.copy_from_slice(&frame));
+
+            if let Ok(ether_frame) = EthernetFrame::new_checked(frame.as_slice()) {
+                self.process_frame(&frame, ether_frame.ethertype(), FrameDirection::Outgoing);
+            }
+        }
+    }
+
+    /// Transmits a link-layer frame sent by the user on the loopback iface.
+    ///
+    /// The loopback device carries IP packets without link-layer headers, and receives whatever
+    /// it transmits. So the frame is delivered to the packet sockets as both an outgoing and an
+    /// incoming frame here, and only the IP packet in it is passed to the device.
+    fn loop_back_frame<T: TxToken>(&self, frame: &[u8], tx_token: T) {
+        let Ok(ether_frame) = EthernetFrame::new_checked(frame) else {
+            return;
+        };
+        let protocol = ether_frame.ethertype();
+
+        self.process_frame(frame, protocol, FrameDirection::Outgoing);
+        self.process_frame(frame, protocol, FrameDirection::Incoming);
+
+        if matches!(protocol, EthernetProtocol::Ipv4 | EthernetProtocol::Ipv6) {
+            let packet = ether_frame.payload();
+            tx_token.consume(packet.len(), |buffer| buffer.copy_from_slice(packet));
+        }
+    }
 }
 
 impl<E: Ext> IfaceCommon<E> {
@@ -203,19 +545,29 @@ impl<E: Ext> IfaceCommon<E> {
             &'pkt [u8],
             &'cx mut Context,
             D::TxToken<'tx>,
-            Option<(Ipv4Packet<&'pkt [u8]>, D::TxToken<'tx>)>,
+            Option<(IpPacket<'pkt>, D::TxToken<'tx>)>,
         >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
+        // An iface that is down neither transmits nor receives packets.
+        if !self.is_up() {
+            return None;
+        }
+
         let mut interface = self.interface();
         interface.context().now = get_network_timestamp();
 
+        // The frames sent by the user are queued before the packets generated below, so they are
+        // transmitted first.
+        self.transmit_frames(device, interface.context().now());
+
         let mut sockets = self.sockets.lock();
 
         loop {
             let mut new_tcp_conns = Vec::new();
 
-            let mut context = PollContext::new(interface.context(), &sockets, &mut new_tcp_conns);
+            let mut context =
+                PollContext::new(interface.context(), self, &sockets, &mut new_tcp_conns);
             context.poll_ingress(device, &mut process_phy, &mut dispatch_phy);
             context.poll_egress(device, &mut dispatch_phy);
 
@@ -245,6 +597,16 @@ impl<E: Ext> IfaceCommon<E> {
                 socket.on_events();
             }
         });
+        sockets.raw.iter().for_each(|socket| {
+            if socket.has_events() {
+                socket.on_events();
+            }
+        });
+        self.packet_sockets.lock().iter().for_each(|socket| {
+            if socket.has_events() {
+                socket.on_events();
+            }
+        });
 
         // Note that only TCP connections can have timers set, so as far as the time to poll is
         // concerned, we only need to consider TCP connections.
@@ -263,6 +625,7 @@ impl<E: Ext> IfaceCommon<E> {
 // FIXME: TCP and UDP ports are independent. Find a way to track the protocol here.
 pub struct BoundPort<E: Ext> {
     iface: Arc<dyn Iface<E>>,
+    addr: Option<IpAddress>,
     port: u16,
 }
 
@@ -278,12 +641,36 @@ impl<E: Ext> BoundPort<E> {
     }
 
     /// Returns the bound endpoint.
-    pub fn endpoint(&self) -> Option<IpEndpoint> {
-        let ip_addr = {
-            let ipv4_addr = self.iface().ipv4_addr()?;
-            IpAddress::Ipv4(ipv4_addr)
-        };
-        Some(IpEndpoint::new(ip_addr, self.port))
+    ///
+    /// If the port is bound to all IPv4 and IPv6 addresses, the unspecified IPv6 address is
+    /// returned, which is what `getsockname` reports for such `AF_INET6` sockets.
+    pub fn endpoint(&self) -> IpEndpoint {
+        let addr = self
+            .addr
+            .unwrap_or(IpAddress::Ipv6(Ipv6Address::UNSPECIFIED));
+        IpEndpoint::new(addr, self.port)
+    }
+
+    /// Returns the endpoint that the smoltcp sockets should listen at or bind to.
+    pub(crate) fn listen_endpoint(&self) -> IpListenEndpoint {
+        IpListenEndpoint {
+            addr: self.addr.filter(|addr| !addr.is_unspecified()),
+            port: self.port,
+        }
+    }
+
+    /// Returns whether the packets sent to the destination address _may_ be processed by the
+    /// sockets bound to the port.
+    ///
+    /// The check only involves the IP version. Whether the address matches is checked by the
+    /// smoltcp sockets.
+    pub(crate) fn can_process(&self, dst_addr: &IpAddress) -> bool {
+        match (self.addr, dst_addr) {
+            (None, _) => true,
+            (Some(IpAddress::Ipv4(_)), IpAddress::Ipv4(_)) => true,
+            (Some(IpAddress::Ipv6(_)), IpAddress::Ipv6(_)) => true,
+            (Some(_), _) => false,
+        }
     }
 }
 
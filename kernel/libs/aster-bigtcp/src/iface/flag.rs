@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: MPL-2.0
+
+bitflags::bitflags! {
+    /// The flags of an iface.
+    ///
+    /// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/if.h>.
+    pub struct InterfaceFlags: u32 {
+        /// The iface is administratively up.
+        const UP          = 1 << 0;
+        /// The broadcast address is valid.
+        const BROADCAST   = 1 << 1;
+        const DEBUG       = 1 << 2;
+        /// The iface is a loopback iface.
+        const LOOPBACK    = 1 << 3;
+        const POINTOPOINT = 1 << 4;
+        const NOTRAILERS  = 1 << 5;
+        /// The iface is operationally up.
+        const RUNNING     = 1 << 6;
+        /// The iface does not use ARP.
+        const NOARP       = 1 << 7;
+        const PROMISC     = 1 << 8;
+        const ALLMULTI    = 1 << 9;
+        const MASTER      = 1 << 10;
+        const SLAVE       = 1 << 11;
+        /// The iface supports multicast.
+        const MULTICAST   = 1 << 12;
+        const PORTSEL     = 1 << 13;
+        const AUTOMEDIA   = 1 << 14;
+        const DYNAMIC     = 1 << 15;
+        /// The carrier of the iface is up.
+        const LOWER_UP    = 1 << 16;
+        const DORMANT     = 1 << 17;
+        const ECHO        = 1 << 18;
+    }
+}
+
+/// The hardware type of an iface.
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/if_arp.h>.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceType {
+    /// Ethernet (`ARPHRD_ETHER`).
+    Ether = 1,
+    /// Loopback (`ARPHRD_LOOPBACK`).
+    Loopback = 772,
+    /// No hardware header (`ARPHRD_NONE`).
+    None = 0xfffe,
+}
@@ -1,11 +1,19 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::sync::Arc;
+use alloc::{sync::Arc, vec::Vec};
 
-use smoltcp::wire::Ipv4Address;
+use smoltcp::wire::{EthernetAddress, IpAddress, Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr};
 
-use super::{port::BindPortConfig, BoundPort};
-use crate::{errors::BindError, ext::Ext};
+use super::{
+    flag::{InterfaceFlags, InterfaceType},
+    port::BindPortConfig,
+    BoundPort,
+};
+use crate::{
+    errors::{packet::SendError, BindError, ConfigError},
+    ext::Ext,
+    socket::NeedIfacePoll,
+};
 
 /// A network interface.
 ///
@@ -27,15 +35,27 @@ impl<E: Ext> dyn Iface<E> {
     /// If [`BindPortConfig::Ephemeral`] is specified, the iface will pick up an ephemeral port for
     /// the socket.
     ///
+    /// The socket is bound to the specified local address. An unspecified address (i.e.,
+    /// `0.0.0.0` or `::`) means all the addresses of that IP version, and `None` means all the
+    /// IPv4 and IPv6 addresses (i.e., a dual-stack socket).
+    ///
     /// FIXME: The reason for binding the socket and the iface together is because there are
     /// limitations inside smoltcp. See discussion at
     /// <https://github.com/smoltcp-rs/smoltcp/issues/779>.
     pub fn bind(
         self: &Arc<Self>,
+        addr: Option<IpAddress>,
         config: BindPortConfig,
     ) -> core::result::Result<BoundPort<E>, BindError> {
         let common = self.common();
-        common.bind(self.clone(), config)
+        common.bind(self.clone(), addr, config)
+    }
+
+    /// Gets the index of the iface.
+    ///
+    /// The index is unique among all ifaces and never changes.
+    pub fn index(&self) -> u32 {
+        self.common().index()
     }
 
     /// Gets the name of the iface.
@@ -45,19 +65,172 @@ impl<E: Ext> dyn Iface<E> {
         self.common().name()
     }
 
-    /// Gets the IPv4 address of the iface, if any.
+    /// Gets the hardware type of the iface.
+    pub fn type_(&self) -> InterfaceType {
+        self.common().type_()
+    }
+
+    /// Gets the flags of the iface.
+    pub fn flags(&self) -> InterfaceFlags {
+        self.common().flags()
+    }
+
+    /// Brings the iface up or down.
+    ///
+    /// An iface that is down neither transmits nor receives packets. The packets queued in the
+    /// sockets are transmitted after the iface is brought up and polled again.
+    pub fn set_up(&self, is_up: bool) {
+        self.common().set_up(is_up)
+    }
+
+    /// Gets the maximum transmission unit (MTU) of the iface.
     ///
-    /// FIXME: One iface may have multiple IPv4 addresses.
+    /// The MTU is the maximum size of the IP packets, excluding the link-layer headers.
+    pub fn mtu(&self) -> usize {
+        self.common().mtu()
+    }
+
+    /// Gets the Ethernet address of the iface, if any.
+    pub fn hardware_addr(&self) -> Option<EthernetAddress> {
+        self.common().hardware_addr()
+    }
+
+    /// Gets the primary IPv4 address of the iface, if any.
     pub fn ipv4_addr(&self) -> Option<Ipv4Address> {
         self.common().ipv4_addr()
     }
 
+    /// Gets all the IPv4 addresses of the iface with their prefix lengths.
+    ///
+    /// The primary IPv4 address, if any, comes first.
+    pub fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.common().ipv4_cidrs()
+    }
+
+    /// Adds an IPv4 address to the iface.
+    ///
+    /// If the iface has no IPv4 addresses, the new address becomes the primary one.
+    pub fn add_ipv4_cidr(&self, cidr: Ipv4Cidr) -> core::result::Result<(), ConfigError> {
+        self.common().add_ipv4_cidr(cidr)
+    }
+
+    /// Removes an IPv4 address from the iface.
+    ///
+    /// This method returns the removed address with its prefix length.
+    pub fn remove_ipv4_cidr(
+        &self,
+        addr: Ipv4Address,
+    ) -> core::result::Result<Ipv4Cidr, ConfigError> {
+        self.common().remove_ipv4_cidr(addr)
+    }
+
+    /// Gets the primary IPv6 address of the iface, if any.
+    pub fn ipv6_addr(&self) -> Option<Ipv6Address> {
+        self.common().ipv6_addr()
+    }
+
+    /// Gets all the IPv6 addresses of the iface with their prefix lengths.
+    ///
+    /// The primary IPv6 address, if any, comes first. For Ethernet ifaces, this is the link-local
+    /// address that is configured automatically.
+    pub fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.common().ipv6_cidrs()
+    }
+
+    /// Adds an IPv6 address to the iface.
+    ///
+    /// If the iface has no IPv6 addresses, the new address becomes the primary one.
+    pub fn add_ipv6_cidr(&self, cidr: Ipv6Cidr) -> core::result::Result<(), ConfigError> {
+        self.common().add_ipv6_cidr(cidr)
+    }
+
+    /// Removes an IPv6 address from the iface.
+    ///
+    /// This method returns the removed address with its prefix length.
+    pub fn remove_ipv6_cidr(
+        &self,
+        addr: Ipv6Address,
+    ) -> core::result::Result<Ipv6Cidr, ConfigError> {
+        self.common().remove_ipv6_cidr(addr)
+    }
+
+    /// Gets the IPv4 routes of the iface.
+    ///
+    /// The routes to the networks that the iface is directly connected to, i.e., the networks of
+    /// the IPv4 addresses, are implied and not included.
+    pub fn ipv4_routes(&self) -> Vec<Ipv4Route> {
+        self.common().ipv4_routes()
+    }
+
+    /// Adds an IPv4 route to the iface.
+    pub fn add_ipv4_route(&self, route: Ipv4Route) -> core::result::Result<(), ConfigError> {
+        self.common().add_ipv4_route(route)
+    }
+
+    /// Removes the IPv4 route to the destination network from the iface.
+    ///
+    /// This method returns the removed route.
+    pub fn remove_ipv4_route(
+        &self,
+        cidr: Ipv4Cidr,
+    ) -> core::result::Result<Ipv4Route, ConfigError> {
+        self.common().remove_ipv4_route(cidr)
+    }
+
+    /// Joins an IPv4 multicast group on the iface.
+    ///
+    /// The group can be joined multiple times (e.g., by different sockets), and it will not be
+    /// left until [`Self::leave_ipv4_multicast_group`] is called the same number of times.
+    ///
+    /// Polling the iface _may_ be required after this method succeeds.
+    pub fn join_ipv4_multicast_group(&self, group: Ipv4Address) -> NeedIfacePoll {
+        if self.common().multicast_groups().join_ipv4(group) {
+            NeedIfacePoll::TRUE
+        } else {
+            NeedIfacePoll::FALSE
+        }
+    }
+
+    /// Leaves an IPv4 multicast group on the iface.
+    ///
+    /// Polling the iface _may_ be required after this method succeeds.
+    pub fn leave_ipv4_multicast_group(&self, group: Ipv4Address) -> NeedIfacePoll {
+        if self.common().multicast_groups().leave_ipv4(group) {
+            NeedIfacePoll::TRUE
+        } else {
+            NeedIfacePoll::FALSE
+        }
+    }
+
+    /// Sends a link-layer frame through the iface.
+    ///
+    /// The closure is called to fill the frame, whose size is specified. The frame must contain
+    /// the link-layer header. Currently, only ifaces that transmit Ethernet frames support this
+    /// method.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send_frame<F, R>(&self, size: usize, f: F) -> core::result::Result<R, SendError>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        self.common().send_frame(size, f)
+    }
+
     /// Returns a reference to the associated [`ScheduleNextPoll`].
     pub fn sched_poll(&self) -> &E::ScheduleNextPoll {
         self.common().sched_poll()
     }
 }
 
+/// An IPv4 route that forwards the packets to a destination network through a gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Route {
+    /// The destination network.
+    pub cidr: Ipv4Cidr,
+    /// The gateway, which must be in a network that the iface is directly connected to.
+    pub gateway: Ipv4Address,
+}
+
 pub(super) mod internal {
     use crate::{ext::Ext, iface::common::IfaceCommon};
 
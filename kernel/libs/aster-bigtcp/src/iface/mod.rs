@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod common;
+mod flag;
 #[allow(clippy::module_inception)]
 mod iface;
+mod multicast;
 mod phy;
 mod poll;
 mod port;
@@ -10,7 +12,8 @@ mod sched;
 mod time;
 
 pub use common::BoundPort;
-pub use iface::Iface;
+pub use flag::{InterfaceFlags, InterfaceType};
+pub use iface::{Iface, Ipv4Route};
 pub use phy::{EtherIface, IpIface};
 pub use port::BindPortConfig;
 pub use sched::ScheduleNextPoll;
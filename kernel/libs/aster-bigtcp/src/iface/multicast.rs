@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! IPv4 multicast group membership.
+//!
+//! The membership is reported to the multicast routers with IGMPv2, which is described in
+//! <https://datatracker.ietf.org/doc/html/rfc2236>.
+
+use alloc::{
+    collections::{btree_map::BTreeMap, vec_deque::VecDeque},
+    vec::Vec,
+};
+
+use ostd::sync::{LocalIrqDisabled, SpinLock};
+use smoltcp::wire::Ipv4Address;
+
+pub(super) struct MulticastGroups {
+    inner: SpinLock<MulticastGroupsInner, LocalIrqDisabled>,
+}
+
+struct MulticastGroupsInner {
+    /// The joined groups and the number of times that they are joined.
+    ipv4_groups: BTreeMap<Ipv4Address, usize>,
+    /// The IGMP messages that have not been transmitted.
+    igmp_to_send: VecDeque<IgmpMessage>,
+}
+
+/// The all-hosts group, which every host joins implicitly.
+///
+/// Membership of this group is never reported.
+const ALL_HOSTS_GROUP: Ipv4Address = Ipv4Address::new(224, 0, 0, 1);
+
+/// The all-routers group, to which the IGMP Leave Group messages are sent.
+const ALL_ROUTERS_GROUP: Ipv4Address = Ipv4Address::new(224, 0, 0, 2);
+
+/// The maximum number of the IGMP messages that have not been transmitted.
+///
+/// The messages that exceed the limit are dropped. This is fine because IGMP messages are
+/// unreliable anyway and the routers will query the membership periodically.
+const MAX_IGMP_TO_SEND: usize = 64;
+
+impl MulticastGroups {
+    pub(super) fn new() -> Self {
+        let inner = MulticastGroupsInner {
+            ipv4_groups: BTreeMap::new(),
+            igmp_to_send: VecDeque::new(),
+        };
+
+        Self {
+            inner: SpinLock::new(inner),
+        }
+    }
+
+    /// Joins an IPv4 multicast group.
+    ///
+    /// The group can be joined multiple times. A report is generated only when the group is
+    /// joined for the first time. This method returns whether the report is generated.
+    pub(super) fn join_ipv4(&self, group: Ipv4Address) -> bool {
+        let mut inner = self.inner.lock();
+
+        let count = inner.ipv4_groups.entry(group).or_insert(0);
+        *count += 1;
+        if *count != 1 || group == ALL_HOSTS_GROUP {
+            return false;
+        }
+
+        inner.push_igmp(IgmpMessage::report(group))
+    }
+
+    /// Leaves an IPv4 multicast group.
+    ///
+    /// The group is left only when it is left as many times as it is joined. A Leave Group
+    /// message is generated at that time. This method returns whether the message is generated.
+    pub(super) fn leave_ipv4(&self, group: Ipv4Address) -> bool {
+        let mut inner = self.inner.lock();
+
+        let Some(count) = inner.ipv4_groups.get_mut(&group) else {
+            return false;
+        };
+        *count -= 1;
+        if *count != 0 {
+            return false;
+        }
+        inner.ipv4_groups.remove(&group);
+
+        if group == ALL_HOSTS_GROUP {
+            return false;
+        }
+
+        inner.push_igmp(IgmpMessage::leave(group))
+    }
+
+    /// Returns whether the IPv4 multicast group is joined.
+    pub(super) fn has_ipv4(&self, group: Ipv4Address) -> bool {
+        group == ALL_HOSTS_GROUP || self.inner.lock().ipv4_groups.contains_key(&group)
+    }
+
+    /// Processes an incoming IGMP message.
+    ///
+    /// If the message is a membership query, reports for the queried groups will be generated.
+    pub(super) fn process_igmp(&self, data: &[u8]) {
+        // Ignore the message if it is too short or if the checksum is wrong.
+        if data.len() < IgmpMessage::LEN || checksum(data) != 0 {
+            return;
+        }
+        if data[0] != IgmpMessage::TYPE_QUERY {
+            return;
+        }
+
+        // An unspecified group means a general query, which queries all groups.
+        let queried = Ipv4Address::new(data[4], data[5], data[6], data[7]);
+
+        let mut inner = self.inner.lock();
+        let reports = inner
+            .ipv4_groups
+            .keys()
+            .filter(|group| **group != ALL_HOSTS_GROUP)
+            .filter(|group| queried.is_unspecified() || **group == queried)
+            .map(|group| IgmpMessage::report(*group))
+            .collect::<Vec<_>>();
+        for report in reports {
+            inner.push_igmp(report);
+        }
+    }
+
+    /// Takes an IGMP message to transmit.
+    pub(super) fn pop_igmp(&self) -> Option<IgmpMessage> {
+        self.inner.lock().igmp_to_send.pop_front()
+    }
+}
+
+impl MulticastGroupsInner {
+    fn push_igmp(&mut self, message: IgmpMessage) -> bool {
+        if self.igmp_to_send.len() >= MAX_IGMP_TO_SEND {
+            return false;
+        }
+
+        self.igmp_to_send.push_back(message);
+        true
+    }
+}
+
+/// An IGMPv2 message to transmit.
+pub(super) struct IgmpMessage {
+    type_: u8,
+    group: Ipv4Address,
+    dst_addr: Ipv4Address,
+}
+
+impl IgmpMessage {
+    /// The length of an IGMPv2 message.
+    pub(super) const LEN: usize = 8;
+
+    /// The hop limit of IGMP messages.
+    ///
+    /// IGMP messages are never forwarded by the multicast routers.
+    pub(super) const HOP_LIMIT: u8 = 1;
+
+    const TYPE_QUERY: u8 = 0x11;
+    const TYPE_REPORT: u8 = 0x16;
+    const TYPE_LEAVE: u8 = 0x17;
+
+    fn report(group: Ipv4Address) -> Self {
+        Self {
+            type_: Self::TYPE_REPORT,
+            group,
+            dst_addr: group,
+        }
+    }
+
+    fn leave(group: Ipv4Address) -> Self {
+        Self {
+            type_: Self::TYPE_LEAVE,
+            group,
+            dst_addr: ALL_ROUTERS_GROUP,
+        }
+    }
+
+    /// Returns the destination address of the message.
+    pub(super) fn dst_addr(&self) -> Ipv4Address {
+        self.dst_addr
+    }
+
+    /// Emits the message into a new buffer.
+    //
+    // TODO: The message should be sent with the IP Router Alert option, but smoltcp cannot emit
+    // IPv4 options.
+    pub(super) fn emit(&self) -> [u8; Self::LEN] {
+        let mut data = [0; Self::LEN];
+        data[0] = self.type_;
+        data[4..8].copy_from_slice(&self.group.octets());
+
+        let checksum = checksum(&data);
+        data[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+        data
+    }
+}
+
+/// Computes the Internet checksum.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc1071>.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum = data
+        .chunks(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]) as u32)
+        .sum::<u32>();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
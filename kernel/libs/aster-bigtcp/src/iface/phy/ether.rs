@@ -4,11 +4,16 @@ use alloc::{collections::btree_map::BTreeMap, string::String, sync::Arc};
 
 use ostd::sync::{LocalIrqDisabled, SpinLock};
 use smoltcp::{
-    iface::{packet::Packet, Config, Context},
+    iface::{
+        packet::{IpPayload, Packet},
+        Config, Context,
+    },
     phy::{DeviceCapabilities, TxToken},
     wire::{
         self, ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol,
-        EthernetRepr, IpAddress, Ipv4Address, Ipv4AddressExt, Ipv4Cidr, Ipv4Packet,
+        EthernetRepr, Icmpv6Packet, Icmpv6Repr, IpAddress, IpProtocol, IpRepr, Ipv4Address,
+        Ipv4AddressExt, Ipv4Packet, Ipv6Address, Ipv6Cidr, Ipv6Packet, Ipv6Repr,
+        NdiscNeighborFlags, NdiscRepr, RawHardwareAddress,
     },
 };
 
@@ -16,9 +21,10 @@ use crate::{
     device::{NotifyDevice, WithDevice},
     ext::Ext,
     iface::{
-        common::IfaceCommon, iface::internal::IfaceInternal, time::get_network_timestamp, Iface,
-        ScheduleNextPoll,
+        common::IfaceCommon, iface::internal::IfaceInternal, poll::IpPacket,
+        time::get_network_timestamp, Iface, InterfaceFlags, InterfaceType, ScheduleNextPoll,
     },
+    socket::FrameDirection,
 };
 
 pub struct EtherIface<D, E: Ext> {
@@ -26,16 +32,37 @@ pub struct EtherIface<D, E: Ext> {
     common: IfaceCommon<E>,
     ether_addr: EthernetAddress,
     arp_table: SpinLock<BTreeMap<Ipv4Address, EthernetAddress>, LocalIrqDisabled>,
+    ndp_table: SpinLock<BTreeMap<Ipv6Address, EthernetAddress>, LocalIrqDisabled>,
+}
+
+/// A packet used for neighbor discovery, i.e., resolving the link-layer address of a neighbor.
+enum NeighborPacket {
+    /// An ARP packet for IPv4.
+    Arp(ArpRepr),
+    /// An NDP packet for IPv6, which is carried by ICMPv6 and has its own Ethernet header.
+    Ndisc(EthernetRepr, Packet<'static>),
 }
 
+/// The hop limit of NDP packets.
+///
+/// NDP packets with other hop limits must be discarded, so that they cannot come from other
+/// networks. See <https://datatracker.ietf.org/doc/html/rfc4861#section-7.1.1>.
+const NDISC_HOP_LIMIT: u8 = 255;
+
 impl<D: WithDevice, E: Ext> EtherIface<D, E> {
+    /// Creates an Ethernet iface.
+    ///
+    /// The iface has no IPv4 addresses and routes initially. They can be configured statically
+    /// after the iface is created, or by a DHCP client that talks through packet sockets.
+    ///
+    /// The iface has an IPv6 link-local address, which is derived from the Ethernet address as
+    /// described in <https://datatracker.ietf.org/doc/html/rfc4862#section-5.3>.
     pub fn new(
         driver: D,
         ether_addr: EthernetAddress,
-        ip_cidr: Ipv4Cidr,
-        gateway: Ipv4Address,
         name: String,
         sched_poll: E::ScheduleNextPoll,
+        flags: InterfaceFlags,
     ) -> Arc<Self> {
         let interface = driver.with(|device| {
             let config = Config::new(wire::HardwareAddress::Ethernet(ether_addr));
@@ -44,26 +71,88 @@ impl<D: WithDevice, E: Ext> EtherIface<D, E> {
             let mut interface = smoltcp::iface::Interface::new(config, device, now);
             interface.update_ip_addrs(|ip_addrs| {
                 debug_assert!(ip_addrs.is_empty());
-                ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                ip_addrs
+                    .push(wire::IpCidr::Ipv6(link_local_cidr(ether_addr)))
+                    .unwrap();
             });
             interface
-                .routes_mut()
-                .add_default_ipv4_route(gateway)
-                .unwrap();
-            interface
         });
 
-        let common = IfaceCommon::new(name, interface, sched_poll);
+        let common = IfaceCommon::new(name, InterfaceType::Ether, flags, interface, sched_poll);
 
         Arc::new(Self {
             driver,
             common,
             ether_addr,
             arp_table: SpinLock::new(BTreeMap::new()),
+            ndp_table: SpinLock::new(BTreeMap::new()),
         })
     }
 }
 
+/// Returns the IPv6 link-local address with the interface identifier in the modified EUI-64
+/// format.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc4291#appendix-A>.
+fn link_local_cidr(ether_addr: EthernetAddress) -> Ipv6Cidr {
+    let mac = ether_addr.0;
+    let addr = Ipv6Address::new(
+        0xfe80,
+        0,
+        0,
+        0,
+        u16::from_be_bytes([mac[0] ^ 0x02, mac[1]]),
+        u16::from_be_bytes([mac[2], 0xff]),
+        u16::from_be_bytes([0xfe, mac[3]]),
+        u16::from_be_bytes([mac[4], mac[5]]),
+    );
+    Ipv6Cidr::new(addr, 64)
+}
+
+/// Returns the Ethernet address that the IPv4 multicast address is mapped to.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc1112#section-6.4>.
+fn ipv4_multicast_ether_addr(addr: &Ipv4Address) -> EthernetAddress {
+    let octets = addr.octets();
+    EthernetAddress([0x01, 0x00, 0x5e, octets[1] & 0x7f, octets[2], octets[3]])
+}
+
+/// Returns the Ethernet address that the IPv6 multicast address is mapped to.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc2464#section-7>.
+fn ipv6_multicast_ether_addr(addr: &Ipv6Address) -> EthernetAddress {
+    let octets = addr.octets();
+    EthernetAddress([0x33, 0x33, octets[12], octets[13], octets[14], octets[15]])
+}
+
+/// Returns the solicited-node multicast address of the IPv6 address.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc4291#section-2.7.1>.
+fn solicited_node_addr(addr: &Ipv6Address) -> Ipv6Address {
+    let octets = addr.octets();
+    Ipv6Address::new(
+        0xff02,
+        0,
+        0,
+        0,
+        0,
+        1,
+        0xff00 | octets[13] as u16,
+        u16::from_be_bytes([octets[14], octets[15]]),
+    )
+}
+
+/// Converts the link-layer address in an NDP option to an Ethernet address.
+fn lladdr_to_ether(lladdr: &RawHardwareAddress) -> Option<EthernetAddress> {
+    let bytes = lladdr.as_bytes();
+    if bytes.len() != 6 {
+        return None;
+    }
+
+    let ether_addr = EthernetAddress::from_bytes(bytes);
+    ether_addr.is_unicast().then_some(ether_addr)
+}
+
 impl<D, E: Ext> IfaceInternal<E> for EtherIface<D, E> {
     fn common(&self) -> &IfaceCommon<E> {
         &self.common
@@ -93,40 +182,53 @@ impl<D, E: Ext> EtherIface<D, E> {
         data: &'pkt [u8],
         iface_cx: &mut Context,
         tx_token: T,
-    ) -> Option<(Ipv4Packet<&'pkt [u8]>, T)> {
-        match self.parse_ip_or_process_arp(data, iface_cx) {
+    ) -> Option<(IpPacket<'pkt>, T)> {
+        match self.parse_ip_or_process_neighbor(data, iface_cx) {
             Ok(pkt) => Some((pkt, tx_token)),
-            Err(Some(arp)) => {
-                Self::emit_arp(&arp, tx_token);
+            Err(Some(neighbor)) => {
+                self.emit_neighbor(&neighbor, &iface_cx.caps, tx_token);
                 None
             }
             Err(None) => None,
         }
     }
 
-    fn parse_ip_or_process_arp<'pkt>(
+    fn parse_ip_or_process_neighbor<'pkt>(
         &self,
         data: &'pkt [u8],
         iface_cx: &mut Context,
-    ) -> Result<Ipv4Packet<&'pkt [u8]>, Option<ArpRepr>> {
+    ) -> Result<IpPacket<'pkt>, Option<NeighborPacket>> {
         // Parse the Ethernet header. Ignore the packet if the header is ill-formed.
         let frame = EthernetFrame::new_checked(data).map_err(|_| None)?;
         let repr = EthernetRepr::parse(&frame).map_err(|_| None)?;
 
-        // Ignore the Ethernet frame if it is not sent to us.
-        if !repr.dst_addr.is_broadcast() && repr.dst_addr != self.ether_addr {
+        // Ignore the Ethernet frame if it is not sent to us. Multicast frames are accepted because
+        // NDP depends on them.
+        if !repr.dst_addr.is_multicast() && repr.dst_addr != self.ether_addr {
             return Err(None);
         }
 
+        // Deliver the Ethernet frame to the packet sockets, regardless of whether the protocol is
+        // supported below.
+        self.common
+            .process_frame(data, repr.ethertype, FrameDirection::Incoming);
+
         // Ignore the Ethernet frame if the protocol is not supported.
         match repr.ethertype {
-            EthernetProtocol::Ipv4 => {
-                Ok(Ipv4Packet::new_checked(frame.payload()).map_err(|_| None)?)
+            EthernetProtocol::Ipv4 => Ok(IpPacket::Ipv4(
+                Ipv4Packet::new_checked(frame.payload()).map_err(|_| None)?,
+            )),
+            EthernetProtocol::Ipv6 => {
+                let pkt = Ipv6Packet::new_checked(frame.payload()).map_err(|_| None)?;
+                if let Some((ip_repr, ndisc_repr)) = self.parse_ndisc(&pkt, iface_cx) {
+                    return Err(self.process_ndisc(&repr, &ip_repr, &ndisc_repr, iface_cx));
+                }
+                Ok(IpPacket::Ipv6(pkt))
             }
             EthernetProtocol::Arp => {
                 let pkt = ArpPacket::new_checked(frame.payload()).map_err(|_| None)?;
                 let arp = ArpRepr::parse(&pkt).map_err(|_| None)?;
-                Err(self.process_arp(&arp, iface_cx))
+                Err(self.process_arp(&arp, iface_cx).map(NeighborPacket::Arp))
             }
             _ => Err(None),
         }
@@ -188,23 +290,163 @@ impl<D, E: Ext> EtherIface<D, E> {
         }
     }
 
+    /// Parses the IPv6 packet as an NDP packet.
+    ///
+    /// This method returns `None` if the packet is not a valid NDP packet for neighbor
+    /// solicitation or advertisement. Such a packet will be passed to the upper layer.
+    fn parse_ndisc(
+        &self,
+        pkt: &Ipv6Packet<&[u8]>,
+        iface_cx: &Context,
+    ) -> Option<(Ipv6Repr, NdiscRepr<'static>)> {
+        let ip_repr = Ipv6Repr::parse(pkt).ok()?;
+        if ip_repr.next_header != IpProtocol::Icmpv6 || ip_repr.hop_limit != NDISC_HOP_LIMIT {
+            return None;
+        }
+
+        let icmp_pkt = Icmpv6Packet::new_checked(pkt.payload()).ok()?;
+        let icmp_repr = Icmpv6Repr::parse(
+            &ip_repr.src_addr,
+            &ip_repr.dst_addr,
+            &icmp_pkt,
+            &iface_cx.checksum_caps(),
+        )
+        .ok()?;
+
+        let ndisc_repr = match icmp_repr {
+            Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+                target_addr,
+                lladdr,
+            }) => NdiscRepr::NeighborSolicit {
+                target_addr,
+                lladdr,
+            },
+            Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+                flags,
+                target_addr,
+                lladdr,
+            }) => NdiscRepr::NeighborAdvert {
+                flags,
+                target_addr,
+                lladdr,
+            },
+            _ => return None,
+        };
+
+        Some((ip_repr, ndisc_repr))
+    }
+
+    fn process_ndisc(
+        &self,
+        ether_repr: &EthernetRepr,
+        ip_repr: &Ipv6Repr,
+        ndisc_repr: &NdiscRepr,
+        iface_cx: &mut Context,
+    ) -> Option<NeighborPacket> {
+        match ndisc_repr {
+            NdiscRepr::NeighborAdvert {
+                target_addr,
+                lladdr,
+                ..
+            } => {
+                // Insert the mapping between the Ethernet address and the IP address.
+                //
+                // TODO: Remove the mapping if it expires.
+                if let Some(ether_addr) = lladdr.as_ref().and_then(lladdr_to_ether) {
+                    self.ndp_table.lock().insert(*target_addr, ether_addr);
+                }
+
+                None
+            }
+            NdiscRepr::NeighborSolicit {
+                target_addr,
+                lladdr,
+            } => {
+                // Ignore the NDP packet if it is sent for duplicate address detection. We do not
+                // perform duplicate address detection ourselves.
+                if ip_repr.src_addr.is_unspecified() {
+                    return None;
+                }
+
+                // Ignore the NDP packet if we do not own the target address.
+                if iface_cx.ipv6_addr().is_none_or(|addr| addr != *target_addr) {
+                    return None;
+                }
+
+                // Insert the mapping between the Ethernet address and the IP address. This saves
+                // a round trip since the neighbor is likely to talk to us soon.
+                if let Some(ether_addr) = lladdr.as_ref().and_then(lladdr_to_ether) {
+                    self.ndp_table.lock().insert(ip_repr.src_addr, ether_addr);
+                }
+
+                let icmp_repr = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+                    flags: NdiscNeighborFlags::SOLICITED | NdiscNeighborFlags::OVERRIDE,
+                    target_addr: *target_addr,
+                    lladdr: Some(RawHardwareAddress::from(self.ether_addr)),
+                });
+                let ip_repr = Ipv6Repr {
+                    src_addr: *target_addr,
+                    dst_addr: ip_repr.src_addr,
+                    next_header: IpProtocol::Icmpv6,
+                    payload_len: icmp_repr.buffer_len(),
+                    hop_limit: NDISC_HOP_LIMIT,
+                };
+
+                Some(NeighborPacket::Ndisc(
+                    EthernetRepr {
+                        src_addr: self.ether_addr,
+                        dst_addr: ether_repr.src_addr,
+                        ethertype: EthernetProtocol::Ipv6,
+                    },
+                    Packet::new(IpRepr::Ipv6(ip_repr), IpPayload::Icmpv6(icmp_repr)),
+                ))
+            }
+            _ => None,
+        }
+    }
+
     fn dispatch<T: TxToken>(&self, pkt: &Packet, iface_cx: &mut Context, tx_token: T) {
-        match self.resolve_ether_or_generate_arp(pkt, iface_cx) {
-            Ok(ether) => Self::emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
-            Err(Some(arp)) => Self::emit_arp(&arp, tx_token),
+        match self.resolve_ether_or_generate_neighbor(pkt, iface_cx) {
+            Ok(ether) => self.emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
+            Err(Some(neighbor)) => self.emit_neighbor(&neighbor, &iface_cx.caps, tx_token),
             Err(None) => (),
         }
     }
 
+    fn resolve_ether_or_generate_neighbor(
+        &self,
+        pkt: &Packet,
+        iface_cx: &mut Context,
+    ) -> Result<EthernetRepr, Option<NeighborPacket>> {
+        match pkt.ip_repr() {
+            IpRepr::Ipv4(_) => self
+                .resolve_ether_or_generate_arp(pkt, iface_cx)
+                .map_err(|arp| arp.map(NeighborPacket::Arp)),
+            IpRepr::Ipv6(ip_repr) => self.resolve_ether_or_generate_ndisc(&ip_repr, iface_cx),
+        }
+    }
+
     fn resolve_ether_or_generate_arp(
         &self,
         pkt: &Packet,
         iface_cx: &mut Context,
     ) -> Result<EthernetRepr, Option<ArpRepr>> {
+        let ether_repr = |dst_addr| EthernetRepr {
+            src_addr: self.ether_addr,
+            dst_addr,
+            ethertype: EthernetProtocol::Ipv4,
+        };
+
+        if let IpAddress::Ipv4(dst_addr) = pkt.ip_repr().dst_addr() {
+            if dst_addr.is_multicast() {
+                return Ok(ether_repr(ipv4_multicast_ether_addr(&dst_addr)));
+            }
+        }
+
         // Resolve the next-hop IP address.
         let next_hop_ip = match iface_cx.route(&pkt.ip_repr().dst_addr(), iface_cx.now()) {
             Some(IpAddress::Ipv4(next_hop_ip)) => next_hop_ip,
-            None => return Err(None),
+            Some(IpAddress::Ipv6(_)) | None => return Err(None),
         };
 
         // Resolve the next-hop Ethernet address.
@@ -225,15 +467,59 @@ impl<D, E: Ext> EtherIface<D, E> {
             }));
         };
 
-        Ok(EthernetRepr {
+        Ok(ether_repr(next_hop_ether))
+    }
+
+    fn resolve_ether_or_generate_ndisc(
+        &self,
+        ip_repr: &Ipv6Repr,
+        iface_cx: &mut Context,
+    ) -> Result<EthernetRepr, Option<NeighborPacket>> {
+        let ether_repr = |dst_addr| EthernetRepr {
             src_addr: self.ether_addr,
-            dst_addr: next_hop_ether,
-            ethertype: EthernetProtocol::Ipv4,
-        })
+            dst_addr,
+            ethertype: EthernetProtocol::Ipv6,
+        };
+
+        if ip_repr.dst_addr.is_multicast() {
+            return Ok(ether_repr(ipv6_multicast_ether_addr(&ip_repr.dst_addr)));
+        }
+
+        // Resolve the next-hop IP address.
+        let next_hop_ip = match iface_cx.route(&IpAddress::Ipv6(ip_repr.dst_addr), iface_cx.now()) {
+            Some(IpAddress::Ipv6(next_hop_ip)) => next_hop_ip,
+            Some(IpAddress::Ipv4(_)) | None => return Err(None),
+        };
+
+        // Resolve the next-hop Ethernet address.
+        if let Some(next_hop_ether) = self.ndp_table.lock().get(&next_hop_ip) {
+            return Ok(ether_repr(*next_hop_ether));
+        }
+
+        // If the next-hop Ethernet address cannot be resolved, we drop the original packet and
+        // send a neighbor solicitation instead, just like what we do for ARP.
+        let dst_addr = solicited_node_addr(&next_hop_ip);
+        let icmp_repr = Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+            target_addr: next_hop_ip,
+            lladdr: Some(RawHardwareAddress::from(self.ether_addr)),
+        });
+        let ndisc_ip_repr = Ipv6Repr {
+            src_addr: ip_repr.src_addr,
+            dst_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+            hop_limit: NDISC_HOP_LIMIT,
+        };
+
+        Err(Some(NeighborPacket::Ndisc(
+            ether_repr(ipv6_multicast_ether_addr(&dst_addr)),
+            Packet::new(IpRepr::Ipv6(ndisc_ip_repr), IpPayload::Icmpv6(icmp_repr)),
+        )))
     }
 
     /// Consumes the token and emits an IP packet.
     fn emit_ip<T: TxToken>(
+        &self,
         ether_repr: &EthernetRepr,
         ip_pkt: &Packet,
         caps: &DeviceCapabilities,
@@ -252,12 +538,33 @@ impl<D, E: Ext> EtherIface<D, E> {
                     &mut frame.payload_mut()[ip_repr.header_len()..],
                     caps,
                 );
+
+                self.common.process_frame(
+                    frame.into_inner(),
+                    ether_repr.ethertype,
+                    FrameDirection::Outgoing,
+                );
             },
         );
     }
 
+    /// Consumes the token and emits a packet for neighbor discovery.
+    fn emit_neighbor<T: TxToken>(
+        &self,
+        neighbor: &NeighborPacket,
+        caps: &DeviceCapabilities,
+        tx_token: T,
+    ) {
+        match neighbor {
+            NeighborPacket::Arp(arp_repr) => self.emit_arp(arp_repr, tx_token),
+            NeighborPacket::Ndisc(ether_repr, ip_pkt) => {
+                self.emit_ip(ether_repr, ip_pkt, caps, tx_token)
+            }
+        }
+    }
+
     /// Consumes the token and emits an ARP packet.
-    fn emit_arp<T: TxToken>(arp_repr: &ArpRepr, tx_token: T) {
+    fn emit_arp<T: TxToken>(&self, arp_repr: &ArpRepr, tx_token: T) {
         let ether_repr = match arp_repr {
             ArpRepr::EthernetIpv4 {
                 source_hardware_addr,
@@ -277,6 +584,12 @@ impl<D, E: Ext> EtherIface<D, E> {
 
             let mut pkt = ArpPacket::new_unchecked(frame.payload_mut());
             arp_repr.emit(&mut pkt);
+
+            self.common.process_frame(
+                frame.into_inner(),
+                EthernetProtocol::Arp,
+                FrameDirection::Outgoing,
+            );
         });
     }
 }
@@ -8,22 +8,28 @@ use smoltcp::{
         packet::{icmp_reply_payload_len, IpPayload, Packet},
         Context,
     },
-    phy::{ChecksumCapabilities, Device, RxToken, TxToken},
+    phy::{ChecksumCapabilities, Device, DeviceCapabilities, RxToken, TxToken},
     wire::{
-        Icmpv4DstUnreachable, Icmpv4Repr, IpAddress, IpProtocol, IpRepr, Ipv4Address, Ipv4Packet,
-        Ipv4Repr, TcpControl, TcpPacket, TcpRepr, UdpPacket, UdpRepr, IPV4_HEADER_LEN,
-        IPV4_MIN_MTU,
+        Icmpv4DstUnreachable, Icmpv4Packet, Icmpv4Repr, Icmpv6DstUnreachable, Icmpv6Repr,
+        IpAddress, IpProtocol, IpRepr, IpVersion, Ipv4Address, Ipv4Packet, Ipv4Repr, Ipv6Packet,
+        Ipv6Repr, TcpControl, TcpPacket, TcpRepr, UdpPacket, UdpRepr, IPV4_HEADER_LEN,
+        IPV4_MIN_MTU, IPV6_HEADER_LEN, IPV6_MIN_MTU,
     },
 };
 
-use super::common::SocketSet;
+use super::{
+    common::{IfaceCommon, SocketSet},
+    multicast::IgmpMessage,
+    InterfaceType,
+};
 use crate::{
     ext::Ext,
-    socket::{TcpConnectionBg, TcpListenerBg, TcpProcessResult},
+    socket::{TcpConnectionBg, TcpListenerBg, TcpProcessResult, UdpSocketBg},
 };
 
 pub(super) struct PollContext<'a, E: Ext> {
     iface_cx: &'a mut Context,
+    iface_common: &'a IfaceCommon<E>,
     sockets: &'a SocketSet<E>,
     new_tcp_conns: &'a mut Vec<KeyableArc<TcpConnectionBg<E>>>,
 }
@@ -31,17 +37,44 @@ pub(super) struct PollContext<'a, E: Ext> {
 impl<'a, E: Ext> PollContext<'a, E> {
     pub(super) fn new(
         iface_cx: &'a mut Context,
+        iface_common: &'a IfaceCommon<E>,
         sockets: &'a SocketSet<E>,
         new_tcp_conns: &'a mut Vec<KeyableArc<TcpConnectionBg<E>>>,
     ) -> Self {
         Self {
             iface_cx,
+            iface_common,
             sockets,
             new_tcp_conns,
         }
     }
 }
 
+/// An IP packet received from the physical layer.
+pub(super) enum IpPacket<'a> {
+    Ipv4(Ipv4Packet<&'a [u8]>),
+    Ipv6(Ipv6Packet<&'a [u8]>),
+}
+
+impl<'a> IpPacket<'a> {
+    /// Checks the IP version and the length of the packet.
+    pub(super) fn new_checked(data: &'a [u8]) -> Option<Self> {
+        match IpVersion::of_packet(data).ok()? {
+            IpVersion::Ipv4 => Ipv4Packet::new_checked(data).ok().map(Self::Ipv4),
+            IpVersion::Ipv6 => Ipv6Packet::new_checked(data).ok().map(Self::Ipv6),
+        }
+    }
+}
+
+/// The reason why a packet cannot be delivered.
+///
+/// This is translated to the ICMPv4 or ICMPv6 code, depending on the IP version of the packet.
+#[derive(Debug, Clone, Copy)]
+enum DstUnreachable {
+    Host,
+    Port,
+}
+
 // This works around <https://github.com/rust-lang/rust/issues/49601>.
 // See the issue above for details.
 pub(super) trait FnHelper<A, B, C, O>: FnMut(A, B, C) -> O {}
@@ -59,7 +92,7 @@ impl<E: Ext> PollContext<'_, E> {
             &'pkt [u8],
             &'cx mut Context,
             D::TxToken<'tx>,
-            Option<(Ipv4Packet<&'pkt [u8]>, D::TxToken<'tx>)>,
+            Option<(IpPacket<'pkt>, D::TxToken<'tx>)>,
         >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
@@ -69,7 +102,11 @@ impl<E: Ext> PollContext<'_, E> {
                     return;
                 };
 
-                let Some(reply) = self.parse_and_process_ipv4(pkt) else {
+                let reply = match pkt {
+                    IpPacket::Ipv4(pkt) => self.parse_and_process_ipv4(pkt),
+                    IpPacket::Ipv6(pkt) => self.parse_and_process_ipv6(pkt),
+                };
+                let Some(reply) = reply else {
                     return;
                 };
 
@@ -85,14 +122,26 @@ impl<E: Ext> PollContext<'_, E> {
         // Parse the IP header. Ignore the packet if the header is ill-formed.
         let repr = Ipv4Repr::parse(&pkt, &self.iface_cx.checksum_caps()).ok()?;
 
-        if !repr.dst_addr.is_broadcast() && !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr)) {
+        if !repr.dst_addr.is_broadcast()
+            && !self.is_joined_multicast(repr.dst_addr)
+            && !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr))
+        {
+            // Multicast packets to the groups that we have not joined also end up here. They are
+            // dropped silently because ICMP messages are never generated for them.
             return self.generate_icmp_unreachable(
                 &IpRepr::Ipv4(repr),
                 pkt.payload(),
-                Icmpv4DstUnreachable::HostUnreachable,
+                DstUnreachable::Host,
             );
         }
 
+        // Deliver a copy of the packet to the raw sockets, regardless of whether the protocol is
+        // supported below.
+        let data = &pkt.as_ref()[..pkt.total_len() as usize];
+        for socket in self.sockets.raw.iter() {
+            socket.process(&repr, data);
+        }
+
         match repr.next_header {
             IpProtocol::Tcp => self.parse_and_process_tcp(
                 &IpRepr::Ipv4(repr),
@@ -104,6 +153,42 @@ impl<E: Ext> PollContext<'_, E> {
                 pkt.payload(),
                 &self.iface_cx.checksum_caps(),
             ),
+            IpProtocol::Icmp => self.parse_and_process_icmpv4(&repr, pkt.payload()),
+            IpProtocol::Igmp => {
+                self.iface_common
+                    .multicast_groups()
+                    .process_igmp(pkt.payload());
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_and_process_ipv6<'pkt>(
+        &mut self,
+        pkt: Ipv6Packet<&'pkt [u8]>,
+    ) -> Option<Packet<'pkt>> {
+        // Parse the IP header. Ignore the packet if the header is ill-formed.
+        let repr = Ipv6Repr::parse(&pkt).ok()?;
+
+        // Unlike IPv4, packets to other hosts are dropped silently. This also covers the packets
+        // sent to the multicast groups that we have not joined, which are common due to NDP.
+        if !repr.dst_addr.is_multicast() && !self.is_unicast_local(IpAddress::Ipv6(repr.dst_addr)) {
+            return None;
+        }
+
+        // TODO: Support IPv6 extension headers.
+        match repr.next_header {
+            IpProtocol::Tcp => self.parse_and_process_tcp(
+                &IpRepr::Ipv6(repr),
+                pkt.payload(),
+                &self.iface_cx.checksum_caps(),
+            ),
+            IpProtocol::Udp => self.parse_and_process_udp(
+                &IpRepr::Ipv6(repr),
+                pkt.payload(),
+                &self.iface_cx.checksum_caps(),
+            ),
             _ => None,
         }
     }
@@ -164,7 +249,7 @@ impl<E: Ext> PollContext<'_, E> {
             .iter()
             .chain(self.new_tcp_conns.iter())
         {
-            if !socket.can_process(tcp_repr.dst_port) {
+            if !socket.can_process(&ip_repr.dst_addr(), tcp_repr.dst_port) {
                 continue;
             }
 
@@ -179,7 +264,7 @@ impl<E: Ext> PollContext<'_, E> {
 
         if tcp_repr.control == TcpControl::Syn && tcp_repr.ack_number.is_none() {
             for socket in self.sockets.tcp_listen.iter() {
-                if !socket.can_process(tcp_repr.dst_port) {
+                if !socket.can_process(&ip_repr.dst_addr(), tcp_repr.dst_port) {
                     continue;
                 }
 
@@ -226,11 +311,7 @@ impl<E: Ext> PollContext<'_, E> {
         .ok()?;
 
         if !self.process_udp(ip_repr, &udp_repr, udp_pkt.payload()) {
-            return self.generate_icmp_unreachable(
-                ip_repr,
-                ip_payload,
-                Icmpv4DstUnreachable::PortUnreachable,
-            );
+            return self.generate_icmp_unreachable(ip_repr, ip_payload, DstUnreachable::Port);
         }
 
         None
@@ -240,7 +321,7 @@ impl<E: Ext> PollContext<'_, E> {
         let mut processed = false;
 
         for socket in self.sockets.udp.iter() {
-            if !socket.can_process(udp_repr.dst_port) {
+            if !socket.can_process(&ip_repr.dst_addr(), udp_repr.dst_port) {
                 continue;
             }
 
@@ -253,11 +334,58 @@ impl<E: Ext> PollContext<'_, E> {
         processed
     }
 
+    fn parse_and_process_icmpv4<'pkt>(
+        &mut self,
+        ip_repr: &Ipv4Repr,
+        ip_payload: &'pkt [u8],
+    ) -> Option<Packet<'pkt>> {
+        // Parse the ICMP header. Ignore the packet if the header is ill-formed.
+        let icmp_pkt = Icmpv4Packet::new_checked(ip_payload).ok()?;
+        let icmp_repr = Icmpv4Repr::parse(&icmp_pkt, &self.iface_cx.checksum_caps()).ok()?;
+
+        match icmp_repr {
+            Icmpv4Repr::EchoRequest {
+                ident,
+                seq_no,
+                data,
+            } => {
+                // Like Linux (with the default value of `icmp_echo_ignore_broadcasts`), echo
+                // requests sent to broadcast addresses are ignored.
+                if ip_repr.dst_addr.is_broadcast() {
+                    return None;
+                }
+
+                let icmp_repr = Icmpv4Repr::EchoReply {
+                    ident,
+                    seq_no,
+                    data,
+                };
+                Some(Packet::new_ipv4(
+                    Ipv4Repr {
+                        src_addr: ip_repr.dst_addr,
+                        dst_addr: ip_repr.src_addr,
+                        next_header: IpProtocol::Icmp,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv4(icmp_repr),
+                ))
+            }
+            Icmpv4Repr::EchoReply { ident, .. } => {
+                for socket in self.sockets.raw.iter() {
+                    socket.process_echo_reply(ip_repr, ident, ip_payload);
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
     fn generate_icmp_unreachable<'pkt>(
         &self,
         ip_repr: &IpRepr,
         ip_payload: &'pkt [u8],
-        reason: Icmpv4DstUnreachable,
+        reason: DstUnreachable,
     ) -> Option<Packet<'pkt>> {
         if !ip_repr.src_addr().is_unicast() || !ip_repr.dst_addr().is_unicast() {
             return None;
@@ -273,28 +401,82 @@ impl<E: Ext> PollContext<'_, E> {
             return None;
         }
 
-        let IpRepr::Ipv4(ipv4_repr) = ip_repr;
+        match ip_repr {
+            IpRepr::Ipv4(ipv4_repr) => {
+                let reason = match reason {
+                    DstUnreachable::Host => Icmpv4DstUnreachable::HostUnreachable,
+                    DstUnreachable::Port => Icmpv4DstUnreachable::PortUnreachable,
+                };
+
+                let reply_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, IPV4_HEADER_LEN);
+                let icmp_repr = Icmpv4Repr::DstUnreachable {
+                    reason,
+                    header: *ipv4_repr,
+                    data: &ip_payload[..reply_len],
+                };
+
+                Some(Packet::new_ipv4(
+                    Ipv4Repr {
+                        src_addr: self
+                            .iface_cx
+                            .ipv4_addr()
+                            .unwrap_or(Ipv4Address::UNSPECIFIED),
+                        dst_addr: ipv4_repr.src_addr,
+                        next_header: IpProtocol::Icmp,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv4(icmp_repr),
+                ))
+            }
+            IpRepr::Ipv6(ipv6_repr) => {
+                let reason = match reason {
+                    DstUnreachable::Host => Icmpv6DstUnreachable::AddrUnreachable,
+                    DstUnreachable::Port => Icmpv6DstUnreachable::PortUnreachable,
+                };
+
+                let reply_len =
+                    icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, IPV6_HEADER_LEN);
+                let icmp_repr = Icmpv6Repr::DstUnreachable {
+                    reason,
+                    header: *ipv6_repr,
+                    data: &ip_payload[..reply_len],
+                };
+
+                // IPv6 packets to other hosts are dropped before reaching here, so the original
+                // destination address is a local address that can be used as the source address.
+                Some(Packet::new(
+                    IpRepr::Ipv6(Ipv6Repr {
+                        src_addr: ipv6_repr.dst_addr,
+                        dst_addr: ipv6_repr.src_addr,
+                        next_header: IpProtocol::Icmpv6,
+                        payload_len: icmp_repr.buffer_len(),
+                        hop_limit: 64,
+                    }),
+                    IpPayload::Icmpv6(icmp_repr),
+                ))
+            }
+        }
+    }
 
-        let reply_len = icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, IPV4_HEADER_LEN);
-        let icmp_repr = Icmpv4Repr::DstUnreachable {
-            reason,
-            header: *ipv4_repr,
-            data: &ip_payload[..reply_len],
+    /// Returns whether a copy of the outgoing multicast packet should be delivered locally.
+    ///
+    /// Like Linux, this is done if the socket enables `IP_MULTICAST_LOOP` and the group is joined.
+    /// It is not done for the loopback iface, where the outgoing packets always come back.
+    fn should_loop_multicast(&self, ip_repr: &IpRepr, socket: &UdpSocketBg<E>) -> bool {
+        let IpAddress::Ipv4(dst_addr) = ip_repr.dst_addr() else {
+            return false;
         };
 
-        Some(Packet::new_ipv4(
-            Ipv4Repr {
-                src_addr: self
-                    .iface_cx
-                    .ipv4_addr()
-                    .unwrap_or(Ipv4Address::UNSPECIFIED),
-                dst_addr: ipv4_repr.src_addr,
-                next_header: IpProtocol::Icmp,
-                payload_len: icmp_repr.buffer_len(),
-                hop_limit: 64,
-            },
-            IpPayload::Icmpv4(icmp_repr),
-        ))
+        self.iface_common.type_() != InterfaceType::Loopback
+            && socket.multicast_loop()
+            && self.is_joined_multicast(dst_addr)
+    }
+
+    /// Returns whether the destination address is an IPv4 multicast group joined by the iface.
+    fn is_joined_multicast(&self, dst_addr: Ipv4Address) -> bool {
+        dst_addr.is_multicast() && self.iface_common.multicast_groups().has_ipv4(dst_addr)
     }
 
     /// Returns whether the destination address is the unicast address of a local interface.
@@ -307,6 +489,10 @@ impl<E: Ext> PollContext<'_, E> {
                 .iface_cx
                 .ipv4_addr()
                 .is_some_and(|addr| addr == dst_addr),
+            IpAddress::Ipv6(dst_addr) => self
+                .iface_cx
+                .ipv6_addr()
+                .is_some_and(|addr| addr == dst_addr),
         }
     }
 }
@@ -318,26 +504,68 @@ impl<E: Ext> PollContext<'_, E> {
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
         while let Some(tx_token) = device.transmit(self.iface_cx.now()) {
-            if !self.dispatch_ipv4(tx_token, dispatch_phy) {
+            if !self.dispatch_ip(tx_token, dispatch_phy) {
                 break;
             }
         }
     }
 
-    fn dispatch_ipv4<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
+    fn dispatch_ip<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
     where
         T: TxToken,
         Q: FnMut(&Packet, &mut Context, T),
     {
+        let (did_something_igmp, tx_token) = self.dispatch_igmp(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_igmp;
+        };
+
         let (did_something_tcp, tx_token) = self.dispatch_tcp(tx_token, dispatch_phy);
 
         let Some(tx_token) = tx_token else {
-            return did_something_tcp;
+            return did_something_igmp || did_something_tcp;
+        };
+
+        let (did_something_udp, tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_igmp || did_something_tcp || did_something_udp;
         };
 
-        let (did_something_udp, _tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
+        let (did_something_raw, _tx_token) = self.dispatch_raw(tx_token, dispatch_phy);
 
-        did_something_tcp || did_something_udp
+        did_something_igmp || did_something_tcp || did_something_udp || did_something_raw
+    }
+
+    fn dispatch_igmp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let Some(message) = self.iface_common.multicast_groups().pop_igmp() else {
+            return (false, Some(tx_token));
+        };
+
+        let ip_repr = Ipv4Repr {
+            src_addr: self
+                .iface_cx
+                .ipv4_addr()
+                .unwrap_or(Ipv4Address::UNSPECIFIED),
+            dst_addr: message.dst_addr(),
+            next_header: IpProtocol::Igmp,
+            payload_len: IgmpMessage::LEN,
+            hop_limit: IgmpMessage::HOP_LIMIT,
+        };
+        let ip_payload = message.emit();
+
+        dispatch_phy(
+            &Packet::new_ipv4(ip_repr, IpPayload::Raw(&ip_payload)),
+            self.iface_cx,
+            tx_token,
+        );
+
+        (true, None)
     }
 
     fn dispatch_tcp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
@@ -363,7 +591,8 @@ impl<E: Ext> PollContext<'_, E> {
 
             let reply =
                 TcpConnectionBg::dispatch(socket, self.iface_cx, |cx, ip_repr, tcp_repr| {
-                    let mut this = PollContext::new(cx, self.sockets, self.new_tcp_conns);
+                    let mut this =
+                        PollContext::new(cx, self.iface_common, self.sockets, self.new_tcp_conns);
 
                     if !this.is_unicast_local(ip_repr.dst_addr()) {
                         dispatch_phy(
@@ -374,7 +603,7 @@ impl<E: Ext> PollContext<'_, E> {
                         return None;
                     }
 
-                    if !socket.can_process(tcp_repr.dst_port) {
+                    if !socket.can_process(&ip_repr.dst_addr(), tcp_repr.dst_port) {
                         return this.process_tcp(ip_repr, tcp_repr);
                     }
 
@@ -454,7 +683,8 @@ impl<E: Ext> PollContext<'_, E> {
             let mut deferred = None;
 
             socket.dispatch(self.iface_cx, |cx, ip_repr, udp_repr, udp_payload| {
-                let mut this = PollContext::new(cx, self.sockets, self.new_tcp_conns);
+                let mut this =
+                    PollContext::new(cx, self.iface_common, self.sockets, self.new_tcp_conns);
 
                 if ip_repr.dst_addr().is_broadcast() || !this.is_unicast_local(ip_repr.dst_addr()) {
                     dispatch_phy(
@@ -462,12 +692,14 @@ impl<E: Ext> PollContext<'_, E> {
                         this.iface_cx,
                         tx_token.take().unwrap(),
                     );
-                    if !ip_repr.dst_addr().is_broadcast() {
+                    if !ip_repr.dst_addr().is_broadcast()
+                        && !this.should_loop_multicast(ip_repr, socket)
+                    {
                         return;
                     }
                 }
 
-                if !socket.can_process(udp_repr.dst_port) {
+                if !socket.can_process(&ip_repr.dst_addr(), udp_repr.dst_port) {
                     // TODO: Generate the ICMP message here once we're able to handle incoming ICMP
                     // messages.
                     let _ = this.process_udp(ip_repr, udp_repr, udp_payload);
@@ -507,4 +739,65 @@ impl<E: Ext> PollContext<'_, E> {
 
         (did_something, tx_token)
     }
+
+    fn dispatch_raw<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let mut tx_token = Some(tx_token);
+        let mut did_something = false;
+
+        for socket in self.sockets.raw.iter() {
+            let Some((ip_repr, ip_payload)) = socket.dispatch(self.iface_cx) else {
+                continue;
+            };
+
+            did_something = true;
+
+            let pkt = Packet::new_ipv4(ip_repr, IpPayload::Raw(&ip_payload));
+            if ip_repr.dst_addr.is_broadcast()
+                || !self.is_unicast_local(IpAddress::Ipv4(ip_repr.dst_addr))
+            {
+                dispatch_phy(&pkt, self.iface_cx, tx_token.take().unwrap());
+                continue;
+            }
+
+            // The packet is sent to ourselves. Unlike TCP and UDP sockets, no locks are held at this
+            // point, so we can process the packet and the replies directly.
+            let mut data = emit_ip_packet(&pkt, &self.iface_cx.caps);
+            loop {
+                let Ok(pkt) = Ipv4Packet::new_checked(data.as_slice()) else {
+                    break;
+                };
+                let Some(reply) = self.parse_and_process_ipv4(pkt) else {
+                    break;
+                };
+
+                if !self.is_unicast_local(reply.ip_repr().dst_addr()) {
+                    dispatch_phy(&reply, self.iface_cx, tx_token.take().unwrap());
+                    break;
+                }
+
+                data = emit_ip_packet(&reply, &self.iface_cx.caps);
+            }
+
+            if tx_token.is_none() {
+                break;
+            }
+        }
+
+        (did_something, tx_token)
+    }
+}
+
+/// Emits the IP packet into a new buffer.
+fn emit_ip_packet(pkt: &Packet, caps: &DeviceCapabilities) -> Vec<u8> {
+    let ip_repr = pkt.ip_repr();
+
+    let mut data = vec![0; ip_repr.buffer_len()];
+    ip_repr.emit(data.as_mut_slice(), &caps.checksum);
+    pkt.emit_payload(&ip_repr, &mut data[ip_repr.header_len()..], caps);
+
+    data
 }
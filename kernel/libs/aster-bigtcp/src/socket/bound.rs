@@ -13,14 +13,15 @@ use smoltcp::{
     iface::Context,
     socket::{tcp::State, udp::UdpMetadata, PollAt},
     time::{Duration, Instant},
-    wire::{IpEndpoint, IpRepr, TcpControl, TcpRepr, UdpRepr},
+    wire::{IpAddress, IpEndpoint, IpRepr, TcpControl, TcpRepr, UdpRepr},
 };
 use spin::Once;
 use takeable::Takeable;
 
 use super::{
+    congestion::{CongestionControl, CongestionInfo, CongestionTracker},
     event::{SocketEventObserver, SocketEvents},
-    option::{RawTcpOption, RawTcpSetOption},
+    option::{RawTcpOption, RawTcpSetOption, RawUdpOption, RawUdpSetOption},
     unbound::{new_tcp_socket, new_udp_socket},
     RawTcpSocket, RawUdpSocket, TcpStateCheck,
 };
@@ -91,6 +92,7 @@ struct RawTcpSocketExt<E: Ext> {
     socket: Box<RawTcpSocket>,
     listener: Option<Arc<TcpListenerBg<E>>>,
     has_connected: bool,
+    congestion: CongestionTracker,
 }
 
 impl<E: Ext> Deref for RawTcpSocketExt<E> {
@@ -159,11 +161,16 @@ impl<E: Ext> RawTcpSocketExt<E> {
 }
 
 impl<E: Ext> TcpConnectionInner<E> {
-    fn new(socket: Box<RawTcpSocket>, listener: Option<Arc<TcpListenerBg<E>>>) -> Self {
+    fn new(
+        socket: Box<RawTcpSocket>,
+        listener: Option<Arc<TcpListenerBg<E>>>,
+        congestion: CongestionTracker,
+    ) -> Self {
         let socket_ext = RawTcpSocketExt {
             socket,
             listener,
             has_connected: false,
+            congestion,
         };
 
         TcpConnectionInner {
@@ -213,6 +220,7 @@ impl<E: Ext> Inner<E> for TcpConnectionInner<E> {
 pub struct TcpBacklog<E: Ext> {
     socket: Box<RawTcpSocket>,
     max_conn: usize,
+    congestion_control: CongestionControl,
     connecting: BTreeSet<TcpConnection<E>>,
     connected: Vec<TcpConnection<E>>,
 }
@@ -244,7 +252,25 @@ impl<E: Ext> Inner<E> for TcpListenerInner<E> {
 }
 
 /// States needed by [`UdpSocketBg`] but not [`TcpConnectionBg`].
-type UdpSocketInner = SpinLock<Box<RawUdpSocket>, LocalIrqDisabled>;
+pub struct UdpSocketInner {
+    socket: SpinLock<Box<RawUdpSocket>, LocalIrqDisabled>,
+    multicast_ttl: AtomicU8,
+    multicast_loop: AtomicBool,
+}
+
+impl UdpSocketInner {
+    fn new(socket: Box<RawUdpSocket>, option: &RawUdpOption) -> Self {
+        Self {
+            socket: SpinLock::new(socket),
+            multicast_ttl: AtomicU8::new(option.multicast_ttl),
+            multicast_loop: AtomicBool::new(option.multicast_loop),
+        }
+    }
+
+    fn lock(&self) -> SpinLockGuard<Box<RawUdpSocket>, LocalIrqDisabled> {
+        self.socket.lock()
+    }
+}
 
 impl<E: Ext> Inner<E> for UdpSocketInner {
     type Observer = E::UdpEventObserver;
@@ -297,10 +323,6 @@ impl<T: Inner<E>, E: Ext> Socket<T, E> {
         self.0.observer.call_once(|| new_observer);
     }
 
-    pub fn local_endpoint(&self) -> Option<IpEndpoint> {
-        self.0.bound.endpoint()
-    }
-
     pub fn iface(&self) -> &Arc<dyn Iface<E>> {
         self.0.bound.iface()
     }
@@ -346,7 +368,9 @@ impl<E: Ext> TcpConnection<E> {
             let common = bound.iface().common();
             let mut iface = common.interface();
 
-            if let Err(err) = socket.connect(iface.context(), remote_endpoint, bound.port()) {
+            if let Err(err) =
+                socket.connect(iface.context(), remote_endpoint, bound.listen_endpoint())
+            {
                 drop(iface);
                 return Err((bound, err));
             }
@@ -354,7 +378,8 @@ impl<E: Ext> TcpConnection<E> {
             socket
         };
 
-        let inner = TcpConnectionInner::new(socket, None);
+        let congestion = CongestionTracker::new(option.congestion_control);
+        let inner = TcpConnectionInner::new(socket, None, congestion);
 
         let connection = Self::new(bound, inner);
         connection.0.update_next_poll_at_ms(PollAt::Now);
@@ -451,6 +476,17 @@ impl<E: Ext> TcpConnection<E> {
         self.0.update_next_poll_at_ms(PollAt::Now);
     }
 
+    /// Returns the local endpoint.
+    ///
+    /// If the port is bound to a wildcard address, the actual local address is returned once the
+    /// connection has picked it.
+    pub fn local_endpoint(&self) -> IpEndpoint {
+        let socket = self.0.inner.lock();
+        socket
+            .local_endpoint()
+            .unwrap_or_else(|| self.0.bound.endpoint())
+    }
+
     /// Calls `f` with an immutable reference to the associated [`RawTcpSocket`].
     //
     // NOTE: If a mutable reference is required, add a method above that correctly updates the next
@@ -462,6 +498,11 @@ impl<E: Ext> TcpConnection<E> {
         let socket = self.0.inner.lock();
         f(&socket)
     }
+
+    /// Returns the congestion information.
+    pub fn congestion_info(&self) -> CongestionInfo {
+        self.0.inner.lock().congestion.info()
+    }
 }
 
 impl<E: Ext> RawTcpSetOption for TcpConnection<E> {
@@ -481,6 +522,11 @@ impl<E: Ext> RawTcpSetOption for TcpConnection<E> {
         let mut socket = self.0.inner.lock();
         socket.set_nagle_enabled(enabled);
     }
+
+    fn set_congestion_control(&self, congestion_control: CongestionControl) {
+        let mut socket = self.0.inner.lock();
+        socket.congestion.set_algorithm(congestion_control);
+    }
 }
 
 impl<E: Ext> TcpListener<E> {
@@ -493,16 +539,12 @@ impl<E: Ext> TcpListener<E> {
         option: &RawTcpOption,
         observer: E::TcpEventObserver,
     ) -> Result<Self, (BoundPort<E>, smoltcp::socket::tcp::ListenError)> {
-        let Some(local_endpoint) = bound.endpoint() else {
-            return Err((bound, smoltcp::socket::tcp::ListenError::Unaddressable));
-        };
-
         let socket = {
             let mut socket = new_tcp_socket();
 
             option.apply(&mut socket);
 
-            if let Err(err) = socket.listen(local_endpoint) {
+            if let Err(err) = socket.listen(bound.listen_endpoint()) {
                 return Err((bound, err));
             }
 
@@ -512,6 +554,7 @@ impl<E: Ext> TcpListener<E> {
         let inner = TcpListenerInner::new(TcpBacklog {
             socket,
             max_conn,
+            congestion_control: option.congestion_control,
             connecting: BTreeSet::new(),
             connected: Vec::new(),
         });
@@ -547,6 +590,11 @@ impl<E: Ext> TcpListener<E> {
         Some((accepted, remote_endpoint.unwrap()))
     }
 
+    /// Returns the local endpoint.
+    pub fn local_endpoint(&self) -> IpEndpoint {
+        self.0.bound.endpoint()
+    }
+
     /// Returns whether there is a TCP connection to accept.
     ///
     /// It's the caller's responsibility to deal with race conditions when using this method.
@@ -567,6 +615,11 @@ impl<E: Ext> RawTcpSetOption for TcpListener<E> {
         let mut backlog = self.0.inner.lock();
         backlog.socket.set_nagle_enabled(enabled);
     }
+
+    fn set_congestion_control(&self, congestion_control: CongestionControl) {
+        let mut backlog = self.0.inner.lock();
+        backlog.congestion_control = congestion_control;
+    }
 }
 
 impl<E: Ext> UdpSocket<E> {
@@ -575,23 +628,20 @@ impl<E: Ext> UdpSocket<E> {
     /// Polling the iface is _not_ required after this method succeeds.
     pub fn new_bind(
         bound: BoundPort<E>,
+        option: &RawUdpOption,
         observer: E::UdpEventObserver,
     ) -> Result<Self, (BoundPort<E>, smoltcp::socket::udp::BindError)> {
-        let Some(local_endpoint) = bound.endpoint() else {
-            return Err((bound, smoltcp::socket::udp::BindError::Unaddressable));
-        };
-
         let socket = {
             let mut socket = new_udp_socket();
 
-            if let Err(err) = socket.bind(local_endpoint) {
+            if let Err(err) = socket.bind(bound.listen_endpoint()) {
                 return Err((bound, err));
             }
 
             socket
         };
 
-        let inner = UdpSocketInner::new(socket);
+        let inner = UdpSocketInner::new(socket, option);
 
         let socket = Self::new(bound, inner);
         socket.init_observer(observer);
@@ -603,6 +653,11 @@ impl<E: Ext> UdpSocket<E> {
         Ok(socket)
     }
 
+    /// Returns the local endpoint.
+    pub fn local_endpoint(&self) -> IpEndpoint {
+        self.0.bound.endpoint()
+    }
+
     /// Sends some data.
     ///
     /// Polling the iface is _always_ required after this method succeeds.
@@ -664,6 +719,19 @@ impl<E: Ext> UdpSocket<E> {
     }
 }
 
+impl<E: Ext> RawUdpSetOption for UdpSocket<E> {
+    fn set_multicast_ttl(&self, ttl: u8) {
+        self.0.inner.multicast_ttl.store(ttl, Ordering::Relaxed);
+    }
+
+    fn set_multicast_loop(&self, enabled: bool) {
+        self.0
+            .inner
+            .multicast_loop
+            .store(enabled, Ordering::Relaxed);
+    }
+}
+
 impl<T: Inner<E>, E: Ext> SocketBg<T, E> {
     pub(crate) fn has_events(&self) -> bool {
         self.events.load(Ordering::Relaxed) != 0
@@ -758,8 +826,8 @@ impl<T: Inner<E>, E: Ext> SocketBg<T, E> {
     /// Returns whether an incoming packet _may_ be processed by the socket.
     ///
     /// The check is intended to be lock-free and fast, but may have false positives.
-    pub(crate) fn can_process(&self, dst_port: u16) -> bool {
-        self.bound.port() == dst_port
+    pub(crate) fn can_process(&self, dst_addr: &IpAddress, dst_port: u16) -> bool {
+        self.bound.port() == dst_port && self.bound.can_process(dst_addr)
     }
 
     /// Returns whether the socket _may_ generate an outgoing packet.
@@ -817,6 +885,7 @@ impl<E: Ext> TcpConnectionBg<E> {
         // to be queued.
         let mut events = SocketEvents::CAN_RECV | SocketEvents::CAN_SEND;
 
+        socket.congestion.on_recv(cx.now(), tcp_repr);
         let result = match socket.process(cx, ip_repr, tcp_repr) {
             None => TcpProcessResult::Processed,
             Some((ip_repr, tcp_repr)) => TcpProcessResult::ProcessedWithReply(ip_repr, tcp_repr),
@@ -846,13 +915,33 @@ impl<E: Ext> TcpConnectionBg<E> {
         let old_state = socket.state();
         let mut events = SocketEvents::empty();
 
+        let RawTcpSocketExt {
+            socket: raw_socket,
+            congestion,
+            ..
+        } = &mut *socket;
+
         let mut reply = None;
-        socket
-            .dispatch(cx, |cx, (ip_repr, tcp_repr)| {
+        let mut is_held_back = false;
+        // The only error is that the segment is held back, which `smoltcp` will generate again
+        // in a later dispatch.
+        let _ = raw_socket.dispatch(cx, |cx, (mut ip_repr, tcp_repr)| {
+            if congestion.allows_send(&tcp_repr) {
+                congestion.on_send(cx.now(), &tcp_repr);
                 reply = dispatch(cx, &ip_repr, &tcp_repr);
-                Ok::<(), ()>(())
-            })
-            .unwrap();
+                return Ok(());
+            }
+
+            // The data are held back by the congestion window, but the acknowledgment is sent
+            // now. Otherwise, the data of the peer would be left unacknowledged.
+            is_held_back = true;
+            if let Some(ack_repr) = congestion.ack_for_held_back(&tcp_repr) {
+                ip_repr.set_payload_len(ack_repr.buffer_len());
+                congestion.on_send(cx.now(), &ack_repr);
+                reply = dispatch(cx, &ip_repr, &ack_repr);
+            }
+            Err(())
+        });
 
         // `dispatch` can return a packet in response to the generated packet. If the socket
         // accepts the packet, we can process it directly.
@@ -860,6 +949,7 @@ impl<E: Ext> TcpConnectionBg<E> {
             if !socket.accepts(cx, ip_repr, tcp_repr) {
                 break;
             }
+            socket.congestion.on_recv(cx.now(), tcp_repr);
             reply = socket.process(cx, ip_repr, tcp_repr);
             events |= SocketEvents::CAN_RECV | SocketEvents::CAN_SEND;
         }
@@ -869,7 +959,15 @@ impl<E: Ext> TcpConnectionBg<E> {
         }
 
         this.add_events(events);
-        this.update_next_poll_at_ms(socket.poll_at(cx));
+        let poll_at = if is_held_back {
+            // `smoltcp` always wants to send the held-back data now. The incoming ACKs will open
+            // the congestion window, so the socket only needs to be polled again when a
+            // retransmission may be due.
+            PollAt::Time(cx.now() + socket.congestion.rto())
+        } else {
+            socket.poll_at(cx)
+        };
+        this.update_next_poll_at_ms(poll_at);
 
         reply
     }
@@ -912,14 +1010,22 @@ impl<E: Ext> TcpListenerBg<E> {
             socket
         };
 
+        // The new connection needs to know the options in the SYN segment.
+        let mut congestion = CongestionTracker::new(backlog.congestion_control);
+        congestion.on_syn(tcp_repr);
+
         let inner = TcpConnectionInner::new(
             core::mem::replace(&mut backlog.socket, new_socket),
             Some(this.clone().into()),
+            congestion,
         );
         let conn = TcpConnection::new(
             this.bound
                 .iface()
-                .bind(BindPortConfig::CanReuse(this.bound.port()))
+                .bind(
+                    Some(ip_repr.dst_addr()),
+                    BindPortConfig::CanReuse(this.bound.port()),
+                )
                 .unwrap(),
             inner,
         );
@@ -963,6 +1069,11 @@ impl<E: Ext> UdpSocketBg<E> {
         true
     }
 
+    /// Returns whether outgoing IPv4 multicast packets are looped back to the local sockets.
+    pub(crate) fn multicast_loop(&self) -> bool {
+        self.inner.multicast_loop.load(Ordering::Relaxed)
+    }
+
     /// Tries to generate an outgoing packet and dispatches the generated packet.
     pub(crate) fn dispatch<D>(&self, cx: &mut Context, dispatch: D)
     where
@@ -971,7 +1082,12 @@ impl<E: Ext> UdpSocketBg<E> {
         let mut socket = self.inner.lock();
 
         socket
-            .dispatch(cx, |cx, _meta, (ip_repr, udp_repr, udp_payload)| {
+            .dispatch(cx, |cx, _meta, (mut ip_repr, udp_repr, udp_payload)| {
+                if let IpRepr::Ipv4(ref mut ipv4_repr) = ip_repr {
+                    if ipv4_repr.dst_addr.is_multicast() {
+                        ipv4_repr.hop_limit = self.inner.multicast_ttl.load(Ordering::Relaxed);
+                    }
+                }
                 dispatch(cx, &ip_repr, &udp_repr, udp_payload);
                 Ok::<(), ()>(())
             })
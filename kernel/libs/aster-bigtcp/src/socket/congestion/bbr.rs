@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The BBR congestion control algorithm.
+//!
+//! The algorithm is described in
+//! <https://datatracker.ietf.org/doc/html/draft-cardwell-iccrg-bbr-congestion-control-00>.
+//!
+//! The original algorithm controls the sending rate mainly by pacing. However, segments are
+//! transmitted as soon as the window allows, so this implementation applies the pacing gains to
+//! the congestion window instead. The Drain phase is replaced by the low-gain phase of the
+//! ProbeBW gain cycle, which is entered right after the Startup phase.
+
+use smoltcp::time::{Duration, Instant};
+
+use super::{initial_cwnd, CongestionController, RttEstimator};
+
+pub(super) struct Bbr {
+    mss: usize,
+    cwnd: usize,
+    mode: Mode,
+    /// The delivery rates (in bytes per second) measured in the recent rounds.
+    bw_samples: [u64; BW_FILTER_ROUNDS],
+    /// The number of rounds that have completed.
+    round_count: usize,
+    /// The start time of the current round.
+    round_start: Option<Instant>,
+    /// The number of bytes delivered in the current round.
+    round_delivered: usize,
+    /// The bandwidth when the Startup phase last saw a significant growth.
+    full_bw: u64,
+    /// The number of rounds without a significant bandwidth growth.
+    full_bw_rounds: usize,
+    /// Whether the bottleneck bandwidth has been reached.
+    is_full_bw_reached: bool,
+    /// The minimum RTT (in microseconds) and the time when it was measured.
+    min_rtt: Option<(u64, Instant)>,
+    /// The index into [`PROBE_BW_GAINS_PERCENT`].
+    cycle_index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Startup,
+    ProbeBw,
+    ProbeRtt { done_at: Instant },
+}
+
+/// The number of rounds in which the maximum delivery rate is taken as the bottleneck bandwidth.
+const BW_FILTER_ROUNDS: usize = 10;
+
+/// The time window in which the minimum RTT is taken as the round-trip propagation time.
+const MIN_RTT_WINDOW: Duration = Duration::from_secs(10);
+
+/// The time to stay in the ProbeRTT phase.
+const PROBE_RTT_DURATION: Duration = Duration::from_millis(200);
+
+/// The gain in the Startup phase (`2 / ln(2)`).
+const STARTUP_GAIN_PERCENT: usize = 289;
+
+/// The gain of the congestion window in the ProbeBW phase.
+const CWND_GAIN_PERCENT: usize = 200;
+
+/// The gain cycle in the ProbeBW phase, which advances once per round.
+const PROBE_BW_GAINS_PERCENT: [usize; 8] = [75, 125, 100, 100, 100, 100, 100, 100];
+
+/// The number of rounds without a 25% bandwidth growth before leaving the Startup phase.
+const FULL_BW_ROUNDS: usize = 3;
+
+/// The minimum congestion window in segments.
+const MIN_CWND_SEGMENTS: usize = 4;
+
+/// The granularity of the clock.
+///
+/// RTTs that are smaller than the granularity cannot be measured precisely, so they are rounded
+/// up to the granularity.
+const CLOCK_GRANULARITY_US: u64 = 1000;
+
+const US_PER_SEC: u64 = 1_000_000;
+
+impl Bbr {
+    pub(super) fn new(mss: usize) -> Self {
+        Self {
+            mss,
+            cwnd: initial_cwnd(mss),
+            mode: Mode::Startup,
+            bw_samples: [0; BW_FILTER_ROUNDS],
+            round_count: 0,
+            round_start: None,
+            round_delivered: 0,
+            full_bw: 0,
+            full_bw_rounds: 0,
+            is_full_bw_reached: false,
+            min_rtt: None,
+            cycle_index: 0,
+        }
+    }
+
+    /// Returns the bottleneck bandwidth in bytes per second.
+    fn max_bw(&self) -> u64 {
+        self.bw_samples.iter().copied().max().unwrap()
+    }
+
+    /// Returns the estimated bandwidth-delay product in bytes.
+    fn bdp(&self) -> usize {
+        let Some((min_rtt_us, _)) = self.min_rtt else {
+            return 0;
+        };
+
+        (self.max_bw() * min_rtt_us.max(CLOCK_GRANULARITY_US) / US_PER_SEC) as usize
+    }
+
+    fn update_min_rtt(&mut self, now: Instant, rtt: &RttEstimator) {
+        let is_expired = self
+            .min_rtt
+            .is_some_and(|(_, measured_at)| now > measured_at + MIN_RTT_WINDOW);
+
+        if let Some(latest) = rtt.latest() {
+            let latest_us = latest.total_micros();
+            if is_expired || self.min_rtt.is_none_or(|(min_us, _)| latest_us < min_us) {
+                self.min_rtt = Some((latest_us, now));
+            }
+        }
+
+        match self.mode {
+            Mode::ProbeRtt { done_at } if now >= done_at => {
+                self.mode = if self.is_full_bw_reached {
+                    Mode::ProbeBw
+                } else {
+                    Mode::Startup
+                };
+            }
+            Mode::ProbeRtt { .. } => (),
+            _ if is_expired => {
+                self.mode = Mode::ProbeRtt {
+                    done_at: now + PROBE_RTT_DURATION,
+                };
+            }
+            _ => (),
+        }
+    }
+
+    fn update_round(&mut self, now: Instant, acked: usize, rtt: &RttEstimator) {
+        self.round_delivered += acked;
+
+        let round_start = *self.round_start.get_or_insert(now);
+        let round_us = (now - round_start).total_micros();
+        let expected_round_us = rtt
+            .srtt()
+            .map_or(0, |srtt| srtt.total_micros())
+            .max(CLOCK_GRANULARITY_US);
+        if round_us < expected_round_us {
+            return;
+        }
+
+        let bw = self.round_delivered as u64 * US_PER_SEC / round_us;
+        self.bw_samples[self.round_count % BW_FILTER_ROUNDS] = bw;
+        self.round_count += 1;
+        self.round_start = Some(now);
+        self.round_delivered = 0;
+
+        match self.mode {
+            Mode::Startup => self.check_full_bw(),
+            Mode::ProbeBw => {
+                self.cycle_index = (self.cycle_index + 1) % PROBE_BW_GAINS_PERCENT.len();
+            }
+            Mode::ProbeRtt { .. } => (),
+        }
+    }
+
+    fn check_full_bw(&mut self) {
+        let max_bw = self.max_bw();
+        if max_bw >= self.full_bw * 5 / 4 {
+            self.full_bw = max_bw;
+            self.full_bw_rounds = 0;
+            return;
+        }
+
+        self.full_bw_rounds += 1;
+        if self.full_bw_rounds >= FULL_BW_ROUNDS {
+            self.is_full_bw_reached = true;
+            self.mode = Mode::ProbeBw;
+            self.cycle_index = 0;
+        }
+    }
+
+    fn update_cwnd(&mut self, acked: usize) {
+        let min_cwnd = self.mss * MIN_CWND_SEGMENTS;
+
+        let target = match self.mode {
+            Mode::Startup => self.bdp() * STARTUP_GAIN_PERCENT / 100,
+            Mode::ProbeBw => {
+                self.bdp() * CWND_GAIN_PERCENT * PROBE_BW_GAINS_PERCENT[self.cycle_index]
+                    / (100 * 100)
+            }
+            Mode::ProbeRtt { .. } => {
+                self.cwnd = min_cwnd;
+                return;
+            }
+        };
+
+        if self.is_full_bw_reached {
+            self.cwnd = (self.cwnd + acked).min(target);
+        } else if self.cwnd < target || target == 0 {
+            self.cwnd += acked;
+        }
+        self.cwnd = self.cwnd.max(min_cwnd);
+    }
+}
+
+impl CongestionController for Bbr {
+    fn cwnd(&self) -> usize {
+        self.cwnd
+    }
+
+    fn ssthresh(&self) -> usize {
+        usize::MAX
+    }
+
+    fn set_mss(&mut self, mss: usize) {
+        *self = Self::new(mss);
+    }
+
+    fn on_ack(&mut self, now: Instant, acked: usize, rtt: &RttEstimator) {
+        self.update_min_rtt(now, rtt);
+        self.update_round(now, acked, rtt);
+        self.update_cwnd(acked);
+    }
+
+    fn on_fast_retransmit(&mut self, _now: Instant) {
+        // BBR does not treat packet losses as congestion signals.
+    }
+
+    fn on_retransmit_timeout(&mut self, _now: Instant) {
+        // The window is restored quickly by the new ACKs if the bandwidth is still available.
+        self.cwnd = self.mss;
+    }
+}
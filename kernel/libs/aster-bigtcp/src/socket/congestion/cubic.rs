@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The CUBIC congestion control algorithm.
+//!
+//! The algorithm is described in <https://datatracker.ietf.org/doc/html/rfc8312>. All the
+//! computations are done with integers, so the constants are written as fractions.
+
+use smoltcp::time::Instant;
+
+use super::{initial_cwnd, min_ssthresh, CongestionController, RttEstimator};
+
+pub(super) struct Cubic {
+    mss: usize,
+    cwnd: usize,
+    ssthresh: usize,
+    /// The window size just before the last reduction.
+    w_max: usize,
+    /// The start time of the current congestion avoidance epoch.
+    epoch_start: Option<Instant>,
+    /// The time period (in milliseconds) to increase the window to `w_max` in this epoch.
+    k_ms: u64,
+    /// The estimated window size of Reno, which is used in the TCP-friendly region.
+    w_est: usize,
+}
+
+/// The multiplicative decrease factor `β_cubic` (0.7).
+const BETA_NUM: usize = 7;
+const BETA_DEN: usize = 10;
+
+/// The scaling constant `C` (0.4).
+const C_NUM: u128 = 4;
+const C_DEN: u128 = 10;
+
+/// The additive increase factor of the TCP-friendly region `α_cubic` (`3 * (1 - β) / (1 + β)`).
+const ALPHA_NUM: usize = 9;
+const ALPHA_DEN: usize = 17;
+
+/// The time that exceeds this limit (about 16 minutes) is clamped to avoid overflows.
+const MAX_TIME_MS: i128 = 1_000_000;
+
+const MS_PER_SEC: u128 = 1000;
+
+impl Cubic {
+    pub(super) fn new(mss: usize) -> Self {
+        Self {
+            mss,
+            cwnd: initial_cwnd(mss),
+            ssthresh: usize::MAX,
+            w_max: 0,
+            epoch_start: None,
+            k_ms: 0,
+            w_est: 0,
+        }
+    }
+
+    fn reduce_ssthresh(&mut self) {
+        // Fast convergence: If the window is reduced again before it reaches the previous
+        // `w_max`, release more bandwidth for the new flows.
+        self.w_max = if self.cwnd < self.w_max {
+            self.cwnd * (BETA_DEN + BETA_NUM) / (BETA_DEN * 2)
+        } else {
+            self.cwnd
+        };
+
+        self.ssthresh = (self.cwnd * BETA_NUM / BETA_DEN).max(min_ssthresh(self.mss));
+        self.epoch_start = None;
+    }
+
+    fn start_epoch(&mut self, now: Instant) -> Instant {
+        if let Some(epoch_start) = self.epoch_start {
+            return epoch_start;
+        }
+
+        // K = cubic_root((W_max - cwnd) / C)
+        self.k_ms = if self.cwnd < self.w_max {
+            let bytes = (self.w_max - self.cwnd) as u128;
+            let k_ms_cubed = bytes * C_DEN * MS_PER_SEC.pow(3) / (C_NUM * self.mss as u128);
+            cbrt(k_ms_cubed.min(u64::MAX as u128) as u64)
+        } else {
+            self.w_max = self.cwnd;
+            0
+        };
+        self.w_est = self.cwnd;
+
+        self.epoch_start = Some(now);
+        now
+    }
+
+    /// Computes `W_cubic(t) = C * (t - K)^3 + W_max`.
+    fn w_cubic(&self, t_ms: u64) -> usize {
+        let offset_ms = (t_ms as i128 - self.k_ms as i128).clamp(-MAX_TIME_MS, MAX_TIME_MS);
+        let delta = offset_ms.pow(3) * C_NUM as i128 * self.mss as i128
+            / (C_DEN * MS_PER_SEC.pow(3)) as i128;
+
+        (self.w_max as i128 + delta).max(self.mss as i128) as usize
+    }
+}
+
+impl CongestionController for Cubic {
+    fn cwnd(&self) -> usize {
+        self.cwnd
+    }
+
+    fn ssthresh(&self) -> usize {
+        self.ssthresh
+    }
+
+    fn set_mss(&mut self, mss: usize) {
+        *self = Self::new(mss);
+    }
+
+    fn on_ack(&mut self, now: Instant, acked: usize, rtt: &RttEstimator) {
+        if self.cwnd < self.ssthresh {
+            self.cwnd += acked.min(self.mss * 2);
+            return;
+        }
+
+        let epoch_start = self.start_epoch(now);
+
+        // The target is the window size after one RTT, which should not grow too fast.
+        let rtt_ms = rtt.srtt().map_or(0, |srtt| srtt.total_millis());
+        let t_ms = (now - epoch_start).total_millis() + rtt_ms;
+        let target = self.w_cubic(t_ms).clamp(self.cwnd, self.cwnd * 3 / 2);
+        if target > self.cwnd {
+            let increment = ((target - self.cwnd) * acked).div_ceil(self.cwnd);
+            self.cwnd = (self.cwnd + increment).min(target);
+        }
+
+        // TCP-friendly region: The window should grow at least as fast as Reno.
+        self.w_est += (ALPHA_NUM * acked * self.mss).div_ceil(ALPHA_DEN * self.cwnd);
+        if self.w_est > self.cwnd {
+            self.cwnd = self.w_est;
+        }
+    }
+
+    fn on_fast_retransmit(&mut self, _now: Instant) {
+        self.reduce_ssthresh();
+        self.cwnd = self.ssthresh;
+    }
+
+    fn on_retransmit_timeout(&mut self, _now: Instant) {
+        self.reduce_ssthresh();
+        self.cwnd = self.mss;
+    }
+}
+
+/// Computes the integer cube root.
+fn cbrt(n: u64) -> u64 {
+    // The result cannot exceed 2^22 - 1, since (2^22)^3 = 2^66 > u64::MAX.
+    let (mut low, mut high) = (0u64, 1u64 << 22);
+
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        if (mid as u128).pow(3) <= n as u128 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    low
+}
@@ -0,0 +1,413 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! TCP congestion control.
+//!
+//! [`smoltcp`] sends as much data as the receive window of the peer allows. To implement
+//! congestion control on top of it, the segments of each TCP connection are observed by a
+//! [`CongestionController`], which maintains the congestion window. The congestion window is then
+//! enforced when the segments are dispatched: a segment with new data beyond the congestion window
+//! is held back and [`smoltcp`] sends it again later. The incoming segments are passed to
+//! [`smoltcp`] untouched, so its view of the receive window of the peer stays intact.
+
+use alloc::boxed::Box;
+
+use smoltcp::{
+    time::{Duration, Instant},
+    wire::{TcpControl, TcpRepr, TcpSeqNumber},
+};
+
+mod bbr;
+mod cubic;
+mod reno;
+mod rtt;
+
+pub use rtt::RttEstimator;
+
+/// A congestion control algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CongestionControl {
+    Reno,
+    Cubic,
+    Bbr,
+}
+
+impl CongestionControl {
+    const RENO: &'static str = "reno";
+    const CUBIC: &'static str = "cubic";
+    const BBR: &'static str = "bbr";
+
+    /// Looks up the congestion control algorithm by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            Self::RENO => Some(Self::Reno),
+            Self::CUBIC => Some(Self::Cubic),
+            Self::BBR => Some(Self::Bbr),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the congestion control algorithm.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Reno => Self::RENO,
+            Self::Cubic => Self::CUBIC,
+            Self::Bbr => Self::BBR,
+        }
+    }
+
+    fn new_controller(&self, mss: usize) -> Box<dyn CongestionController> {
+        match self {
+            Self::Reno => Box::new(reno::Reno::new(mss)),
+            Self::Cubic => Box::new(cubic::Cubic::new(mss)),
+            Self::Bbr => Box::new(bbr::Bbr::new(mss)),
+        }
+    }
+}
+
+/// A congestion controller that maintains the congestion window of a TCP connection.
+///
+/// All the window sizes are in bytes.
+pub trait CongestionController: Send {
+    /// Returns the congestion window.
+    fn cwnd(&self) -> usize;
+
+    /// Returns the slow start threshold.
+    fn ssthresh(&self) -> usize;
+
+    /// Resets the controller with a new maximum segment size.
+    ///
+    /// This method is called when the connection is established, before any data is sent.
+    fn set_mss(&mut self, mss: usize);
+
+    /// Called when new data is acknowledged.
+    ///
+    /// This method will not be called in the loss recovery process.
+    fn on_ack(&mut self, now: Instant, acked: usize, rtt: &RttEstimator);
+
+    /// Called when a segment loss is detected by duplicate ACKs.
+    fn on_fast_retransmit(&mut self, now: Instant);
+
+    /// Called when a segment is retransmitted because the retransmission timer expires.
+    fn on_retransmit_timeout(&mut self, now: Instant);
+}
+
+/// The state of the congestion control.
+///
+/// The values have the same meaning as Linux's `tcp_ca_state`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CongestionState {
+    /// Nothing bad has been observed.
+    Open,
+    /// Duplicate ACKs have been received, but they are not enough to detect a loss.
+    Disorder,
+    /// A loss has been detected by duplicate ACKs and the fast recovery is in progress.
+    Recovery,
+    /// The retransmission timer has expired and the lost segments are being retransmitted.
+    Loss,
+}
+
+/// The congestion information of a TCP connection.
+#[derive(Debug, Clone, Copy)]
+pub struct CongestionInfo {
+    /// The congestion control algorithm.
+    pub algorithm: CongestionControl,
+    /// The state of the congestion control.
+    pub state: CongestionState,
+    /// The maximum segment size in bytes.
+    pub mss: usize,
+    /// The congestion window in bytes.
+    pub cwnd: usize,
+    /// The slow start threshold in bytes.
+    pub ssthresh: usize,
+    /// The number of bytes that have been sent but not acknowledged.
+    pub unacked: usize,
+    /// The window scale of the peer.
+    pub remote_win_shift: u8,
+    /// The smoothed RTT.
+    pub srtt: Option<Duration>,
+    /// The RTT variation.
+    pub rttvar: Duration,
+    /// The minimum RTT.
+    pub min_rtt: Option<Duration>,
+    /// The retransmission timeout.
+    pub rto: Duration,
+    /// The number of retransmission timeouts since the last acknowledgment of new data.
+    pub retransmits: u32,
+    /// The total number of retransmitted segments.
+    pub total_retrans: u32,
+    /// The total number of acknowledged bytes.
+    pub bytes_acked: u64,
+}
+
+/// The maximum segment size if the peer does not specify one.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc9293#section-3.7.1>.
+const DEFAULT_MSS: usize = 536;
+
+/// The lower bound of the maximum segment size.
+///
+/// Smaller values specified by the peer are ignored, following Linux's `TCP_MIN_MSS`.
+const MIN_MSS: usize = 88;
+
+/// The number of duplicate ACKs that indicate a segment loss.
+const DUP_ACK_THRESHOLD: usize = 3;
+
+/// Returns the initial congestion window.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc6928>.
+fn initial_cwnd(mss: usize) -> usize {
+    mss * 10
+}
+
+/// Returns the minimum slow start threshold after a segment loss.
+fn min_ssthresh(mss: usize) -> usize {
+    mss * 2
+}
+
+/// A tracker that observes the segments of a TCP connection and drives the congestion
+/// controller.
+pub(super) struct CongestionTracker {
+    algorithm: CongestionControl,
+    controller: Box<dyn CongestionController>,
+    rtt: RttEstimator,
+    mss: usize,
+    remote_win_shift: u8,
+    /// The oldest unacknowledged sequence number and the next sequence number to send.
+    ///
+    /// This is `None` before the first segment is sent.
+    send_range: Option<(TcpSeqNumber, TcpSeqNumber)>,
+    /// The end sequence number and the sending time of the segment that is being timed.
+    timed_segment: Option<(TcpSeqNumber, Instant)>,
+    /// The acknowledgment number in the last outgoing segment.
+    last_ack: Option<TcpSeqNumber>,
+    dup_acks: usize,
+    /// The loss recovery state and the sequence number that ends the recovery.
+    recovery: Option<(CongestionState, TcpSeqNumber)>,
+    retransmits: u32,
+    total_retrans: u32,
+    bytes_acked: u64,
+}
+
+impl CongestionTracker {
+    pub(super) fn new(algorithm: CongestionControl) -> Self {
+        Self {
+            algorithm,
+            controller: algorithm.new_controller(DEFAULT_MSS),
+            rtt: RttEstimator::new(),
+            mss: DEFAULT_MSS,
+            remote_win_shift: 0,
+            send_range: None,
+            timed_segment: None,
+            last_ack: None,
+            dup_acks: 0,
+            recovery: None,
+            retransmits: 0,
+            total_retrans: 0,
+            bytes_acked: 0,
+        }
+    }
+
+    /// Switches to another congestion control algorithm.
+    pub(super) fn set_algorithm(&mut self, algorithm: CongestionControl) {
+        if self.algorithm == algorithm {
+            return;
+        }
+
+        self.algorithm = algorithm;
+        self.controller = algorithm.new_controller(self.mss);
+    }
+
+    /// Returns whether an outgoing segment is allowed by the congestion window.
+    ///
+    /// Only new data are limited. Segments without data and retransmissions are always
+    /// allowed, and so is new data if nothing is in flight, so the connection never stalls.
+    pub(super) fn allows_send(&self, tcp_repr: &TcpRepr) -> bool {
+        if tcp_repr.payload.is_empty() {
+            return true;
+        }
+
+        let Some((una, next)) = self.send_range else {
+            return true;
+        };
+        if tcp_repr.seq_number < next || una == next {
+            return true;
+        }
+
+        let end = tcp_repr.seq_number + tcp_repr.payload.len();
+        end - una <= self.controller.cwnd()
+    }
+
+    /// Returns the acknowledgment to be sent instead of a segment that is held back.
+    ///
+    /// The acknowledgment is only needed if it acknowledges new data. Otherwise, the peer
+    /// would take it as a duplicate ACK.
+    pub(super) fn ack_for_held_back<'a>(&self, tcp_repr: &TcpRepr<'a>) -> Option<TcpRepr<'a>> {
+        if tcp_repr.ack_number.is_none() || tcp_repr.ack_number == self.last_ack {
+            return None;
+        }
+
+        Some(TcpRepr {
+            control: TcpControl::None,
+            payload: &[],
+            ..*tcp_repr
+        })
+    }
+
+    /// Observes an outgoing segment.
+    pub(super) fn on_send(&mut self, now: Instant, tcp_repr: &TcpRepr) {
+        if tcp_repr.ack_number.is_some() {
+            self.last_ack = tcp_repr.ack_number;
+        }
+
+        let len = tcp_repr.segment_len();
+        if len == 0 || tcp_repr.control == TcpControl::Rst {
+            return;
+        }
+
+        let seq = tcp_repr.seq_number;
+        let end = seq + len;
+
+        let Some((una, next)) = self.send_range else {
+            self.send_range = Some((seq, end));
+            self.timed_segment = Some((end, now));
+            return;
+        };
+
+        if seq >= next {
+            if self.timed_segment.is_none() {
+                self.timed_segment = Some((end, now));
+            }
+            self.send_range = Some((una, end));
+        } else if seq >= una {
+            // Keep-alive segments are sent with `seq = una - 1`, so they are not counted here.
+            self.on_retransmit(now, seq, una);
+            self.send_range = Some((una, end.max(next)));
+        }
+    }
+
+    fn on_retransmit(&mut self, now: Instant, seq: TcpSeqNumber, una: TcpSeqNumber) {
+        // Karn's algorithm: Retransmitted segments cannot be used to measure the RTT.
+        self.timed_segment = None;
+        self.total_retrans += 1;
+
+        // Retransmissions in the fast recovery are triggered by the duplicate ACKs. Other
+        // retransmissions of the first unacknowledged segment are triggered by the timer.
+        let is_timeout = match self.recovery {
+            Some((CongestionState::Recovery, _)) => false,
+            Some((CongestionState::Loss, _)) => seq == una,
+            _ => true,
+        };
+        if !is_timeout {
+            return;
+        }
+
+        let (_, next) = self.send_range.unwrap();
+        self.recovery = Some((CongestionState::Loss, next));
+        self.dup_acks = 0;
+        self.retransmits += 1;
+        self.controller.on_retransmit_timeout(now);
+    }
+
+    /// Observes an incoming segment.
+    pub(super) fn on_recv(&mut self, now: Instant, tcp_repr: &TcpRepr) {
+        match tcp_repr.control {
+            TcpControl::Rst => return,
+            TcpControl::Syn => self.on_syn(tcp_repr),
+            _ => (),
+        }
+
+        let (Some(ack), Some((una, next))) = (tcp_repr.ack_number, self.send_range) else {
+            return;
+        };
+
+        if ack > una && ack <= next {
+            self.on_new_ack(now, ack, una);
+        } else if ack == una
+            && next > una
+            && tcp_repr.payload.is_empty()
+            && tcp_repr.control == TcpControl::None
+        {
+            self.on_dup_ack(now);
+        }
+    }
+
+    /// Observes the options in an incoming SYN segment.
+    pub(super) fn on_syn(&mut self, tcp_repr: &TcpRepr) {
+        self.remote_win_shift = tcp_repr.window_scale.unwrap_or(0);
+
+        if let Some(mss) = tcp_repr.max_seg_size {
+            self.mss = (mss as usize).max(MIN_MSS);
+            self.controller.set_mss(self.mss);
+        }
+    }
+
+    fn on_new_ack(&mut self, now: Instant, ack: TcpSeqNumber, una: TcpSeqNumber) {
+        let acked = ack - una;
+        let (_, next) = self.send_range.unwrap();
+        self.send_range = Some((ack, next));
+
+        self.dup_acks = 0;
+        self.retransmits = 0;
+        self.bytes_acked += acked as u64;
+
+        if let Some((end, sent_at)) = self.timed_segment {
+            if ack >= end {
+                self.rtt.sample((now - sent_at).total_micros());
+                self.timed_segment = None;
+            }
+        }
+
+        if let Some((_, recover)) = self.recovery {
+            // Partial ACKs do not end the loss recovery.
+            if ack < recover {
+                return;
+            }
+            self.recovery = None;
+        }
+
+        self.controller.on_ack(now, acked, &self.rtt);
+    }
+
+    fn on_dup_ack(&mut self, now: Instant) {
+        self.dup_acks += 1;
+        if self.dup_acks != DUP_ACK_THRESHOLD || self.recovery.is_some() {
+            return;
+        }
+
+        let (_, next) = self.send_range.unwrap();
+        self.recovery = Some((CongestionState::Recovery, next));
+        self.controller.on_fast_retransmit(now);
+    }
+
+    /// Returns the retransmission timeout.
+    pub(super) fn rto(&self) -> Duration {
+        self.rtt.rto()
+    }
+
+    /// Returns the congestion information.
+    pub(super) fn info(&self) -> CongestionInfo {
+        let state = match self.recovery {
+            Some((state, _)) => state,
+            None if self.dup_acks > 0 => CongestionState::Disorder,
+            None => CongestionState::Open,
+        };
+        let unacked = self.send_range.map_or(0, |(una, next)| next - una);
+
+        CongestionInfo {
+            algorithm: self.algorithm,
+            state,
+            mss: self.mss,
+            cwnd: self.controller.cwnd(),
+            ssthresh: self.controller.ssthresh(),
+            unacked,
+            remote_win_shift: self.remote_win_shift,
+            srtt: self.rtt.srtt(),
+            rttvar: self.rtt.rttvar(),
+            min_rtt: self.rtt.min_rtt(),
+            rto: self.rtt.rto(),
+            retransmits: self.retransmits,
+            total_retrans: self.total_retrans,
+            bytes_acked: self.bytes_acked,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The Reno congestion control algorithm.
+//!
+//! The algorithm is described in <https://datatracker.ietf.org/doc/html/rfc5681>.
+
+use smoltcp::time::Instant;
+
+use super::{initial_cwnd, min_ssthresh, CongestionController, RttEstimator};
+
+pub(super) struct Reno {
+    mss: usize,
+    cwnd: usize,
+    ssthresh: usize,
+    /// The number of bytes acknowledged since the congestion window was last increased in the
+    /// congestion avoidance phase.
+    bytes_acked: usize,
+}
+
+impl Reno {
+    pub(super) fn new(mss: usize) -> Self {
+        Self {
+            mss,
+            cwnd: initial_cwnd(mss),
+            ssthresh: usize::MAX,
+            bytes_acked: 0,
+        }
+    }
+
+    fn reduce_ssthresh(&mut self) {
+        self.ssthresh = (self.cwnd / 2).max(min_ssthresh(self.mss));
+        self.bytes_acked = 0;
+    }
+}
+
+impl CongestionController for Reno {
+    fn cwnd(&self) -> usize {
+        self.cwnd
+    }
+
+    fn ssthresh(&self) -> usize {
+        self.ssthresh
+    }
+
+    fn set_mss(&mut self, mss: usize) {
+        *self = Self::new(mss);
+    }
+
+    fn on_ack(&mut self, _now: Instant, acked: usize, _rtt: &RttEstimator) {
+        if self.cwnd < self.ssthresh {
+            // Slow start, with the byte counting limit described in
+            // <https://datatracker.ietf.org/doc/html/rfc3465>.
+            self.cwnd += acked.min(self.mss * 2);
+            return;
+        }
+
+        // Congestion avoidance, which increases the window by one segment per RTT.
+        self.bytes_acked += acked;
+        if self.bytes_acked >= self.cwnd {
+            self.bytes_acked -= self.cwnd;
+            self.cwnd += self.mss;
+        }
+    }
+
+    fn on_fast_retransmit(&mut self, _now: Instant) {
+        self.reduce_ssthresh();
+        self.cwnd = self.ssthresh;
+    }
+
+    fn on_retransmit_timeout(&mut self, _now: Instant) {
+        self.reduce_ssthresh();
+        self.cwnd = self.mss;
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use smoltcp::time::Duration;
+
+/// An estimator of the round-trip time (RTT).
+///
+/// The smoothed RTT and the RTT variation are computed as described in
+/// <https://datatracker.ietf.org/doc/html/rfc6298>.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RttEstimator {
+    /// The smoothed RTT in microseconds.
+    srtt_us: Option<u64>,
+    /// The RTT variation in microseconds.
+    rttvar_us: u64,
+    /// The latest RTT sample in microseconds.
+    latest_us: Option<u64>,
+    /// The minimum RTT sample in microseconds.
+    min_us: Option<u64>,
+}
+
+/// The initial retransmission timeout before any RTT samples are taken.
+const INITIAL_RTO: Duration = Duration::from_secs(1);
+
+/// The minimum retransmission timeout.
+///
+/// This follows Linux instead of the RFC, which suggests one second.
+const MIN_RTO: Duration = Duration::from_millis(200);
+
+impl RttEstimator {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the estimator with a new RTT sample.
+    pub(super) fn sample(&mut self, rtt_us: u64) {
+        match self.srtt_us {
+            None => {
+                self.srtt_us = Some(rtt_us);
+                self.rttvar_us = rtt_us / 2;
+            }
+            Some(srtt_us) => {
+                // RTTVAR <- (1 - 1/4) * RTTVAR + 1/4 * |SRTT - R'|
+                // SRTT <- (1 - 1/8) * SRTT + 1/8 * R'
+                self.rttvar_us = (self.rttvar_us * 3 + srtt_us.abs_diff(rtt_us)) / 4;
+                self.srtt_us = Some((srtt_us * 7 + rtt_us) / 8);
+            }
+        }
+
+        self.latest_us = Some(rtt_us);
+        self.min_us = Some(self.min_us.map_or(rtt_us, |min_us| min_us.min(rtt_us)));
+    }
+
+    /// Returns the smoothed RTT, or `None` if there are no RTT samples.
+    pub fn srtt(&self) -> Option<Duration> {
+        self.srtt_us.map(Duration::from_micros)
+    }
+
+    /// Returns the RTT variation.
+    pub fn rttvar(&self) -> Duration {
+        Duration::from_micros(self.rttvar_us)
+    }
+
+    /// Returns the latest RTT sample, or `None` if there are no RTT samples.
+    pub fn latest(&self) -> Option<Duration> {
+        self.latest_us.map(Duration::from_micros)
+    }
+
+    /// Returns the minimum RTT sample, or `None` if there are no RTT samples.
+    pub fn min_rtt(&self) -> Option<Duration> {
+        self.min_us.map(Duration::from_micros)
+    }
+
+    /// Returns the retransmission timeout.
+    pub fn rto(&self) -> Duration {
+        let Some(srtt_us) = self.srtt_us else {
+            return INITIAL_RTO;
+        };
+
+        let rto = Duration::from_micros(srtt_us + self.rttvar_us * 4);
+        if rto < MIN_RTO {
+            MIN_RTO
+        } else {
+            rto
+        }
+    }
+}
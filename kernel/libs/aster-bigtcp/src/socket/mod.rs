@@ -1,16 +1,26 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod bound;
+mod congestion;
 mod event;
 mod option;
+mod packet;
+mod raw;
 mod state;
 mod unbound;
 
 pub use bound::{ConnectState, NeedIfacePoll, TcpConnection, TcpListener, UdpSocket};
 pub(crate) use bound::{TcpConnectionBg, TcpListenerBg, TcpProcessResult, UdpSocketBg};
+pub use congestion::{
+    CongestionControl, CongestionController, CongestionInfo, CongestionState, RttEstimator,
+};
 pub use event::{SocketEventObserver, SocketEvents};
-pub use option::{RawTcpOption, RawTcpSetOption};
-pub use state::TcpStateCheck;
+pub use option::{RawTcpOption, RawTcpSetOption, RawUdpOption, RawUdpSetOption};
+pub(crate) use packet::PacketSocketBg;
+pub use packet::{FrameDirection, PacketSocket, PACKET_RECV_BUF_LEN};
+pub(crate) use raw::RawSocketBg;
+pub use raw::{RawIpHeader, RawSocket, RAW_RECV_BUF_LEN, RAW_SEND_BUF_LEN};
+pub use state::{TcpState, TcpStateCheck};
 pub use unbound::{TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN};
 
 pub type RawTcpSocket = smoltcp::socket::tcp::Socket<'static>;
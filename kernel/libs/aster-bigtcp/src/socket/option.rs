@@ -2,7 +2,7 @@
 
 use smoltcp::time::Duration;
 
-use super::{NeedIfacePoll, RawTcpSocket};
+use super::{CongestionControl, NeedIfacePoll, RawTcpSocket};
 
 /// A trait defines setting socket options on a raw socket.
 pub trait RawTcpSetOption {
@@ -15,6 +15,11 @@ pub trait RawTcpSetOption {
     ///
     /// Polling the iface is _not_ required after this method succeeds.
     fn set_nagle_enabled(&self, enabled: bool);
+
+    /// Sets the congestion control algorithm.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    fn set_congestion_control(&self, congestion_control: CongestionControl);
 }
 
 /// Socket options on a raw socket.
@@ -23,6 +28,8 @@ pub struct RawTcpOption {
     pub keep_alive: Option<Duration>,
     /// Whether Nagle's algorithm is enabled.
     pub is_nagle_enabled: bool,
+    /// The congestion control algorithm.
+    pub congestion_control: CongestionControl,
 }
 
 impl RawTcpOption {
@@ -36,3 +43,24 @@ impl RawTcpOption {
         to.set_nagle_enabled(from.nagle_enabled());
     }
 }
+
+/// A trait defines setting socket options on a raw UDP socket.
+pub trait RawUdpSetOption {
+    /// Sets the TTL of outgoing IPv4 multicast packets.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    fn set_multicast_ttl(&self, ttl: u8);
+
+    /// Sets whether outgoing IPv4 multicast packets are looped back to the local sockets.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    fn set_multicast_loop(&self, enabled: bool);
+}
+
+/// Socket options on a raw UDP socket.
+pub struct RawUdpOption {
+    /// The TTL of outgoing IPv4 multicast packets.
+    pub multicast_ttl: u8,
+    /// Whether outgoing IPv4 multicast packets are looped back to the local sockets.
+    pub multicast_loop: bool,
+}
@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{collections::vec_deque::VecDeque, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use keyable_arc::KeyableArc;
+use ostd::sync::{LocalIrqDisabled, SpinLock};
+use smoltcp::wire::EthernetProtocol;
+
+use super::event::{SocketEventObserver, SocketEvents};
+use crate::{errors::packet::RecvError, ext::Ext, iface::Iface};
+
+/// The maximum total length of the frames queued in a packet socket for receiving.
+pub const PACKET_RECV_BUF_LEN: usize = 65536 * 2;
+
+/// A packet socket that receives the link-layer frames of an iface.
+///
+/// The received frames include the link-layer headers. Sending frames does not require a packet
+/// socket, which is done by [`Iface::send_frame`].
+///
+/// Like Linux, the frames sent by the iface are also received, but only if the socket receives
+/// the frames of all protocols. This allows the socket to capture the traffic in both directions.
+///
+/// When dropped, the socket is automatically removed from the iface.
+///
+/// [`Iface::send_frame`]: crate::iface::Iface::send_frame
+pub struct PacketSocket<E: Ext>(KeyableArc<PacketSocketBg<E>>);
+
+/// The background part of [`PacketSocket`], which handles frames from the network.
+pub(crate) struct PacketSocketBg<E: Ext> {
+    iface: Arc<dyn Iface<E>>,
+    protocol: Option<EthernetProtocol>,
+    recv_queue: SpinLock<RecvQueue, LocalIrqDisabled>,
+    observer: E::PacketEventObserver,
+    events: AtomicU8,
+}
+
+/// The direction of a frame, from the perspective of the iface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDirection {
+    /// The frame is received by the iface.
+    Incoming,
+    /// The frame is sent by the iface.
+    Outgoing,
+}
+
+struct RecvQueue {
+    frames: VecDeque<(Vec<u8>, FrameDirection)>,
+    /// The total length of the frames.
+    len: usize,
+}
+
+impl<E: Ext> PacketSocket<E> {
+    /// Creates a packet socket that receives the frames of the protocol from the iface.
+    ///
+    /// If the protocol is `None`, the frames of all protocols are received.
+    pub fn new(
+        iface: Arc<dyn Iface<E>>,
+        protocol: Option<EthernetProtocol>,
+        observer: E::PacketEventObserver,
+    ) -> Self {
+        let socket = KeyableArc::new(PacketSocketBg {
+            iface,
+            protocol,
+            recv_queue: SpinLock::new(RecvQueue {
+                frames: VecDeque::new(),
+                len: 0,
+            }),
+            observer,
+            events: AtomicU8::new(0),
+        });
+        socket.iface.common().register_packet_socket(socket.clone());
+
+        Self(socket)
+    }
+
+    /// Returns a reference to the iface.
+    pub fn iface(&self) -> &Arc<dyn Iface<E>> {
+        &self.0.iface
+    }
+
+    /// Returns the protocol of the frames that the socket receives.
+    pub fn protocol(&self) -> Option<EthernetProtocol> {
+        self.0.protocol
+    }
+
+    /// Receives a frame.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv<F, R>(&self, f: F) -> Result<R, RecvError>
+    where
+        F: FnOnce(&[u8], FrameDirection) -> R,
+    {
+        let mut recv_queue = self.0.recv_queue.lock();
+
+        let (frame, direction) = recv_queue.frames.pop_front().ok_or(RecvError::Exhausted)?;
+        recv_queue.len -= frame.len();
+        drop(recv_queue);
+
+        Ok(f(&frame, direction))
+    }
+
+    /// Returns whether there are frames to receive.
+    pub fn can_recv(&self) -> bool {
+        !self.0.recv_queue.lock().frames.is_empty()
+    }
+}
+
+impl<E: Ext> Drop for PacketSocket<E> {
+    fn drop(&mut self) {
+        self.0.iface.common().remove_packet_socket(&self.0);
+    }
+}
+
+impl<E: Ext> PacketSocketBg<E> {
+    /// Queues the frame if the socket receives the frames of its protocol.
+    pub(crate) fn process(
+        &self,
+        frame: &[u8],
+        protocol: EthernetProtocol,
+        direction: FrameDirection,
+    ) {
+        match (self.protocol, direction) {
+            (None, _) => (),
+            (Some(expected), FrameDirection::Incoming) if expected == protocol => (),
+            (Some(_), _) => return,
+        }
+
+        let mut recv_queue = self.recv_queue.lock();
+
+        // Like other datagram sockets, the frame is dropped if the receive buffer is full.
+        if recv_queue.len + frame.len() > PACKET_RECV_BUF_LEN {
+            return;
+        }
+        recv_queue.len += frame.len();
+        recv_queue.frames.push_back((frame.to_vec(), direction));
+
+        self.events
+            .fetch_or(SocketEvents::CAN_RECV.bits(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn has_events(&self) -> bool {
+        self.events.load(Ordering::Relaxed) != 0
+    }
+
+    pub(crate) fn on_events(&self) {
+        // This method can only be called to process network events, so we assume we are holding the
+        // poll lock and no race conditions can occur.
+        let events = self.events.load(Ordering::Relaxed);
+        self.events.store(0, Ordering::Relaxed);
+
+        self.observer
+            .on_events(SocketEvents::from_bits_truncate(events));
+    }
+}
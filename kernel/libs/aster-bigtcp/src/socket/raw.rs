@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{collections::vec_deque::VecDeque, sync::Arc, vec, vec::Vec};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use keyable_arc::KeyableArc;
+use ostd::sync::{LocalIrqDisabled, SpinLock};
+use smoltcp::{
+    iface::Context,
+    wire::{IpAddress, IpProtocol, Ipv4Address, Ipv4Repr, IPV4_HEADER_LEN},
+};
+
+use super::event::{SocketEventObserver, SocketEvents};
+use crate::{
+    errors::raw::{RecvError, SendError},
+    ext::Ext,
+    iface::{BoundPort, Iface},
+};
+
+/// The maximum total length of the packets queued in a raw socket for receiving.
+pub const RAW_RECV_BUF_LEN: usize = 65536 * 2;
+/// The maximum total length of the packets queued in a raw socket for sending.
+pub const RAW_SEND_BUF_LEN: usize = 65536 * 2;
+
+/// A raw socket that sends and receives the IPv4 packets of a protocol through an iface.
+///
+/// A raw socket can also be an ICMP datagram socket (a.k.a. a ping socket), which is created by
+/// [`RawSocket::new_ping`]. Such a socket only receives the ICMP echo replies to itself.
+///
+/// When dropped, the socket is automatically removed from the iface.
+pub struct RawSocket<E: Ext>(KeyableArc<RawSocketBg<E>>);
+
+/// The background part of [`RawSocket`], which handles packets from and to the network.
+pub(crate) struct RawSocketBg<E: Ext> {
+    iface: Arc<dyn Iface<E>>,
+    kind: RawKind<E>,
+    recv_queue: SpinLock<PacketQueue<IpAddress>, LocalIrqDisabled>,
+    send_queue: SpinLock<PacketQueue<RawIpHeader>, LocalIrqDisabled>,
+    observer: E::RawEventObserver,
+    events: AtomicU8,
+}
+
+enum RawKind<E: Ext> {
+    /// A raw socket that receives all the packets of the protocol, including the IP headers.
+    Raw(IpProtocol),
+    /// An ICMP datagram socket that receives the echo replies whose identifier is the bound
+    /// port, excluding the IP headers.
+    Ping(BoundPort<E>),
+}
+
+/// The IP header of a packet sent by a raw socket.
+#[derive(Debug, Clone, Copy)]
+pub struct RawIpHeader {
+    /// The source address, or `None` to use the address of the iface.
+    pub src_addr: Option<Ipv4Address>,
+    /// The destination address.
+    pub dst_addr: Ipv4Address,
+    /// The protocol of the payload.
+    pub protocol: IpProtocol,
+    /// The time to live.
+    pub hop_limit: u8,
+}
+
+/// A queue of packets, where each packet has some metadata.
+struct PacketQueue<M> {
+    packets: VecDeque<(Vec<u8>, M)>,
+    /// The total length of the packets.
+    len: usize,
+}
+
+impl<M> PacketQueue<M> {
+    fn new() -> Self {
+        Self {
+            packets: VecDeque::new(),
+            len: 0,
+        }
+    }
+
+    /// Pushes the packet if the total length does not exceed the limit.
+    fn push(&mut self, packet: Vec<u8>, meta: M, limit: usize) -> bool {
+        if self.len + packet.len() > limit {
+            return false;
+        }
+
+        self.len += packet.len();
+        self.packets.push_back((packet, meta));
+        true
+    }
+
+    fn pop(&mut self) -> Option<(Vec<u8>, M)> {
+        let (packet, meta) = self.packets.pop_front()?;
+        self.len -= packet.len();
+        Some((packet, meta))
+    }
+}
+
+impl<E: Ext> RawSocket<E> {
+    /// Creates a raw socket that receives the packets of the protocol from the iface.
+    pub fn new(
+        iface: Arc<dyn Iface<E>>,
+        protocol: IpProtocol,
+        observer: E::RawEventObserver,
+    ) -> Self {
+        Self::new_with_kind(iface, RawKind::Raw(protocol), observer)
+    }
+
+    /// Creates an ICMP datagram socket with the bound port as the identifier of echo messages.
+    pub fn new_ping(bound: BoundPort<E>, observer: E::RawEventObserver) -> Self {
+        let iface = bound.iface().clone();
+        Self::new_with_kind(iface, RawKind::Ping(bound), observer)
+    }
+
+    fn new_with_kind(
+        iface: Arc<dyn Iface<E>>,
+        kind: RawKind<E>,
+        observer: E::RawEventObserver,
+    ) -> Self {
+        let socket = KeyableArc::new(RawSocketBg {
+            iface,
+            kind,
+            recv_queue: SpinLock::new(PacketQueue::new()),
+            send_queue: SpinLock::new(PacketQueue::new()),
+            observer,
+            events: AtomicU8::new(0),
+        });
+        socket.iface.common().register_raw_socket(socket.clone());
+
+        Self(socket)
+    }
+
+    /// Returns a reference to the iface.
+    pub fn iface(&self) -> &Arc<dyn Iface<E>> {
+        &self.0.iface
+    }
+
+    /// Returns a reference to the bound port, if this is an ICMP datagram socket.
+    pub fn bound_port(&self) -> Option<&BoundPort<E>> {
+        match &self.0.kind {
+            RawKind::Raw(_) => None,
+            RawKind::Ping(bound) => Some(bound),
+        }
+    }
+
+    /// Sends an IP packet.
+    ///
+    /// The closure is called to fill the payload, whose size is specified. The IP header is
+    /// generated from the specified one.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send<F, R>(&self, header: RawIpHeader, size: usize, f: F) -> Result<R, SendError>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        // IP fragmentation is not supported, so the packet must fit in the MTU.
+        if IPV4_HEADER_LEN + size > self.0.iface.mtu() {
+            return Err(SendError::TooLarge);
+        }
+
+        let mut send_queue = self.0.send_queue.lock();
+        if send_queue.len + size > RAW_SEND_BUF_LEN {
+            return Err(SendError::BufferFull);
+        }
+
+        let mut payload = vec![0; size];
+        let result = f(&mut payload);
+        let pushed = send_queue.push(payload, header, RAW_SEND_BUF_LEN);
+        debug_assert!(pushed);
+
+        Ok(result)
+    }
+
+    /// Receives an IP packet.
+    ///
+    /// For raw sockets, the packet includes the IP header. For ICMP datagram sockets, the packet
+    /// is the ICMP message. The source address of the packet is also provided.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv<F, R>(&self, f: F) -> Result<R, RecvError>
+    where
+        F: FnOnce(&[u8], IpAddress) -> R,
+    {
+        let (packet, src_addr) = self.0.recv_queue.lock().pop().ok_or(RecvError::Exhausted)?;
+
+        Ok(f(&packet, src_addr))
+    }
+
+    /// Returns whether there are packets to receive.
+    pub fn can_recv(&self) -> bool {
+        !self.0.recv_queue.lock().packets.is_empty()
+    }
+
+    /// Returns whether there is room to queue more packets for sending.
+    pub fn can_send(&self) -> bool {
+        self.0.send_queue.lock().len < RAW_SEND_BUF_LEN
+    }
+}
+
+impl<E: Ext> Drop for RawSocket<E> {
+    fn drop(&mut self) {
+        self.0.iface.common().remove_raw_socket(&self.0);
+    }
+}
+
+impl<E: Ext> RawSocketBg<E> {
+    /// Queues a received IP packet if this is a raw socket for its protocol.
+    ///
+    /// The packet includes the IP header.
+    pub(crate) fn process(&self, ip_repr: &Ipv4Repr, packet: &[u8]) {
+        match &self.kind {
+            RawKind::Raw(protocol) if *protocol == ip_repr.next_header => (),
+            _ => return,
+        }
+
+        self.queue_recv(packet, IpAddress::Ipv4(ip_repr.src_addr));
+    }
+
+    /// Queues a received ICMP echo reply if this is an ICMP datagram socket for its identifier.
+    ///
+    /// The packet is the ICMP message.
+    pub(crate) fn process_echo_reply(&self, ip_repr: &Ipv4Repr, ident: u16, icmp_packet: &[u8]) {
+        let RawKind::Ping(bound) = &self.kind else {
+            return;
+        };
+
+        let dst_addr = IpAddress::Ipv4(ip_repr.dst_addr);
+        if bound.port() != ident
+            || bound
+                .listen_endpoint()
+                .addr
+                .is_some_and(|addr| addr != dst_addr)
+        {
+            return;
+        }
+
+        self.queue_recv(icmp_packet, IpAddress::Ipv4(ip_repr.src_addr));
+    }
+
+    fn queue_recv(&self, packet: &[u8], src_addr: IpAddress) {
+        // Like other datagram sockets, the packet is dropped if the receive buffer is full.
+        if !self
+            .recv_queue
+            .lock()
+            .push(packet.to_vec(), src_addr, RAW_RECV_BUF_LEN)
+        {
+            return;
+        }
+
+        self.events
+            .fetch_or(SocketEvents::CAN_RECV.bits(), Ordering::Relaxed);
+    }
+
+    /// Dequeues a packet to send.
+    ///
+    /// The source address of the returned IP header is filled in if it is not specified.
+    pub(crate) fn dispatch(&self, cx: &Context) -> Option<(Ipv4Repr, Vec<u8>)> {
+        let (payload, header) = self.send_queue.lock().pop()?;
+
+        // Dequeuing a packet means that we can queue more packets.
+        self.events
+            .fetch_or(SocketEvents::CAN_SEND.bits(), Ordering::Relaxed);
+
+        let ip_repr = Ipv4Repr {
+            src_addr: header
+                .src_addr
+                .or_else(|| cx.ipv4_addr())
+                .unwrap_or(Ipv4Address::UNSPECIFIED),
+            dst_addr: header.dst_addr,
+            next_header: header.protocol,
+            payload_len: payload.len(),
+            hop_limit: header.hop_limit,
+        };
+
+        Some((ip_repr, payload))
+    }
+
+    pub(crate) fn has_events(&self) -> bool {
+        self.events.load(Ordering::Relaxed) != 0
+    }
+
+    pub(crate) fn on_events(&self) {
+        // This method can only be called to process network events, so we assume we are holding the
+        // poll lock and no race conditions can occur.
+        let events = self.events.load(Ordering::Relaxed);
+        self.events.store(0, Ordering::Relaxed);
+
+        self.observer
+            .on_events(SocketEvents::from_bits_truncate(events));
+    }
+}
@@ -111,6 +111,116 @@ impl GpRegs {
     }
 }
 
+/// General-purpose registers in the layout of `struct user_regs_struct`.
+///
+/// This is the layout used by `PTRACE_GETREGS` and `PTRACE_SETREGS`.
+#[derive(Debug, Clone, Copy, Pod, Default)]
+#[repr(C)]
+pub struct PtraceRegs {
+    pub r15: usize,
+    pub r14: usize,
+    pub r13: usize,
+    pub r12: usize,
+    pub rbp: usize,
+    pub rbx: usize,
+    pub r11: usize,
+    pub r10: usize,
+    pub r9: usize,
+    pub r8: usize,
+    pub rax: usize,
+    pub rcx: usize,
+    pub rdx: usize,
+    pub rsi: usize,
+    pub rdi: usize,
+    pub orig_rax: usize,
+    pub rip: usize,
+    pub cs: usize,
+    pub rflags: usize,
+    pub rsp: usize,
+    pub ss: usize,
+    pub fs_base: usize,
+    pub gs_base: usize,
+    pub ds: usize,
+    pub es: usize,
+    pub fs: usize,
+    pub gs: usize,
+}
+
+// The segment selectors of the user space, which are the same as Linux.
+const USER_CS: usize = 0x33;
+const USER_SS: usize = 0x2b;
+
+// The flags in RFLAGS that can be changed by the tracer.
+// Reference: <https://elixir.bootlin.com/linux/v6.0.9/source/arch/x86/kernel/ptrace.c#L325>.
+const PTRACE_RFLAGS_MASK: usize = 0x50dd5;
+
+impl PtraceRegs {
+    /// Creates the registers from the user context.
+    ///
+    /// `orig_rax` is the syscall number if the thread is stopped in a syscall,
+    /// or `usize::MAX` (i.e., -1) otherwise.
+    pub fn from_user_context(user_ctx: &UserContext, orig_rax: usize) -> Self {
+        let regs = user_ctx.general_regs();
+        Self {
+            r15: regs.r15,
+            r14: regs.r14,
+            r13: regs.r13,
+            r12: regs.r12,
+            rbp: regs.rbp,
+            rbx: regs.rbx,
+            r11: regs.r11,
+            r10: regs.r10,
+            r9: regs.r9,
+            r8: regs.r8,
+            rax: regs.rax,
+            rcx: regs.rcx,
+            rdx: regs.rdx,
+            rsi: regs.rsi,
+            rdi: regs.rdi,
+            orig_rax,
+            rip: regs.rip,
+            cs: USER_CS,
+            rflags: regs.rflags,
+            rsp: regs.rsp,
+            ss: USER_SS,
+            fs_base: regs.fsbase,
+            gs_base: regs.gsbase,
+            ds: 0,
+            es: 0,
+            fs: 0,
+            gs: 0,
+        }
+    }
+
+    /// Copies the registers to the user context.
+    ///
+    /// The segment selectors are ignored and only the flags that can be
+    /// changed by the user are copied to RFLAGS.
+    pub fn copy_to_user_context(&self, user_ctx: &mut UserContext) {
+        let regs = user_ctx.general_regs_mut();
+        regs.r15 = self.r15;
+        regs.r14 = self.r14;
+        regs.r13 = self.r13;
+        regs.r12 = self.r12;
+        regs.rbp = self.rbp;
+        regs.rbx = self.rbx;
+        regs.r11 = self.r11;
+        regs.r10 = self.r10;
+        regs.r9 = self.r9;
+        regs.r8 = self.r8;
+        regs.rax = self.rax;
+        regs.rcx = self.rcx;
+        regs.rdx = self.rdx;
+        regs.rsi = self.rsi;
+        regs.rdi = self.rdi;
+        regs.rip = self.rip;
+        regs.rflags = (regs.rflags & !PTRACE_RFLAGS_MASK) | (self.rflags & PTRACE_RFLAGS_MASK);
+        regs.rsp = self.rsp;
+        regs.fsbase = self.fs_base;
+        regs.gsbase = self.gs_base;
+    }
+}
+
 impl TryFrom<&CpuExceptionInfo> for PageFaultInfo {
     // [`Err`] indicates that the [`CpuExceptionInfo`] is not a page fault,
     // with no additional error information.
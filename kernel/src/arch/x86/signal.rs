@@ -25,6 +25,8 @@ impl From<&CpuExceptionInfo> for FaultSignal {
             CpuException::ALIGNMENT_CHECK => (SIGBUS, BUS_ADRALN, None),
             CpuException::INVALID_OPCODE => (SIGILL, ILL_ILLOPC, None),
             CpuException::GENERAL_PROTECTION_FAULT => (SIGBUS, BUS_ADRERR, None),
+            CpuException::DEBUG => (SIGTRAP, TRAP_TRACE, None),
+            CpuException::BREAKPOINT => (SIGTRAP, TRAP_BRKPT, None),
             CpuException::PAGE_FAULT => {
                 const PF_ERR_FLAG_PRESENT: usize = 1usize << 0;
                 let code = if trap_info.error_code & PF_ERR_FLAG_PRESENT != 0 {
@@ -7,6 +7,7 @@ mod pty;
 mod random;
 mod shm;
 pub mod tty;
+mod tun;
 mod urandom;
 mod zero;
 
@@ -54,6 +55,8 @@ pub fn init() -> Result<()> {
     add_node(random, "random")?;
     let urandom = Arc::new(urandom::Urandom);
     add_node(urandom, "urandom")?;
+    let tun = Arc::new(tun::TunDevice);
+    add_node(tun, "net/tun")?;
     pty::init()?;
     shm::init()?;
     Ok(())
@@ -78,6 +81,7 @@ pub fn get_device(dev: usize) -> Result<Arc<dyn Device>> {
         (5, 0) => Ok(Arc::new(tty::TtyDevice)),
         (1, 8) => Ok(Arc::new(random::Random)),
         (1, 9) => Ok(Arc::new(urandom::Urandom)),
+        (10, 200) => Ok(Arc::new(tun::TunDevice)),
         _ => return_errno_with_message!(Errno::EINVAL, "unsupported device"),
     }
 }
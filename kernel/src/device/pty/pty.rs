@@ -12,7 +12,7 @@ use crate::{
         file_table::FdFlags,
         fs_resolver::FsPath,
         inode_handle::FileIo,
-        utils::{AccessMode, Inode, InodeMode, IoctlCmd},
+        utils::{AccessMode, Inode, InodeMode, IoctlCmd, StatusFlags},
     },
     prelude::*,
     process::{
@@ -145,7 +145,7 @@ impl Pollable for PtyMaster {
 }
 
 impl FileIo for PtyMaster {
-    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+    fn read(&self, writer: &mut VmWriter, _status_flags: StatusFlags) -> Result<usize> {
         if !writer.has_avail() {
             return Ok(0);
         }
@@ -154,7 +154,7 @@ impl FileIo for PtyMaster {
         self.wait_events(IoEvents::IN, None, || self.try_read(writer))
     }
 
-    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+    fn write(&self, reader: &mut VmReader, _status_flags: StatusFlags) -> Result<usize> {
         let buf = reader.collect()?;
         let write_len = buf.len();
         let mut input = self.input.lock();
@@ -342,7 +342,7 @@ impl Pollable for PtySlave {
 }
 
 impl FileIo for PtySlave {
-    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+    fn read(&self, writer: &mut VmWriter, _status_flags: StatusFlags) -> Result<usize> {
         let mut buf = vec![0u8; writer.avail()];
         self.job_control.wait_until_in_foreground()?;
         let read_len = self.master().output.read(&mut buf)?;
@@ -350,7 +350,7 @@ impl FileIo for PtySlave {
         Ok(read_len)
     }
 
-    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+    fn write(&self, reader: &mut VmReader, _status_flags: StatusFlags) -> Result<usize> {
         let buf = reader.collect()?;
         let write_len = buf.len();
         let master = self.master();
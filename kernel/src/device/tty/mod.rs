@@ -12,7 +12,7 @@ use crate::{
     fs::{
         device::{Device, DeviceId, DeviceType},
         inode_handle::FileIo,
-        utils::IoctlCmd,
+        utils::{IoctlCmd, StatusFlags},
     },
     prelude::*,
     process::{
@@ -79,7 +79,7 @@ impl Pollable for Tty {
 }
 
 impl FileIo for Tty {
-    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+    fn read(&self, writer: &mut VmWriter, _status_flags: StatusFlags) -> Result<usize> {
         let mut buf = vec![0; writer.avail()];
         self.job_control.wait_until_in_foreground()?;
         let read_len = self.ldisc.read(buf.as_mut_slice())?;
@@ -87,7 +87,7 @@ impl FileIo for Tty {
         Ok(read_len)
     }
 
-    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+    fn write(&self, reader: &mut VmReader, _status_flags: StatusFlags) -> Result<usize> {
         let buf = reader.collect()?;
         if let Ok(content) = alloc::str::from_utf8(&buf) {
             print!("{content}");
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#![allow(unused_variables)]
+
+use crate::{
+    events::IoEvents,
+    fs::{
+        device::{Device, DeviceId, DeviceType},
+        inode_handle::FileIo,
+        utils::StatusFlags,
+    },
+    net::iface::TunFile,
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+};
+
+/// Corresponds to `/dev/net/tun` in the file system. Each opened file of this device can be
+/// attached to a new TUN/TAP iface.
+pub struct TunDevice;
+
+impl Device for TunDevice {
+    fn type_(&self) -> DeviceType {
+        DeviceType::CharDevice
+    }
+
+    fn id(&self) -> DeviceId {
+        // Same value with Linux
+        DeviceId::new(10, 200)
+    }
+
+    fn open(&self) -> Result<Option<Arc<dyn FileIo>>> {
+        Ok(Some(TunFile::new() as Arc<dyn FileIo>))
+    }
+}
+
+impl Pollable for TunDevice {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        IoEvents::empty()
+    }
+}
+
+impl FileIo for TunDevice {
+    fn read(&self, writer: &mut VmWriter, _status_flags: StatusFlags) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "cannot read tun device");
+    }
+
+    fn write(&self, reader: &mut VmReader, _status_flags: StatusFlags) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "cannot write tun device");
+    }
+}
@@ -116,20 +116,22 @@ impl Inode for PtySlaveInode {
         self.metadata.write().ctime = time;
     }
 
+    // The opened files access the device with their own status flags. See
+    // `InodeHandle_::read_at` and `InodeHandle_::write_at`.
     fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
-        self.device.read(writer)
+        self.device.read(writer, StatusFlags::empty())
     }
 
     fn read_direct_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
-        self.device.read(writer)
+        self.device.read(writer, StatusFlags::empty())
     }
 
     fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
-        self.device.write(reader)
+        self.device.write(reader, StatusFlags::empty())
     }
 
     fn write_direct_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
-        self.device.write(reader)
+        self.device.write(reader, StatusFlags::empty())
     }
 
     fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
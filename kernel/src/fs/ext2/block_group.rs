@@ -1,11 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::mem::size_of;
+
 use id_alloc::IdAlloc;
 
 use super::{
     block_ptr::Ext2Bid,
     fs::Ext2,
-    inode::{Inode, InodeDesc, RawInode},
+    inode::{Inode, InodeDesc},
     prelude::*,
     super_block::SuperBlock,
 };
@@ -41,10 +43,23 @@ impl BlockGroup {
                 let descriptor = {
                     // Read the block group descriptor
                     // TODO: if the main is corrupted, should we load the backup?
-                    let offset = idx * core::mem::size_of::<RawGroupDescriptor>();
-                    let raw_descriptor = group_descriptors_segment
-                        .read_val::<RawGroupDescriptor>(offset)
+                    let desc_size = super_block.desc_size();
+                    let mut raw_descriptor = RawGroupDescriptor::new_zeroed();
+                    group_descriptors_segment
+                        .read_bytes(
+                            idx * desc_size,
+                            &mut raw_descriptor.as_bytes_mut()[..desc_size],
+                        )
                         .unwrap();
+                    if super_block.has_group_desc_csum()
+                        && raw_descriptor.checksum
+                            != raw_descriptor.compute_checksum(idx, super_block)
+                    {
+                        return_errno_with_message!(
+                            Errno::EBADMSG,
+                            "bad block group descriptor checksum"
+                        );
+                    }
                     GroupDescriptor::from(raw_descriptor)
                 };
 
@@ -56,20 +71,47 @@ impl BlockGroup {
                     block_device.read_bytes(bid as usize * BLOCK_SIZE, &mut buf)?;
                     Ok(IdAlloc::from_bytes_with_capacity(&buf, capacity))
                 };
+                let check_bitmap = |bitmap: &IdAlloc, checksum: u32| -> Result<()> {
+                    if !super_block.has_metadata_csum() {
+                        return Ok(());
+                    }
+                    let mut expected = crc32c(super_block.checksum_seed(), bitmap.as_bytes());
+                    if super_block.desc_size() < size_of::<RawGroupDescriptor>() {
+                        // Only the low 16 bits are stored in the small descriptor.
+                        expected &= 0xFFFF;
+                    }
+                    if checksum != expected {
+                        return_errno_with_message!(Errno::EBADMSG, "bad bitmap checksum");
+                    }
+                    Ok(())
+                };
 
-                let block_bitmap = get_bitmap(
-                    descriptor.block_bitmap_bid,
-                    super_block.blocks_per_group() as usize,
-                )?;
-                let inode_bitmap = get_bitmap(
-                    descriptor.inode_bitmap_bid,
-                    super_block.inodes_per_group() as usize,
-                )?;
+                let block_bitmap = if descriptor.flags.contains(GroupFlags::BLOCK_UNINIT) {
+                    init_block_bitmap(idx, &descriptor, super_block)
+                } else {
+                    let bitmap = get_bitmap(
+                        descriptor.block_bitmap_bid,
+                        super_block.blocks_per_group() as usize,
+                    )?;
+                    check_bitmap(&bitmap, descriptor.block_bitmap_csum)?;
+                    bitmap
+                };
+                let inode_bitmap = if descriptor.flags.contains(GroupFlags::INODE_UNINIT) {
+                    IdAlloc::with_capacity(super_block.inodes_per_group() as usize)
+                } else {
+                    let bitmap = get_bitmap(
+                        descriptor.inode_bitmap_bid,
+                        super_block.inodes_per_group() as usize,
+                    )?;
+                    check_bitmap(&bitmap, descriptor.inode_bitmap_csum)?;
+                    bitmap
+                };
 
                 GroupMetadata {
                     descriptor,
                     block_bitmap,
                     inode_bitmap,
+                    inodes_per_group: super_block.inodes_per_group(),
                 }
             };
 
@@ -126,17 +168,13 @@ impl BlockGroup {
     /// This method may load the raw inode metadata from block device.
     fn load_inode(&self, inode_idx: u32) -> Result<Arc<Inode>> {
         let fs = self.fs();
-        let raw_inode = {
-            let offset = (inode_idx as usize) * fs.inode_size();
-            self.raw_inodes_cache
-                .pages()
-                .read_val::<RawInode>(offset)
-                .unwrap()
-        };
-        let inode_desc = Dirty::new(InodeDesc::try_from(raw_inode)?);
+        let mut raw_inode = vec![0u8; fs.inode_size()];
+        self.read_raw_inode(inode_idx, &mut raw_inode);
         let ino = inode_idx + self.idx as u32 * fs.inodes_per_group() + 1;
+        fs.verify_inode_checksum(ino, &raw_inode)?;
+        let inode_desc = Dirty::new(InodeDesc::from_raw_bytes(&raw_inode)?);
 
-        Ok(Inode::new(ino, self.idx, inode_desc, Arc::downgrade(&fs)))
+        Inode::new(ino, self.idx, inode_desc, Arc::downgrade(&fs))
     }
 
     /// Inserts the inode into the inode cache.
@@ -208,40 +246,62 @@ impl BlockGroup {
         inner.metadata.free_blocks(range);
     }
 
-    /// Writes back the raw inode metadata to the raw inode metadata cache.
-    pub fn sync_raw_inode(&self, inode_idx: u32, raw_inode: &RawInode) {
+    /// Reads the raw inode record, including the extra fields, from the raw inode metadata cache.
+    pub fn read_raw_inode(&self, inode_idx: u32, buf: &mut [u8]) {
         let offset = (inode_idx as usize) * self.fs().inode_size();
         self.raw_inodes_cache
             .pages()
-            .write_val(offset, raw_inode)
+            .read_bytes(offset, buf)
+            .unwrap();
+    }
+
+    /// Writes back the raw inode record, including the extra fields, to the raw inode metadata cache.
+    pub fn sync_raw_inode(&self, inode_idx: u32, buf: &[u8]) {
+        let offset = (inode_idx as usize) * self.fs().inode_size();
+        self.raw_inodes_cache
+            .pages()
+            .write_bytes(offset, buf)
             .unwrap();
     }
 
     /// Writes back the metadata of this group.
-    pub fn sync_metadata(&self) -> Result<()> {
+    pub fn sync_metadata(&self, super_block: &SuperBlock) -> Result<()> {
         if !self.bg_impl.inner.read().metadata.is_dirty() {
             return Ok(());
         }
 
         let mut inner = self.bg_impl.inner.write();
         let fs = self.fs();
+        // Updates the checksums of the bitmaps.
+        if super_block.has_metadata_csum() {
+            let metadata = inner.metadata.deref_mut();
+            let seed = super_block.checksum_seed();
+            metadata.descriptor.block_bitmap_csum = crc32c(seed, metadata.block_bitmap.as_bytes());
+            metadata.descriptor.inode_bitmap_csum = crc32c(seed, metadata.inode_bitmap.as_bytes());
+        }
+
         // Writes back the descriptor.
         let raw_descriptor = RawGroupDescriptor::from(&inner.metadata.descriptor);
-        self.fs().sync_group_descriptor(self.idx, &raw_descriptor)?;
+        fs.sync_group_descriptor(self.idx, &raw_descriptor, super_block)?;
+
+        // The bitmap occupies a whole block, whose padding bits are set.
+        let bitmap_block = |bitmap: &IdAlloc| -> Vec<u8> {
+            let mut buf = vec![0xFFu8; BLOCK_SIZE];
+            buf[..bitmap.as_bytes().len()].copy_from_slice(bitmap.as_bytes());
+            buf
+        };
 
         let mut bio_waiter = BioWaiter::new();
         // Writes back the inode bitmap.
-        let inode_bitmap_bid = Bid::new(inner.metadata.descriptor.inode_bitmap_bid as u64);
-        bio_waiter.concat(fs.block_device().write_bytes_async(
-            inode_bitmap_bid.to_offset(),
-            inner.metadata.inode_bitmap.as_bytes(),
+        bio_waiter.concat(fs.write_meta_block_async(
+            inner.metadata.descriptor.inode_bitmap_bid,
+            &bitmap_block(&inner.metadata.inode_bitmap),
         )?);
 
         // Writes back the block bitmap.
-        let block_bitmap_bid = Bid::new(inner.metadata.descriptor.block_bitmap_bid as u64);
-        bio_waiter.concat(fs.block_device().write_bytes_async(
-            block_bitmap_bid.to_offset(),
-            inner.metadata.block_bitmap.as_bytes(),
+        bio_waiter.concat(fs.write_meta_block_async(
+            inner.metadata.descriptor.block_bitmap_bid,
+            &bitmap_block(&inner.metadata.block_bitmap),
         )?);
 
         // Waits for the completion of all submitted bios.
@@ -320,22 +380,18 @@ impl Debug for BlockGroup {
 impl PageCacheBackend for BlockGroupImpl {
     fn read_page_async(&self, idx: usize, frame: &Frame) -> Result<BioWaiter> {
         let bid = self.inode_table_bid + idx as Ext2Bid;
-        let bio_segment =
-            BioSegment::new_from_segment(frame.clone().into(), BioDirection::FromDevice);
         self.fs
             .upgrade()
             .unwrap()
-            .read_blocks_async(bid, bio_segment)
+            .read_meta_blocks_async(bid, &frame.clone().into())
     }
 
     fn write_page_async(&self, idx: usize, frame: &Frame) -> Result<BioWaiter> {
         let bid = self.inode_table_bid + idx as Ext2Bid;
-        let bio_segment =
-            BioSegment::new_from_segment(frame.clone().into(), BioDirection::ToDevice);
         self.fs
             .upgrade()
             .unwrap()
-            .write_blocks_async(bid, bio_segment)
+            .write_meta_blocks_async(bid, &frame.clone().into())
     }
 
     fn npages(&self) -> usize {
@@ -354,6 +410,7 @@ struct GroupMetadata {
     descriptor: GroupDescriptor,
     block_bitmap: IdAlloc,
     inode_bitmap: IdAlloc,
+    inodes_per_group: u32,
 }
 
 impl GroupMetadata {
@@ -362,12 +419,18 @@ impl GroupMetadata {
     }
 
     pub fn alloc_inode(&mut self, is_dir: bool) -> Option<u32> {
-        let inode_idx = self.inode_bitmap.alloc()?;
+        let inode_idx = self.inode_bitmap.alloc()? as u32;
+        self.descriptor.flags.remove(GroupFlags::INODE_UNINIT);
+        // The inodes after the used part of the inode table are uninitialized.
+        let unused_start = self.inodes_per_group - self.descriptor.itable_unused as u32;
+        if inode_idx >= unused_start {
+            self.descriptor.itable_unused = (self.inodes_per_group - inode_idx - 1) as u16;
+        }
         self.dec_free_inodes();
         if is_dir {
             self.inc_dirs();
         }
-        Some(inode_idx as u32)
+        Some(inode_idx)
     }
 
     pub fn free_inode(&mut self, inode_idx: u32, is_dir: bool) {
@@ -391,6 +454,7 @@ impl GroupMetadata {
                 continue;
             };
             self.dec_free_blocks(current_count as u16);
+            self.descriptor.flags.remove(GroupFlags::BLOCK_UNINIT);
             return Some((range.start as Ext2Bid)..(range.end as Ext2Bid));
         }
         None
@@ -445,6 +509,43 @@ impl GroupMetadata {
     }
 }
 
+/// Constructs the block bitmap of a group whose block bitmap is uninitialized.
+///
+/// Such a group only contains the metadata, i.e., the backups of the superblock and
+/// the group descriptors, and the bitmaps and the inode table that lie in this group.
+fn init_block_bitmap(
+    idx: usize,
+    descriptor: &GroupDescriptor,
+    super_block: &SuperBlock,
+) -> IdAlloc {
+    let blocks_per_group = super_block.blocks_per_group();
+    let group_start =
+        idx as Ext2Bid * blocks_per_group + super_block.first_data_block().to_raw() as Ext2Bid;
+    let group_end = group_start + blocks_per_group;
+
+    let mut used_ranges = Vec::new();
+    if super_block.has_super_block(idx) {
+        let len =
+            1 + super_block.group_descriptors_blocks_count() + super_block.reserved_gdt_blocks();
+        used_ranges.push(group_start..group_start + len);
+    }
+    used_ranges.push(descriptor.block_bitmap_bid..descriptor.block_bitmap_bid + 1);
+    used_ranges.push(descriptor.inode_bitmap_bid..descriptor.inode_bitmap_bid + 1);
+    let inode_table_len = (super_block.inodes_per_group() as usize * super_block.inode_size())
+        .div_ceil(BLOCK_SIZE) as Ext2Bid;
+    used_ranges.push(descriptor.inode_table_bid..descriptor.inode_table_bid + inode_table_len);
+    // The blocks beyond the end of the filesystem are marked as used.
+    used_ranges.push(super_block.total_blocks().max(group_start)..group_end);
+
+    let mut bitmap = IdAlloc::with_capacity(blocks_per_group as usize);
+    for range in used_ranges {
+        for bid in range.start.max(group_start)..range.end.min(group_end) {
+            bitmap.alloc_specific((bid - group_start) as usize);
+        }
+    }
+    bitmap
+}
+
 /// The in-memory rust block group descriptor.
 ///
 /// The block group descriptor contains information regarding where important data
@@ -463,6 +564,16 @@ struct GroupDescriptor {
     free_inodes_count: u16,
     /// Number of directories in group
     dirs_count: u16,
+    /// Group flags
+    flags: GroupFlags,
+    /// Number of unused inodes at the end of the inode table
+    itable_unused: u16,
+    /// Checksum of the block bitmap
+    block_bitmap_csum: u32,
+    /// Checksum of the inode bitmap
+    inode_bitmap_csum: u32,
+    /// The raw descriptor, which keeps the fields that are not interpreted.
+    raw: RawGroupDescriptor,
 }
 
 impl From<RawGroupDescriptor> for GroupDescriptor {
@@ -474,15 +585,35 @@ impl From<RawGroupDescriptor> for GroupDescriptor {
             free_blocks_count: desc.free_blocks_count,
             free_inodes_count: desc.free_inodes_count,
             dirs_count: desc.dirs_count,
+            flags: GroupFlags::from_bits_truncate(desc.flags),
+            itable_unused: desc.itable_unused,
+            block_bitmap_csum: (desc.block_bitmap_csum_high as u32) << 16
+                | desc.block_bitmap_csum as u32,
+            inode_bitmap_csum: (desc.inode_bitmap_csum_high as u32) << 16
+                | desc.inode_bitmap_csum as u32,
+            raw: desc,
         }
     }
 }
 
-const_assert!(core::mem::size_of::<RawGroupDescriptor>() == 32);
+bitflags! {
+    /// The flags of a block group.
+    struct GroupFlags: u16 {
+        /// The inode bitmap and the inode table are uninitialized.
+        const INODE_UNINIT = 1 << 0;
+        /// The block bitmap is uninitialized.
+        const BLOCK_UNINIT = 1 << 1;
+        /// The inode table is zeroed.
+        const INODE_ZEROED = 1 << 2;
+    }
+}
+
+const_assert!(core::mem::size_of::<RawGroupDescriptor>() == 64);
 
 /// The raw block group descriptor.
 ///
-/// The table starts on the first block following the superblock.
+/// The table starts on the first block following the superblock. If the `IS_64BIT`
+/// feature is not set, only the first 32 bytes of each descriptor are stored.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod)]
 pub(super) struct RawGroupDescriptor {
@@ -492,8 +623,53 @@ pub(super) struct RawGroupDescriptor {
     pub free_blocks_count: u16,
     pub free_inodes_count: u16,
     pub dirs_count: u16,
-    pad: u16,
-    reserved: [u32; 3],
+    pub flags: u16,
+    exclude_bitmap: u32,
+    pub block_bitmap_csum: u16,
+    pub inode_bitmap_csum: u16,
+    pub itable_unused: u16,
+    pub checksum: u16,
+    // The following fields only exist if the `IS_64BIT` feature is set.
+    block_bitmap_high: u32,
+    inode_bitmap_high: u32,
+    inode_table_high: u32,
+    free_blocks_count_high: u16,
+    free_inodes_count_high: u16,
+    dirs_count_high: u16,
+    itable_unused_high: u16,
+    exclude_bitmap_high: u32,
+    pub block_bitmap_csum_high: u16,
+    pub inode_bitmap_csum_high: u16,
+    reserved: u32,
+}
+
+impl RawGroupDescriptor {
+    /// Computes the checksum of the descriptor of the `idx`-th block group.
+    ///
+    /// The checksum is CRC32C if the `METADATA_CSUM` feature is set, or CRC16 if the
+    /// `GDT_CSUM` feature is set.
+    pub fn compute_checksum(&self, idx: usize, super_block: &SuperBlock) -> u16 {
+        let bytes = &self.as_bytes()[..super_block.desc_size()];
+        let checksum_offset = core::mem::offset_of!(Self, checksum);
+        let (before, after) = (
+            &bytes[..checksum_offset],
+            &bytes[checksum_offset + size_of::<u16>()..],
+        );
+        let group = (idx as u32).to_le_bytes();
+
+        if super_block.has_metadata_csum() {
+            let crc = crc32c(super_block.checksum_seed(), &group);
+            let crc = crc32c(crc, before);
+            let crc = crc32c(crc, &[0u8; 2]);
+            let crc = crc32c(crc, after);
+            (crc & 0xFFFF) as u16
+        } else {
+            let crc = crc16(!0, super_block.uuid());
+            let crc = crc16(crc, &group);
+            let crc = crc16(crc, before);
+            crc16(crc, after)
+        }
+    }
 }
 
 impl From<&GroupDescriptor> for RawGroupDescriptor {
@@ -505,8 +681,13 @@ impl From<&GroupDescriptor> for RawGroupDescriptor {
             free_blocks_count: desc.free_blocks_count,
             free_inodes_count: desc.free_inodes_count,
             dirs_count: desc.dirs_count,
-            pad: 0u16,
-            reserved: [0u32; 3],
+            flags: desc.flags.bits(),
+            itable_unused: desc.itable_unused,
+            block_bitmap_csum: desc.block_bitmap_csum as u16,
+            inode_bitmap_csum: desc.inode_bitmap_csum as u16,
+            block_bitmap_csum_high: (desc.block_bitmap_csum >> 16) as u16,
+            inode_bitmap_csum_high: (desc.inode_bitmap_csum >> 16) as u16,
+            ..desc.raw
         }
     }
 }
@@ -152,6 +152,32 @@ impl BidPath {
     }
 }
 
+/// Returns the number of indirect blocks required to map the first `nblocks` blocks.
+pub fn indirect_blocks_count(nblocks: Ext2Bid) -> Ext2Bid {
+    let mut remaining = nblocks.saturating_sub(MAX_DIRECT_BLOCKS);
+    if remaining == 0 {
+        return 0;
+    }
+
+    // The single indirect block.
+    let mut count = 1;
+    remaining = remaining.saturating_sub(MAX_INDIRECT_BLOCKS);
+    if remaining == 0 {
+        return count;
+    }
+
+    // The double indirect block and its indirect blocks.
+    let db_blocks = remaining.min(MAX_DB_INDIRECT_BLOCKS);
+    count += 1 + db_blocks.div_ceil(MAX_INDIRECT_BLOCKS);
+    remaining -= db_blocks;
+    if remaining == 0 {
+        return count;
+    }
+
+    // The treble indirect block and its indirect blocks.
+    count + 1 + remaining.div_ceil(MAX_DB_INDIRECT_BLOCKS) + remaining.div_ceil(MAX_INDIRECT_BLOCKS)
+}
+
 /// Direct pointers to blocks.
 pub const DIRECT_RANGE: core::ops::Range<usize> = 0..12;
 /// The number of direct blocks.
@@ -44,6 +44,19 @@ impl DirEntry {
         Self::new(parent_ino, "..", InodeType::Dir)
     }
 
+    /// Constructs an unused `DirEntry` that occupies `record_len` bytes.
+    pub(super) fn unused(record_len: usize) -> Self {
+        Self {
+            header: DirEntryHeader {
+                ino: 0,
+                record_len: record_len as _,
+                name_len: 0,
+                inode_type: 0,
+            },
+            name: CStr256::from(""),
+        }
+    }
+
     /// Returns a reference to the header.
     fn header(&self) -> &DirEntryHeader {
         &self.header
@@ -94,8 +107,28 @@ impl DirEntry {
     pub(super) fn gap_len(&self) -> usize {
         self.record_len() - self.actual_len()
     }
+
+    /// Returns whether the entry is unused, i.e., its inode number is zero.
+    fn is_empty(&self) -> bool {
+        self.header.ino == 0
+    }
+
+    /// Returns whether the entry is the tail that stores the checksum of the block.
+    fn is_tail(&self) -> bool {
+        self.header.ino == 0
+            && self.header.record_len as usize == TAIL_LEN
+            && self.header.name_len == 0
+            && self.header.inode_type == TAIL_FILE_TYPE
+    }
 }
 
+/// The length of the tail, which is a fake entry at the end of each directory block
+/// that stores the checksum of the block if the metadata checksums are enabled.
+const TAIL_LEN: usize = 12;
+
+/// The file type of the tail.
+const TAIL_FILE_TYPE: u8 = 0xDE;
+
 /// The header of `DirEntry`.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod)]
@@ -154,6 +187,8 @@ impl From<DirEntryFileType> for InodeType {
 }
 
 /// A reader for reading `DirEntry` from the page cache.
+///
+/// The unused entries, whose inode numbers are zero, are skipped.
 pub struct DirEntryReader<'a> {
     page_cache: &'a PageCache,
     name_buf: [u8; MAX_FNAME_LEN],
@@ -170,17 +205,20 @@ impl<'a> DirEntryReader<'a> {
         }
     }
 
-    /// Reads one `DirEntry` from the current offset.
-    pub fn read_entry(&mut self) -> Result<DirEntry> {
+    /// Reads one `DirEntry` from the current offset, which may be unused.
+    fn read_entry(&mut self) -> Result<DirEntry> {
+        if self.offset + DirEntry::header_len() > self.page_cache.pages().size() {
+            return_errno!(Errno::ENOENT);
+        }
         let header = self
             .page_cache
             .pages()
             .read_val::<DirEntryHeader>(self.offset)?;
-        if header.ino == 0 {
-            return_errno!(Errno::ENOENT);
+        let name_len = header.name_len as usize;
+        if (header.record_len as usize) < (DirEntry::header_len() + name_len).align_up(4) {
+            return_errno_with_message!(Errno::EUCLEAN, "the directory entry is corrupted");
         }
 
-        let name_len = header.name_len as usize;
         self.page_cache.pages().read_bytes(
             self.offset + DirEntry::header_len(),
             &mut self.name_buf[..name_len],
@@ -193,37 +231,55 @@ impl<'a> DirEntryReader<'a> {
 
         Ok(entry)
     }
+
+    /// Reads the next `DirEntry`, including the unused ones.
+    fn next_record(&mut self) -> Option<(usize, DirEntry)> {
+        let offset = self.offset;
+        let entry = self.read_entry().ok()?;
+        Some((offset, entry))
+    }
 }
 
 impl Iterator for DirEntryReader<'_> {
     type Item = (usize, DirEntry);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let offset = self.offset;
-        let entry = match self.read_entry() {
-            Ok(entry) => entry,
-            Err(_) => {
-                return None;
+        loop {
+            let (offset, entry) = self.next_record()?;
+            if !entry.is_empty() {
+                return Some((offset, entry));
             }
-        };
-
-        Some((offset, entry))
+        }
     }
 }
 
 /// A writer for modifying `DirEntry` of the page cache.
+///
+/// An entry never crosses the boundary of blocks. If the metadata checksums are
+/// enabled, each block ends with a tail that stores the checksum of the block.
 pub struct DirEntryWriter<'a> {
     page_cache: &'a PageCache,
     offset: usize,
+    /// The seed of the checksums of the directory blocks, or `None` if checksums are disabled.
+    csum_seed: Option<u32>,
+    /// Whether the directory is indexed by a hash tree, whose blocks must not be shrunk.
+    is_indexed: bool,
 }
 
 // TODO: Improve the efficiency of the writer operations.
 impl<'a> DirEntryWriter<'a> {
     /// Constructs a writer with the given page cache and offset.
-    pub(super) fn new(page_cache: &'a PageCache, from_offset: usize) -> Self {
+    pub(super) fn new(
+        page_cache: &'a PageCache,
+        from_offset: usize,
+        csum_seed: Option<u32>,
+        is_indexed: bool,
+    ) -> Self {
         Self {
             page_cache,
             offset: from_offset,
+            csum_seed,
+            is_indexed,
         }
     }
 
@@ -236,65 +292,148 @@ impl<'a> DirEntryWriter<'a> {
             self.offset + DirEntry::header_len(),
             entry.name().as_bytes(),
         )?;
+        self.update_checksum(self.offset)?;
         self.offset += entry.record_len();
         Ok(())
     }
 
     /// Appends a new `DirEntry` starting from the current offset.
     ///
-    /// If there is a gap between existing entries, inserts the new entry into the gap；
-    /// If there is no available space, expands the size and appends the new entry at the end.
+    /// If there is an unused entry or a gap between existing entries that is large enough,
+    /// inserts the new entry there; If there is no available space, expands the size and
+    /// appends the new entry at the new block.
     pub fn append_entry(&mut self, mut new_entry: DirEntry) -> Result<()> {
-        let Some((offset, mut entry)) = DirEntryReader::new(self.page_cache, self.offset)
-            .find(|(_, entry)| entry.gap_len() >= new_entry.record_len())
-        else {
-            // Resize and append it at the new block.
-            let old_size = self.page_cache.pages().size();
-            let new_size = old_size + BLOCK_SIZE;
-            self.page_cache.resize(new_size)?;
-            new_entry.set_record_len(BLOCK_SIZE);
-            self.offset = old_size;
-            self.write_entry(&new_entry)?;
+        let size = self.page_cache.pages().size();
+        if self.insert_within(&new_entry, self.offset..size)? {
             return Ok(());
+        }
+
+        // Resize and append it at the new block.
+        let block_offset = self.append_block()?;
+        new_entry.set_record_len(BLOCK_SIZE - self.tail_len());
+        self.offset = block_offset;
+        self.write_entry(&new_entry)
+    }
+
+    /// Inserts a new `DirEntry` into the block starting from `block_offset`.
+    ///
+    /// Returns `false` if there is no available space in the block.
+    pub fn insert_in_block(&mut self, block_offset: usize, new_entry: &DirEntry) -> Result<bool> {
+        self.insert_within(new_entry, block_offset..block_offset + BLOCK_SIZE)
+    }
+
+    /// Inserts a new `DirEntry` into an unused entry or a gap between existing entries
+    /// that starts within the `range`.
+    ///
+    /// Returns `false` if there is no available space.
+    fn insert_within(&mut self, new_entry: &DirEntry, range: Range<usize>) -> Result<bool> {
+        let mut new_entry = new_entry.clone();
+        let mut reader = DirEntryReader::new(self.page_cache, range.start);
+        while let Some((offset, mut entry)) = reader.next_record() {
+            if offset >= range.end {
+                break;
+            }
+            if entry.is_tail() {
+                continue;
+            }
+
+            if entry.is_empty() && entry.record_len() >= new_entry.record_len() {
+                // Reuse the unused entry.
+                new_entry.set_record_len(entry.record_len());
+                self.offset = offset;
+                self.write_entry(&new_entry)?;
+                return Ok(true);
+            }
+
+            if !entry.is_empty() && entry.gap_len() >= new_entry.record_len() {
+                // Write in the gap between existing entries.
+                new_entry.set_record_len(entry.gap_len());
+                entry.set_record_len(entry.actual_len());
+                self.offset = offset;
+                self.write_entry(&entry)?;
+                self.write_entry(&new_entry)?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Expands the size by an empty block and returns the offset of the block.
+    pub fn append_block(&mut self) -> Result<usize> {
+        let old_size = self.page_cache.pages().size();
+        self.page_cache.resize(old_size + BLOCK_SIZE)?;
+        self.write_block_entries(old_size, &[])?;
+        Ok(old_size)
+    }
+
+    /// Overwrites the block starting from `block_offset` with the `entries`.
+    ///
+    /// The entries are packed from the beginning of the block, and the last one takes
+    /// the rest of the block. The entries must fit in the block.
+    pub fn write_block_entries(&mut self, block_offset: usize, entries: &[DirEntry]) -> Result<()> {
+        if self.csum_seed.is_some() {
+            self.write_tail(block_offset)?;
+        }
+        let end = block_offset + BLOCK_SIZE - self.tail_len();
+        self.offset = block_offset;
+        let Some((last, entries)) = entries.split_last() else {
+            return self.write_entry(&DirEntry::unused(end - block_offset));
         };
 
-        // Write in the gap between existing entries.
-        new_entry.set_record_len(entry.gap_len());
-        entry.set_record_len(entry.actual_len());
-        self.offset = offset;
-        self.write_entry(&entry)?;
-        self.write_entry(&new_entry)?;
-        Ok(())
+        for entry in entries {
+            let mut entry = entry.clone();
+            entry.set_record_len(entry.actual_len());
+            self.write_entry(&entry)?;
+        }
+        let mut last = last.clone();
+        last.set_record_len(end - self.offset);
+        self.write_entry(&last)
     }
 
-    /// Removes and returns an existing `DirEntry` indicated by `name`.
+    /// Removes and returns an existing `DirEntry` indicated by `name`, which is searched
+    /// from the block that contains the current offset.
+    ///
+    /// The space of the entry is merged into the previous entry in the same block. If
+    /// the entry is the first one in the block, it is marked as unused instead.
     pub fn remove_entry(&mut self, name: &str) -> Result<DirEntry> {
-        let self_entry_record_len = DirEntry::self_entry(0).record_len();
-        let reader = DirEntryReader::new(self.page_cache, 0);
-        let next_reader = DirEntryReader::new(self.page_cache, self_entry_record_len);
-        let Some(((pre_offset, mut pre_entry), (offset, entry))) = reader
-            .zip(next_reader)
-            .find(|((offset, _), (_, dir_entry))| dir_entry.name() == name)
-        else {
-            return_errno!(Errno::ENOENT);
+        let mut reader = DirEntryReader::new(self.page_cache, self.offset.align_down(BLOCK_SIZE));
+        let mut pre_entry: Option<(usize, DirEntry)> = None;
+        let (offset, mut entry) = loop {
+            let Some((offset, entry)) = reader.next_record() else {
+                return_errno!(Errno::ENOENT);
+            };
+            if !entry.is_empty() && entry.name() == name {
+                break (offset, entry);
+            }
+            pre_entry = Some((offset, entry));
         };
 
-        if DirEntryReader::new(self.page_cache, offset)
-            .next()
-            .is_none()
-            && Bid::from_offset(pre_offset) != Bid::from_offset(offset)
-        {
-            // Shrink the size.
-            let new_size = pre_offset.align_up(BLOCK_SIZE);
-            self.page_cache.resize(new_size)?;
-            pre_entry.set_record_len(new_size - pre_offset);
-            self.offset = pre_offset;
-            self.write_entry(&pre_entry)?;
-        } else {
-            // Update the previous entry.
-            pre_entry.set_record_len(pre_entry.record_len() + entry.record_len());
-            self.offset = pre_offset;
-            self.write_entry(&pre_entry)?;
+        let block_offset = offset.align_down(BLOCK_SIZE);
+        match pre_entry {
+            Some((pre_offset, mut pre_entry)) if pre_offset >= block_offset => {
+                // Update the previous entry.
+                pre_entry.set_record_len(pre_entry.record_len() + entry.record_len());
+                self.offset = pre_offset;
+                self.write_entry(&pre_entry)?;
+            }
+            _ => {
+                let removed_entry = entry.clone();
+                entry.set_ino(0);
+                self.offset = offset;
+                self.write_entry(&entry)?;
+                entry = removed_entry;
+            }
+        }
+
+        // Shrink the size if the last block becomes unused, unless it is indexed.
+        let size = self.page_cache.pages().size();
+        if !self.is_indexed && block_offset > 0 && block_offset + BLOCK_SIZE == size {
+            let first_entry = DirEntryReader::new(self.page_cache, block_offset).next_record();
+            if first_entry.is_some_and(|(_, first_entry)| {
+                first_entry.is_empty() && first_entry.record_len() == BLOCK_SIZE - self.tail_len()
+            }) {
+                self.page_cache.resize(block_offset)?;
+            }
         }
 
         Ok(entry)
@@ -323,4 +462,98 @@ impl<'a> DirEntryWriter<'a> {
         }
         Ok(())
     }
+
+    /// Converts the hash-indexed directory to a linear one.
+    ///
+    /// The index blocks are valid linear blocks whose index data are hidden in the unused
+    /// space, so only the tails need to be added if the metadata checksums are enabled.
+    pub fn drop_index(&mut self) -> Result<()> {
+        if self.csum_seed.is_none() {
+            return Ok(());
+        }
+
+        let size = self.page_cache.pages().size();
+        for block_offset in (0..size).step_by(BLOCK_SIZE) {
+            let mut reader = DirEntryReader::new(self.page_cache, block_offset);
+            let (offset, mut entry) = if block_offset == 0 {
+                // The index root follows the ".." entry.
+                let _ = reader.next_record();
+                let Some(record) = reader.next_record() else {
+                    continue;
+                };
+                record
+            } else {
+                // The index node is hidden in an unused entry that covers the whole block.
+                let Some(record) = reader.next_record().filter(|(_, entry)| entry.is_empty())
+                else {
+                    continue;
+                };
+                record
+            };
+            if offset + entry.record_len() != block_offset + BLOCK_SIZE
+                || entry.gap_len() < TAIL_LEN
+            {
+                continue;
+            }
+
+            entry.set_record_len(entry.record_len() - TAIL_LEN);
+            self.write_tail(block_offset)?;
+            self.offset = offset;
+            self.write_entry(&entry)?;
+        }
+        Ok(())
+    }
+
+    fn tail_len(&self) -> usize {
+        if self.csum_seed.is_some() {
+            TAIL_LEN
+        } else {
+            0
+        }
+    }
+
+    /// Writes an empty tail at the end of the block starting from `block_offset`.
+    fn write_tail(&self, block_offset: usize) -> Result<()> {
+        let tail = DirEntryHeader {
+            ino: 0,
+            record_len: TAIL_LEN as u16,
+            name_len: 0,
+            inode_type: TAIL_FILE_TYPE,
+        };
+        let tail_offset = block_offset + BLOCK_SIZE - TAIL_LEN;
+        self.page_cache.pages().write_val(tail_offset, &tail)?;
+        self.page_cache
+            .pages()
+            .write_val(tail_offset + DirEntry::header_len(), &0u32)?;
+        Ok(())
+    }
+
+    /// Updates the checksum in the tail of the block that contains `offset`.
+    ///
+    /// The checksum is not updated if the block has no tail.
+    fn update_checksum(&self, offset: usize) -> Result<()> {
+        let Some(seed) = self.csum_seed else {
+            return Ok(());
+        };
+
+        let block_offset = offset.align_down(BLOCK_SIZE);
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        self.page_cache.pages().read_bytes(block_offset, &mut buf)?;
+        let tail_offset = BLOCK_SIZE - TAIL_LEN;
+        let tail = DirEntryHeader::from_bytes(&buf[tail_offset..]);
+        if tail.ino != 0
+            || tail.record_len as usize != TAIL_LEN
+            || tail.name_len != 0
+            || tail.inode_type != TAIL_FILE_TYPE
+        {
+            return Ok(());
+        }
+
+        let checksum = crc32c(seed, &buf[..tail_offset]);
+        self.page_cache.pages().write_val(
+            block_offset + tail_offset + DirEntry::header_len(),
+            &checksum,
+        )?;
+        Ok(())
+    }
 }
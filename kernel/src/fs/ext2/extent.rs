@@ -0,0 +1,538 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Extent trees of Ext4.
+//!
+//! An inode with the `EXTENTS` flag maps its blocks with an extent tree instead of
+//! the block pointers. The root node of the tree is stored in the space of the block
+//! pointers, and the other nodes are stored in separate blocks.
+//!
+//! The whole tree is loaded into memory as a map of extents, and the nodes are rebuilt
+//! when the inode is written back. This is simple and fast enough since the files
+//! rarely have many extents.
+//!
+//! The on-disk format is described in
+//! <https://www.kernel.org/doc/html/latest/filesystems/ext4/dynamic.html#extent-tree>.
+
+use core::mem::size_of;
+
+use super::{
+    block_ptr::{Ext2Bid, BID_SIZE, MAX_BLOCK_PTRS},
+    fs::Ext2,
+    prelude::*,
+};
+
+/// The magic number of the extent tree nodes.
+const EXTENT_MAGIC: u16 = 0xF30A;
+
+/// The max depth of the tree, which is the same as Linux.
+const MAX_DEPTH: u16 = 5;
+
+/// The max length of an initialized extent.
+const MAX_INIT_LEN: Ext2Bid = 32768;
+
+/// The max length of an uninitialized extent.
+///
+/// The length field larger than [`MAX_INIT_LEN`] indicates an uninitialized extent.
+const MAX_UNINIT_LEN: Ext2Bid = MAX_INIT_LEN - 1;
+
+const HEADER_SIZE: usize = size_of::<RawExtentHeader>();
+const ENTRY_SIZE: usize = size_of::<RawExtent>();
+
+/// The max number of entries in the root node, which is stored in the block pointers.
+const ROOT_MAX_ENTRIES: usize = (MAX_BLOCK_PTRS * BID_SIZE - HEADER_SIZE) / ENTRY_SIZE;
+
+/// The max number of entries in a node block.
+///
+/// The checksum of the node block follows the entries.
+const NODE_MAX_ENTRIES: usize = (BLOCK_SIZE - HEADER_SIZE) / ENTRY_SIZE;
+
+/// The extent tree of an inode.
+#[derive(Debug)]
+pub(super) struct ExtentTree {
+    /// The extents, indexed by their first file block.
+    extents: BTreeMap<Ext2Bid, Extent>,
+    /// The device blocks that store the nodes except the root.
+    node_bids: Vec<Ext2Bid>,
+    /// The seed of the checksums of the node blocks, or `None` if checksums are disabled.
+    csum_seed: Option<u32>,
+    is_dirty: bool,
+}
+
+/// A range of consecutive file blocks that maps to consecutive device blocks.
+#[derive(Clone, Copy, Debug)]
+struct Extent {
+    /// The first device block.
+    start: Ext2Bid,
+    len: Ext2Bid,
+    /// Whether the blocks are allocated but not written yet, which are read as zeros.
+    is_unwritten: bool,
+}
+
+impl Extent {
+    fn max_len(is_unwritten: bool) -> Ext2Bid {
+        if is_unwritten {
+            MAX_UNINIT_LEN
+        } else {
+            MAX_INIT_LEN
+        }
+    }
+
+    /// Checks if the `next` extent can be merged into this one.
+    fn can_merge(&self, next: &Extent) -> bool {
+        self.is_unwritten == next.is_unwritten
+            && self.start + self.len == next.start
+            && self.len + next.len <= Self::max_len(self.is_unwritten)
+    }
+}
+
+/// The mapping of a range of file blocks.
+#[derive(Clone, Debug)]
+pub(super) enum BlockMapping {
+    /// The file blocks are mapped to the device blocks.
+    Mapped(Range<Ext2Bid>),
+    /// The file blocks are mapped to the device blocks which are not written yet.
+    Unwritten(Range<Ext2Bid>),
+    /// The specified number of file blocks are not mapped.
+    Hole(Ext2Bid),
+}
+
+impl BlockMapping {
+    /// Returns the number of file blocks in this mapping.
+    pub fn len(&self) -> Ext2Bid {
+        match self {
+            Self::Mapped(device_range) | Self::Unwritten(device_range) => {
+                device_range.len() as Ext2Bid
+            }
+            Self::Hole(len) => *len,
+        }
+    }
+}
+
+impl ExtentTree {
+    /// Initializes an empty root node in the block pointers of a new inode.
+    pub fn init_root(root: &mut [u8]) {
+        root.fill(0);
+        write_node(root, 0, ROOT_MAX_ENTRIES, &[]);
+    }
+
+    /// Loads the extent tree whose root node is stored in `root`.
+    ///
+    /// The node blocks are read by `read_block`. The `csum_seed` is the checksum seed of
+    /// the inode, or `None` if checksums are disabled.
+    pub fn load(
+        read_block: &dyn Fn(Ext2Bid, &mut [u8]) -> Result<()>,
+        root: &[u8],
+        csum_seed: Option<u32>,
+    ) -> Result<Self> {
+        let mut tree = Self {
+            extents: BTreeMap::new(),
+            node_bids: Vec::new(),
+            csum_seed,
+            is_dirty: false,
+        };
+        tree.load_node(read_block, root, None)?;
+        Ok(tree)
+    }
+
+    fn load_node(
+        &mut self,
+        read_block: &dyn Fn(Ext2Bid, &mut [u8]) -> Result<()>,
+        node: &[u8],
+        expected_depth: Option<u16>,
+    ) -> Result<()> {
+        let corrupted = || Error::with_message(Errno::EUCLEAN, "the extent tree is corrupted");
+
+        let header = RawExtentHeader::from_bytes(node);
+        if header.magic != EXTENT_MAGIC
+            || header.depth > MAX_DEPTH
+            || expected_depth.is_some_and(|depth| depth != header.depth)
+            || header.entries > header.max_entries
+            || HEADER_SIZE + header.max_entries as usize * ENTRY_SIZE > node.len()
+        {
+            return Err(corrupted());
+        }
+
+        for i in 0..header.entries as usize {
+            let entry = &node[HEADER_SIZE + i * ENTRY_SIZE..];
+            if header.depth == 0 {
+                let extent = RawExtent::from_bytes(entry);
+                if extent.start_high != 0 {
+                    return Err(corrupted());
+                }
+                let (len, is_unwritten) = if extent.len as Ext2Bid > MAX_INIT_LEN {
+                    (extent.len as Ext2Bid - MAX_INIT_LEN, true)
+                } else {
+                    (extent.len as Ext2Bid, false)
+                };
+                if len == 0 {
+                    continue;
+                }
+                self.extents.insert(
+                    extent.block,
+                    Extent {
+                        start: extent.start_low,
+                        len,
+                        is_unwritten,
+                    },
+                );
+                continue;
+            }
+
+            let index = RawExtentIndex::from_bytes(entry);
+            if index.leaf_high != 0 || index.leaf_low == 0 {
+                return Err(corrupted());
+            }
+            let mut buf = vec![0u8; BLOCK_SIZE];
+            read_block(index.leaf_low, &mut buf)?;
+            self.check_node_block(&buf)?;
+            self.node_bids.push(index.leaf_low);
+            self.load_node(read_block, &buf, Some(header.depth - 1))?;
+        }
+
+        Ok(())
+    }
+
+    fn check_node_block(&self, buf: &[u8]) -> Result<()> {
+        let Some(seed) = self.csum_seed else {
+            return Ok(());
+        };
+
+        let header = RawExtentHeader::from_bytes(buf);
+        let tail_offset = HEADER_SIZE + header.max_entries as usize * ENTRY_SIZE;
+        if tail_offset + size_of::<u32>() > buf.len() {
+            return_errno_with_message!(Errno::EUCLEAN, "the extent tree is corrupted");
+        }
+        let checksum = u32::from_le_bytes(buf[tail_offset..tail_offset + 4].try_into().unwrap());
+        if checksum != crc32c(seed, &buf[..tail_offset]) {
+            return_errno_with_message!(Errno::EBADMSG, "bad extent block checksum");
+        }
+        Ok(())
+    }
+
+    /// Returns whether the tree has been modified since it was loaded or stored.
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty
+    }
+
+    /// Returns the number of blocks occupied by the tree, including the data blocks and
+    /// the node blocks.
+    pub fn occupied_blocks(&self) -> u64 {
+        let data_blocks: u64 = self.extents.values().map(|extent| extent.len as u64).sum();
+        data_blocks + self.node_bids.len() as u64
+    }
+
+    /// Maps the file blocks starting from `range.start`.
+    ///
+    /// The returned mapping may cover fewer blocks than `range`.
+    ///
+    /// # Panics
+    ///
+    /// If the `range` is empty, this method will panic.
+    pub fn map(&self, range: Range<Ext2Bid>) -> BlockMapping {
+        assert!(!range.is_empty());
+
+        if let Some((&first, extent)) = self.extents.range(..=range.start).next_back() {
+            if range.start < first + extent.len {
+                let offset = range.start - first;
+                let len = (extent.len - offset).min(range.len() as Ext2Bid);
+                let device_range = extent.start + offset..extent.start + offset + len;
+                return if extent.is_unwritten {
+                    BlockMapping::Unwritten(device_range)
+                } else {
+                    BlockMapping::Mapped(device_range)
+                };
+            }
+        }
+
+        let hole_end = self
+            .extents
+            .range(range.start..)
+            .next()
+            .map_or(range.end, |(&first, _)| first.min(range.end));
+        BlockMapping::Hole(hole_end - range.start)
+    }
+
+    /// Returns the last device block mapped before the file block `bid`, which is a good
+    /// hint for allocating the blocks from `bid`.
+    pub fn prev_device_bid(&self, bid: Ext2Bid) -> Option<Ext2Bid> {
+        self.extents
+            .range(..bid)
+            .next_back()
+            .map(|(&first, extent)| extent.start + (bid - first).min(extent.len) - 1)
+    }
+
+    /// Maps the file blocks starting from `bid` to the `device_range`.
+    ///
+    /// The file blocks must not be mapped before.
+    pub fn insert(&mut self, bid: Ext2Bid, device_range: Range<Ext2Bid>, is_unwritten: bool) {
+        let mut bid = bid;
+        let mut device_start = device_range.start;
+        let mut remaining = device_range.len() as Ext2Bid;
+        while remaining > 0 {
+            let len = remaining.min(Extent::max_len(is_unwritten));
+            self.extents.insert(
+                bid,
+                Extent {
+                    start: device_start,
+                    len,
+                    is_unwritten,
+                },
+            );
+            self.merge_around(bid);
+
+            bid += len;
+            device_start += len;
+            remaining -= len;
+        }
+        self.is_dirty = true;
+    }
+
+    /// Marks the unwritten blocks in the file block `range` as written.
+    pub fn mark_written(&mut self, range: Range<Ext2Bid>) {
+        let mut bid = range.start;
+        while bid < range.end {
+            let Some((first, extent)) = self
+                .extents
+                .range(..=bid)
+                .next_back()
+                .map(|(&first, &extent)| (first, extent))
+                .filter(|(first, extent)| bid < first + extent.len)
+            else {
+                // Skips the hole.
+                bid = self
+                    .extents
+                    .range(bid..)
+                    .next()
+                    .map_or(range.end, |(&first, _)| first.min(range.end));
+                continue;
+            };
+
+            let extent_end = first + extent.len;
+            let end = extent_end.min(range.end);
+            if extent.is_unwritten {
+                // Splits the extent, and only the part in `bid..end` is written.
+                self.extents.remove(&first);
+                if first < bid {
+                    self.extents.insert(
+                        first,
+                        Extent {
+                            len: bid - first,
+                            ..extent
+                        },
+                    );
+                }
+                if end < extent_end {
+                    self.extents.insert(
+                        end,
+                        Extent {
+                            start: extent.start + (end - first),
+                            len: extent_end - end,
+                            ..extent
+                        },
+                    );
+                }
+                self.extents.insert(
+                    bid,
+                    Extent {
+                        start: extent.start + (bid - first),
+                        len: end - bid,
+                        is_unwritten: false,
+                    },
+                );
+                self.merge_around(bid);
+                self.is_dirty = true;
+            }
+            bid = end;
+        }
+    }
+
+    /// Unmaps the file blocks from `bid`, and returns the device ranges to be freed.
+    pub fn truncate(&mut self, bid: Ext2Bid) -> Vec<Range<Ext2Bid>> {
+        let mut freed = Vec::new();
+
+        // Trims the extent that crosses `bid`.
+        if let Some((&first, extent)) = self.extents.range_mut(..bid).next_back() {
+            if first + extent.len > bid {
+                let kept_len = bid - first;
+                freed.push(extent.start + kept_len..extent.start + extent.len);
+                extent.len = kept_len;
+                self.is_dirty = true;
+            }
+        }
+
+        for (_, extent) in self.extents.split_off(&bid) {
+            freed.push(extent.start..extent.start + extent.len);
+            self.is_dirty = true;
+        }
+        freed
+    }
+
+    /// Merges the extent starting from `bid` with its neighbors if possible.
+    fn merge_around(&mut self, bid: Ext2Bid) {
+        let extent = self.extents[&bid];
+        let next_bid = bid + extent.len;
+        if let Some(next) = self.extents.get(&next_bid).copied() {
+            if extent.can_merge(&next) {
+                self.extents.remove(&next_bid);
+                self.extents.get_mut(&bid).unwrap().len += next.len;
+            }
+        }
+
+        let extent = self.extents[&bid];
+        if let Some((&prev_bid, prev)) = self.extents.range_mut(..bid).next_back() {
+            if prev_bid + prev.len == bid && prev.can_merge(&extent) {
+                prev.len += extent.len;
+                self.extents.remove(&bid);
+            }
+        }
+    }
+
+    /// Writes back the tree, whose root node is stored in `root`.
+    ///
+    /// The node blocks are rebuilt from the extents. The existing node blocks are reused,
+    /// and new ones are allocated from the `block_group_idx` group first if needed.
+    pub fn store(&mut self, fs: &Ext2, root: &mut [u8], block_group_idx: usize) -> Result<()> {
+        let mut entries: Vec<(Ext2Bid, [u8; ENTRY_SIZE])> = self
+            .extents
+            .iter()
+            .map(|(&bid, extent)| {
+                let len = if extent.is_unwritten {
+                    extent.len + MAX_INIT_LEN
+                } else {
+                    extent.len
+                };
+                let raw_extent = RawExtent {
+                    block: bid,
+                    len: len as u16,
+                    start_high: 0,
+                    start_low: extent.start,
+                };
+                (bid, raw_extent.as_bytes().try_into().unwrap())
+            })
+            .collect();
+
+        // Adjusts the node blocks to the number required by the entries.
+        let nodes_count = {
+            let mut count = 0;
+            let mut level_entries = entries.len();
+            while level_entries > ROOT_MAX_ENTRIES {
+                level_entries = level_entries.div_ceil(NODE_MAX_ENTRIES);
+                count += level_entries;
+            }
+            count
+        };
+        while self.node_bids.len() < nodes_count {
+            let bid = fs
+                .alloc_blocks(block_group_idx, 1)
+                .ok_or_else(|| Error::with_message(Errno::ENOSPC, "no space on device"))?
+                .start;
+            self.node_bids.push(bid);
+        }
+        while self.node_bids.len() > nodes_count {
+            let bid = self.node_bids.pop().unwrap();
+            fs.free_blocks(bid..bid + 1)?;
+        }
+
+        // Writes the node blocks level by level, from the leaves to the root.
+        let mut node_bids = self.node_bids.iter();
+        let mut depth = 0;
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        while entries.len() > ROOT_MAX_ENTRIES {
+            let mut index_entries = Vec::new();
+            for node_entries in entries.chunks(NODE_MAX_ENTRIES) {
+                let bid = *node_bids.next().unwrap();
+                buf.fill(0);
+                let tail_offset = write_node(&mut buf, depth, NODE_MAX_ENTRIES, node_entries);
+                if let Some(seed) = self.csum_seed {
+                    let checksum = crc32c(seed, &buf[..tail_offset]);
+                    buf[tail_offset..tail_offset + 4].copy_from_slice(&checksum.to_le_bytes());
+                }
+                fs.write_meta_block(bid, &buf)?;
+
+                let first_bid = node_entries[0].0;
+                let raw_index = RawExtentIndex {
+                    block: first_bid,
+                    leaf_low: bid,
+                    leaf_high: 0,
+                    unused: 0,
+                };
+                index_entries.push((first_bid, raw_index.as_bytes().try_into().unwrap()));
+            }
+            entries = index_entries;
+            depth += 1;
+        }
+
+        root.fill(0);
+        write_node(root, depth, ROOT_MAX_ENTRIES, &entries);
+        self.is_dirty = false;
+        Ok(())
+    }
+}
+
+/// Writes the header and the entries of a node to `buf`, and returns the offset of the tail.
+fn write_node(
+    buf: &mut [u8],
+    depth: u16,
+    max_entries: usize,
+    entries: &[(Ext2Bid, [u8; ENTRY_SIZE])],
+) -> usize {
+    let header = RawExtentHeader {
+        magic: EXTENT_MAGIC,
+        entries: entries.len() as u16,
+        max_entries: max_entries as u16,
+        depth,
+        generation: 0,
+    };
+    buf[..HEADER_SIZE].copy_from_slice(header.as_bytes());
+    for (i, (_, entry)) in entries.iter().enumerate() {
+        let offset = HEADER_SIZE + i * ENTRY_SIZE;
+        buf[offset..offset + ENTRY_SIZE].copy_from_slice(entry);
+    }
+    HEADER_SIZE + max_entries * ENTRY_SIZE
+}
+
+const_assert!(size_of::<RawExtentHeader>() == 12);
+
+/// The header of each node.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+struct RawExtentHeader {
+    magic: u16,
+    /// The number of valid entries following the header.
+    entries: u16,
+    /// The max number of entries that could follow the header.
+    max_entries: u16,
+    /// The depth of this node, where zero means a leaf node.
+    depth: u16,
+    generation: u32,
+}
+
+const_assert!(size_of::<RawExtent>() == 12);
+
+/// The entry of a leaf node.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+struct RawExtent {
+    /// The first file block covered by this extent.
+    block: u32,
+    /// The number of blocks covered by this extent.
+    ///
+    /// If the value is larger than 32768, the extent is uninitialized and the actual
+    /// length is `len - 32768`.
+    len: u16,
+    start_high: u16,
+    start_low: u32,
+}
+
+const_assert!(size_of::<RawExtentIndex>() == 12);
+
+/// The entry of an internal node.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+struct RawExtentIndex {
+    /// The first file block covered by the child node.
+    block: u32,
+    leaf_low: u32,
+    leaf_high: u16,
+    unused: u16,
+}
@@ -5,10 +5,12 @@
 use super::{
     block_group::{BlockGroup, RawGroupDescriptor},
     block_ptr::Ext2Bid,
-    inode::{FilePerm, Inode, InodeDesc, RawInode},
+    inode::{raw_inode_checksum_offsets, FilePerm, Inode, InodeDesc, RawInode},
+    journal::{open_journal, set_needs_recovery, CommitTimer},
     prelude::*,
-    super_block::{RawSuperBlock, SuperBlock, SUPER_BLOCK_OFFSET},
+    super_block::{FeatureInCompatSet, RawSuperBlock, SuperBlock, SUPER_BLOCK_OFFSET},
 };
+use crate::fs::jbd2::Journal;
 
 /// The root inode number.
 const ROOT_INO: u32 = 2;
@@ -23,7 +25,24 @@ pub struct Ext2 {
     blocks_per_group: Ext2Bid,
     inode_size: usize,
     block_size: usize,
+    desc_size: usize,
+    /// The seed of the metadata checksums, or `None` if the checksums are disabled.
+    csum_seed: Option<u32>,
     group_descriptors_segment: Segment,
+    /// The journal that the metadata are written through, or `None` if the filesystem
+    /// has no journal.
+    journal: Option<Journal>,
+    /// The timer that commits the journal periodically, or `None` if the filesystem has
+    /// no journal.
+    commit_timer: Option<CommitTimer>,
+    /// The lock that keeps the journal commits at the boundaries of the operations.
+    ///
+    /// The operations that modify the metadata hold the lock for reading, and the
+    /// metadata are written back and committed with the lock held for writing.
+    op_lock: RwMutex<()>,
+    /// The lock that serializes the updates of the reference counts of xattr blocks,
+    /// which may be shared by multiple inodes.
+    xattr_block_lock: Mutex<()>,
     self_ref: Weak<Self>,
 }
 
@@ -32,20 +51,32 @@ impl Ext2 {
     pub fn open(block_device: Arc<dyn BlockDevice>) -> Result<Arc<Self>> {
         // Load the superblock
         // TODO: if the main superblock is corrupted, should we load the backup?
-        let super_block = {
+        let load_super_block = || -> Result<SuperBlock> {
             let raw_super_block = block_device.read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)?;
-            SuperBlock::try_from(raw_super_block)?
+            SuperBlock::try_from(raw_super_block)
         };
+        let mut super_block = load_super_block()?;
         assert_eq!(
             super_block.block_size(),
             BLOCK_SIZE,
             "currently only support 4096-byte block size"
         );
 
+        // Replays the journal before loading the other metadata, which may be stale.
+        let journal = if super_block.has_journal() {
+            let journal = open_journal(&block_device, &super_block)?;
+            if journal.needs_recovery() || super_block.needs_recovery() {
+                journal.recover()?;
+                set_needs_recovery(block_device.as_ref(), false)?;
+                super_block = load_super_block()?;
+            }
+            Some(journal)
+        } else {
+            None
+        };
+
         let group_descriptors_segment = {
-            let npages = ((super_block.block_groups_count() as usize)
-                * core::mem::size_of::<RawGroupDescriptor>())
-            .div_ceil(BLOCK_SIZE);
+            let npages = super_block.group_descriptors_blocks_count() as usize;
             let segment = FrameAllocOptions::new(npages)
                 .uninit(true)
                 .alloc_contiguous()?;
@@ -80,11 +111,16 @@ impl Ext2 {
             Ok(block_groups)
         };
 
+        let has_journal = journal.is_some();
         let ext2 = Arc::new_cyclic(|weak_ref| Self {
             inodes_per_group: super_block.inodes_per_group(),
             blocks_per_group: super_block.blocks_per_group(),
             inode_size: super_block.inode_size(),
             block_size: super_block.block_size(),
+            desc_size: super_block.desc_size(),
+            csum_seed: super_block
+                .has_metadata_csum()
+                .then(|| super_block.checksum_seed()),
             block_groups: load_block_groups(
                 weak_ref.clone(),
                 block_device.as_ref(),
@@ -94,6 +130,10 @@ impl Ext2 {
             block_device,
             super_block: RwMutex::new(Dirty::new(super_block)),
             group_descriptors_segment,
+            journal,
+            commit_timer: has_journal.then(|| CommitTimer::new(weak_ref.clone())),
+            op_lock: RwMutex::new(()),
+            xattr_block_lock: Mutex::new(()),
             self_ref: weak_ref.clone(),
         });
         Ok(ext2)
@@ -104,6 +144,11 @@ impl Ext2 {
         self.block_device.as_ref()
     }
 
+    /// Returns whether the metadata are written through the journal.
+    pub fn has_journal(&self) -> bool {
+        self.journal.is_some()
+    }
+
     /// Returns the size of block.
     pub fn block_size(&self) -> usize {
         self.block_size
@@ -150,11 +195,13 @@ impl Ext2 {
     ) -> Result<Arc<Inode>> {
         let (block_group_idx, ino) =
             self.alloc_ino(dir_block_group_idx, inode_type == InodeType::Dir)?;
+        let block_group = &self.block_groups[block_group_idx];
+        // Clears the stale data left by the previously freed inode, e.g., in-inode xattrs.
+        block_group.sync_raw_inode(self.inode_idx(ino), &vec![0u8; self.inode_size]);
         let inode = {
-            let inode_desc = InodeDesc::new(inode_type, file_perm);
-            Inode::new(ino, block_group_idx, inode_desc, self.self_ref.clone())
+            let inode_desc = InodeDesc::new(inode_type, file_perm, &self.super_block.read());
+            Inode::new(ino, block_group_idx, inode_desc, self.self_ref.clone())?
         };
-        let block_group = &self.block_groups[block_group_idx];
         block_group.insert_cache(self.inode_idx(ino), inode.clone());
         Ok(inode)
     }
@@ -198,21 +245,115 @@ impl Ext2 {
 
     /// Writes back the metadata of inode.
     pub(super) fn sync_inode(&self, ino: u32, inode: &InodeDesc) -> Result<()> {
+        self.update_raw_inode(ino, |raw_inode| inode.to_raw_bytes(raw_inode))
+    }
+
+    /// Reads the raw bytes after the metadata of inode in the inode table.
+    pub(super) fn read_inode_extra(&self, ino: u32) -> Result<Vec<u8>> {
         let (_, block_group) = self.block_group_of_ino(ino)?;
         let inode_idx = self.inode_idx(ino);
-        block_group.sync_raw_inode(inode_idx, &RawInode::from(inode));
+        let mut buf = vec![0u8; self.inode_size];
+        block_group.read_raw_inode(inode_idx, &mut buf);
+        Ok(buf.split_off(core::mem::size_of::<RawInode>()))
+    }
+
+    /// Writes back the raw bytes after the metadata of inode in the inode table.
+    pub(super) fn sync_inode_extra(&self, ino: u32, buf: &[u8]) -> Result<()> {
+        self.update_raw_inode(ino, |raw_inode| {
+            raw_inode[core::mem::size_of::<RawInode>()..].copy_from_slice(buf)
+        })
+    }
+
+    /// Updates the raw inode record in the inode table with `f`, then updates its checksum.
+    fn update_raw_inode(&self, ino: u32, f: impl FnOnce(&mut [u8])) -> Result<()> {
+        let (_, block_group) = self.block_group_of_ino(ino)?;
+        let inode_idx = self.inode_idx(ino);
+        let mut raw_inode = vec![0u8; self.inode_size];
+        block_group.read_raw_inode(inode_idx, &mut raw_inode);
+        f(&mut raw_inode);
+        if let Some(checksum) = self.compute_inode_checksum(ino, &raw_inode) {
+            let (low_offset, high_offset) = raw_inode_checksum_offsets(&raw_inode);
+            raw_inode[low_offset..low_offset + 2].copy_from_slice(&(checksum as u16).to_le_bytes());
+            if let Some(offset) = high_offset {
+                raw_inode[offset..offset + 2]
+                    .copy_from_slice(&((checksum >> 16) as u16).to_le_bytes());
+            }
+        }
+        block_group.sync_raw_inode(inode_idx, &raw_inode);
         Ok(())
     }
 
+    /// Verifies the checksum of the raw inode record.
+    pub(super) fn verify_inode_checksum(&self, ino: u32, raw_inode: &[u8]) -> Result<()> {
+        let Some(mut expected) = self.compute_inode_checksum(ino, raw_inode) else {
+            return Ok(());
+        };
+
+        let (low_offset, high_offset) = raw_inode_checksum_offsets(raw_inode);
+        let read_u16 =
+            |offset: usize| u16::from_le_bytes([raw_inode[offset], raw_inode[offset + 1]]);
+        let mut checksum = read_u16(low_offset) as u32;
+        match high_offset {
+            Some(offset) => checksum |= (read_u16(offset) as u32) << 16,
+            None => expected &= 0xFFFF,
+        }
+        if checksum != expected {
+            return_errno_with_message!(Errno::EBADMSG, "bad inode checksum");
+        }
+        Ok(())
+    }
+
+    /// Computes the checksum of the raw inode record.
+    ///
+    /// Returns `None` if the metadata checksums are disabled.
+    fn compute_inode_checksum(&self, ino: u32, raw_inode: &[u8]) -> Option<u32> {
+        let generation = RawInode::from_bytes(raw_inode).generation;
+        let seed = self.inode_csum_seed(ino, generation)?;
+
+        // The checksum fields are treated as zeros.
+        let (low_offset, high_offset) = raw_inode_checksum_offsets(raw_inode);
+        let mut raw_inode = raw_inode.to_vec();
+        raw_inode[low_offset..low_offset + 2].fill(0);
+        if let Some(offset) = high_offset {
+            raw_inode[offset..offset + 2].fill(0);
+        }
+        Some(crc32c(seed, &raw_inode))
+    }
+
+    /// Returns the seed of the checksums of the metadata that belong to an inode.
+    ///
+    /// Returns `None` if the metadata checksums are disabled.
+    pub(super) fn inode_csum_seed(&self, ino: u32, generation: u32) -> Option<u32> {
+        let seed = crc32c(self.csum_seed?, &ino.to_le_bytes());
+        Some(crc32c(seed, &generation.to_le_bytes()))
+    }
+
+    /// Returns the seed of the metadata checksums.
+    ///
+    /// Returns `None` if the metadata checksums are disabled.
+    pub(super) fn csum_seed(&self) -> Option<u32> {
+        self.csum_seed
+    }
+
+    /// Acquires the lock that serializes the updates of the shared xattr blocks.
+    pub(super) fn xattr_block_lock(&self) -> MutexGuard<()> {
+        self.xattr_block_lock.lock()
+    }
+
     /// Writes back the block group descriptor to the descriptors table.
     pub(super) fn sync_group_descriptor(
         &self,
         block_group_idx: usize,
         raw_descriptor: &RawGroupDescriptor,
+        super_block: &SuperBlock,
     ) -> Result<()> {
-        let offset = block_group_idx * core::mem::size_of::<RawGroupDescriptor>();
+        let mut raw_descriptor = *raw_descriptor;
+        if super_block.has_group_desc_csum() {
+            raw_descriptor.checksum = raw_descriptor.compute_checksum(block_group_idx, super_block);
+        }
+        let offset = block_group_idx * self.desc_size;
         self.group_descriptors_segment
-            .write_val(offset, raw_descriptor)?;
+            .write_bytes(offset, &raw_descriptor.as_bytes()[..self.desc_size])?;
         Ok(())
     }
 
@@ -278,6 +419,11 @@ impl Ext2 {
 
     /// Frees a range of blocks.
     pub(super) fn free_blocks(&self, range: Range<Ext2Bid>) -> Result<()> {
+        // The freed metadata blocks must not overwrite the blocks reused later.
+        if let Some(journal) = self.journal.as_ref() {
+            journal.forget(Bid::new(range.start as u64)..Bid::new(range.end as u64));
+        }
+
         let mut current_range = range.clone();
         while !current_range.is_empty() {
             let (_, block_group) = self.block_group_of_bid(current_range.start)?;
@@ -344,45 +490,201 @@ impl Ext2 {
         Ok(waiter)
     }
 
+    /// Reads a metadata block synchronously.
+    ///
+    /// If the journal keeps a newer version of the block, the block is read from the journal.
+    pub(super) fn read_meta_block(&self, bid: Ext2Bid, buf: &mut [u8]) -> Result<()> {
+        let bid = Bid::new(bid as u64);
+        if self
+            .journal
+            .as_ref()
+            .is_some_and(|journal| journal.read_block(bid, buf))
+        {
+            return Ok(());
+        }
+        self.block_device.read_bytes(bid.to_offset(), buf)?;
+        Ok(())
+    }
+
+    /// Writes a metadata block synchronously.
+    ///
+    /// If the filesystem has a journal, the block is written to the running transaction,
+    /// and will reach the device after the transaction is committed.
+    pub(super) fn write_meta_block(&self, bid: Ext2Bid, buf: &[u8]) -> Result<()> {
+        let bid = Bid::new(bid as u64);
+        match self.journal.as_ref() {
+            Some(journal) => journal.write_block(bid, buf),
+            None => self.block_device.write_bytes(bid.to_offset(), buf)?,
+        }
+        Ok(())
+    }
+
+    /// Writes a metadata block asynchronously.
+    ///
+    /// If the filesystem has a journal, the block is written to the running transaction,
+    /// and the returned waiter is completed.
+    pub(super) fn write_meta_block_async(&self, bid: Ext2Bid, buf: &[u8]) -> Result<BioWaiter> {
+        let bid = Bid::new(bid as u64);
+        let Some(journal) = self.journal.as_ref() else {
+            let waiter = self.block_device.write_bytes_async(bid.to_offset(), buf)?;
+            return Ok(waiter);
+        };
+        journal.write_block(bid, buf);
+        Ok(BioWaiter::new())
+    }
+
+    /// Reads contiguous metadata blocks starting from the `bid` to the `segment` synchronously.
+    pub(super) fn read_meta_blocks(&self, bid: Ext2Bid, segment: &Segment) -> Result<()> {
+        match self.read_meta_blocks_async(bid, segment)?.wait() {
+            Some(BioStatus::Complete) => Ok(()),
+            _ => return_errno!(Errno::EIO),
+        }
+    }
+
+    /// Reads contiguous metadata blocks starting from the `bid` to the `segment` asynchronously.
+    pub(super) fn read_meta_blocks_async(
+        &self,
+        bid: Ext2Bid,
+        segment: &Segment,
+    ) -> Result<BioWaiter> {
+        let nblocks = segment.nbytes().div_ceil(BLOCK_SIZE) as Ext2Bid;
+        let is_journaled = self.journal.as_ref().is_some_and(|journal| {
+            (bid..bid + nblocks).any(|bid| journal.contains(Bid::new(bid as u64)))
+        });
+        if !is_journaled {
+            let bio_segment =
+                BioSegment::new_from_segment(segment.clone(), BioDirection::FromDevice);
+            return self.read_blocks_async(bid, bio_segment);
+        }
+
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        for idx in 0..nblocks {
+            self.read_meta_block(bid + idx, &mut buf)?;
+            segment.write_bytes(idx as usize * BLOCK_SIZE, &buf)?;
+        }
+        Ok(BioWaiter::new())
+    }
+
+    /// Writes contiguous metadata blocks starting from the `bid` from the `segment`
+    /// asynchronously.
+    ///
+    /// If the filesystem has a journal, the blocks are written to the running transaction,
+    /// and the returned waiter is completed.
+    pub(super) fn write_meta_blocks_async(
+        &self,
+        bid: Ext2Bid,
+        segment: &Segment,
+    ) -> Result<BioWaiter> {
+        let Some(journal) = self.journal.as_ref() else {
+            let bio_segment = BioSegment::new_from_segment(segment.clone(), BioDirection::ToDevice);
+            return self.write_blocks_async(bid, bio_segment);
+        };
+
+        let nblocks = segment.nbytes().div_ceil(BLOCK_SIZE) as Ext2Bid;
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        for idx in 0..nblocks {
+            segment.read_bytes(idx as usize * BLOCK_SIZE, &mut buf)?;
+            journal.write_block(Bid::new((bid + idx) as u64), &buf);
+        }
+        Ok(BioWaiter::new())
+    }
+
+    /// Runs an operation that may modify the metadata.
+    ///
+    /// The journal is never committed in the middle of an operation, so the metadata of
+    /// the operation reach the device as a whole. If the running transaction has taken a
+    /// large part of the log, it is committed before the operation starts.
+    pub(super) fn run_op<T>(&self, op: impl FnOnce() -> Result<T>) -> Result<T> {
+        if self
+            .journal
+            .as_ref()
+            .is_some_and(|journal| journal.needs_commit())
+        {
+            self.sync_all()?;
+        }
+
+        let _op_guard = self.op_lock.read();
+        op()
+    }
+
+    /// Writes back all the cached inodes and the metadata to the block device.
+    ///
+    /// If the filesystem has a journal, the metadata are committed as a transaction while
+    /// no operation is running.
+    pub fn sync_all(&self) -> Result<()> {
+        let _op_guard = self.op_lock.write();
+        self.sync_all_inodes()?;
+        self.sync_metadata()
+    }
+
     /// Writes back the metadata to the block device.
+    ///
+    /// If the filesystem has a journal, the metadata written so far, including those of
+    /// the inodes, are committed as a transaction.
     pub fn sync_metadata(&self) -> Result<()> {
+        let has_uncommitted = self
+            .journal
+            .as_ref()
+            .is_some_and(|journal| journal.has_uncommitted());
         // If the superblock is clean, the block groups must be clean.
-        if !self.super_block.read().is_dirty() {
+        if !self.super_block.read().is_dirty() && !has_uncommitted {
             return Ok(());
         }
 
         let mut super_block = self.super_block.write();
+        if !super_block.is_dirty() {
+            return self.commit_journal();
+        }
+
         // Writes back the metadata of block groups
         for block_group in &self.block_groups {
-            block_group.sync_metadata()?;
+            block_group.sync_metadata(&super_block)?;
         }
 
         // Writes back the main superblock and group descriptor table.
         let mut bio_waiter = BioWaiter::new();
-        let raw_super_block = RawSuperBlock::from((*super_block).deref());
-        bio_waiter.concat(
-            self.block_device
-                .write_bytes_async(SUPER_BLOCK_OFFSET, raw_super_block.as_bytes())?,
-        );
-        let group_descriptors_bio_segment = BioSegment::new_from_segment(
-            self.group_descriptors_segment.clone(),
-            BioDirection::ToDevice,
-        );
-        bio_waiter.concat(self.block_device.write_blocks_async(
-            super_block.group_descriptors_bid(0),
-            group_descriptors_bio_segment.clone(),
+        let mut raw_super_block = RawSuperBlock::from((*super_block).deref());
+        super_block.update_checksum(&mut raw_super_block);
+        if self.journal.is_some() {
+            // The journaled superblock keeps the flag, which is cleared after the commit.
+            let mut raw_journaled_super_block = raw_super_block;
+            raw_journaled_super_block.feature_incompat |= FeatureInCompatSet::RECOVER.bits();
+            super_block.update_checksum(&mut raw_journaled_super_block);
+
+            let bid = super_block.bid(0).to_raw() as Ext2Bid;
+            let offset = SUPER_BLOCK_OFFSET % BLOCK_SIZE;
+            let mut buf = vec![0u8; BLOCK_SIZE];
+            self.read_meta_block(bid, &mut buf)?;
+            buf[offset..offset + core::mem::size_of::<RawSuperBlock>()]
+                .copy_from_slice(raw_journaled_super_block.as_bytes());
+            self.write_meta_block(bid, &buf)?;
+        } else {
+            bio_waiter.concat(
+                self.block_device
+                    .write_bytes_async(SUPER_BLOCK_OFFSET, raw_super_block.as_bytes())?,
+            );
+        }
+        bio_waiter.concat(self.write_meta_blocks_async(
+            super_block.group_descriptors_bid(0).to_raw() as Ext2Bid,
+            &self.group_descriptors_segment,
         )?);
         bio_waiter
             .wait()
             .ok_or_else(|| Error::with_message(Errno::EIO, "failed to sync main metadata"))?;
         drop(bio_waiter);
+        self.commit_journal()?;
 
         // Writes back the backups of superblock and group descriptor table.
+        let group_descriptors_bio_segment = BioSegment::new_from_segment(
+            self.group_descriptors_segment.clone(),
+            BioDirection::ToDevice,
+        );
         let mut raw_super_block_backup = raw_super_block;
         for idx in 1..super_block.block_groups_count() {
             if super_block.is_backup_group(idx as usize) {
                 let mut bio_waiter = BioWaiter::new();
                 raw_super_block_backup.block_group_idx = idx as u16;
+                super_block.update_checksum(&mut raw_super_block_backup);
                 bio_waiter.concat(self.block_device.write_bytes_async(
                     super_block.bid(idx as usize).to_offset(),
                     raw_super_block_backup.as_bytes(),
@@ -402,6 +704,23 @@ impl Ext2 {
         Ok(())
     }
 
+    /// Commits the metadata kept by the journal, if any.
+    ///
+    /// The superblock on the device is marked as needing recovery while the log is not
+    /// empty, so that the other implementations will replay the journal if a crash occurs.
+    fn commit_journal(&self) -> Result<()> {
+        let Some(journal) = self.journal.as_ref() else {
+            return Ok(());
+        };
+        if !journal.has_uncommitted() {
+            return Ok(());
+        }
+
+        set_needs_recovery(self.block_device(), true)?;
+        journal.commit()?;
+        set_needs_recovery(self.block_device(), false)
+    }
+
     /// Writes back all the cached inodes to the block device.
     pub fn sync_all_inodes(&self) -> Result<()> {
         for block_group in &self.block_groups {
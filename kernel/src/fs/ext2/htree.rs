@@ -0,0 +1,722 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The hash tree (htree) index of directories.
+//!
+//! A hash-indexed directory stores its entries in the leaf blocks as a linear directory
+//! does, while each leaf block holds the entries whose name hashes fall into a range.
+//! The ranges are recorded in the root, i.e., the first block of the directory, and at
+//! most one level of index nodes below it. The index data are hidden in the unused space
+//! of the directory entries, so the directory is still valid as a linear one.
+//!
+//! The format is described in
+//! <https://www.kernel.org/doc/html/latest/filesystems/ext4/directory.html#hash-tree-directories>.
+
+use core::mem::size_of;
+
+use super::{
+    dir::{DirEntry, DirEntryReader, DirEntryWriter},
+    prelude::*,
+    super_block::SuperBlock,
+};
+
+/// The offset of the index information in the root, which follows the "." and ".." entries.
+const ROOT_INFO_OFFSET: usize = 24;
+
+/// The offset of the index entries in the root.
+const ROOT_ENTRIES_OFFSET: usize = ROOT_INFO_OFFSET + size_of::<RawDxRootInfo>();
+
+/// The offset of the index entries in a node, which follow an unused directory entry
+/// that covers the whole block.
+const NODE_ENTRIES_OFFSET: usize = 8;
+
+/// The length of the tail at the end of an index node, which consists of a reserved
+/// field and the checksum of the node if the metadata checksums are enabled.
+const DX_TAIL_LEN: usize = 8;
+
+/// The max number of the levels of the index nodes below the root.
+///
+/// More levels need the `LARGEDIR` feature, which is not supported.
+const MAX_LEVELS: u8 = 1;
+
+/// A hash index of a directory.
+pub(super) struct HTree<'a> {
+    page_cache: &'a PageCache,
+    hasher: NameHasher,
+    /// The hash version recorded in the root.
+    hash_version: u8,
+    /// The number of the levels of the index nodes below the root.
+    levels: u8,
+    /// The seed of the checksums of the directory blocks, or `None` if checksums are disabled.
+    csum_seed: Option<u32>,
+}
+
+/// A node of the index, which is either the root or a node below it.
+struct DxNode {
+    /// The directory block that stores the node.
+    block: u32,
+    /// The index entries, each of which maps the hashes starting from the first item to
+    /// the block of the second item.
+    ///
+    /// The hash of the first entry is not stored, which is always zero.
+    entries: Vec<(u32, u32)>,
+    /// The max number of the index entries.
+    limit: usize,
+}
+
+/// The path from the root to a leaf block, where each node is paired with the position
+/// of the index entry that is followed.
+type DxPath = Vec<(DxNode, usize)>;
+
+impl<'a> HTree<'a> {
+    /// Loads the index of the directory from the root.
+    pub fn load(
+        page_cache: &'a PageCache,
+        super_block: &SuperBlock,
+        csum_seed: Option<u32>,
+    ) -> Result<Self> {
+        let info = page_cache
+            .pages()
+            .read_val::<RawDxRootInfo>(ROOT_INFO_OFFSET)?;
+        if info.info_length as usize != size_of::<RawDxRootInfo>() {
+            return_errno_with_message!(Errno::EUCLEAN, "the directory index is corrupted");
+        }
+        if info.indirect_levels > MAX_LEVELS || info.unused_flags & 1 != 0 {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the directory index is too deep");
+        }
+
+        let htree = Self {
+            page_cache,
+            hasher: NameHasher::new(info.hash_version, super_block)?,
+            hash_version: info.hash_version,
+            levels: info.indirect_levels,
+            csum_seed,
+        };
+        htree.read_node(0)?;
+        Ok(htree)
+    }
+
+    /// Creates the index of a linear directory that has only one block.
+    ///
+    /// The entries except "." and ".." are moved to a new leaf block, so that the first
+    /// block can be used as the root.
+    pub fn create(
+        page_cache: &'a PageCache,
+        super_block: &SuperBlock,
+        csum_seed: Option<u32>,
+    ) -> Result<()> {
+        let hash_version = super_block.def_hash_version();
+        let htree = Self {
+            page_cache,
+            hasher: NameHasher::new(hash_version, super_block)?,
+            hash_version,
+            levels: 0,
+            csum_seed,
+        };
+
+        let entries: Vec<DirEntry> = DirEntryReader::new(page_cache, 0)
+            .take_while(|(offset, _)| *offset < BLOCK_SIZE)
+            .map(|(_, entry)| entry)
+            .collect();
+        let [dot, dot_dot, entries @ ..] = entries.as_slice() else {
+            return_errno_with_message!(Errno::EUCLEAN, "the directory is corrupted");
+        };
+        if dot.name() != "." || dot_dot.name() != ".." {
+            return_errno_with_message!(Errno::EUCLEAN, "the directory is corrupted");
+        }
+
+        let mut writer = htree.writer();
+        let leaf_offset = writer.append_block()?;
+        writer.write_block_entries(leaf_offset, entries)?;
+
+        // The ".." entry covers the rest of the root, which hides the index.
+        let mut writer = htree.writer();
+        let mut dot = dot.clone();
+        dot.set_record_len(dot.actual_len());
+        writer.write_entry(&dot)?;
+        let mut dot_dot = dot_dot.clone();
+        dot_dot.set_record_len(BLOCK_SIZE - dot.record_len());
+        writer.write_entry(&dot_dot)?;
+
+        htree.write_node(&DxNode {
+            block: 0,
+            entries: vec![(0, (leaf_offset / BLOCK_SIZE) as u32)],
+            limit: htree.root_limit(),
+        })
+    }
+
+    /// Looks up the entry by `name`.
+    ///
+    /// Returns the offset and the entry, or `None` if the entry does not exist.
+    pub fn lookup(&self, name: &str) -> Result<Option<(usize, DirEntry)>> {
+        let hash = self.hasher.hash(name.as_bytes());
+        let mut path = self.probe(hash)?;
+        loop {
+            let leaf_offset = leaf_block(&path) as usize * BLOCK_SIZE;
+            let found = DirEntryReader::new(self.page_cache, leaf_offset)
+                .take_while(|(offset, _)| *offset < leaf_offset + BLOCK_SIZE)
+                .find(|(_, entry)| entry.name() == name);
+            if found.is_some() {
+                return Ok(found);
+            }
+
+            // The entries with the same hash may continue in the next leaf block.
+            if !self.next_leaf(&mut path, hash)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Inserts the `entry` into the leaf block that covers the hash of its name.
+    ///
+    /// The leaf block is split if it is full. Returns `false` if the index is full, in
+    /// which case the entry is not inserted.
+    pub fn insert(&mut self, entry: &DirEntry) -> Result<bool> {
+        let hash = self.hasher.hash(entry.name().as_bytes());
+        let mut path = self.probe(hash)?;
+        let leaf = leaf_block(&path);
+        if self
+            .writer()
+            .insert_in_block(leaf as usize * BLOCK_SIZE, entry)?
+        {
+            return Ok(true);
+        }
+
+        // Splitting the leaf block takes a new index entry.
+        if !self.reserve_index_entry(&mut path)? {
+            return Ok(false);
+        }
+        let Some((new_leaf, split_hash, is_continued)) = self.split_leaf(leaf)? else {
+            return Ok(false);
+        };
+        let (node, pos) = path.last_mut().unwrap();
+        // The lowest bit marks that the entries with the hash span the two blocks.
+        node.entries
+            .insert(*pos + 1, (split_hash | is_continued as u32, new_leaf));
+        self.write_node(node)?;
+
+        let target = if hash >= split_hash { new_leaf } else { leaf };
+        self.writer()
+            .insert_in_block(target as usize * BLOCK_SIZE, entry)
+    }
+
+    /// Rewrites the root to update its checksum, after the ".." entry is modified.
+    pub fn update_root_checksum(&self) -> Result<()> {
+        if self.csum_seed.is_none() {
+            return Ok(());
+        }
+        let root = self.read_node(0)?;
+        self.write_node(&root)
+    }
+
+    /// Finds the path from the root to the leaf block that covers the `hash`.
+    fn probe(&self, hash: u32) -> Result<DxPath> {
+        let mut path = DxPath::new();
+        let mut node = self.read_node(0)?;
+        loop {
+            // The last index entry whose hash is not larger than `hash`.
+            let pos = node.entries[1..].partition_point(|(entry_hash, _)| *entry_hash <= hash);
+            let child = node.entries[pos].1;
+            path.push((node, pos));
+            if path.len() > self.levels as usize {
+                return Ok(path);
+            }
+            node = self.read_node(child)?;
+        }
+    }
+
+    /// Moves the `path` to the next leaf block if it continues the entries of the `hash`.
+    ///
+    /// Returns `false` if there is no such block.
+    fn next_leaf(&self, path: &mut DxPath, hash: u32) -> Result<bool> {
+        let mut level = path.len() - 1;
+        loop {
+            let (node, pos) = &mut path[level];
+            *pos += 1;
+            if *pos < node.entries.len() {
+                break;
+            }
+            if level == 0 {
+                return Ok(false);
+            }
+            level -= 1;
+        }
+
+        let (node, pos) = &path[level];
+        if node.entries[*pos].0 & !1 != hash {
+            return Ok(false);
+        }
+
+        path.truncate(level + 1);
+        while path.len() <= self.levels as usize {
+            let (node, pos) = path.last().unwrap();
+            let child = self.read_node(node.entries[*pos].1)?;
+            path.push((child, 0));
+        }
+        Ok(true)
+    }
+
+    /// Makes room for an index entry in the last node of the `path`, which is updated
+    /// if the index is restructured.
+    ///
+    /// Returns `false` if the index is full.
+    fn reserve_index_entry(&mut self, path: &mut DxPath) -> Result<bool> {
+        let (node, _) = path.last().unwrap();
+        if node.entries.len() < node.limit {
+            return Ok(true);
+        }
+
+        if path.len() == 1 {
+            // The root is full, so its entries are moved to a new node below it.
+            if self.levels >= MAX_LEVELS {
+                return Ok(false);
+            }
+            let (mut root, pos) = path.pop().unwrap();
+            let block = self.append_node_block()?;
+            let node = DxNode {
+                block,
+                entries: core::mem::replace(&mut root.entries, vec![(0, block)]),
+                limit: self.node_limit(),
+            };
+            self.levels += 1;
+            self.write_node(&node)?;
+            self.write_node(&root)?;
+            path.push((root, 0));
+            path.push((node, pos));
+            return Ok(true);
+        }
+
+        // The node below the root is full, so it is split if the root has room.
+        let (root, _) = &path[0];
+        if root.entries.len() >= root.limit {
+            return Ok(false);
+        }
+        let (mut node, pos) = path.pop().unwrap();
+        let (mut root, root_pos) = path.pop().unwrap();
+        let half = node.entries.len() / 2;
+        let mut new_node = DxNode {
+            block: self.append_node_block()?,
+            entries: node.entries.split_off(half),
+            limit: node.limit,
+        };
+        let split_hash = core::mem::replace(&mut new_node.entries[0].0, 0);
+        root.entries
+            .insert(root_pos + 1, (split_hash, new_node.block));
+        self.write_node(&new_node)?;
+        self.write_node(&node)?;
+        self.write_node(&root)?;
+
+        if pos >= half {
+            path.push((root, root_pos + 1));
+            path.push((new_node, pos - half));
+        } else {
+            path.push((root, root_pos));
+            path.push((node, pos));
+        }
+        Ok(true)
+    }
+
+    /// Splits the full leaf `block` by moving the entries with the larger hashes to
+    /// a new leaf block.
+    ///
+    /// Returns the new block, the least hash in it, and whether the entries with that
+    /// hash also remain in the old block. Returns `None` if the block cannot be split.
+    fn split_leaf(&self, block: u32) -> Result<Option<(u32, u32, bool)>> {
+        let block_offset = block as usize * BLOCK_SIZE;
+        let mut entries: Vec<(u32, DirEntry)> = DirEntryReader::new(self.page_cache, block_offset)
+            .take_while(|(offset, _)| *offset < block_offset + BLOCK_SIZE)
+            .map(|(_, entry)| (self.hasher.hash(entry.name().as_bytes()), entry))
+            .collect();
+        if entries.len() < 2 {
+            return Ok(None);
+        }
+        entries.sort_by_key(|(hash, _)| *hash);
+
+        // Moves about half of the bytes, starting from the entry with the largest hash.
+        let mut split = entries.len();
+        let mut moved_len = 0;
+        for (idx, (_, entry)) in entries.iter().enumerate().rev() {
+            if moved_len + entry.actual_len() / 2 > BLOCK_SIZE / 2 {
+                break;
+            }
+            moved_len += entry.actual_len();
+            split = idx;
+        }
+        let split = split.clamp(1, entries.len() - 1);
+        let split_hash = entries[split].0;
+        let is_continued = entries[split - 1].0 == split_hash;
+
+        let right: Vec<DirEntry> = entries
+            .split_off(split)
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect();
+        let left: Vec<DirEntry> = entries.into_iter().map(|(_, entry)| entry).collect();
+
+        let mut writer = self.writer();
+        let new_offset = writer.append_block()?;
+        writer.write_block_entries(new_offset, &right)?;
+        writer.write_block_entries(block_offset, &left)?;
+        Ok(Some((
+            (new_offset / BLOCK_SIZE) as u32,
+            split_hash,
+            is_continued,
+        )))
+    }
+
+    /// Reads the index node stored in the `block`.
+    fn read_node(&self, block: u32) -> Result<DxNode> {
+        let corrupted = || Error::with_message(Errno::EUCLEAN, "the directory index is corrupted");
+        let nblocks = self.page_cache.pages().size() / BLOCK_SIZE;
+        if block as usize >= nblocks {
+            return Err(corrupted());
+        }
+
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        self.page_cache
+            .pages()
+            .read_bytes(block as usize * BLOCK_SIZE, &mut buf)?;
+        let (entries_offset, limit) = self.node_layout(block);
+        let count_limit = RawDxCountLimit::from_bytes(&buf[entries_offset..]);
+        let count = count_limit.count as usize;
+        if count_limit.limit as usize != limit || count == 0 || count > limit {
+            return Err(corrupted());
+        }
+
+        let entries: Vec<(u32, u32)> = (0..count)
+            .map(|idx| {
+                let entry =
+                    RawDxEntry::from_bytes(&buf[entries_offset + idx * size_of::<RawDxEntry>()..]);
+                let hash = if idx == 0 { 0 } else { entry.hash };
+                (hash, entry.block)
+            })
+            .collect();
+        if entries
+            .iter()
+            .any(|(_, block)| *block == 0 || *block as usize >= nblocks)
+        {
+            return Err(corrupted());
+        }
+        Ok(DxNode {
+            block,
+            entries,
+            limit,
+        })
+    }
+
+    /// Writes the index `node` to its block.
+    fn write_node(&self, node: &DxNode) -> Result<()> {
+        let block_offset = node.block as usize * BLOCK_SIZE;
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        let (entries_offset, limit) = self.node_layout(node.block);
+        if node.block == 0 {
+            // The root keeps the "." and ".." entries.
+            self.page_cache
+                .pages()
+                .read_bytes(block_offset, &mut buf[..ROOT_INFO_OFFSET])?;
+            let info = RawDxRootInfo {
+                reserved_zero: 0,
+                hash_version: self.hash_version,
+                info_length: size_of::<RawDxRootInfo>() as u8,
+                indirect_levels: self.levels,
+                unused_flags: 0,
+            };
+            buf[ROOT_INFO_OFFSET..ROOT_ENTRIES_OFFSET].copy_from_slice(info.as_bytes());
+        } else {
+            // The node is hidden in an unused entry that covers the whole block.
+            buf[4..6].copy_from_slice(&(BLOCK_SIZE as u16).to_le_bytes());
+        }
+
+        for (idx, (hash, block)) in node.entries.iter().enumerate() {
+            let entry = RawDxEntry {
+                hash: *hash,
+                block: *block,
+            };
+            let offset = entries_offset + idx * size_of::<RawDxEntry>();
+            buf[offset..offset + size_of::<RawDxEntry>()].copy_from_slice(entry.as_bytes());
+        }
+        let count_limit = RawDxCountLimit {
+            limit: limit as u16,
+            count: node.entries.len() as u16,
+        };
+        buf[entries_offset..entries_offset + size_of::<RawDxCountLimit>()]
+            .copy_from_slice(count_limit.as_bytes());
+
+        if let Some(seed) = self.csum_seed {
+            // The checksum covers the used entries and the tail with the checksum as zero.
+            let tail_offset = entries_offset + limit * size_of::<RawDxEntry>();
+            let len = entries_offset + node.entries.len() * size_of::<RawDxEntry>();
+            let checksum = crc32c(seed, &buf[..len]);
+            let checksum = crc32c(checksum, &buf[tail_offset..tail_offset + 4]);
+            let checksum = crc32c(checksum, &[0u8; 4]);
+            buf[tail_offset + 4..tail_offset + 8].copy_from_slice(&checksum.to_le_bytes());
+        }
+
+        self.page_cache.pages().write_bytes(block_offset, &buf)?;
+        Ok(())
+    }
+
+    /// Returns the offset of the index entries and the max number of them in the node
+    /// stored in the `block`.
+    fn node_layout(&self, block: u32) -> (usize, usize) {
+        if block == 0 {
+            (ROOT_ENTRIES_OFFSET, self.root_limit())
+        } else {
+            (NODE_ENTRIES_OFFSET, self.node_limit())
+        }
+    }
+
+    fn root_limit(&self) -> usize {
+        (BLOCK_SIZE - ROOT_ENTRIES_OFFSET - self.tail_len()) / size_of::<RawDxEntry>()
+    }
+
+    fn node_limit(&self) -> usize {
+        (BLOCK_SIZE - NODE_ENTRIES_OFFSET - self.tail_len()) / size_of::<RawDxEntry>()
+    }
+
+    /// Returns the length of the tail that stores the checksum of an index node.
+    fn tail_len(&self) -> usize {
+        if self.csum_seed.is_some() {
+            DX_TAIL_LEN
+        } else {
+            0
+        }
+    }
+
+    /// Appends a new block for an index node.
+    fn append_node_block(&self) -> Result<u32> {
+        let block_offset = self.writer().append_block()?;
+        Ok((block_offset / BLOCK_SIZE) as u32)
+    }
+
+    fn writer(&self) -> DirEntryWriter<'a> {
+        DirEntryWriter::new(self.page_cache, 0, self.csum_seed, true)
+    }
+}
+
+/// Returns the leaf block that the `path` leads to.
+fn leaf_block(path: &DxPath) -> u32 {
+    let (node, pos) = path.last().unwrap();
+    node.entries[*pos].1
+}
+
+/// The hash versions of the directory index.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, TryFromInt)]
+enum HashVersion {
+    Legacy = 0,
+    HalfMd4 = 1,
+    Tea = 2,
+    LegacyUnsigned = 3,
+    HalfMd4Unsigned = 4,
+    TeaUnsigned = 5,
+}
+
+/// The hasher of the names in a hash-indexed directory.
+#[derive(Clone, Copy, Debug)]
+struct NameHasher {
+    version: HashVersion,
+    seed: [u32; 4],
+}
+
+impl NameHasher {
+    /// Creates the hasher of the hash `version` recorded in the root.
+    ///
+    /// The superblock decides whether the names are hashed as signed or unsigned chars.
+    fn new(version: u8, super_block: &SuperBlock) -> Result<Self> {
+        let version = match HashVersion::try_from(version) {
+            Ok(HashVersion::Legacy) if super_block.has_unsigned_hash() => {
+                HashVersion::LegacyUnsigned
+            }
+            Ok(HashVersion::HalfMd4) if super_block.has_unsigned_hash() => {
+                HashVersion::HalfMd4Unsigned
+            }
+            Ok(HashVersion::Tea) if super_block.has_unsigned_hash() => HashVersion::TeaUnsigned,
+            Ok(version) => version,
+            Err(_) => {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "unsupported directory hash")
+            }
+        };
+
+        // The default seed is used if the seed is all zeros.
+        let seed = super_block.hash_seed();
+        let seed = if seed.iter().any(|word| *word != 0) {
+            seed
+        } else {
+            [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476]
+        };
+        Ok(Self { version, seed })
+    }
+
+    /// Returns the hash of the `name`, whose lowest bit is always zero.
+    fn hash(&self, name: &[u8]) -> u32 {
+        let mut buf = self.seed;
+        let hash = match self.version {
+            HashVersion::Legacy => legacy_hash(name, true),
+            HashVersion::LegacyUnsigned => legacy_hash(name, false),
+            HashVersion::HalfMd4 | HashVersion::HalfMd4Unsigned => {
+                let is_signed = self.version == HashVersion::HalfMd4;
+                for offset in (0..name.len()).step_by(32) {
+                    half_md4_transform(&mut buf, &str_to_hash_buf(&name[offset..], is_signed));
+                }
+                buf[1]
+            }
+            HashVersion::Tea | HashVersion::TeaUnsigned => {
+                let is_signed = self.version == HashVersion::Tea;
+                for offset in (0..name.len()).step_by(16) {
+                    tea_transform(&mut buf, &str_to_hash_buf(&name[offset..], is_signed));
+                }
+                buf[0]
+            }
+        };
+
+        // The largest hash is reserved as the end of the directory.
+        let hash = hash & !1;
+        if hash == 0xFFFF_FFFE {
+            0xFFFF_FFFC
+        } else {
+            hash
+        }
+    }
+}
+
+/// Computes the legacy hash of the `name`.
+fn legacy_hash(name: &[u8], is_signed: bool) -> u32 {
+    let (mut hash0, mut hash1) = (0x12A3_FE2Du32, 0x37AB_E8F9u32);
+    for &byte in name {
+        let value = if is_signed {
+            byte as i8 as i32
+        } else {
+            byte as i32
+        };
+        let mut hash = hash1.wrapping_add(hash0 ^ value.wrapping_mul(7152373) as u32);
+        if hash & 0x8000_0000 != 0 {
+            hash = hash.wrapping_sub(0x7FFF_FFFF);
+        }
+        hash1 = hash0;
+        hash0 = hash;
+    }
+    hash0 << 1
+}
+
+/// Packs the beginning of the `msg` into `N` words, which are padded with the length
+/// of the `msg`.
+fn str_to_hash_buf<const N: usize>(msg: &[u8], is_signed: bool) -> [u32; N] {
+    let mut pad = msg.len() as u32 | ((msg.len() as u32) << 8);
+    pad |= pad << 16;
+
+    let mut buf = [pad; N];
+    let mut value = pad;
+    let mut idx = 0;
+    for (i, &byte) in msg.iter().take(N * 4).enumerate() {
+        let byte = if is_signed {
+            byte as i8 as u32
+        } else {
+            byte as u32
+        };
+        value = byte.wrapping_add(value << 8);
+        if i % 4 == 3 {
+            buf[idx] = value;
+            value = pad;
+            idx += 1;
+        }
+    }
+    if idx < N {
+        buf[idx] = value;
+    }
+    buf
+}
+
+/// The transform of the TEA hash.
+fn tea_transform(buf: &mut [u32; 4], input: &[u32; 4]) {
+    const DELTA: u32 = 0x9E37_79B9;
+
+    let (mut b0, mut b1) = (buf[0], buf[1]);
+    let [a, b, c, d] = *input;
+    let mut sum = 0u32;
+    for _ in 0..16 {
+        sum = sum.wrapping_add(DELTA);
+        b0 = b0.wrapping_add(
+            (b1 << 4).wrapping_add(a) ^ b1.wrapping_add(sum) ^ (b1 >> 5).wrapping_add(b),
+        );
+        b1 = b1.wrapping_add(
+            (b0 << 4).wrapping_add(c) ^ b0.wrapping_add(sum) ^ (b0 >> 5).wrapping_add(d),
+        );
+    }
+    buf[0] = buf[0].wrapping_add(b0);
+    buf[1] = buf[1].wrapping_add(b1);
+}
+
+/// The transform of the half MD4 hash, which has three rounds of eight steps each.
+fn half_md4_transform(buf: &mut [u32; 4], input: &[u32; 8]) {
+    type RoundFn = fn(u32, u32, u32) -> u32;
+    const ROUNDS: [(RoundFn, u32, [usize; 8], [u32; 4]); 3] = [
+        (
+            |x, y, z| z ^ (x & (y ^ z)),
+            0,
+            [0, 1, 2, 3, 4, 5, 6, 7],
+            [3, 7, 11, 19],
+        ),
+        (
+            |x, y, z| (x & y).wrapping_add((x ^ y) & z),
+            0x5A82_7999,
+            [1, 3, 5, 7, 0, 2, 4, 6],
+            [3, 5, 9, 13],
+        ),
+        (
+            |x, y, z| x ^ y ^ z,
+            0x6ED9_EBA1,
+            [3, 7, 2, 6, 1, 5, 0, 4],
+            [3, 9, 11, 15],
+        ),
+    ];
+
+    let mut state = *buf;
+    for (round_fn, constant, indexes, shifts) in ROUNDS {
+        for (step, &idx) in indexes.iter().enumerate() {
+            // The steps update `a`, `d`, `c` and `b` in turn.
+            let target = (4 - step % 4) % 4;
+            let value = round_fn(
+                state[(target + 1) % 4],
+                state[(target + 2) % 4],
+                state[(target + 3) % 4],
+            );
+            state[target] = state[target]
+                .wrapping_add(value)
+                .wrapping_add(input[idx].wrapping_add(constant))
+                .rotate_left(shifts[step % 4]);
+        }
+    }
+    for (word, value) in buf.iter_mut().zip(state) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+/// The information of the index in the root.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawDxRootInfo {
+    reserved_zero: u32,
+    hash_version: u8,
+    /// The length of this structure.
+    info_length: u8,
+    /// The number of the levels of the index nodes below the root.
+    indirect_levels: u8,
+    unused_flags: u8,
+}
+
+/// The number of the index entries in a node and the max number of them, which are
+/// stored in place of the hash of the first entry.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawDxCountLimit {
+    limit: u16,
+    count: u16,
+}
+
+/// An index entry.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RawDxEntry {
+    hash: u32,
+    block: u32,
+}
@@ -12,9 +12,7 @@ use crate::{
 
 impl FileSystem for Ext2 {
     fn sync(&self) -> Result<()> {
-        self.sync_all_inodes()?;
-        self.sync_metadata()?;
-        Ok(())
+        self.sync_all()
     }
 
     fn root_inode(&self) -> Arc<dyn Inode> {
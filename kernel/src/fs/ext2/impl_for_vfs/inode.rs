@@ -11,7 +11,7 @@ use crate::{
         ext2::{FilePerm, Inode as Ext2Inode},
         utils::{
             DirentVisitor, Extension, FallocMode, FileSystem, Inode, InodeMode, InodeType,
-            IoctlCmd, Metadata, MknodType,
+            IoctlCmd, Metadata, MknodType, XattrName, XattrSetFlags,
         },
     },
     prelude::*,
@@ -25,7 +25,7 @@ impl Inode for Ext2Inode {
     }
 
     fn resize(&self, new_size: usize) -> Result<()> {
-        self.resize(new_size)
+        self.fs().run_op(|| self.resize(new_size))
     }
 
     fn metadata(&self) -> Metadata {
@@ -104,24 +104,26 @@ impl Inode for Ext2Inode {
     }
 
     fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
-        self.write_at(offset, reader)
+        self.fs().run_op(|| self.write_at(offset, reader))
     }
 
     fn write_direct_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
-        self.write_direct_at(offset, reader)
+        self.fs().run_op(|| self.write_direct_at(offset, reader))
     }
 
     fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
-        Ok(self.create(name, type_, mode.into())?)
+        Ok(self.fs().run_op(|| self.create(name, type_, mode.into()))?)
     }
 
     fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Arc<dyn Inode>> {
         let inode_type = type_.inode_type();
         let inode = match type_ {
             MknodType::CharDeviceNode(dev) | MknodType::BlockDeviceNode(dev) => {
-                let inode = self.create(name, inode_type, mode.into())?;
-                inode.set_device_id(dev.id().into()).unwrap();
-                inode
+                self.fs().run_op(|| {
+                    let inode = self.create(name, inode_type, mode.into())?;
+                    inode.set_device_id(dev.id().into()).unwrap();
+                    Ok(inode)
+                })?
             }
             _ => todo!(),
         };
@@ -141,22 +143,22 @@ impl Inode for Ext2Inode {
         let old = old
             .downcast_ref::<Ext2Inode>()
             .ok_or_else(|| Error::with_message(Errno::EXDEV, "not same fs"))?;
-        self.link(old, name)
+        self.fs().run_op(|| self.link(old, name))
     }
 
     fn unlink(&self, name: &str) -> Result<()> {
-        self.unlink(name)
+        self.fs().run_op(|| self.unlink(name))
     }
 
     fn rmdir(&self, name: &str) -> Result<()> {
-        self.rmdir(name)
+        self.fs().run_op(|| self.rmdir(name))
     }
 
     fn rename(&self, old_name: &str, target: &Arc<dyn Inode>, new_name: &str) -> Result<()> {
         let target = target
             .downcast_ref::<Ext2Inode>()
             .ok_or_else(|| Error::with_message(Errno::EXDEV, "not same fs"))?;
-        self.rename(old_name, target, new_name)
+        self.fs().run_op(|| self.rename(old_name, target, new_name))
     }
 
     fn read_link(&self) -> Result<String> {
@@ -164,25 +166,49 @@ impl Inode for Ext2Inode {
     }
 
     fn write_link(&self, target: &str) -> Result<()> {
-        self.write_link(target)
+        self.fs().run_op(|| self.write_link(target))
     }
 
     fn fallocate(&self, mode: FallocMode, offset: usize, len: usize) -> Result<()> {
-        self.fallocate(mode, offset, len)
+        self.fs().run_op(|| self.fallocate(mode, offset, len))
     }
 
     fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
         Err(Error::new(Errno::EINVAL))
     }
 
+    fn set_xattr(&self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        self.fs().run_op(|| self.set_xattr(name, value, flags))
+    }
+
+    fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>> {
+        self.get_xattr(name)
+    }
+
+    fn list_xattr(&self) -> Result<Vec<String>> {
+        self.list_xattr()
+    }
+
+    fn remove_xattr(&self, name: XattrName) -> Result<()> {
+        self.fs().run_op(|| self.remove_xattr(name))
+    }
+
     fn sync_all(&self) -> Result<()> {
-        self.sync_all()?;
+        // The metadata of the inode are kept by the journal until they are committed
+        // together with the other dirty metadata. The transaction may also hold the
+        // metadata of other inodes, so their data are written back before the commit.
+        if self.fs().has_journal() {
+            self.fs().sync_all()?;
+        } else {
+            self.sync_all()?;
+        }
         self.fs().block_device().sync()?;
         Ok(())
     }
 
     fn sync_data(&self) -> Result<()> {
-        self.sync_data()?;
+        // Writing back the data may allocate the blocks of the unwritten extents.
+        self.fs().run_op(|| self.sync_data())?;
         self.fs().block_device().sync()?;
         Ok(())
     }
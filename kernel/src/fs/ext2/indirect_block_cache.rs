@@ -42,9 +42,7 @@ impl IndirectBlockCache {
         let fs = self.fs();
         let load_block = || -> Result<IndirectBlock> {
             let mut block = IndirectBlock::alloc_uninit()?;
-            let bio_segment =
-                BioSegment::new_from_segment(block.frame.clone().into(), BioDirection::FromDevice);
-            fs.read_blocks(bid, bio_segment)?;
+            fs.read_meta_blocks(bid, &block.frame.clone().into())?;
             block.state = State::UpToDate;
             Ok(block)
         };
@@ -61,9 +59,7 @@ impl IndirectBlockCache {
         let fs = self.fs();
         let load_block = || -> Result<IndirectBlock> {
             let mut block = IndirectBlock::alloc_uninit()?;
-            let bio_segment =
-                BioSegment::new_from_segment(block.frame.clone().into(), BioDirection::FromDevice);
-            fs.read_blocks(bid, bio_segment)?;
+            fs.read_meta_blocks(bid, &block.frame.clone().into())?;
             block.state = State::UpToDate;
             Ok(block)
         };
@@ -108,11 +104,10 @@ impl IndirectBlockCache {
         for _ in 0..num {
             let (bid, block) = self.cache.pop_lru().unwrap();
             if block.is_dirty() {
-                let bio_segment = BioSegment::new_from_segment(
-                    block.frame.clone().into(),
-                    BioDirection::ToDevice,
+                bio_waiter.concat(
+                    self.fs()
+                        .write_meta_blocks_async(bid, &block.frame.clone().into())?,
                 );
-                bio_waiter.concat(self.fs().write_blocks_async(bid, bio_segment)?);
             }
         }
 
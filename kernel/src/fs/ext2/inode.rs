@@ -4,20 +4,29 @@
 #![allow(unused_variables)]
 
 use alloc::{borrow::ToOwned, rc::Rc};
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::{
+    mem::{offset_of, size_of},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use inherit_methods_macro::inherit_methods;
 
 use super::{
-    block_ptr::{BidPath, BlockPtrs, Ext2Bid, BID_SIZE, MAX_BLOCK_PTRS},
+    block_ptr::{
+        indirect_blocks_count, BidPath, BlockPtrs, Ext2Bid, BID_SIZE, DIRECT_RANGE, MAX_BLOCK_PTRS,
+    },
     dir::{DirEntry, DirEntryReader, DirEntryWriter},
+    extent::{BlockMapping, ExtentTree},
     fs::Ext2,
+    htree::HTree,
     indirect_block_cache::{IndirectBlock, IndirectBlockCache},
     prelude::*,
+    super_block::{FeatureCompatSet, FeatureInCompatSet, FeatureRoCompatSet, SuperBlock},
     utils::now,
+    xattr::{self, Xattrs},
 };
 use crate::{
-    fs::utils::{Extension, FallocMode, InodeMode, Metadata},
+    fs::utils::{Extension, FallocMode, InodeMode, Metadata, XattrName, XattrSetFlags},
     process::{Gid, Uid},
 };
 
@@ -27,6 +36,12 @@ pub const MAX_FNAME_LEN: usize = 255;
 /// Max path length of the fast symlink.
 pub const MAX_FAST_SYMLINK_LEN: usize = MAX_BLOCK_PTRS * BID_SIZE;
 
+/// Max number of hard links, which is the same as Ext4.
+///
+/// If the `DIR_NLINK` feature is set, a directory with more subdirectories has
+/// one hard link, which means that the number is not tracked.
+pub const MAX_HARD_LINKS: u16 = 65000;
+
 /// The Ext2 inode.
 pub struct Inode {
     ino: u32,
@@ -43,15 +58,32 @@ impl Inode {
         block_group_idx: usize,
         desc: Dirty<InodeDesc>,
         fs: Weak<Ext2>,
-    ) -> Arc<Self> {
-        Arc::new_cyclic(|weak_self| Self {
+    ) -> Result<Arc<Self>> {
+        let extent_tree = if desc.flags.contains(FileFlags::EXTENTS) {
+            let fs = fs.upgrade().unwrap();
+            let csum_seed = fs.inode_csum_seed(ino, desc.generation);
+            Some(ExtentTree::load(
+                &|bid, buf| fs.read_meta_block(bid, buf),
+                desc.block_ptrs.as_bytes(),
+                csum_seed,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Arc::new_cyclic(|weak_self| Self {
             ino,
             type_: desc.type_,
             block_group_idx,
-            inner: RwMutex::new(InodeInner::new(desc, weak_self.clone(), fs.clone())),
+            inner: RwMutex::new(InodeInner::new(
+                desc,
+                extent_tree,
+                weak_self.clone(),
+                fs.clone(),
+            )),
             fs,
             extension: Extension::new(),
-        })
+        }))
     }
 
     pub fn ino(&self) -> u32 {
@@ -132,6 +164,16 @@ impl Inode {
         if inner.get_entry(name).is_some() {
             return_errno!(Errno::EEXIST);
         }
+        if inode_type == InodeType::Dir
+            && inner.hard_links() >= MAX_HARD_LINKS
+            && !self
+                .fs()
+                .super_block()
+                .feature_ro_compat()
+                .contains(FeatureRoCompatSet::DIR_NLINK)
+        {
+            return_errno_with_message!(Errno::EMLINK, "too many subdirectories");
+        }
 
         let inode = self
             .fs()
@@ -203,6 +245,9 @@ impl Inode {
         if inode_type == InodeType::Dir {
             return_errno!(Errno::EPERM);
         }
+        if inode.hard_links() >= MAX_HARD_LINKS {
+            return_errno_with_message!(Errno::EMLINK, "too many hard links");
+        }
 
         if inner.get_entry(name).is_some() {
             return_errno!(Errno::EEXIST);
@@ -599,14 +644,14 @@ impl Inode {
 
             let try_readdir = |offset: &mut usize, visitor: &mut dyn DirentVisitor| -> Result<()> {
                 let dir_entry_reader = DirEntryReader::new(&inner.page_cache, *offset);
-                for (_, dir_entry) in dir_entry_reader {
+                for (entry_offset, dir_entry) in dir_entry_reader {
                     visitor.visit(
                         dir_entry.name(),
                         dir_entry.ino() as u64,
                         dir_entry.type_(),
                         dir_entry.record_len(),
                     )?;
-                    *offset += dir_entry.record_len();
+                    *offset = entry_offset + dir_entry.record_len();
                 }
 
                 Ok(())
@@ -772,6 +817,28 @@ impl Inode {
         &self.extension
     }
 
+    pub fn set_xattr(&self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        let mut inner = self.inner.write();
+        inner.set_xattr(name, value, flags)?;
+        inner.set_ctime(now());
+        Ok(())
+    }
+
+    pub fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>> {
+        self.inner.read().get_xattr(name)
+    }
+
+    pub fn list_xattr(&self) -> Result<Vec<String>> {
+        self.inner.read().list_xattr()
+    }
+
+    pub fn remove_xattr(&self, name: XattrName) -> Result<()> {
+        let mut inner = self.inner.write();
+        inner.remove_xattr(name)?;
+        inner.set_ctime(now());
+        Ok(())
+    }
+
     pub fn fallocate(&self, mode: FallocMode, offset: usize, len: usize) -> Result<()> {
         if self.inode_type() != InodeType::File {
             return_errno_with_message!(Errno::EISDIR, "not regular file");
@@ -821,7 +888,6 @@ impl Inode {
     pub fn file_flags(&self) -> FileFlags;
     pub fn hard_links(&self) -> u16;
     pub fn blocks_count(&self) -> Ext2Bid;
-    pub fn acl(&self) -> Option<Bid>;
     pub fn atime(&self) -> Duration;
     pub fn mtime(&self) -> Duration;
     pub fn ctime(&self) -> Duration;
@@ -899,9 +965,14 @@ struct InodeInner {
 }
 
 impl InodeInner {
-    pub fn new(desc: Dirty<InodeDesc>, weak_self: Weak<Inode>, fs: Weak<Ext2>) -> Self {
+    pub fn new(
+        desc: Dirty<InodeDesc>,
+        extent_tree: Option<ExtentTree>,
+        weak_self: Weak<Inode>,
+        fs: Weak<Ext2>,
+    ) -> Self {
         let num_page_bytes = desc.num_page_bytes();
-        let inode_impl = InodeImpl::new(desc, weak_self, fs);
+        let inode_impl = InodeImpl::new(desc, extent_tree, weak_self, fs);
         Self {
             page_cache: PageCache::with_capacity(
                 num_page_bytes,
@@ -991,7 +1062,7 @@ impl InodeInner {
     }
 
     pub fn write_link(&mut self, target: &str) -> Result<()> {
-        if target.len() <= MAX_FAST_SYMLINK_LEN {
+        if target.len() <= MAX_FAST_SYMLINK_LEN && !self.inode_impl.uses_extents() {
             return self.inode_impl.write_link(target);
         }
 
@@ -1006,7 +1077,7 @@ impl InodeInner {
 
     pub fn read_link(&self) -> Result<String> {
         let file_size = self.inode_impl.file_size();
-        if file_size <= MAX_FAST_SYMLINK_LEN {
+        if file_size <= MAX_FAST_SYMLINK_LEN && !self.inode_impl.uses_extents() {
             return self.inode_impl.read_link();
         }
 
@@ -1021,6 +1092,7 @@ impl InodeInner {
     fn init_dir(&mut self, self_ino: u32, parent_ino: u32) -> Result<()> {
         self.append_entry(DirEntry::self_entry(self_ino))?;
         self.append_entry(DirEntry::parent_entry(parent_ino))?;
+        self.inc_hard_links(); // for "."
         Ok(())
     }
 
@@ -1029,6 +1101,13 @@ impl InodeInner {
     }
 
     pub fn get_entry(&self, name: &str) -> Option<(usize, DirEntry)> {
+        if name != "." && name != ".." {
+            if let Some(htree) = self.htree() {
+                if let Ok(found) = htree.lookup(name) {
+                    return found;
+                }
+            }
+        }
         DirEntryReader::new(&self.page_cache, 0).find(|(offset, entry)| entry.name() == name)
     }
 
@@ -1037,23 +1116,61 @@ impl InodeInner {
     }
 
     pub fn append_entry(&mut self, entry: DirEntry) -> Result<()> {
-        let is_dir = entry.type_() == InodeType::Dir;
-        let is_parent = entry.name() == "..";
+        let is_subdir =
+            entry.type_() == InodeType::Dir && entry.name() != "." && entry.name() != "..";
 
-        DirEntryWriter::new(&self.page_cache, 0).append_entry(entry)?;
+        self.insert_entry(entry)?;
         let file_size = self.inode_impl.file_size();
         let page_cache_size = self.page_cache.pages().size();
         if page_cache_size > file_size {
             self.inode_impl.resize(page_cache_size)?;
         }
-        if is_dir && !is_parent {
-            self.inc_hard_links(); // for ".."
+        if is_subdir {
+            self.inode_impl.inc_dir_links(); // for ".."
         }
         Ok(())
     }
 
+    /// Inserts the entry into the hash index if the directory is indexed, or appends it
+    /// linearly otherwise.
+    ///
+    /// A linear directory is indexed once its first block is full.
+    fn insert_entry(&mut self, entry: DirEntry) -> Result<()> {
+        if !self.inode_impl.file_flags().contains(FileFlags::INDEX_DIR) {
+            let can_create_index = self.page_cache.pages().size() == BLOCK_SIZE
+                && self.inode_impl.fs().super_block().has_dir_index();
+            let mut writer = self.dir_entry_writer(0)?;
+            if !can_create_index {
+                return writer.append_entry(entry);
+            }
+            if writer.insert_in_block(0, &entry)? {
+                return Ok(());
+            }
+
+            // The directory remains a valid linear one if the index is not fully created.
+            let created = HTree::create(
+                &self.page_cache,
+                &self.inode_impl.fs().super_block(),
+                self.inode_impl.csum_seed(),
+            );
+            if created.is_err() {
+                return self.dir_entry_writer(0)?.append_entry(entry);
+            }
+            self.inode_impl.add_file_flags(FileFlags::INDEX_DIR);
+        }
+
+        if let Some(mut htree) = self.htree() {
+            if htree.insert(&entry)? {
+                return Ok(());
+            }
+        }
+        // The index is full or unusable, so the directory falls back to a linear one.
+        self.drop_index()?;
+        self.dir_entry_writer(0)?.append_entry(entry)
+    }
+
     pub fn remove_entry_at(&mut self, name: &str, offset: usize) -> Result<()> {
-        let entry = DirEntryWriter::new(&self.page_cache, offset).remove_entry(name)?;
+        let entry = self.dir_entry_writer(offset)?.remove_entry(name)?;
         let is_dir = entry.type_() == InodeType::Dir;
         let file_size = self.inode_impl.file_size();
         let page_cache_size = self.page_cache.pages().size();
@@ -1061,13 +1178,20 @@ impl InodeInner {
             self.inode_impl.resize(page_cache_size)?;
         }
         if is_dir {
-            self.dec_hard_links(); // for ".."
+            self.inode_impl.dec_dir_links(); // for ".."
         }
         Ok(())
     }
 
     pub fn rename_entry_at(&mut self, old_name: &str, new_name: &str, offset: usize) -> Result<()> {
-        DirEntryWriter::new(&self.page_cache, offset).rename_entry(old_name, new_name)?;
+        if self.htree().is_some() {
+            // The new name belongs to another leaf block in general.
+            let entry = self.dir_entry_writer(offset)?.remove_entry(old_name)?;
+            self.insert_entry(DirEntry::new(entry.ino(), new_name, entry.type_()))?;
+        } else {
+            self.dir_entry_writer(offset)?
+                .rename_entry(old_name, new_name)?;
+        }
         let file_size = self.inode_impl.file_size();
         let page_cache_size = self.page_cache.pages().size();
         if page_cache_size != file_size {
@@ -1079,7 +1203,49 @@ impl InodeInner {
     pub fn set_parent_ino(&mut self, parent_ino: u32) -> Result<()> {
         let (offset, mut entry) = self.get_entry("..").unwrap();
         entry.set_ino(parent_ino);
-        DirEntryWriter::new(&self.page_cache, offset).write_entry(&entry)?;
+        self.dir_entry_writer(offset)?.write_entry(&entry)?;
+        // The checksum of the index root covers the ".." entry.
+        if let Some(htree) = self.htree() {
+            htree.update_root_checksum()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the hash index of the directory, or `None` if the directory is not indexed
+    /// or the index is not usable.
+    fn htree(&self) -> Option<HTree<'_>> {
+        if !self.inode_impl.file_flags().contains(FileFlags::INDEX_DIR) {
+            return None;
+        }
+        HTree::load(
+            &self.page_cache,
+            &self.inode_impl.fs().super_block(),
+            self.inode_impl.csum_seed(),
+        )
+        .ok()
+    }
+
+    fn dir_entry_writer(&mut self, offset: usize) -> Result<DirEntryWriter> {
+        let is_indexed = self.htree().is_some();
+        if !is_indexed {
+            // The hash index cannot be maintained, so it is dropped before any modification.
+            self.drop_index()?;
+        }
+        Ok(DirEntryWriter::new(
+            &self.page_cache,
+            offset,
+            self.inode_impl.csum_seed(),
+            is_indexed,
+        ))
+    }
+
+    /// Converts the directory to a linear one if it is indexed.
+    fn drop_index(&mut self) -> Result<()> {
+        if self.inode_impl.file_flags().contains(FileFlags::INDEX_DIR) {
+            DirEntryWriter::new(&self.page_cache, 0, self.inode_impl.csum_seed(), false)
+                .drop_index()?;
+            self.inode_impl.remove_file_flags(FileFlags::INDEX_DIR);
+        }
         Ok(())
     }
 
@@ -1106,7 +1272,6 @@ impl InodeInner {
     pub fn inc_hard_links(&mut self);
     pub fn dec_hard_links(&mut self);
     pub fn blocks_count(&self) -> Ext2Bid;
-    pub fn acl(&self) -> Option<Bid>;
     pub fn atime(&self) -> Duration;
     pub fn set_atime(&mut self, time: Duration);
     pub fn mtime(&self) -> Duration;
@@ -1115,6 +1280,10 @@ impl InodeInner {
     pub fn set_ctime(&mut self, time: Duration);
     pub fn device_id(&self) -> u64;
     pub fn set_device_id(&mut self, device_id: u64);
+    pub fn set_xattr(&mut self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()>;
+    pub fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>>;
+    pub fn list_xattr(&self) -> Result<Vec<String>>;
+    pub fn remove_xattr(&mut self, name: XattrName) -> Result<()>;
     pub fn sync_metadata(&mut self) -> Result<()>;
 }
 
@@ -1127,11 +1296,18 @@ struct InodeImpl {
 }
 
 impl InodeImpl {
-    pub fn new(desc: Dirty<InodeDesc>, weak_self: Weak<Inode>, fs: Weak<Ext2>) -> Self {
+    pub fn new(
+        desc: Dirty<InodeDesc>,
+        extent_tree: Option<ExtentTree>,
+        weak_self: Weak<Inode>,
+        fs: Weak<Ext2>,
+    ) -> Self {
         let block_manager = InodeBlockManager {
             nblocks: AtomicUsize::new(desc.blocks_count() as _),
             block_ptrs: RwMutex::new(desc.block_ptrs),
             indirect_blocks: RwMutex::new(IndirectBlockCache::new(fs.clone())),
+            extent_tree: extent_tree.map(RwMutex::new),
+            is_metadata: matches!(desc.type_, InodeType::Dir | InodeType::SymLink),
             fs,
         };
         Self {
@@ -1187,6 +1363,25 @@ impl InodeImpl {
         self.desc.flags
     }
 
+    pub fn add_file_flags(&mut self, flags: FileFlags) {
+        self.desc.flags.insert(flags);
+    }
+
+    pub fn remove_file_flags(&mut self, flags: FileFlags) {
+        self.desc.flags.remove(flags);
+    }
+
+    /// Returns whether the blocks are mapped by an extent tree.
+    pub fn uses_extents(&self) -> bool {
+        self.block_manager.extent_tree.is_some()
+    }
+
+    /// Returns the seed of the checksums of the metadata that belong to this inode.
+    pub fn csum_seed(&self) -> Option<u32> {
+        self.fs()
+            .inode_csum_seed(self.inode().ino(), self.desc.generation)
+    }
+
     pub fn hard_links(&self) -> u16 {
         self.desc.hard_links
     }
@@ -1200,12 +1395,29 @@ impl InodeImpl {
         self.desc.hard_links -= 1;
     }
 
-    pub fn blocks_count(&self) -> Ext2Bid {
-        self.desc.blocks_count()
+    /// Increases the hard links of the directory for a new subdirectory.
+    ///
+    /// One hard link means that the number is not tracked, see [`MAX_HARD_LINKS`].
+    pub fn inc_dir_links(&mut self) {
+        if self.desc.hard_links == 1 {
+            return;
+        }
+        if self.desc.hard_links >= MAX_HARD_LINKS {
+            self.desc.hard_links = 1;
+            return;
+        }
+        self.desc.hard_links += 1;
     }
 
-    pub fn acl(&self) -> Option<Bid> {
-        self.desc.acl
+    /// Decreases the hard links of the directory for a removed subdirectory.
+    pub fn dec_dir_links(&mut self) {
+        if self.desc.hard_links > 2 {
+            self.desc.hard_links -= 1;
+        }
+    }
+
+    pub fn blocks_count(&self) -> Ext2Bid {
+        self.desc.blocks_count()
     }
 
     pub fn atime(&self) -> Duration {
@@ -1264,7 +1476,12 @@ impl InodeImpl {
     }
 
     pub fn sync_metadata(&mut self) -> Result<()> {
-        if !self.desc.is_dirty() {
+        let is_extent_tree_dirty = self
+            .block_manager
+            .extent_tree
+            .as_ref()
+            .is_some_and(|extent_tree| extent_tree.read().is_dirty());
+        if !self.desc.is_dirty() && !is_extent_tree_dirty {
             return Ok(());
         }
 
@@ -1273,6 +1490,10 @@ impl InodeImpl {
             self.resize(0)?;
             // Adds the check here to prevent double-free.
             if !self.is_freed {
+                if self.desc.xattr_bid != 0 {
+                    xattr::release_block(&inode.fs(), self.desc.xattr_bid)?;
+                    self.desc.xattr_bid = 0;
+                }
                 inode
                     .fs()
                     .free_inode(inode.ino(), self.desc.type_ == InodeType::Dir)?;
@@ -1280,11 +1501,96 @@ impl InodeImpl {
             }
         }
 
+        if let Some(extent_tree) = self.block_manager.extent_tree.as_ref() {
+            let mut extent_tree = extent_tree.write();
+            if extent_tree.is_dirty() {
+                extent_tree.store(
+                    &inode.fs(),
+                    self.desc.block_ptrs.as_bytes_mut(),
+                    inode.block_group_idx(),
+                )?;
+            }
+        }
+        self.desc.occupied_blocks = self.occupied_blocks();
+
         self.block_manager.indirect_blocks.write().evict_all()?;
         inode.fs().sync_inode(inode.ino(), &self.desc)?;
         self.desc.clear_dirty();
         Ok(())
     }
+
+    /// Returns the number of blocks occupied by this inode, including the data blocks
+    /// and the metadata blocks.
+    fn occupied_blocks(&self) -> u64 {
+        let blocks = match self.block_manager.extent_tree.as_ref() {
+            Some(extent_tree) => extent_tree.read().occupied_blocks(),
+            None => {
+                let data_blocks = self.desc.blocks_count();
+                (data_blocks + indirect_blocks_count(data_blocks)) as u64
+            }
+        };
+        let xattr_blocks = (self.desc.xattr_bid != 0) as u64;
+        blocks + xattr_blocks
+    }
+}
+
+// Implementation for extended attributes.
+impl InodeImpl {
+    pub fn set_xattr(&mut self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        let mut xattrs = self.load_xattrs()?;
+        xattrs.set(name, value, flags)?;
+        self.store_xattrs(xattrs)
+    }
+
+    pub fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>> {
+        let xattrs = self.load_xattrs()?;
+        xattrs
+            .get(name)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| Error::with_message(Errno::ENODATA, "the xattr does not exist"))
+    }
+
+    pub fn list_xattr(&self) -> Result<Vec<String>> {
+        Ok(self.load_xattrs()?.list())
+    }
+
+    pub fn remove_xattr(&mut self, name: XattrName) -> Result<()> {
+        let mut xattrs = self.load_xattrs()?;
+        xattrs.remove(name)?;
+        self.store_xattrs(xattrs)
+    }
+
+    fn load_xattrs(&self) -> Result<Xattrs> {
+        let fs = self.fs();
+        if !fs
+            .super_block()
+            .feature_compat()
+            .contains(FeatureCompatSet::EXT_ATTR)
+        {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "xattrs are not enabled");
+        }
+        Xattrs::load(
+            &fs,
+            self.inode().ino(),
+            self.desc.extra_isize,
+            self.desc.xattr_bid,
+        )
+    }
+
+    fn store_xattrs(&mut self, mut xattrs: Xattrs) -> Result<()> {
+        let inode = self.inode();
+        let xattr_bid = xattrs.store(&inode.fs(), inode.block_group_idx())?;
+        if xattr_bid != self.desc.xattr_bid {
+            self.desc.xattr_bid = xattr_bid;
+        }
+        // The extra inode fields may be reserved for the in-inode attributes.
+        if self.desc.extra_isize == 0 {
+            if let Some(extra_isize) = xattrs.extra_isize() {
+                self.desc.extra_isize = extra_isize as u16;
+            }
+        }
+        Ok(())
+    }
 }
 
 // Heavy implementation for inode resizing.
@@ -1312,7 +1618,11 @@ impl InodeImpl {
             if new_blocks - old_blocks > self.fs().super_block().free_blocks_count() {
                 return_errno_with_message!(Errno::ENOSPC, "not enough free blocks");
             }
-            self.expand_blocks(old_blocks..new_blocks)?;
+            if self.uses_extents() {
+                self.expand_extent_blocks(old_blocks..new_blocks)?;
+            } else {
+                self.expand_blocks(old_blocks..new_blocks)?;
+            }
         }
 
         // Expands the size
@@ -1565,7 +1875,10 @@ impl InodeImpl {
         let old_blocks = self.desc.blocks_count();
 
         // Shrinks block count if necessary
-        if new_blocks < old_blocks {
+        if self.uses_extents() {
+            // The blocks beyond the size may also be mapped by the extent tree.
+            self.shrink_extent_blocks(new_blocks);
+        } else if new_blocks < old_blocks {
             self.shrink_blocks(new_blocks..old_blocks);
         }
 
@@ -1621,6 +1934,10 @@ impl InodeImpl {
         let device_range_reader =
             DeviceRangeReader::new(&self.block_manager, range.clone()).unwrap();
         for device_range in device_range_reader {
+            // Skips the holes.
+            if device_range.start == 0 {
+                continue;
+            }
             fs.free_blocks(device_range.clone()).unwrap();
         }
 
@@ -1650,8 +1967,8 @@ impl InodeImpl {
                     return Ok(());
                 }
 
-                self.desc.block_ptrs.set_indirect(bid);
-                block_ptrs.set_indirect(bid);
+                self.desc.block_ptrs.set_indirect(0);
+                block_ptrs.set_indirect(0);
                 indirect_blocks.remove(indirect_bid);
                 self.fs()
                     .free_blocks(indirect_bid..indirect_bid + 1)
@@ -1724,6 +2041,72 @@ impl InodeImpl {
     }
 }
 
+// Implementation for the inodes whose blocks are mapped by extent trees.
+impl InodeImpl {
+    /// Expands the blocks mapped by the extent tree.
+    ///
+    /// After a successful expansion, the block count will be enlarged to `range.end`.
+    /// The new blocks of regular files are mapped as unwritten, so they are read as zeros
+    /// until they are written.
+    fn expand_extent_blocks(&mut self, range: Range<Ext2Bid>) -> Result<()> {
+        let fs = self.fs();
+        let inode_block_group_idx = self.inode().block_group_idx;
+        let is_unwritten = self.desc.type_ == InodeType::File;
+
+        let mut extent_tree = self.block_manager.extent_tree.as_ref().unwrap().write();
+        let mut bid = range.start;
+        while bid < range.end {
+            let hole_len = match extent_tree.map(bid..range.end) {
+                BlockMapping::Hole(hole_len) => hole_len,
+                // The blocks may have been preallocated beyond the size.
+                mapping => {
+                    bid += mapping.len();
+                    continue;
+                }
+            };
+
+            // Allocates the blocks next to the previous ones if possible.
+            let block_group_idx = extent_tree
+                .prev_device_bid(bid)
+                .map_or(inode_block_group_idx, |device_bid| {
+                    ((device_bid + 1) / fs.blocks_per_group()) as usize
+                });
+            let Some(device_range) = fs.alloc_blocks(block_group_idx, hole_len) else {
+                for device_range in extent_tree.truncate(range.start) {
+                    fs.free_blocks(device_range).unwrap();
+                }
+                return_errno_with_message!(Errno::ENOSPC, "can not allocate blocks");
+            };
+            let len = device_range.len() as Ext2Bid;
+            extent_tree.insert(bid, device_range, is_unwritten);
+            bid += len;
+        }
+        drop(extent_tree);
+
+        self.desc.blocks_count = range.end;
+        Ok(())
+    }
+
+    /// Shrinks the blocks mapped by the extent tree.
+    ///
+    /// After the reduction, the blocks from `bid` will be unmapped.
+    fn shrink_extent_blocks(&mut self, bid: Ext2Bid) {
+        let fs = self.fs();
+        let freed_ranges = self
+            .block_manager
+            .extent_tree
+            .as_ref()
+            .unwrap()
+            .write()
+            .truncate(bid);
+        for device_range in freed_ranges {
+            fs.free_blocks(device_range).unwrap();
+        }
+
+        self.desc.blocks_count = self.desc.blocks_count.min(bid);
+    }
+}
+
 #[inherit_methods(from = "self.block_manager")]
 impl InodeImpl {
     pub fn read_blocks_async(
@@ -1754,6 +2137,12 @@ struct InodeBlockManager {
     /// frequent reads access the `InodeDesc` copy without locking.
     block_ptrs: RwMutex<BlockPtrs>,
     indirect_blocks: RwMutex<IndirectBlockCache>,
+    /// The extent tree that maps the blocks instead of the block pointers, if the inode
+    /// has the `EXTENTS` flag.
+    extent_tree: Option<RwMutex<ExtentTree>>,
+    /// Whether the blocks are metadata, i.e., the blocks of a directory or a symlink, which
+    /// are written through the journal.
+    is_metadata: bool,
     fs: Weak<Ext2>,
 }
 
@@ -1768,9 +2157,14 @@ impl InodeBlockManager {
         debug_assert!(nblocks * BLOCK_SIZE <= writer.avail());
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
+        for dev_range in self.device_ranges_to_read(bid..bid + nblocks as Ext2Bid)? {
             let start_bid = dev_range.start as Ext2Bid;
             let range_nblocks = dev_range.len();
+            // The holes are read as zeros.
+            if start_bid == 0 {
+                writer.fill_zeros(range_nblocks * BLOCK_SIZE)?;
+                continue;
+            }
 
             let bio_segment = BioSegment::alloc(range_nblocks, BioDirection::FromDevice);
             bio_segment.reader().unwrap().read_fallible(writer)?;
@@ -1792,11 +2186,21 @@ impl InodeBlockManager {
     pub fn read_block_async(&self, bid: Ext2Bid, frame: &Frame) -> Result<BioWaiter> {
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)? {
+        for dev_range in self.device_ranges_to_read(bid..bid + 1 as Ext2Bid)? {
             let start_bid = dev_range.start as Ext2Bid;
-            let bio_segment =
-                BioSegment::new_from_segment(frame.clone().into(), BioDirection::FromDevice);
-            let waiter = self.fs().read_blocks_async(start_bid, bio_segment)?;
+            // The hole is read as zeros.
+            if start_bid == 0 {
+                frame.writer().fill(0u8);
+                continue;
+            }
+            let waiter = if self.is_metadata {
+                self.fs()
+                    .read_meta_blocks_async(start_bid, &frame.clone().into())?
+            } else {
+                let bio_segment =
+                    BioSegment::new_from_segment(frame.clone().into(), BioDirection::FromDevice);
+                self.fs().read_blocks_async(start_bid, bio_segment)?
+            };
             bio_waiter.concat(waiter);
         }
 
@@ -1813,7 +2217,7 @@ impl InodeBlockManager {
         debug_assert_eq!(nblocks * BLOCK_SIZE, reader.remain());
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + nblocks as Ext2Bid)? {
+        for dev_range in self.device_ranges_to_write(bid..bid + nblocks as Ext2Bid)? {
             let start_bid = dev_range.start as Ext2Bid;
             let range_nblocks = dev_range.len();
 
@@ -1837,17 +2241,95 @@ impl InodeBlockManager {
     pub fn write_block_async(&self, bid: Ext2Bid, frame: &Frame) -> Result<BioWaiter> {
         let mut bio_waiter = BioWaiter::new();
 
-        for dev_range in DeviceRangeReader::new(self, bid..bid + 1 as Ext2Bid)? {
+        for dev_range in self.device_ranges_to_write(bid..bid + 1 as Ext2Bid)? {
             let start_bid = dev_range.start as Ext2Bid;
-            let bio_segment =
-                BioSegment::new_from_segment(frame.clone().into(), BioDirection::ToDevice);
-            let waiter = self.fs().write_blocks_async(start_bid, bio_segment)?;
+            let waiter = if self.is_metadata {
+                self.fs()
+                    .write_meta_blocks_async(start_bid, &frame.clone().into())?
+            } else {
+                let bio_segment =
+                    BioSegment::new_from_segment(frame.clone().into(), BioDirection::ToDevice);
+                self.fs().write_blocks_async(start_bid, bio_segment)?
+            };
             bio_waiter.concat(waiter);
         }
 
         Ok(bio_waiter)
     }
 
+    /// Returns the device ranges to read the file blocks in `range`.
+    ///
+    /// The holes, and the unwritten blocks which should be read as zeros, are returned
+    /// as the device ranges starting from zero.
+    fn device_ranges_to_read(&self, range: Range<Ext2Bid>) -> Result<Vec<Range<Ext2Bid>>> {
+        let Some(extent_tree) = self.extent_tree.as_ref() else {
+            return Ok(DeviceRangeReader::new(self, range)?.collect());
+        };
+
+        let extent_tree = extent_tree.read();
+        let mut device_ranges = Vec::new();
+        let mut range = range;
+        while !range.is_empty() {
+            let mapping = extent_tree.map(range.clone());
+            range.start += mapping.len();
+            match mapping {
+                BlockMapping::Mapped(device_range) => device_ranges.push(device_range),
+                BlockMapping::Unwritten(device_range) => {
+                    device_ranges.push(0..device_range.len() as Ext2Bid)
+                }
+                BlockMapping::Hole(len) => device_ranges.push(0..len),
+            }
+        }
+        Ok(device_ranges)
+    }
+
+    /// Returns the device ranges to write the file blocks in `range`.
+    ///
+    /// The unwritten blocks are marked as written, and the holes are allocated if the
+    /// blocks are mapped by the extent tree.
+    fn device_ranges_to_write(&self, range: Range<Ext2Bid>) -> Result<Vec<Range<Ext2Bid>>> {
+        let Some(extent_tree) = self.extent_tree.as_ref() else {
+            let device_ranges: Vec<_> = DeviceRangeReader::new(self, range)?.collect();
+            if device_ranges
+                .iter()
+                .any(|device_range| device_range.start == 0)
+            {
+                return_errno_with_message!(Errno::EIO, "cannot write to the holes");
+            }
+            return Ok(device_ranges);
+        };
+
+        let fs = self.fs();
+        let mut extent_tree = extent_tree.write();
+        let mut device_ranges = Vec::new();
+        let mut range = range;
+        while !range.is_empty() {
+            let device_range = match extent_tree.map(range.clone()) {
+                BlockMapping::Mapped(device_range) => device_range,
+                BlockMapping::Unwritten(device_range) => {
+                    extent_tree
+                        .mark_written(range.start..range.start + device_range.len() as Ext2Bid);
+                    device_range
+                }
+                BlockMapping::Hole(len) => {
+                    let block_group_idx = extent_tree
+                        .prev_device_bid(range.start)
+                        .map_or(0, |device_bid| {
+                            ((device_bid + 1) / fs.blocks_per_group()) as usize
+                        });
+                    let device_range = fs
+                        .alloc_blocks(block_group_idx, len)
+                        .ok_or_else(|| Error::with_message(Errno::ENOSPC, "no space on device"))?;
+                    extent_tree.insert(range.start, device_range.clone(), false);
+                    device_range
+                }
+            };
+            range.start += device_range.len() as Ext2Bid;
+            device_ranges.push(device_range);
+        }
+        Ok(device_ranges)
+    }
+
     pub fn nblocks(&self) -> usize {
         self.nblocks.load(Ordering::Acquire)
     }
@@ -1925,7 +2407,13 @@ impl<'a> DeviceRangeReader<'a> {
             };
             match device_range {
                 Some(ref mut range) => {
-                    if device_bid == range.end {
+                    // A hole is represented by a device range starting from zero.
+                    let is_consecutive = if range.start == 0 {
+                        device_bid == 0
+                    } else {
+                        device_bid == range.end
+                    };
+                    if is_consecutive {
                         range.end += 1;
                     } else {
                         break;
@@ -2031,69 +2519,179 @@ pub(super) struct InodeDesc {
     hard_links: u16,
     /// Number of blocks.
     blocks_count: Ext2Bid,
+    /// Number of blocks occupied on the device, including the metadata blocks.
+    occupied_blocks: u64,
     /// File flags.
     flags: FileFlags,
-    /// Pointers to blocks.
+    /// Pointers to blocks, or the root node of the extent tree.
     block_ptrs: BlockPtrs,
-    /// File or directory acl block.
-    acl: Option<Bid>,
+    /// The block that stores the extended attributes, or zero if there is none.
+    xattr_bid: Ext2Bid,
+    /// File version, which also seeds the checksums.
+    generation: u32,
+    /// Size of the extra inode fields in use.
+    extra_isize: u16,
 }
 
-impl TryFrom<RawInode> for InodeDesc {
-    type Error = crate::error::Error;
+impl InodeDesc {
+    pub fn new(type_: InodeType, perm: FilePerm, super_block: &SuperBlock) -> Dirty<Self> {
+        let now = now();
+        let extra_isize =
+            if super_block.inode_size() >= size_of::<RawInode>() + size_of::<RawInodeExtra>() {
+                size_of::<RawInodeExtra>() as u16
+            } else {
+                0
+            };
+        let mut flags = FileFlags::empty();
+        let mut block_ptrs = BlockPtrs::default();
+        if super_block
+            .feature_incompat()
+            .contains(FeatureInCompatSet::EXTENTS)
+            && matches!(type_, InodeType::File | InodeType::Dir)
+        {
+            flags.insert(FileFlags::EXTENTS);
+            ExtentTree::init_root(block_ptrs.as_bytes_mut());
+        }
+
+        Dirty::new_dirty(Self {
+            type_,
+            perm,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            atime: now,
+            ctime: now,
+            mtime: now,
+            dtime: Duration::ZERO,
+            hard_links: 1,
+            blocks_count: 0,
+            occupied_blocks: 0,
+            flags,
+            block_ptrs,
+            xattr_bid: 0,
+            generation: 0,
+            extra_isize,
+        })
+    }
+
+    /// Parses the raw inode record in the inode table.
+    pub fn from_raw_bytes(raw_inode: &[u8]) -> Result<Self> {
+        let inode = RawInode::from_bytes(raw_inode);
+        let extra_len = raw_inode.len() - size_of::<RawInode>();
+        let extra_isize = if extra_len >= size_of::<u16>() {
+            u16::from_le_bytes([raw_inode[128], raw_inode[129]])
+        } else {
+            0
+        };
+        if extra_isize % 4 != 0 || extra_isize as usize > extra_len {
+            return_errno_with_message!(Errno::EUCLEAN, "the extra inode size is invalid");
+        }
+        let times_extra = RawTimesExtra::from_bytes(raw_inode, extra_isize);
 
-    fn try_from(inode: RawInode) -> Result<Self> {
         let inode_type = InodeType::from_raw_mode(inode.mode)?;
-        Ok(Self {
+        let flags = FileFlags::from_bits(inode.flags)
+            .ok_or(Error::with_message(Errno::EINVAL, "invalid file flags"))?;
+        if flags.contains(FileFlags::INLINE_DATA) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the inline data is not supported");
+        }
+        let occupied_blocks = {
+            let blocks =
+                (inode.os_dependent_2.blocks_high as u64) << 32 | inode.blocks_count as u64;
+            if flags.contains(FileFlags::HUGE_FILE) {
+                blocks
+            } else {
+                blocks / (BLOCK_SIZE / SECTOR_SIZE) as u64
+            }
+        };
+        let mut desc = Self {
             type_: inode_type,
             perm: FilePerm::from_raw_mode(inode.mode)?,
             uid: (inode.os_dependent_2.uid_high as u32) << 16 | inode.uid as u32,
             gid: (inode.os_dependent_2.gid_high as u32) << 16 | inode.gid as u32,
-            size: if inode_type == InodeType::File {
+            size: if matches!(inode_type, InodeType::File | InodeType::Dir) {
                 (inode.size_high as usize) << 32 | inode.size_low as usize
             } else {
                 inode.size_low as usize
             },
-            atime: Duration::from(inode.atime),
-            ctime: Duration::from(inode.ctime),
-            mtime: Duration::from(inode.mtime),
+            atime: decode_time(inode.atime, times_extra.atime_extra),
+            ctime: decode_time(inode.ctime, times_extra.ctime_extra),
+            mtime: decode_time(inode.mtime, times_extra.mtime_extra),
             dtime: Duration::from(inode.dtime),
             hard_links: inode.hard_links,
-            blocks_count: inode.blocks_count,
-            flags: FileFlags::from_bits(inode.flags)
-                .ok_or(Error::with_message(Errno::EINVAL, "invalid file flags"))?,
+            blocks_count: 0,
+            occupied_blocks,
+            flags,
             block_ptrs: inode.block_ptrs,
-            acl: match inode_type {
-                InodeType::File => Some(Bid::new(inode.file_acl as _)),
-                InodeType::Dir => Some(Bid::new(inode.size_high as _)),
-                _ => None,
-            },
-        })
+            xattr_bid: inode.file_acl,
+            generation: inode.generation,
+            extra_isize,
+        };
+        desc.blocks_count = desc.size_to_blocks(desc.size);
+        Ok(desc)
     }
-}
 
-impl InodeDesc {
-    pub fn new(type_: InodeType, perm: FilePerm) -> Dirty<Self> {
-        let now = now();
-        Dirty::new_dirty(Self {
-            type_,
-            perm,
-            uid: 0,
-            gid: 0,
-            size: 0,
-            atime: now,
-            ctime: now,
-            mtime: now,
-            dtime: Duration::ZERO,
-            hard_links: 1,
-            blocks_count: 0,
-            flags: FileFlags::empty(),
-            block_ptrs: BlockPtrs::default(),
-            acl: match type_ {
-                InodeType::File | InodeType::Dir => Some(Bid::new(0)),
-                _ => None,
+    /// Writes the inode to the raw inode record in the inode table.
+    ///
+    /// The fields that are not maintained in memory are kept as is.
+    pub fn to_raw_bytes(&self, raw_inode: &mut [u8]) {
+        let old_inode = RawInode::from_bytes(raw_inode);
+        // The number of blocks is in the unit of sectors unless it overflows 48 bits.
+        let (blocks, flags) = {
+            let sectors = self.occupied_blocks * (BLOCK_SIZE / SECTOR_SIZE) as u64;
+            if sectors >> 48 == 0 {
+                (sectors, self.flags - FileFlags::HUGE_FILE)
+            } else {
+                (self.occupied_blocks, self.flags | FileFlags::HUGE_FILE)
+            }
+        };
+        let (atime, atime_extra) = encode_time(self.atime);
+        let (ctime, ctime_extra) = encode_time(self.ctime);
+        let (mtime, mtime_extra) = encode_time(self.mtime);
+
+        let inode = RawInode {
+            mode: self.type_ as u16 | self.perm.bits(),
+            uid: self.uid as u16,
+            size_low: self.size as u32,
+            atime,
+            ctime,
+            mtime,
+            dtime: UnixTime::from(self.dtime),
+            gid: self.gid as u16,
+            hard_links: self.hard_links,
+            blocks_count: blocks as u32,
+            flags: flags.bits(),
+            block_ptrs: self.block_ptrs,
+            generation: self.generation,
+            file_acl: self.xattr_bid,
+            size_high: match self.type_ {
+                InodeType::File | InodeType::Dir => (self.size >> 32) as u32,
+                _ => Default::default(),
             },
-        })
+            os_dependent_2: Osd2 {
+                blocks_high: (blocks >> 32) as u16,
+                uid_high: (self.uid >> 16) as u16,
+                gid_high: (self.gid >> 16) as u16,
+                ..old_inode.os_dependent_2
+            },
+            ..old_inode
+        };
+        raw_inode[..size_of::<RawInode>()].copy_from_slice(inode.as_bytes());
+
+        if self.extra_isize == 0 {
+            return;
+        }
+        let extra = &mut raw_inode[size_of::<RawInode>()..];
+        extra[..2].copy_from_slice(&self.extra_isize.to_le_bytes());
+        let times_extra = [
+            (offset_of!(RawInodeExtra, ctime_extra), ctime_extra),
+            (offset_of!(RawInodeExtra, mtime_extra), mtime_extra),
+            (offset_of!(RawInodeExtra, atime_extra), atime_extra),
+        ];
+        for (offset, time_extra) in times_extra {
+            if offset + size_of::<u32>() <= self.extra_isize as usize {
+                extra[offset..offset + 4].copy_from_slice(&time_extra.to_le_bytes());
+            }
+        }
     }
 
     pub fn num_page_bytes(&self) -> usize {
@@ -2109,15 +2707,124 @@ impl InodeDesc {
         blocks
     }
 
+    /// Returns the device ranges that store the blocks of the inode, in the order of the
+    /// file blocks.
+    ///
+    /// The block mapping is read from the `block_device` directly, so this is only used
+    /// before the filesystem is loaded, e.g., to locate the journal.
+    pub fn read_device_ranges(
+        &self,
+        block_device: &dyn BlockDevice,
+    ) -> Result<Vec<Range<Ext2Bid>>> {
+        let mut device_ranges = Vec::new();
+        if self.flags.contains(FileFlags::EXTENTS) {
+            let read_block = |bid: Ext2Bid, buf: &mut [u8]| -> Result<()> {
+                block_device.read_bytes(bid as usize * BLOCK_SIZE, buf)?;
+                Ok(())
+            };
+            let extent_tree = ExtentTree::load(&read_block, self.block_ptrs.as_bytes(), None)?;
+            let mut bid = 0;
+            while bid < self.blocks_count {
+                match extent_tree.map(bid..self.blocks_count) {
+                    BlockMapping::Mapped(device_range) => {
+                        bid += device_range.len() as Ext2Bid;
+                        push_device_range(&mut device_ranges, device_range);
+                    }
+                    _ => return_errno_with_message!(Errno::EUCLEAN, "the inode has holes"),
+                }
+            }
+            return Ok(device_ranges);
+        }
+
+        let mut remaining = self.blocks_count;
+        for idx in DIRECT_RANGE {
+            if remaining == 0 {
+                return Ok(device_ranges);
+            }
+            push_device_bid(&mut device_ranges, self.block_ptrs.direct(idx))?;
+            remaining -= 1;
+        }
+        let indirect_roots = [
+            (self.block_ptrs.indirect(), 1),
+            (self.block_ptrs.db_indirect(), 2),
+            (self.block_ptrs.tb_indirect(), 3),
+        ];
+        for (bid, level) in indirect_roots {
+            if remaining == 0 {
+                break;
+            }
+            read_indirect_device_ranges(
+                block_device,
+                bid,
+                level,
+                &mut remaining,
+                &mut device_ranges,
+            )?;
+        }
+        Ok(device_ranges)
+    }
+
     #[inline]
     fn size_to_blocks(&self, size: usize) -> Ext2Bid {
-        if self.type_ == InodeType::SymLink && size <= MAX_FAST_SYMLINK_LEN {
+        // The symlink with extents stores the target in the data blocks.
+        if self.type_ == InodeType::SymLink
+            && size <= MAX_FAST_SYMLINK_LEN
+            && !self.flags.contains(FileFlags::EXTENTS)
+        {
             return 0;
         }
         size.div_ceil(BLOCK_SIZE) as Ext2Bid
     }
 }
 
+/// Appends the device block `bid` to the device ranges.
+fn push_device_bid(device_ranges: &mut Vec<Range<Ext2Bid>>, bid: Ext2Bid) -> Result<()> {
+    if bid == 0 {
+        return_errno_with_message!(Errno::EUCLEAN, "the inode has holes");
+    }
+    push_device_range(device_ranges, bid..bid + 1);
+    Ok(())
+}
+
+/// Appends the `device_range` to the device ranges, merging it into the last one if they
+/// are consecutive.
+fn push_device_range(device_ranges: &mut Vec<Range<Ext2Bid>>, device_range: Range<Ext2Bid>) {
+    match device_ranges.last_mut() {
+        Some(last) if last.end == device_range.start => last.end = device_range.end,
+        _ => device_ranges.push(device_range),
+    }
+}
+
+/// Appends the device ranges mapped by the indirect block `bid` at the `level`, until
+/// there are no `remaining` blocks.
+fn read_indirect_device_ranges(
+    block_device: &dyn BlockDevice,
+    bid: Ext2Bid,
+    level: u32,
+    remaining: &mut Ext2Bid,
+    device_ranges: &mut Vec<Range<Ext2Bid>>,
+) -> Result<()> {
+    if bid == 0 {
+        return_errno_with_message!(Errno::EUCLEAN, "the inode has holes");
+    }
+    let mut buf = vec![0u8; BLOCK_SIZE];
+    block_device.read_bytes(bid as usize * BLOCK_SIZE, &mut buf)?;
+
+    for raw_bid in buf.chunks_exact(BID_SIZE) {
+        if *remaining == 0 {
+            break;
+        }
+        let bid = Ext2Bid::from_le_bytes(raw_bid.try_into().unwrap());
+        if level == 1 {
+            push_device_bid(device_ranges, bid)?;
+            *remaining -= 1;
+        } else {
+            read_indirect_device_ranges(block_device, bid, level - 1, remaining, device_ranges)?;
+        }
+    }
+    Ok(())
+}
+
 bitflags! {
     pub struct FilePerm: u16 {
         /// set-user-ID
@@ -2193,6 +2900,22 @@ bitflags! {
         const DIR_SYNC = 1 << 16;
         /// Top of directory hierarchies.
         const TOP_DIR = 1 << 17;
+        /// The number of blocks is in the unit of filesystem blocks, not sectors.
+        const HUGE_FILE = 1 << 18;
+        /// The blocks are mapped by an extent tree.
+        const EXTENTS = 1 << 19;
+        /// Verity protected file.
+        const VERITY = 1 << 20;
+        /// The inode stores a large extended attribute value.
+        const EA_INODE = 1 << 21;
+        /// Direct access.
+        const DAX = 1 << 25;
+        /// The data is stored in the inode.
+        const INLINE_DATA = 1 << 28;
+        /// Create with the parent's project ID.
+        const PROJ_INHERIT = 1 << 29;
+        /// Casefolded directory.
+        const CASEFOLD = 1 << 30;
         /// Reserved for ext2 lib.
         const RESERVED = 1 << 31;
     }
@@ -2243,53 +2966,112 @@ pub(super) struct RawInode {
     pub os_dependent_2: Osd2,
 }
 
-impl From<&InodeDesc> for RawInode {
-    fn from(inode: &InodeDesc) -> Self {
-        Self {
-            mode: inode.type_ as u16 | inode.perm.bits(),
-            uid: inode.uid as u16,
-            size_low: inode.size as u32,
-            atime: UnixTime::from(inode.atime),
-            ctime: UnixTime::from(inode.ctime),
-            mtime: UnixTime::from(inode.mtime),
-            dtime: UnixTime::from(inode.dtime),
-            gid: inode.gid as u16,
-            hard_links: inode.hard_links,
-            blocks_count: inode.blocks_count,
-            flags: inode.flags.bits(),
-            block_ptrs: inode.block_ptrs,
-            file_acl: match inode.acl {
-                Some(acl) if inode.type_ == InodeType::File => acl.to_raw() as u32,
-                _ => Default::default(),
-            },
-            size_high: match inode.acl {
-                Some(acl) if inode.type_ == InodeType::Dir => acl.to_raw() as u32,
-                _ => Default::default(),
-            },
-            os_dependent_2: Osd2 {
-                uid_high: (inode.uid >> 16) as u16,
-                gid_high: (inode.gid >> 16) as u16,
-                ..Default::default()
-            },
-            ..Default::default()
-        }
-    }
-}
-
 /// OS dependent Value 2
 #[repr(C)]
 #[derive(Clone, Copy, Default, Debug, Pod)]
 pub(super) struct Osd2 {
-    /// Fragment number.
-    pub frag_num: u8,
-    /// Fragment size.
-    pub frag_size: u8,
-    pad1: u16,
+    /// High 16 bits of the number of blocks.
+    pub blocks_high: u16,
+    /// High 16 bits of the xattr block.
+    pub file_acl_high: u16,
     /// High 16 bits of User Id.
     pub uid_high: u16,
     /// High 16 bits of Group Id.
     pub gid_high: u16,
-    reserved2: u32,
+    /// Low 16 bits of the inode checksum.
+    pub checksum_low: u16,
+    reserved: u16,
+}
+
+const_assert!(core::mem::size_of::<RawInodeExtra>() == 32);
+
+/// The extra fields after the `RawInode`, each of which is used only if it is within
+/// the extra inode size.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+pub(super) struct RawInodeExtra {
+    /// Size of the extra fields in use.
+    pub extra_isize: u16,
+    /// High 16 bits of the inode checksum.
+    pub checksum_high: u16,
+    /// Extra change time bits.
+    pub ctime_extra: u32,
+    /// Extra modification time bits.
+    pub mtime_extra: u32,
+    /// Extra access time bits.
+    pub atime_extra: u32,
+    /// Creation time.
+    pub crtime: UnixTime,
+    /// Extra creation time bits.
+    pub crtime_extra: u32,
+    /// High 32 bits of the version.
+    pub version_high: u32,
+    /// Project Id.
+    pub project_id: u32,
+}
+
+/// The extra time bits in the raw inode record, which are absent if they are not within
+/// the extra inode size.
+struct RawTimesExtra {
+    ctime_extra: Option<u32>,
+    mtime_extra: Option<u32>,
+    atime_extra: Option<u32>,
+}
+
+impl RawTimesExtra {
+    fn from_bytes(raw_inode: &[u8], extra_isize: u16) -> Self {
+        let extra = &raw_inode[size_of::<RawInode>()..];
+        let field = |offset: usize| {
+            (offset + size_of::<u32>() <= extra_isize as usize)
+                .then(|| u32::from_le_bytes(extra[offset..offset + 4].try_into().unwrap()))
+        };
+        Self {
+            ctime_extra: field(offset_of!(RawInodeExtra, ctime_extra)),
+            mtime_extra: field(offset_of!(RawInodeExtra, mtime_extra)),
+            atime_extra: field(offset_of!(RawInodeExtra, atime_extra)),
+        }
+    }
+}
+
+/// Returns the offsets of the low and high 16 bits of the checksum in the raw inode
+/// record, where the high bits are absent if they are not within the extra inode size.
+pub(super) fn raw_inode_checksum_offsets(raw_inode: &[u8]) -> (usize, Option<usize>) {
+    let low_offset = offset_of!(RawInode, os_dependent_2) + offset_of!(Osd2, checksum_low);
+
+    let extra = &raw_inode[size_of::<RawInode>()..];
+    if extra.len() < size_of::<u16>() {
+        return (low_offset, None);
+    }
+    let extra_isize = u16::from_le_bytes([extra[0], extra[1]]) as usize;
+    let high_offset = offset_of!(RawInodeExtra, checksum_high);
+    if high_offset + size_of::<u16>() > extra_isize {
+        return (low_offset, None);
+    }
+    (low_offset, Some(size_of::<RawInode>() + high_offset))
+}
+
+/// Encodes the time as the seconds and the extra bits, which consist of the epoch bits
+/// and the nanoseconds.
+fn encode_time(time: Duration) -> (UnixTime, u32) {
+    let secs = time.as_secs() as i64;
+    let epoch = ((secs - secs as i32 as i64) >> 32) as u32 & 0b11;
+    let extra = epoch | time.subsec_nanos() << 2;
+    (UnixTime::from(time), extra)
+}
+
+/// Decodes the time from the seconds and the optional extra bits.
+fn decode_time(time: UnixTime, extra: Option<u32>) -> Duration {
+    // The seconds are signed, and the time before the Unix epoch is clamped.
+    let secs = Duration::from(time).as_secs() as u32 as i32 as i64;
+    let Some(extra) = extra else {
+        return Duration::from_secs(secs.max(0) as u64);
+    };
+    let secs = secs + (((extra & 0b11) as i64) << 32);
+    let nanos = (extra >> 2).min(999_999_999);
+    if secs < 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(secs as u64, nanos)
 }
 
 fn is_block_aligned(offset: usize) -> bool {
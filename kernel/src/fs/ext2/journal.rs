@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The journal of Ext3 and Ext4.
+//!
+//! The journal is stored in the blocks of the journal inode in the JBD2 format. Once the
+//! journal is opened, the metadata blocks written by the filesystem are kept in the running
+//! transaction, which is committed when the metadata are synced, every
+//! [`COMMIT_INTERVAL`], or when the transaction takes a large part of the log.
+
+use super::{
+    block_group::RawGroupDescriptor,
+    fs::Ext2,
+    inode::InodeDesc,
+    prelude::*,
+    super_block::{
+        FeatureInCompatSet, FeatureRoCompatSet, RawSuperBlock, SuperBlock, SUPER_BLOCK_OFFSET,
+    },
+};
+use crate::{
+    fs::jbd2::Journal,
+    thread::work_queue::{submit_work_item, work_item::WorkItem, WorkPriority},
+    time::{clocks::MonotonicClock, timer::Timeout, Timer},
+};
+
+/// The interval between the periodic commits, which is the default of Linux.
+const COMMIT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Opens the journal stored in the journal inode.
+///
+/// The block groups have not been loaded at this point, so the journal inode is read from
+/// the block device directly.
+pub(super) fn open_journal(
+    block_device: &Arc<dyn BlockDevice>,
+    super_block: &SuperBlock,
+) -> Result<Journal> {
+    let ino = super_block.journal_ino();
+    if ino == 0 {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the external journal is not supported");
+    }
+
+    // Both the group descriptor and the inode record never cross the block boundary.
+    let read_in_block = |offset: usize, buf: &mut [u8]| -> Result<()> {
+        let mut block = vec![0u8; BLOCK_SIZE];
+        block_device.read_bytes(offset.align_down(BLOCK_SIZE), &mut block)?;
+        let offset = offset % BLOCK_SIZE;
+        buf.copy_from_slice(&block[offset..offset + buf.len()]);
+        Ok(())
+    };
+
+    let block_group_idx = ((ino - 1) / super_block.inodes_per_group()) as usize;
+    let inode_idx = ((ino - 1) % super_block.inodes_per_group()) as usize;
+    let mut raw_descriptor = RawGroupDescriptor::new_zeroed();
+    read_in_block(
+        super_block.group_descriptors_bid(0).to_offset()
+            + block_group_idx * super_block.desc_size(),
+        &mut raw_descriptor.as_bytes_mut()[..super_block.desc_size()],
+    )?;
+    let mut raw_inode = vec![0u8; super_block.inode_size()];
+    read_in_block(
+        raw_descriptor.inode_table as usize * BLOCK_SIZE + inode_idx * super_block.inode_size(),
+        &mut raw_inode,
+    )?;
+
+    let area = InodeDesc::from_raw_bytes(&raw_inode)?
+        .read_device_ranges(block_device.as_ref())?
+        .into_iter()
+        .map(|range| Bid::new(range.start as u64)..Bid::new(range.end as u64))
+        .collect();
+    Journal::load(block_device.clone(), area)
+}
+
+/// Sets or clears the flag of the superblock on the device, which indicates that the
+/// journal may have transactions to replay.
+///
+/// The superblock is updated in place without passing through the journal, and the device
+/// is flushed before returning.
+pub(super) fn set_needs_recovery(
+    block_device: &dyn BlockDevice,
+    needs_recovery: bool,
+) -> Result<()> {
+    let mut raw_super_block = block_device.read_val::<RawSuperBlock>(SUPER_BLOCK_OFFSET)?;
+    if needs_recovery {
+        raw_super_block.feature_incompat |= FeatureInCompatSet::RECOVER.bits();
+    } else {
+        raw_super_block.feature_incompat &= !FeatureInCompatSet::RECOVER.bits();
+    }
+    if raw_super_block.feature_ro_compat & FeatureRoCompatSet::METADATA_CSUM.bits() != 0 {
+        raw_super_block.checksum = raw_super_block.compute_checksum();
+    }
+    block_device.write_val(SUPER_BLOCK_OFFSET, &raw_super_block)?;
+
+    match block_device.sync()? {
+        BioStatus::Complete => Ok(()),
+        err_status => Err(Error::from(err_status)),
+    }
+}
+
+/// The timer that commits the journal every [`COMMIT_INTERVAL`].
+///
+/// A crash loses at most the metadata written during the last interval, even if the
+/// filesystem is never synced explicitly.
+pub(super) struct CommitTimer(Arc<Timer>);
+
+impl CommitTimer {
+    /// Creates and starts the timer of the filesystem `fs`.
+    pub(super) fn new(fs: Weak<Ext2>) -> Self {
+        // The commit may sleep, so it is done in a work queue instead of the timer callback.
+        let work_item = WorkItem::new(Box::new(move || {
+            let Some(fs) = fs.upgrade() else {
+                return;
+            };
+            if let Err(err) = fs.sync_all() {
+                warn!("ext2: failed to commit the journal: {:?}", err);
+            }
+        }));
+        let timer = MonotonicClock::timer_manager().create_timer(move || {
+            submit_work_item(work_item.clone(), WorkPriority::Normal);
+        });
+        timer.set_interval(COMMIT_INTERVAL);
+        timer.set_timeout(Timeout::After(COMMIT_INTERVAL));
+        Self(timer)
+    }
+}
+
+impl Drop for CommitTimer {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+impl Debug for CommitTimer {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("CommitTimer")
+            .field("interval", &self.0.interval())
+            .finish()
+    }
+}
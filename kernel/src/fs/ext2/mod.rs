@@ -14,6 +14,11 @@
 //!    stored in PageCache, which accelerates the performance of data access.
 //! 3. Compatible with queue-based block device. The filesystem can submits multiple
 //!    BIO requests to be block device at once, thereby enhancing I/O performance.
+//! 4. Compatible with Ext4 images created with the default options. The extent trees,
+//!    the 64-bit block group descriptors, the flexible block groups, the metadata
+//!    checksums and the hash-indexed directories are supported. The metadata are
+//!    written through the journal, which is replayed at mount time if the system
+//!    crashed.
 //!
 //! # Example
 //!
@@ -43,10 +48,14 @@ pub use super_block::{SuperBlock, MAGIC_NUM};
 mod block_group;
 mod block_ptr;
 mod dir;
+mod extent;
 mod fs;
+mod htree;
 mod impl_for_vfs;
 mod indirect_block_cache;
 mod inode;
+mod journal;
 mod prelude;
 mod super_block;
 mod utils;
+mod xattr;
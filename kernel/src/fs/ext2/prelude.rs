@@ -9,7 +9,7 @@ pub(super) use align_ext::AlignExt;
 pub(super) use aster_block::{
     bio::{BioDirection, BioSegment, BioStatus, BioWaiter},
     id::Bid,
-    BlockDevice, BLOCK_SIZE,
+    BlockDevice, BLOCK_SIZE, SECTOR_SIZE,
 };
 pub(super) use aster_rights::Full;
 pub(super) use ostd::{
@@ -20,7 +20,9 @@ pub(super) use static_assertions::const_assert;
 
 pub(super) use super::utils::{Dirty, IsPowerOf};
 pub(super) use crate::{
-    fs::utils::{CStr256, DirentVisitor, InodeType, PageCache, PageCacheBackend, Str16, Str64},
+    fs::utils::{
+        crc16, crc32c, CStr256, DirentVisitor, InodeType, PageCache, PageCacheBackend, Str16, Str64,
+    },
     prelude::*,
     time::UnixTime,
     vm::vmo::Vmo,
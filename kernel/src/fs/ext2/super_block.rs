@@ -10,6 +10,18 @@ pub const SUPER_BLOCK_OFFSET: usize = 1024;
 
 const SUPER_BLOCK_SIZE: usize = 1024;
 
+/// The size of the block group descriptor if the `IS_64BIT` feature is not set.
+const GOOD_OLD_DESC_SIZE: usize = 32;
+
+/// The minimal size of the block group descriptor if the `IS_64BIT` feature is set.
+const MIN_DESC_SIZE_64BIT: usize = 64;
+
+/// The type of the metadata checksum, which can only be CRC32C.
+const CHECKSUM_TYPE_CRC32C: u8 = 1;
+
+/// The flag in the superblock that makes the directory index hash the names as unsigned chars.
+const UNSIGNED_HASH_FLAG: u32 = 1 << 1;
+
 /// The in-memory rust superblock.
 ///
 /// It contains all information about the layout of the Ext2.
@@ -91,15 +103,79 @@ pub struct SuperBlock {
     prealloc_file_blocks: u8,
     /// Number of blocks to preallocate for directories.
     prealloc_dir_blocks: u8,
+    /// Number of blocks reserved for the growth of the group descriptor table.
+    reserved_gdt_blocks: u16,
+    /// Size of the block group descriptor.
+    desc_size: usize,
+    /// Number of bytes of the extra inode fields that new inodes should reserve.
+    want_extra_isize: u16,
+    /// The seed of the metadata checksums.
+    checksum_seed: u32,
+    /// The raw superblock, which keeps the fields that are not interpreted.
+    raw: RawSuperBlock,
 }
 
 impl TryFrom<RawSuperBlock> for SuperBlock {
     type Error = crate::error::Error;
 
     fn try_from(sb: RawSuperBlock) -> Result<Self> {
+        let feature_incompat = {
+            let features = FeatureInCompatSet::from_bits(sb.feature_incompat).ok_or(
+                Error::with_message(Errno::EINVAL, "invalid feature incompat set"),
+            )?;
+            if features.contains(FeatureInCompatSet::RECOVER)
+                && sb.feature_compat & FeatureCompatSet::HAS_JOURNAL.bits() == 0
+            {
+                return_errno_with_message!(Errno::EINVAL, "the journal needs recovery");
+            }
+            if !(features - FeatureInCompatSet::SUPPORTED).is_empty() {
+                return_errno_with_message!(Errno::EINVAL, "unsupported feature incompat set");
+            }
+            features
+        };
+        let feature_ro_compat = {
+            let features = FeatureRoCompatSet::from_bits(sb.feature_ro_compat).ok_or(
+                Error::with_message(Errno::EINVAL, "invalid feature ro compat set"),
+            )?;
+            if !(features - FeatureRoCompatSet::SUPPORTED).is_empty() {
+                return_errno_with_message!(Errno::EINVAL, "unsupported feature ro compat set");
+            }
+            features
+        };
+        let feature_compat = {
+            // The unknown compatible features can be ignored safely.
+            let features = FeatureCompatSet::from_bits_truncate(sb.feature_compat);
+            if features.contains(FeatureCompatSet::SPARSE_SUPER2) {
+                return_errno_with_message!(Errno::EINVAL, "unsupported feature compat set");
+            }
+            features
+        };
+        let is_64bit = feature_incompat.contains(FeatureInCompatSet::IS_64BIT);
+
+        let checksum_seed = if feature_ro_compat.contains(FeatureRoCompatSet::METADATA_CSUM) {
+            if sb.checksum_type != CHECKSUM_TYPE_CRC32C {
+                return_errno_with_message!(Errno::EINVAL, "unsupported checksum type");
+            }
+            if sb.checksum != sb.compute_checksum() {
+                return_errno_with_message!(Errno::EBADMSG, "bad superblock checksum");
+            }
+            if feature_incompat.contains(FeatureInCompatSet::CSUM_SEED) {
+                sb.checksum_seed
+            } else {
+                crc32c(!0, &sb.uuid)
+            }
+        } else {
+            0
+        };
+
         Ok(Self {
             inodes_count: sb.inodes_count,
-            blocks_count: sb.blocks_count,
+            blocks_count: {
+                if is_64bit && sb.blocks_count_high != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "too many blocks");
+                }
+                sb.blocks_count
+            },
             reserved_blocks_count: sb.reserved_blocks_count,
             free_blocks_count: sb.free_blocks_count,
             free_inodes_count: sb.free_inodes_count,
@@ -158,20 +234,30 @@ impl TryFrom<RawSuperBlock> for SuperBlock {
                 inode_size
             },
             block_group_idx: sb.block_group_idx as _,
-            feature_compat: FeatureCompatSet::from_bits(sb.feature_compat).ok_or(
-                Error::with_message(Errno::EINVAL, "invalid feature compat set"),
-            )?,
-            feature_incompat: FeatureInCompatSet::from_bits(sb.feature_incompat).ok_or(
-                Error::with_message(Errno::EINVAL, "invalid feature incompat set"),
-            )?,
-            feature_ro_compat: FeatureRoCompatSet::from_bits(sb.feature_ro_compat).ok_or(
-                Error::with_message(Errno::EINVAL, "invalid feature ro compat set"),
-            )?,
+            feature_compat,
+            feature_incompat,
+            feature_ro_compat,
             uuid: sb.uuid,
             volume_name: sb.volume_name,
             last_mounted_dir: sb.last_mounted_dir,
             prealloc_file_blocks: sb.prealloc_file_blocks,
             prealloc_dir_blocks: sb.prealloc_dir_blocks,
+            reserved_gdt_blocks: sb.reserved_gdt_blocks,
+            desc_size: if is_64bit {
+                let desc_size = sb.desc_size as usize;
+                if desc_size < MIN_DESC_SIZE_64BIT
+                    || desc_size > SUPER_BLOCK_SIZE
+                    || !desc_size.is_power_of_two()
+                {
+                    return_errno_with_message!(Errno::EINVAL, "invalid descriptor size");
+                }
+                desc_size
+            } else {
+                GOOD_OLD_DESC_SIZE
+            },
+            want_extra_isize: sb.want_extra_isize,
+            checksum_seed,
+            raw: sb,
         })
     }
 }
@@ -214,7 +300,92 @@ impl SuperBlock {
 
     /// Returns the number of block groups.
     pub fn block_groups_count(&self) -> u32 {
-        self.blocks_count / self.blocks_per_group
+        let first_data_block = self.first_data_block.to_raw() as u32;
+        (self.blocks_count - first_data_block).div_ceil(self.blocks_per_group)
+    }
+
+    /// Returns the first data block.
+    pub fn first_data_block(&self) -> Bid {
+        self.first_data_block
+    }
+
+    /// Returns the size of block group descriptor.
+    pub fn desc_size(&self) -> usize {
+        self.desc_size
+    }
+
+    /// Returns the number of blocks reserved for the growth of the group descriptor table.
+    pub fn reserved_gdt_blocks(&self) -> u32 {
+        self.reserved_gdt_blocks as u32
+    }
+
+    /// Returns the number of blocks occupied by the group descriptor table.
+    pub fn group_descriptors_blocks_count(&self) -> u32 {
+        ((self.block_groups_count() as usize) * self.desc_size).div_ceil(self.block_size) as u32
+    }
+
+    /// Returns the number of bytes of the extra inode fields that new inodes should reserve.
+    pub fn want_extra_isize(&self) -> u16 {
+        self.want_extra_isize
+    }
+
+    /// Returns whether the metadata are protected by CRC32C checksums.
+    pub fn has_metadata_csum(&self) -> bool {
+        self.feature_ro_compat
+            .contains(FeatureRoCompatSet::METADATA_CSUM)
+    }
+
+    /// Returns whether the block group descriptors are protected by checksums.
+    ///
+    /// If so, the uninitialized block groups are also supported.
+    pub fn has_group_desc_csum(&self) -> bool {
+        self.feature_ro_compat
+            .intersects(FeatureRoCompatSet::METADATA_CSUM | FeatureRoCompatSet::GDT_CSUM)
+    }
+
+    /// Returns the seed of the metadata checksums.
+    pub fn checksum_seed(&self) -> u32 {
+        self.checksum_seed
+    }
+
+    /// Returns the UUID of the filesystem.
+    pub fn uuid(&self) -> &[u8; 16] {
+        &self.uuid
+    }
+
+    /// Returns whether the filesystem has a journal.
+    pub fn has_journal(&self) -> bool {
+        self.feature_compat.contains(FeatureCompatSet::HAS_JOURNAL)
+    }
+
+    /// Returns whether the directories can be indexed by hash trees.
+    pub fn has_dir_index(&self) -> bool {
+        self.feature_compat.contains(FeatureCompatSet::DIR_INDEX)
+    }
+
+    /// Returns whether the filesystem needs to replay its journal.
+    pub fn needs_recovery(&self) -> bool {
+        self.feature_incompat.contains(FeatureInCompatSet::RECOVER)
+    }
+
+    /// Returns the inode number of the journal, or zero if the journal is on another device.
+    pub fn journal_ino(&self) -> u32 {
+        self.raw.journal_ino
+    }
+
+    /// Returns the seed of the hashes in the directory indexes.
+    pub fn hash_seed(&self) -> [u32; 4] {
+        self.raw.hash_seed
+    }
+
+    /// Returns the hash version of the new directory indexes.
+    pub fn def_hash_version(&self) -> u8 {
+        self.raw.def_hash_version
+    }
+
+    /// Returns whether the directory indexes hash the names as unsigned chars.
+    pub fn has_unsigned_hash(&self) -> bool {
+        self.raw.flags & UNSIGNED_HASH_FLAG != 0
     }
 
     /// Returns the filesystem state.
@@ -273,6 +444,18 @@ impl SuperBlock {
         self.free_inodes_count -= 1;
     }
 
+    /// Checks if the block group contains the super block or its backup.
+    pub(super) fn has_super_block(&self, block_group_idx: usize) -> bool {
+        block_group_idx == 0 || self.is_backup_group(block_group_idx)
+    }
+
+    /// Updates the checksum of the raw superblock if the metadata checksums are enabled.
+    pub(super) fn update_checksum(&self, raw_super_block: &mut RawSuperBlock) {
+        if self.has_metadata_csum() {
+            raw_super_block.checksum = raw_super_block.compute_checksum();
+        }
+    }
+
     /// Checks if the block group will backup the super block.
     pub(super) fn is_backup_group(&self, block_group_idx: usize) -> bool {
         if block_group_idx == 0 {
@@ -337,6 +520,20 @@ bitflags! {
         const RESIZE_INO = 1 << 4;
         /// Directories use hash index
         const DIR_INDEX = 1 << 5;
+        /// Lazy block group (not used)
+        const LAZY_BG = 1 << 6;
+        /// Exclude inode (not used)
+        const EXCLUDE_INODE = 1 << 7;
+        /// Exclude bitmap (not used)
+        const EXCLUDE_BITMAP = 1 << 8;
+        /// Only two backups of the superblock exist
+        const SPARSE_SUPER2 = 1 << 9;
+        /// File system supports fast commits of the journal
+        const FAST_COMMIT = 1 << 10;
+        /// Inode numbers never change
+        const STABLE_INODES = 1 << 11;
+        /// File system uses an orphan file
+        const ORPHAN_FILE = 1 << 12;
     }
 }
 
@@ -353,9 +550,43 @@ bitflags! {
         const JOURNAL_DEV = 1 << 3;
         /// Metablock block group
         const META_BG = 1 << 4;
+        /// Files use extent trees
+        const EXTENTS = 1 << 6;
+        /// File system can have more than 2^32 blocks
+        const IS_64BIT = 1 << 7;
+        /// Multiple mount protection
+        const MMP = 1 << 8;
+        /// Metadata of block groups can be placed together
+        const FLEX_BG = 1 << 9;
+        /// Inodes can store large extended attribute values
+        const EA_INODE = 1 << 10;
+        /// Directory entries contain extra data
+        const DIRDATA = 1 << 12;
+        /// Metadata checksum seed is stored in the superblock
+        const CSUM_SEED = 1 << 13;
+        /// Directories can be larger than 2GB or have 3-level hash index
+        const LARGEDIR = 1 << 14;
+        /// Data can be stored in the inode
+        const INLINE_DATA = 1 << 15;
+        /// File system has encrypted inodes
+        const ENCRYPT = 1 << 16;
+        /// Directories can be case-insensitive
+        const CASEFOLD = 1 << 17;
     }
 }
 
+impl FeatureInCompatSet {
+    /// The incompatible features that are supported.
+    const SUPPORTED: Self = Self::from_bits_truncate(
+        Self::FILETYPE.bits()
+            | Self::EXTENTS.bits()
+            | Self::IS_64BIT.bits()
+            | Self::FLEX_BG.bits()
+            | Self::CSUM_SEED.bits()
+            | Self::RECOVER.bits(),
+    );
+}
+
 bitflags! {
     /// Readonly-compatible feature set.
     pub struct FeatureRoCompatSet: u32 {
@@ -365,9 +596,49 @@ bitflags! {
         const LARGE_FILE = 1 << 1;
         /// Directory contents are stored in the form of a Binary Tree
         const BTREE_DIR = 1 << 2;
+        /// File system has files whose sizes are represented in units of blocks
+        const HUGE_FILE = 1 << 3;
+        /// Group descriptors have checksums
+        const GDT_CSUM = 1 << 4;
+        /// Directories can have more than 65000 subdirectories
+        const DIR_NLINK = 1 << 5;
+        /// Inodes have extra fields
+        const EXTRA_ISIZE = 1 << 6;
+        /// File system has a snapshot
+        const HAS_SNAPSHOT = 1 << 7;
+        /// Quota is handled transactionally with the journal
+        const QUOTA = 1 << 8;
+        /// Blocks are allocated in clusters
+        const BIGALLOC = 1 << 9;
+        /// Metadata have checksums
+        const METADATA_CSUM = 1 << 10;
+        /// File system supports replicas (not used)
+        const REPLICA = 1 << 11;
+        /// File system can only be mounted readonly
+        const READONLY = 1 << 12;
+        /// File system tracks project quotas
+        const PROJECT = 1 << 13;
+        /// File system has verity files
+        const VERITY = 1 << 15;
+        /// The orphan file may have valid entries
+        const ORPHAN_PRESENT = 1 << 16;
     }
 }
 
+impl FeatureRoCompatSet {
+    /// The readonly-compatible features that are supported.
+    const SUPPORTED: Self = Self::from_bits_truncate(
+        Self::SPARSE_SUPER.bits()
+            | Self::LARGE_FILE.bits()
+            | Self::BTREE_DIR.bits()
+            | Self::HUGE_FILE.bits()
+            | Self::GDT_CSUM.bits()
+            | Self::DIR_NLINK.bits()
+            | Self::EXTRA_ISIZE.bits()
+            | Self::METADATA_CSUM.bits(),
+    );
+}
+
 #[repr(u16)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, TryFromInt)]
 pub enum FsState {
@@ -419,7 +690,7 @@ const_assert!(core::mem::size_of::<RawSuperBlock>() == SUPER_BLOCK_SIZE);
 
 /// The raw superblock, it must be exactly 1024 bytes in length.
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Pod, Default)]
+#[derive(Clone, Copy, Debug, Pod)]
 pub(super) struct RawSuperBlock {
     pub inodes_count: u32,
     pub blocks_count: u32,
@@ -463,7 +734,8 @@ pub(super) struct RawSuperBlock {
     pub algorithm_usage_bitmap: u32,
     pub prealloc_file_blocks: u8,
     pub prealloc_dir_blocks: u8,
-    padding1: u16,
+    /// Number of blocks reserved for the growth of the group descriptor table.
+    pub reserved_gdt_blocks: u16,
     ///
     /// This fields are for journaling support in Ext3.
     ///
@@ -479,13 +751,55 @@ pub(super) struct RawSuperBlock {
     pub hash_seed: [u32; 4],
     /// Default hash version to use
     pub def_hash_version: u8,
-    reserved_char_pad: u8,
-    reserved_word_pad: u16,
+    journal_backup_type: u8,
+    /// Size of the block group descriptor, if the `IS_64BIT` feature is set.
+    pub desc_size: u16,
     /// Default mount options.
     pub default_mount_opts: u32,
     /// First metablock block group.
     pub first_meta_bg: u32,
-    reserved: Reserved,
+    /// The time when the filesystem was created.
+    pub mkfs_time: UnixTime,
+    /// Backup of the block pointers of the journal inode.
+    journal_blocks: [u32; 17],
+    ///
+    /// These fields are for Ext4.
+    ///
+    /// High 32 bits of the total number of blocks.
+    pub blocks_count_high: u32,
+    /// High 32 bits of the number of reserved blocks.
+    pub reserved_blocks_count_high: u32,
+    /// High 32 bits of the number of free blocks.
+    pub free_blocks_count_high: u32,
+    /// All inodes have at least this many bytes of extra fields.
+    pub min_extra_isize: u16,
+    /// New inodes should reserve this many bytes of extra fields.
+    pub want_extra_isize: u16,
+    /// Miscellaneous flags.
+    pub flags: u32,
+    raid_stride: u16,
+    mmp_update_interval: u16,
+    mmp_block: [u32; 2],
+    raid_stripe_width: u32,
+    /// The number of block groups in a flexible block group is `1 << log_groups_per_flex`.
+    pub log_groups_per_flex: u8,
+    /// The type of the metadata checksums.
+    pub checksum_type: u8,
+    reserved_pad: [u8; 2],
+    reserved1: [u32; 62],
+    /// The seed of the metadata checksums, if the `CSUM_SEED` feature is set.
+    pub checksum_seed: u32,
+    reserved2: [u32; 98],
+    /// The checksum of the superblock.
+    pub checksum: u32,
+}
+
+impl RawSuperBlock {
+    /// Computes the checksum of the superblock.
+    pub fn compute_checksum(&self) -> u32 {
+        let len = core::mem::offset_of!(RawSuperBlock, checksum);
+        crc32c(!0, &self.as_bytes()[..len])
+    }
 }
 
 impl From<&SuperBlock> for RawSuperBlock {
@@ -518,7 +832,8 @@ impl From<&SuperBlock> for RawSuperBlock {
             first_ino: sb.first_ino,
             inode_size: sb.inode_size as u16,
             block_group_idx: sb.block_group_idx as u16,
-            feature_compat: sb.feature_compat.bits(),
+            // Keep the unknown compatible features.
+            feature_compat: sb.raw.feature_compat,
             feature_incompat: sb.feature_incompat.bits(),
             feature_ro_compat: sb.feature_ro_compat.bits(),
             uuid: sb.uuid,
@@ -526,17 +841,7 @@ impl From<&SuperBlock> for RawSuperBlock {
             last_mounted_dir: sb.last_mounted_dir,
             prealloc_file_blocks: sb.prealloc_file_blocks,
             prealloc_dir_blocks: sb.prealloc_dir_blocks,
-            ..Default::default()
+            ..sb.raw
         }
     }
 }
-
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Pod)]
-struct Reserved([u32; 190]);
-
-impl Default for Reserved {
-    fn default() -> Self {
-        Self([0u32; 190])
-    }
-}
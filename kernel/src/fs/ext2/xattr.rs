@@ -0,0 +1,564 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Extended attributes of Ext2.
+//!
+//! The extended attributes of an inode are stored in two places:
+//! 1. The space after the `RawInode` in the inode table, if the inode size is
+//!    larger than that of `RawInode` (the in-inode attributes).
+//! 2. A block referenced by the `file_acl` field of the inode (the block attributes).
+//!    The block may be shared by multiple inodes whose attributes are identical.
+//!
+//! Both places use the on-disk format of Linux. The format is described in
+//! <https://www.kernel.org/doc/html/latest/filesystems/ext4/dynamic.html#extended-attributes>.
+
+use super::{block_ptr::Ext2Bid, fs::Ext2, inode::RawInodeExtra, prelude::*};
+use crate::fs::utils::{XattrName, XattrNamespace, XattrSetFlags};
+
+/// The magic number of the xattr block and the in-inode xattr area.
+const XATTR_MAGIC: u32 = 0xEA02_0000;
+
+/// The size of the extra inode fields that are reserved if the inode does not specify one.
+///
+/// Use the same value as Linux, which covers all the extra fields of the Ext4 inode.
+const DEFAULT_EXTRA_ISIZE: usize = core::mem::size_of::<RawInodeExtra>();
+
+/// The size of the terminator of the entry table.
+const ENTRY_TERMINATOR_SIZE: usize = 4;
+
+const_assert!(core::mem::size_of::<RawXattrBlockHeader>() == 32);
+
+/// The header of the xattr block.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+struct RawXattrBlockHeader {
+    magic: u32,
+    /// The number of inodes that share this block.
+    refcount: u32,
+    /// The number of blocks, which must be one.
+    blocks: u32,
+    /// The hash of all the entries.
+    hash: u32,
+    checksum: u32,
+    reserved: [u32; 3],
+}
+
+const_assert!(core::mem::size_of::<RawXattrEntry>() == 16);
+
+/// The fixed part of an xattr entry, which is followed by the name.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+struct RawXattrEntry {
+    name_len: u8,
+    name_index: u8,
+    /// The offset of the value.
+    ///
+    /// For the block attributes, the offset is relative to the start of the block. For the
+    /// in-inode attributes, the offset is relative to the first entry.
+    value_offset: u16,
+    /// The inode that stores the value, which is only used by Ext4 (`ea_inode`).
+    value_inum: u32,
+    value_size: u32,
+    hash: u32,
+}
+
+/// The index of the namespace of an xattr entry.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromInt)]
+enum NameIndex {
+    User = 1,
+    PosixAclAccess = 2,
+    PosixAclDefault = 3,
+    Trusted = 4,
+    Security = 6,
+    System = 7,
+}
+
+impl From<XattrNamespace> for NameIndex {
+    fn from(namespace: XattrNamespace) -> Self {
+        match namespace {
+            XattrNamespace::User => Self::User,
+            XattrNamespace::Trusted => Self::Trusted,
+            XattrNamespace::Security => Self::Security,
+        }
+    }
+}
+
+impl NameIndex {
+    /// Returns the VFS namespace, or `None` if the entries of the index are not exposed.
+    fn namespace(&self) -> Option<XattrNamespace> {
+        match self {
+            Self::User => Some(XattrNamespace::User),
+            Self::Trusted => Some(XattrNamespace::Trusted),
+            Self::Security => Some(XattrNamespace::Security),
+            Self::PosixAclAccess | Self::PosixAclDefault | Self::System => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct XattrEntry {
+    /// The raw name index, which is kept even if it is unknown.
+    name_index: u8,
+    name: Vec<u8>,
+    value: Vec<u8>,
+}
+
+impl XattrEntry {
+    fn matches(&self, name_index: NameIndex, name: &[u8]) -> bool {
+        self.name_index == name_index as u8 && self.name == name
+    }
+
+    fn full_name(&self) -> Option<String> {
+        let namespace = NameIndex::try_from(self.name_index).ok()?.namespace()?;
+        let name = core::str::from_utf8(&self.name).ok()?;
+        Some(alloc::format!("{}{}", namespace.prefix(), name))
+    }
+
+    /// Returns the length of the entry in the entry table.
+    fn entry_len(&self) -> usize {
+        (core::mem::size_of::<RawXattrEntry>() + self.name.len()).align_up(4)
+    }
+
+    /// Returns the space occupied by the entry and the value.
+    fn space(&self) -> usize {
+        self.entry_len() + self.value.len().align_up(4)
+    }
+
+    /// Computes the hash of the entry in the same way as Linux.
+    fn hash(&self) -> u32 {
+        const NAME_HASH_SHIFT: u32 = 5;
+        const VALUE_HASH_SHIFT: u32 = 16;
+
+        let mut hash = 0u32;
+        for &byte in self.name.iter() {
+            // Linux treats the name as signed characters.
+            hash = hash.rotate_left(NAME_HASH_SHIFT) ^ (byte as i8 as u32);
+        }
+        for chunk in self.value.chunks(4) {
+            let mut word = [0u8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            hash = hash.rotate_left(VALUE_HASH_SHIFT) ^ u32::from_le_bytes(word);
+        }
+        hash
+    }
+
+    /// Compares the entries in the order in which they are sorted in the xattr block.
+    fn cmp_in_block(&self, other: &Self) -> core::cmp::Ordering {
+        (self.name_index, self.name.len(), &self.name).cmp(&(
+            other.name_index,
+            other.name.len(),
+            &other.name,
+        ))
+    }
+}
+
+/// The xattr block that is currently referenced by the inode.
+#[derive(Clone, Copy, Debug)]
+struct XattrBlock {
+    bid: Ext2Bid,
+    refcount: u32,
+}
+
+/// The extended attributes of an inode.
+pub(super) struct Xattrs {
+    ino: u32,
+    /// The raw bytes after the `RawInode` in the inode table.
+    inode_extra: Vec<u8>,
+    /// The size of the extra inode fields, after which the in-inode xattr area follows.
+    ///
+    /// This is `None` if there is no room for the in-inode xattr area.
+    extra_isize: Option<usize>,
+    in_inode: Vec<XattrEntry>,
+    block: Option<XattrBlock>,
+    in_block: Vec<XattrEntry>,
+}
+
+impl Xattrs {
+    /// Loads the extended attributes of the inode.
+    ///
+    /// The `extra_isize` is the size of the extra inode fields in use, which has been
+    /// validated with the inode. The `block_bid` is the xattr block referenced by the
+    /// inode, or zero if there is none.
+    pub fn load(fs: &Ext2, ino: u32, extra_isize: u16, block_bid: Ext2Bid) -> Result<Self> {
+        let inode_extra = fs.read_inode_extra(ino)?;
+        // A zero `i_extra_isize` means that the in-inode xattr area has not been used.
+        let is_in_inode_used = extra_isize != 0;
+
+        let extra_isize = if inode_extra.len() < 2 {
+            None
+        } else {
+            let extra_isize = if is_in_inode_used {
+                extra_isize as usize
+            } else {
+                DEFAULT_EXTRA_ISIZE
+            };
+            let min_len = extra_isize + core::mem::size_of::<u32>() + ENTRY_TERMINATOR_SIZE;
+            (inode_extra.len() >= min_len).then_some(extra_isize)
+        };
+
+        let mut in_inode = Vec::new();
+        if let Some(extra_isize) = extra_isize {
+            let area = &inode_extra[extra_isize..];
+            let magic = u32::from_le_bytes(area[..4].try_into().unwrap());
+            if magic == XATTR_MAGIC && is_in_inode_used {
+                in_inode = read_entries(&area[4..], 0)?;
+            }
+        }
+
+        let mut block = None;
+        let mut in_block = Vec::new();
+        if block_bid != 0 {
+            let mut buf = vec![0u8; BLOCK_SIZE];
+            fs.read_meta_block(block_bid, &mut buf)?;
+
+            let header = RawXattrBlockHeader::from_bytes(&buf);
+            if header.magic != XATTR_MAGIC || header.blocks != 1 {
+                return_errno_with_message!(Errno::EUCLEAN, "the xattr block is corrupted");
+            }
+            if block_checksum(fs, block_bid, &buf).is_some_and(|csum| csum != header.checksum) {
+                return_errno_with_message!(Errno::EBADMSG, "bad xattr block checksum");
+            }
+            block = Some(XattrBlock {
+                bid: block_bid,
+                refcount: header.refcount,
+            });
+            in_block = read_entries(&buf, core::mem::size_of::<RawXattrBlockHeader>())?;
+        }
+
+        Ok(Self {
+            ino,
+            inode_extra,
+            extra_isize,
+            in_inode,
+            block,
+            in_block,
+        })
+    }
+
+    /// Returns the size of the extra inode fields, or `None` if there is no room for
+    /// the in-inode xattr area.
+    pub fn extra_isize(&self) -> Option<usize> {
+        self.extra_isize
+    }
+
+    /// Returns the value of the attribute.
+    pub fn get(&self, name: XattrName) -> Option<&[u8]> {
+        let name_index = NameIndex::from(name.namespace());
+        self.in_inode
+            .iter()
+            .chain(self.in_block.iter())
+            .find(|entry| entry.matches(name_index, name.name().as_bytes()))
+            .map(|entry| entry.value.as_slice())
+    }
+
+    /// Returns the full names of all the attributes.
+    pub fn list(&self) -> Vec<String> {
+        self.in_inode
+            .iter()
+            .chain(self.in_block.iter())
+            .filter_map(XattrEntry::full_name)
+            .collect()
+    }
+
+    /// Sets the value of the attribute.
+    ///
+    /// The change takes effect on the device only after [`Self::store`] is called.
+    pub fn set(&mut self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        if value.len() > BLOCK_SIZE {
+            return_errno_with_message!(Errno::ERANGE, "the xattr value is too large");
+        }
+
+        let name_index = NameIndex::from(name.namespace());
+        let old_entry = self.take(name_index, name.name().as_bytes());
+        if let Err(err) = flags.check(old_entry.is_some()) {
+            self.restore(old_entry);
+            return Err(err);
+        }
+
+        let new_entry = XattrEntry {
+            name_index: name_index as u8,
+            name: name.name().as_bytes().to_vec(),
+            value: value.to_vec(),
+        };
+        if new_entry.space() <= self.in_inode_free_space() {
+            self.in_inode.push(new_entry);
+        } else if new_entry.space() <= self.in_block_free_space() {
+            let pos = self
+                .in_block
+                .partition_point(|entry| entry.cmp_in_block(&new_entry).is_lt());
+            self.in_block.insert(pos, new_entry);
+        } else {
+            self.restore(old_entry);
+            return_errno_with_message!(Errno::ENOSPC, "no space for the xattr");
+        }
+
+        Ok(())
+    }
+
+    /// Removes the attribute.
+    ///
+    /// The change takes effect on the device only after [`Self::store`] is called.
+    pub fn remove(&mut self, name: XattrName) -> Result<()> {
+        let name_index = NameIndex::from(name.namespace());
+        self.take(name_index, name.name().as_bytes())
+            .map(|_| ())
+            .ok_or_else(|| Error::with_message(Errno::ENODATA, "the xattr does not exist"))
+    }
+
+    /// Writes back the attributes and returns the new xattr block, or zero if there is none.
+    ///
+    /// A new block is allocated from the `block_group_idx` group first if needed.
+    pub fn store(&mut self, fs: &Ext2, block_group_idx: usize) -> Result<Ext2Bid> {
+        if let Some(extra_isize) = self.extra_isize {
+            self.store_in_inode(extra_isize);
+            fs.sync_inode_extra(self.ino, &self.inode_extra)?;
+        }
+
+        if self.in_block.is_empty() {
+            if let Some(block) = self.block.take() {
+                release_block(fs, block.bid)?;
+            }
+            return Ok(0);
+        }
+
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        let header_len = core::mem::size_of::<RawXattrBlockHeader>();
+        let hashes = write_entries(&mut buf, header_len, &self.in_block);
+        let header = RawXattrBlockHeader {
+            magic: XATTR_MAGIC,
+            refcount: 1,
+            blocks: 1,
+            hash: block_hash(&hashes),
+            ..Default::default()
+        };
+        buf[..header_len].copy_from_slice(header.as_bytes());
+
+        // The shared block cannot be modified in place.
+        let bid = match self.block {
+            Some(block) if block.refcount == 1 => block.bid,
+            old_block => {
+                let new_bid = fs
+                    .alloc_blocks(block_group_idx, 1)
+                    .ok_or_else(|| Error::with_message(Errno::ENOSPC, "no space on device"))?
+                    .start;
+                if let Some(old_block) = old_block {
+                    release_block(fs, old_block.bid)?;
+                }
+                new_bid
+            }
+        };
+        update_block_checksum(fs, bid, &mut buf);
+        fs.write_meta_block(bid, &buf)?;
+        self.block = Some(XattrBlock { bid, refcount: 1 });
+
+        Ok(bid)
+    }
+
+    fn store_in_inode(&mut self, extra_isize: usize) {
+        // Reserve the extra inode fields if they have not been used.
+        if self.inode_extra[..2] == [0, 0] {
+            self.inode_extra[..extra_isize].fill(0);
+            self.inode_extra[..2].copy_from_slice(&(extra_isize as u16).to_le_bytes());
+        }
+
+        let area = &mut self.inode_extra[extra_isize..];
+        area.fill(0);
+        if self.in_inode.is_empty() {
+            return;
+        }
+        area[..4].copy_from_slice(&XATTR_MAGIC.to_le_bytes());
+        write_entries(&mut area[4..], 0, &self.in_inode);
+    }
+
+    fn take(&mut self, name_index: NameIndex, name: &[u8]) -> Option<(bool, usize, XattrEntry)> {
+        if let Some(pos) = self
+            .in_inode
+            .iter()
+            .position(|entry| entry.matches(name_index, name))
+        {
+            return Some((true, pos, self.in_inode.remove(pos)));
+        }
+
+        let pos = self
+            .in_block
+            .iter()
+            .position(|entry| entry.matches(name_index, name))?;
+        Some((false, pos, self.in_block.remove(pos)))
+    }
+
+    fn restore(&mut self, taken: Option<(bool, usize, XattrEntry)>) {
+        match taken {
+            Some((true, pos, entry)) => self.in_inode.insert(pos, entry),
+            Some((false, pos, entry)) => self.in_block.insert(pos, entry),
+            None => (),
+        }
+    }
+
+    fn in_inode_free_space(&self) -> usize {
+        let Some(extra_isize) = self.extra_isize else {
+            return 0;
+        };
+        let capacity = self.inode_extra.len()
+            - extra_isize
+            - core::mem::size_of::<u32>()
+            - ENTRY_TERMINATOR_SIZE;
+        capacity.saturating_sub(used_space(&self.in_inode))
+    }
+
+    fn in_block_free_space(&self) -> usize {
+        let capacity =
+            BLOCK_SIZE - core::mem::size_of::<RawXattrBlockHeader>() - ENTRY_TERMINATOR_SIZE;
+        capacity.saturating_sub(used_space(&self.in_block))
+    }
+}
+
+/// Releases the xattr block referenced by an inode.
+///
+/// The block is freed if no other inode shares it.
+pub(super) fn release_block(fs: &Ext2, bid: Ext2Bid) -> Result<()> {
+    let _guard = fs.xattr_block_lock();
+
+    let mut buf = vec![0u8; BLOCK_SIZE];
+    fs.read_meta_block(bid, &mut buf)?;
+    let mut header = RawXattrBlockHeader::from_bytes(&buf);
+    if header.magic != XATTR_MAGIC {
+        return_errno_with_message!(Errno::EUCLEAN, "the xattr block is corrupted");
+    }
+
+    if header.refcount > 1 {
+        header.refcount -= 1;
+        buf[..core::mem::size_of::<RawXattrBlockHeader>()].copy_from_slice(header.as_bytes());
+        update_block_checksum(fs, bid, &mut buf);
+        fs.write_meta_block(bid, &buf)?;
+        return Ok(());
+    }
+
+    fs.free_blocks(bid..bid + 1)
+}
+
+/// Computes the checksum of the xattr block, which also covers the block ID since the
+/// block may be shared by inodes.
+///
+/// Returns `None` if the metadata checksums are disabled.
+fn block_checksum(fs: &Ext2, bid: Ext2Bid, buf: &[u8]) -> Option<u32> {
+    let checksum_offset = core::mem::offset_of!(RawXattrBlockHeader, checksum);
+    let seed = crc32c(fs.csum_seed()?, &(bid as u64).to_le_bytes());
+    let seed = crc32c(seed, &buf[..checksum_offset]);
+    let seed = crc32c(seed, &[0u8; 4]);
+    Some(crc32c(seed, &buf[checksum_offset + 4..]))
+}
+
+fn update_block_checksum(fs: &Ext2, bid: Ext2Bid, buf: &mut [u8]) {
+    let Some(checksum) = block_checksum(fs, bid, buf) else {
+        return;
+    };
+    let checksum_offset = core::mem::offset_of!(RawXattrBlockHeader, checksum);
+    buf[checksum_offset..checksum_offset + 4].copy_from_slice(&checksum.to_le_bytes());
+}
+
+fn used_space(entries: &[XattrEntry]) -> usize {
+    entries.iter().map(XattrEntry::space).sum()
+}
+
+/// Reads the entries, where the entry table starts at `entries_offset` of `buf`.
+///
+/// The value offsets are relative to the start of `buf`.
+fn read_entries(buf: &[u8], entries_offset: usize) -> Result<Vec<XattrEntry>> {
+    let corrupted = || Error::with_message(Errno::EUCLEAN, "the xattr entries are corrupted");
+
+    let mut entries = Vec::new();
+    let mut offset = entries_offset;
+    loop {
+        let terminator = buf
+            .get(offset..offset + ENTRY_TERMINATOR_SIZE)
+            .ok_or_else(corrupted)?;
+        if terminator.iter().all(|&byte| byte == 0) {
+            break;
+        }
+
+        let raw_entry = buf
+            .get(offset..offset + core::mem::size_of::<RawXattrEntry>())
+            .map(RawXattrEntry::from_bytes)
+            .ok_or_else(corrupted)?;
+        if raw_entry.value_inum != 0 {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "xattr values in inodes are not supported"
+            );
+        }
+
+        let name_offset = offset + core::mem::size_of::<RawXattrEntry>();
+        let name = buf
+            .get(name_offset..name_offset + raw_entry.name_len as usize)
+            .ok_or_else(corrupted)?;
+        let value_offset = raw_entry.value_offset as usize;
+        let value = buf
+            .get(value_offset..value_offset + raw_entry.value_size as usize)
+            .ok_or_else(corrupted)?;
+
+        let entry = XattrEntry {
+            name_index: raw_entry.name_index,
+            name: name.to_vec(),
+            value: value.to_vec(),
+        };
+        offset += entry.entry_len();
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Writes the entries, where the entry table starts at `entries_offset` of `buf`, and returns
+/// the hashes of the entries.
+///
+/// The values are placed at the end of `buf`, and the value offsets are relative to the start of
+/// `buf`. The `buf` must be zeroed and large enough to hold the entries.
+fn write_entries(buf: &mut [u8], entries_offset: usize, entries: &[XattrEntry]) -> Vec<u32> {
+    let mut hashes = Vec::with_capacity(entries.len());
+    let mut entry_offset = entries_offset;
+    let mut value_offset = buf.len();
+
+    for entry in entries {
+        let value_len = entry.value.len();
+        let raw_value_offset = if value_len == 0 {
+            0
+        } else {
+            value_offset -= value_len.align_up(4);
+            buf[value_offset..value_offset + value_len].copy_from_slice(&entry.value);
+            value_offset
+        };
+
+        let hash = entry.hash();
+        let raw_entry = RawXattrEntry {
+            name_len: entry.name.len() as u8,
+            name_index: entry.name_index,
+            value_offset: raw_value_offset as u16,
+            value_inum: 0,
+            value_size: value_len as u32,
+            hash,
+        };
+        let name_offset = entry_offset + core::mem::size_of::<RawXattrEntry>();
+        buf[entry_offset..name_offset].copy_from_slice(raw_entry.as_bytes());
+        buf[name_offset..name_offset + entry.name.len()].copy_from_slice(&entry.name);
+
+        entry_offset += entry.entry_len();
+        hashes.push(hash);
+    }
+
+    debug_assert!(entry_offset + ENTRY_TERMINATOR_SIZE <= value_offset);
+    hashes
+}
+
+/// Computes the hash of the xattr block in the same way as Linux.
+fn block_hash(entry_hashes: &[u32]) -> u32 {
+    const BLOCK_HASH_SHIFT: u32 = 16;
+
+    let mut hash = 0u32;
+    for &entry_hash in entry_hashes {
+        if entry_hash == 0 {
+            return 0;
+        }
+        hash = hash.rotate_left(BLOCK_HASH_SHIFT) ^ entry_hash;
+    }
+    hash
+}
@@ -12,6 +12,7 @@ use crate::{
     net::socket::Socket,
     prelude::*,
     process::{signal::Pollable, Gid, Uid},
+    vm::vmo::Vmo,
 };
 
 /// The basic operations defined on a file
@@ -24,6 +25,26 @@ pub trait FileLike: Pollable + Send + Sync + Any {
         return_errno_with_message!(Errno::EBADF, "the file is not valid for writing");
     }
 
+    /// Reads as if the file were in the non-blocking mode.
+    ///
+    /// If no data are available, this method fails with `EAGAIN` instead of waiting, even if
+    /// `O_NONBLOCK` is not set. The default implementation calls [`read`], which suits the
+    /// files whose reads never wait for events.
+    ///
+    /// [`read`]: FileLike::read
+    fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read(writer)
+    }
+
+    /// Writes as if the file were in the non-blocking mode.
+    ///
+    /// See [`read_nonblocking`] for details.
+    ///
+    /// [`read_nonblocking`]: FileLike::read_nonblocking
+    fn write_nonblocking(&self, reader: &mut VmReader) -> Result<usize> {
+        self.write(reader)
+    }
+
     /// Read at the given file offset.
     ///
     /// The file must be seekable to support `read_at`.
@@ -100,6 +121,15 @@ pub trait FileLike: Pollable + Send + Sync + Any {
         return_errno_with_message!(Errno::EOPNOTSUPP, "fallocate is not supported");
     }
 
+    /// Returns the VMO to memory-map the file and the offset in the VMO
+    /// that corresponds to `offset` in the file.
+    ///
+    /// This is for the files whose contents are not in the page caches of
+    /// inodes, e.g., the rings of io_uring instances.
+    fn mmap_vmo(&self, offset: usize) -> Result<(Vmo, usize)> {
+        return_errno_with_message!(Errno::ENODEV, "the file cannot be memory-mapped");
+    }
+
     fn as_socket(self: Arc<Self>) -> Option<Arc<dyn Socket>> {
         None
     }
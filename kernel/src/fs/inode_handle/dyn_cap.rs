@@ -28,6 +28,7 @@ impl InodeHandle<Rights> {
         if inode.type_() == InodeType::Dir && access_mode.is_writable() {
             return_errno_with_message!(Errno::EISDIR, "directory cannot open to write");
         }
+        inode.prepare_open(access_mode)?;
 
         let file_io = if let Some(device) = inode.as_device() {
             device.open()?
@@ -42,6 +43,7 @@ impl InodeHandle<Rights> {
             access_mode,
             status_flags: AtomicU32::new(status_flags.bits()),
         });
+        inner.dentry.notify(InotifyMask::IN_OPEN);
         Ok(Self(inner, Rights::from(access_mode)))
     }
 
@@ -108,6 +110,20 @@ impl FileLike for InodeHandle<Rights> {
         self.0.write(reader)
     }
 
+    fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        if !self.1.contains(Rights::READ) {
+            return_errno_with_message!(Errno::EBADF, "file is not readable");
+        }
+        self.0.read_nonblocking(writer)
+    }
+
+    fn write_nonblocking(&self, reader: &mut VmReader) -> Result<usize> {
+        if !self.1.contains(Rights::WRITE) {
+            return_errno_with_message!(Errno::EBADF, "file is not writable");
+        }
+        self.0.write_nonblocking(reader)
+    }
+
     fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
         if !self.1.contains(Rights::READ) {
             return_errno_with_message!(Errno::EBADF, "file is not readable");
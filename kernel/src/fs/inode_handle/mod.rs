@@ -17,6 +17,7 @@ use crate::{
     fs::{
         device::Device,
         file_handle::FileLike,
+        inotify::InotifyMask,
         path::Dentry,
         utils::{
             AccessMode, DirentVisitor, FallocMode, FileRange, FlockItem, FlockList, InodeMode,
@@ -47,71 +48,121 @@ struct InodeHandle_ {
 
 impl InodeHandle_ {
     pub fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_with_flags(writer, self.status_flags())
+    }
+
+    pub fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        self.write_with_flags(reader, self.status_flags())
+    }
+
+    pub fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_with_flags(writer, self.status_flags() | StatusFlags::O_NONBLOCK)
+    }
+
+    pub fn write_nonblocking(&self, reader: &mut VmReader) -> Result<usize> {
+        self.write_with_flags(reader, self.status_flags() | StatusFlags::O_NONBLOCK)
+    }
+
+    fn read_with_flags(&self, writer: &mut VmWriter, status_flags: StatusFlags) -> Result<usize> {
         if let Some(ref file_io) = self.file_io {
-            return file_io.read(writer);
+            return file_io.read(writer, status_flags);
         }
 
         if !self.dentry.inode().is_seekable() {
-            return self.read_at(0, writer);
+            return self.read_at_with_flags(0, writer, status_flags);
         }
 
         let mut offset = self.offset.lock();
 
-        let len = self.read_at(*offset, writer)?;
+        let len = self.read_at_with_flags(*offset, writer, status_flags)?;
 
         *offset += len;
         Ok(len)
     }
 
-    pub fn write(&self, reader: &mut VmReader) -> Result<usize> {
+    fn write_with_flags(&self, reader: &mut VmReader, status_flags: StatusFlags) -> Result<usize> {
         if let Some(ref file_io) = self.file_io {
-            return file_io.write(reader);
+            return file_io.write(reader, status_flags);
         }
 
         if !self.dentry.inode().is_seekable() {
-            return self.write_at(0, reader);
+            return self.write_at_with_flags(0, reader, status_flags);
         }
 
         let mut offset = self.offset.lock();
 
-        if self.status_flags().contains(StatusFlags::O_APPEND) {
+        if status_flags.contains(StatusFlags::O_APPEND) {
             *offset = self.dentry.size();
         }
 
-        let len = self.write_at(*offset, reader)?;
+        let len = self.write_at_with_flags(*offset, reader, status_flags)?;
 
         *offset += len;
         Ok(len)
     }
 
     pub fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        self.read_at_with_flags(offset, writer, self.status_flags())
+    }
+
+    pub fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        self.write_at_with_flags(offset, reader, self.status_flags())
+    }
+
+    fn read_at_with_flags(
+        &self,
+        offset: usize,
+        writer: &mut VmWriter,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
         if let Some(ref file_io) = self.file_io {
             todo!("support read_at for FileIo");
         }
 
-        if self.status_flags().contains(StatusFlags::O_DIRECT) {
-            self.dentry.inode().read_direct_at(offset, writer)
+        let inode = self.dentry.inode();
+        let len = if let Some(device) = inode.as_device() {
+            // The device sees the status flags of the file, e.g., `O_NONBLOCK`.
+            device.read(writer, status_flags)?
+        } else if status_flags.contains(StatusFlags::O_DIRECT) {
+            inode.read_direct_at(offset, writer)?
         } else {
-            self.dentry.inode().read_at(offset, writer)
+            inode.read_at(offset, writer)?
+        };
+
+        if len > 0 {
+            self.dentry.notify(InotifyMask::IN_ACCESS);
         }
+        Ok(len)
     }
 
-    pub fn write_at(&self, mut offset: usize, reader: &mut VmReader) -> Result<usize> {
+    fn write_at_with_flags(
+        &self,
+        mut offset: usize,
+        reader: &mut VmReader,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
         if let Some(ref file_io) = self.file_io {
             todo!("support write_at for FileIo");
         }
 
-        let status_flags = self.status_flags();
         if status_flags.contains(StatusFlags::O_APPEND) {
             // If the file has the O_APPEND flag, the offset is ignored
             offset = self.dentry.size();
         }
 
-        if status_flags.contains(StatusFlags::O_DIRECT) {
-            self.dentry.inode().write_direct_at(offset, reader)
+        let inode = self.dentry.inode();
+        let len = if let Some(device) = inode.as_device() {
+            device.write(reader, status_flags)?
+        } else if status_flags.contains(StatusFlags::O_DIRECT) {
+            inode.write_direct_at(offset, reader)?
         } else {
-            self.dentry.inode().write_at(offset, reader)
+            inode.write_at(offset, reader)?
+        };
+
+        if len > 0 {
+            self.dentry.notify(InotifyMask::IN_MODIFY);
         }
+        Ok(len)
     }
 
     pub fn read_to_end(&self, buf: &mut Vec<u8>) -> Result<usize> {
@@ -218,7 +269,9 @@ impl InodeHandle_ {
             );
         }
 
-        self.dentry.inode().fallocate(mode, offset, len)
+        self.dentry.inode().fallocate(mode, offset, len)?;
+        self.dentry.notify(InotifyMask::IN_MODIFY);
+        Ok(())
     }
 
     fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
@@ -344,6 +397,16 @@ impl InodeHandle_ {
     pub fn set_group(&self, gid: Gid) -> Result<()>;
 }
 
+impl Drop for InodeHandle_ {
+    fn drop(&mut self) {
+        if self.access_mode.is_writable() {
+            self.dentry.notify(InotifyMask::IN_CLOSE_WRITE);
+        } else {
+            self.dentry.notify(InotifyMask::IN_CLOSE_NOWRITE);
+        }
+    }
+}
+
 impl Debug for InodeHandle_ {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("InodeHandle_")
@@ -393,9 +456,9 @@ impl<R> Drop for InodeHandle<R> {
 }
 
 pub trait FileIo: Pollable + Send + Sync + 'static {
-    fn read(&self, writer: &mut VmWriter) -> Result<usize>;
+    fn read(&self, writer: &mut VmWriter, status_flags: StatusFlags) -> Result<usize>;
 
-    fn write(&self, reader: &mut VmReader) -> Result<usize>;
+    fn write(&self, reader: &mut VmReader, status_flags: StatusFlags) -> Result<usize>;
 
     fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
         return_errno_with_message!(Errno::EINVAL, "ioctl is not supported");
@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use align_ext::AlignExt;
+
+use super::{InotifyMask, InotifyWatch, InotifyWatchList};
+use crate::{
+    current_userspace,
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{Inode, InodeMode, IoctlCmd, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+};
+
+/// A file-like object that provides the inotify API.
+///
+/// Each watch added by `inotify_add_watch` is identified by a watch descriptor
+/// and is registered in the `InotifyWatchList` of the watched inode. The events
+/// reported to the watches are queued in the `InotifyFile` and can be read in the
+/// format of `struct inotify_event`.
+pub struct InotifyFile {
+    watches: Mutex<BTreeMap<i32, (Arc<dyn Inode>, Arc<InotifyWatch>)>>,
+    next_wd: AtomicU32,
+    events: Mutex<VecDeque<InotifyEvent>>,
+    pollee: Pollee,
+    is_nonblocking: AtomicBool,
+    this: Weak<InotifyFile>,
+}
+
+impl InotifyFile {
+    /// The maximum number of queued events, which is the default value of
+    /// `/proc/sys/fs/inotify/max_queued_events` on Linux.
+    const MAX_QUEUED_EVENTS: usize = 16384;
+
+    /// Creates a new inotify file.
+    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+        Arc::new_cyclic(|weak_self| Self {
+            watches: Mutex::new(BTreeMap::new()),
+            next_wd: AtomicU32::new(1),
+            events: Mutex::new(VecDeque::new()),
+            pollee: Pollee::new(),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            this: weak_self.clone(),
+        })
+    }
+
+    /// Adds a new watch on `inode` or modifies the existing one.
+    ///
+    /// Returns the watch descriptor.
+    pub fn add_watch(&self, inode: Arc<dyn Inode>, mask: InotifyMask) -> Result<i32> {
+        let Some(extension) = inode.extension() else {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the inode cannot be watched");
+        };
+        let watch_list = extension.get_or_put_default::<InotifyWatchList>();
+
+        let mut watches = self.watches.lock();
+
+        if let Some(watch) = watch_list.find(&self.this) {
+            if mask.contains(InotifyMask::IN_MASK_CREATE) {
+                return_errno_with_message!(Errno::EEXIST, "the inode is already watched");
+            }
+            if mask.contains(InotifyMask::IN_MASK_ADD) {
+                watch.mask.fetch_or(mask.bits(), Ordering::Relaxed);
+            } else {
+                watch.mask.store(mask.bits(), Ordering::Relaxed);
+            }
+            return Ok(watch.wd);
+        }
+
+        let wd = self.next_wd.fetch_add(1, Ordering::Relaxed) as i32;
+        let watch = Arc::new(InotifyWatch {
+            wd,
+            mask: AtomicU32::new(mask.bits()),
+            owner: self.this.clone(),
+        });
+        watch_list.add(watch.clone());
+        watches.insert(wd, (inode, watch));
+
+        Ok(wd)
+    }
+
+    /// Removes the watch identified by `wd`.
+    ///
+    /// An `IN_IGNORED` event is queued for the removed watch.
+    pub fn remove_watch(&self, wd: i32) -> Result<()> {
+        let Some((inode, watch)) = self.watches.lock().remove(&wd) else {
+            return_errno_with_message!(Errno::EINVAL, "the watch descriptor is not valid");
+        };
+
+        if let Some(watch_list) = inode
+            .extension()
+            .and_then(|ext| ext.get::<InotifyWatchList>())
+        {
+            watch_list.remove(&watch);
+        }
+        self.queue_event(wd, InotifyMask::IN_IGNORED, 0, None);
+
+        Ok(())
+    }
+
+    /// Queues an event, which will be available to read.
+    pub(super) fn queue_event(&self, wd: i32, mask: InotifyMask, cookie: u32, name: Option<&str>) {
+        let mut events = self.events.lock();
+
+        let event = if events.len() < Self::MAX_QUEUED_EVENTS {
+            InotifyEvent {
+                wd,
+                mask,
+                cookie,
+                name: name.map(String::from),
+            }
+        } else {
+            InotifyEvent::overflow()
+        };
+
+        // Coalesce identical events as Linux does. This also ensures that at most one
+        // `IN_Q_OVERFLOW` event is queued when the queue is full.
+        if events.back() == Some(&event) {
+            return;
+        }
+        events.push_back(event);
+        drop(events);
+
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let mut events = self.events.lock();
+        if events.is_empty() {
+            return_errno_with_message!(Errno::EAGAIN, "no inotify events are available");
+        }
+
+        let mut read_len = 0;
+        while let Some(event) = events.front() {
+            let event_len = event.len();
+            if event_len > writer.avail() {
+                break;
+            }
+            event.write_to(writer)?;
+            read_len += event_len;
+            events.pop_front();
+        }
+
+        if read_len == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is too small for an event");
+        }
+        if events.is_empty() {
+            self.pollee.invalidate();
+        }
+
+        Ok(read_len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if self.events.lock().is_empty() {
+            IoEvents::empty()
+        } else {
+            IoEvents::IN
+        }
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn read_impl(&self, writer: &mut VmWriter, is_nonblocking: bool) -> Result<usize> {
+        if is_nonblocking {
+            self.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+        }
+    }
+}
+
+impl Drop for InotifyFile {
+    fn drop(&mut self) {
+        let watches = core::mem::take(&mut *self.watches.lock());
+        for (inode, watch) in watches.values() {
+            if let Some(watch_list) = inode
+                .extension()
+                .and_then(|ext| ext.get::<InotifyWatchList>())
+            {
+                watch_list.remove(watch);
+            }
+        }
+    }
+}
+
+impl Pollable for InotifyFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for InotifyFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_impl(writer, self.is_nonblocking())
+    }
+
+    fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_impl(writer, true)
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        match cmd {
+            IoctlCmd::FIONREAD => {
+                let len: usize = self.events.lock().iter().map(InotifyEvent::len).sum();
+                current_userspace!().write_val(arg, &(len as i32))?;
+                Ok(0)
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "the ioctl command is not supported"),
+        }
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.is_nonblocking.store(
+            new_flags.contains(StatusFlags::O_NONBLOCK),
+            Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `InotifyFile` to it.
+        Metadata::new_file(
+            0,
+            InodeMode::from_bits_truncate(0o600),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+/// An event queued in an [`InotifyFile`].
+#[derive(Debug, PartialEq, Eq)]
+struct InotifyEvent {
+    wd: i32,
+    mask: InotifyMask,
+    cookie: u32,
+    name: Option<String>,
+}
+
+/// The header of `struct inotify_event`.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct CInotifyEvent {
+    wd: i32,
+    mask: u32,
+    cookie: u32,
+    len: u32,
+}
+
+impl InotifyEvent {
+    fn overflow() -> Self {
+        Self {
+            wd: -1,
+            mask: InotifyMask::IN_Q_OVERFLOW,
+            cookie: 0,
+            name: None,
+        }
+    }
+
+    /// Returns the length of the name field, including the null terminator and
+    /// the padding that aligns the next event.
+    fn name_len(&self) -> usize {
+        match self.name.as_ref() {
+            Some(name) => (name.len() + 1).align_up(size_of::<CInotifyEvent>()),
+            None => 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        size_of::<CInotifyEvent>() + self.name_len()
+    }
+
+    fn write_to(&self, writer: &mut VmWriter) -> Result<()> {
+        let name_len = self.name_len();
+        let header = CInotifyEvent {
+            wd: self.wd,
+            mask: self.mask.bits(),
+            cookie: self.cookie,
+            len: name_len as u32,
+        };
+        writer.write_val(&header)?;
+
+        if let Some(name) = self.name.as_ref() {
+            let mut buf = vec![0u8; name_len];
+            buf[..name.len()].copy_from_slice(name.as_bytes());
+            writer.write_fallible(&mut buf.as_slice().into())?;
+        }
+
+        Ok(())
+    }
+}
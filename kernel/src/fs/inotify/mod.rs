@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The inotify mechanism for monitoring file system events.
+//!
+//! The watches of an inode are kept in the inode's [`Extension`] as an
+//! `InotifyWatchList`, so any file system that supports inode extensions
+//! (e.g., ramfs, ext2 and exfat) can be watched. The VFS dentry layer reports
+//! the events by calling [`notify_self`] and [`notify_child`] after the
+//! corresponding operations succeed.
+//!
+//! [`Extension`]: crate::fs::utils::Extension
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+pub use file::InotifyFile;
+
+use crate::{
+    fs::utils::{Inode, InodeType},
+    prelude::*,
+};
+
+mod file;
+
+bitflags! {
+    /// The inotify event mask and the flags of `inotify_add_watch`.
+    pub struct InotifyMask: u32 {
+        /// File was accessed.
+        const IN_ACCESS        = 0x0000_0001;
+        /// File was modified.
+        const IN_MODIFY        = 0x0000_0002;
+        /// Metadata changed.
+        const IN_ATTRIB        = 0x0000_0004;
+        /// Writable file was closed.
+        const IN_CLOSE_WRITE   = 0x0000_0008;
+        /// Unwritable file was closed.
+        const IN_CLOSE_NOWRITE = 0x0000_0010;
+        /// File was opened.
+        const IN_OPEN          = 0x0000_0020;
+        /// File was moved from the watched directory.
+        const IN_MOVED_FROM    = 0x0000_0040;
+        /// File was moved to the watched directory.
+        const IN_MOVED_TO      = 0x0000_0080;
+        /// File was created in the watched directory.
+        const IN_CREATE        = 0x0000_0100;
+        /// File was deleted from the watched directory.
+        const IN_DELETE        = 0x0000_0200;
+        /// The watched file was deleted.
+        const IN_DELETE_SELF   = 0x0000_0400;
+        /// The watched file was moved.
+        const IN_MOVE_SELF     = 0x0000_0800;
+
+        /// The backing file system was unmounted.
+        const IN_UNMOUNT       = 0x0000_2000;
+        /// The event queue overflowed.
+        const IN_Q_OVERFLOW    = 0x0000_4000;
+        /// The watch was removed.
+        const IN_IGNORED       = 0x0000_8000;
+
+        /// Only watch the path if it is a directory.
+        const IN_ONLYDIR       = 0x0100_0000;
+        /// Do not follow a symbolic link.
+        const IN_DONT_FOLLOW   = 0x0200_0000;
+        /// Exclude events on unlinked children.
+        const IN_EXCL_UNLINK   = 0x0400_0000;
+        /// Only create watches.
+        const IN_MASK_CREATE   = 0x1000_0000;
+        /// Add to the mask of an already existing watch.
+        const IN_MASK_ADD      = 0x2000_0000;
+        /// The subject of the event is a directory.
+        const IN_ISDIR         = 0x4000_0000;
+        /// Only send the event once.
+        const IN_ONESHOT       = 0x8000_0000;
+
+        const IN_CLOSE = Self::IN_CLOSE_WRITE.bits | Self::IN_CLOSE_NOWRITE.bits;
+        const IN_MOVE = Self::IN_MOVED_FROM.bits | Self::IN_MOVED_TO.bits;
+        const IN_ALL_EVENTS = Self::IN_ACCESS.bits
+            | Self::IN_MODIFY.bits
+            | Self::IN_ATTRIB.bits
+            | Self::IN_CLOSE.bits
+            | Self::IN_OPEN.bits
+            | Self::IN_MOVE.bits
+            | Self::IN_CREATE.bits
+            | Self::IN_DELETE.bits
+            | Self::IN_DELETE_SELF.bits
+            | Self::IN_MOVE_SELF.bits;
+    }
+}
+
+impl InotifyMask {
+    /// The events that are always reported, regardless of the watch mask.
+    const ALWAYS_REPORTED: Self = Self::from_bits_truncate(
+        Self::IN_UNMOUNT.bits | Self::IN_Q_OVERFLOW.bits | Self::IN_IGNORED.bits,
+    );
+}
+
+/// The inotify watches placed on one inode.
+#[derive(Default)]
+struct InotifyWatchList {
+    watches: Mutex<Vec<Arc<InotifyWatch>>>,
+}
+
+impl InotifyWatchList {
+    fn find(&self, owner: &Weak<InotifyFile>) -> Option<Arc<InotifyWatch>> {
+        self.watches
+            .lock()
+            .iter()
+            .find(|watch| Weak::ptr_eq(&watch.owner, owner))
+            .cloned()
+    }
+
+    fn add(&self, watch: Arc<InotifyWatch>) {
+        self.watches.lock().push(watch);
+        NR_WATCHES.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn remove(&self, watch: &Arc<InotifyWatch>) {
+        let mut watches = self.watches.lock();
+        if let Some(pos) = watches.iter().position(|w| Arc::ptr_eq(w, watch)) {
+            watches.swap_remove(pos);
+            NR_WATCHES.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A watch that an inotify instance places on an inode.
+struct InotifyWatch {
+    wd: i32,
+    mask: AtomicU32,
+    owner: Weak<InotifyFile>,
+}
+
+impl InotifyWatch {
+    fn mask(&self) -> InotifyMask {
+        InotifyMask::from_bits_truncate(self.mask.load(Ordering::Relaxed))
+    }
+}
+
+/// The total number of watches in the system.
+///
+/// This allows the hot paths (e.g., `read` and `write`) to skip the
+/// extension lookup when nobody is watching anything.
+static NR_WATCHES: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns whether any inotify watch exists in the system.
+pub fn has_watches() -> bool {
+    NR_WATCHES.load(Ordering::Relaxed) > 0
+}
+
+/// Allocates a cookie that associates an `IN_MOVED_FROM` event with
+/// the corresponding `IN_MOVED_TO` event.
+pub fn new_cookie() -> u32 {
+    static NEXT_COOKIE: AtomicU32 = AtomicU32::new(1);
+    NEXT_COOKIE.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Reports `events` that happened on `inode` itself to the watches on it.
+pub fn notify_self(inode: &dyn Inode, events: InotifyMask) {
+    if !has_watches() {
+        return;
+    }
+    notify(inode, events | isdir_flag(inode), None, 0);
+}
+
+/// Reports `events` that happened on a child of the directory `dir_inode` to
+/// the watches on the directory.
+///
+/// The child is given as `child_inode` and is named `name` in the directory.
+pub fn notify_child(
+    dir_inode: &dyn Inode,
+    child_inode: &dyn Inode,
+    events: InotifyMask,
+    name: &str,
+    cookie: u32,
+) {
+    if !has_watches() {
+        return;
+    }
+    notify(
+        dir_inode,
+        events | isdir_flag(child_inode),
+        Some(name),
+        cookie,
+    );
+}
+
+/// Reports that one link to `inode` has been removed.
+///
+/// The change of the link count is reported as `IN_ATTRIB`, and `IN_DELETE_SELF`
+/// is reported if the last link is gone.
+pub fn notify_unlinked(inode: &dyn Inode) {
+    if !has_watches() {
+        return;
+    }
+    let metadata = inode.metadata();
+    if metadata.type_ != InodeType::Dir {
+        notify_self(inode, InotifyMask::IN_ATTRIB);
+    }
+    if metadata.nlinks == 0 || metadata.type_ == InodeType::Dir {
+        notify_self(inode, InotifyMask::IN_DELETE_SELF);
+    }
+}
+
+fn isdir_flag(inode: &dyn Inode) -> InotifyMask {
+    if inode.type_() == InodeType::Dir {
+        InotifyMask::IN_ISDIR
+    } else {
+        InotifyMask::empty()
+    }
+}
+
+fn notify(inode: &dyn Inode, events: InotifyMask, name: Option<&str>, cookie: u32) {
+    let Some(watch_list) = inode
+        .extension()
+        .and_then(|ext| ext.get::<InotifyWatchList>())
+    else {
+        return;
+    };
+
+    // Take a snapshot so that the watch list is not locked while
+    // the events are being queued.
+    let watches = watch_list.watches.lock().clone();
+    for watch in watches {
+        let Some(owner) = watch.owner.upgrade() else {
+            continue;
+        };
+        let watch_mask = watch.mask();
+        if !watch_mask.intersects(events & InotifyMask::IN_ALL_EVENTS) {
+            continue;
+        }
+
+        let reported = (events & (watch_mask | InotifyMask::ALWAYS_REPORTED))
+            | (events & InotifyMask::IN_ISDIR);
+        owner.queue_event(watch.wd, reported, cookie, name);
+
+        if (events.contains(InotifyMask::IN_DELETE_SELF) && name.is_none())
+            || watch_mask.contains(InotifyMask::IN_ONESHOT)
+        {
+            let _ = owner.remove_watch(watch.wd);
+        }
+    }
+}
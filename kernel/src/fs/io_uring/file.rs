@@ -0,0 +1,378 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use ostd::sync::WaitQueue;
+
+use super::{
+    request::Request,
+    rings::Rings,
+    uapi::{
+        Features, IoUringCqe, IoUringOp, IoUringParams, IoUringProbe, IoUringProbeOp, RegisterOp,
+        SetupFlags, IORING_OFF_CQ_RING, IORING_OFF_SQES, IORING_OFF_SQ_RING, IO_URING_OP_SUPPORTED,
+    },
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        file_table::FileDesc,
+        utils::{InodeMode, Metadata},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    syscall::EventFile,
+    vm::vmo::Vmo,
+};
+
+/// The maximum number of SQ entries.
+const IORING_MAX_ENTRIES: u32 = 32768;
+/// The maximum number of CQ entries.
+const IORING_MAX_CQ_ENTRIES: u32 = 2 * IORING_MAX_ENTRIES;
+/// The maximum number of registered files.
+const IORING_MAX_FIXED_FILES: u32 = 1 << 15;
+
+/// A file-like object that represents an io_uring instance.
+pub struct IoUringFile {
+    rings: Rings,
+    /// The requests that are submitted but not completed.
+    requests: Mutex<BTreeMap<u64, Arc<Request>>>,
+    next_request_id: AtomicU64,
+    /// The number of completions, excluding the completions of timeouts.
+    num_completions: AtomicU64,
+    registered_files: Mutex<Vec<Option<Arc<dyn FileLike>>>>,
+    /// The eventfd that is signaled when CQEs are posted.
+    eventfd: Mutex<Option<Arc<dyn FileLike>>>,
+    pollee: Pollee,
+    cq_wait_queue: WaitQueue,
+    weak_self: Weak<IoUringFile>,
+}
+
+impl IoUringFile {
+    /// Creates an io_uring instance with at least `entries` SQ entries.
+    ///
+    /// The output fields of `params` are filled in, so that the user knows how to access the
+    /// rings.
+    pub fn new(entries: u32, params: &mut IoUringParams) -> Result<Arc<Self>> {
+        let flags = SetupFlags::from_bits(params.flags)
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the setup flags are invalid"))?;
+        if flags.intersects(!(SetupFlags::CQSIZE | SetupFlags::CLAMP)) {
+            return_errno_with_message!(Errno::EINVAL, "the setup flags are not supported");
+        }
+        if params.resv != [0; 3] {
+            return_errno_with_message!(Errno::EINVAL, "the reserved fields are not zero");
+        }
+
+        if entries == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the number of entries cannot be zero");
+        }
+        if entries > IORING_MAX_ENTRIES && !flags.contains(SetupFlags::CLAMP) {
+            return_errno_with_message!(Errno::EINVAL, "too many SQ entries");
+        }
+        let sq_entries = entries.min(IORING_MAX_ENTRIES).next_power_of_two();
+
+        let cq_entries = if flags.contains(SetupFlags::CQSIZE) {
+            let cq_entries = params.cq_entries;
+            if cq_entries == 0 {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the number of CQ entries cannot be zero"
+                );
+            }
+            if cq_entries > IORING_MAX_CQ_ENTRIES && !flags.contains(SetupFlags::CLAMP) {
+                return_errno_with_message!(Errno::EINVAL, "too many CQ entries");
+            }
+            let cq_entries = cq_entries.min(IORING_MAX_CQ_ENTRIES).next_power_of_two();
+            if cq_entries < sq_entries {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the CQ entries cannot be fewer than the SQ entries"
+                );
+            }
+            cq_entries
+        } else {
+            2 * sq_entries
+        };
+
+        let rings = Rings::new(sq_entries, cq_entries)?;
+
+        params.sq_entries = sq_entries;
+        params.cq_entries = cq_entries;
+        params.features = (Features::SINGLE_MMAP
+            | Features::SUBMIT_STABLE
+            | Features::RW_CUR_POS
+            | Features::FAST_POLL)
+            .bits();
+        params.sq_off = rings.sq_offsets();
+        params.cq_off = rings.cq_offsets();
+
+        Ok(Arc::new_cyclic(|weak_self| Self {
+            rings,
+            requests: Mutex::new(BTreeMap::new()),
+            next_request_id: AtomicU64::new(0),
+            num_completions: AtomicU64::new(0),
+            registered_files: Mutex::new(Vec::new()),
+            eventfd: Mutex::new(None),
+            pollee: Pollee::new(),
+            cq_wait_queue: WaitQueue::new(),
+            weak_self: weak_self.clone(),
+        }))
+    }
+
+    /// Submits at most `to_submit` SQEs.
+    ///
+    /// The SQEs are consumed in order. An SQE that fails to be prepared is still consumed, and
+    /// its error is posted as a CQE, but the submission stops there. This method returns the
+    /// number of the consumed SQEs.
+    pub fn submit(&self, to_submit: u32, ctx: &Context) -> Result<u32> {
+        let mut num_submitted = 0;
+
+        while num_submitted < to_submit {
+            let Some(sqe) = self.rings.pop_sqe()? else {
+                break;
+            };
+            num_submitted += 1;
+
+            let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+            match Request::new(&sqe, self, id, ctx) {
+                Ok(request) => {
+                    self.requests.lock().insert(id, request.clone());
+                    request.start();
+                }
+                Err(err) => {
+                    self.post_cqe(sqe.user_data, -(err.error() as i32), true);
+                    break;
+                }
+            }
+        }
+
+        Ok(num_submitted)
+    }
+
+    /// Waits until there are at least `min_complete` CQEs.
+    ///
+    /// The waiting can be interrupted by a signal.
+    pub fn wait_cqes(&self, min_complete: u32) -> Result<()> {
+        let min_complete = min_complete.min(self.rings.cq_entries());
+        self.cq_wait_queue
+            .pause_until(|| (self.rings.cq_ready() >= min_complete).then_some(()))
+    }
+
+    /// Performs an `io_uring_register` operation.
+    pub fn register(&self, op: RegisterOp, arg: Vaddr, nr_args: u32, ctx: &Context) -> Result<()> {
+        match op {
+            RegisterOp::RegisterFiles => self.register_files(arg, nr_args, ctx),
+            RegisterOp::UnregisterFiles => {
+                if arg != 0 || nr_args != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "the arguments must be empty");
+                }
+                let mut registered_files = self.registered_files.lock();
+                if registered_files.is_empty() {
+                    return_errno_with_message!(Errno::ENXIO, "no files are registered");
+                }
+                registered_files.clear();
+                Ok(())
+            }
+            RegisterOp::RegisterEventfd => {
+                if nr_args != 1 {
+                    return_errno_with_message!(Errno::EINVAL, "only one eventfd can be registered");
+                }
+                let mut eventfd = self.eventfd.lock();
+                if eventfd.is_some() {
+                    return_errno_with_message!(Errno::EBUSY, "an eventfd is already registered");
+                }
+                let fd = ctx.user_space().read_val::<FileDesc>(arg)?;
+                let file = ctx.posix_thread.file_table().lock().get_file(fd)?.clone();
+                if file.downcast_ref::<EventFile>().is_none() {
+                    return_errno_with_message!(Errno::EINVAL, "the file is not an eventfd");
+                }
+                *eventfd = Some(file);
+                Ok(())
+            }
+            RegisterOp::UnregisterEventfd => {
+                if arg != 0 || nr_args != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "the arguments must be empty");
+                }
+                if self.eventfd.lock().take().is_none() {
+                    return_errno_with_message!(Errno::ENXIO, "no eventfd is registered");
+                }
+                Ok(())
+            }
+            RegisterOp::RegisterProbe => self.register_probe(arg, nr_args, ctx),
+            _ => return_errno_with_message!(Errno::EINVAL, "the register opcode is not supported"),
+        }
+    }
+
+    fn register_files(&self, arg: Vaddr, nr_args: u32, ctx: &Context) -> Result<()> {
+        let mut registered_files = self.registered_files.lock();
+        if !registered_files.is_empty() {
+            return_errno_with_message!(Errno::EBUSY, "the files are already registered");
+        }
+        if nr_args == 0 || nr_args > IORING_MAX_FIXED_FILES {
+            return_errno_with_message!(Errno::EINVAL, "the number of files is invalid");
+        }
+
+        let user_space = ctx.user_space();
+        let file_table = ctx.posix_thread.file_table().lock();
+        let mut files = Vec::with_capacity(nr_args as usize);
+        for i in 0..nr_args as usize {
+            let fd = user_space.read_val::<FileDesc>(arg + i * size_of::<FileDesc>())?;
+            // A file descriptor of `-1` leaves a sparse slot.
+            if fd == -1 {
+                files.push(None);
+                continue;
+            }
+
+            let file = file_table.get_file(fd)?;
+            if file.downcast_ref::<IoUringFile>().is_some() {
+                return_errno_with_message!(Errno::EBADF, "an io_uring file cannot be registered");
+            }
+            files.push(Some(file.clone()));
+        }
+
+        *registered_files = files;
+        Ok(())
+    }
+
+    fn register_probe(&self, arg: Vaddr, nr_args: u32, ctx: &Context) -> Result<()> {
+        let user_space = ctx.user_space();
+
+        let ops_len = nr_args.min(IoUringOp::LAST as u32 + 1) as u8;
+        let probe = IoUringProbe {
+            last_op: IoUringOp::LAST as u8,
+            ops_len,
+            resv: 0,
+            resv2: [0; 3],
+        };
+        user_space.write_val(arg, &probe)?;
+
+        for op in 0..ops_len {
+            let flags = if IoUringOp::try_from(op).is_ok() {
+                IO_URING_OP_SUPPORTED
+            } else {
+                0
+            };
+            let probe_op = IoUringProbeOp {
+                op,
+                resv: 0,
+                flags,
+                resv2: 0,
+            };
+            let offset = size_of::<IoUringProbe>() + op as usize * size_of::<IoUringProbeOp>();
+            user_space.write_val(arg + offset, &probe_op)?;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn weak_self(&self) -> Weak<IoUringFile> {
+        self.weak_self.clone()
+    }
+
+    /// Returns the registered file at `index`.
+    pub(super) fn registered_file(&self, index: i32) -> Result<Arc<dyn FileLike>> {
+        let registered_files = self.registered_files.lock();
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| registered_files.get(index))
+            .and_then(|file| file.clone())
+            .ok_or_else(|| Error::with_message(Errno::EBADF, "the registered file does not exist"))
+    }
+
+    /// Returns the number of completions, excluding the completions of timeouts.
+    pub(super) fn num_completions(&self) -> u64 {
+        self.num_completions.load(Ordering::Relaxed)
+    }
+
+    /// Completes a request with the result.
+    pub(super) fn complete(&self, request: &Request, res: i32) {
+        self.requests.lock().remove(&request.id());
+        self.post_cqe(request.user_data(), res, !request.is_timeout());
+    }
+
+    fn post_cqe(&self, user_data: u64, res: i32, is_counted: bool) {
+        let cqe = IoUringCqe {
+            user_data,
+            res,
+            flags: 0,
+        };
+        if let Err(err) = self.rings.push_cqe(&cqe) {
+            warn!("failed to post the CQE: {:?}", err);
+        }
+
+        self.pollee.notify(IoEvents::IN);
+        self.cq_wait_queue.wake_all();
+        if let Some(eventfd) = self.eventfd.lock().clone() {
+            let _ = eventfd.write_bytes(&1u64.to_ne_bytes());
+        }
+
+        if is_counted {
+            self.count_completion();
+        }
+    }
+
+    /// Counts a completion and completes the timeouts that wait for it.
+    fn count_completion(&self) {
+        let num_completions = self.num_completions.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let completed_timeouts: Vec<_> = self
+            .requests
+            .lock()
+            .values()
+            .filter(|request| {
+                request
+                    .target_completions()
+                    .is_some_and(|target| target <= num_completions)
+            })
+            .cloned()
+            .collect();
+        for timeout in completed_timeouts {
+            timeout.complete(0);
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+
+        if self.rings.cq_ready() > 0 {
+            events |= IoEvents::IN;
+        }
+        if self.rings.sq_has_room() {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+}
+
+impl Pollable for IoUringFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        // The user consumes CQEs and produces SQEs without notifying the kernel, so the cached
+        // events may be stale.
+        self.pollee.invalidate();
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for IoUringFile {
+    fn mmap_vmo(&self, offset: usize) -> Result<(Vmo, usize)> {
+        let vmo = match offset {
+            // The SQ and the CQ share the same memory, as `Features::SINGLE_MMAP` tells.
+            IORING_OFF_SQ_RING | IORING_OFF_CQ_RING => self.rings.vmo(),
+            IORING_OFF_SQES => self.rings.sqes(),
+            _ => return_errno_with_message!(Errno::EINVAL, "the mmap offset is invalid"),
+        };
+        Ok((vmo.dup()?, 0))
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `IoUringFile` to it.
+        Metadata::new_file(
+            0,
+            InodeMode::from_bits_truncate(0o600),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The io_uring asynchronous I/O interface.
+//!
+//! An io_uring instance has two rings shared with the user space: the submission queue (SQ),
+//! where the user puts submission queue entries (SQEs), and the completion queue (CQ), where the
+//! kernel puts completion queue entries (CQEs). Both are backed by VMOs, which the user maps by
+//! calling `mmap` on the io_uring file.
+//!
+//! The SQEs are consumed by `io_uring_enter`, which prepares them as requests and runs them
+//! asynchronously on the worker pools of the work queues. The io_uring file is readable when the
+//! CQ is not empty, so the completions can also be waited for with `poll` or `epoll`.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/io_uring.7.html>.
+
+mod file;
+mod request;
+mod rings;
+mod uapi;
+
+pub use file::IoUringFile;
+pub use uapi::{EnterFlags, IoUringParams, RegisterOp};
@@ -0,0 +1,647 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    cmp::min,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use super::{
+    file::IoUringFile,
+    uapi::{IoUringOp, IoUringSqe, SqeFlags, IORING_FSYNC_DATASYNC, IORING_TIMEOUT_ABS},
+};
+use crate::{
+    events::{IoEvents, Observer},
+    fs::{
+        file_handle::FileLike,
+        file_table::{FdFlags, FileTable},
+        inode_handle::InodeHandle,
+        utils::{CreationFlags, StatusFlags},
+    },
+    net::socket::{MessageHeader, SendRecvFlags, Socket, SocketAddr},
+    prelude::*,
+    process::{
+        signal::{PollAdaptor, Pollable},
+        Process,
+    },
+    thread::work_queue::{submit_work_item, work_item::WorkItem, WorkPriority},
+    time::{
+        clocks::MonotonicClock,
+        timer::{Timeout, Timer},
+        timespec_t,
+    },
+    util::{copy_io_vecs_from_user, net::socket_addr_into_c_bytes_and},
+};
+
+/// The maximum number of bytes that a request transfers.
+///
+/// The data are copied through a kernel buffer because the workers cannot access the user
+/// space directly, so larger transfers are made short, as `read` and `write` permit.
+const MAX_TRANSFER_LEN: usize = 1 << 20;
+
+/// The maximum number of IO vectors of `READV` and `WRITEV`.
+const IOV_MAX: usize = 1024;
+
+/// A request that is submitted to an io_uring instance.
+///
+/// A request is run by the workers of the work queues. The operations are executed in the
+/// non-blocking mode, regardless of the status flags of the file. If its file is not ready,
+/// the request registers an observer to the file and is run again when the file has events,
+/// so it never blocks a worker while waiting.
+pub(super) struct Request {
+    id: u64,
+    user_data: u64,
+    op: Op,
+    file: Option<Arc<dyn FileLike>>,
+    ring: Weak<IoUringFile>,
+    /// The process whose memory is accessed by the request.
+    process: Weak<Process>,
+    /// The file table where the accepted sockets are installed.
+    file_table: Weak<SpinLock<FileTable>>,
+    work_item: Arc<WorkItem>,
+    timer: Option<Arc<Timer>>,
+    /// The observer that runs the request again when the file has events.
+    ///
+    /// The lock is held while the request is running, so that a request that is woken up
+    /// while it is running does not run concurrently with itself.
+    poller: Mutex<Option<PollAdaptor<RequestWaker>>>,
+    is_completed: AtomicBool,
+}
+
+/// An operation with the arguments prepared at the submission.
+///
+/// The arguments are copied from the user space at the submission, so the user can reuse
+/// the memory after `io_uring_enter` returns.
+enum Op {
+    Nop,
+    Read {
+        buf: Vaddr,
+        len: usize,
+        offset: Option<usize>,
+    },
+    Write {
+        buf: Vaddr,
+        len: usize,
+        offset: Option<usize>,
+    },
+    Readv {
+        io_vecs: Box<[(Vaddr, usize)]>,
+        offset: Option<usize>,
+    },
+    Writev {
+        io_vecs: Box<[(Vaddr, usize)]>,
+        offset: Option<usize>,
+    },
+    Fsync {
+        is_datasync: bool,
+    },
+    PollAdd {
+        events: IoEvents,
+    },
+    Timeout {
+        timeout: Timeout,
+        /// The number of completions that completes the timeout before it expires.
+        target_completions: Option<u64>,
+    },
+    Accept {
+        addr: Vaddr,
+        addr_len_ptr: Vaddr,
+        flags: AcceptFlags,
+    },
+    Recv {
+        buf: Vaddr,
+        len: usize,
+        flags: SendRecvFlags,
+    },
+    Send {
+        buf: Vaddr,
+        len: usize,
+        flags: SendRecvFlags,
+    },
+}
+
+impl Request {
+    /// Prepares a request from an SQE.
+    pub(super) fn new(
+        sqe: &IoUringSqe,
+        ring: &IoUringFile,
+        id: u64,
+        ctx: &Context,
+    ) -> Result<Arc<Self>> {
+        let opcode = IoUringOp::try_from(sqe.opcode)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the opcode is not supported"))?;
+        let sqe_flags = SqeFlags::from_bits(sqe.flags)
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the SQE flags are invalid"))?;
+        if sqe_flags.intersects(
+            SqeFlags::IO_DRAIN
+                | SqeFlags::IO_LINK
+                | SqeFlags::IO_HARDLINK
+                | SqeFlags::BUFFER_SELECT,
+        ) {
+            return_errno_with_message!(Errno::EINVAL, "the SQE flags are not supported");
+        }
+        if sqe.ioprio != 0 || sqe.buf_index != 0 || sqe.personality != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the SQE fields are not supported");
+        }
+
+        let op = Op::prepare(opcode, sqe, ring, ctx)?;
+        let file = if op.has_file() {
+            let file = if sqe_flags.contains(SqeFlags::FIXED_FILE) {
+                ring.registered_file(sqe.fd)?
+            } else {
+                let file_table = ctx.posix_thread.file_table().lock();
+                file_table.get_file(sqe.fd)?.clone()
+            };
+            Some(file)
+        } else {
+            None
+        };
+
+        let request = Arc::new_cyclic(|weak_self: &Weak<Request>| {
+            let weak_self = weak_self.clone();
+            let work_item = WorkItem::new(Box::new(move || {
+                if let Some(request) = weak_self.upgrade() {
+                    request.run();
+                }
+            }));
+
+            let timer = matches!(op, Op::Timeout { .. }).then(|| {
+                let work_item = work_item.clone();
+                // The callback runs in the interrupt context, so it only submits the work.
+                MonotonicClock::timer_manager().create_timer(move || {
+                    submit_work_item(work_item.clone(), WorkPriority::Normal);
+                })
+            });
+
+            Request {
+                id,
+                user_data: sqe.user_data,
+                op,
+                file,
+                ring: ring.weak_self(),
+                process: ctx.posix_thread.weak_process(),
+                file_table: Arc::downgrade(ctx.posix_thread.file_table()),
+                work_item,
+                timer,
+                poller: Mutex::new(None),
+                is_completed: AtomicBool::new(false),
+            }
+        });
+
+        Ok(request)
+    }
+
+    pub(super) fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(super) fn user_data(&self) -> u64 {
+        self.user_data
+    }
+
+    /// Returns whether the request is a timeout.
+    ///
+    /// The completions of timeouts are not counted by other timeouts.
+    pub(super) fn is_timeout(&self) -> bool {
+        matches!(self.op, Op::Timeout { .. })
+    }
+
+    /// Returns the number of completions that completes the request if it is a timeout.
+    pub(super) fn target_completions(&self) -> Option<u64> {
+        match self.op {
+            Op::Timeout {
+                target_completions, ..
+            } => target_completions,
+            _ => None,
+        }
+    }
+
+    /// Starts running the request asynchronously.
+    pub(super) fn start(&self) {
+        match &self.op {
+            Op::Timeout { timeout, .. } => {
+                self.timer.as_ref().unwrap().set_timeout(timeout.clone())
+            }
+            _ => {
+                submit_work_item(self.work_item.clone(), WorkPriority::Normal);
+            }
+        }
+    }
+
+    /// Completes the request with the result.
+    ///
+    /// A request is completed only once. The later calls have no effects.
+    pub(super) fn complete(&self, res: i32) {
+        if self.is_completed.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        if let Some(timer) = &self.timer {
+            timer.cancel();
+        }
+
+        if let Some(ring) = self.ring.upgrade() {
+            ring.complete(self, res);
+        }
+    }
+
+    fn run(&self) {
+        let mut poller = self.poller.lock();
+        if self.is_completed.load(Ordering::Acquire) {
+            return;
+        }
+
+        let result = if self.is_timeout() {
+            // A timeout only runs when its timer expires.
+            Err(Error::with_message(Errno::ETIME, "the timeout expired"))
+        } else {
+            loop {
+                let io_events = self.op.io_events();
+                if let Some(mask) = io_events {
+                    let adaptor = poller.insert(PollAdaptor::with_observer(RequestWaker(
+                        self.work_item.clone(),
+                    )));
+                    if self
+                        .file()
+                        .poll(mask, Some(adaptor.as_handle_mut()))
+                        .is_empty()
+                    {
+                        return;
+                    }
+                }
+
+                match self.execute() {
+                    Err(err) if err.error() == Errno::EAGAIN && io_events.is_some() => (),
+                    result => break result,
+                }
+            }
+        };
+        *poller = None;
+
+        let res = match result {
+            Ok(res) => res,
+            Err(err) => -(err.error() as i32),
+        };
+        self.complete(res);
+    }
+
+    fn execute(&self) -> Result<i32> {
+        match &self.op {
+            Op::Nop => Ok(0),
+            Op::Read { buf, len, offset } => {
+                let mut kernel_buf = vec![0u8; min(*len, MAX_TRANSFER_LEN)];
+                let read_len = self.read_file(*offset, &mut kernel_buf)?;
+                self.process()?
+                    .root_vmar()
+                    .write_for_user(*buf, &kernel_buf[..read_len])?;
+                Ok(read_len as i32)
+            }
+            Op::Write { buf, len, offset } => {
+                let mut kernel_buf = vec![0u8; min(*len, MAX_TRANSFER_LEN)];
+                self.process()?
+                    .root_vmar()
+                    .read_for_user(*buf, &mut kernel_buf)?;
+                let written_len = self.write_file(*offset, &kernel_buf)?;
+                Ok(written_len as i32)
+            }
+            Op::Readv { io_vecs, offset } => {
+                let mut kernel_buf = vec![0u8; total_len(io_vecs)];
+                let read_len = self.read_file(*offset, &mut kernel_buf)?;
+                self.scatter(io_vecs, &kernel_buf[..read_len])?;
+                Ok(read_len as i32)
+            }
+            Op::Writev { io_vecs, offset } => {
+                let mut kernel_buf = vec![0u8; total_len(io_vecs)];
+                self.gather(io_vecs, &mut kernel_buf)?;
+                let written_len = self.write_file(*offset, &kernel_buf)?;
+                Ok(written_len as i32)
+            }
+            Op::Fsync { is_datasync } => {
+                let inode_handle = self
+                    .file()
+                    .downcast_ref::<InodeHandle>()
+                    .ok_or_else(|| Error::with_message(Errno::EINVAL, "not inode"))?;
+                if *is_datasync {
+                    inode_handle.dentry().sync_data()?;
+                } else {
+                    inode_handle.dentry().sync_all()?;
+                }
+                Ok(0)
+            }
+            Op::PollAdd { events } => {
+                let events = self.file().poll(*events, None);
+                if events.is_empty() {
+                    return_errno_with_message!(Errno::EAGAIN, "the file has no events");
+                }
+                Ok(events.bits() as i32)
+            }
+            Op::Timeout { .. } => unreachable!("a timeout is never executed"),
+            Op::Accept {
+                addr,
+                addr_len_ptr,
+                flags,
+            } => {
+                let (connected_socket, socket_addr) = self.socket()?.accept_nonblocking()?;
+
+                if flags.contains(AcceptFlags::SOCK_NONBLOCK) {
+                    connected_socket.set_status_flags(StatusFlags::O_NONBLOCK)?;
+                }
+                let fd_flags = if flags.contains(AcceptFlags::SOCK_CLOEXEC) {
+                    FdFlags::CLOEXEC
+                } else {
+                    FdFlags::empty()
+                };
+
+                if *addr != 0 {
+                    self.write_socket_addr(&socket_addr, *addr, *addr_len_ptr)?;
+                }
+
+                let file_table = self.file_table.upgrade().ok_or_else(|| {
+                    Error::with_message(Errno::ECANCELED, "the file table has been dropped")
+                })?;
+                let fd = file_table.lock().insert(connected_socket, fd_flags);
+                Ok(fd)
+            }
+            Op::Recv { buf, len, flags } => {
+                let mut kernel_buf = vec![0u8; min(*len, MAX_TRANSFER_LEN)];
+                let (recv_len, _) = self.socket()?.recvmsg(
+                    &mut VmWriter::from(kernel_buf.as_mut_slice()).to_fallible(),
+                    *flags | SendRecvFlags::MSG_DONTWAIT,
+                )?;
+                self.process()?
+                    .root_vmar()
+                    .write_for_user(*buf, &kernel_buf[..recv_len])?;
+                Ok(recv_len as i32)
+            }
+            Op::Send { buf, len, flags } => {
+                let mut kernel_buf = vec![0u8; min(*len, MAX_TRANSFER_LEN)];
+                self.process()?
+                    .root_vmar()
+                    .read_for_user(*buf, &mut kernel_buf)?;
+                let sent_len = self.socket()?.sendmsg(
+                    &mut VmReader::from(kernel_buf.as_slice()).to_fallible(),
+                    MessageHeader::new(None, Vec::new()),
+                    *flags | SendRecvFlags::MSG_DONTWAIT,
+                )?;
+                Ok(sent_len as i32)
+            }
+        }
+    }
+
+    fn file(&self) -> &Arc<dyn FileLike> {
+        self.file.as_ref().unwrap()
+    }
+
+    fn socket(&self) -> Result<Arc<dyn Socket>> {
+        self.file()
+            .clone()
+            .as_socket()
+            .ok_or_else(|| Error::with_message(Errno::ENOTSOCK, "the file is not a socket"))
+    }
+
+    fn process(&self) -> Result<Arc<Process>> {
+        self.process
+            .upgrade()
+            .ok_or_else(|| Error::with_message(Errno::ECANCELED, "the process has been dropped"))
+    }
+
+    fn read_file(&self, offset: Option<usize>, buf: &mut [u8]) -> Result<usize> {
+        if let Some(offset) = offset {
+            return self.file().read_bytes_at(offset, buf);
+        }
+
+        let mut writer = VmWriter::from(buf).to_fallible();
+        // Sockets do not block if `MSG_DONTWAIT` is set, even if `O_NONBLOCK` is not set.
+        if let Some(socket) = self.file().clone().as_socket() {
+            let (read_len, _) = socket.recvmsg(&mut writer, SendRecvFlags::MSG_DONTWAIT)?;
+            return Ok(read_len);
+        }
+        self.file().read_nonblocking(&mut writer)
+    }
+
+    fn write_file(&self, offset: Option<usize>, buf: &[u8]) -> Result<usize> {
+        if let Some(offset) = offset {
+            return self.file().write_bytes_at(offset, buf);
+        }
+
+        let mut reader = VmReader::from(buf).to_fallible();
+        if let Some(socket) = self.file().clone().as_socket() {
+            return socket.sendmsg(
+                &mut reader,
+                MessageHeader::new(None, Vec::new()),
+                SendRecvFlags::MSG_DONTWAIT,
+            );
+        }
+        self.file().write_nonblocking(&mut reader)
+    }
+
+    /// Copies the data to the user buffers of the IO vectors.
+    fn scatter(&self, io_vecs: &[(Vaddr, usize)], mut data: &[u8]) -> Result<()> {
+        let process = self.process()?;
+        for (base, len) in io_vecs {
+            if data.is_empty() {
+                break;
+            }
+            let copy_len = min(*len, data.len());
+            process
+                .root_vmar()
+                .write_for_user(*base, &data[..copy_len])?;
+            data = &data[copy_len..];
+        }
+        Ok(())
+    }
+
+    /// Copies the data from the user buffers of the IO vectors.
+    fn gather(&self, io_vecs: &[(Vaddr, usize)], mut buf: &mut [u8]) -> Result<()> {
+        let process = self.process()?;
+        for (base, len) in io_vecs {
+            if buf.is_empty() {
+                break;
+            }
+            let copy_len = min(*len, buf.len());
+            process
+                .root_vmar()
+                .read_for_user(*base, &mut buf[..copy_len])?;
+            buf = &mut buf[copy_len..];
+        }
+        Ok(())
+    }
+
+    fn write_socket_addr(
+        &self,
+        socket_addr: &SocketAddr,
+        dest: Vaddr,
+        max_len_ptr: Vaddr,
+    ) -> Result<()> {
+        let process = self.process()?;
+        let root_vmar = process.root_vmar();
+
+        let mut max_len = 0i32;
+        root_vmar.read_for_user(max_len_ptr, max_len.as_bytes_mut())?;
+        if max_len < 0 {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the socket address length cannot be negative"
+            );
+        }
+
+        let actual_len = socket_addr_into_c_bytes_and(socket_addr, |bytes| {
+            let written_len = min(bytes.len(), max_len as usize);
+            root_vmar.write_for_user(dest, &bytes[..written_len])?;
+            Ok::<i32, Error>(bytes.len() as i32)
+        })?;
+        root_vmar.write_for_user(max_len_ptr, actual_len.as_bytes())
+    }
+}
+
+impl Op {
+    fn prepare(
+        opcode: IoUringOp,
+        sqe: &IoUringSqe,
+        ring: &IoUringFile,
+        ctx: &Context,
+    ) -> Result<Self> {
+        let op = match opcode {
+            IoUringOp::Nop => Self::Nop,
+            IoUringOp::Read => Self::Read {
+                buf: sqe.addr as Vaddr,
+                len: sqe.len as usize,
+                offset: file_offset(sqe.off)?,
+            },
+            IoUringOp::Write => Self::Write {
+                buf: sqe.addr as Vaddr,
+                len: sqe.len as usize,
+                offset: file_offset(sqe.off)?,
+            },
+            IoUringOp::Readv => Self::Readv {
+                io_vecs: copy_io_vecs(sqe, ctx)?,
+                offset: file_offset(sqe.off)?,
+            },
+            IoUringOp::Writev => Self::Writev {
+                io_vecs: copy_io_vecs(sqe, ctx)?,
+                offset: file_offset(sqe.off)?,
+            },
+            IoUringOp::Fsync => {
+                if sqe.op_flags & !IORING_FSYNC_DATASYNC != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "the fsync flags are invalid");
+                }
+                Self::Fsync {
+                    is_datasync: sqe.op_flags & IORING_FSYNC_DATASYNC != 0,
+                }
+            }
+            IoUringOp::PollAdd => {
+                let events = IoEvents::from_bits_truncate(sqe.op_flags);
+                // The request would wait for no events and never complete.
+                if events.is_empty() {
+                    return_errno_with_message!(Errno::EINVAL, "the poll events are empty");
+                }
+                Self::PollAdd { events }
+            }
+            IoUringOp::Timeout => {
+                if sqe.len != 1 {
+                    return_errno_with_message!(Errno::EINVAL, "the timeout count must be one");
+                }
+                if sqe.op_flags & !IORING_TIMEOUT_ABS != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "the timeout flags are invalid");
+                }
+
+                let duration = {
+                    let timespec = ctx.user_space().read_val::<timespec_t>(sqe.addr as Vaddr)?;
+                    Duration::try_from(timespec)?
+                };
+                let timeout = if sqe.op_flags & IORING_TIMEOUT_ABS != 0 {
+                    Timeout::When(duration)
+                } else {
+                    Timeout::After(duration)
+                };
+                let target_completions = (sqe.off != 0).then(|| ring.num_completions() + sqe.off);
+
+                Self::Timeout {
+                    timeout,
+                    target_completions,
+                }
+            }
+            IoUringOp::Accept => Self::Accept {
+                addr: sqe.addr as Vaddr,
+                addr_len_ptr: sqe.off as Vaddr,
+                flags: AcceptFlags::from_bits(sqe.op_flags).ok_or_else(|| {
+                    Error::with_message(Errno::EINVAL, "the accept flags are invalid")
+                })?,
+            },
+            IoUringOp::Recv => Self::Recv {
+                buf: sqe.addr as Vaddr,
+                len: sqe.len as usize,
+                flags: SendRecvFlags::from_bits_truncate(sqe.op_flags as i32),
+            },
+            IoUringOp::Send => Self::Send {
+                buf: sqe.addr as Vaddr,
+                len: sqe.len as usize,
+                flags: SendRecvFlags::from_bits_truncate(sqe.op_flags as i32),
+            },
+        };
+
+        Ok(op)
+    }
+
+    fn has_file(&self) -> bool {
+        !matches!(self, Self::Nop | Self::Timeout { .. })
+    }
+
+    /// Returns the events that the file must have before the operation is executed.
+    fn io_events(&self) -> Option<IoEvents> {
+        match self {
+            Self::Read { .. } | Self::Readv { .. } | Self::Accept { .. } | Self::Recv { .. } => {
+                Some(IoEvents::IN)
+            }
+            Self::Write { .. } | Self::Writev { .. } | Self::Send { .. } => Some(IoEvents::OUT),
+            Self::PollAdd { events } => Some(*events),
+            Self::Nop | Self::Fsync { .. } | Self::Timeout { .. } => None,
+        }
+    }
+}
+
+/// Converts the offset of an SQE to a file offset.
+///
+/// An offset of `-1` means the current file position.
+fn file_offset(off: u64) -> Result<Option<usize>> {
+    if off == u64::MAX {
+        return Ok(None);
+    }
+    if off > isize::MAX as u64 {
+        return_errno_with_message!(Errno::EINVAL, "the offset is too large");
+    }
+    Ok(Some(off as usize))
+}
+
+fn copy_io_vecs(sqe: &IoUringSqe, ctx: &Context) -> Result<Box<[(Vaddr, usize)]>> {
+    if sqe.len as usize > IOV_MAX {
+        return_errno_with_message!(Errno::EINVAL, "too many IO vectors");
+    }
+    copy_io_vecs_from_user(ctx, sqe.addr as Vaddr, sqe.len as usize)
+}
+
+fn total_len(io_vecs: &[(Vaddr, usize)]) -> usize {
+    io_vecs
+        .iter()
+        .fold(0usize, |total, (_, len)| total.saturating_add(*len))
+        .min(MAX_TRANSFER_LEN)
+}
+
+/// An observer that submits the work of a request when the file has events.
+struct RequestWaker(Arc<WorkItem>);
+
+impl Observer<IoEvents> for RequestWaker {
+    fn on_events(&self, _events: &IoEvents) {
+        submit_work_item(self.0.clone(), WorkPriority::Normal);
+    }
+}
+
+bitflags! {
+    struct AcceptFlags: u32 {
+        const SOCK_NONBLOCK = NONBLOCK;
+        const SOCK_CLOEXEC = CLOEXEC;
+    }
+}
+
+const NONBLOCK: u32 = StatusFlags::O_NONBLOCK.bits();
+const CLOEXEC: u32 = CreationFlags::O_CLOEXEC.bits();
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{fence, Ordering};
+
+use align_ext::AlignExt;
+use aster_rights::Rights;
+use ostd::mm::{Frame, VmIo};
+
+use super::uapi::{IoCqringOffsets, IoSqringOffsets, IoUringCqe, IoUringSqe};
+use crate::{
+    prelude::*,
+    vm::vmo::{Vmo, VmoOptions},
+};
+
+/// The submission queue (SQ) and the completion queue (CQ) of an io_uring instance.
+///
+/// The SQ and the CQ are in one VMO, which is laid out as follows. The SQEs are in another VMO.
+///
+/// | Offset               | Content                                  |
+/// |----------------------|------------------------------------------|
+/// | 0                    | The SQ head and tail                     |
+/// | 64                   | The CQ head and tail                     |
+/// | 128                  | The masks, the sizes, the flags, etc.    |
+/// | 192                  | The CQEs                                 |
+/// | After the CQEs       | The SQ array, i.e., the indices of SQEs  |
+///
+/// The user produces SQEs by advancing the SQ tail and consumes CQEs by advancing the CQ head,
+/// while the kernel does the opposite. The heads and the tails are on different cache lines
+/// because they are written by different sides.
+pub(super) struct Rings {
+    vmo: Vmo<Rights>,
+    sqes: Vmo<Rights>,
+    /// The first page of `vmo`, which contains all the fields before the CQEs.
+    header: Frame,
+    sq_entries: u32,
+    cq_entries: u32,
+    /// The SQ head, which is only advanced by the kernel.
+    sq_head: Mutex<u32>,
+    /// The CQ tail, which is only advanced by the kernel.
+    cq_tail: Mutex<u32>,
+}
+
+const SQ_HEAD: usize = 0;
+const SQ_TAIL: usize = 4;
+const CQ_HEAD: usize = 64;
+const CQ_TAIL: usize = 68;
+const SQ_RING_MASK: usize = 128;
+const SQ_RING_ENTRIES: usize = 132;
+const CQ_RING_MASK: usize = 136;
+const CQ_RING_ENTRIES: usize = 140;
+const SQ_FLAGS: usize = 144;
+const SQ_DROPPED: usize = 148;
+const CQ_FLAGS: usize = 152;
+const CQ_OVERFLOW: usize = 156;
+const CQES: usize = 192;
+
+impl Rings {
+    /// Allocates the rings.
+    ///
+    /// The numbers of entries must be powers of two.
+    pub(super) fn new(sq_entries: u32, cq_entries: u32) -> Result<Self> {
+        debug_assert!(sq_entries.is_power_of_two() && cq_entries.is_power_of_two());
+
+        let vmo_size = (Self::sq_array_offset(cq_entries) + sq_entries as usize * size_of::<u32>())
+            .align_up(PAGE_SIZE);
+        let vmo = VmoOptions::<Rights>::new(vmo_size).alloc()?;
+        let sqes_size = (sq_entries as usize * size_of::<IoUringSqe>()).align_up(PAGE_SIZE);
+        let sqes = VmoOptions::<Rights>::new(sqes_size).alloc()?;
+
+        let header = vmo.commit_page(0)?;
+        let rings = Self {
+            vmo,
+            sqes,
+            header,
+            sq_entries,
+            cq_entries,
+            sq_head: Mutex::new(0),
+            cq_tail: Mutex::new(0),
+        };
+        rings.store(SQ_RING_MASK, sq_entries - 1);
+        rings.store(SQ_RING_ENTRIES, sq_entries);
+        rings.store(CQ_RING_MASK, cq_entries - 1);
+        rings.store(CQ_RING_ENTRIES, cq_entries);
+
+        Ok(rings)
+    }
+
+    fn sq_array_offset(cq_entries: u32) -> usize {
+        CQES + cq_entries as usize * size_of::<IoUringCqe>()
+    }
+
+    /// Returns the VMO that contains the SQ and the CQ.
+    pub(super) fn vmo(&self) -> &Vmo<Rights> {
+        &self.vmo
+    }
+
+    /// Returns the VMO that contains the SQEs.
+    pub(super) fn sqes(&self) -> &Vmo<Rights> {
+        &self.sqes
+    }
+
+    pub(super) fn cq_entries(&self) -> u32 {
+        self.cq_entries
+    }
+
+    /// Returns the offsets of the SQ fields for the user.
+    pub(super) fn sq_offsets(&self) -> IoSqringOffsets {
+        IoSqringOffsets {
+            head: SQ_HEAD as u32,
+            tail: SQ_TAIL as u32,
+            ring_mask: SQ_RING_MASK as u32,
+            ring_entries: SQ_RING_ENTRIES as u32,
+            flags: SQ_FLAGS as u32,
+            dropped: SQ_DROPPED as u32,
+            array: Self::sq_array_offset(self.cq_entries) as u32,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the offsets of the CQ fields for the user.
+    pub(super) fn cq_offsets(&self) -> IoCqringOffsets {
+        IoCqringOffsets {
+            head: CQ_HEAD as u32,
+            tail: CQ_TAIL as u32,
+            ring_mask: CQ_RING_MASK as u32,
+            ring_entries: CQ_RING_ENTRIES as u32,
+            overflow: CQ_OVERFLOW as u32,
+            cqes: CQES as u32,
+            flags: CQ_FLAGS as u32,
+            ..Default::default()
+        }
+    }
+
+    /// Consumes an SQE.
+    ///
+    /// This method returns `None` if the SQ is empty. The entries of the SQ array that refer to
+    /// invalid SQEs are skipped and counted as dropped.
+    pub(super) fn pop_sqe(&self) -> Result<Option<IoUringSqe>> {
+        let mut sq_head = self.sq_head.lock();
+
+        loop {
+            let sq_tail = self.load(SQ_TAIL);
+            // Pairs with the release store of the SQ tail by the user, so that the SQEs are
+            // visible here.
+            fence(Ordering::Acquire);
+            if *sq_head == sq_tail {
+                return Ok(None);
+            }
+
+            let array_offset = Self::sq_array_offset(self.cq_entries)
+                + (*sq_head & (self.sq_entries - 1)) as usize * size_of::<u32>();
+            let index = self.vmo.read_val::<u32>(array_offset)?;
+
+            *sq_head = sq_head.wrapping_add(1);
+            // Pairs with the acquire load of the SQ head by the user, so that the SQE is no
+            // longer read after the user reuses it.
+            fence(Ordering::Release);
+            self.store(SQ_HEAD, *sq_head);
+
+            if index >= self.sq_entries {
+                self.store(SQ_DROPPED, self.load(SQ_DROPPED).wrapping_add(1));
+                continue;
+            }
+
+            let sqe = self
+                .sqes
+                .read_val::<IoUringSqe>(index as usize * size_of::<IoUringSqe>())?;
+            return Ok(Some(sqe));
+        }
+    }
+
+    /// Posts a CQE.
+    ///
+    /// If the CQ is full, the CQE is discarded and counted as overflowed.
+    pub(super) fn push_cqe(&self, cqe: &IoUringCqe) -> Result<()> {
+        let mut cq_tail = self.cq_tail.lock();
+
+        let cq_head = self.load(CQ_HEAD);
+        if cq_tail.wrapping_sub(cq_head) >= self.cq_entries {
+            self.store(CQ_OVERFLOW, self.load(CQ_OVERFLOW).wrapping_add(1));
+            return_errno_with_message!(Errno::EOVERFLOW, "the CQ is full");
+        }
+
+        let cqe_offset =
+            CQES + (*cq_tail & (self.cq_entries - 1)) as usize * size_of::<IoUringCqe>();
+        self.vmo.write_val(cqe_offset, cqe)?;
+
+        *cq_tail = cq_tail.wrapping_add(1);
+        // Pairs with the acquire load of the CQ tail by the user, so that the CQE is visible
+        // there.
+        fence(Ordering::Release);
+        self.store(CQ_TAIL, *cq_tail);
+
+        Ok(())
+    }
+
+    /// Returns the number of the CQEs that have not been consumed by the user.
+    pub(super) fn cq_ready(&self) -> u32 {
+        let cq_tail = *self.cq_tail.lock();
+        cq_tail.wrapping_sub(self.load(CQ_HEAD))
+    }
+
+    /// Returns whether the SQ has room for more SQEs.
+    pub(super) fn sq_has_room(&self) -> bool {
+        let sq_head = *self.sq_head.lock();
+        self.load(SQ_TAIL).wrapping_sub(sq_head) < self.sq_entries
+    }
+
+    fn load(&self, offset: usize) -> u32 {
+        // The offset is always within the header, so the load cannot fail.
+        self.header.reader().skip(offset).read_once().unwrap()
+    }
+
+    fn store(&self, offset: usize, value: u32) {
+        // The offset is always within the header, so the store cannot fail.
+        self.header
+            .writer()
+            .skip(offset)
+            .write_once(&value)
+            .unwrap()
+    }
+}
@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The io_uring definitions that are shared with the user space.
+//!
+//! See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/io_uring.h>.
+
+use crate::prelude::*;
+
+/// The `mmap` offset of the SQ ring.
+pub(super) const IORING_OFF_SQ_RING: usize = 0;
+/// The `mmap` offset of the CQ ring.
+pub(super) const IORING_OFF_CQ_RING: usize = 0x8000000;
+/// The `mmap` offset of the SQE array.
+pub(super) const IORING_OFF_SQES: usize = 0x10000000;
+
+/// The parameters of `io_uring_setup`.
+///
+/// The user specifies the flags and, optionally, the number of the CQ entries. The kernel fills
+/// in the rest, which tells the user how to access the rings.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub struct IoUringParams {
+    pub sq_entries: u32,
+    pub cq_entries: u32,
+    pub flags: u32,
+    pub sq_thread_cpu: u32,
+    pub sq_thread_idle: u32,
+    pub features: u32,
+    pub wq_fd: u32,
+    pub resv: [u32; 3],
+    pub sq_off: IoSqringOffsets,
+    pub cq_off: IoCqringOffsets,
+}
+
+/// The offsets of the SQ fields in the memory mapped with [`IORING_OFF_SQ_RING`].
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+pub struct IoSqringOffsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub flags: u32,
+    pub dropped: u32,
+    pub array: u32,
+    pub resv1: u32,
+    pub user_addr: u64,
+}
+
+/// The offsets of the CQ fields in the memory mapped with [`IORING_OFF_CQ_RING`].
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+pub struct IoCqringOffsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub overflow: u32,
+    pub cqes: u32,
+    pub flags: u32,
+    pub resv1: u32,
+    pub user_addr: u64,
+}
+
+/// A submission queue entry (SQE).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct IoUringSqe {
+    pub opcode: u8,
+    pub flags: u8,
+    pub ioprio: u16,
+    pub fd: i32,
+    /// The file offset, or the address of the socket address length for `ACCEPT`.
+    pub off: u64,
+    /// The buffer address, or the address of other opcode-specific arguments.
+    pub addr: u64,
+    pub len: u32,
+    /// The opcode-specific flags, e.g., `fsync_flags`, `poll32_events`, and `msg_flags`.
+    pub op_flags: u32,
+    pub user_data: u64,
+    pub buf_index: u16,
+    pub personality: u16,
+    pub splice_fd_in: i32,
+    pub addr3: u64,
+    pub pad: u64,
+}
+
+/// A completion queue entry (CQE).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct IoUringCqe {
+    pub user_data: u64,
+    pub res: i32,
+    pub flags: u32,
+}
+
+/// The header of the result of `IORING_REGISTER_PROBE`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct IoUringProbe {
+    pub last_op: u8,
+    pub ops_len: u8,
+    pub resv: u16,
+    pub resv2: [u32; 3],
+}
+
+/// An entry of the result of `IORING_REGISTER_PROBE`, which follows [`IoUringProbe`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct IoUringProbeOp {
+    pub op: u8,
+    pub resv: u8,
+    pub flags: u16,
+    pub resv2: u32,
+}
+
+/// The flag of [`IoUringProbeOp`] that indicates the opcode is supported.
+pub(super) const IO_URING_OP_SUPPORTED: u16 = 1 << 0;
+
+/// The opcodes of SQEs.
+///
+/// Only the supported opcodes are listed.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+pub(super) enum IoUringOp {
+    Nop = 0,
+    Readv = 1,
+    Writev = 2,
+    Fsync = 3,
+    PollAdd = 6,
+    Timeout = 11,
+    Accept = 13,
+    Read = 22,
+    Write = 23,
+    Send = 26,
+    Recv = 27,
+}
+
+impl IoUringOp {
+    /// The last supported opcode.
+    pub(super) const LAST: IoUringOp = Self::Recv;
+}
+
+/// The opcodes of `io_uring_register`.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+pub enum RegisterOp {
+    RegisterBuffers = 0,
+    UnregisterBuffers = 1,
+    RegisterFiles = 2,
+    UnregisterFiles = 3,
+    RegisterEventfd = 4,
+    UnregisterEventfd = 5,
+    RegisterFilesUpdate = 6,
+    RegisterEventfdAsync = 7,
+    RegisterProbe = 8,
+}
+
+bitflags! {
+    /// The flags of `io_uring_setup`.
+    pub struct SetupFlags: u32 {
+        const IOPOLL     = 1 << 0;
+        const SQPOLL     = 1 << 1;
+        const SQ_AFF     = 1 << 2;
+        const CQSIZE     = 1 << 3;
+        const CLAMP      = 1 << 4;
+        const ATTACH_WQ  = 1 << 5;
+        const R_DISABLED = 1 << 6;
+    }
+}
+
+bitflags! {
+    /// The features reported by `io_uring_setup`.
+    pub(super) struct Features: u32 {
+        const SINGLE_MMAP     = 1 << 0;
+        const NODROP          = 1 << 1;
+        const SUBMIT_STABLE   = 1 << 2;
+        const RW_CUR_POS      = 1 << 3;
+        const CUR_PERSONALITY = 1 << 4;
+        const FAST_POLL       = 1 << 5;
+    }
+}
+
+bitflags! {
+    /// The flags of `io_uring_enter`.
+    pub struct EnterFlags: u32 {
+        const GETEVENTS = 1 << 0;
+        const SQ_WAKEUP = 1 << 1;
+        const SQ_WAIT   = 1 << 2;
+        const EXT_ARG   = 1 << 3;
+    }
+}
+
+bitflags! {
+    /// The flags of SQEs.
+    pub(super) struct SqeFlags: u8 {
+        const FIXED_FILE    = 1 << 0;
+        const IO_DRAIN      = 1 << 1;
+        const IO_LINK       = 1 << 2;
+        const IO_HARDLINK   = 1 << 3;
+        const ASYNC         = 1 << 4;
+        const BUFFER_SELECT = 1 << 5;
+    }
+}
+
+/// The flag of `fsync_flags` to sync only the data.
+pub(super) const IORING_FSYNC_DATASYNC: u32 = 1 << 0;
+
+/// The flag of `timeout_flags` to specify an absolute timeout.
+pub(super) const IORING_TIMEOUT_ABS: u32 = 1 << 0;
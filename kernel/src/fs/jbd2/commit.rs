@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Commits and checkpoints of the transactions.
+
+use core::mem::size_of;
+
+use aster_block::{bio::BioWaiter, id::Bid, BLOCK_SIZE};
+use ostd::mm::VmIo;
+
+use super::{
+    raw::{
+        Be32, Be64, BlockType, RawCommitHeader, RawHeader, Tag, TagFlags, JBD2_MAGIC, UUID_SIZE,
+    },
+    Journal,
+};
+use crate::{fs::utils::crc32c, prelude::*, time::clocks::RealTimeCoarseClock};
+
+impl Journal {
+    /// Commits the running transaction, then writes its blocks to their home locations.
+    ///
+    /// The blocks are logged before the commit block, and the commit block reaches the
+    /// device before any block is written to its home location. After this method returns
+    /// successfully, the log is empty.
+    ///
+    /// The transaction is committed as a whole. If it does not fit in the log, this method
+    /// fails with `ENOSPC` and the blocks are kept in the journal.
+    pub fn commit(&self) -> Result<()> {
+        let _commit_guard = self.commit_lock.lock();
+
+        // The blocks that failed to be committed last time are committed together.
+        let bids: Vec<Bid> = {
+            let mut transactions = self.transactions.lock();
+            let running = core::mem::take(&mut transactions.running);
+            transactions.committing.extend(running);
+            transactions.committing.keys().copied().collect()
+        };
+
+        // Splitting the transaction would make a part of it reach the device without
+        // the others if a crash occurs in between.
+        if bids.len() > self.max_transaction_blocks() {
+            return_errno_with_message!(Errno::ENOSPC, "the transaction does not fit in the log");
+        }
+        self.commit_blocks(&bids)
+    }
+
+    /// Commits the blocks as a transaction, then checkpoints it.
+    fn commit_blocks(&self, bids: &[Bid]) -> Result<()> {
+        let blocks: Vec<(Bid, Arc<[u8]>)> = {
+            let transactions = self.transactions.lock();
+            bids.iter()
+                .filter_map(|bid| {
+                    let block = transactions.committing.get(bid)?;
+                    Some((*bid, block.clone()))
+                })
+                .collect()
+        };
+        // All the blocks have been forgotten.
+        if blocks.is_empty() {
+            return Ok(());
+        }
+
+        let sequence = self.super_block.lock().sequence.get();
+        self.write_transaction(sequence, &blocks)?;
+        {
+            let mut super_block = self.super_block.lock();
+            super_block.start = Be32::new(self.first);
+            self.write_super_block(&mut super_block)?;
+        }
+
+        self.checkpoint(bids)?;
+        let mut super_block = self.super_block.lock();
+        super_block.start = Be32::new(0);
+        super_block.sequence = Be32::new(sequence.wrapping_add(1));
+        self.write_super_block(&mut super_block)
+    }
+
+    /// Writes the blocks to the log as the transaction `sequence`.
+    pub(super) fn write_transaction(
+        &self,
+        sequence: u32,
+        blocks: &[(Bid, Arc<[u8]>)],
+    ) -> Result<()> {
+        let tag_size = self.tag_layout.tag_size();
+        let mut bio_waiter = BioWaiter::new();
+        let mut log_block = self.first;
+
+        for descriptor_blocks in blocks.chunks(self.tags_per_descriptor()) {
+            let descriptor_log_block = log_block;
+            log_block += 1;
+
+            let mut descriptor = vec![0u8; BLOCK_SIZE];
+            descriptor[..size_of::<RawHeader>()]
+                .copy_from_slice(RawHeader::new(BlockType::Descriptor, sequence).as_bytes());
+            let mut offset = size_of::<RawHeader>();
+            for (i, (bid, block)) in descriptor_blocks.iter().enumerate() {
+                let mut flags = TagFlags::empty();
+                // A logged block that starts with the magic number would be mistaken for
+                // a journal block, so the magic number is escaped.
+                let mut data = block.to_vec();
+                if data[..4] == JBD2_MAGIC.to_be_bytes() {
+                    data[..4].fill(0);
+                    flags |= TagFlags::ESCAPE;
+                }
+                if i > 0 {
+                    flags |= TagFlags::SAME_UUID;
+                }
+                if i == descriptor_blocks.len() - 1 {
+                    flags |= TagFlags::LAST_TAG;
+                }
+
+                let tag = Tag {
+                    block: bid.to_raw(),
+                    flags,
+                    checksum: self.block_checksum(sequence, &data).unwrap_or(0),
+                };
+                self.tag_layout.write_tag(&mut descriptor[offset..], &tag);
+                offset += tag_size;
+                if i == 0 {
+                    descriptor[offset..offset + UUID_SIZE].copy_from_slice(&self.uuid);
+                    offset += UUID_SIZE;
+                }
+
+                bio_waiter.concat(
+                    self.device
+                        .write_bytes_async(self.device_bid(log_block).to_offset(), &data)?,
+                );
+                log_block += 1;
+            }
+
+            if let Some(checksum) = self.tail_checksum(&descriptor) {
+                descriptor[BLOCK_SIZE - 4..].copy_from_slice(&checksum.to_be_bytes());
+            }
+            bio_waiter.concat(self.device.write_bytes_async(
+                self.device_bid(descriptor_log_block).to_offset(),
+                &descriptor,
+            )?);
+        }
+
+        bio_waiter
+            .wait()
+            .ok_or_else(|| Error::with_message(Errno::EIO, "failed to write the journal"))?;
+        // The commit block must not reach the device before the other blocks.
+        self.flush()?;
+
+        let commit_block = self.commit_block(sequence);
+        self.device
+            .write_bytes(self.device_bid(log_block).to_offset(), &commit_block)?;
+        self.flush()
+    }
+
+    /// Builds the commit block of the transaction `sequence`.
+    fn commit_block(&self, sequence: u32) -> Vec<u8> {
+        let now = RealTimeCoarseClock::get().read_time();
+        let mut header = RawCommitHeader {
+            header: RawHeader::new(BlockType::Commit, sequence),
+            commit_sec: Be64::new(now.as_secs()),
+            commit_nsec: Be32::new(now.subsec_nanos()),
+            ..Default::default()
+        };
+
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        let header_len = size_of::<RawCommitHeader>();
+        buf[..header_len].copy_from_slice(header.as_bytes());
+        if let Some(seed) = self.csum_seed {
+            header.checksum[0] = Be32::new(crc32c(seed, &buf));
+            buf[..header_len].copy_from_slice(header.as_bytes());
+        }
+        buf
+    }
+
+    /// Writes the committed blocks to their home locations.
+    fn checkpoint(&self, bids: &[Bid]) -> Result<()> {
+        // The lock is held until the blocks reach the device, so the blocks cannot be
+        // forgotten and reused as data blocks before being overwritten here.
+        let mut transactions = self.transactions.lock();
+
+        let mut bio_waiter = BioWaiter::new();
+        for bid in bids {
+            if let Some(block) = transactions.committing.get(bid) {
+                bio_waiter.concat(self.device.write_bytes_async(bid.to_offset(), block)?);
+            }
+        }
+        bio_waiter
+            .wait()
+            .ok_or_else(|| Error::with_message(Errno::EIO, "failed to checkpoint the journal"))?;
+        self.flush()?;
+
+        for bid in bids {
+            transactions.committing.remove(bid);
+        }
+        Ok(())
+    }
+
+    /// Returns the max number of blocks in a transaction that fits in the log.
+    pub(super) fn max_transaction_blocks(&self) -> usize {
+        // Each transaction takes a descriptor block for every `tags` blocks, and a commit block.
+        let log_len = (self.len - self.first) as usize;
+        let tags = self.tags_per_descriptor();
+        ((log_len - 2) * tags / (tags + 1)).max(1)
+    }
+}
@@ -0,0 +1,530 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A journal layer that is compatible with JBD2 of Linux.
+//!
+//! JBD2 (Journaling Block Device 2) is the journal used by Ext3 and Ext4. Before the metadata
+//! blocks modified by a transaction are written to their home locations, they are logged in
+//! the journal together with a commit block. If the system crashes in the middle of the
+//! writeback, the committed transactions are replayed from the log at the next mount, and the
+//! uncommitted ones are discarded as a whole.
+//!
+//! This journal works in the ordered mode. Only the metadata blocks pass through the journal,
+//! and the filesystem is expected to write back the file data before committing the metadata
+//! that refer to them. A transaction is committed and checkpointed synchronously, so the log
+//! is empty again once the commit returns, which keeps the journal clean for `e2fsck`.
+//!
+//! The on-disk format is described in
+//! <https://www.kernel.org/doc/html/latest/filesystems/ext4/journal.html>.
+//!
+//! The blocks of a transaction are never split across multiple commits, so the filesystem
+//! should commit the running transaction, at the boundary of its operations, before the
+//! transaction outgrows the log. [`Journal::needs_commit`] tells when it is time to do so.
+//!
+//! # Limitation
+//!
+//! The external journal devices and the fast commits are not supported.
+
+use core::{mem::size_of, ops::Range};
+
+use aster_block::{bio::BioStatus, id::Bid, BlockDevice, BLOCK_SIZE};
+use ostd::mm::VmIo;
+
+use self::raw::{
+    Be32, BlockType, FeatureInCompat, RawHeader, RawSuperBlock, TagLayout, CHECKSUM_TYPE_CRC32C,
+    TAIL_SIZE,
+};
+use crate::{fs::utils::crc32c, prelude::*};
+
+mod commit;
+mod raw;
+mod recovery;
+
+/// The running transaction should be committed once it takes `1 / COMMIT_THRESHOLD_DIVISOR`
+/// of the log, which leaves room for the metadata written back during the commit.
+const COMMIT_THRESHOLD_DIVISOR: usize = 4;
+
+/// A journal that is compatible with JBD2.
+pub struct Journal {
+    device: Arc<dyn BlockDevice>,
+    /// The device blocks that store the journal, where the journal block `i` is the `i`-th
+    /// block of the concatenated ranges.
+    area: Vec<Range<Bid>>,
+    /// The number of blocks in the journal.
+    len: u32,
+    /// The first block of the log, which follows the journal superblock.
+    first: u32,
+    features: FeatureInCompat,
+    tag_layout: TagLayout,
+    uuid: [u8; 16],
+    /// The seed of the checksums, or `None` if the checksums are disabled.
+    csum_seed: Option<u32>,
+    super_block: Mutex<RawSuperBlock>,
+    transactions: Mutex<Transactions>,
+    /// The lock that serializes the commits.
+    commit_lock: Mutex<()>,
+}
+
+/// The metadata blocks that have not been written to their home locations.
+#[derive(Default)]
+struct Transactions {
+    /// The blocks modified by the running transaction.
+    running: BTreeMap<Bid, Arc<[u8]>>,
+    /// The blocks of the committing transaction.
+    committing: BTreeMap<Bid, Arc<[u8]>>,
+}
+
+impl Journal {
+    /// Loads the journal stored in the `area` of the `device`.
+    ///
+    /// The `area` lists the device blocks of the journal in order, e.g., the blocks mapped
+    /// by the journal inode of Ext4.
+    pub fn load(device: Arc<dyn BlockDevice>, area: Vec<Range<Bid>>) -> Result<Self> {
+        let Some(first_range) = area.first() else {
+            return_errno_with_message!(Errno::EINVAL, "the journal is empty");
+        };
+        let area_len: u64 = area
+            .iter()
+            .map(|range| range.end.to_raw() - range.start.to_raw())
+            .sum();
+
+        let super_block = device.read_val::<RawSuperBlock>(first_range.start.to_offset())?;
+        let corrupted = || Error::with_message(Errno::EUCLEAN, "the journal is corrupted");
+        let features = match super_block.header.block_type() {
+            Some(BlockType::SuperBlockV1) => FeatureInCompat::empty(),
+            Some(BlockType::SuperBlockV2) => {
+                let features = FeatureInCompat::from_bits(super_block.feature_incompat.get())
+                    .ok_or_else(corrupted)?;
+                if !(features - FeatureInCompat::SUPPORTED).is_empty() {
+                    return_errno_with_message!(
+                        Errno::EOPNOTSUPP,
+                        "unsupported journal feature incompat set"
+                    );
+                }
+                features
+            }
+            _ => return Err(corrupted()),
+        };
+        if super_block.block_size.get() as usize != BLOCK_SIZE {
+            return_errno_with_message!(Errno::EINVAL, "unsupported journal block size");
+        }
+
+        let len = super_block.max_len.get();
+        let first = super_block.first.get();
+        // The log must be able to hold a descriptor block, a data block and a commit block.
+        if len as u64 > area_len || first == 0 || first.saturating_add(3) > len {
+            return Err(corrupted());
+        }
+
+        let csum_seed = if features.intersects(FeatureInCompat::CSUM_V2 | FeatureInCompat::CSUM_V3)
+        {
+            if features.contains(FeatureInCompat::CSUM_V2 | FeatureInCompat::CSUM_V3) {
+                return Err(corrupted());
+            }
+            if super_block.checksum_type != CHECKSUM_TYPE_CRC32C {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "unsupported journal checksum type");
+            }
+            if super_block.checksum.get() != super_block.compute_checksum() {
+                return_errno_with_message!(Errno::EBADMSG, "bad journal superblock checksum");
+            }
+            Some(crc32c(!0, &super_block.uuid))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            device,
+            area,
+            len,
+            first,
+            features,
+            tag_layout: TagLayout::new(features),
+            uuid: super_block.uuid,
+            csum_seed,
+            super_block: Mutex::new(super_block),
+            transactions: Mutex::new(Transactions::default()),
+            commit_lock: Mutex::new(()),
+        })
+    }
+
+    /// Returns whether the log has transactions to replay.
+    pub fn needs_recovery(&self) -> bool {
+        self.super_block.lock().start.get() != 0
+    }
+
+    /// Returns whether there are metadata blocks that have not been committed.
+    pub fn has_uncommitted(&self) -> bool {
+        let transactions = self.transactions.lock();
+        !transactions.running.is_empty() || !transactions.committing.is_empty()
+    }
+
+    /// Returns whether the running transaction has taken a large part of the log.
+    ///
+    /// The filesystem should commit the transaction before starting more operations,
+    /// otherwise the transaction may become too large to be committed.
+    pub fn needs_commit(&self) -> bool {
+        let transactions = self.transactions.lock();
+        let nblocks = transactions.running.len() + transactions.committing.len();
+        nblocks >= self.max_transaction_blocks() / COMMIT_THRESHOLD_DIVISOR
+    }
+
+    /// Returns whether the journal keeps a newer version of the metadata block `bid` than
+    /// its home location.
+    pub fn contains(&self, bid: Bid) -> bool {
+        let transactions = self.transactions.lock();
+        transactions.running.contains_key(&bid) || transactions.committing.contains_key(&bid)
+    }
+
+    /// Reads the newer version of the metadata block `bid` kept by the journal.
+    ///
+    /// Returns `false` if the journal has no such version, in which case the block should be
+    /// read from its home location.
+    ///
+    /// # Panics
+    ///
+    /// If the length of `buf` is not the block size, this method will panic.
+    pub fn read_block(&self, bid: Bid, buf: &mut [u8]) -> bool {
+        let transactions = self.transactions.lock();
+        let Some(block) = transactions
+            .running
+            .get(&bid)
+            .or_else(|| transactions.committing.get(&bid))
+        else {
+            return false;
+        };
+        buf.copy_from_slice(block);
+        true
+    }
+
+    /// Writes the metadata block `bid` in the running transaction.
+    ///
+    /// The block is written to its home location after the transaction is committed.
+    ///
+    /// # Panics
+    ///
+    /// If the length of `buf` is not the block size, this method will panic.
+    pub fn write_block(&self, bid: Bid, buf: &[u8]) {
+        assert_eq!(buf.len(), BLOCK_SIZE);
+        self.transactions.lock().running.insert(bid, Arc::from(buf));
+    }
+
+    /// Forgets the metadata blocks in `range`, which have been freed by the filesystem.
+    ///
+    /// The blocks will not be written to their home locations by the journal, so they
+    /// can be reused as data blocks safely.
+    pub fn forget(&self, range: Range<Bid>) {
+        let mut transactions = self.transactions.lock();
+        let Transactions {
+            running,
+            committing,
+        } = &mut *transactions;
+        for blocks in [running, committing] {
+            let bids: Vec<Bid> = blocks.range(range.clone()).map(|(bid, _)| *bid).collect();
+            for bid in bids {
+                blocks.remove(&bid);
+            }
+        }
+    }
+
+    /// Returns the device block of the journal block `block`.
+    fn device_bid(&self, block: u32) -> Bid {
+        let mut offset = block as u64;
+        for range in self.area.iter() {
+            let len = range.end.to_raw() - range.start.to_raw();
+            if offset < len {
+                return range.start + offset;
+            }
+            offset -= len;
+        }
+        unreachable!("the block is out of the journal");
+    }
+
+    /// Returns the journal block that follows `block` in the log.
+    ///
+    /// The log is circular, so the last block is followed by the first one.
+    fn next_block(&self, block: u32) -> u32 {
+        if block + 1 >= self.len {
+            self.first
+        } else {
+            block + 1
+        }
+    }
+
+    /// Returns the max number of tags in a descriptor block.
+    fn tags_per_descriptor(&self) -> usize {
+        let space = BLOCK_SIZE - size_of::<RawHeader>() - self.tail_size() - raw::UUID_SIZE;
+        space / self.tag_layout.tag_size()
+    }
+
+    /// Returns the size of the checksum at the tail of the descriptor and revoke blocks.
+    fn tail_size(&self) -> usize {
+        if self.csum_seed.is_some() {
+            TAIL_SIZE
+        } else {
+            0
+        }
+    }
+
+    /// Computes the checksum of a descriptor or revoke block, whose tail is treated as zeros.
+    fn tail_checksum(&self, buf: &[u8]) -> Option<u32> {
+        let seed = self.csum_seed?;
+        let tail_offset = BLOCK_SIZE - TAIL_SIZE;
+        let crc = crc32c(seed, &buf[..tail_offset]);
+        Some(crc32c(crc, &[0u8; TAIL_SIZE]))
+    }
+
+    /// Computes the checksum of a logged block in the transaction `sequence`.
+    fn block_checksum(&self, sequence: u32, buf: &[u8]) -> Option<u32> {
+        let seed = self.csum_seed?;
+        let crc = crc32c(seed, &sequence.to_be_bytes());
+        Some(crc32c(crc, buf))
+    }
+
+    fn read_log_block(&self, block: u32, buf: &mut [u8]) -> Result<()> {
+        self.device
+            .read_bytes(self.device_bid(block).to_offset(), buf)?;
+        Ok(())
+    }
+
+    /// Writes back the journal superblock, then flushes the device.
+    fn write_super_block(&self, super_block: &mut RawSuperBlock) -> Result<()> {
+        if self.csum_seed.is_some() {
+            super_block.checksum = Be32::new(super_block.compute_checksum());
+        }
+        self.device
+            .write_val(self.device_bid(0).to_offset(), super_block)?;
+        self.flush()
+    }
+
+    /// Flushes the volatile cache of the device.
+    fn flush(&self) -> Result<()> {
+        match self.device.sync()? {
+            BioStatus::Complete => Ok(()),
+            err_status => Err(Error::from(err_status)),
+        }
+    }
+}
+
+impl Debug for Journal {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Journal")
+            .field("len", &self.len)
+            .field("first", &self.first)
+            .field("features", &self.features)
+            .finish()
+    }
+}
+
+#[cfg(ktest)]
+mod test {
+    use aster_block::{
+        bio::{BioEnqueueError, BioType, SubmittedBio},
+        BlockDeviceMeta, SECTOR_SIZE,
+    };
+    use ostd::{
+        mm::{FrameAllocOptions, Segment},
+        prelude::*,
+    };
+
+    use super::*;
+
+    /// The number of blocks of the memory disk.
+    const DISK_BLOCKS: usize = 64;
+    /// The number of blocks of the journal, which starts from the first block of the disk.
+    const JOURNAL_BLOCKS: u32 = 32;
+    /// The first block that is out of the journal.
+    const HOME_BID: u64 = 40;
+
+    #[derive(Debug)]
+    struct MemoryDisk(Segment);
+
+    impl BlockDevice for MemoryDisk {
+        fn enqueue(&self, bio: SubmittedBio) -> core::result::Result<(), BioEnqueueError> {
+            let mut offset = bio.sid_range().start.to_raw() as usize * SECTOR_SIZE;
+            for seg in bio.segments() {
+                let size = match bio.type_() {
+                    BioType::Read => seg
+                        .inner_segment()
+                        .writer()
+                        .write(&mut self.0.reader().skip(offset)),
+                    BioType::Write => self
+                        .0
+                        .writer()
+                        .skip(offset)
+                        .write(&mut seg.inner_segment().reader()),
+                    _ => 0,
+                };
+                offset += size;
+            }
+            bio.complete(BioStatus::Complete);
+            Ok(())
+        }
+
+        fn metadata(&self) -> BlockDeviceMeta {
+            BlockDeviceMeta {
+                max_nr_segments_per_bio: usize::MAX,
+                nr_sectors: self.0.nbytes() / SECTOR_SIZE,
+            }
+        }
+    }
+
+    /// Creates a memory disk with an empty journal, like `mke2fs` does.
+    fn new_disk() -> Arc<dyn BlockDevice> {
+        let segment = FrameAllocOptions::new(DISK_BLOCKS)
+            .alloc_contiguous()
+            .unwrap();
+        let disk = Arc::new(MemoryDisk(segment));
+
+        let mut super_block = RawSuperBlock::new_zeroed();
+        super_block.header = RawHeader::new(BlockType::SuperBlockV2, 0);
+        super_block.block_size = Be32::new(BLOCK_SIZE as u32);
+        super_block.max_len = Be32::new(JOURNAL_BLOCKS);
+        super_block.first = Be32::new(1);
+        super_block.sequence = Be32::new(1);
+        super_block.feature_incompat = Be32::new(
+            (FeatureInCompat::REVOKE | FeatureInCompat::IS_64BIT | FeatureInCompat::CSUM_V3).bits(),
+        );
+        super_block.checksum_type = CHECKSUM_TYPE_CRC32C;
+        super_block.uuid = [0x5A; 16];
+        super_block.checksum = Be32::new(super_block.compute_checksum());
+        disk.write_val(0, &super_block).unwrap();
+        disk
+    }
+
+    fn load_journal(disk: &Arc<dyn BlockDevice>) -> Journal {
+        Journal::load(
+            disk.clone(),
+            vec![Bid::new(0)..Bid::new(JOURNAL_BLOCKS as u64)],
+        )
+        .unwrap()
+    }
+
+    fn read_home_block(disk: &Arc<dyn BlockDevice>, bid: u64) -> Vec<u8> {
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        disk.read_bytes(Bid::new(bid).to_offset(), &mut buf)
+            .unwrap();
+        buf
+    }
+
+    #[ktest]
+    fn commit() {
+        let disk = new_disk();
+        let journal = load_journal(&disk);
+
+        let block = vec![0x11u8; BLOCK_SIZE];
+        // The block that starts with the magic number must be escaped in the log.
+        let mut magic_block = vec![0x22u8; BLOCK_SIZE];
+        magic_block[..4].copy_from_slice(&raw::JBD2_MAGIC.to_be_bytes());
+        journal.write_block(Bid::new(HOME_BID), &block);
+        journal.write_block(Bid::new(HOME_BID + 1), &magic_block);
+
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        assert!(journal.read_block(Bid::new(HOME_BID), &mut buf));
+        assert_eq!(buf, block);
+        assert_ne!(read_home_block(&disk, HOME_BID), block);
+
+        journal.commit().unwrap();
+        assert!(!journal.has_uncommitted());
+        assert!(!journal.needs_recovery());
+        assert_eq!(read_home_block(&disk, HOME_BID), block);
+        assert_eq!(read_home_block(&disk, HOME_BID + 1), magic_block);
+        assert_eq!(journal.super_block.lock().sequence.get(), 2);
+    }
+
+    #[ktest]
+    fn forget() {
+        let disk = new_disk();
+        let journal = load_journal(&disk);
+
+        journal.write_block(Bid::new(HOME_BID), &vec![0x33u8; BLOCK_SIZE]);
+        journal.forget(Bid::new(HOME_BID)..Bid::new(HOME_BID + 1));
+        assert!(!journal.contains(Bid::new(HOME_BID)));
+
+        journal.commit().unwrap();
+        assert_eq!(read_home_block(&disk, HOME_BID), vec![0u8; BLOCK_SIZE]);
+    }
+
+    #[ktest]
+    fn needs_commit() {
+        let disk = new_disk();
+        let journal = load_journal(&disk);
+
+        let block = vec![0x66u8; BLOCK_SIZE];
+        let mut bid = JOURNAL_BLOCKS as u64;
+        while !journal.needs_commit() {
+            journal.write_block(Bid::new(bid), &block);
+            bid += 1;
+        }
+        assert!(bid - (JOURNAL_BLOCKS as u64) < journal.max_transaction_blocks() as u64);
+
+        journal.commit().unwrap();
+        assert!(!journal.needs_commit());
+        assert_eq!(read_home_block(&disk, bid - 1), block);
+    }
+
+    #[ktest]
+    fn commit_oversized() {
+        let disk = new_disk();
+        let journal = load_journal(&disk);
+
+        let block = vec![0x77u8; BLOCK_SIZE];
+        let nblocks = journal.max_transaction_blocks() as u64 + 1;
+        for bid in JOURNAL_BLOCKS as u64..JOURNAL_BLOCKS as u64 + nblocks {
+            journal.write_block(Bid::new(bid), &block);
+        }
+
+        // No part of the transaction reaches the device.
+        assert!(journal.commit().is_err());
+        assert!(journal.has_uncommitted());
+        assert!(!journal.needs_recovery());
+        assert_eq!(
+            read_home_block(&disk, JOURNAL_BLOCKS as u64),
+            vec![0u8; BLOCK_SIZE]
+        );
+    }
+
+    #[ktest]
+    fn recover() {
+        let disk = new_disk();
+        let block = vec![0x44u8; BLOCK_SIZE];
+        {
+            let journal = load_journal(&disk);
+            let blocks: Vec<(Bid, Arc<[u8]>)> = vec![(Bid::new(HOME_BID), Arc::from(&block[..]))];
+            // Crashes after the transaction is committed but before it is checkpointed.
+            journal.write_transaction(1, &blocks).unwrap();
+            let mut super_block = journal.super_block.lock();
+            super_block.start = Be32::new(journal.first);
+            journal.write_super_block(&mut super_block).unwrap();
+        }
+
+        let journal = load_journal(&disk);
+        assert!(journal.needs_recovery());
+        journal.recover().unwrap();
+        assert!(!journal.needs_recovery());
+        assert_eq!(read_home_block(&disk, HOME_BID), block);
+        assert_eq!(journal.super_block.lock().sequence.get(), 3);
+    }
+
+    #[ktest]
+    fn discard_uncommitted() {
+        let disk = new_disk();
+        {
+            let journal = load_journal(&disk);
+            let blocks: Vec<(Bid, Arc<[u8]>)> =
+                vec![(Bid::new(HOME_BID), Arc::from(&[0x55u8; BLOCK_SIZE][..]))];
+            journal.write_transaction(1, &blocks).unwrap();
+            // Crashes before the commit block reaches the device, which follows the
+            // descriptor block and the logged block.
+            let commit_bid = journal.device_bid(journal.first + 2);
+            disk.write_bytes(commit_bid.to_offset(), &vec![0u8; BLOCK_SIZE])
+                .unwrap();
+            let mut super_block = journal.super_block.lock();
+            super_block.start = Be32::new(journal.first);
+            journal.write_super_block(&mut super_block).unwrap();
+        }
+
+        let journal = load_journal(&disk);
+        journal.recover().unwrap();
+        assert!(!journal.needs_recovery());
+        assert_eq!(read_home_block(&disk, HOME_BID), vec![0u8; BLOCK_SIZE]);
+    }
+}
@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The on-disk structures of JBD2.
+//!
+//! Unlike Ext4, all the fields of the journal are stored in big-endian.
+
+use core::mem::size_of;
+
+use static_assertions::const_assert;
+
+use crate::{fs::utils::crc32c, prelude::*};
+
+/// The magic number of the journal blocks.
+pub(super) const JBD2_MAGIC: u32 = 0xC03B_3998;
+
+/// The type of the checksums, which can only be CRC32C.
+pub(super) const CHECKSUM_TYPE_CRC32C: u8 = 4;
+
+/// The size of the UUID that follows the first tag of a descriptor block.
+pub(super) const UUID_SIZE: usize = 16;
+
+/// The size of the checksum at the tail of the descriptor and revoke blocks.
+pub(super) const TAIL_SIZE: usize = size_of::<u32>();
+
+macro_rules! define_big_endian {
+    ($name:ident, $ty:ty) => {
+        /// A big-endian integer.
+        #[repr(C)]
+        #[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Pod)]
+        pub(super) struct $name {
+            raw: [u8; size_of::<$ty>()],
+        }
+
+        impl $name {
+            pub fn new(value: $ty) -> Self {
+                Self {
+                    raw: value.to_be_bytes(),
+                }
+            }
+
+            pub fn get(self) -> $ty {
+                <$ty>::from_be_bytes(self.raw)
+            }
+        }
+    };
+}
+
+define_big_endian!(Be32, u32);
+define_big_endian!(Be64, u64);
+
+/// The types of the journal blocks.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, TryFromInt)]
+pub(super) enum BlockType {
+    Descriptor = 1,
+    Commit = 2,
+    SuperBlockV1 = 3,
+    SuperBlockV2 = 4,
+    Revoke = 5,
+}
+
+bitflags! {
+    /// Incompatible feature set of the journal.
+    pub(super) struct FeatureInCompat: u32 {
+        /// Revoke blocks are used
+        const REVOKE = 1 << 0;
+        /// Block numbers have 64 bits
+        const IS_64BIT = 1 << 1;
+        /// Commit blocks are written without waiting for the data blocks
+        const ASYNC_COMMIT = 1 << 2;
+        /// The version 2 checksums are used
+        const CSUM_V2 = 1 << 3;
+        /// The version 3 checksums are used
+        const CSUM_V3 = 1 << 4;
+        /// The fast commits are used
+        const FAST_COMMIT = 1 << 5;
+    }
+}
+
+impl FeatureInCompat {
+    /// The incompatible features that are supported.
+    pub const SUPPORTED: Self = Self::from_bits_truncate(
+        Self::REVOKE.bits()
+            | Self::IS_64BIT.bits()
+            | Self::ASYNC_COMMIT.bits()
+            | Self::CSUM_V2.bits()
+            | Self::CSUM_V3.bits(),
+    );
+}
+
+bitflags! {
+    /// The flags of a tag in the descriptor block.
+    pub(super) struct TagFlags: u16 {
+        /// The first four bytes of the logged block were the magic number and are zeroed
+        const ESCAPE = 1 << 0;
+        /// The tag is not followed by a UUID
+        const SAME_UUID = 1 << 1;
+        /// The block has been deleted by this transaction (not used)
+        const DELETED = 1 << 2;
+        /// The tag is the last one in the descriptor block
+        const LAST_TAG = 1 << 3;
+    }
+}
+
+const_assert!(size_of::<RawHeader>() == 12);
+
+/// The header of each journal block except the data blocks.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+pub(super) struct RawHeader {
+    pub magic: Be32,
+    pub block_type: Be32,
+    /// The transaction that this block belongs to.
+    pub sequence: Be32,
+}
+
+impl RawHeader {
+    pub fn new(block_type: BlockType, sequence: u32) -> Self {
+        Self {
+            magic: Be32::new(JBD2_MAGIC),
+            block_type: Be32::new(block_type as u32),
+            sequence: Be32::new(sequence),
+        }
+    }
+
+    /// Returns the type of the block, or `None` if the block is not a journal block.
+    pub fn block_type(&self) -> Option<BlockType> {
+        if self.magic.get() != JBD2_MAGIC {
+            return None;
+        }
+        BlockType::try_from(self.block_type.get()).ok()
+    }
+}
+
+const_assert!(size_of::<RawSuperBlock>() == 1024);
+
+/// The superblock of the journal, which is stored in the first block of the journal.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct RawSuperBlock {
+    pub header: RawHeader,
+    pub block_size: Be32,
+    /// The number of blocks in the journal.
+    pub max_len: Be32,
+    /// The first block of the log.
+    pub first: Be32,
+    /// The sequence of the first transaction expected in the log.
+    pub sequence: Be32,
+    /// The block of the first transaction in the log, or zero if the log is empty.
+    pub start: Be32,
+    /// The error number, which is set if the filesystem is aborted.
+    pub errno: Be32,
+    // These fields are valid in the version 2 superblock.
+    pub feature_compat: Be32,
+    pub feature_incompat: Be32,
+    pub feature_ro_compat: Be32,
+    pub uuid: [u8; 16],
+    pub nr_users: Be32,
+    pub dynsuper: Be32,
+    pub max_transaction: Be32,
+    pub max_trans_data: Be32,
+    pub checksum_type: u8,
+    padding1: [u8; 3],
+    /// The number of blocks reserved for the fast commits.
+    pub num_fc_blocks: Be32,
+    pub head: Be32,
+    padding2: [u32; 40],
+    /// The checksum of the superblock.
+    pub checksum: Be32,
+    /// The UUIDs of the filesystems that share the journal.
+    pub users: [u8; 768],
+}
+
+impl RawSuperBlock {
+    /// Computes the checksum of the superblock.
+    pub fn compute_checksum(&self) -> u32 {
+        let mut sb = *self;
+        sb.checksum = Be32::default();
+        crc32c(!0, sb.as_bytes())
+    }
+}
+
+/// The header of the commit block.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+pub(super) struct RawCommitHeader {
+    pub header: RawHeader,
+    pub checksum_type: u8,
+    pub checksum_size: u8,
+    padding: [u8; 2],
+    pub checksum: [Be32; 8],
+    pub commit_sec: Be64,
+    pub commit_nsec: Be32,
+}
+
+/// The header of the revoke block, which is followed by the revoked block numbers.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, Pod)]
+pub(super) struct RawRevokeHeader {
+    pub header: RawHeader,
+    /// The number of bytes used in the block, including this header.
+    pub count: Be32,
+}
+
+/// The layout of the tags in the descriptor blocks, which depends on the features.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct TagLayout {
+    is_64bit: bool,
+    csum_v2: bool,
+    csum_v3: bool,
+}
+
+/// A tag in the descriptor block, which describes a logged block.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Tag {
+    /// The home location of the logged block.
+    pub block: u64,
+    pub flags: TagFlags,
+    /// The checksum of the logged block, which is truncated to 16 bits if the version 2
+    /// checksums are used.
+    pub checksum: u32,
+}
+
+impl TagLayout {
+    pub fn new(features: FeatureInCompat) -> Self {
+        Self {
+            is_64bit: features.contains(FeatureInCompat::IS_64BIT),
+            csum_v2: features.contains(FeatureInCompat::CSUM_V2),
+            csum_v3: features.contains(FeatureInCompat::CSUM_V3),
+        }
+    }
+
+    /// Returns the size of a tag, which is the same as `journal_tag_bytes` of Linux.
+    pub fn tag_size(&self) -> usize {
+        if self.csum_v3 {
+            return 16;
+        }
+        let size = if self.csum_v2 { 14 } else { 12 };
+        if self.is_64bit {
+            size
+        } else {
+            size - 4
+        }
+    }
+
+    /// Parses the tag at the beginning of `buf`.
+    pub fn read_tag(&self, buf: &[u8]) -> Tag {
+        let be32 = |offset: usize| u32::from_be_bytes(buf[offset..offset + 4].try_into().unwrap());
+        let be16 = |offset: usize| u16::from_be_bytes(buf[offset..offset + 2].try_into().unwrap());
+
+        let (flags, high, checksum) = if self.csum_v3 {
+            (be32(4) as u16, be32(8), be32(12))
+        } else {
+            let high = if self.is_64bit { be32(8) } else { 0 };
+            (be16(6), high, be16(4) as u32)
+        };
+        Tag {
+            block: (high as u64) << 32 | be32(0) as u64,
+            flags: TagFlags::from_bits_truncate(flags),
+            checksum,
+        }
+    }
+
+    /// Writes the `tag` to the beginning of `buf`.
+    pub fn write_tag(&self, buf: &mut [u8], tag: &Tag) {
+        let mut put = |offset: usize, bytes: &[u8]| {
+            buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+
+        put(0, &(tag.block as u32).to_be_bytes());
+        if self.csum_v3 {
+            put(4, &(tag.flags.bits() as u32).to_be_bytes());
+            put(8, &((tag.block >> 32) as u32).to_be_bytes());
+            put(12, &tag.checksum.to_be_bytes());
+            return;
+        }
+        put(4, &(tag.checksum as u16).to_be_bytes());
+        put(6, &tag.flags.bits().to_be_bytes());
+        if self.is_64bit {
+            put(8, &((tag.block >> 32) as u32).to_be_bytes());
+        }
+    }
+}
@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Recovery of the journal, which replays the committed transactions in the log.
+
+use core::mem::size_of;
+
+use aster_block::{id::Bid, BLOCK_SIZE};
+use ostd::mm::VmIo;
+
+use super::{
+    raw::{
+        Be32, BlockType, FeatureInCompat, RawCommitHeader, RawHeader, RawRevokeHeader, Tag,
+        TagFlags, JBD2_MAGIC, TAIL_SIZE, UUID_SIZE,
+    },
+    Journal,
+};
+use crate::{fs::utils::crc32c, prelude::*};
+
+/// A committed transaction found in the log.
+struct Transaction {
+    sequence: u32,
+    /// The logged blocks, each of which is located by the journal block and described by
+    /// the tag.
+    blocks: Vec<(u32, Tag)>,
+    /// The blocks revoked by this transaction, which must not be replayed from the earlier
+    /// transactions.
+    revoked: Vec<u64>,
+}
+
+impl Transaction {
+    fn new(sequence: u32) -> Self {
+        Self {
+            sequence,
+            blocks: Vec::new(),
+            revoked: Vec::new(),
+        }
+    }
+}
+
+impl Journal {
+    /// Replays the committed transactions in the log, then marks the log as empty.
+    ///
+    /// A transaction without a valid commit block is discarded, which is the case if the
+    /// system crashed before the transaction was committed.
+    pub fn recover(&self) -> Result<()> {
+        let mut super_block = self.super_block.lock();
+        let start = super_block.start.get();
+        if start == 0 {
+            return Ok(());
+        }
+
+        let (transactions, end_sequence) = self.scan(start, super_block.sequence.get())?;
+
+        // The later revocation takes effect on the earlier transactions and itself.
+        let mut revoked: BTreeMap<u64, u32> = BTreeMap::new();
+        for transaction in transactions.iter() {
+            for block in transaction.revoked.iter() {
+                revoked.insert(*block, transaction.sequence);
+            }
+        }
+
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        for transaction in transactions.iter() {
+            for (log_block, tag) in transaction.blocks.iter() {
+                if revoked
+                    .get(&tag.block)
+                    .is_some_and(|&sequence| !sequence_after(transaction.sequence, sequence))
+                {
+                    continue;
+                }
+
+                self.read_log_block(*log_block, &mut buf)?;
+                if !self.verify_block_checksum(transaction.sequence, &buf, tag.checksum) {
+                    warn!("jbd2: bad checksum of the logged block {}", tag.block);
+                    continue;
+                }
+                if tag.flags.contains(TagFlags::ESCAPE) {
+                    buf[..4].copy_from_slice(&JBD2_MAGIC.to_be_bytes());
+                }
+                self.device
+                    .write_bytes(Bid::new(tag.block).to_offset(), &buf)?;
+            }
+        }
+        self.flush()?;
+
+        // Skips a sequence in case a stale block of the next transaction remains in the log.
+        super_block.start = Be32::new(0);
+        super_block.sequence = Be32::new(end_sequence.wrapping_add(1));
+        self.write_super_block(&mut super_block)
+    }
+
+    /// Scans the log from the journal block `start`, where the first transaction is
+    /// expected to be the `sequence`.
+    ///
+    /// Returns the committed transactions and the sequence that ends the log.
+    fn scan(&self, start: u32, sequence: u32) -> Result<(Vec<Transaction>, u32)> {
+        if start < self.first || start >= self.len {
+            return_errno_with_message!(Errno::EUCLEAN, "the journal is corrupted");
+        }
+
+        let mut transactions = Vec::new();
+        let mut transaction = Transaction::new(sequence);
+        let mut buf = vec![0u8; BLOCK_SIZE];
+        let mut log_block = start;
+        // The log never wraps around the journal more than once.
+        let mut remaining = self.len - self.first;
+
+        while remaining > 0 {
+            self.read_log_block(log_block, &mut buf)?;
+            let header = RawHeader::from_bytes(&buf[..size_of::<RawHeader>()]);
+            if header.sequence.get() != transaction.sequence {
+                break;
+            }
+
+            match header.block_type() {
+                Some(BlockType::Descriptor) => {
+                    if !self.verify_tail_checksum(&buf) {
+                        break;
+                    }
+                    let tags = self.read_tags(&buf);
+                    remaining = remaining.saturating_sub(tags.len() as u32);
+                    for tag in tags {
+                        log_block = self.next_block(log_block);
+                        transaction.blocks.push((log_block, tag));
+                    }
+                }
+                Some(BlockType::Revoke) => {
+                    if !self.verify_tail_checksum(&buf) {
+                        break;
+                    }
+                    let Some(revoked) = self.read_revoked(&buf) else {
+                        break;
+                    };
+                    transaction.revoked.extend(revoked);
+                }
+                Some(BlockType::Commit) => {
+                    if !self.verify_commit_checksum(&buf) {
+                        break;
+                    }
+                    let next_transaction = Transaction::new(transaction.sequence.wrapping_add(1));
+                    transactions.push(core::mem::replace(&mut transaction, next_transaction));
+                }
+                _ => break,
+            }
+
+            log_block = self.next_block(log_block);
+            remaining = remaining.saturating_sub(1);
+        }
+
+        Ok((transactions, transaction.sequence))
+    }
+
+    /// Parses the tags in the descriptor block.
+    fn read_tags(&self, buf: &[u8]) -> Vec<Tag> {
+        let tag_size = self.tag_layout.tag_size();
+        let end = BLOCK_SIZE - self.tail_size();
+        let mut offset = size_of::<RawHeader>();
+        let mut tags = Vec::new();
+        while offset + tag_size <= end {
+            let tag = self.tag_layout.read_tag(&buf[offset..]);
+            offset += tag_size;
+            if !tag.flags.contains(TagFlags::SAME_UUID) {
+                offset += UUID_SIZE;
+            }
+            tags.push(tag);
+            if tag.flags.contains(TagFlags::LAST_TAG) {
+                break;
+            }
+        }
+        tags
+    }
+
+    /// Parses the revoked blocks in the revoke block.
+    ///
+    /// Returns `None` if the revoke block is corrupted.
+    fn read_revoked(&self, buf: &[u8]) -> Option<Vec<u64>> {
+        let header = RawRevokeHeader::from_bytes(&buf[..size_of::<RawRevokeHeader>()]);
+        let count = header.count.get() as usize;
+        if count < size_of::<RawRevokeHeader>() || count > BLOCK_SIZE - self.tail_size() {
+            return None;
+        }
+
+        let record_size = if self.features.contains(FeatureInCompat::IS_64BIT) {
+            size_of::<u64>()
+        } else {
+            size_of::<u32>()
+        };
+        let records = &buf[size_of::<RawRevokeHeader>()..count];
+        let revoked = records
+            .chunks_exact(record_size)
+            .map(|record| {
+                if record_size == size_of::<u64>() {
+                    u64::from_be_bytes(record.try_into().unwrap())
+                } else {
+                    u32::from_be_bytes(record.try_into().unwrap()) as u64
+                }
+            })
+            .collect();
+        Some(revoked)
+    }
+
+    fn verify_tail_checksum(&self, buf: &[u8]) -> bool {
+        let Some(expected) = self.tail_checksum(buf) else {
+            return true;
+        };
+        let checksum = u32::from_be_bytes(buf[BLOCK_SIZE - TAIL_SIZE..].try_into().unwrap());
+        checksum == expected
+    }
+
+    fn verify_commit_checksum(&self, buf: &[u8]) -> bool {
+        let Some(seed) = self.csum_seed else {
+            return true;
+        };
+
+        // The checksum is computed with its own field treated as zeros.
+        let header_len = size_of::<RawCommitHeader>();
+        let mut header = RawCommitHeader::from_bytes(&buf[..header_len]);
+        let checksum = header.checksum[0].get();
+        header.checksum[0] = Be32::new(0);
+        let crc = crc32c(seed, header.as_bytes());
+        checksum == crc32c(crc, &buf[header_len..])
+    }
+
+    fn verify_block_checksum(&self, sequence: u32, buf: &[u8], checksum: u32) -> bool {
+        let Some(expected) = self.block_checksum(sequence, buf) else {
+            return true;
+        };
+        if self.features.contains(FeatureInCompat::CSUM_V3) {
+            checksum == expected
+        } else {
+            checksum == expected & 0xFFFF
+        }
+    }
+}
+
+/// Returns whether the sequence `a` is after `b`, taking the wraparound into account.
+fn sequence_after(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
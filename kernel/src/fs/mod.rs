@@ -8,7 +8,12 @@ pub mod file_handle;
 pub mod file_table;
 pub mod fs_resolver;
 pub mod inode_handle;
+pub mod inotify;
+pub mod io_uring;
+pub mod jbd2;
+pub mod mqueue;
 pub mod named_pipe;
+pub mod overlayfs;
 pub mod path;
 pub mod pipe;
 pub mod procfs;
@@ -49,6 +54,7 @@ fn start_block_device(device_name: &str) -> Result<Arc<dyn BlockDevice>> {
 pub fn lazy_init() {
     //The device name is specified in qemu args as --serial={device_name}
     let ext2_device_name = "vext2";
+    let ext4_device_name = "vext4";
     let exfat_device_name = "vexfat";
 
     if let Ok(block_device_ext2) = start_block_device(ext2_device_name) {
@@ -58,6 +64,13 @@ pub fn lazy_init() {
         self::rootfs::mount_fs_at(ext2_fs, &target_path).unwrap();
     }
 
+    if let Ok(block_device_ext4) = start_block_device(ext4_device_name) {
+        let ext4_fs = Ext2::open(block_device_ext4).unwrap();
+        let target_path = FsPath::try_from("/ext4").unwrap();
+        println!("[kernel] Mount Ext4 fs at {:?} ", target_path);
+        self::rootfs::mount_fs_at(ext4_fs, &target_path).unwrap();
+    }
+
     if let Ok(block_device_exfat) = start_block_device(exfat_device_name) {
         let exfat_fs = ExfatFS::open(block_device_exfat, ExfatMountOptions::default()).unwrap();
         let target_path = FsPath::try_from("/exfat").unwrap();
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::*;
+use crate::{
+    events::IoEvents,
+    process::signal::{PollHandle, Pollable},
+};
+
+/// The inode of a POSIX message queue.
+pub struct MqueueInode {
+    queue: Arc<MessageQueue>,
+    metadata: RwLock<Metadata>,
+    fs: Weak<MqueueFS>,
+}
+
+impl MqueueInode {
+    pub(super) fn new(
+        ino: u64,
+        mode: InodeMode,
+        uid: Uid,
+        gid: Gid,
+        queue: MessageQueue,
+        fs: Weak<MqueueFS>,
+    ) -> Arc<Self> {
+        let mut metadata = Metadata::new_file(ino, mode, BLOCK_SIZE);
+        metadata.uid = uid;
+        metadata.gid = gid;
+
+        Arc::new(Self {
+            queue: Arc::new(queue),
+            metadata: RwLock::new(metadata),
+            fs,
+        })
+    }
+
+    pub fn queue(&self) -> &Arc<MessageQueue> {
+        &self.queue
+    }
+}
+
+impl Inode for MqueueInode {
+    /// Do not cache dentry in DCACHE.
+    ///
+    /// The queue can be removed by `mq_unlink` through the internal mount,
+    /// which is not seen by the dentries of other mounts.
+    fn is_dentry_cacheable(&self) -> bool {
+        false
+    }
+
+    fn size(&self) -> usize {
+        self.metadata.read().size
+    }
+
+    fn resize(&self, new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EINVAL))
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino as _
+    }
+
+    fn type_(&self) -> InodeType {
+        self.metadata.read().type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    /// Reads the status of the queue.
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let status = self.queue.status();
+        let Some(status) = status.as_bytes().get(offset..) else {
+            return Ok(0);
+        };
+        let len = writer.write_fallible(&mut status.into())?;
+        Ok(len)
+    }
+
+    fn read_direct_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        self.read_at(offset, writer)
+    }
+
+    fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        Err(Error::new(Errno::EINVAL))
+    }
+
+    fn write_direct_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        Err(Error::new(Errno::EINVAL))
+    }
+
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.queue.poll(mask, poller)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
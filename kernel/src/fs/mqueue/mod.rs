@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#![allow(unused_variables)]
+
+//! The mqueue file system.
+//!
+//! Each regular file in the file system is a POSIX message queue. The queues
+//! are created by `mq_open` and removed by `mq_unlink`, which operate on an
+//! internal mount of the file system. The file system can also be mounted
+//! (normally at `/dev/mqueue`) to list and remove the queues.
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use aster_util::slot_vec::SlotVec;
+use spin::Once;
+
+pub use self::inode::MqueueInode;
+use crate::{
+    fs::{
+        path::{Dentry, MountNode},
+        utils::{
+            DirentVisitor, FileSystem, FsFlags, Inode, InodeMode, InodeType, Metadata, MknodType,
+            SuperBlock, NAME_MAX,
+        },
+    },
+    ipc::msg::posix::{MessageQueue, DFLT_MSGMAX, DFLT_MSGSIZEMAX},
+    prelude::*,
+    process::{Gid, Uid},
+    time::clocks::RealTimeCoarseClock,
+};
+
+mod inode;
+
+const MQUEUE_MAGIC: u64 = 0x19800202;
+const BLOCK_SIZE: usize = 4096;
+const ROOT_INO: u64 = 1;
+
+/// Returns the mqueue file system.
+///
+/// All the mounts of the file system share the same queues.
+pub fn mqueue_fs() -> Arc<MqueueFS> {
+    static MQUEUE_FS: Once<Arc<MqueueFS>> = Once::new();
+    MQUEUE_FS.call_once(MqueueFS::new).clone()
+}
+
+/// Returns the root of the internal mount of the mqueue file system.
+pub fn mqueue_root() -> Dentry {
+    static MQUEUE_MOUNT: Once<Arc<MountNode>> = Once::new();
+    let mount_node = MQUEUE_MOUNT.call_once(|| MountNode::new_root(mqueue_fs()));
+    Dentry::new_fs_root(mount_node.clone())
+}
+
+pub struct MqueueFS {
+    sb: SuperBlock,
+    root: Arc<RootInode>,
+    next_ino: AtomicU64,
+}
+
+impl MqueueFS {
+    fn new() -> Arc<Self> {
+        Arc::new_cyclic(|weak_self| Self {
+            sb: SuperBlock::new(MQUEUE_MAGIC, BLOCK_SIZE, NAME_MAX),
+            root: RootInode::new(weak_self.clone()),
+            next_ino: AtomicU64::new(ROOT_INO + 1),
+        })
+    }
+
+    /// Creates a queue file named `name` with the owner, the permission mode,
+    /// and the limits of the queue.
+    pub fn create_queue(
+        &self,
+        name: &str,
+        mode: InodeMode,
+        uid: Uid,
+        gid: Gid,
+        queue: MessageQueue,
+    ) -> Result<Arc<MqueueInode>> {
+        self.root.create_queue(name, mode, uid, gid, queue)
+    }
+
+    fn alloc_ino(&self) -> u64 {
+        self.next_ino.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+impl FileSystem for MqueueFS {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+
+    fn flags(&self) -> FsFlags {
+        FsFlags::empty()
+    }
+}
+
+struct RootInode {
+    queues: RwLock<SlotVec<(String, Arc<MqueueInode>)>>,
+    metadata: RwLock<Metadata>,
+    fs: Weak<MqueueFS>,
+}
+
+impl RootInode {
+    fn new(fs: Weak<MqueueFS>) -> Arc<Self> {
+        Arc::new(Self {
+            queues: RwLock::new(SlotVec::new()),
+            metadata: RwLock::new(Metadata::new_dir(
+                ROOT_INO,
+                InodeMode::from_bits_truncate(0o1777),
+                BLOCK_SIZE,
+            )),
+            fs,
+        })
+    }
+
+    fn create_queue(
+        &self,
+        name: &str,
+        mode: InodeMode,
+        uid: Uid,
+        gid: Gid,
+        queue: MessageQueue,
+    ) -> Result<Arc<MqueueInode>> {
+        if name.len() > NAME_MAX {
+            return_errno_with_message!(Errno::ENAMETOOLONG, "the name is too long");
+        }
+
+        let mut queues = self.queues.write();
+        if queues
+            .idxes_and_items()
+            .any(|(_, (child, _))| child == name)
+        {
+            return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+        }
+
+        let fs = self.fs.upgrade().unwrap();
+        let inode = MqueueInode::new(fs.alloc_ino(), mode, uid, gid, queue, self.fs.clone());
+        queues.put((name.to_string(), inode.clone()));
+        drop(queues);
+
+        let now = now();
+        let mut metadata = self.metadata.write();
+        metadata.mtime = now;
+        metadata.ctime = now;
+        Ok(inode)
+    }
+}
+
+impl Inode for RootInode {
+    fn size(&self) -> usize {
+        self.metadata.read().size
+    }
+
+    fn resize(&self, new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn metadata(&self) -> Metadata {
+        *self.metadata.read()
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino as _
+    }
+
+    fn type_(&self) -> InodeType {
+        self.metadata.read().type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().atime
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().atime = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().mtime
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().mtime = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().ctime
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().ctime = time;
+    }
+
+    /// Creates a queue with the default limits.
+    fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        if type_ != InodeType::File {
+            return_errno_with_message!(Errno::EPERM, "only queues can be created");
+        }
+
+        let queue = MessageQueue::new(DFLT_MSGMAX, DFLT_MSGSIZEMAX);
+        let inode = self.create_queue(name, mode, Uid::new_root(), Gid::new_root(), queue)?;
+        Ok(inode)
+    }
+
+    fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Arc<dyn Inode>> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        let try_readdir = |offset: &mut usize, visitor: &mut dyn DirentVisitor| -> Result<()> {
+            // Read the 2 special entries.
+            if *offset == 0 {
+                visitor.visit(".", self.ino(), self.type_(), *offset)?;
+                *offset += 1;
+            }
+            if *offset == 1 {
+                visitor.visit("..", self.ino(), self.type_(), *offset)?;
+                *offset += 1;
+            }
+
+            // Read the queues.
+            let queues = self.queues.read();
+            let start_offset = *offset;
+            for (idx, (name, inode)) in queues
+                .idxes_and_items()
+                .map(|(idx, (name, inode))| (idx + 2, (name, inode)))
+                .skip_while(|(idx, _)| idx < &start_offset)
+            {
+                visitor.visit(name.as_ref(), inode.ino(), inode.type_(), idx)?;
+                *offset = idx + 1;
+            }
+            Ok(())
+        };
+
+        let mut iterate_offset = offset;
+        match try_readdir(&mut iterate_offset, visitor) {
+            Err(e) if offset == iterate_offset => Err(e),
+            _ => Ok(iterate_offset - offset),
+        }
+    }
+
+    fn link(&self, old: &Arc<dyn Inode>, name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        let mut queues = self.queues.write();
+        let Some(idx) = queues
+            .idxes_and_items()
+            .find(|(_, (child, _))| child == name)
+            .map(|(idx, _)| idx)
+        else {
+            return_errno_with_message!(Errno::ENOENT, "the queue does not exist");
+        };
+        // The queue is destroyed after all its descriptors are closed.
+        queues.remove(idx);
+        drop(queues);
+
+        let now = now();
+        let mut metadata = self.metadata.write();
+        metadata.mtime = now;
+        metadata.ctime = now;
+        Ok(())
+    }
+
+    fn rmdir(&self, name: &str) -> Result<()> {
+        Err(Error::new(Errno::ENOTDIR))
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        let inode = match name {
+            "." | ".." => self.fs().root_inode(),
+            name => self
+                .queues
+                .read()
+                .idxes_and_items()
+                .find(|(_, (child, _))| child == name)
+                .map(|(_, (_, inode))| inode.clone())
+                .ok_or(Error::new(Errno::ENOENT))?,
+        };
+        Ok(inode)
+    }
+
+    fn rename(&self, old_name: &str, target: &Arc<dyn Inode>, new_name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+}
+
+fn now() -> Duration {
+    RealTimeCoarseClock::get().read_time()
+}
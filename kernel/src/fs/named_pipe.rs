@@ -46,6 +46,14 @@ impl FileLike for NamedPipe {
         self.writer.write(reader)
     }
 
+    fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.reader.read_nonblocking(writer)
+    }
+
+    fn write_nonblocking(&self, reader: &mut VmReader) -> Result<usize> {
+        self.writer.write_nonblocking(reader)
+    }
+
     fn access_mode(&self) -> AccessMode {
         AccessMode::O_RDWR
     }
@@ -0,0 +1,930 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use aster_rights::Full;
+
+use super::{whiteout::Whiteout, *};
+use crate::{
+    device::get_device,
+    events::IoEvents,
+    fs::{
+        device::Device,
+        utils::{
+            AccessMode, DirentVisitor, Extension, FallocMode, FileSystem, FsFlags, Inode,
+            InodeMode, InodeType, IoctlCmd, Metadata, MknodType, SuperBlock, XattrName,
+            XattrSetFlags,
+        },
+    },
+    prelude::*,
+    process::{signal::PollHandle, Gid, Uid},
+    vm::vmo::Vmo,
+};
+
+/// The options of mounting an overlay file system.
+///
+/// They come from the `data` argument of `mount`, e.g.,
+/// `lowerdir=/lower1:/lower2,upperdir=/upper,workdir=/work`.
+#[derive(Debug, Default)]
+pub struct OverlayMountOptions {
+    /// The paths of the lower directories, from the top to the bottom.
+    pub lowerdirs: Vec<String>,
+    /// The path of the upper directory.
+    pub upperdir: Option<String>,
+    /// The path of the work directory, which must be on the same file system as
+    /// the upper directory.
+    pub workdir: Option<String>,
+}
+
+impl OverlayMountOptions {
+    /// Parses the comma-separated mount options.
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut options = Self::default();
+
+        for option in data.split(',').filter(|option| !option.is_empty()) {
+            let (key, value) = option.split_once('=').unwrap_or((option, ""));
+            match key {
+                "lowerdir" => {
+                    options.lowerdirs = value.split(':').map(String::from).collect();
+                    if options.lowerdirs.iter().any(|dir| dir.is_empty()) {
+                        return_errno_with_message!(Errno::EINVAL, "the lowerdir is empty");
+                    }
+                }
+                "upperdir" if !value.is_empty() => options.upperdir = Some(String::from(value)),
+                "workdir" if !value.is_empty() => options.workdir = Some(String::from(value)),
+                "upperdir" | "workdir" => {
+                    return_errno_with_message!(Errno::EINVAL, "the upperdir or workdir is empty")
+                }
+                _ => warn!("unsupported overlay mount option: {}", option),
+            }
+        }
+
+        if options.lowerdirs.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "the lowerdir is missing");
+        }
+        if options.upperdir.is_some() != options.workdir.is_some() {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the upperdir and workdir must be specified together"
+            );
+        }
+
+        Ok(options)
+    }
+}
+
+/// A file system that merges a writable upper directory and read-only lower directories.
+pub struct OverlayFS {
+    /// The super block
+    sb: SuperBlock,
+    /// Root inode
+    root: Arc<OverlayInode>,
+    /// The directory in which files are prepared before being moved to the upper layer.
+    ///
+    /// It is `None` if the overlay is read-only.
+    work: Option<Arc<dyn Inode>>,
+    /// An allocator of the names of the temporary files in `work`
+    tmp_allocator: AtomicU64,
+}
+
+impl OverlayFS {
+    /// Creates an overlay file system.
+    ///
+    /// The `lowers` are ordered from the top to the bottom. If `upper_and_work` is `None`,
+    /// the overlay is read-only.
+    pub fn new(
+        lowers: Vec<Arc<dyn Inode>>,
+        upper_and_work: Option<(Arc<dyn Inode>, Arc<dyn Inode>)>,
+    ) -> Result<Arc<Self>> {
+        if lowers.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "no lower layer");
+        }
+        if lowers.iter().any(|lower| lower.type_() != InodeType::Dir) {
+            return_errno_with_message!(Errno::ENOTDIR, "the lower layer is not a directory");
+        }
+
+        let (upper, work) = match upper_and_work {
+            Some((upper, work_parent)) => {
+                if upper.type_() != InodeType::Dir || work_parent.type_() != InodeType::Dir {
+                    return_errno_with_message!(
+                        Errno::ENOTDIR,
+                        "the upper or work layer is not a directory"
+                    );
+                }
+                if !Arc::ptr_eq(&upper.fs(), &work_parent.fs()) {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the upper and work layers must be on the same file system"
+                    );
+                }
+                let work = prepare_work_dir(&work_parent)?;
+                (Some(upper), Some(work))
+            }
+            None => (None, None),
+        };
+
+        Ok(Arc::new_cyclic(|weak_fs| Self {
+            sb: SuperBlock::new(OVERLAYFS_MAGIC, BLOCK_SIZE, NAME_MAX),
+            root: Arc::new_cyclic(|weak_root| OverlayInode {
+                typ: InodeType::Dir,
+                upper: Mutex::new(upper),
+                lowers,
+                name_and_parent: Mutex::new(None),
+                children: Mutex::new(BTreeMap::new()),
+                this: weak_root.clone(),
+                fs: weak_fs.clone(),
+                extension: Extension::new(),
+            }),
+            work,
+            tmp_allocator: AtomicU64::new(0),
+        }))
+    }
+
+    fn alloc_tmp_name(&self) -> String {
+        format!("#{:x}", self.tmp_allocator.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl FileSystem for OverlayFS {
+    fn sync(&self) -> Result<()> {
+        if let Some(upper) = self.root.upper() {
+            upper.fs().sync()?;
+        }
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+
+    fn flags(&self) -> FsFlags {
+        FsFlags::empty()
+    }
+}
+
+/// An inode of `OverlayFS`.
+///
+/// A directory may be backed by the directories of the same name in several layers,
+/// while other types of inodes are backed by exactly one inode, which is in the upper
+/// layer once copied up.
+pub(super) struct OverlayInode {
+    /// Type of the inode
+    typ: InodeType,
+    /// The inode in the upper layer
+    upper: Mutex<Option<Arc<dyn Inode>>>,
+    /// The inodes in the lower layers, from the top to the bottom
+    lowers: Vec<Arc<dyn Inode>>,
+    /// The name and the parent, which is `None` for the root or a removed inode
+    name_and_parent: Mutex<Option<(String, Arc<OverlayInode>)>>,
+    /// The children that have been looked up
+    children: Mutex<BTreeMap<String, Weak<OverlayInode>>>,
+    /// Reference to self
+    this: Weak<OverlayInode>,
+    /// Reference to fs
+    fs: Weak<OverlayFS>,
+    /// Extensions
+    extension: Extension,
+}
+
+impl OverlayInode {
+    fn new_child(
+        &self,
+        name: &str,
+        upper: Option<Arc<dyn Inode>>,
+        lowers: Vec<Arc<dyn Inode>>,
+    ) -> Arc<Self> {
+        let typ = upper.as_ref().unwrap_or_else(|| &lowers[0]).type_();
+        let child = Arc::new_cyclic(|weak_child| Self {
+            typ,
+            upper: Mutex::new(upper),
+            lowers,
+            name_and_parent: Mutex::new(Some((String::from(name), self.this()))),
+            children: Mutex::new(BTreeMap::new()),
+            this: weak_child.clone(),
+            fs: self.fs.clone(),
+            extension: Extension::new(),
+        });
+        self.children
+            .lock()
+            .insert(String::from(name), Arc::downgrade(&child));
+        child
+    }
+
+    fn this(&self) -> Arc<Self> {
+        self.this.upgrade().unwrap()
+    }
+
+    fn overlay_fs(&self) -> Arc<OverlayFS> {
+        self.fs.upgrade().unwrap()
+    }
+
+    fn upper(&self) -> Option<Arc<dyn Inode>> {
+        self.upper.lock().clone()
+    }
+
+    /// Returns the inode that currently provides the data and the attributes.
+    fn real(&self) -> Arc<dyn Inode> {
+        self.upper().unwrap_or_else(|| self.lowers[0].clone())
+    }
+
+    fn check_dir(&self) -> Result<()> {
+        if self.typ != InodeType::Dir {
+            return_errno_with_message!(Errno::ENOTDIR, "self is not dir");
+        }
+        Ok(())
+    }
+
+    /// Copies up the inode if it is not in the upper layer, and returns the upper inode.
+    fn copy_up(&self) -> Result<Arc<dyn Inode>> {
+        if let Some(upper) = self.upper() {
+            return Ok(upper);
+        }
+
+        let fs = self.overlay_fs();
+        let Some(work) = fs.work.as_ref() else {
+            return_errno_with_message!(Errno::EROFS, "the overlay has no upper layer");
+        };
+        let Some((name, parent)) = self.name_and_parent.lock().clone() else {
+            return_errno_with_message!(Errno::ENOENT, "the inode has been removed");
+        };
+        let parent_upper = parent.copy_up()?;
+
+        let mut upper = self.upper.lock();
+        if let Some(upper) = upper.as_ref() {
+            return Ok(upper.clone());
+        }
+
+        let tmp_name = fs.alloc_tmp_name();
+        let tmp = copy_inode_to(&self.lowers[0], work, &tmp_name)?;
+        if let Err(err) = work.rename(&tmp_name, &parent_upper, &name) {
+            remove_entry(work, &tmp_name, self.typ);
+            return Err(err);
+        }
+
+        *upper = Some(tmp.clone());
+        Ok(tmp)
+    }
+
+    /// Looks up a name in the layers without using the cache of the children.
+    fn lookup_in_layers(&self, name: &str) -> Result<Arc<Self>> {
+        let mut upper = None;
+        if let Some(upper_dir) = self.upper() {
+            match lookup_entry(&upper_dir, name)? {
+                Some(child) if Whiteout::is_whiteout(child.as_ref()) => {
+                    return_errno_with_message!(Errno::ENOENT, "the name is whited out");
+                }
+                Some(child) if child.type_() != InodeType::Dir || is_opaque(&child) => {
+                    return Ok(self.new_child(name, Some(child), Vec::new()));
+                }
+                child => upper = child,
+            }
+        }
+
+        let mut lowers = Vec::new();
+        for lower_dir in self.lowers.iter() {
+            let Some(child) = lookup_entry(lower_dir, name)? else {
+                continue;
+            };
+            if Whiteout::is_whiteout(child.as_ref()) {
+                break;
+            }
+            if child.type_() != InodeType::Dir {
+                // A non-directory hides the layers below it. It is visible only if no
+                // directory has been found in the layers above it.
+                if upper.is_none() && lowers.is_empty() {
+                    lowers.push(child);
+                }
+                break;
+            }
+
+            let is_opaque = is_opaque(&child);
+            lowers.push(child);
+            if is_opaque {
+                break;
+            }
+        }
+
+        if upper.is_none() && lowers.is_empty() {
+            return_errno_with_message!(Errno::ENOENT, "the name does not exist");
+        }
+        Ok(self.new_child(name, upper, lowers))
+    }
+
+    /// Returns whether a lower layer has the name, in which case a whiteout is required
+    /// to remove the name from the merged view.
+    fn lower_has(&self, name: &str) -> Result<bool> {
+        for lower_dir in self.lowers.iter() {
+            if let Some(child) = lookup_entry(lower_dir, name)? {
+                return Ok(!Whiteout::is_whiteout(child.as_ref()));
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the entries of the merged view except "." and "..".
+    fn merged_entries(&self) -> Result<Vec<(String, u64, InodeType)>> {
+        let mut entries = Vec::new();
+        let mut seen_names = BTreeSet::new();
+
+        for layer_dir in self.upper().iter().chain(self.lowers.iter()) {
+            for (name, ino, type_) in read_entries(layer_dir)? {
+                if name == "." || name == ".." || !seen_names.insert(name.clone()) {
+                    continue;
+                }
+                if type_ == InodeType::CharDevice {
+                    let child = layer_dir.lookup(&name)?;
+                    if Whiteout::is_whiteout(child.as_ref()) {
+                        continue;
+                    }
+                }
+                entries.push((name, ino, type_));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Prepares the creation of the name, and returns the upper directory and whether
+    /// the name has been whited out.
+    fn prepare_create(&self, name: &str) -> Result<(Arc<dyn Inode>, bool)> {
+        self.check_dir()?;
+        match self.lookup(name) {
+            Ok(_) => return_errno_with_message!(Errno::EEXIST, "the name already exists"),
+            Err(err) if err.error() == Errno::ENOENT => {}
+            Err(err) => return Err(err),
+        }
+
+        let upper = self.copy_up()?;
+        let is_whited_out = match lookup_entry(&upper, name)? {
+            Some(whiteout) if Whiteout::is_whiteout(whiteout.as_ref()) => {
+                upper.unlink(name)?;
+                true
+            }
+            _ => false,
+        };
+        Ok((upper, is_whited_out))
+    }
+
+    /// Removes a child from the merged view, leaving a whiteout if a lower layer has it.
+    fn remove_child(&self, name: &str, child: &Arc<Self>) -> Result<()> {
+        let upper = self.copy_up()?;
+        if let Some(child_upper) = child.upper() {
+            if child.typ == InodeType::Dir {
+                clear_whiteouts(&child_upper)?;
+                upper.rmdir(name)?;
+            } else {
+                upper.unlink(name)?;
+            }
+        }
+        if self.lower_has(name)? {
+            create_whiteout(&upper, name)?;
+        }
+
+        self.children.lock().remove(name);
+        *child.name_and_parent.lock() = None;
+        Ok(())
+    }
+}
+
+impl Inode for OverlayInode {
+    fn page_cache(&self) -> Option<Vmo<Full>> {
+        self.real().page_cache()
+    }
+
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        self.real().read_at(offset, writer)
+    }
+
+    fn read_direct_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        self.real().read_direct_at(offset, writer)
+    }
+
+    fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        self.copy_up()?.write_at(offset, reader)
+    }
+
+    fn write_direct_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        self.copy_up()?.write_direct_at(offset, reader)
+    }
+
+    fn size(&self) -> usize {
+        self.real().size()
+    }
+
+    fn resize(&self, new_size: usize) -> Result<()> {
+        self.copy_up()?.resize(new_size)
+    }
+
+    fn metadata(&self) -> Metadata {
+        self.real().metadata()
+    }
+
+    fn ino(&self) -> u64 {
+        self.real().ino()
+    }
+
+    fn type_(&self) -> InodeType {
+        self.typ
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        self.real().mode()
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.copy_up()?.set_mode(mode)
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        self.real().owner()
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.copy_up()?.set_owner(uid)
+    }
+
+    fn group(&self) -> Result<Gid> {
+        self.real().group()
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.copy_up()?.set_group(gid)
+    }
+
+    fn atime(&self) -> Duration {
+        self.real().atime()
+    }
+
+    fn set_atime(&self, time: Duration) {
+        if let Ok(upper) = self.copy_up() {
+            upper.set_atime(time);
+        }
+    }
+
+    fn mtime(&self) -> Duration {
+        self.real().mtime()
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        if let Ok(upper) = self.copy_up() {
+            upper.set_mtime(time);
+        }
+    }
+
+    fn ctime(&self) -> Duration {
+        self.real().ctime()
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        if let Ok(upper) = self.copy_up() {
+            upper.set_ctime(time);
+        }
+    }
+
+    fn prepare_open(&self, access_mode: AccessMode) -> Result<()> {
+        // Copy up the file before it can be written, including via shared memory mappings
+        // of its page cache.
+        if access_mode.is_writable() {
+            self.copy_up()?;
+        }
+        Ok(())
+    }
+
+    fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        let (upper, is_whited_out) = self.prepare_create(name)?;
+        let new_upper = upper.create(name, type_, mode)?;
+        if type_ == InodeType::Dir && is_whited_out {
+            set_opaque(&new_upper)?;
+        }
+
+        Ok(self.new_child(name, Some(new_upper), Vec::new()))
+    }
+
+    fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Arc<dyn Inode>> {
+        let (upper, _) = self.prepare_create(name)?;
+        let new_upper = upper.mknod(name, mode, type_)?;
+
+        Ok(self.new_child(name, Some(new_upper), Vec::new()))
+    }
+
+    fn as_device(&self) -> Option<Arc<dyn Device>> {
+        self.real().as_device()
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        self.check_dir()?;
+
+        let parent_ino = match self.name_and_parent.lock().as_ref() {
+            Some((_, parent)) => parent.ino(),
+            None => self.ino(),
+        };
+        let mut entries = vec![
+            (String::from("."), self.ino(), InodeType::Dir),
+            (String::from(".."), parent_ino, InodeType::Dir),
+        ];
+        entries.extend(self.merged_entries()?);
+
+        let try_visit = |idx: &mut usize, visitor: &mut dyn DirentVisitor| -> Result<()> {
+            for (name, ino, type_) in entries.iter().skip(*idx) {
+                visitor.visit(name, *ino, *type_, *idx)?;
+                *idx += 1;
+            }
+            Ok(())
+        };
+
+        let mut iterate_idx = offset;
+        match try_visit(&mut iterate_idx, visitor) {
+            Err(e) if offset == iterate_idx => Err(e),
+            _ => Ok(iterate_idx - offset),
+        }
+    }
+
+    fn link(&self, old: &Arc<dyn Inode>, name: &str) -> Result<()> {
+        let Some(old) = old.downcast_ref::<OverlayInode>() else {
+            return_errno_with_message!(Errno::EXDEV, "not same fs");
+        };
+        if old.typ == InodeType::Dir {
+            return_errno_with_message!(Errno::EPERM, "old is a dir");
+        }
+
+        let old_upper = old.copy_up()?;
+        let (upper, _) = self.prepare_create(name)?;
+        upper.link(&old_upper, name)
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        self.check_dir()?;
+        let child = self.lookup_child(name)?;
+        if child.typ == InodeType::Dir {
+            return_errno_with_message!(Errno::EISDIR, "unlink on dir");
+        }
+
+        self.remove_child(name, &child)
+    }
+
+    fn rmdir(&self, name: &str) -> Result<()> {
+        self.check_dir()?;
+        let child = self.lookup_child(name)?;
+        if child.typ != InodeType::Dir {
+            return_errno_with_message!(Errno::ENOTDIR, "rmdir on not dir");
+        }
+        if !child.merged_entries()?.is_empty() {
+            return_errno_with_message!(Errno::ENOTEMPTY, "dir not empty");
+        }
+
+        self.remove_child(name, &child)
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        Ok(self.lookup_child(name)?)
+    }
+
+    fn rename(&self, old_name: &str, target: &Arc<dyn Inode>, new_name: &str) -> Result<()> {
+        let Some(target) = target.downcast_ref::<OverlayInode>() else {
+            return_errno_with_message!(Errno::EXDEV, "not same fs");
+        };
+        self.check_dir()?;
+        target.check_dir()?;
+        if self.this.ptr_eq(&target.this) && old_name == new_name {
+            return Ok(());
+        }
+
+        let old = self.lookup_child(old_name)?;
+        if old.typ == InodeType::Dir && !old.lowers.is_empty() {
+            // The merged directories cannot be moved as a whole in the upper layer.
+            return_errno_with_message!(Errno::EXDEV, "the dir has lower layers");
+        }
+
+        let replaced = match target.lookup_child(new_name) {
+            Ok(replaced) => Some(replaced),
+            Err(err) if err.error() == Errno::ENOENT => None,
+            Err(err) => return Err(err),
+        };
+        if let Some(replaced) = replaced.as_ref() {
+            if Arc::ptr_eq(&old, replaced) {
+                return Ok(());
+            }
+            match (old.typ == InodeType::Dir, replaced.typ == InodeType::Dir) {
+                (true, false) => return_errno_with_message!(Errno::ENOTDIR, "old is not dir"),
+                (false, true) => return_errno_with_message!(Errno::EISDIR, "new is dir"),
+                (true, true) => {
+                    if !replaced.merged_entries()?.is_empty() {
+                        return_errno_with_message!(Errno::ENOTEMPTY, "dir not empty");
+                    }
+                }
+                (false, false) => {}
+            }
+        }
+
+        let old_upper = old.copy_up()?;
+        let self_upper = self.copy_up()?;
+        let target_upper = target.copy_up()?;
+        match lookup_entry(&target_upper, new_name)? {
+            Some(whiteout) if Whiteout::is_whiteout(whiteout.as_ref()) => {
+                target_upper.unlink(new_name)?
+            }
+            Some(replaced_upper) if replaced_upper.type_() == InodeType::Dir => {
+                clear_whiteouts(&replaced_upper)?
+            }
+            _ => {}
+        }
+
+        self_upper.rename(old_name, &target_upper, new_name)?;
+        if old.typ == InodeType::Dir && target.lower_has(new_name)? {
+            set_opaque(&old_upper)?;
+        }
+        if self.lower_has(old_name)? {
+            create_whiteout(&self_upper, old_name)?;
+        }
+
+        if let Some(replaced) = replaced {
+            *replaced.name_and_parent.lock() = None;
+        }
+        self.children.lock().remove(old_name);
+        *old.name_and_parent.lock() = Some((String::from(new_name), target.this()));
+        target
+            .children
+            .lock()
+            .insert(String::from(new_name), Arc::downgrade(&old));
+        Ok(())
+    }
+
+    fn read_link(&self) -> Result<String> {
+        self.real().read_link()
+    }
+
+    fn write_link(&self, target: &str) -> Result<()> {
+        self.copy_up()?.write_link(target)
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        self.real().ioctl(cmd, arg)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        match self.upper() {
+            Some(upper) => upper.sync_all(),
+            None => Ok(()),
+        }
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        match self.upper() {
+            Some(upper) => upper.sync_data(),
+            None => Ok(()),
+        }
+    }
+
+    fn fallocate(&self, mode: FallocMode, offset: usize, len: usize) -> Result<()> {
+        self.copy_up()?.fallocate(mode, offset, len)
+    }
+
+    fn set_xattr(&self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        if is_private_xattr(&name) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the xattr is private to the overlay");
+        }
+        self.copy_up()?.set_xattr(name, value, flags)
+    }
+
+    fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>> {
+        if is_private_xattr(&name) {
+            return_errno_with_message!(Errno::ENODATA, "the xattr is private to the overlay");
+        }
+        self.real().get_xattr(name)
+    }
+
+    fn list_xattr(&self) -> Result<Vec<String>> {
+        let mut names = self.real().list_xattr()?;
+        names.retain(|name| !name.starts_with(PRIVATE_XATTR_PREFIX));
+        Ok(names)
+    }
+
+    fn remove_xattr(&self, name: XattrName) -> Result<()> {
+        if is_private_xattr(&name) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the xattr is private to the overlay");
+        }
+        self.copy_up()?.remove_xattr(name)
+    }
+
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.real().poll(mask, poller)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.overlay_fs()
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.real().is_seekable()
+    }
+
+    fn extension(&self) -> Option<&Extension> {
+        Some(&self.extension)
+    }
+}
+
+impl OverlayInode {
+    fn lookup_child(&self, name: &str) -> Result<Arc<Self>> {
+        self.check_dir()?;
+        match name {
+            "." => return Ok(self.this()),
+            ".." => {
+                return Ok(match self.name_and_parent.lock().as_ref() {
+                    Some((_, parent)) => parent.clone(),
+                    None => self.this(),
+                })
+            }
+            _ => {}
+        }
+
+        let cached = self.children.lock().get(name).and_then(Weak::upgrade);
+        match cached {
+            Some(child) => Ok(child),
+            None => self.lookup_in_layers(name),
+        }
+    }
+}
+
+/// Creates the empty work directory under `work_parent`, removing the leftovers of
+/// the previous mounts.
+fn prepare_work_dir(work_parent: &Arc<dyn Inode>) -> Result<Arc<dyn Inode>> {
+    if let Some(old_work) = lookup_entry(work_parent, WORK_DIR_NAME)? {
+        if old_work.type_() == InodeType::Dir {
+            remove_dir_all(&old_work)?;
+            work_parent.rmdir(WORK_DIR_NAME)?;
+        } else {
+            work_parent.unlink(WORK_DIR_NAME)?;
+        }
+    }
+
+    work_parent.create(
+        WORK_DIR_NAME,
+        InodeType::Dir,
+        InodeMode::from_bits_truncate(0),
+    )
+}
+
+/// Copies the inode with its data and attributes to `dir` under `name`.
+fn copy_inode_to(src: &Arc<dyn Inode>, dir: &Arc<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+    let metadata = src.metadata();
+    let mode = metadata.mode;
+    let dst = match metadata.type_ {
+        InodeType::CharDevice | InodeType::BlockDevice => {
+            let device = match src.as_device() {
+                Some(device) => device,
+                None => get_device(metadata.rdev as usize)?,
+            };
+            dir.mknod(name, mode, device.into())?
+        }
+        InodeType::NamedPipe => dir.mknod(name, mode, MknodType::NamedPipeNode)?,
+        type_ => dir.create(name, type_, mode)?,
+    };
+
+    let copy_attributes = || -> Result<()> {
+        match metadata.type_ {
+            InodeType::File => copy_data(src, &dst, metadata.size)?,
+            InodeType::SymLink => dst.write_link(&src.read_link()?)?,
+            _ => {}
+        }
+        copy_xattrs(src, &dst)?;
+        dst.set_owner(metadata.uid)?;
+        dst.set_group(metadata.gid)?;
+        dst.set_atime(metadata.atime);
+        dst.set_mtime(metadata.mtime);
+        Ok(())
+    };
+    if let Err(err) = copy_attributes() {
+        remove_entry(dir, name, metadata.type_);
+        return Err(err);
+    }
+
+    Ok(dst)
+}
+
+fn copy_data(src: &Arc<dyn Inode>, dst: &Arc<dyn Inode>, size: usize) -> Result<()> {
+    let mut buf = vec![0u8; PAGE_SIZE];
+    let mut offset = 0;
+    while offset < size {
+        let len = src.read_bytes_at(offset, &mut buf)?;
+        if len == 0 {
+            break;
+        }
+        dst.write_bytes_at(offset, &buf[..len])?;
+        offset += len;
+    }
+
+    if dst.size() != size {
+        dst.resize(size)?;
+    }
+    Ok(())
+}
+
+fn copy_xattrs(src: &Arc<dyn Inode>, dst: &Arc<dyn Inode>) -> Result<()> {
+    let names = match src.list_xattr() {
+        Ok(names) => names,
+        Err(err) if err.error() == Errno::EOPNOTSUPP => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    for full_name in names
+        .iter()
+        .filter(|name| !name.starts_with(PRIVATE_XATTR_PREFIX))
+    {
+        let name = XattrName::try_from_full_name(full_name)?;
+        let value = src.get_xattr(name)?;
+        dst.set_xattr(name, &value, XattrSetFlags::empty())?;
+    }
+    Ok(())
+}
+
+/// Looks up the name in the directory, returning `None` if it does not exist.
+fn lookup_entry(dir: &Arc<dyn Inode>, name: &str) -> Result<Option<Arc<dyn Inode>>> {
+    match dir.lookup(name) {
+        Ok(inode) => Ok(Some(inode)),
+        Err(err) if err.error() == Errno::ENOENT => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Removes the entry on a best-effort basis, which is used to clean up on failures.
+fn remove_entry(dir: &Arc<dyn Inode>, name: &str, type_: InodeType) {
+    let _ = if type_ == InodeType::Dir {
+        dir.rmdir(name)
+    } else {
+        dir.unlink(name)
+    };
+}
+
+fn read_entries(dir: &Arc<dyn Inode>) -> Result<Vec<(String, u64, InodeType)>> {
+    struct Collector(Vec<(String, u64, InodeType)>);
+
+    impl DirentVisitor for Collector {
+        fn visit(&mut self, name: &str, ino: u64, type_: InodeType, _offset: usize) -> Result<()> {
+            self.0.push((String::from(name), ino, type_));
+            Ok(())
+        }
+    }
+
+    let mut collector = Collector(Vec::new());
+    let mut offset = 0;
+    loop {
+        let cnt = dir.readdir_at(offset, &mut collector)?;
+        if cnt == 0 {
+            break;
+        }
+        offset += cnt;
+    }
+    Ok(collector.0)
+}
+
+fn remove_dir_all(dir: &Arc<dyn Inode>) -> Result<()> {
+    for (name, _, type_) in read_entries(dir)? {
+        if name == "." || name == ".." {
+            continue;
+        }
+        if type_ == InodeType::Dir {
+            remove_dir_all(&dir.lookup(&name)?)?;
+            dir.rmdir(&name)?;
+        } else {
+            dir.unlink(&name)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes the whiteouts in an upper directory whose merged view is empty.
+fn clear_whiteouts(dir: &Arc<dyn Inode>) -> Result<()> {
+    for (name, _, type_) in read_entries(dir)? {
+        if type_ == InodeType::CharDevice && Whiteout::is_whiteout(dir.lookup(&name)?.as_ref()) {
+            dir.unlink(&name)?;
+        }
+    }
+    Ok(())
+}
+
+fn create_whiteout(dir: &Arc<dyn Inode>, name: &str) -> Result<()> {
+    let whiteout: Arc<dyn Device> = Arc::new(Whiteout);
+    dir.mknod(name, InodeMode::from_bits_truncate(0), whiteout.into())?;
+    Ok(())
+}
+
+fn is_opaque(dir: &Arc<dyn Inode>) -> bool {
+    let name = XattrName::try_from_full_name(OPAQUE_XATTR).unwrap();
+    matches!(dir.get_xattr(name), Ok(value) if value == b"y")
+}
+
+fn set_opaque(dir: &Arc<dyn Inode>) -> Result<()> {
+    let name = XattrName::try_from_full_name(OPAQUE_XATTR).unwrap();
+    dir.set_xattr(name, b"y", XattrSetFlags::empty())
+}
+
+fn is_private_xattr(name: &XattrName) -> bool {
+    name.full_name().starts_with(PRIVATE_XATTR_PREFIX)
+}
@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A union file system that stacks a writable upper directory on read-only lower ones.
+//!
+//! The overlay presents the merged view of its layers:
+//! 1. A name is searched in the upper layer first, then in the lower layers from
+//!    the top to the bottom. Only directories are merged across layers.
+//! 2. Modifying a file that comes from a lower layer first copies it up, i.e., copies
+//!    its data and attributes to the upper layer (via the work directory).
+//! 3. Removing a name that exists in a lower layer leaves a whiteout in the upper layer,
+//!    which is a character device with the device number 0/0.
+//! 4. A directory with the `trusted.overlay.opaque` xattr set to `y` hides the directories
+//!    of the same name in the layers below it.
+//!
+//! Without an upper layer, the overlay is read-only. The layers should not be modified
+//! except through the overlay while it is mounted.
+
+pub use fs::{OverlayFS, OverlayMountOptions};
+
+mod fs;
+mod whiteout;
+
+const OVERLAYFS_MAGIC: u64 = 0x794c_7630;
+const BLOCK_SIZE: usize = 4096;
+const NAME_MAX: usize = 255;
+
+/// The name of the directory that holds the files being copied up.
+const WORK_DIR_NAME: &str = "work";
+/// The prefix of the xattrs used internally by the overlay.
+const PRIVATE_XATTR_PREFIX: &str = "trusted.overlay.";
+/// The xattr that marks an opaque directory.
+const OPAQUE_XATTR: &str = "trusted.overlay.opaque";
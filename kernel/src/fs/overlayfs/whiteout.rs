@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    events::IoEvents,
+    fs::{
+        device::{Device, DeviceId, DeviceType},
+        inode_handle::FileIo,
+        utils::{Inode, InodeType, StatusFlags},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+};
+
+/// The device of whiteouts.
+///
+/// A whiteout is a character device with the device number 0/0, which cannot be opened.
+pub(super) struct Whiteout;
+
+impl Whiteout {
+    /// Returns whether the inode is a whiteout.
+    pub(super) fn is_whiteout(inode: &dyn Inode) -> bool {
+        inode.type_() == InodeType::CharDevice && inode.metadata().rdev == 0
+    }
+}
+
+impl Device for Whiteout {
+    fn type_(&self) -> DeviceType {
+        DeviceType::CharDevice
+    }
+
+    fn id(&self) -> DeviceId {
+        DeviceId::new(0, 0)
+    }
+
+    fn open(&self) -> Result<Option<Arc<dyn FileIo>>> {
+        return_errno_with_message!(Errno::ENXIO, "a whiteout cannot be opened");
+    }
+}
+
+impl Pollable for Whiteout {
+    fn poll(&self, _mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        IoEvents::empty()
+    }
+}
+
+impl FileIo for Whiteout {
+    fn read(&self, _writer: &mut VmWriter, _status_flags: StatusFlags) -> Result<usize> {
+        return_errno_with_message!(Errno::ENXIO, "a whiteout cannot be read");
+    }
+
+    fn write(&self, _reader: &mut VmReader, _status_flags: StatusFlags) -> Result<usize> {
+        return_errno_with_message!(Errno::ENXIO, "a whiteout cannot be written");
+    }
+}
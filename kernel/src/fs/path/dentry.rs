@@ -14,8 +14,12 @@ use ostd::sync::RwMutexWriteGuard;
 
 use crate::{
     fs::{
+        inotify::{self, InotifyMask},
         path::mount::MountNode,
-        utils::{FileSystem, Inode, InodeMode, InodeType, Metadata, MknodType, NAME_MAX},
+        utils::{
+            FileSystem, Inode, InodeMode, InodeType, Metadata, MknodType, XattrName, XattrSetFlags,
+            NAME_MAX,
+        },
     },
     prelude::*,
     process::{Gid, Uid},
@@ -34,7 +38,11 @@ pub struct Dentry_ {
     inode: Arc<dyn Inode>,
     name_and_parent: RwLock<Option<(String, Arc<Dentry_>)>>,
     children: RwMutex<Children>,
-    flags: AtomicU32,
+    /// The number of mount nodes mounted on this dentry.
+    ///
+    /// The same dentry can be a mountpoint in several mount trees (e.g., in
+    /// different mount namespaces).
+    mount_count: AtomicU32,
     this: Weak<Dentry_>,
 }
 
@@ -50,7 +58,7 @@ impl Dentry_ {
     fn new(inode: Arc<dyn Inode>, options: DentryOptions) -> Arc<Self> {
         Arc::new_cyclic(|weak_self| Self {
             inode,
-            flags: AtomicU32::new(DentryFlags::empty().bits()),
+            mount_count: AtomicU32::new(0),
             name_and_parent: match options {
                 DentryOptions::Leaf(name_and_parent) => RwLock::new(Some(name_and_parent)),
                 _ => RwLock::new(None),
@@ -99,11 +107,6 @@ impl Dentry_ {
         &self.inode
     }
 
-    fn flags(&self) -> DentryFlags {
-        let flags = self.flags.load(Ordering::Relaxed);
-        DentryFlags::from_bits(flags).unwrap()
-    }
-
     /// Checks if this dentry is a descendant (child, grandchild, or
     /// great-grandchild, etc.) of another dentry.
     pub fn is_descendant_of(&self, ancestor: &Arc<Self>) -> bool {
@@ -118,17 +121,16 @@ impl Dentry_ {
     }
 
     pub fn is_mountpoint(&self) -> bool {
-        self.flags().contains(DentryFlags::MOUNTED)
+        self.mount_count.load(Ordering::Acquire) > 0
     }
 
-    pub fn set_mountpoint_dentry(&self) {
-        self.flags
-            .fetch_or(DentryFlags::MOUNTED.bits(), Ordering::Release);
+    pub(super) fn inc_mount_count(&self) {
+        self.mount_count.fetch_add(1, Ordering::Release);
     }
 
-    pub fn clear_mountpoint(&self) {
-        self.flags
-            .fetch_and(!(DentryFlags::MOUNTED.bits()), Ordering::Release);
+    pub(super) fn dec_mount_count(&self) {
+        let old_count = self.mount_count.fetch_sub(1, Ordering::Release);
+        debug_assert!(old_count > 0);
     }
 
     /// Currently, the root `Dentry_` of a fs is the root of a mount.
@@ -148,6 +150,13 @@ impl Dentry_ {
         }
 
         let new_inode = self.inode.create(name, type_, mode)?;
+        inotify::notify_child(
+            self.inode.as_ref(),
+            new_inode.as_ref(),
+            InotifyMask::IN_CREATE,
+            name,
+            0,
+        );
         let name = String::from(name);
         let new_child = Dentry_::new(new_inode, DentryOptions::Leaf((name.clone(), self.this())));
 
@@ -187,6 +196,13 @@ impl Dentry_ {
         }
 
         let inode = self.inode.mknod(name, mode, type_)?;
+        inotify::notify_child(
+            self.inode.as_ref(),
+            inode.as_ref(),
+            InotifyMask::IN_CREATE,
+            name,
+            0,
+        );
         let name = String::from(name);
         let new_child = Dentry_::new(inode, DentryOptions::Leaf((name.clone(), self.this())));
 
@@ -208,6 +224,14 @@ impl Dentry_ {
 
         let old_inode = old.inode();
         self.inode.link(old_inode, name)?;
+        inotify::notify_self(old_inode.as_ref(), InotifyMask::IN_ATTRIB);
+        inotify::notify_child(
+            self.inode.as_ref(),
+            old_inode.as_ref(),
+            InotifyMask::IN_CREATE,
+            name,
+            0,
+        );
         let name = String::from(name);
         let dentry = Dentry_::new(
             old_inode.clone(),
@@ -228,10 +252,14 @@ impl Dentry_ {
         let children = self.children.upread();
         children.check_mountpoint(name)?;
 
+        let target = self.find_inode_to_notify(&children, name);
         self.inode.unlink(name)?;
 
         let mut children = children.upgrade();
         children.delete(name);
+        if let Some(target) = target {
+            self.notify_child_removed(target.as_ref(), name);
+        }
         Ok(())
     }
 
@@ -244,10 +272,14 @@ impl Dentry_ {
         let children = self.children.upread();
         children.check_mountpoint(name)?;
 
+        let target = self.find_inode_to_notify(&children, name);
         self.inode.rmdir(name)?;
 
         let mut children = children.upgrade();
         children.delete(name);
+        if let Some(target) = target {
+            self.notify_child_removed(target.as_ref(), name);
+        }
         Ok(())
     }
 
@@ -270,7 +302,12 @@ impl Dentry_ {
             let old_dentry = children.check_mountpoint_then_find(old_name)?;
             children.check_mountpoint(new_name)?;
 
+            let moved = self.find_inode_to_notify(&children, old_name);
+            let replaced = self.find_inode_to_notify(&children, new_name);
             self.inode.rename(old_name, &self.inode, new_name)?;
+            if let Some(moved) = moved {
+                self.notify_renamed(moved.as_ref(), old_name, self, new_name, replaced);
+            }
 
             let mut children = children.upgrade();
             match old_dentry.as_ref() {
@@ -290,7 +327,12 @@ impl Dentry_ {
             let old_dentry = self_children.check_mountpoint_then_find(old_name)?;
             new_dir_children.check_mountpoint(new_name)?;
 
+            let moved = self.find_inode_to_notify(&self_children, old_name);
+            let replaced = new_dir.find_inode_to_notify(&new_dir_children, new_name);
             self.inode.rename(old_name, &new_dir.inode, new_name)?;
+            if let Some(moved) = moved {
+                self.notify_renamed(moved.as_ref(), old_name, new_dir, new_name, replaced);
+            }
             match old_dentry.as_ref() {
                 Some(dentry) => {
                     self_children.delete(old_name);
@@ -304,6 +346,107 @@ impl Dentry_ {
         }
         Ok(())
     }
+
+    /// Reports `events` that happened on this `Dentry_` to the inotify watches
+    /// on its inode and on its parent directory.
+    pub fn notify(&self, events: InotifyMask) {
+        if !inotify::has_watches() {
+            return;
+        }
+
+        inotify::notify_self(self.inode.as_ref(), events);
+        if let Some((name, parent)) = self.name_and_parent.read().as_ref() {
+            inotify::notify_child(parent.inode.as_ref(), self.inode.as_ref(), events, name, 0);
+        }
+    }
+
+    /// Finds the inode of the child named `name` if it may need to be notified.
+    fn find_inode_to_notify(&self, children: &Children, name: &str) -> Option<Arc<dyn Inode>> {
+        if !inotify::has_watches() {
+            return None;
+        }
+
+        match children.find(name) {
+            Some(dentry) => Some(dentry.inode.clone()),
+            None => self.inode.lookup(name).ok(),
+        }
+    }
+
+    fn notify_child_removed(&self, child_inode: &dyn Inode, name: &str) {
+        inotify::notify_child(
+            self.inode.as_ref(),
+            child_inode,
+            InotifyMask::IN_DELETE,
+            name,
+            0,
+        );
+        inotify::notify_unlinked(child_inode);
+    }
+
+    fn notify_renamed(
+        &self,
+        moved_inode: &dyn Inode,
+        old_name: &str,
+        new_dir: &Dentry_,
+        new_name: &str,
+        replaced_inode: Option<Arc<dyn Inode>>,
+    ) {
+        let cookie = inotify::new_cookie();
+        inotify::notify_child(
+            self.inode.as_ref(),
+            moved_inode,
+            InotifyMask::IN_MOVED_FROM,
+            old_name,
+            cookie,
+        );
+        inotify::notify_child(
+            new_dir.inode.as_ref(),
+            moved_inode,
+            InotifyMask::IN_MOVED_TO,
+            new_name,
+            cookie,
+        );
+        inotify::notify_self(moved_inode, InotifyMask::IN_MOVE_SELF);
+        if let Some(replaced_inode) = replaced_inode {
+            inotify::notify_unlinked(replaced_inode.as_ref());
+        }
+    }
+
+    pub fn resize(&self, size: usize) -> Result<()> {
+        self.inode.resize(size)?;
+        self.notify(InotifyMask::IN_MODIFY);
+        Ok(())
+    }
+
+    pub fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.inode.set_mode(mode)?;
+        self.notify(InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
+
+    pub fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.inode.set_owner(uid)?;
+        self.notify(InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
+
+    pub fn set_group(&self, gid: Gid) -> Result<()> {
+        self.inode.set_group(gid)?;
+        self.notify(InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
+
+    pub fn set_xattr(&self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        self.inode.set_xattr(name, value, flags)?;
+        self.notify(InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
+
+    pub fn remove_xattr(&self, name: XattrName) -> Result<()> {
+        self.inode.remove_xattr(name)?;
+        self.notify(InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
 }
 
 #[inherit_methods(from = "self.inode")]
@@ -314,26 +457,24 @@ impl Dentry_ {
     pub fn metadata(&self) -> Metadata;
     pub fn type_(&self) -> InodeType;
     pub fn mode(&self) -> Result<InodeMode>;
-    pub fn set_mode(&self, mode: InodeMode) -> Result<()>;
     pub fn size(&self) -> usize;
-    pub fn resize(&self, size: usize) -> Result<()>;
     pub fn owner(&self) -> Result<Uid>;
-    pub fn set_owner(&self, uid: Uid) -> Result<()>;
     pub fn group(&self) -> Result<Gid>;
-    pub fn set_group(&self, gid: Gid) -> Result<()>;
     pub fn atime(&self) -> Duration;
     pub fn set_atime(&self, time: Duration);
     pub fn mtime(&self) -> Duration;
     pub fn set_mtime(&self, time: Duration);
     pub fn ctime(&self) -> Duration;
     pub fn set_ctime(&self, time: Duration);
+    pub fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>>;
+    pub fn list_xattr(&self) -> Result<Vec<String>>;
 }
 
 impl Debug for Dentry_ {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("Dentry_")
             .field("inode", &self.inode)
-            .field("flags", &self.flags())
+            .field("mount_count", &self.mount_count.load(Ordering::Relaxed))
             .finish()
     }
 }
@@ -362,12 +503,6 @@ impl DentryKey {
     }
 }
 
-bitflags! {
-    struct DentryFlags: u32 {
-        const MOUNTED = 1 << 0;
-    }
-}
-
 enum DentryOptions {
     Root,
     Leaf((String, Arc<Dentry_>)),
@@ -465,7 +600,7 @@ impl Dentry {
         Ok(Self::new(self.mount_node.clone(), new_child_dentry))
     }
 
-    fn new(mount_node: Arc<MountNode>, inner: Arc<Dentry_>) -> Self {
+    pub(super) fn new(mount_node: Arc<MountNode>, inner: Arc<Dentry_>) -> Self {
         Self { mount_node, inner }
     }
 
@@ -588,8 +723,7 @@ impl Dentry {
     /// Makes current `Dentry` to be a mountpoint,
     /// sets it as the mountpoint of the child mount.
     pub(super) fn set_mountpoint(&self, child_mount: Arc<MountNode>) {
-        child_mount.set_mountpoint_dentry(&self.inner);
-        self.inner.set_mountpoint_dentry();
+        self.mount_node.add_child(&self.inner, child_mount);
     }
 
     /// Mounts the fs on current `Dentry` as a mountpoint.
@@ -607,9 +741,7 @@ impl Dentry {
             return_errno_with_message!(Errno::EINVAL, "can not mount on root");
         }
 
-        let child_mount = self.mount_node.mount(fs, &self.this())?;
-        self.set_mountpoint(child_mount.clone());
-        Ok(child_mount)
+        self.mount_node.mount(fs, &self.this())
     }
 
     /// Unmounts and returns the mounted child mount.
@@ -627,9 +759,7 @@ impl Dentry {
         let mountpoint_mount_node = self.mount_node.parent().unwrap().upgrade().unwrap();
         let mountpoint = Self::new(mountpoint_mount_node.clone(), mountpoint_dentry.clone());
 
-        let child_mount = mountpoint_mount_node.unmount(&mountpoint)?;
-        mountpoint_dentry.clear_mountpoint();
-        Ok(child_mount)
+        mountpoint_mount_node.unmount(&mountpoint)
     }
 
     /// Creates a `Dentry` by making an inode of the `type_` with the `mode`.
@@ -685,6 +815,11 @@ impl Dentry {
     pub fn mount_node(&self) -> &Arc<MountNode> {
         &self.mount_node
     }
+
+    /// Gets the inner `Dentry_` of current `Dentry`.
+    pub(super) fn inner(&self) -> &Arc<Dentry_> {
+        &self.inner
+    }
 }
 
 #[inherit_methods(from = "self.inner")]
@@ -708,8 +843,13 @@ impl Dentry {
     pub fn set_mtime(&self, time: Duration);
     pub fn ctime(&self) -> Duration;
     pub fn set_ctime(&self, time: Duration);
+    pub fn set_xattr(&self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()>;
+    pub fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>>;
+    pub fn list_xattr(&self) -> Result<Vec<String>>;
+    pub fn remove_xattr(&self, name: XattrName) -> Result<()>;
     pub fn key(&self) -> DentryKey;
     pub fn inode(&self) -> &Arc<dyn Inode>;
     pub fn is_root_of_mount(&self) -> bool;
     pub fn is_mountpoint(&self) -> bool;
+    pub fn notify(&self, events: InotifyMask);
 }
@@ -4,6 +4,8 @@
 
 pub use dentry::{Dentry, DentryKey};
 pub use mount::MountNode;
+pub use namespace::{init_mnt_ns, MountNamespace};
 
 mod dentry;
 mod mount;
+mod namespace;
@@ -77,9 +77,8 @@ impl MountNode {
             return_errno!(Errno::ENOTDIR);
         }
 
-        let key = mountpoint.key();
         let child_mount = Self::new(fs, Some(Arc::downgrade(mountpoint.mount_node())));
-        self.children.write().insert(key, child_mount.clone());
+        mountpoint.set_mountpoint(child_mount.clone());
         Ok(child_mount)
     }
 
@@ -92,9 +91,7 @@ impl MountNode {
         }
 
         let child_mount = self
-            .children
-            .write()
-            .remove(&mountpoint.key())
+            .remove_child(&mountpoint.key())
             .ok_or_else(|| Error::with_message(Errno::ENOENT, "can not find child mount"))?;
         Ok(child_mount)
     }
@@ -139,19 +136,14 @@ impl MountNode {
             let old_children = old_mount.children.read();
             for old_child_mount in old_children.values() {
                 let mountpoint_dentry = old_child_mount.mountpoint_dentry().unwrap();
-                if !mountpoint_dentry.is_descendant_of(old_mount.root_dentry()) {
+                if !Arc::ptr_eq(&mountpoint_dentry, old_mount.root_dentry())
+                    && !mountpoint_dentry.is_descendant_of(old_mount.root_dentry())
+                {
                     continue;
                 }
                 let new_child_mount =
                     old_child_mount.clone_mount_node(old_child_mount.root_dentry());
-                let key = mountpoint_dentry.key();
-                new_parent_mount
-                    .children
-                    .write()
-                    .insert(key, new_child_mount.clone());
-                new_child_mount.set_parent(&new_parent_mount);
-                new_child_mount
-                    .set_mountpoint_dentry(&old_child_mount.mountpoint_dentry().unwrap());
+                new_parent_mount.add_child(&mountpoint_dentry, new_child_mount.clone());
                 stack.push(old_child_mount.clone());
                 new_stack.push(new_child_mount);
             }
@@ -164,25 +156,44 @@ impl MountNode {
     fn detach_mount_node(&self) {
         if let Some(parent) = self.parent() {
             let parent = parent.upgrade().unwrap();
-            parent
-                .children
-                .write()
-                .remove(&self.mountpoint_dentry().unwrap().key());
+            parent.remove_child(&self.mountpoint_dentry().unwrap().key());
         }
     }
 
     /// Attaches the mount node to the mountpoint.
     fn attach_mount_node(&self, mountpoint: &Dentry) {
-        let key = mountpoint.key();
-        mountpoint
-            .mount_node()
-            .children
-            .write()
-            .insert(key, self.this());
-        self.set_parent(mountpoint.mount_node());
         mountpoint.set_mountpoint(self.this());
     }
 
+    /// Mounts a child mount node on the mountpoint, which is a `Dentry_` in this mount node.
+    ///
+    /// The child mount node previously mounted on the mountpoint, if any, is replaced.
+    pub(super) fn add_child(&self, mountpoint: &Arc<Dentry_>, child_mount: Arc<Self>) {
+        child_mount.set_parent(&self.this());
+        child_mount.set_mountpoint_dentry(mountpoint);
+        mountpoint.inc_mount_count();
+
+        let old_child_mount = self.children.write().insert(mountpoint.key(), child_mount);
+        if let Some(old_child_mount) = old_child_mount {
+            old_child_mount
+                .mountpoint_dentry()
+                .unwrap()
+                .dec_mount_count();
+        }
+    }
+
+    /// Removes the child mount node mounted on the mountpoint with the key.
+    fn remove_child(&self, key: &DentryKey) -> Option<Arc<Self>> {
+        let child_mount = self.children.write().remove(key)?;
+        child_mount.mountpoint_dentry().unwrap().dec_mount_count();
+        Some(child_mount)
+    }
+
+    /// Gets a child mount node by the key of the mountpoint.
+    pub(super) fn get_by_key(&self, key: &DentryKey) -> Option<Arc<Self>> {
+        self.children.read().get(key).cloned()
+    }
+
     /// Grafts the mount node tree to the mountpoint.
     pub fn graft_mount_node_tree(&self, mountpoint: &Dentry) -> Result<()> {
         if mountpoint.type_() != InodeType::Dir {
@@ -258,6 +269,14 @@ impl MountNode {
     }
 }
 
+impl Drop for MountNode {
+    fn drop(&mut self) {
+        for child_mount in self.children.read().values() {
+            child_mount.mountpoint_dentry().unwrap().dec_mount_count();
+        }
+    }
+}
+
 impl Debug for MountNode {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("MountNode")
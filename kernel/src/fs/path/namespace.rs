@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use crate::{
+    fs::{
+        path::{Dentry, MountNode},
+        rootfs::root_mount,
+    },
+    prelude::*,
+};
+
+/// A mount namespace, which is a mount tree of its own.
+///
+/// The mounts and unmounts in a mount namespace are invisible in the others.
+pub struct MountNamespace {
+    root: Arc<MountNode>,
+}
+
+impl MountNamespace {
+    /// Returns the root mount node of the namespace.
+    pub fn root(&self) -> &Arc<MountNode> {
+        &self.root
+    }
+
+    /// Creates a new namespace with a copy of the mount tree of this namespace.
+    pub fn new_copy(&self) -> Arc<Self> {
+        let root = self
+            .root
+            .clone_mount_node_tree(self.root.root_dentry(), true);
+        Arc::new(Self { root })
+    }
+
+    /// Finds the `Dentry` in `new_ns` at the same location as `dentry` in this namespace.
+    ///
+    /// `new_ns` should be a copy of this namespace. If the location cannot be
+    /// found, e.g., `dentry` does not belong to this namespace or it has been
+    /// unmounted in `new_ns`, `dentry` itself is returned.
+    pub fn translate(&self, dentry: &Dentry, new_ns: &MountNamespace) -> Dentry {
+        // Collect the mountpoints from the mount of `dentry` up to the root mount.
+        let mut mountpoint_keys = Vec::new();
+        let mut mount_node = dentry.mount_node().clone();
+        while !Arc::ptr_eq(&mount_node, &self.root) {
+            let (Some(mountpoint), Some(parent)) = (
+                mount_node.mountpoint_dentry(),
+                mount_node.parent().and_then(|parent| parent.upgrade()),
+            ) else {
+                return dentry.clone();
+            };
+            mountpoint_keys.push(mountpoint.key());
+            mount_node = parent;
+        }
+
+        // Then walk down the new mount tree along the mountpoints.
+        let mut new_mount_node = new_ns.root.clone();
+        for key in mountpoint_keys.iter().rev() {
+            let Some(child_mount) = new_mount_node.get_by_key(key) else {
+                return dentry.clone();
+            };
+            new_mount_node = child_mount;
+        }
+
+        Dentry::new(new_mount_node, dentry.inner().clone())
+    }
+}
+
+static INIT_MNT_NS: Once<Arc<MountNamespace>> = Once::new();
+
+/// Returns the initial mount namespace, whose mount tree is the one of the root file system.
+pub fn init_mnt_ns() -> &'static Arc<MountNamespace> {
+    INIT_MNT_NS.call_once(|| {
+        Arc::new(MountNamespace {
+            root: root_mount().clone(),
+        })
+    })
+}
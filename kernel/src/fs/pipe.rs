@@ -68,6 +68,10 @@ impl FileLike for PipeReader {
         Ok(read_len)
     }
 
+    fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.consumer.try_read(writer)
+    }
+
     fn status_flags(&self) -> StatusFlags {
         StatusFlags::from_bits_truncate(self.status_flags.load(Ordering::Relaxed))
     }
@@ -137,6 +141,10 @@ impl FileLike for PipeWriter {
         }
     }
 
+    fn write_nonblocking(&self, reader: &mut VmReader) -> Result<usize> {
+        self.producer.try_write(reader)
+    }
+
     fn status_flags(&self) -> StatusFlags {
         StatusFlags::from_bits_truncate(self.status_flags.load(Ordering::Relaxed))
     }
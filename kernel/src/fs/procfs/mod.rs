@@ -4,6 +4,7 @@ use core::sync::atomic::{AtomicU64, Ordering};
 
 use filesystems::{FileSystemType, FILESYSTEM_TYPES};
 
+pub use self::pid::ns_file_target;
 use self::{
     cpuinfo::CpuInfoFileOps,
     loadavg::LoadAvgFileOps,
@@ -22,6 +23,7 @@ use crate::{
     },
     prelude::*,
     process::{
+        namespace::PidNamespace,
         process_table::{self, PidEvent},
         Pid,
     },
@@ -43,8 +45,12 @@ pub(super) fn init() {
             FileSystemType::new("proc", true),
             FileSystemType::new("ramfs", true),
             FileSystemType::new("devpts", true),
+            FileSystemType::new("mqueue", true),
             FileSystemType::new("ext2", false),
+            FileSystemType::new("ext3", false),
+            FileSystemType::new("ext4", false),
             FileSystemType::new("exfat", false),
+            FileSystemType::new("overlay", true),
         ]
     });
 }
@@ -63,10 +69,11 @@ pub struct ProcFS {
 }
 
 impl ProcFS {
-    pub fn new() -> Arc<Self> {
+    /// Creates a procfs that shows the processes in `pid_ns`.
+    pub fn new(pid_ns: Arc<PidNamespace>) -> Arc<Self> {
         Arc::new_cyclic(|weak_fs| Self {
             sb: SuperBlock::new(PROC_MAGIC, BLOCK_SIZE, NAME_MAX),
-            root: RootDirOps::new_inode(weak_fs.clone()),
+            root: RootDirOps::new_inode(pid_ns, weak_fs.clone()),
             inode_allocator: AtomicU64::new(PROC_ROOT_INO + 1),
         })
     }
@@ -95,11 +102,15 @@ impl FileSystem for ProcFS {
 }
 
 /// Represents the inode at `/proc`.
-struct RootDirOps;
+///
+/// Only the processes in the PID namespace are listed, by their PIDs in the namespace.
+struct RootDirOps {
+    pid_ns: Arc<PidNamespace>,
+}
 
 impl RootDirOps {
-    pub fn new_inode(fs: Weak<ProcFS>) -> Arc<dyn Inode> {
-        let root_inode = ProcDirBuilder::new(Self)
+    pub fn new_inode(pid_ns: Arc<PidNamespace>, fs: Weak<ProcFS>) -> Arc<dyn Inode> {
+        let root_inode = ProcDirBuilder::new(Self { pid_ns })
             .fs(fs)
             .ino(PROC_ROOT_INO)
             .build()
@@ -113,6 +124,9 @@ impl RootDirOps {
 impl Observer<PidEvent> for ProcDir<RootDirOps> {
     fn on_events(&self, events: &PidEvent) {
         let PidEvent::Exit(pid) = events;
+        let Some(pid) = self.inner().pid_ns.local_id(*pid) else {
+            return;
+        };
         let mut cached_children = self.cached_children().write();
         cached_children.remove_entry_by_name(&pid.to_string());
     }
@@ -121,11 +135,11 @@ impl Observer<PidEvent> for ProcDir<RootDirOps> {
 impl DirOps for RootDirOps {
     fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
         let child = if name == "self" {
-            SelfSymOps::new_inode(this_ptr.clone())
+            SelfSymOps::new_inode(self.pid_ns.clone(), this_ptr.clone())
         } else if name == "sys" {
             SysDirOps::new_inode(this_ptr.clone())
         } else if name == "thread-self" {
-            ThreadSelfSymOps::new_inode(this_ptr.clone())
+            ThreadSelfSymOps::new_inode(self.pid_ns.clone(), this_ptr.clone())
         } else if name == "filesystems" {
             FileSystemsFileOps::new_inode(this_ptr.clone())
         } else if name == "meminfo" {
@@ -135,9 +149,12 @@ impl DirOps for RootDirOps {
         } else if name == "cpuinfo" {
             CpuInfoFileOps::new_inode(this_ptr.clone())
         } else if let Ok(pid) = name.parse::<Pid>() {
-            let process_ref =
-                process_table::get_process(pid).ok_or_else(|| Error::new(Errno::ENOENT))?;
-            PidDirOps::new_inode(process_ref, this_ptr.clone())
+            let process_ref = self
+                .pid_ns
+                .global_id(pid)
+                .and_then(process_table::get_process)
+                .ok_or_else(|| Error::new(Errno::ENOENT))?;
+            PidDirOps::new_inode(process_ref, self.pid_ns.clone(), this_ptr.clone())
         } else {
             return_errno!(Errno::ENOENT);
         };
@@ -150,9 +167,11 @@ impl DirOps for RootDirOps {
             this.downcast_ref::<ProcDir<RootDirOps>>().unwrap().this()
         };
         let mut cached_children = this.cached_children().write();
-        cached_children.put_entry_if_not_found("self", || SelfSymOps::new_inode(this_ptr.clone()));
+        cached_children.put_entry_if_not_found("self", || {
+            SelfSymOps::new_inode(self.pid_ns.clone(), this_ptr.clone())
+        });
         cached_children.put_entry_if_not_found("thread-self", || {
-            ThreadSelfSymOps::new_inode(this_ptr.clone())
+            ThreadSelfSymOps::new_inode(self.pid_ns.clone(), this_ptr.clone())
         });
         cached_children.put_entry_if_not_found("sys", || SysDirOps::new_inode(this_ptr.clone()));
         cached_children.put_entry_if_not_found("filesystems", || {
@@ -165,9 +184,11 @@ impl DirOps for RootDirOps {
         cached_children
             .put_entry_if_not_found("cpuinfo", || CpuInfoFileOps::new_inode(this_ptr.clone()));
         for process in process_table::process_table_mut().iter() {
-            let pid = process.pid().to_string();
-            cached_children.put_entry_if_not_found(&pid, || {
-                PidDirOps::new_inode(process.clone(), this_ptr.clone())
+            let Some(pid) = self.pid_ns.local_id(process.pid()) else {
+                continue;
+            };
+            cached_children.put_entry_if_not_found(&pid.to_string(), || {
+                PidDirOps::new_inode(process.clone(), self.pid_ns.clone(), this_ptr.clone())
             });
         }
     }
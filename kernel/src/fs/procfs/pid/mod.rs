@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub use self::ns::ns_file_target;
 use self::{
-    cmdline::CmdlineFileOps, comm::CommFileOps, exe::ExeSymOps, fd::FdDirOps, task::TaskDirOps,
+    cmdline::CmdlineFileOps, comm::CommFileOps, exe::ExeSymOps, fd::FdDirOps, ns::NsDirOps,
+    task::TaskDirOps,
 };
 use super::template::{DirOps, ProcDir, ProcDirBuilder};
 use crate::{
@@ -11,23 +13,30 @@ use crate::{
         utils::{DirEntryVecExt, Inode},
     },
     prelude::*,
-    process::{posix_thread::AsPosixThread, Process},
+    process::{namespace::PidNamespace, posix_thread::AsPosixThread, Process},
 };
 
 mod cmdline;
 mod comm;
 mod exe;
 mod fd;
+mod ns;
 mod stat;
 mod status;
 mod task;
 
 /// Represents the inode at `/proc/[pid]`.
-pub struct PidDirOps(Arc<Process>);
+///
+/// The PIDs in the directory are the ones in the PID namespace of the procfs.
+pub struct PidDirOps(Arc<Process>, Arc<PidNamespace>);
 
 impl PidDirOps {
-    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
-        let pid_inode = ProcDirBuilder::new(Self(process_ref.clone()))
+    pub fn new_inode(
+        process_ref: Arc<Process>,
+        pid_ns: Arc<PidNamespace>,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        let pid_inode = ProcDirBuilder::new(Self(process_ref.clone(), pid_ns))
             .parent(parent)
             // The pid directories must be volatile, because it is just associated with one process.
             .volatile()
@@ -57,9 +66,14 @@ impl DirOps for PidDirOps {
             "comm" => CommFileOps::new_inode(self.0.clone(), this_ptr.clone()),
             "fd" => FdDirOps::new_inode(self.0.clone(), this_ptr.clone()),
             "cmdline" => CmdlineFileOps::new_inode(self.0.clone(), this_ptr.clone()),
-            "status" => status::StatusFileOps::new_inode(self.0.clone(), this_ptr.clone()),
-            "stat" => stat::StatFileOps::new_inode(self.0.clone(), this_ptr.clone()),
-            "task" => TaskDirOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "status" => {
+                status::StatusFileOps::new_inode(self.0.clone(), self.1.clone(), this_ptr.clone())
+            }
+            "stat" => {
+                stat::StatFileOps::new_inode(self.0.clone(), self.1.clone(), this_ptr.clone())
+            }
+            "ns" => NsDirOps::new_inode(self.0.clone(), this_ptr.clone()),
+            "task" => TaskDirOps::new_inode(self.0.clone(), self.1.clone(), this_ptr.clone()),
             _ => return_errno!(Errno::ENOENT),
         };
         Ok(inode)
@@ -84,13 +98,16 @@ impl DirOps for PidDirOps {
             CmdlineFileOps::new_inode(self.0.clone(), this_ptr.clone())
         });
         cached_children.put_entry_if_not_found("status", || {
-            status::StatusFileOps::new_inode(self.0.clone(), this_ptr.clone())
+            status::StatusFileOps::new_inode(self.0.clone(), self.1.clone(), this_ptr.clone())
         });
         cached_children.put_entry_if_not_found("stat", || {
-            stat::StatFileOps::new_inode(self.0.clone(), this_ptr.clone())
+            stat::StatFileOps::new_inode(self.0.clone(), self.1.clone(), this_ptr.clone())
+        });
+        cached_children.put_entry_if_not_found("ns", || {
+            NsDirOps::new_inode(self.0.clone(), this_ptr.clone())
         });
         cached_children.put_entry_if_not_found("task", || {
-            TaskDirOps::new_inode(self.0.clone(), this_ptr.clone())
+            TaskDirOps::new_inode(self.0.clone(), self.1.clone(), this_ptr.clone())
         });
     }
 }
@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::{
+        procfs::template::{DirOps, FileOps, ProcDir, ProcDirBuilder, ProcFile, ProcFileBuilder},
+        utils::{DirEntryVecExt, Inode},
+    },
+    prelude::*,
+    process::namespace::NsKind,
+    Process,
+};
+
+/// Represents the inode at `/proc/[pid]/ns`.
+pub struct NsDirOps(Arc<Process>);
+
+impl NsDirOps {
+    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(process_ref))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl DirOps for NsDirOps {
+    fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let Some(kind) = NsKind::from_name(name) else {
+            return_errno!(Errno::ENOENT);
+        };
+        Ok(NsFileOps::new_inode(self.0.clone(), kind, this_ptr))
+    }
+
+    fn populate_children(&self, this_ptr: Weak<dyn Inode>) {
+        let this = {
+            let this = this_ptr.upgrade().unwrap();
+            this.downcast_ref::<ProcDir<NsDirOps>>().unwrap().this()
+        };
+        let mut cached_children = this.cached_children().write();
+        for kind in NsKind::ALL {
+            cached_children.put_entry_if_not_found(kind.name(), || {
+                NsFileOps::new_inode(self.0.clone(), kind, this_ptr.clone())
+            });
+        }
+    }
+}
+
+/// Represents the inode at `/proc/[pid]/ns/[kind]`.
+///
+/// The file refers to a namespace of the process. It cannot be read, but it
+/// can be opened and passed to `setns`.
+struct NsFileOps(Arc<Process>, NsKind);
+
+impl NsFileOps {
+    pub fn new_inode(
+        process_ref: Arc<Process>,
+        kind: NsKind,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref, kind))
+            .parent(parent)
+            .build()
+            .unwrap()
+    }
+}
+
+impl FileOps for NsFileOps {
+    fn data(&self) -> Result<Vec<u8>> {
+        return_errno_with_message!(Errno::EINVAL, "namespace files cannot be read")
+    }
+}
+
+/// Returns the process and the kind of the namespace that the inode refers to,
+/// if the inode is a file in `/proc/[pid]/ns`.
+pub fn ns_file_target(inode: &Arc<dyn Inode>) -> Option<(Arc<Process>, NsKind)> {
+    let ns_file = inode.downcast_ref::<ProcFile<NsFileOps>>()?;
+    let NsFileOps(process, kind) = ns_file.inner();
+    Some((process.clone(), *kind))
+}
@@ -8,7 +8,7 @@ use crate::{
         utils::Inode,
     },
     prelude::*,
-    process::posix_thread::AsPosixThread,
+    process::{namespace::PidNamespace, posix_thread::AsPosixThread},
     Process,
 };
 
@@ -58,11 +58,15 @@ use crate::{
 /// - Mems_allowed_list: List of memory nodes allowed for this process.
 /// - voluntary_ctxt_switches: Number of voluntary context switches.
 /// - nonvoluntary_ctxt_switches: Number of nonvoluntary context switches.
-pub struct StatusFileOps(Arc<Process>);
+pub struct StatusFileOps(Arc<Process>, Arc<PidNamespace>);
 
 impl StatusFileOps {
-    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
-        ProcFileBuilder::new(Self(process_ref))
+    pub fn new_inode(
+        process_ref: Arc<Process>,
+        pid_ns: Arc<PidNamespace>,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        ProcFileBuilder::new(Self(process_ref, pid_ns))
             .parent(parent)
             .build()
             .unwrap()
@@ -72,15 +76,17 @@ impl StatusFileOps {
 impl FileOps for StatusFileOps {
     fn data(&self) -> Result<Vec<u8>> {
         let process = &self.0;
+        let pid = self.1.local_id(process.pid()).unwrap_or(0);
+        let ppid = self.1.local_id(process.parent().pid()).unwrap_or(0);
         let main_thread = process.main_thread();
         let file_table = main_thread.as_posix_thread().unwrap().file_table();
 
         let mut status_output = String::new();
         writeln!(status_output, "Name:\t{}", process.executable_path()).unwrap();
-        writeln!(status_output, "Tgid:\t{}", process.pid()).unwrap();
-        writeln!(status_output, "Pid:\t{}", process.pid()).unwrap();
-        writeln!(status_output, "PPid:\t{}", process.parent().pid()).unwrap();
-        writeln!(status_output, "TracerPid:\t{}", process.parent().pid()).unwrap(); // Assuming TracerPid is the same as PPid
+        writeln!(status_output, "Tgid:\t{}", pid).unwrap();
+        writeln!(status_output, "Pid:\t{}", pid).unwrap();
+        writeln!(status_output, "PPid:\t{}", ppid).unwrap();
+        writeln!(status_output, "TracerPid:\t{}", ppid).unwrap(); // Assuming TracerPid is the same as PPid
         writeln!(status_output, "FDSize:\t{}", file_table.lock().len()).unwrap();
         writeln!(
             status_output,
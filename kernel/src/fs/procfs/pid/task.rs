@@ -8,16 +8,20 @@ use crate::{
         procfs::template::{DirOps, ProcDir, ProcDirBuilder},
         utils::{DirEntryVecExt, Inode},
     },
-    process::posix_thread::AsPosixThread,
+    process::{namespace::PidNamespace, posix_thread::AsPosixThread},
     Process,
 };
 
 /// Represents the inode at `/proc/[pid]/task`.
-pub struct TaskDirOps(Arc<Process>);
+pub struct TaskDirOps(Arc<Process>, Arc<PidNamespace>);
 
 impl TaskDirOps {
-    pub fn new_inode(process_ref: Arc<Process>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
-        ProcDirBuilder::new(Self(process_ref))
+    pub fn new_inode(
+        process_ref: Arc<Process>,
+        pid_ns: Arc<PidNamespace>,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        ProcDirBuilder::new(Self(process_ref, pid_ns))
             .parent(parent)
             .build()
             .unwrap()
@@ -63,8 +67,13 @@ impl DirOps for ThreadDirOps {
 
 impl DirOps for TaskDirOps {
     fn lookup_child(&self, this_ptr: Weak<dyn Inode>, name: &str) -> Result<Arc<dyn Inode>> {
+        let tid = name
+            .parse::<u32>()
+            .ok()
+            .and_then(|tid| self.1.global_id(tid))
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "No such thread"))?;
         for task in self.0.tasks().lock().as_slice() {
-            if task.as_posix_thread().unwrap().tid() != name.parse::<u32>().unwrap() {
+            if task.as_posix_thread().unwrap().tid() != tid {
                 continue;
             }
             return Ok(ThreadDirOps::new_inode(self.0.clone(), this_ptr));
@@ -79,10 +88,12 @@ impl DirOps for TaskDirOps {
         };
         let mut cached_children = this.cached_children().write();
         for task in self.0.tasks().lock().as_slice() {
-            cached_children.put_entry_if_not_found(
-                &format!("{}", task.as_posix_thread().unwrap().tid()),
-                || ThreadDirOps::new_inode(self.0.clone(), this_ptr.clone()),
-            );
+            let Some(tid) = self.1.local_id(task.as_posix_thread().unwrap().tid()) else {
+                continue;
+            };
+            cached_children.put_entry_if_not_found(&format!("{}", tid), || {
+                ThreadDirOps::new_inode(self.0.clone(), this_ptr.clone())
+            });
         }
     }
 }
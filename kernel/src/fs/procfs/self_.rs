@@ -6,19 +6,28 @@ use crate::{
         utils::Inode,
     },
     prelude::*,
+    process::namespace::PidNamespace,
 };
 
 /// Represents the inode at `/proc/self`.
-pub struct SelfSymOps;
+pub struct SelfSymOps(Arc<PidNamespace>);
 
 impl SelfSymOps {
-    pub fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
-        ProcSymBuilder::new(Self).parent(parent).build().unwrap()
+    pub fn new_inode(pid_ns: Arc<PidNamespace>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        ProcSymBuilder::new(Self(pid_ns))
+            .parent(parent)
+            .build()
+            .unwrap()
     }
 }
 
 impl SymOps for SelfSymOps {
     fn read_link(&self) -> Result<String> {
-        Ok(current!().pid().to_string())
+        // The current process is invisible if it is outside the PID namespace of the procfs.
+        let pid = self
+            .0
+            .local_id(current!().pid())
+            .ok_or_else(|| Error::new(Errno::ENOENT))?;
+        Ok(pid.to_string())
     }
 }
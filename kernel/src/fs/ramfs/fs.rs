@@ -23,8 +23,9 @@ use crate::{
         file_handle::FileLike,
         named_pipe::NamedPipe,
         utils::{
-            CStr256, DirentVisitor, Extension, FallocMode, FileSystem, FsFlags, Inode, InodeMode,
-            InodeType, IoctlCmd, Metadata, MknodType, PageCache, PageCacheBackend, SuperBlock,
+            CStr256, DirentVisitor, Extension, FallocMode, FileSeals, FileSystem, FsFlags, Inode,
+            InodeMode, InodeType, IoctlCmd, Metadata, MknodType, PageCache, PageCacheBackend,
+            StatusFlags, SuperBlock, Xattr, XattrName, XattrSetFlags,
         },
     },
     prelude::*,
@@ -58,6 +59,7 @@ impl RamFS {
                 typ: InodeType::Dir,
                 this: weak_root.clone(),
                 fs: weak_fs.clone(),
+                xattr: Xattr::new(),
                 extension: Extension::new(),
             }),
             inode_allocator: AtomicU64::new(ROOT_INO + 1),
@@ -89,7 +91,7 @@ impl FileSystem for RamFS {
 }
 
 /// An inode of `RamFs`.
-struct RamInode {
+pub(super) struct RamInode {
     /// Inode inner specifics
     inner: Inner,
     /// Inode metadata
@@ -102,6 +104,8 @@ struct RamInode {
     this: Weak<RamInode>,
     /// Reference to fs
     fs: Weak<RamFS>,
+    /// Extended attributes
+    xattr: Xattr,
     /// Extensions
     extension: Extension,
 }
@@ -190,6 +194,8 @@ struct InodeMeta {
     nlinks: usize,
     uid: Uid,
     gid: Gid,
+    /// The seals of the file, which only take effect on regular files.
+    seals: FileSeals,
 }
 
 impl InodeMeta {
@@ -205,6 +211,8 @@ impl InodeMeta {
             nlinks: 1,
             uid,
             gid,
+            // Files that are not created by `memfd_create` can never be sealed.
+            seals: FileSeals::F_SEAL_SEAL,
         }
     }
 
@@ -220,6 +228,8 @@ impl InodeMeta {
             nlinks: NUM_SPECIAL_ENTRIES,
             uid,
             gid,
+            // Files that are not created by `memfd_create` can never be sealed.
+            seals: FileSeals::F_SEAL_SEAL,
         }
     }
 
@@ -397,6 +407,7 @@ impl RamInode {
             typ: InodeType::Dir,
             this: weak_self.clone(),
             fs: Arc::downgrade(fs),
+            xattr: Xattr::new(),
             extension: Extension::new(),
         })
     }
@@ -409,6 +420,7 @@ impl RamInode {
             typ: InodeType::File,
             this: weak_self.clone(),
             fs: Arc::downgrade(fs),
+            xattr: Xattr::new(),
             extension: Extension::new(),
         })
     }
@@ -421,6 +433,7 @@ impl RamInode {
             typ: InodeType::SymLink,
             this: weak_self.clone(),
             fs: Arc::downgrade(fs),
+            xattr: Xattr::new(),
             extension: Extension::new(),
         })
     }
@@ -439,6 +452,7 @@ impl RamInode {
             typ: InodeType::from(device.type_()),
             this: weak_self.clone(),
             fs: Arc::downgrade(fs),
+            xattr: Xattr::new(),
             extension: Extension::new(),
         })
     }
@@ -451,6 +465,7 @@ impl RamInode {
             typ: InodeType::Socket,
             this: weak_self.clone(),
             fs: Arc::downgrade(fs),
+            xattr: Xattr::new(),
             extension: Extension::new(),
         })
     }
@@ -463,6 +478,7 @@ impl RamInode {
             typ: InodeType::NamedPipe,
             this: weak_self.clone(),
             fs: Arc::downgrade(fs),
+            xattr: Xattr::new(),
             extension: Extension::new(),
         })
     }
@@ -481,6 +497,26 @@ impl RamInode {
             .ok_or(Error::new(Errno::ENOENT))?;
         Ok(inode)
     }
+
+    /// Sets the seals of a newly-created file.
+    ///
+    /// Unlike [`Inode::add_seals`], this can remove `F_SEAL_SEAL` so that
+    /// files created by `memfd_create` can be sealed later.
+    pub(super) fn set_initial_seals(&self, seals: FileSeals) {
+        debug_assert_eq!(self.typ, InodeType::File);
+        self.metadata.lock().seals = seals;
+    }
+
+    fn check_seals_for_write(&self, should_expand_size: bool) -> Result<()> {
+        let seals = self.metadata.lock().seals;
+        if seals.intersects(FileSeals::F_SEAL_WRITE | FileSeals::F_SEAL_FUTURE_WRITE) {
+            return_errno_with_message!(Errno::EPERM, "the file is sealed for writing");
+        }
+        if should_expand_size && seals.contains(FileSeals::F_SEAL_GROW) {
+            return_errno_with_message!(Errno::EPERM, "the file size is sealed");
+        }
+        Ok(())
+    }
 }
 
 impl PageCacheBackend for RamInode {
@@ -525,7 +561,9 @@ impl Inode for RamInode {
                     read_len
                 }
                 Inner::Device(device) => {
-                    device.read(writer)?
+                    // The opened files read the device with their own status flags. See
+                    // `InodeHandle_::read_at`.
+                    device.read(writer, StatusFlags::empty())?
                     // Typically, devices like "/dev/zero" or "/dev/null" do not require modifying
                     // timestamps here. Please adjust this behavior accordingly if there are special devices.
                 }
@@ -553,6 +591,7 @@ impl Inode for RamInode {
                 let write_len = reader.remain();
                 let new_size = offset + write_len;
                 let should_expand_size = new_size > file_size;
+                self.check_seals_for_write(should_expand_size)?;
                 let new_size_aligned = new_size.align_up(BLOCK_SIZE);
                 if should_expand_size {
                     page_cache.resize(new_size_aligned)?;
@@ -571,7 +610,9 @@ impl Inode for RamInode {
             }
             InodeType::CharDevice | InodeType::BlockDevice => {
                 let device = self.inner.as_device().unwrap();
-                device.write(reader)?
+                // The opened files write the device with their own status flags. See
+                // `InodeHandle_::write_at`.
+                device.write(reader, StatusFlags::empty())?
                 // Typically, devices like "/dev/zero" or "/dev/null" do not require modifying
                 // timestamps here. Please adjust this behavior accordingly if there are special devices.
             }
@@ -602,6 +643,13 @@ impl Inode for RamInode {
             return Ok(());
         }
 
+        let seals = self.metadata.lock().seals;
+        if (new_size < file_size && seals.contains(FileSeals::F_SEAL_SHRINK))
+            || (new_size > file_size && seals.contains(FileSeals::F_SEAL_GROW))
+        {
+            return_errno_with_message!(Errno::EPERM, "the file size is sealed");
+        }
+
         let page_cache = self.inner.as_file().unwrap();
         page_cache.resize(new_size)?;
 
@@ -651,6 +699,12 @@ impl Inode for RamInode {
 
     fn set_mode(&self, mode: InodeMode) -> Result<()> {
         let mut inode_meta = self.metadata.lock();
+        if inode_meta.seals.contains(FileSeals::F_SEAL_EXEC)
+            && (inode_meta.mode ^ mode)
+                .intersects(InodeMode::S_IXUSR | InodeMode::S_IXGRP | InodeMode::S_IXOTH)
+        {
+            return_errno_with_message!(Errno::EPERM, "the executable bits are sealed");
+        }
         inode_meta.mode = mode;
         inode_meta.set_ctime(now());
         Ok(())
@@ -1159,6 +1213,7 @@ impl Inode for RamInode {
                 if offset >= file_size {
                     return Ok(());
                 }
+                self.check_seals_for_write(false)?;
                 let range = offset..file_size.min(offset + len);
                 // TODO: Think of a more light-weight approach
                 self.inner.as_file().unwrap().fill_zeros(range)
@@ -1189,6 +1244,62 @@ impl Inode for RamInode {
     fn extension(&self) -> Option<&Extension> {
         Some(&self.extension)
     }
+
+    fn seals(&self) -> Result<FileSeals> {
+        if self.typ != InodeType::File {
+            return_errno_with_message!(Errno::EINVAL, "the file cannot be sealed");
+        }
+        Ok(self.metadata.lock().seals)
+    }
+
+    fn add_seals(&self, seals: FileSeals) -> Result<()> {
+        if self.typ != InodeType::File {
+            return_errno_with_message!(Errno::EINVAL, "the file cannot be sealed");
+        }
+
+        let mut inode_meta = self.metadata.lock();
+        if inode_meta.seals.contains(FileSeals::F_SEAL_SEAL) {
+            return_errno_with_message!(Errno::EPERM, "the seals are sealed");
+        }
+
+        let writable_mapping_status = self
+            .inner
+            .as_file()
+            .unwrap()
+            .pages()
+            .writable_mapping_status();
+        if seals.contains(FileSeals::F_SEAL_WRITE)
+            && !inode_meta.seals.contains(FileSeals::F_SEAL_WRITE)
+        {
+            writable_mapping_status.deny()?;
+        }
+        if seals.contains(FileSeals::F_SEAL_FUTURE_WRITE) {
+            writable_mapping_status.deny_new();
+        }
+
+        inode_meta.seals |= seals;
+        Ok(())
+    }
+
+    fn set_xattr(&self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        self.xattr.set(name, value, flags)?;
+        self.set_ctime(now());
+        Ok(())
+    }
+
+    fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>> {
+        self.xattr.get(name)
+    }
+
+    fn list_xattr(&self) -> Result<Vec<String>> {
+        Ok(self.xattr.list())
+    }
+
+    fn remove_xattr(&self, name: XattrName) -> Result<()> {
+        self.xattr.remove(name)?;
+        self.set_ctime(now());
+        Ok(())
+    }
 }
 
 fn write_lock_two_direntries_by_ino<'a>(
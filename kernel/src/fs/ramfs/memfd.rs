@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Anonymous files created by `memfd_create`.
+//!
+//! A memfd is a regular file in a private `RamFS` instance that is unlinked
+//! right after its creation, so it can only be accessed through its file
+//! descriptors (or `/proc/[pid]/fd`). Its page cache can be mapped like any
+//! other ramfs file, which makes memfds suitable for sharing memory.
+
+use alloc::format;
+
+use spin::Once;
+
+use super::{fs::RamInode, RamFS};
+use crate::{
+    fs::{
+        inode_handle::InodeHandle,
+        path::{Dentry, MountNode},
+        utils::{AccessMode, FileSeals, InodeMode, InodeType, StatusFlags},
+    },
+    prelude::*,
+};
+
+/// The maximum length of the name of a memfd, excluding the `memfd:` prefix.
+pub const MEMFD_NAME_MAX: usize = 249;
+
+/// Creates a memfd named `memfd:{name}`.
+///
+/// The file initially has the seals in `seals`, and the permission bits in `mode`.
+pub fn new_memfd(name: &str, seals: FileSeals, mode: InodeMode) -> Result<InodeHandle> {
+    if name.len() > MEMFD_NAME_MAX {
+        return_errno_with_message!(Errno::EINVAL, "the memfd name is too long");
+    }
+
+    static MEMFD_MOUNT: Once<Arc<MountNode>> = Once::new();
+    let mount_node = MEMFD_MOUNT.call_once(|| MountNode::new_root(RamFS::new()));
+    let root = Dentry::new_fs_root(mount_node.clone());
+
+    let dentry = {
+        // Memfds may share the same name, so the name must be unlinked
+        // before another memfd is created with it.
+        static CREATION_LOCK: Mutex<()> = Mutex::new(());
+        let _guard = CREATION_LOCK.lock();
+
+        let name = format!("memfd:{}", name);
+        let dentry = root.new_fs_child(&name, InodeType::File, mode)?;
+        root.unlink(&name)?;
+        dentry
+    };
+
+    let ram_inode = dentry.inode().downcast_ref::<RamInode>().unwrap();
+    ram_inode.set_initial_seals(seals);
+
+    InodeHandle::new_unchecked_access(dentry, AccessMode::O_RDWR, StatusFlags::empty())
+}
@@ -12,7 +12,7 @@ use super::{
     ramfs::RamFS,
     utils::{FileSystem, InodeMode, InodeType},
 };
-use crate::prelude::*;
+use crate::{prelude::*, process::namespace::init_pid_ns};
 
 /// Unpack and prepare the rootfs from the initramfs CPIO buffer.
 pub fn init(initramfs_buf: &[u8]) -> Result<()> {
@@ -78,7 +78,7 @@ pub fn init(initramfs_buf: &[u8]) -> Result<()> {
     }
     // Mount ProcFS
     let proc_dentry = fs.lookup(&FsPath::try_from("/proc")?)?;
-    proc_dentry.mount(ProcFS::new())?;
+    proc_dentry.mount(ProcFS::new(init_pid_ns().clone()))?;
     // Mount DevFS
     let dev_dentry = fs.lookup(&FsPath::try_from("/dev")?)?;
     dev_dentry.mount(RamFS::new())?;
@@ -35,6 +35,10 @@ const PIPE_BUF: usize = 4096;
 #[cfg(ktest)]
 const PIPE_BUF: usize = 2;
 
+/// The length of the header that stores the length of a message written by
+/// [`Producer::try_write_message`].
+const MESSAGE_HEADER_LEN: usize = core::mem::size_of::<u32>();
+
 impl<T> Channel<T> {
     /// Creates a new channel with the given capacity.
     ///
@@ -155,6 +159,40 @@ impl Producer<u8> {
     }
 }
 
+impl Producer<u8> {
+    /// Tries to write `reader` to the channel as a single message.
+    ///
+    /// The message is either written as a whole or not written at all. Its boundary is preserved
+    /// if it is read by [`Consumer::try_read_message`]. Messages and bytes written by
+    /// [`Self::try_write`] should not be mixed in one channel.
+    ///
+    /// - Returns `Ok(_)` with the length of the message if successful.
+    /// - Returns `Err(EMSGSIZE)` if the message is too large to fit in the channel.
+    /// - Returns `Err(EPIPE)` if the channel is shut down.
+    /// - Returns `Err(EAGAIN)` if the channel is full.
+    pub fn try_write_message(&self, reader: &mut dyn MultiRead) -> Result<usize> {
+        let message_len = reader.sum_lens();
+        if MESSAGE_HEADER_LEN + message_len > self.0.common.capacity() {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+        }
+
+        if self.is_shutdown() {
+            return_errno_with_message!(Errno::EPIPE, "the channel is shut down");
+        }
+
+        let mut message = vec![0; MESSAGE_HEADER_LEN + message_len];
+        message[..MESSAGE_HEADER_LEN].copy_from_slice(&(message_len as u32).to_ne_bytes());
+        reader.read(&mut VmWriter::from(&mut message[MESSAGE_HEADER_LEN..]))?;
+
+        if !self.0.write_message(&message) {
+            return_errno_with_message!(Errno::EAGAIN, "the channel is full");
+        }
+        self.peer_end().pollee.notify(IoEvents::IN);
+
+        Ok(message_len)
+    }
+}
+
 impl<T: Pod> Producer<T> {
     /// Tries to push `item` to the channel.
     ///
@@ -216,6 +254,17 @@ impl Consumer<u8> {
     /// - Returns `Ok(0)` if the channel is shut down and there is no data left.
     /// - Returns `Err(EAGAIN)` if the channel is empty.
     pub fn try_read(&self, writer: &mut dyn MultiWrite) -> Result<usize> {
+        self.try_read_with_max_len(writer, usize::MAX)
+    }
+
+    /// Tries to read at most `max_len` bytes from the channel.
+    ///
+    /// The return values are the same as those of [`Self::try_read`].
+    pub fn try_read_with_max_len(
+        &self,
+        writer: &mut dyn MultiWrite,
+        max_len: usize,
+    ) -> Result<usize> {
         if writer.is_empty() {
             return Ok(0);
         }
@@ -223,7 +272,7 @@ impl Consumer<u8> {
         // This must be recorded before the actual operation to avoid race conditions.
         let is_shutdown = self.is_shutdown();
 
-        let read_len = self.0.read(writer)?;
+        let read_len = self.0.read(writer, max_len)?;
         self.peer_end().pollee.notify(IoEvents::OUT);
         self.this_end().pollee.invalidate();
 
@@ -237,6 +286,35 @@ impl Consumer<u8> {
     }
 }
 
+impl Consumer<u8> {
+    /// Tries to read a message written by [`Producer::try_write_message`] from the channel.
+    ///
+    /// If `writer` cannot hold the whole message, the message is truncated and the rest of it is
+    /// discarded.
+    ///
+    /// - Returns `Ok((_, _))` with the number of bytes read and the length of the message if
+    ///   successful.
+    /// - Returns `Ok((0, 0))` if the channel is shut down and there is no message left.
+    /// - Returns `Err(EAGAIN)` if the channel is empty.
+    pub fn try_read_message(&self, writer: &mut dyn MultiWrite) -> Result<(usize, usize)> {
+        // This must be recorded before the actual operation to avoid race conditions.
+        let is_shutdown = self.is_shutdown();
+
+        let message = self.0.read_message();
+        self.peer_end().pollee.notify(IoEvents::OUT);
+        self.this_end().pollee.invalidate();
+
+        if let Some(message) = message {
+            let read_len = writer.write(&mut VmReader::from(message.as_slice()))?;
+            Ok((read_len, message.len()))
+        } else if is_shutdown {
+            Ok((0, 0))
+        } else {
+            return_errno_with_message!(Errno::EAGAIN, "the channel is empty");
+        }
+    }
+}
+
 impl<T: Pod> Consumer<T> {
     /// Tries to read an item from the channel.
     ///
@@ -283,9 +361,9 @@ impl<T, R: TRights> Fifo<T, R> {
 
 impl<R: TRights> Fifo<u8, R> {
     #[require(R > Read)]
-    pub fn read(&self, writer: &mut dyn MultiWrite) -> Result<usize> {
+    pub fn read(&self, writer: &mut dyn MultiWrite, max_len: usize) -> Result<usize> {
         let mut rb = self.common.consumer.rb();
-        rb.read_fallible(writer)
+        rb.read_fallible_with_max_len(writer, max_len)
     }
 
     #[require(R > Write)]
@@ -297,6 +375,28 @@ impl<R: TRights> Fifo<u8, R> {
         }
         rb.write_fallible(reader)
     }
+
+    /// Writes a message, including its header, if there is enough space for it.
+    #[require(R > Write)]
+    pub fn write_message(&self, message: &[u8]) -> bool {
+        let mut rb = self.common.producer.rb();
+        rb.push_slice(message).is_some()
+    }
+
+    /// Reads a message, excluding its header.
+    #[require(R > Read)]
+    pub fn read_message(&self) -> Option<Vec<u8>> {
+        let mut rb = self.common.consumer.rb();
+
+        // The header and the payload are pushed at once, so we either have the whole message or
+        // nothing at all.
+        let mut header = [0; MESSAGE_HEADER_LEN];
+        rb.pop_slice(&mut header)?;
+
+        let mut message = vec![0; u32::from_ne_bytes(header) as usize];
+        rb.pop_slice(&mut message).unwrap();
+        Some(message)
+    }
 }
 
 impl<T: Pod, R: TRights> Fifo<T, R> {
@@ -424,4 +524,41 @@ mod test {
             .unwrap();
         assert_eq!(data, expected_data);
     }
+
+    #[ktest]
+    fn test_channel_messages() {
+        let channel = Channel::with_capacity(16);
+        let (producer, consumer) = channel.split();
+
+        let data = [1u8, 3, 7];
+
+        for len in [3, 0] {
+            let write_len = producer
+                .try_write_message(&mut VmReader::from(&data[..len]).to_fallible())
+                .unwrap();
+            assert_eq!(write_len, len);
+        }
+        // Each message has a header of 4 bytes, so there is no room for another message.
+        let err = producer
+            .try_write_message(&mut VmReader::from(data.as_slice()).to_fallible())
+            .unwrap_err();
+        assert_eq!(err.error(), Errno::EAGAIN);
+
+        let mut buf = [0u8; 2];
+        let lens = consumer
+            .try_read_message(&mut VmWriter::from(buf.as_mut_slice()).to_fallible())
+            .unwrap();
+        assert_eq!(lens, (2, 3));
+        assert_eq!(buf, data[..2]);
+
+        let lens = consumer
+            .try_read_message(&mut VmWriter::from(buf.as_mut_slice()).to_fallible())
+            .unwrap();
+        assert_eq!(lens, (0, 0));
+
+        let err = consumer
+            .try_read_message(&mut VmWriter::from(buf.as_mut_slice()).to_fallible())
+            .unwrap_err();
+        assert_eq!(err.error(), Errno::EAGAIN);
+    }
 }
@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Checksums of the on-disk metadata.
+//!
+//! Ext4 and JBD2 protect their metadata with CRC32C, while the group descriptors
+//! of Ext4 may also be protected with CRC16 if the `gdt_csum` feature is enabled.
+//!
+//! Same as Linux, the functions here neither invert the initial value nor the result,
+//! so that a checksum can be computed by chaining multiple calls.
+
+/// The reversed polynomial of CRC32C (Castagnoli).
+const CRC32C_POLY: u32 = 0x82F6_3B78;
+
+/// The reversed polynomial of CRC16 (ANSI).
+const CRC16_POLY: u16 = 0xA001;
+
+static CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32C_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+static CRC16_TABLE: [u16; 256] = {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u16;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC16_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Updates the CRC32C checksum `crc` with `data`.
+pub fn crc32c(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        CRC32C_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Updates the CRC16 checksum `crc` with `data`.
+pub fn crc16(crc: u16, data: &[u8]) -> u16 {
+    data.iter().fold(crc, |crc, &byte| {
+        CRC16_TABLE[((crc ^ byte as u16) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
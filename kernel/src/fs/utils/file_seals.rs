@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// The seals of a file, which restrict the operations on the file.
+    ///
+    /// Seals can only be added but never removed. See the man page of
+    /// `memfd_create(2)` for more details.
+    pub struct FileSeals: u32 {
+        /// Prevents further seals from being set.
+        const F_SEAL_SEAL = 0x0001;
+        /// Prevents the file from being shrunk.
+        const F_SEAL_SHRINK = 0x0002;
+        /// Prevents the file from being grown.
+        const F_SEAL_GROW = 0x0004;
+        /// Prevents writes, including writable shared mappings.
+        const F_SEAL_WRITE = 0x0008;
+        /// Like `F_SEAL_WRITE`, but existing writable shared mappings are kept.
+        const F_SEAL_FUTURE_WRITE = 0x0010;
+        /// Prevents the executable bits of the file mode from being changed.
+        const F_SEAL_EXEC = 0x0020;
+    }
+}
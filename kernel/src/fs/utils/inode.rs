@@ -7,12 +7,18 @@ use core::{any::TypeId, time::Duration};
 use aster_rights::Full;
 use core2::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, Write};
 
-use super::{DirentVisitor, FallocMode, FileSystem, IoctlCmd};
+use super::{
+    AccessMode, DirentVisitor, FallocMode, FileSeals, FileSystem, IoctlCmd, XattrName,
+    XattrSetFlags,
+};
 use crate::{
     events::IoEvents,
     fs::device::{Device, DeviceType},
     prelude::*,
-    process::{signal::PollHandle, Gid, Uid},
+    process::{
+        credentials::capabilities::CapSet, posix_thread::AsPosixThread, signal::PollHandle, Gid,
+        Uid,
+    },
     time::clocks::RealTimeCoarseClock,
     vm::vmo::Vmo,
 };
@@ -109,6 +115,18 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// The permissions to access an inode.
+    pub struct Permission: u16 {
+        /// Execute a file or search a directory.
+        const MAY_EXEC = 0o001;
+        /// Write.
+        const MAY_WRITE = 0o002;
+        /// Read.
+        const MAY_READ = 0o004;
+    }
+}
+
 impl InodeMode {
     pub fn is_readable(&self) -> bool {
         self.contains(Self::S_IRUSR)
@@ -336,6 +354,14 @@ pub trait Inode: Any + Sync + Send {
         Err(Error::new(Errno::EISDIR))
     }
 
+    /// Prepares the inode for being opened with the access mode.
+    ///
+    /// This is called before the file is opened, so stacked file systems can make the
+    /// inode ready for the following operations, e.g., copy it up before it is written.
+    fn prepare_open(&self, access_mode: AccessMode) -> Result<()> {
+        Ok(())
+    }
+
     fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
         Err(Error::new(Errno::ENOTDIR))
     }
@@ -398,6 +424,36 @@ pub trait Inode: Any + Sync + Send {
         return_errno!(Errno::EOPNOTSUPP);
     }
 
+    /// Returns the seals of the file.
+    fn seals(&self) -> Result<FileSeals> {
+        return_errno_with_message!(Errno::EINVAL, "the file cannot be sealed");
+    }
+
+    /// Adds new seals to the seals of the file.
+    fn add_seals(&self, _seals: FileSeals) -> Result<()> {
+        return_errno_with_message!(Errno::EINVAL, "the file cannot be sealed");
+    }
+
+    /// Sets the value of an extended attribute.
+    fn set_xattr(&self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "xattrs are not supported");
+    }
+
+    /// Gets the value of an extended attribute.
+    fn get_xattr(&self, name: XattrName) -> Result<Vec<u8>> {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "xattrs are not supported");
+    }
+
+    /// Lists the full names of all extended attributes.
+    fn list_xattr(&self) -> Result<Vec<String>> {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "xattrs are not supported");
+    }
+
+    /// Removes an extended attribute.
+    fn remove_xattr(&self, name: XattrName) -> Result<()> {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "xattrs are not supported");
+    }
+
     fn poll(&self, mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
         let events = IoEvents::IN | IoEvents::OUT;
         events & mask
@@ -441,6 +497,52 @@ impl dyn Inode {
         (self as &dyn Any).downcast_ref::<T>()
     }
 
+    /// Checks whether the current thread has the permissions to access the inode.
+    ///
+    /// Like Linux, the permission bits of the owner, the group or the others are checked against
+    /// the filesystem UID and GIDs of the thread. `CAP_DAC_OVERRIDE` and `CAP_DAC_READ_SEARCH`
+    /// bypass the checks.
+    pub fn check_permission(&self, perm: Permission) -> Result<()> {
+        let current = current_thread!();
+        let credentials = current.as_posix_thread().unwrap().credentials();
+
+        let mode = self.mode()?;
+        let group = self.group()?;
+        let granted_bits = if credentials.fsuid() == self.owner()? {
+            mode.bits() >> 6
+        } else if credentials.fsgid() == group || credentials.groups().contains(&group) {
+            mode.bits() >> 3
+        } else {
+            mode.bits()
+        };
+        if Permission::from_bits_truncate(granted_bits).contains(perm) {
+            return Ok(());
+        }
+
+        let capset = credentials.effective_capset();
+        let is_dir = self.type_() == InodeType::Dir;
+
+        // Executing a file still requires one of its execute bits to be set.
+        let is_executable =
+            is_dir || mode.intersects(InodeMode::S_IXUSR | InodeMode::S_IXGRP | InodeMode::S_IXOTH);
+        if capset.contains(CapSet::DAC_OVERRIDE)
+            && (is_executable || !perm.contains(Permission::MAY_EXEC))
+        {
+            return Ok(());
+        }
+
+        let read_search = if is_dir {
+            Permission::MAY_READ | Permission::MAY_EXEC
+        } else {
+            Permission::MAY_READ
+        };
+        if capset.contains(CapSet::DAC_READ_SEARCH) && read_search.contains(perm) {
+            return Ok(());
+        }
+
+        return_errno_with_message!(Errno::EACCES, "the permission is denied");
+    }
+
     pub fn read_to_end(&self, buf: &mut Vec<u8>) -> Result<usize> {
         if !self.type_().support_read() {
             return_errno!(Errno::EISDIR);
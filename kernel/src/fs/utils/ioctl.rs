@@ -33,12 +33,40 @@ pub enum IoctlCmd {
     FIOCLEX = 0x5451,
     /// Enable or disable asynchronous I/O mode.
     FIOASYNC = 0x5452,
+    /// Get the name of a network interface by its index
+    SIOCGIFNAME = 0x8910,
+    /// Get the list of the addresses of network interfaces
+    SIOCGIFCONF = 0x8912,
+    /// Get the flags of a network interface
+    SIOCGIFFLAGS = 0x8913,
+    /// Set the flags of a network interface
+    SIOCSIFFLAGS = 0x8914,
+    /// Get the address of a network interface
+    SIOCGIFADDR = 0x8915,
+    /// Set the address of a network interface
+    SIOCSIFADDR = 0x8916,
+    /// Get the broadcast address of a network interface
+    SIOCGIFBRDADDR = 0x8919,
+    /// Get the network mask of a network interface
+    SIOCGIFNETMASK = 0x891b,
+    /// Set the network mask of a network interface
+    SIOCSIFNETMASK = 0x891c,
+    /// Get the MTU of a network interface
+    SIOCGIFMTU = 0x8921,
+    /// Get the hardware address of a network interface
+    SIOCGIFHWADDR = 0x8927,
+    /// Get the index of a network interface by its name
+    SIOCGIFINDEX = 0x8933,
     /// Get Pty Number
     TIOCGPTN = 0x80045430,
     /// Lock/unlock Pty
     TIOCSPTLCK = 0x40045431,
     /// Safely open the slave
     TIOCGPTPEER = 0x40045441,
+    /// Attach a TUN/TAP iface to the file
+    TUNSETIFF = 0x400454ca,
+    /// Get the TUN/TAP iface attached to the file
+    TUNGETIFF = 0x800454d2,
     /// Get tdx report using TDCALL
     TDXGETREPORT = 0xc4405401,
 }
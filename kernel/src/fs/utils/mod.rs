@@ -4,14 +4,16 @@
 
 pub use access_mode::AccessMode;
 pub use channel::{Channel, Consumer, Producer};
+pub use checksum::{crc16, crc32c};
 pub use creation_flags::CreationFlags;
 pub use dirent_visitor::DirentVisitor;
 pub use direntry_vec::DirEntryVecExt;
 pub use falloc_mode::FallocMode;
 pub use file_creation_mask::FileCreationMask;
+pub use file_seals::FileSeals;
 pub use flock::{FlockItem, FlockList, FlockType};
 pub use fs::{FileSystem, FsFlags, SuperBlock};
-pub use inode::{Extension, Inode, InodeMode, InodeType, Metadata, MknodType};
+pub use inode::{Extension, Inode, InodeMode, InodeType, Metadata, MknodType, Permission};
 pub use ioctl::IoctlCmd;
 pub use page_cache::{PageCache, PageCacheBackend};
 pub use random_test::{generate_random_operation, new_fs_in_memory};
@@ -19,14 +21,20 @@ pub use range_lock::{
     FileRange, RangeLockItem, RangeLockItemBuilder, RangeLockList, RangeLockType, OFFSET_MAX,
 };
 pub use status_flags::StatusFlags;
+pub use xattr::{
+    Xattr, XattrName, XattrNamespace, XattrSetFlags, XATTR_LIST_MAX_LEN, XATTR_NAME_MAX_LEN,
+    XATTR_VALUE_MAX_LEN,
+};
 
 mod access_mode;
 mod channel;
+mod checksum;
 mod creation_flags;
 mod dirent_visitor;
 mod direntry_vec;
 mod falloc_mode;
 mod file_creation_mask;
+mod file_seals;
 mod flock;
 mod fs;
 mod inode;
@@ -35,6 +43,7 @@ mod page_cache;
 mod random_test;
 mod range_lock;
 mod status_flags;
+mod xattr;
 
 use core::{
     borrow::Borrow,
@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::prelude::*;
+
+/// The maximum length of the name of an extended attribute (including the namespace prefix).
+pub const XATTR_NAME_MAX_LEN: usize = 255;
+
+/// The maximum length of the value of an extended attribute.
+pub const XATTR_VALUE_MAX_LEN: usize = 65536;
+
+/// The maximum length of the list of extended attribute names.
+pub const XATTR_LIST_MAX_LEN: usize = 65536;
+
+/// The namespace of an extended attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum XattrNamespace {
+    /// The `user.` namespace, whose attributes are controlled by the file permission bits.
+    User,
+    /// The `trusted.` namespace, whose attributes are only accessible to privileged processes.
+    Trusted,
+    /// The `security.` namespace, which is used by the security modules.
+    Security,
+}
+
+impl XattrNamespace {
+    const ALL: [Self; 3] = [Self::User, Self::Trusted, Self::Security];
+
+    /// Returns the prefix of the names in the namespace.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::User => "user.",
+            Self::Trusted => "trusted.",
+            Self::Security => "security.",
+        }
+    }
+}
+
+/// The full name of an extended attribute, i.e., the namespace prefix followed by the name
+/// within the namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XattrName<'a> {
+    namespace: XattrNamespace,
+    full_name: &'a str,
+}
+
+impl<'a> XattrName<'a> {
+    /// Parses the full name of an extended attribute.
+    pub fn try_from_full_name(full_name: &'a str) -> Result<Self> {
+        if full_name.is_empty() || full_name.len() > XATTR_NAME_MAX_LEN {
+            return_errno_with_message!(Errno::ERANGE, "the xattr name length is invalid");
+        }
+
+        let namespace = XattrNamespace::ALL
+            .into_iter()
+            .find(|namespace| full_name.starts_with(namespace.prefix()))
+            .ok_or_else(|| {
+                Error::with_message(Errno::EOPNOTSUPP, "the xattr namespace is not supported")
+            })?;
+        if full_name.len() == namespace.prefix().len() {
+            return_errno_with_message!(Errno::EINVAL, "the xattr name is empty");
+        }
+
+        Ok(Self {
+            namespace,
+            full_name,
+        })
+    }
+
+    /// Returns the namespace.
+    pub fn namespace(&self) -> XattrNamespace {
+        self.namespace
+    }
+
+    /// Returns the full name, including the namespace prefix.
+    pub fn full_name(&self) -> &'a str {
+        self.full_name
+    }
+
+    /// Returns the name within the namespace, i.e., the full name without the namespace prefix.
+    pub fn name(&self) -> &'a str {
+        &self.full_name[self.namespace.prefix().len()..]
+    }
+}
+
+bitflags! {
+    /// The flags that control how an extended attribute is set.
+    pub struct XattrSetFlags: u32 {
+        /// Fails if the attribute already exists (`XATTR_CREATE`).
+        const CREATE_ONLY = 1 << 0;
+        /// Fails if the attribute does not exist (`XATTR_REPLACE`).
+        const REPLACE_ONLY = 1 << 1;
+    }
+}
+
+impl XattrSetFlags {
+    /// Checks whether an attribute can be set, given whether it already exists.
+    pub fn check(&self, exists: bool) -> Result<()> {
+        if exists && self.contains(Self::CREATE_ONLY) {
+            return_errno_with_message!(Errno::EEXIST, "the xattr already exists");
+        }
+        if !exists && self.contains(Self::REPLACE_ONLY) {
+            return_errno_with_message!(Errno::ENODATA, "the xattr does not exist");
+        }
+        Ok(())
+    }
+}
+
+/// An in-memory storage of extended attributes.
+///
+/// This is for the file systems that keep all the inodes in memory.
+pub struct Xattr {
+    attrs: RwMutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl Xattr {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        Self {
+            attrs: RwMutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Sets the value of an attribute.
+    pub fn set(&self, name: XattrName, value: &[u8], flags: XattrSetFlags) -> Result<()> {
+        let mut attrs = self.attrs.write();
+
+        match attrs.get_mut(name.full_name()) {
+            Some(old_value) => {
+                flags.check(true)?;
+                *old_value = value.to_vec();
+            }
+            None => {
+                flags.check(false)?;
+                attrs.insert(name.full_name().to_string(), value.to_vec());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the value of an attribute.
+    pub fn get(&self, name: XattrName) -> Result<Vec<u8>> {
+        self.attrs
+            .read()
+            .get(name.full_name())
+            .cloned()
+            .ok_or_else(|| Error::with_message(Errno::ENODATA, "the xattr does not exist"))
+    }
+
+    /// Lists the full names of all attributes.
+    pub fn list(&self) -> Vec<String> {
+        self.attrs.read().keys().cloned().collect()
+    }
+
+    /// Removes an attribute.
+    pub fn remove(&self, name: XattrName) -> Result<()> {
+        self.attrs
+            .write()
+            .remove(name.full_name())
+            .map(|_| ())
+            .ok_or_else(|| Error::with_message(Errno::ENODATA, "the xattr does not exist"))
+    }
+}
+
+impl Default for Xattr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,11 +1,20 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_rights::ReadOp;
+use spin::Once;
+
+use self::{
+    msg::system_v::queue::MsgQueues, semaphore::system_v::sem_set::SemaphoreSets,
+    shm::system_v::segment::ShmSegments,
+};
 use crate::{
     prelude::*,
-    process::{Gid, Uid},
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Uid},
 };
 
+pub mod msg;
 pub mod semaphore;
+pub mod shm;
 
 #[allow(non_camel_case_types)]
 pub type key_t = i32;
@@ -23,6 +32,15 @@ bitflags! {
     }
 }
 
+bitflags! {
+    pub struct PermissionMode: u16{
+        const ALTER  = 0o002;
+        const EXEC   = 0o001;
+        const WRITE  = 0o002;
+        const READ   = 0o004;
+    }
+}
+
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, TryFromInt)]
 #[allow(non_camel_case_types)]
@@ -86,7 +104,63 @@ impl IpcPermission {
         self.mode
     }
 
-    pub(self) fn new_sem_perm(key: key_t, uid: Uid, gid: Gid, mode: u16) -> Self {
+    /// Checks whether the process with `credentials` is granted `required_perm`.
+    ///
+    /// The permission bits are chosen by comparing the effective IDs and the
+    /// supplementary groups of the process against the owner and the creator.
+    pub fn check_perm(
+        &self,
+        credentials: &Credentials<ReadOp>,
+        required_perm: PermissionMode,
+    ) -> Result<()> {
+        let euid = credentials.euid();
+        let is_in_group =
+            |gid: Gid| credentials.egid() == gid || credentials.groups().contains(&gid);
+
+        let granted_mode = if euid == self.uid || euid == self.cuid {
+            self.mode >> 6
+        } else if is_in_group(self.gid) || is_in_group(self.cguid) {
+            self.mode >> 3
+        } else {
+            self.mode
+        };
+        if PermissionMode::from_bits_truncate(granted_mode).contains(required_perm)
+            || credentials.effective_capset().contains(CapSet::IPC_OWNER)
+        {
+            return Ok(());
+        }
+
+        return_errno_with_message!(Errno::EACCES, "the IPC permission is denied");
+    }
+
+    /// Checks whether the process with `credentials` is allowed to change
+    /// the permission or remove the IPC object.
+    pub fn check_owner(&self, credentials: &Credentials<ReadOp>) -> Result<()> {
+        let euid = credentials.euid();
+        if euid == self.uid
+            || euid == self.cuid
+            || credentials.effective_capset().contains(CapSet::SYS_ADMIN)
+        {
+            return Ok(());
+        }
+
+        return_errno_with_message!(Errno::EPERM, "the process is not the owner");
+    }
+
+    /// Sets the owner and the permission mode.
+    pub fn set_owner_and_mode(&mut self, uid: Uid, gid: Gid, mode: u16) {
+        self.uid = uid;
+        self.gid = gid;
+        self.mode = mode & 0o777;
+    }
+
+    /// Detaches the IPC object from its key, so that it can no longer be
+    /// looked up by the key.
+    pub fn remove_key(&mut self) {
+        self.key = IPC_PRIVATE;
+    }
+
+    pub(self) fn new(key: key_t, uid: Uid, gid: Gid, mode: u16) -> Self {
         Self {
             key,
             uid,
@@ -98,6 +172,80 @@ impl IpcPermission {
     }
 }
 
-pub(super) fn init() {
-    semaphore::init();
+/// The key that always creates a new IPC object.
+pub const IPC_PRIVATE: key_t = 0;
+
+/// The permission of an IPC object, used by the `IPC_STAT` and `IPC_SET` commands.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct ipc64_perm {
+    pub key: key_t,
+    pub uid: u32,
+    pub gid: u32,
+    pub cuid: u32,
+    pub cgid: u32,
+    pub mode: u32,
+    pub seq: u16,
+    pub __pad2: u16,
+    pub __pad3: u32,
+    pub __unused1: u64,
+    pub __unused2: u64,
+}
+
+impl From<&IpcPermission> for ipc64_perm {
+    fn from(permission: &IpcPermission) -> Self {
+        Self {
+            key: permission.key,
+            uid: permission.uid.into(),
+            gid: permission.gid.into(),
+            cuid: permission.cuid.into(),
+            cgid: permission.cguid.into(),
+            mode: permission.mode as u32,
+            seq: 0,
+            __pad2: 0,
+            __pad3: 0,
+            __unused1: 0,
+            __unused2: 0,
+        }
+    }
+}
+
+/// An IPC namespace, which isolates the System V IPC objects.
+///
+/// POSIX message queues live in the mqueue file system instead.
+pub struct IpcNamespace {
+    sem_sets: SemaphoreSets,
+    shm_segments: ShmSegments,
+    msg_queues: MsgQueues,
+}
+
+impl IpcNamespace {
+    /// Creates a new namespace without any IPC objects.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            sem_sets: SemaphoreSets::new(),
+            shm_segments: ShmSegments::new(),
+            msg_queues: MsgQueues::new(),
+        })
+    }
+
+    pub fn sem_sets(&self) -> &SemaphoreSets {
+        &self.sem_sets
+    }
+
+    pub fn shm_segments(&self) -> &ShmSegments {
+        &self.shm_segments
+    }
+
+    pub fn msg_queues(&self) -> &MsgQueues {
+        &self.msg_queues
+    }
+}
+
+static INIT_IPC_NS: Once<Arc<IpcNamespace>> = Once::new();
+
+/// Returns the initial IPC namespace.
+pub fn init_ipc_ns() -> &'static Arc<IpcNamespace> {
+    INIT_IPC_NS.call_once(IpcNamespace::new)
 }
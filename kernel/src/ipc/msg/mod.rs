@@ -0,0 +1,7 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Message queues for the system, including System V message queues and
+//! POSIX message queues.
+
+pub mod posix;
+pub mod system_v;
@@ -0,0 +1,384 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! POSIX message queue.
+//!
+//! A POSIX message queue is a file in the mqueue file system (see
+//! [`crate::fs::mqueue`]). This module implements the queue itself, which is
+//! shared by all the files that refer to the same queue.
+
+use alloc::format;
+use core::fmt;
+
+use ostd::sync::WaitQueue;
+
+use crate::{
+    events::IoEvents,
+    prelude::*,
+    process::{
+        signal::{
+            c_types::{siginfo_t, sigval_t},
+            constants::SI_MESGQ,
+            sig_num::SigNum,
+            signals::Signal,
+            PollHandle, Pollable, Pollee,
+        },
+        Pid, Process, Uid,
+    },
+    time::wait::TimeoutExt,
+};
+
+// The following constant values are derived from the default values in Linux.
+
+/// Priorities of messages must be less than this value.
+pub const MQ_PRIO_MAX: u32 = 32768;
+/// Default maximum number of messages in a queue.
+pub const DFLT_MSGMAX: usize = 10;
+/// Default maximum size in bytes of a message.
+pub const DFLT_MSGSIZEMAX: usize = 8192;
+/// Maximum number of messages in a queue for unprivileged processes.
+pub const MSGMAX: usize = 10;
+/// Maximum size in bytes of a message for unprivileged processes.
+pub const MSGSIZEMAX: usize = 8192;
+/// Maximum number of messages in a queue for privileged processes.
+pub const HARD_MSGMAX: usize = 65536;
+/// Maximum size in bytes of a message for privileged processes.
+pub const HARD_MSGSIZEMAX: usize = 16 * 1024 * 1024;
+
+/// The attributes of a POSIX message queue.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct mq_attr {
+    pub mq_flags: i64,
+    pub mq_maxmsg: i64,
+    pub mq_msgsize: i64,
+    pub mq_curmsgs: i64,
+    pub __reserved: [i64; 4],
+}
+
+/// A POSIX message queue.
+pub struct MessageQueue {
+    /// Maximum number of messages
+    max_msgs: usize,
+    /// Maximum size in bytes of a message
+    msg_size: usize,
+    inner: Mutex<MessageQueueInner>,
+    /// Senders that wait for the queue to become non-full
+    send_wait_queue: WaitQueue,
+    /// Receivers that wait for the queue to become non-empty
+    recv_wait_queue: WaitQueue,
+    pollee: Pollee,
+}
+
+struct MessageQueueInner {
+    /// Messages grouped by their priorities
+    messages: BTreeMap<u32, VecDeque<Vec<u8>>>,
+    /// Number of messages
+    nr_msgs: usize,
+    /// Total size in bytes of the messages
+    nr_bytes: usize,
+    /// Number of receivers that are blocked on the queue
+    nr_waiting_receivers: usize,
+    /// The process registered to be notified of new messages
+    notification: Option<Notification>,
+}
+
+/// A registration of `mq_notify`.
+pub struct Notification {
+    process: Weak<Process>,
+    pid: Pid,
+    /// The signal to be sent, or `None` if no signal is sent (`SIGEV_NONE`)
+    signal: Option<(SigNum, sigval_t)>,
+}
+
+impl Notification {
+    /// Creates a registration that sends `signal` with `value` to `process`.
+    pub fn new_signal(process: &Arc<Process>, signal: SigNum, value: sigval_t) -> Self {
+        Self {
+            process: Arc::downgrade(process),
+            pid: process.pid(),
+            signal: Some((signal, value)),
+        }
+    }
+
+    /// Creates a registration that sends nothing to `process`.
+    pub fn new_none(process: &Arc<Process>) -> Self {
+        Self {
+            process: Arc::downgrade(process),
+            pid: process.pid(),
+            signal: None,
+        }
+    }
+}
+
+impl MessageQueue {
+    pub fn new(max_msgs: usize, msg_size: usize) -> Self {
+        Self {
+            max_msgs,
+            msg_size,
+            inner: Mutex::new(MessageQueueInner {
+                messages: BTreeMap::new(),
+                nr_msgs: 0,
+                nr_bytes: 0,
+                nr_waiting_receivers: 0,
+                notification: None,
+            }),
+            send_wait_queue: WaitQueue::new(),
+            recv_wait_queue: WaitQueue::new(),
+            pollee: Pollee::new(),
+        }
+    }
+
+    pub fn max_msgs(&self) -> usize {
+        self.max_msgs
+    }
+
+    pub fn msg_size(&self) -> usize {
+        self.msg_size
+    }
+
+    /// Returns the number of messages in the queue.
+    pub fn nr_msgs(&self) -> usize {
+        self.inner.lock().nr_msgs
+    }
+
+    /// Sends a message with the priority `prio` to the queue.
+    ///
+    /// If the queue is full, this method blocks until there is room for the
+    /// message or the timeout expires, unless `is_nonblocking` is true.
+    pub fn send(
+        &self,
+        msg: Vec<u8>,
+        prio: u32,
+        is_nonblocking: bool,
+        timeout: TimeoutExt,
+        sender: (Pid, Uid),
+    ) -> Result<()> {
+        if msg.len() > self.msg_size {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+        }
+        if prio >= MQ_PRIO_MAX {
+            return_errno_with_message!(Errno::EINVAL, "the priority is too high");
+        }
+
+        let mut msg = Some(msg);
+        if is_nonblocking {
+            return self.try_send(&mut msg, prio, sender);
+        }
+
+        self.send_wait_queue.pause_until_or_timeout(
+            || match self.try_send(&mut msg, prio, sender) {
+                Err(err) if err.error() == Errno::EAGAIN => None,
+                res => Some(res),
+            },
+            timeout,
+        )?
+    }
+
+    fn try_send(&self, msg: &mut Option<Vec<u8>>, prio: u32, sender: (Pid, Uid)) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.nr_msgs == self.max_msgs {
+            return_errno_with_message!(Errno::EAGAIN, "the queue is full");
+        }
+
+        let msg = msg.take().unwrap();
+        inner.nr_msgs += 1;
+        inner.nr_bytes += msg.len();
+        inner.messages.entry(prio).or_default().push_back(msg);
+
+        // The registered process is notified only if the queue was empty and
+        // no one is waiting for the new message.
+        if inner.nr_msgs == 1 && inner.nr_waiting_receivers == 0 {
+            if let Some(notification) = inner.notification.take() {
+                notify(notification, sender);
+            }
+        }
+        drop(inner);
+
+        self.pollee.notify(IoEvents::IN);
+        self.recv_wait_queue.wake_all();
+        Ok(())
+    }
+
+    /// Receives the oldest message with the highest priority from the queue.
+    ///
+    /// Returns the message and its priority. If the queue is empty, this method
+    /// blocks until a message arrives or the timeout expires, unless
+    /// `is_nonblocking` is true.
+    pub fn receive(
+        &self,
+        buf_len: usize,
+        is_nonblocking: bool,
+        timeout: TimeoutExt,
+    ) -> Result<(Vec<u8>, u32)> {
+        if buf_len < self.msg_size {
+            return_errno_with_message!(Errno::EMSGSIZE, "the buffer is too small");
+        }
+
+        if is_nonblocking {
+            return self.try_receive();
+        }
+
+        self.inner.lock().nr_waiting_receivers += 1;
+        let res = self.recv_wait_queue.pause_until_or_timeout(
+            || match self.try_receive() {
+                Err(err) if err.error() == Errno::EAGAIN => None,
+                res => Some(res),
+            },
+            timeout,
+        );
+        self.inner.lock().nr_waiting_receivers -= 1;
+        res?
+    }
+
+    fn try_receive(&self) -> Result<(Vec<u8>, u32)> {
+        let mut inner = self.inner.lock();
+
+        let Some(mut entry) = inner.messages.last_entry() else {
+            return_errno_with_message!(Errno::EAGAIN, "the queue is empty");
+        };
+        let prio = *entry.key();
+        let msg = entry.get_mut().pop_front().unwrap();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+        inner.nr_msgs -= 1;
+        inner.nr_bytes -= msg.len();
+        drop(inner);
+
+        self.pollee.notify(IoEvents::OUT);
+        self.send_wait_queue.wake_all();
+        Ok((msg, prio))
+    }
+
+    /// Registers `notification` to be notified when a message arrives at the
+    /// empty queue, as `mq_notify` does.
+    ///
+    /// At most one process can be registered at a time. The registration is
+    /// removed once the process is notified.
+    pub fn register_notification(&self, notification: Notification) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if let Some(registered) = inner.notification.as_ref()
+            && registered.process.strong_count() > 0
+        {
+            return_errno_with_message!(Errno::EBUSY, "another process has been registered");
+        }
+        inner.notification = Some(notification);
+        Ok(())
+    }
+
+    /// Removes the registration of the process with `pid`.
+    pub fn unregister_notification(&self, pid: Pid) {
+        let mut inner = self.inner.lock();
+        if inner
+            .notification
+            .as_ref()
+            .is_some_and(|notification| notification.pid == pid)
+        {
+            inner.notification = None;
+        }
+    }
+
+    /// Returns the status of the queue, which is read from the queue file.
+    pub fn status(&self) -> String {
+        const SIGEV_SIGNAL: i32 = 0;
+        const SIGEV_NONE: i32 = 1;
+
+        let inner = self.inner.lock();
+        let (notify, signo, notify_pid) = match inner.notification.as_ref() {
+            Some(Notification {
+                pid,
+                signal: Some((signo, _)),
+                ..
+            }) => (SIGEV_SIGNAL, signo.as_u8() as i32, *pid),
+            Some(Notification {
+                pid, signal: None, ..
+            }) => (SIGEV_NONE, 0, *pid),
+            None => (0, 0, 0),
+        };
+
+        format!(
+            "QSIZE:{:<10} NOTIFY:{:<5} SIGNO:{:<5} NOTIFY_PID:{:<6}\n",
+            inner.nr_bytes, notify, signo, notify_pid
+        )
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+
+        let mut events = IoEvents::empty();
+        if inner.nr_msgs > 0 {
+            events |= IoEvents::IN;
+        }
+        if inner.nr_msgs < self.max_msgs {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+}
+
+impl Pollable for MessageQueue {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl Debug for MessageQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageQueue")
+            .field("max_msgs", &self.max_msgs)
+            .field("msg_size", &self.msg_size)
+            .field("nr_msgs", &self.nr_msgs())
+            .finish()
+    }
+}
+
+fn notify(notification: Notification, sender: (Pid, Uid)) {
+    let Some((num, value)) = notification.signal else {
+        return;
+    };
+    let Some(process) = notification.process.upgrade() else {
+        return;
+    };
+
+    let (pid, uid) = sender;
+    process.enqueue_signal(MessageQueueSignal {
+        num,
+        pid,
+        uid,
+        value,
+    });
+}
+
+/// The signal sent to notify a process of a new message.
+#[derive(Clone, Copy)]
+struct MessageQueueSignal {
+    num: SigNum,
+    pid: Pid,
+    uid: Uid,
+    value: sigval_t,
+}
+
+impl Signal for MessageQueueSignal {
+    fn num(&self) -> SigNum {
+        self.num
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        let mut info = siginfo_t::new(self.num, SI_MESGQ);
+        info.set_si_pid_uid(self.pid, self.uid);
+        info.set_si_value(self.value);
+        info
+    }
+}
+
+impl Debug for MessageQueueSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageQueueSignal")
+            .field("num", &self.num)
+            .field("pid", &self.pid)
+            .field("uid", &self.uid)
+            .finish()
+    }
+}
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V message queue.
+
+use crate::prelude::*;
+
+pub mod queue;
+
+bitflags! {
+    /// The flags of `msgsnd` and `msgrcv`.
+    pub struct MsgFlags: u32 {
+        /// Return immediately instead of blocking.
+        const IPC_NOWAIT = 0o4000;
+        /// Truncate the message if it is too long.
+        const MSG_NOERROR = 0o10000;
+        /// Receive the first message whose type differs from the given type.
+        const MSG_EXCEPT = 0o20000;
+        /// Copy the message at the given position without removing it.
+        const MSG_COPY = 0o40000;
+    }
+}
+
+/// The commands of `msgctl`.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+pub enum MsgControlCmd {
+    IPC_RMID = 0,
+    IPC_SET = 1,
+    IPC_STAT = 2,
+}
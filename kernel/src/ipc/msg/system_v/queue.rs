@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use aster_rights::ReadOp;
+use id_alloc::IdAlloc;
+use ostd::sync::WaitQueue;
+
+use super::MsgFlags;
+use crate::{
+    ipc::{ipc64_perm, key_t, IpcFlags, IpcPermission, PermissionMode, IPC_PRIVATE},
+    prelude::*,
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Pid, Uid},
+    time::clocks::RealTimeCoarseClock,
+};
+
+// The following constant values are derived from the default values in Linux.
+
+/// Maximum number of message queues.
+pub const MSGMNI: usize = 32000;
+/// Maximum size in bytes of a message.
+pub const MSGMAX: usize = 8192;
+/// Default maximum size in bytes of a message queue.
+pub const MSGMNB: usize = 16384;
+
+#[derive(Debug)]
+pub struct MessageQueue {
+    /// Queue ID
+    id: key_t,
+    /// Inner
+    inner: Mutex<MessageQueueInner>,
+    /// Senders that wait for the queue to have enough room
+    send_wait_queue: WaitQueue,
+    /// Receivers that wait for the wanted messages
+    recv_wait_queue: WaitQueue,
+}
+
+#[derive(Debug)]
+struct MessageQueueInner {
+    /// Queue permission
+    permission: IpcPermission,
+    /// Whether the queue is removed by `IPC_RMID`
+    is_removed: bool,
+    /// Messages in the order they are sent
+    messages: VecDeque<Message>,
+    /// Total size in bytes of the messages
+    nr_bytes: usize,
+    /// Maximum size in bytes of the messages
+    max_bytes: usize,
+    /// PID of the last `msgsnd`
+    last_send_pid: Pid,
+    /// PID of the last `msgrcv`
+    last_recv_pid: Pid,
+    /// Last `msgsnd` time
+    stime: Duration,
+    /// Last `msgrcv` time
+    rtime: Duration,
+    /// Creation time or last modification via `msgctl`
+    ctime: Duration,
+}
+
+/// A message in a System V message queue.
+#[derive(Debug)]
+pub struct Message {
+    mtype: i64,
+    data: Vec<u8>,
+}
+
+impl Message {
+    pub fn new(mtype: i64, data: Vec<u8>) -> Self {
+        Self { mtype, data }
+    }
+
+    pub fn mtype(&self) -> i64 {
+        self.mtype
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl MessageQueue {
+    pub fn id(&self) -> key_t {
+        self.id
+    }
+
+    /// Sends `msg` to the queue, as `msgsnd` does.
+    ///
+    /// If the queue does not have enough room for the message, this method
+    /// blocks until it does, unless `IPC_NOWAIT` is specified in `flags`.
+    pub fn send(
+        &self,
+        msg: Message,
+        flags: MsgFlags,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<()> {
+        self.inner
+            .lock()
+            .permission
+            .check_perm(credentials, PermissionMode::WRITE)?;
+
+        let mut msg = Some(msg);
+        if flags.contains(MsgFlags::IPC_NOWAIT) {
+            return self.try_send(&mut msg, pid);
+        }
+
+        self.send_wait_queue
+            .pause_until(|| match self.try_send(&mut msg, pid) {
+                Err(err) if err.error() == Errno::EAGAIN => None,
+                res => Some(res),
+            })?
+    }
+
+    fn try_send(&self, msg: &mut Option<Message>, pid: Pid) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.is_removed {
+            return_errno_with_message!(Errno::EIDRM, "the queue is removed");
+        }
+
+        // The number of messages is also limited, so that a queue cannot be
+        // filled with too many empty messages.
+        let len = msg.as_ref().unwrap().data.len();
+        if inner.nr_bytes + len > inner.max_bytes || inner.messages.len() + 1 > inner.max_bytes {
+            return_errno_with_message!(Errno::EAGAIN, "the queue is full");
+        }
+
+        inner.messages.push_back(msg.take().unwrap());
+        inner.nr_bytes += len;
+        inner.last_send_pid = pid;
+        inner.stime = now();
+        drop(inner);
+
+        self.recv_wait_queue.wake_all();
+        Ok(())
+    }
+
+    /// Receives a message of `mtype` from the queue, as `msgrcv` does.
+    ///
+    /// The message is chosen according to `mtype`:
+    ///  - If `mtype` is zero, the first message is received;
+    ///  - If `mtype` is positive, the first message of `mtype` is received,
+    ///    or the first message not of `mtype` if `MSG_EXCEPT` is specified;
+    ///  - If `mtype` is negative, the first message with the lowest type that
+    ///    is less than or equal to the absolute value of `mtype` is received.
+    ///
+    /// If the message is longer than `max_len`, it is truncated if
+    /// `MSG_NOERROR` is specified, otherwise it is left in the queue and this
+    /// method fails with `E2BIG`.
+    ///
+    /// If no message is available, this method blocks until one is, unless
+    /// `IPC_NOWAIT` is specified in `flags`.
+    pub fn receive(
+        &self,
+        mtype: i64,
+        max_len: usize,
+        flags: MsgFlags,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<Message> {
+        self.inner
+            .lock()
+            .permission
+            .check_perm(credentials, PermissionMode::READ)?;
+
+        if flags.contains(MsgFlags::IPC_NOWAIT) {
+            return self.try_receive(mtype, max_len, flags, pid);
+        }
+
+        self.recv_wait_queue
+            .pause_until(|| match self.try_receive(mtype, max_len, flags, pid) {
+                Err(err) if err.error() == Errno::ENOMSG => None,
+                res => Some(res),
+            })?
+    }
+
+    fn try_receive(
+        &self,
+        mtype: i64,
+        max_len: usize,
+        flags: MsgFlags,
+        pid: Pid,
+    ) -> Result<Message> {
+        let mut inner = self.inner.lock();
+        if inner.is_removed {
+            return_errno_with_message!(Errno::EIDRM, "the queue is removed");
+        }
+
+        let messages = &inner.messages;
+        let index = if mtype == 0 {
+            (!messages.is_empty()).then_some(0)
+        } else if mtype > 0 && flags.contains(MsgFlags::MSG_EXCEPT) {
+            messages.iter().position(|msg| msg.mtype != mtype)
+        } else if mtype > 0 {
+            messages.iter().position(|msg| msg.mtype == mtype)
+        } else {
+            messages
+                .iter()
+                .enumerate()
+                .filter(|(_, msg)| msg.mtype <= mtype.saturating_abs())
+                .min_by_key(|(index, msg)| (msg.mtype, *index))
+                .map(|(index, _)| index)
+        };
+        let Some(index) = index else {
+            return_errno_with_message!(Errno::ENOMSG, "no message of the wanted type");
+        };
+
+        if messages[index].data.len() > max_len && !flags.contains(MsgFlags::MSG_NOERROR) {
+            return_errno_with_message!(Errno::E2BIG, "the message is too long");
+        }
+
+        let mut msg = inner.messages.remove(index).unwrap();
+        inner.nr_bytes -= msg.data.len();
+        inner.last_recv_pid = pid;
+        inner.rtime = now();
+        drop(inner);
+
+        msg.data.truncate(max_len);
+        self.send_wait_queue.wake_all();
+        Ok(msg)
+    }
+
+    /// Sets the owner, the permission mode and the maximum size in bytes of
+    /// the queue, as `IPC_SET` does.
+    pub fn set_attributes(
+        &self,
+        credentials: &Credentials<ReadOp>,
+        uid: Uid,
+        gid: Gid,
+        mode: u16,
+        max_bytes: usize,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.permission.check_owner(credentials)?;
+        if max_bytes > MSGMNB
+            && max_bytes > inner.max_bytes
+            && !credentials
+                .effective_capset()
+                .contains(CapSet::SYS_RESOURCE)
+        {
+            return_errno_with_message!(Errno::EPERM, "the queue size cannot be raised");
+        }
+
+        inner.permission.set_owner_and_mode(uid, gid, mode);
+        inner.max_bytes = max_bytes;
+        inner.ctime = now();
+        drop(inner);
+
+        // Senders may be able to send messages now.
+        self.send_wait_queue.wake_all();
+        Ok(())
+    }
+
+    /// Returns the status of the queue, as `IPC_STAT` does.
+    pub fn stat(&self, credentials: &Credentials<ReadOp>) -> Result<msqid_ds> {
+        let inner = self.inner.lock();
+        inner
+            .permission
+            .check_perm(credentials, PermissionMode::READ)?;
+
+        Ok(msqid_ds {
+            msg_perm: ipc64_perm::from(&inner.permission),
+            msg_stime: inner.stime.as_secs() as i64,
+            msg_rtime: inner.rtime.as_secs() as i64,
+            msg_ctime: inner.ctime.as_secs() as i64,
+            msg_cbytes: inner.nr_bytes as u64,
+            msg_qnum: inner.messages.len() as u64,
+            msg_qbytes: inner.max_bytes as u64,
+            msg_lspid: inner.last_send_pid,
+            msg_lrpid: inner.last_recv_pid,
+            __unused4: 0,
+            __unused5: 0,
+        })
+    }
+
+    fn key(&self) -> key_t {
+        self.inner.lock().permission.key()
+    }
+
+    fn new(id: key_t, key: key_t, mode: u16, credentials: &Credentials<ReadOp>) -> Self {
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Self {
+            id,
+            inner: Mutex::new(MessageQueueInner {
+                permission,
+                is_removed: false,
+                messages: VecDeque::new(),
+                nr_bytes: 0,
+                max_bytes: MSGMNB,
+                last_send_pid: 0,
+                last_recv_pid: 0,
+                stime: Duration::ZERO,
+                rtime: Duration::ZERO,
+                ctime: now(),
+            }),
+            send_wait_queue: WaitQueue::new(),
+            recv_wait_queue: WaitQueue::new(),
+        }
+    }
+}
+
+/// The status of a message queue, used by the `IPC_STAT` and `IPC_SET` commands.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct msqid_ds {
+    pub msg_perm: ipc64_perm,
+    pub msg_stime: i64,
+    pub msg_rtime: i64,
+    pub msg_ctime: i64,
+    pub msg_cbytes: u64,
+    pub msg_qnum: u64,
+    pub msg_qbytes: u64,
+    pub msg_lspid: Pid,
+    pub msg_lrpid: Pid,
+    pub __unused4: u64,
+    pub __unused5: u64,
+}
+
+/// The message queues in an IPC namespace.
+pub struct MsgQueues {
+    id_allocator: SpinLock<IdAlloc>,
+    /// The queues indexed by their IDs
+    queues: RwMutex<BTreeMap<key_t, Arc<MessageQueue>>>,
+}
+
+impl MsgQueues {
+    pub(in crate::ipc) fn new() -> Self {
+        Self {
+            id_allocator: SpinLock::new(IdAlloc::with_capacity(MSGMNI)),
+            queues: RwMutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the ID of the queue associated with `key`.
+    ///
+    /// A new queue is created if `key` is `IPC_PRIVATE`, or if no queue is
+    /// associated with `key` and `IPC_CREAT` is specified in `flags`.
+    pub fn get_or_create_queue(
+        &self,
+        key: key_t,
+        flags: IpcFlags,
+        mode: u16,
+        credentials: &Credentials<ReadOp>,
+    ) -> Result<key_t> {
+        let mut queues = self.queues.write();
+
+        if key != IPC_PRIVATE {
+            if let Some(queue) = queues.values().find(|queue| queue.key() == key) {
+                if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+                    return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+                }
+                // All the permissions in `mode` are checked against the permissions
+                // granted to the current process.
+                let required_perm =
+                    PermissionMode::from_bits_truncate(mode | mode >> 3 | mode >> 6);
+                queue
+                    .inner
+                    .lock()
+                    .permission
+                    .check_perm(credentials, required_perm)?;
+                return Ok(queue.id());
+            }
+
+            if !flags.contains(IpcFlags::IPC_CREAT) {
+                return_errno_with_message!(Errno::ENOENT, "the queue does not exist");
+            }
+        }
+
+        let id = self
+            .id_allocator
+            .lock()
+            .alloc()
+            .ok_or(Error::new(Errno::ENOSPC))? as key_t;
+        let queue = MessageQueue::new(id, key, mode, credentials);
+        queues.insert(id, Arc::new(queue));
+
+        Ok(id)
+    }
+
+    /// Returns the queue with `id`.
+    pub fn get_queue(&self, id: key_t) -> Result<Arc<MessageQueue>> {
+        self.queues
+            .read()
+            .get(&id)
+            .cloned()
+            .ok_or(Error::with_message(
+                Errno::EINVAL,
+                "the queue does not exist",
+            ))
+    }
+
+    /// Removes the queue with `id`, as `IPC_RMID` does.
+    ///
+    /// The processes blocked on the queue are woken up and fail with `EIDRM`.
+    pub fn remove_queue(&self, id: key_t, credentials: &Credentials<ReadOp>) -> Result<()> {
+        let mut queues = self.queues.write();
+        let queue = queues.get(&id).ok_or(Error::with_message(
+            Errno::EINVAL,
+            "the queue does not exist",
+        ))?;
+
+        {
+            let mut inner = queue.inner.lock();
+            inner.permission.check_owner(credentials)?;
+            inner.is_removed = true;
+            inner.messages.clear();
+            inner.nr_bytes = 0;
+        }
+        queue.send_wait_queue.wake_all();
+        queue.recv_wait_queue.wake_all();
+
+        queues.remove(&id);
+        self.id_allocator.lock().free(id as usize);
+        Ok(())
+    }
+}
+
+fn now() -> Duration {
+    RealTimeCoarseClock::get().read_time()
+}
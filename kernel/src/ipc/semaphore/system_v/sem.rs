@@ -11,7 +11,7 @@ use ostd::sync::{PreemptDisabled, Waiter, Waker};
 
 use super::sem_set::{SemSetInner, SEMVMX};
 use crate::{
-    ipc::{key_t, semaphore::system_v::sem_set::sem_sets, IpcFlags},
+    ipc::{key_t, IpcFlags},
     prelude::*,
     process::Pid,
     time::{clocks::JIFFIES_TIMER_MANAGER, timer::Timeout},
@@ -153,7 +153,9 @@ pub fn sem_op(
         warn!("Found duplicate sop");
     }
 
-    let local_sem_sets = sem_sets();
+    let ns_proxy = ctx.posix_thread.ns_proxy();
+    let sem_sets = ns_proxy.ipc_ns().sem_sets();
+    let local_sem_sets = sem_sets.sem_sets();
     let sem_set = local_sem_sets
         .get(&sem_id)
         .ok_or(Error::new(Errno::EINVAL))?;
@@ -205,8 +207,10 @@ pub fn sem_op(
         Status::Removed => Err(Error::new(Errno::EIDRM)),
         Status::Pending => {
             // FIXME: Getting sem_sets maybe time-consuming.
-            let sem_sets = sem_sets();
-            let sem_set = sem_sets.get(&sem_id).ok_or(Error::new(Errno::EINVAL))?;
+            let local_sem_sets = sem_sets.sem_sets();
+            let sem_set = local_sem_sets
+                .get(&sem_id)
+                .ok_or(Error::new(Errno::EINVAL))?;
             let mut inner = sem_set.inner();
 
             let pending_ops = if alter {
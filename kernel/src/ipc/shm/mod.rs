@@ -0,0 +1,8 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Shared memory for the system.
+//!
+//! POSIX shared memory is built on the files in `/dev/shm`, so only System V
+//! shared memory lives here.
+
+pub mod system_v;
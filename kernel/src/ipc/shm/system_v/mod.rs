@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V shared memory.
+
+use crate::prelude::*;
+
+pub mod segment;
+
+bitflags! {
+    /// The flags of `shmat`.
+    pub struct ShmFlags: u32 {
+        /// Attach the segment for read-only access.
+        const SHM_RDONLY = 0o10000;
+        /// Round the attach address down to a multiple of `SHMLBA`.
+        const SHM_RND = 0o20000;
+        /// Take over the existing mappings in the range.
+        const SHM_REMAP = 0o40000;
+        /// Allow the contents of the segment to be executed.
+        const SHM_EXEC = 0o100000;
+    }
+}
+
+/// The commands of `shmctl`.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+pub enum ShmControlCmd {
+    IPC_RMID = 0,
+    IPC_SET = 1,
+    IPC_STAT = 2,
+
+    SHM_LOCK = 11,
+    SHM_UNLOCK = 12,
+}
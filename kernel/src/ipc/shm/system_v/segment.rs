@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::collections::btree_map::BTreeMap;
+use core::time::Duration;
+
+use align_ext::AlignExt;
+use aster_rights::{ReadOp, Rights};
+use id_alloc::IdAlloc;
+
+use crate::{
+    ipc::{ipc64_perm, key_t, IpcFlags, IpcPermission, PermissionMode, IPC_PRIVATE},
+    prelude::*,
+    process::{credentials::capabilities::CapSet, Credentials, Gid, Pid, Uid},
+    time::clocks::RealTimeCoarseClock,
+    vm::vmo::{Vmo, VmoOptions},
+};
+
+// The following constant values are derived from the default values in Linux.
+
+/// Maximum number of shared memory segments.
+pub const SHMMNI: usize = 4096;
+/// Minimum size in bytes of a shared memory segment.
+pub const SHMMIN: usize = 1;
+/// Maximum size in bytes of a shared memory segment.
+pub const SHMMAX: usize = usize::MAX - (1 << 24);
+
+/// The mode bit that indicates the segment will be destroyed after the last detach.
+const SHM_DEST: u32 = 0o1000;
+/// The mode bit that indicates the segment is locked in memory.
+const SHM_LOCKED: u32 = 0o2000;
+
+#[derive(Debug)]
+pub struct ShmSegment {
+    /// Segment ID
+    id: key_t,
+    /// Size in bytes requested by `shmget`
+    size: usize,
+    /// The memory of the segment, which is shared by all attachments
+    vmo: Vmo<Rights>,
+    /// Creator's PID
+    creator_pid: Pid,
+    /// Inner
+    inner: SpinLock<ShmSegmentInner>,
+}
+
+#[derive(Debug)]
+struct ShmSegmentInner {
+    /// Segment permission
+    permission: IpcPermission,
+    /// Whether the segment is marked to be destroyed by `IPC_RMID`
+    is_removed: bool,
+    /// Whether the segment is locked by `SHM_LOCK`
+    is_locked: bool,
+    /// PID of the last `shmat` or `shmdt`
+    last_pid: Pid,
+    /// Last attach time
+    atime: Duration,
+    /// Last detach time
+    dtime: Duration,
+    /// Creation time or last modification via `shmctl`
+    ctime: Duration,
+}
+
+impl ShmSegment {
+    pub fn id(&self) -> key_t {
+        self.id
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn vmo(&self) -> &Vmo<Rights> {
+        &self.vmo
+    }
+
+    /// Returns the number of current attaches.
+    pub fn nattch(&self) -> usize {
+        self.vmo.nr_mappings()
+    }
+
+    pub fn check_perm(
+        &self,
+        credentials: &Credentials<ReadOp>,
+        required_perm: PermissionMode,
+    ) -> Result<()> {
+        self.inner
+            .lock()
+            .permission
+            .check_perm(credentials, required_perm)
+    }
+
+    /// Records that the segment is attached by the process with `pid`.
+    pub fn on_attached(&self, pid: Pid) {
+        let mut inner = self.inner.lock();
+        inner.last_pid = pid;
+        inner.atime = now();
+    }
+
+    /// Records that the segment is detached by the process with `pid`.
+    pub fn on_detached(&self, pid: Pid) {
+        let mut inner = self.inner.lock();
+        inner.last_pid = pid;
+        inner.dtime = now();
+    }
+
+    /// Sets the owner and the permission mode, as `IPC_SET` does.
+    pub fn set_owner_and_mode(
+        &self,
+        credentials: &Credentials<ReadOp>,
+        uid: Uid,
+        gid: Gid,
+        mode: u16,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock();
+        inner.permission.check_owner(credentials)?;
+        inner.permission.set_owner_and_mode(uid, gid, mode);
+        inner.ctime = now();
+        Ok(())
+    }
+
+    /// Locks or unlocks the segment in memory, as `SHM_LOCK` and `SHM_UNLOCK` do.
+    ///
+    /// Pages of the segment are never swapped out, so this only changes the
+    /// status reported by `IPC_STAT`.
+    pub fn set_locked(&self, credentials: &Credentials<ReadOp>, is_locked: bool) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if !credentials.effective_capset().contains(CapSet::IPC_LOCK) {
+            inner.permission.check_owner(credentials)?;
+        }
+        inner.is_locked = is_locked;
+        inner.ctime = now();
+        Ok(())
+    }
+
+    /// Returns the status of the segment, as `IPC_STAT` does.
+    pub fn stat(&self) -> shmid_ds {
+        let inner = self.inner.lock();
+
+        let mut shm_perm = ipc64_perm::from(&inner.permission);
+        if inner.is_removed {
+            shm_perm.mode |= SHM_DEST;
+        }
+        if inner.is_locked {
+            shm_perm.mode |= SHM_LOCKED;
+        }
+
+        shmid_ds {
+            shm_perm,
+            shm_segsz: self.size as u64,
+            shm_atime: inner.atime.as_secs() as i64,
+            shm_dtime: inner.dtime.as_secs() as i64,
+            shm_ctime: inner.ctime.as_secs() as i64,
+            shm_cpid: self.creator_pid,
+            shm_lpid: inner.last_pid,
+            shm_nattch: self.nattch() as u64,
+            __unused4: 0,
+            __unused5: 0,
+        }
+    }
+
+    fn key(&self) -> key_t {
+        self.inner.lock().permission.key()
+    }
+
+    fn is_removed(&self) -> bool {
+        self.inner.lock().is_removed
+    }
+
+    fn new(
+        id: key_t,
+        key: key_t,
+        size: usize,
+        mode: u16,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<Self> {
+        let vmo = VmoOptions::<Rights>::new(size.align_up(PAGE_SIZE)).alloc()?;
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Ok(Self {
+            id,
+            size,
+            vmo,
+            creator_pid: pid,
+            inner: SpinLock::new(ShmSegmentInner {
+                permission,
+                is_removed: false,
+                is_locked: false,
+                last_pid: 0,
+                atime: Duration::ZERO,
+                dtime: Duration::ZERO,
+                ctime: now(),
+            }),
+        })
+    }
+}
+
+/// The status of a shared memory segment, used by the `IPC_STAT` and `IPC_SET` commands.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct shmid_ds {
+    pub shm_perm: ipc64_perm,
+    pub shm_segsz: u64,
+    pub shm_atime: i64,
+    pub shm_dtime: i64,
+    pub shm_ctime: i64,
+    pub shm_cpid: Pid,
+    pub shm_lpid: Pid,
+    pub shm_nattch: u64,
+    pub __unused4: u64,
+    pub __unused5: u64,
+}
+
+/// The shared memory segments in an IPC namespace.
+pub struct ShmSegments {
+    id_allocator: SpinLock<IdAlloc>,
+    /// The segments indexed by their IDs
+    segments: RwLock<BTreeMap<key_t, Arc<ShmSegment>>>,
+}
+
+impl ShmSegments {
+    pub(in crate::ipc) fn new() -> Self {
+        Self {
+            id_allocator: SpinLock::new(IdAlloc::with_capacity(SHMMNI)),
+            segments: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the ID of the segment associated with `key`.
+    ///
+    /// A new segment is created if `key` is `IPC_PRIVATE`, or if no segment is
+    /// associated with `key` and `IPC_CREAT` is specified in `flags`.
+    pub fn get_or_create_segment(
+        &self,
+        key: key_t,
+        size: usize,
+        flags: IpcFlags,
+        mode: u16,
+        credentials: &Credentials<ReadOp>,
+        pid: Pid,
+    ) -> Result<key_t> {
+        let mut segments = self.segments.write();
+        self.destroy_removed_segments(&mut segments);
+
+        if key != IPC_PRIVATE {
+            if let Some(segment) = segments.values().find(|segment| segment.key() == key) {
+                if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+                    return_errno_with_message!(Errno::EEXIST, "the segment already exists");
+                }
+                if size > segment.size() {
+                    return_errno_with_message!(Errno::EINVAL, "the segment is too small");
+                }
+                // All the permissions in `mode` are checked against the permissions
+                // granted to the current process.
+                let required_perm =
+                    PermissionMode::from_bits_truncate(mode | mode >> 3 | mode >> 6);
+                segment.check_perm(credentials, required_perm)?;
+                return Ok(segment.id());
+            }
+
+            if !flags.contains(IpcFlags::IPC_CREAT) {
+                return_errno_with_message!(Errno::ENOENT, "the segment does not exist");
+            }
+        }
+
+        if !(SHMMIN..=SHMMAX).contains(&size) {
+            return_errno_with_message!(Errno::EINVAL, "the segment size is invalid");
+        }
+
+        let id = self
+            .id_allocator
+            .lock()
+            .alloc()
+            .ok_or(Error::new(Errno::ENOSPC))? as key_t;
+        let segment = match ShmSegment::new(id, key, size, mode, credentials, pid) {
+            Ok(segment) => segment,
+            Err(err) => {
+                self.id_allocator.lock().free(id as usize);
+                return Err(err);
+            }
+        };
+        segments.insert(id, Arc::new(segment));
+
+        Ok(id)
+    }
+
+    /// Returns the segment with `id`.
+    pub fn get_segment(&self, id: key_t) -> Result<Arc<ShmSegment>> {
+        let mut segments = self.segments.write();
+        self.destroy_removed_segments(&mut segments);
+
+        segments.get(&id).cloned().ok_or(Error::with_message(
+            Errno::EINVAL,
+            "the segment does not exist",
+        ))
+    }
+
+    /// Returns all the segments in the namespace.
+    pub fn segments(&self) -> Vec<Arc<ShmSegment>> {
+        self.segments.read().values().cloned().collect()
+    }
+
+    /// Marks the segment with `id` to be destroyed, as `IPC_RMID` does.
+    ///
+    /// The segment can no longer be found by its key. It is destroyed after it
+    /// is detached from all processes.
+    pub fn remove_segment(&self, id: key_t, credentials: &Credentials<ReadOp>) -> Result<()> {
+        let mut segments = self.segments.write();
+        let segment = segments.get(&id).ok_or(Error::with_message(
+            Errno::EINVAL,
+            "the segment does not exist",
+        ))?;
+
+        {
+            let mut inner = segment.inner.lock();
+            inner.permission.check_owner(credentials)?;
+            inner.permission.remove_key();
+            inner.is_removed = true;
+            inner.ctime = now();
+        }
+
+        self.destroy_removed_segments(&mut segments);
+        Ok(())
+    }
+
+    /// Destroys the segments that are removed and no longer attached.
+    ///
+    /// A segment can be detached without `shmdt` (e.g., by `munmap` or on
+    /// process exit), which does not notify the segment. So removed segments
+    /// are checked and destroyed lazily.
+    pub fn destroy_unused_segments(&self) {
+        self.destroy_removed_segments(&mut self.segments.write());
+    }
+
+    fn destroy_removed_segments(&self, segments: &mut BTreeMap<key_t, Arc<ShmSegment>>) {
+        segments.retain(|id, segment| {
+            if !segment.is_removed() || segment.nattch() > 0 {
+                return true;
+            }
+            self.id_allocator.lock().free(*id as usize);
+            false
+        });
+    }
+}
+
+fn now() -> Duration {
+    RealTimeCoarseClock::get().read_time()
+}
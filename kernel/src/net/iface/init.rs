@@ -1,53 +1,91 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{borrow::ToOwned, sync::Arc};
+use alloc::{borrow::ToOwned, format, sync::Arc};
 
 use aster_bigtcp::device::WithDevice;
-use ostd::sync::LocalIrqDisabled;
-use spin::Once;
+use aster_network::AnyNetworkDevice;
+use ostd::{
+    boot::{kcmdline::ModuleArg, kernel_cmdline},
+    sync::LocalIrqDisabled,
+};
 
 use super::{poll::poll_ifaces, Iface};
 use crate::{net::iface::sched::PollScheduler, prelude::*};
 
-pub static IFACES: Once<Vec<Arc<Iface>>> = Once::new();
+/// All the ifaces.
+///
+/// The first iface is the default one (i.e., the first virtio iface, or the loopback iface if
+/// there are no virtio ifaces). The ifaces created at runtime (e.g., TUN/TAP ifaces) are appended
+/// to the end, and they are removed when they are destroyed.
+pub static IFACES: RwMutex<Vec<Arc<Iface>>> = RwMutex::new(Vec::new());
 
 pub fn init() {
-    IFACES.call_once(|| {
-        let iface_virtio = new_virtio();
-        let iface_loopback = new_loopback();
-        vec![iface_virtio, iface_loopback]
-    });
-
-    for (name, _) in aster_network::all_devices() {
-        let callback = || {
-            // TODO: further check that the irq num is the same as iface's irq num
-            let iface_virtio = &IFACES.get().unwrap()[0];
-            iface_virtio.poll();
+    // Create the loopback iface first so that its index is one, as in Linux.
+    let iface_loopback = new_loopback();
+
+    let mut ifaces = Vec::new();
+    for (index, (name, device)) in virtio_devices().into_iter().enumerate() {
+        let iface = new_virtio(index, device);
+
+        let callback = {
+            let iface = iface.clone();
+            move || iface.poll()
         };
-        aster_network::register_recv_callback(&name, callback);
+        aster_network::register_recv_callback(&name, callback.clone());
         aster_network::register_send_callback(&name, callback);
+
+        ifaces.push(iface);
     }
+    ifaces.push(iface_loopback);
+
+    *IFACES.write() = ifaces;
 
     poll_ifaces();
 }
 
-fn new_virtio() -> Arc<Iface> {
+/// Returns the names and the devices of all the virtio-net devices.
+///
+/// The devices are sorted in the order in which they are initialized.
+fn virtio_devices() -> Vec<(String, NetworkDeviceRef)> {
+    use aster_virtio::device::network::DEVICE_NAME_PREFIX;
+
+    let mut devices = aster_network::all_devices()
+        .into_iter()
+        .filter_map(|(name, device)| {
+            let index = name
+                .strip_prefix(DEVICE_NAME_PREFIX)?
+                .parse::<usize>()
+                .ok()?;
+            Some((index, name, device))
+        })
+        .collect::<Vec<_>>();
+    devices.sort_by_key(|(index, _, _)| *index);
+
+    devices
+        .into_iter()
+        .map(|(_, name, device)| (name, device))
+        .collect()
+}
+
+type NetworkDeviceRef = Arc<SpinLock<dyn AnyNetworkDevice, LocalIrqDisabled>>;
+
+/// Creates the iface for the `index`-th virtio-net device.
+///
+/// Only the first iface is configured statically (see [`Ipv4Config`]). The other ifaces are left
+/// unconfigured, and their addresses and routes should be set up in the user space.
+fn new_virtio(index: usize, device: NetworkDeviceRef) -> Arc<Iface> {
     use aster_bigtcp::{
-        iface::EtherIface,
+        iface::{EtherIface, InterfaceFlags, Ipv4Route},
         wire::{EthernetAddress, Ipv4Address, Ipv4Cidr},
     };
-    use aster_network::AnyNetworkDevice;
-    use aster_virtio::device::network::DEVICE_NAME;
 
     const VIRTIO_ADDRESS: Ipv4Address = Ipv4Address::new(10, 0, 2, 15);
     const VIRTIO_ADDRESS_PREFIX_LEN: u8 = 24; // mask: 255.255.255.0
     const VIRTIO_GATEWAY: Ipv4Address = Ipv4Address::new(10, 0, 2, 2);
 
-    let virtio_net = aster_network::get_device(DEVICE_NAME).unwrap();
-
-    let ether_addr = virtio_net.lock().mac_addr().0;
+    let ether_addr = device.lock().mac_addr().0;
 
-    struct Wrapper(Arc<SpinLock<dyn AnyNetworkDevice, LocalIrqDisabled>>);
+    struct Wrapper(NetworkDeviceRef);
 
     impl WithDevice for Wrapper {
         type Device = dyn AnyNetworkDevice;
@@ -61,25 +99,89 @@ fn new_virtio() -> Arc<Iface> {
         }
     }
 
-    EtherIface::new(
-        Wrapper(virtio_net),
+    let iface = EtherIface::new(
+        Wrapper(device),
         EthernetAddress(ether_addr),
-        Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN),
-        VIRTIO_GATEWAY,
-        "virtio".to_owned(),
+        format!("virtio{}", index),
         PollScheduler::new(),
-    )
+        InterfaceFlags::UP
+            | InterfaceFlags::BROADCAST
+            | InterfaceFlags::RUNNING
+            | InterfaceFlags::MULTICAST
+            | InterfaceFlags::LOWER_UP,
+    ) as Arc<Iface>;
+
+    if index != 0 {
+        return iface;
+    }
+
+    match ipv4_config() {
+        Ipv4Config::Static => {
+            iface
+                .add_ipv4_cidr(Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN))
+                .unwrap();
+            iface
+                .add_ipv4_route(Ipv4Route {
+                    cidr: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+                    gateway: VIRTIO_GATEWAY,
+                })
+                .unwrap();
+        }
+        Ipv4Config::Dhcp => {
+            info!("[Network] The virtio iface is left unconfigured for DHCP clients");
+        }
+    }
+
+    iface
+}
+
+/// The way to configure the IPv4 address of the first virtio iface at boot.
+enum Ipv4Config {
+    /// The address is the one assigned by QEMU's user networking (i.e., SLIRP).
+    Static,
+    /// The address is left unconfigured, and should be obtained by a DHCP client (e.g., BusyBox's
+    /// `udhcpc`) in the user space.
+    Dhcp,
+}
+
+/// Parses the way to configure the IPv4 address from the kernel command line.
+///
+/// The way is specified by `net.ip=static` or `net.ip=dhcp`. The default is `static`.
+fn ipv4_config() -> Ipv4Config {
+    let value = kernel_cmdline()
+        .get_module_args("net")
+        .and_then(|module_args| {
+            module_args.iter().find_map(|arg| match arg {
+                ModuleArg::KeyVal(name, value) if name.as_bytes() == b"ip" => Some(value),
+                _ => None,
+            })
+        })
+        .map(|value| value.to_string_lossy());
+
+    match value.as_deref() {
+        None | Some("static") => Ipv4Config::Static,
+        Some("dhcp") => Ipv4Config::Dhcp,
+        Some(value) => {
+            warn!(
+                "[Network] Unknown IPv4 configuration `{}`, using `static`",
+                value
+            );
+            Ipv4Config::Static
+        }
+    }
 }
 
 fn new_loopback() -> Arc<Iface> {
     use aster_bigtcp::{
         device::{Loopback, Medium},
-        iface::IpIface,
-        wire::{Ipv4Address, Ipv4Cidr},
+        iface::{InterfaceFlags, InterfaceType, IpIface},
+        wire::{IpCidr, Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr},
     };
 
     const LOOPBACK_ADDRESS: Ipv4Address = Ipv4Address::new(127, 0, 0, 1);
     const LOOPBACK_ADDRESS_PREFIX_LEN: u8 = 8; // mask: 255.0.0.0
+    const LOOPBACK_ADDRESS_V6: Ipv6Address = Ipv6Address::LOCALHOST;
+    const LOOPBACK_ADDRESS_V6_PREFIX_LEN: u8 = 128;
 
     struct Wrapper(Mutex<Loopback>);
 
@@ -97,8 +199,19 @@ fn new_loopback() -> Arc<Iface> {
 
     IpIface::new(
         Wrapper(Mutex::new(Loopback::new(Medium::Ip))),
-        Ipv4Cidr::new(LOOPBACK_ADDRESS, LOOPBACK_ADDRESS_PREFIX_LEN),
+        &[
+            IpCidr::Ipv4(Ipv4Cidr::new(LOOPBACK_ADDRESS, LOOPBACK_ADDRESS_PREFIX_LEN)),
+            IpCidr::Ipv6(Ipv6Cidr::new(
+                LOOPBACK_ADDRESS_V6,
+                LOOPBACK_ADDRESS_V6_PREFIX_LEN,
+            )),
+        ],
         "lo".to_owned(),
         PollScheduler::new(),
+        InterfaceType::Loopback,
+        InterfaceFlags::UP
+            | InterfaceFlags::LOOPBACK
+            | InterfaceFlags::RUNNING
+            | InterfaceFlags::LOWER_UP,
     ) as _
 }
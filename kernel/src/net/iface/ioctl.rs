@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `ioctl` commands that query and configure ifaces.
+//!
+//! These commands are issued on sockets of any type, and are used by legacy tools (e.g.,
+//! BusyBox's `ifconfig`). New tools use rtnetlink sockets instead.
+//!
+//! See <https://www.man7.org/linux/man-pages/man7/netdevice.7.html>.
+
+use aster_bigtcp::{
+    iface::InterfaceFlags,
+    wire::{Ipv4Address, Ipv4Cidr},
+};
+
+use super::{check_config_permission, iface_by_index, iface_by_name, Iface, IFACES};
+use crate::{
+    fs::utils::IoctlCmd,
+    prelude::*,
+    util::net::{CSocketAddrFamily, CSocketAddrInet},
+};
+
+/// The maximum length of iface names, including the trailing NUL.
+pub(super) const IFNAMSIZ: usize = 16;
+
+/// The request of most iface `ioctl` commands (`struct ifreq`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CIfReq {
+    name: [u8; IFNAMSIZ],
+    /// The union of the command-specific data.
+    data: [u8; 24],
+}
+
+impl CIfReq {
+    pub(super) fn new(name: &str) -> Self {
+        let mut req = Self::new_zeroed();
+        // The name of an iface is always shorter than `IFNAMSIZ`.
+        req.name[..name.len()].copy_from_slice(name.as_bytes());
+        req
+    }
+
+    /// Returns the iface name in the request, or `None` if the name is not valid UTF-8.
+    pub(super) fn name(&self) -> Option<&str> {
+        let len = self
+            .name
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(IFNAMSIZ);
+        core::str::from_utf8(&self.name[..len]).ok()
+    }
+
+    fn iface(&self) -> Result<Arc<Iface>> {
+        self.name()
+            .and_then(iface_by_name)
+            .ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))
+    }
+
+    pub(super) fn data<T: Pod>(&self) -> T {
+        T::from_bytes(&self.data)
+    }
+
+    pub(super) fn set_data<T: Pod>(&mut self, value: &T) {
+        self.data[..size_of::<T>()].copy_from_slice(value.as_bytes());
+    }
+
+    /// Returns the IPv4 address in the request.
+    fn ipv4_addr(&self) -> Result<Ipv4Address> {
+        if self.data::<u16>() != CSocketAddrFamily::AF_INET as u16 {
+            return_errno_with_message!(Errno::EINVAL, "the address is not an IPv4 address");
+        }
+        let (addr, _) = self.data::<CSocketAddrInet>().into();
+        Ok(addr)
+    }
+
+    fn set_ipv4_addr(&mut self, addr: Ipv4Address) {
+        self.set_data(&CSocketAddrInet::from((addr, 0)));
+    }
+}
+
+/// The request of `SIOCGIFCONF` (`struct ifconf`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIfConf {
+    /// The length of the buffer.
+    len: i32,
+    pad: u32,
+    /// The address of the buffer, which is an array of [`CIfReq`].
+    buf: Vaddr,
+}
+
+/// The hardware address reported by `SIOCGIFHWADDR` (`struct sockaddr`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CHardwareAddr {
+    /// The hardware type.
+    family: u16,
+    data: [u8; 14],
+}
+
+/// Handles the iface `ioctl` commands.
+pub fn iface_ioctl(cmd: IoctlCmd, arg: Vaddr, ctx: &Context) -> Result<i32> {
+    let user_space = ctx.user_space();
+
+    if let IoctlCmd::SIOCGIFCONF = cmd {
+        let mut conf = user_space.read_val::<CIfConf>(arg)?;
+        conf.len = get_conf(&conf, ctx)?;
+        user_space.write_val(arg, &conf)?;
+        return Ok(0);
+    }
+
+    let mut req = user_space.read_val::<CIfReq>(arg)?;
+
+    match cmd {
+        IoctlCmd::SIOCGIFNAME => {
+            let index = req.data::<i32>();
+            let iface = u32::try_from(index)
+                .ok()
+                .and_then(iface_by_index)
+                .ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))?;
+            req = CIfReq::new(iface.name());
+            req.set_data(&index);
+        }
+        IoctlCmd::SIOCGIFINDEX => {
+            let iface = req.iface()?;
+            req.set_data(&(iface.index() as i32));
+        }
+        IoctlCmd::SIOCGIFFLAGS => {
+            let iface = req.iface()?;
+            // The flags are truncated to a `short`, as in Linux.
+            req.set_data(&(iface.flags().bits() as u16));
+        }
+        IoctlCmd::SIOCSIFFLAGS => {
+            check_config_permission()?;
+            let iface = req.iface()?;
+            let flags = InterfaceFlags::from_bits_truncate(req.data::<u16>() as u32);
+            // Only the `UP` flag can be changed, and the other flags are ignored.
+            let is_up = flags.contains(InterfaceFlags::UP);
+            if is_up != iface.flags().contains(InterfaceFlags::UP) {
+                iface.set_up(is_up);
+                // Transmit the packets that are queued while the iface is down.
+                iface.poll();
+            }
+            return Ok(0);
+        }
+        IoctlCmd::SIOCGIFADDR => {
+            let cidr = primary_cidr(&req.iface()?)?;
+            req.set_ipv4_addr(cidr.address());
+        }
+        IoctlCmd::SIOCSIFADDR => {
+            check_config_permission()?;
+            let iface = req.iface()?;
+            set_addr(&iface, req.ipv4_addr()?)?;
+            return Ok(0);
+        }
+        IoctlCmd::SIOCGIFBRDADDR => {
+            let cidr = primary_cidr(&req.iface()?)?;
+            req.set_ipv4_addr(cidr.broadcast().unwrap_or(Ipv4Address::UNSPECIFIED));
+        }
+        IoctlCmd::SIOCGIFNETMASK => {
+            let cidr = primary_cidr(&req.iface()?)?;
+            req.set_ipv4_addr(cidr.netmask());
+        }
+        IoctlCmd::SIOCSIFNETMASK => {
+            check_config_permission()?;
+            let iface = req.iface()?;
+            set_netmask(&iface, req.ipv4_addr()?)?;
+            return Ok(0);
+        }
+        IoctlCmd::SIOCGIFMTU => {
+            let iface = req.iface()?;
+            req.set_data(&(iface.mtu() as i32));
+        }
+        IoctlCmd::SIOCGIFHWADDR => {
+            let iface = req.iface()?;
+            let mut hardware_addr = CHardwareAddr {
+                family: iface.type_() as u16,
+                data: [0; 14],
+            };
+            if let Some(ether_addr) = iface.hardware_addr() {
+                hardware_addr.data[..6].copy_from_slice(&ether_addr.0);
+            }
+            req.set_data(&hardware_addr);
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the ioctl command is not an iface command"),
+    }
+
+    user_space.write_val(arg, &req)?;
+    Ok(0)
+}
+
+/// Lists the IPv4 addresses of the ifaces in the buffer of `SIOCGIFCONF`.
+///
+/// This method returns the length of the written entries. If the buffer is null, nothing is
+/// written, and the length of all the entries is returned.
+fn get_conf(conf: &CIfConf, ctx: &Context) -> Result<i32> {
+    let mut ifaces = IFACES.read().clone();
+    ifaces.sort_by_key(|iface| iface.index());
+
+    let reqs = ifaces.iter().flat_map(|iface| {
+        iface.ipv4_cidrs().into_iter().map(|cidr| {
+            let mut req = CIfReq::new(iface.name());
+            req.set_ipv4_addr(cidr.address());
+            req
+        })
+    });
+
+    if conf.buf == 0 {
+        return Ok((reqs.count() * size_of::<CIfReq>()) as i32);
+    }
+
+    let max_reqs = conf.len.max(0) as usize / size_of::<CIfReq>();
+    let mut len = 0;
+    for req in reqs.take(max_reqs) {
+        ctx.user_space().write_val(conf.buf + len, &req)?;
+        len += size_of::<CIfReq>();
+    }
+
+    Ok(len as i32)
+}
+
+fn primary_cidr(iface: &Iface) -> Result<Ipv4Cidr> {
+    iface
+        .ipv4_cidrs()
+        .first()
+        .copied()
+        .ok_or_else(|| Error::with_message(Errno::EADDRNOTAVAIL, "the iface has no IPv4 address"))
+}
+
+/// Replaces the primary IPv4 address of the iface.
+///
+/// Like Linux, the prefix length is reset to the one of the address class. Setting the address to
+/// `0.0.0.0` removes the primary address.
+fn set_addr(iface: &Iface, addr: Ipv4Address) -> Result<()> {
+    if addr.is_unspecified() {
+        if let Ok(old_cidr) = primary_cidr(iface) {
+            // The address is the primary one, so the removal cannot fail.
+            iface.remove_ipv4_cidr(old_cidr.address()).unwrap();
+        }
+        return Ok(());
+    }
+
+    let prefix_len = match addr.octets()[0] {
+        0..=127 => 8,
+        128..=191 => 16,
+        192..=223 => 24,
+        _ => return_errno_with_message!(Errno::EINVAL, "the address is not a unicast address"),
+    };
+
+    replace_primary_cidr(iface, Ipv4Cidr::new(addr, prefix_len))
+}
+
+/// Changes the prefix length of the primary IPv4 address of the iface.
+fn set_netmask(iface: &Iface, netmask: Ipv4Address) -> Result<()> {
+    let old_cidr = primary_cidr(iface)?;
+    let new_cidr = Ipv4Cidr::from_netmask(old_cidr.address(), netmask)
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the network mask is invalid"))?;
+
+    replace_primary_cidr(iface, new_cidr)
+}
+
+fn replace_primary_cidr(iface: &Iface, new_cidr: Ipv4Cidr) -> Result<()> {
+    if let Ok(old_cidr) = primary_cidr(iface) {
+        // The address is the primary one, so the removal cannot fail.
+        iface.remove_ipv4_cidr(old_cidr.address()).unwrap();
+    }
+    // The new address may be one of the secondary addresses.
+    let _ = iface.remove_ipv4_cidr(new_cidr.address());
+
+    iface
+        .add_ipv4_cidr(new_cidr)
+        .map_err(|_| Error::with_message(Errno::ENOSPC, "there are too many addresses"))
+}
@@ -2,11 +2,22 @@
 
 mod ext;
 mod init;
+mod ioctl;
 mod poll;
+mod route;
 mod sched;
+mod tun;
 
 pub use init::{init, IFACES};
+pub use ioctl::iface_ioctl;
 pub use poll::lazy_init;
+pub use route::{find_ipv4_route, lookup_route, Route};
+pub use tun::TunFile;
+
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+};
 
 pub type Iface = dyn aster_bigtcp::iface::Iface<ext::BigtcpExt>;
 pub type BoundPort = aster_bigtcp::iface::BoundPort<ext::BigtcpExt>;
@@ -14,3 +25,37 @@ pub type BoundPort = aster_bigtcp::iface::BoundPort<ext::BigtcpExt>;
 pub type TcpConnection = aster_bigtcp::socket::TcpConnection<ext::BigtcpExt>;
 pub type TcpListener = aster_bigtcp::socket::TcpListener<ext::BigtcpExt>;
 pub type UdpSocket = aster_bigtcp::socket::UdpSocket<ext::BigtcpExt>;
+pub type PacketSocket = aster_bigtcp::socket::PacketSocket<ext::BigtcpExt>;
+pub type RawSocket = aster_bigtcp::socket::RawSocket<ext::BigtcpExt>;
+
+/// Finds the iface with the index.
+pub fn iface_by_index(index: u32) -> Option<Arc<Iface>> {
+    IFACES
+        .read()
+        .iter()
+        .find(|iface| iface.index() == index)
+        .cloned()
+}
+
+/// Finds the iface with the name.
+pub fn iface_by_name(name: &str) -> Option<Arc<Iface>> {
+    IFACES
+        .read()
+        .iter()
+        .find(|iface| iface.name() == name)
+        .cloned()
+}
+
+/// Checks whether the current thread is allowed to change the configuration of the ifaces.
+///
+/// Like Linux, this requires the `CAP_NET_ADMIN` capability.
+pub fn check_config_permission() -> Result<()> {
+    let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+    if !credentials.effective_capset().contains(CapSet::NET_ADMIN) {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "changing the iface configuration requires CAP_NET_ADMIN"
+        );
+    }
+    Ok(())
+}
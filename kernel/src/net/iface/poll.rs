@@ -10,20 +10,23 @@ use super::{Iface, IFACES};
 use crate::{sched::priority::Priority, thread::kernel_thread::ThreadOptions, WaitTimeout};
 
 pub fn lazy_init() {
-    for iface in IFACES.get().unwrap() {
+    for iface in IFACES.read().iter() {
         spawn_background_poll_thread(iface.clone());
     }
 }
 
 pub(super) fn poll_ifaces() {
-    let ifaces = IFACES.get().unwrap();
+    let ifaces = IFACES.read();
 
     for iface in ifaces.iter() {
         iface.poll();
     }
 }
 
-fn spawn_background_poll_thread(iface: Arc<Iface>) {
+/// Spawns a thread that polls the iface in the background.
+///
+/// The thread exits after the poll scheduler of the iface is stopped.
+pub(super) fn spawn_background_poll_thread(iface: Arc<Iface>) {
     let task_fn = move || {
         trace!("spawn background poll thread for {}", iface.name());
 
@@ -34,9 +37,23 @@ fn spawn_background_poll_thread(iface: Arc<Iface>) {
             let next_poll_at_ms = if let Some(next_poll_at_ms) = sched_poll.next_poll_at_ms() {
                 next_poll_at_ms
             } else {
-                wait_queue.wait_until(|| sched_poll.next_poll_at_ms())
+                let next_poll_at_ms = wait_queue.wait_until(|| {
+                    if sched_poll.is_stopped() {
+                        Some(None)
+                    } else {
+                        sched_poll.next_poll_at_ms().map(Some)
+                    }
+                });
+                match next_poll_at_ms {
+                    Some(next_poll_at_ms) => next_poll_at_ms,
+                    None => break,
+                }
             };
 
+            if sched_poll.is_stopped() {
+                break;
+            }
+
             let now_as_ms = Jiffies::elapsed().as_duration().as_millis() as u64;
 
             // FIXME: Ideally, we should perform the `poll` just before `next_poll_at_ms`.
@@ -54,9 +71,12 @@ fn spawn_background_poll_thread(iface: Arc<Iface>) {
 
             let duration = Duration::from_millis(next_poll_at_ms - now_as_ms);
             let _ = wait_queue.wait_until_or_timeout(
-                // If `sched_poll.next_poll_at_ms()` changes to an earlier time, we will end the
-                // waiting.
-                || (sched_poll.next_poll_at_ms()? < next_poll_at_ms).then_some(()),
+                // If `sched_poll.next_poll_at_ms()` changes to an earlier time or the scheduler is
+                // stopped, we will end the waiting.
+                || {
+                    (sched_poll.is_stopped() || sched_poll.next_poll_at_ms()? < next_poll_at_ms)
+                        .then_some(())
+                },
                 &duration,
             );
         }
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The routing table.
+//!
+//! There is only one routing table, which is shared by all ifaces. It contains the routes to the
+//! networks that the ifaces are directly connected to, which are derived from the IP addresses of
+//! the ifaces, and the routes through gateways, which are added by the user. The routes through
+//! gateways are stored in the ifaces that they go through, so that the ifaces can find the next
+//! hops when transmitting packets.
+
+use aster_bigtcp::{
+    iface::{InterfaceFlags, Ipv4Route},
+    wire::{IpAddress, IpCidr, Ipv4Cidr},
+};
+
+use super::{Iface, IFACES};
+use crate::prelude::*;
+
+/// The result of looking up the routing table.
+pub struct Route {
+    /// The iface that the packets go through.
+    pub iface: Arc<Iface>,
+    /// The gateway that the packets are sent to, or `None` if the destination is directly
+    /// connected to the iface.
+    pub gateway: Option<IpAddress>,
+}
+
+/// Looks up the route that the packets to the destination go through.
+///
+/// The route with the longest prefix wins. If there is a tie, the route to a directly connected
+/// network wins, and then the route through the iface that comes first in [`IFACES`] wins.
+///
+/// Like Linux, the routes through the ifaces that are down are ignored.
+pub fn lookup_route(dst: &IpAddress) -> Option<Route> {
+    // The candidates are ordered by (prefix length, whether it is directly connected).
+    let mut best: Option<((u8, bool), Route)> = None;
+
+    for iface in IFACES.read().iter() {
+        if !iface.flags().contains(InterfaceFlags::UP) {
+            continue;
+        }
+
+        for (cidr, gateway) in iface_routes(iface) {
+            if !cidr.contains_addr(dst) {
+                continue;
+            }
+
+            let key = (cidr.prefix_len(), gateway.is_none());
+            if best.as_ref().is_some_and(|(best_key, _)| *best_key >= key) {
+                continue;
+            }
+            best = Some((
+                key,
+                Route {
+                    iface: iface.clone(),
+                    gateway,
+                },
+            ));
+        }
+    }
+
+    best.map(|(_, route)| route)
+}
+
+/// Finds the IPv4 route through a gateway to the destination network.
+pub fn find_ipv4_route(cidr: Ipv4Cidr) -> Option<(Arc<Iface>, Ipv4Route)> {
+    IFACES.read().iter().find_map(|iface| {
+        iface
+            .ipv4_routes()
+            .into_iter()
+            .find(|route| route.cidr == cidr)
+            .map(|route| (iface.clone(), route))
+    })
+}
+
+/// Returns all the routes through the iface, together with their gateways.
+fn iface_routes(iface: &Iface) -> impl Iterator<Item = (IpCidr, Option<IpAddress>)> {
+    let ipv4_connected = iface
+        .ipv4_cidrs()
+        .into_iter()
+        .map(|cidr| (IpCidr::Ipv4(cidr), None));
+    let ipv6_connected = iface
+        .ipv6_cidrs()
+        .into_iter()
+        .map(|cidr| (IpCidr::Ipv6(cidr), None));
+    let ipv4_routes = iface.ipv4_routes().into_iter().map(|route| {
+        (
+            IpCidr::Ipv4(route.cidr),
+            Some(IpAddress::Ipv4(route.gateway)),
+        )
+    });
+
+    ipv4_connected.chain(ipv6_connected).chain(ipv4_routes)
+}
@@ -0,0 +1,560 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! TUN/TAP ifaces.
+//!
+//! A TUN/TAP iface is created by opening `/dev/net/tun` and issuing `TUNSETIFF` on the file. The
+//! packets transmitted by the iface can be read from the file, and the packets written to the
+//! file are received by the iface. A TUN iface works with IP packets, while a TAP iface works with
+//! Ethernet frames.
+//!
+//! Like Linux, the iface is destroyed when the file is closed, unless it is made persistent. We do
+//! not support persistent ifaces yet.
+//!
+//! See <https://www.kernel.org/doc/html/latest/networking/tuntap.html>.
+
+use alloc::format;
+
+use aster_bigtcp::{
+    device::{self, DeviceCapabilities, Medium, NotifyDevice, WithDevice},
+    iface::{EtherIface, InterfaceFlags, InterfaceType, IpIface},
+    time::Instant,
+    wire::{EthernetAddress, EthernetProtocol},
+};
+
+use super::{
+    check_config_permission,
+    init::IFACES,
+    ioctl::{CIfReq, IFNAMSIZ},
+    poll::spawn_background_poll_thread,
+    sched::PollScheduler,
+    Iface,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        inode_handle::FileIo,
+        utils::{IoctlCmd, StatusFlags},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    util::random::getrandom,
+};
+
+/// A file opened from `/dev/net/tun`.
+pub struct TunFile {
+    tun: Mutex<Option<Arc<Tun>>>,
+    pollee: Pollee,
+}
+
+impl TunFile {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            tun: Mutex::new(None),
+            pollee: Pollee::new(),
+        })
+    }
+
+    fn tun(&self) -> Result<Arc<Tun>> {
+        self.tun
+            .lock()
+            .clone()
+            .ok_or_else(|| Error::with_message(Errno::EBADFD, "no iface is attached to the file"))
+    }
+
+    /// Creates an iface and attaches it to the file.
+    ///
+    /// The name of the created iface is written back to the request.
+    fn set_iff(&self, req: &mut CIfReq) -> Result<()> {
+        let mut tun = self.tun.lock();
+        if tun.is_some() {
+            return_errno_with_message!(Errno::EEXIST, "an iface has been attached to the file");
+        }
+
+        check_config_permission()?;
+
+        // Like Linux, unknown flags are ignored.
+        let flags = TunFlags::from_bits_truncate(req.data::<u16>());
+        if !TunFlags::SUPPORTED.contains(flags) {
+            return_errno_with_message!(Errno::EINVAL, "the flags are not supported");
+        }
+        // Like Linux, `IFF_TUN` takes precedence over `IFF_TAP`.
+        let (kind, flags) = if flags.contains(TunFlags::IFF_TUN) {
+            (TunKind::Tun, flags - TunFlags::IFF_TAP)
+        } else if flags.contains(TunFlags::IFF_TAP) {
+            (TunKind::Tap, flags)
+        } else {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "one of `IFF_TUN` and `IFF_TAP` must be specified"
+            );
+        };
+
+        let template = match req.name() {
+            Some("") => kind.default_name(),
+            Some(name) => name,
+            None => return_errno_with_message!(Errno::EINVAL, "the iface name is invalid"),
+        };
+
+        let phy = Arc::new(Mutex::new(TunPhy::new(kind, self.pollee.clone())));
+
+        let iface = {
+            // Hold the lock until the iface is registered, so no one can take the same name.
+            let mut ifaces = IFACES.write();
+            let name = alloc_name(&ifaces, template)?;
+            let iface = new_iface(name, kind, phy.clone())?;
+            ifaces.push(iface.clone());
+            iface
+        };
+        spawn_background_poll_thread(iface.clone());
+
+        // `IFF_TUN_EXCL` only takes effect when creating the iface, so it is not recorded.
+        let flags = flags - TunFlags::IFF_TUN_EXCL;
+
+        *req = CIfReq::new(iface.name());
+        req.set_data(&flags.bits());
+
+        *tun = Some(Arc::new(Tun {
+            iface,
+            phy,
+            kind,
+            flags,
+        }));
+        self.pollee.notify(IoEvents::OUT);
+
+        Ok(())
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let Some(tun) = self.tun.lock().clone() else {
+            return IoEvents::ERR;
+        };
+
+        if tun.phy.lock().tx_queue.is_empty() {
+            IoEvents::OUT
+        } else {
+            IoEvents::IN | IoEvents::OUT
+        }
+    }
+}
+
+impl Pollable for TunFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileIo for TunFile {
+    fn read(&self, writer: &mut VmWriter, status_flags: StatusFlags) -> Result<usize> {
+        let tun = self.tun()?;
+
+        if status_flags.contains(StatusFlags::O_NONBLOCK) {
+            tun.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || tun.try_read(writer))
+        }
+    }
+
+    fn write(&self, reader: &mut VmReader, _status_flags: StatusFlags) -> Result<usize> {
+        self.tun()?.write(reader)
+    }
+
+    fn ioctl(&self, cmd: IoctlCmd, arg: usize) -> Result<i32> {
+        match cmd {
+            IoctlCmd::TUNSETIFF => {
+                let mut req = current_userspace!().read_val::<CIfReq>(arg)?;
+                self.set_iff(&mut req)?;
+                current_userspace!().write_val(arg, &req)?;
+            }
+            IoctlCmd::TUNGETIFF => {
+                let tun = self.tun()?;
+                let mut req = CIfReq::new(tun.iface.name());
+                req.set_data(&tun.flags.bits());
+                current_userspace!().write_val(arg, &req)?;
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "the ioctl command is unknown"),
+        }
+
+        Ok(0)
+    }
+}
+
+/// A TUN/TAP iface that is attached to a file.
+struct Tun {
+    iface: Arc<Iface>,
+    phy: Arc<Mutex<TunPhy>>,
+    kind: TunKind,
+    flags: TunFlags,
+}
+
+/// The packet information (`struct tun_pi`).
+///
+/// Unless `IFF_NO_PI` is specified, this is prepended to every packet read from or written to
+/// the file.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CTunPi {
+    flags: u16,
+    /// The link-layer protocol of the packet in network byte order.
+    proto: u16,
+}
+
+/// The flag in [`CTunPi`] indicating that the packet is truncated because the buffer is too small.
+const TUN_PKT_STRIP: u16 = 0x0001;
+
+/// The maximum length of packets written to the file.
+///
+/// This is the length of the largest IP packet plus the length of the Ethernet header.
+const MAX_PACKET_LEN: usize = u16::MAX as usize + ETHER_HEADER_LEN;
+
+const ETHER_HEADER_LEN: usize = 14;
+
+impl Tun {
+    fn has_pi(&self) -> bool {
+        !self.flags.contains(TunFlags::IFF_NO_PI)
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let pi_len = if self.has_pi() {
+            size_of::<CTunPi>()
+        } else {
+            0
+        };
+
+        let mut phy = self.phy.lock();
+        let packet = phy.tx_queue.pop_front();
+        phy.pollee.invalidate();
+        drop(phy);
+
+        let Some(packet) = packet else {
+            return_errno_with_message!(Errno::EAGAIN, "no packets are available");
+        };
+
+        // Like Linux, the packet is dropped if the buffer is too small.
+        if writer.avail() < pi_len {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is too small");
+        }
+
+        // Like datagram sockets, the rest of a truncated packet is discarded.
+        let packet_len = packet.len().min(writer.avail() - pi_len);
+
+        if self.has_pi() {
+            let pi = CTunPi {
+                flags: if packet_len < packet.len() {
+                    TUN_PKT_STRIP
+                } else {
+                    0
+                },
+                proto: self.kind.protocol(&packet).to_be(),
+            };
+            writer.write_val(&pi)?;
+        }
+        writer.write_fallible(&mut packet[..packet_len].into())?;
+
+        Ok(pi_len + packet_len)
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        let len = reader.remain();
+
+        if self.has_pi() {
+            if len < size_of::<CTunPi>() {
+                return_errno_with_message!(Errno::EINVAL, "the packet information is missing");
+            }
+            // TODO: Use the protocol in the packet information. Currently, the protocol is
+            // determined by the packet itself.
+            reader.read_val::<CTunPi>()?;
+        }
+
+        if reader.remain() > MAX_PACKET_LEN {
+            return_errno_with_message!(Errno::EINVAL, "the packet is too large");
+        }
+        let packet = reader.collect()?;
+
+        match self.kind {
+            TunKind::Tun if !self.has_pi() && self.kind.protocol(&packet) == 0 => {
+                return_errno_with_message!(Errno::EINVAL, "the packet is not an IP packet");
+            }
+            TunKind::Tap if packet.len() < ETHER_HEADER_LEN => {
+                return_errno_with_message!(Errno::EINVAL, "the Ethernet frame is too short");
+            }
+            _ => (),
+        }
+
+        if !self.iface.flags().contains(InterfaceFlags::UP) {
+            return_errno_with_message!(Errno::EIO, "the iface is down");
+        }
+
+        self.phy.lock().rx_queue.push_back(packet);
+        self.iface.poll();
+
+        Ok(len)
+    }
+}
+
+impl Drop for Tun {
+    fn drop(&mut self) {
+        let index = self.iface.index();
+        IFACES.write().retain(|iface| iface.index() != index);
+
+        // Sockets may still hold the iface. Bringing it down ensures that they cannot send or
+        // receive packets through it anymore.
+        self.iface.set_up(false);
+        self.iface.sched_poll().stop();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TunKind {
+    /// A TUN iface, which works with IP packets.
+    Tun,
+    /// A TAP iface, which works with Ethernet frames.
+    Tap,
+}
+
+impl TunKind {
+    fn default_name(self) -> &'static str {
+        match self {
+            Self::Tun => "tun%d",
+            Self::Tap => "tap%d",
+        }
+    }
+
+    fn medium(self) -> Medium {
+        match self {
+            Self::Tun => Medium::Ip,
+            Self::Tap => Medium::Ethernet,
+        }
+    }
+
+    /// Returns the link-layer protocol of the packet, or zero if it is unknown.
+    fn protocol(self, packet: &[u8]) -> u16 {
+        match self {
+            Self::Tun => match packet.first().map(|byte| byte >> 4) {
+                Some(4) => EthernetProtocol::Ipv4.into(),
+                Some(6) => EthernetProtocol::Ipv6.into(),
+                _ => 0,
+            },
+            Self::Tap => packet
+                .get(12..ETHER_HEADER_LEN)
+                .map_or(0, |bytes| u16::from_be_bytes([bytes[0], bytes[1]])),
+        }
+    }
+}
+
+bitflags! {
+    /// The flags of TUN/TAP ifaces, which are specified in `TUNSETIFF`.
+    ///
+    /// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/if_tun.h>.
+    struct TunFlags: u16 {
+        const IFF_TUN         = 0x0001;
+        const IFF_TAP         = 0x0002;
+        const IFF_NAPI        = 0x0010;
+        const IFF_NAPI_FRAGS  = 0x0020;
+        const IFF_NO_CARRIER  = 0x0040;
+        const IFF_MULTI_QUEUE = 0x0100;
+        const IFF_NO_PI       = 0x1000;
+        /// This flag is obsolete and ignored.
+        const IFF_ONE_QUEUE   = 0x2000;
+        const IFF_VNET_HDR    = 0x4000;
+        const IFF_TUN_EXCL    = 0x8000;
+    }
+}
+
+impl TunFlags {
+    const SUPPORTED: Self = Self::IFF_TUN
+        .union(Self::IFF_TAP)
+        .union(Self::IFF_NO_PI)
+        .union(Self::IFF_ONE_QUEUE)
+        .union(Self::IFF_TUN_EXCL);
+}
+
+/// Allocates an iface name from the template.
+///
+/// Like Linux, if the template contains `%d`, it is replaced by the smallest number that makes the
+/// name unique.
+fn alloc_name(ifaces: &[Arc<Iface>], template: &str) -> Result<String> {
+    let is_used = |name: &str| ifaces.iter().any(|iface| iface.name() == name);
+
+    let Some((prefix, suffix)) = template.split_once('%') else {
+        check_name(template)?;
+        if is_used(template) {
+            // TUN/TAP ifaces are not persistent, so an existing iface is always in use.
+            return_errno_with_message!(Errno::EBUSY, "the iface already exists");
+        }
+        return Ok(template.to_string());
+    };
+
+    let Some(suffix) = suffix
+        .strip_prefix('d')
+        .filter(|suffix| !suffix.contains('%'))
+    else {
+        return_errno_with_message!(Errno::EINVAL, "the iface name template is invalid");
+    };
+
+    for i in 0u32.. {
+        let name = format!("{}{}{}", prefix, i, suffix);
+        if name.len() >= IFNAMSIZ {
+            break;
+        }
+        if !is_used(&name) {
+            check_name(&name)?;
+            return Ok(name);
+        }
+    }
+
+    return_errno_with_message!(Errno::ENFILE, "no iface names are available");
+}
+
+/// Checks whether the iface name is valid, like `dev_valid_name` in Linux.
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() >= IFNAMSIZ
+        || name == "."
+        || name == ".."
+        || name
+            .chars()
+            .any(|c| c == '/' || c == ':' || c.is_whitespace())
+    {
+        return_errno_with_message!(Errno::EINVAL, "the iface name is invalid");
+    }
+
+    Ok(())
+}
+
+fn new_iface(name: String, kind: TunKind, phy: Arc<Mutex<TunPhy>>) -> Result<Arc<Iface>> {
+    struct Wrapper(Arc<Mutex<TunPhy>>);
+
+    impl WithDevice for Wrapper {
+        type Device = TunPhy;
+
+        fn with<F, R>(&self, f: F) -> R
+        where
+            F: FnOnce(&mut Self::Device) -> R,
+        {
+            let mut phy = self.0.lock();
+            f(&mut phy)
+        }
+    }
+
+    let iface = match kind {
+        // Like Linux, TUN ifaces have no link-layer addresses and use no ARP.
+        TunKind::Tun => IpIface::new(
+            Wrapper(phy),
+            &[],
+            name,
+            PollScheduler::new(),
+            InterfaceType::None,
+            InterfaceFlags::POINTOPOINT | InterfaceFlags::NOARP | InterfaceFlags::MULTICAST,
+        ) as Arc<Iface>,
+        TunKind::Tap => {
+            // Like Linux, TAP ifaces have random, unicast, and locally administered Ethernet
+            // addresses.
+            let mut ether_addr = [0u8; 6];
+            getrandom(&mut ether_addr)?;
+            ether_addr[0] = (ether_addr[0] & !0x01) | 0x02;
+
+            EtherIface::new(
+                Wrapper(phy),
+                EthernetAddress(ether_addr),
+                name,
+                PollScheduler::new(),
+                InterfaceFlags::BROADCAST | InterfaceFlags::MULTICAST,
+            ) as Arc<Iface>
+        }
+    };
+
+    Ok(iface)
+}
+
+/// The physical device of a TUN/TAP iface.
+///
+/// Instead of transmitting and receiving packets on wires, the device queues them for the file.
+struct TunPhy {
+    /// The packets written to the file, to be received by the iface.
+    rx_queue: VecDeque<Vec<u8>>,
+    /// The packets transmitted by the iface, to be read from the file.
+    tx_queue: VecDeque<Vec<u8>>,
+    medium: Medium,
+    /// The pollee of the file, which is notified when packets are transmitted.
+    pollee: Pollee,
+}
+
+/// The maximum number of packets that are transmitted by the iface but not read from the file.
+///
+/// Like Linux (i.e., the default `txqueuelen` of TUN/TAP ifaces), more packets are dropped.
+const MAX_TX_QUEUE_LEN: usize = 500;
+
+/// The MTU of TUN/TAP ifaces, which is the default value in Linux.
+const TUN_MTU: usize = 1500;
+
+impl TunPhy {
+    fn new(kind: TunKind, pollee: Pollee) -> Self {
+        Self {
+            rx_queue: VecDeque::new(),
+            tx_queue: VecDeque::new(),
+            medium: kind.medium(),
+            pollee,
+        }
+    }
+}
+
+impl device::Device for TunPhy {
+    type RxToken<'a> = RxToken;
+    type TxToken<'a> = TxToken<'a>;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let packet = self.rx_queue.pop_front()?;
+        Some((RxToken(packet), TxToken(self)))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TxToken(self))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+
+        caps.medium = self.medium;
+        caps.max_transmission_unit = match self.medium {
+            Medium::Ethernet => TUN_MTU + ETHER_HEADER_LEN,
+            _ => TUN_MTU,
+        };
+
+        caps
+    }
+}
+
+impl NotifyDevice for TunPhy {
+    fn notify_poll_end(&mut self) {}
+}
+
+struct RxToken(Vec<u8>);
+
+impl device::RxToken for RxToken {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(&self.0)
+    }
+}
+
+struct TxToken<'a>(&'a mut TunPhy);
+
+impl device::TxToken for TxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut packet = vec![0u8; len];
+        let res = f(&mut packet);
+
+        if self.0.tx_queue.len() < MAX_TX_QUEUE_LEN {
+            self.0.tx_queue.push_back(packet);
+            self.0.pollee.notify(IoEvents::IN);
+        }
+
+        res
+    }
+}
@@ -1,37 +1,119 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use aster_bigtcp::wire::{IpAddress, IpEndpoint, Ipv4Address};
+use aster_bigtcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint, Ipv4Address, Ipv6Address};
 
 use crate::{net::socket::SocketAddr, prelude::*, return_errno_with_message};
 
-impl TryFrom<SocketAddr> for IpEndpoint {
-    type Error = Error;
+/// The address family of an IP socket.
+///
+/// An `AF_INET6` socket can talk to IPv4 peers via IPv4-mapped IPv6 addresses (i.e.,
+/// `::ffff:a.b.c.d`), unless the `IPV6_V6ONLY` option is set. Internally, such addresses are
+/// always represented as IPv4 addresses, so the conversion happens here at the boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl IpFamily {
+    /// Converts a socket address to the endpoint to bind to.
+    ///
+    /// For an `AF_INET6` socket, the unspecified address (i.e., `::`) means all IPv4 and IPv6
+    /// addresses, unless `v6_only` is true. This is represented by `None`.
+    pub(super) fn to_local_endpoint(
+        self,
+        socket_addr: SocketAddr,
+        v6_only: bool,
+    ) -> Result<IpListenEndpoint> {
+        let (addr, port) = match (self, socket_addr) {
+            (IpFamily::Ipv4, SocketAddr::IPv4(addr, port)) => (Some(IpAddress::Ipv4(addr)), port),
+            (IpFamily::Ipv6, SocketAddr::IPv6(addr, port)) => {
+                let addr = if let Some(ipv4_addr) = addr.to_ipv4_mapped() {
+                    if v6_only {
+                        return_errno_with_message!(
+                            Errno::EINVAL,
+                            "IPv4-mapped addresses cannot be bound by IPv6-only sockets"
+                        );
+                    }
+                    Some(IpAddress::Ipv4(ipv4_addr))
+                } else if addr.is_unspecified() && !v6_only {
+                    None
+                } else {
+                    Some(IpAddress::Ipv6(addr))
+                };
+                (addr, port)
+            }
+            _ => return_errno_with_message!(
+                Errno::EAFNOSUPPORT,
+                "the address is in an unsupported address family"
+            ),
+        };
+
+        Ok(IpListenEndpoint { addr, port })
+    }
 
-    fn try_from(value: SocketAddr) -> Result<Self> {
-        match value {
-            SocketAddr::IPv4(addr, port) => Ok(IpEndpoint::new(addr.into(), port)),
+    /// Converts a socket address to the remote endpoint to connect or send to.
+    pub(super) fn to_remote_endpoint(
+        self,
+        socket_addr: SocketAddr,
+        v6_only: bool,
+    ) -> Result<IpEndpoint> {
+        match (self, socket_addr) {
+            (IpFamily::Ipv4, SocketAddr::IPv4(addr, port)) => {
+                Ok(IpEndpoint::new(IpAddress::Ipv4(addr), port))
+            }
+            (IpFamily::Ipv6, SocketAddr::IPv6(addr, port)) => {
+                let Some(ipv4_addr) = addr.to_ipv4_mapped() else {
+                    return Ok(IpEndpoint::new(IpAddress::Ipv6(addr), port));
+                };
+                if v6_only {
+                    return_errno_with_message!(
+                        Errno::ENETUNREACH,
+                        "IPv4-mapped addresses cannot be reached by IPv6-only sockets"
+                    );
+                }
+                Ok(IpEndpoint::new(IpAddress::Ipv4(ipv4_addr), port))
+            }
             _ => return_errno_with_message!(
                 Errno::EAFNOSUPPORT,
                 "the address is in an unsupported address family"
             ),
         }
     }
-}
 
-impl From<IpEndpoint> for SocketAddr {
-    fn from(endpoint: IpEndpoint) -> Self {
+    /// Converts an endpoint to the socket address reported to the user program.
+    pub(super) fn to_socket_addr(self, endpoint: IpEndpoint) -> SocketAddr {
         let port = endpoint.port;
-        match endpoint.addr {
-            IpAddress::Ipv4(addr) => SocketAddr::IPv4(addr, port),
-            // TODO: support IPv6
+        match (self, endpoint.addr) {
+            (IpFamily::Ipv4, IpAddress::Ipv4(addr)) => SocketAddr::IPv4(addr, port),
+            (IpFamily::Ipv6, IpAddress::Ipv4(addr)) => {
+                SocketAddr::IPv6(addr.to_ipv6_mapped(), port)
+            }
+            (_, IpAddress::Ipv6(addr)) => SocketAddr::IPv6(addr, port),
         }
     }
-}
 
-/// A local endpoint, which indicates that the local endpoint is unspecified.
-///
-/// According to the Linux man pages and the Linux implementation, `getsockname()` will _not_ fail
-/// even if the socket is unbound. Instead, it will return an unspecified socket address. This
-/// unspecified endpoint helps with that.
-pub(super) const UNSPECIFIED_LOCAL_ENDPOINT: IpEndpoint =
-    IpEndpoint::new(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED), 0);
+    /// Returns the wildcard endpoint with an ephemeral port.
+    ///
+    /// This is used when the socket is bound implicitly, e.g., by `listen()`.
+    pub(super) fn wildcard_endpoint(self, v6_only: bool) -> IpListenEndpoint {
+        let addr = match self {
+            IpFamily::Ipv4 => Some(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED)),
+            IpFamily::Ipv6 if v6_only => Some(IpAddress::Ipv6(Ipv6Address::UNSPECIFIED)),
+            IpFamily::Ipv6 => None,
+        };
+
+        IpListenEndpoint { addr, port: 0 }
+    }
+
+    /// Returns the unspecified socket address.
+    ///
+    /// According to the Linux man pages and the Linux implementation, `getsockname()` will _not_
+    /// fail even if the socket is unbound. Instead, it will return an unspecified socket address.
+    pub(super) fn unspecified_socket_addr(self) -> SocketAddr {
+        match self {
+            IpFamily::Ipv4 => SocketAddr::IPv4(Ipv4Address::UNSPECIFIED, 0),
+            IpFamily::Ipv6 => SocketAddr::IPv6(Ipv6Address::UNSPECIFIED, 0),
+        }
+    }
+}
@@ -3,50 +3,70 @@
 use aster_bigtcp::{
     errors::BindError,
     iface::BindPortConfig,
-    wire::{IpAddress, IpEndpoint},
+    wire::{IpAddress, IpEndpoint, IpListenEndpoint, Ipv4Address, Ipv6Address},
 };
 
 use crate::{
-    net::iface::{BoundPort, Iface, IFACES},
+    net::iface::{lookup_route, BoundPort, Iface, IFACES},
     prelude::*,
 };
 
-pub(super) fn get_iface_to_bind(ip_addr: &IpAddress) -> Option<Arc<Iface>> {
-    let ifaces = IFACES.get().unwrap();
-    let IpAddress::Ipv4(ipv4_addr) = ip_addr;
+pub(super) fn get_iface_to_bind(ip_addr: Option<&IpAddress>) -> Option<Arc<Iface>> {
+    let ifaces = IFACES.read();
+
+    let Some(ip_addr) = ip_addr.filter(|ip_addr| !ip_addr.is_unspecified()) else {
+        // FIXME: Sockets bound to the wildcard address should receive packets from all ifaces.
+        // Currently, they are bound to the default iface (i.e., the first virtio-net), which means
+        // that they are not reachable via other ifaces.
+        return Some(ifaces[0].clone());
+    };
+
     ifaces
         .iter()
-        .find(|iface| {
-            if let Some(iface_ipv4_addr) = iface.ipv4_addr() {
-                iface_ipv4_addr == *ipv4_addr
-            } else {
-                false
-            }
+        .find(|iface| match ip_addr {
+            IpAddress::Ipv4(ipv4_addr) => iface.ipv4_addr() == Some(*ipv4_addr),
+            IpAddress::Ipv6(ipv6_addr) => iface.ipv6_addr() == Some(*ipv6_addr),
         })
         .map(Clone::clone)
 }
 
-/// Get a suitable iface to deal with sendto/connect request if the socket is not bound to an iface.
-/// If the remote address is the same as that of some iface, we will use the iface.
-/// Otherwise, we will use a default interface.
-fn get_ephemeral_iface(remote_ip_addr: &IpAddress) -> Arc<Iface> {
-    let ifaces = IFACES.get().unwrap();
-    let IpAddress::Ipv4(remote_ipv4_addr) = remote_ip_addr;
-    if let Some(iface) = ifaces.iter().find(|iface| {
-        if let Some(iface_ipv4_addr) = iface.ipv4_addr() {
-            iface_ipv4_addr == *remote_ipv4_addr
-        } else {
-            false
-        }
+/// Gets a suitable iface to deal with sendto/connect requests if the socket is not bound to an iface.
+///
+/// If the remote address is the same as that of some iface, we will use the iface. Otherwise, we
+/// will use the iface that the routing table chooses.
+pub(super) fn get_ephemeral_iface(remote_ip_addr: &IpAddress) -> Result<Arc<Iface>> {
+    if let Some(iface) = IFACES.read().iter().find(|iface| match remote_ip_addr {
+        IpAddress::Ipv4(remote_ipv4_addr) => iface.ipv4_addr() == Some(*remote_ipv4_addr),
+        IpAddress::Ipv6(remote_ipv6_addr) => iface.ipv6_addr() == Some(*remote_ipv6_addr),
     }) {
-        return iface.clone();
+        return Ok(iface.clone());
     }
-    // FIXME: use the virtio-net as the default interface
-    ifaces[0].clone()
+
+    lookup_route(remote_ip_addr)
+        .map(|route| route.iface)
+        .ok_or_else(|| Error::with_message(Errno::ENETUNREACH, "the network is unreachable"))
+}
+
+/// Returns whether the address is a broadcast address.
+///
+/// This includes the limited broadcast address (i.e., `255.255.255.255`) and the directed
+/// broadcast addresses of the networks that the ifaces are directly connected to.
+pub(super) fn is_broadcast_addr(ip_addr: &IpAddress) -> bool {
+    let IpAddress::Ipv4(ipv4_addr) = ip_addr else {
+        return false;
+    };
+
+    ipv4_addr.is_broadcast()
+        || IFACES.read().iter().any(|iface| {
+            iface
+                .ipv4_cidrs()
+                .iter()
+                .any(|cidr| cidr.broadcast() == Some(*ipv4_addr))
+        })
 }
 
-pub(super) fn bind_port(endpoint: &IpEndpoint, can_reuse: bool) -> Result<BoundPort> {
-    let iface = match get_iface_to_bind(&endpoint.addr) {
+pub(super) fn bind_port(endpoint: &IpListenEndpoint, can_reuse: bool) -> Result<BoundPort> {
+    let iface = match get_iface_to_bind(endpoint.addr.as_ref()) {
         Some(iface) => iface,
         None => {
             return_errno_with_message!(
@@ -58,7 +78,7 @@ pub(super) fn bind_port(endpoint: &IpEndpoint, can_reuse: bool) -> Result<BoundP
 
     let bind_port_config = BindPortConfig::new(endpoint.port, can_reuse);
 
-    Ok(iface.bind(bind_port_config)?)
+    Ok(iface.bind(endpoint.addr, bind_port_config)?)
 }
 
 impl From<BindError> for Error {
@@ -74,8 +94,20 @@ impl From<BindError> for Error {
     }
 }
 
-pub(super) fn get_ephemeral_endpoint(remote_endpoint: &IpEndpoint) -> IpEndpoint {
-    let iface = get_ephemeral_iface(&remote_endpoint.addr);
-    let ip_addr = iface.ipv4_addr().unwrap();
-    IpEndpoint::new(IpAddress::Ipv4(ip_addr), 0)
+pub(super) fn get_ephemeral_endpoint(remote_endpoint: &IpEndpoint) -> Result<IpListenEndpoint> {
+    let iface = get_ephemeral_iface(&remote_endpoint.addr)?;
+    // The iface may have no addresses of the IP version if they are removed by the user. In this
+    // case, the unspecified address is used and connecting from it will fail.
+    let ip_addr = match remote_endpoint.addr {
+        IpAddress::Ipv4(_) => {
+            IpAddress::Ipv4(iface.ipv4_addr().unwrap_or(Ipv4Address::UNSPECIFIED))
+        }
+        IpAddress::Ipv6(_) => {
+            IpAddress::Ipv6(iface.ipv6_addr().unwrap_or(Ipv6Address::UNSPECIFIED))
+        }
+    };
+    Ok(IpListenEndpoint {
+        addr: Some(ip_addr),
+        port: 0,
+    })
 }
@@ -2,6 +2,7 @@
 
 use aster_bigtcp::{
     errors::udp::{RecvError, SendError},
+    socket::RawUdpSetOption,
     wire::IpEndpoint,
 };
 
@@ -29,7 +30,7 @@ impl BoundDatagram {
     }
 
     pub fn local_endpoint(&self) -> IpEndpoint {
-        self.bound_socket.local_endpoint().unwrap()
+        self.bound_socket.local_endpoint()
     }
 
     pub fn remote_endpoint(&self) -> Option<IpEndpoint> {
@@ -44,6 +45,10 @@ impl BoundDatagram {
         self.bound_socket.iface()
     }
 
+    pub fn set_raw_option<R>(&self, set_option: impl FnOnce(&dyn RawUdpSetOption) -> R) -> R {
+        set_option(&self.bound_socket)
+    }
+
     pub fn try_recv(
         &self,
         writer: &mut dyn MultiWrite,
@@ -2,19 +2,32 @@
 
 use core::sync::atomic::{AtomicBool, Ordering};
 
-use aster_bigtcp::wire::IpEndpoint;
+use aster_bigtcp::{
+    socket::{NeedIfacePoll, RawUdpOption, RawUdpSetOption},
+    wire::{IpEndpoint, IpListenEndpoint},
+};
 use ostd::sync::PreemptDisabled;
 use takeable::Takeable;
 
-use self::{bound::BoundDatagram, unbound::UnboundDatagram};
-use super::{common::get_ephemeral_endpoint, UNSPECIFIED_LOCAL_ENDPOINT};
+use self::{
+    bound::BoundDatagram,
+    multicast::MulticastMemberships,
+    options::{AddMembership, DropMembership, MulticastLoop, MulticastTtl},
+    unbound::UnboundDatagram,
+    util::UdpOptionSet,
+};
+use super::{
+    common::{get_ephemeral_endpoint, is_broadcast_addr},
+    options::IpOptionSet,
+    IpFamily,
+};
 use crate::{
     events::IoEvents,
     fs::{
         file_handle::FileLike,
         utils::{InodeMode, Metadata, StatusFlags},
     },
-    match_sock_option_mut,
+    match_sock_option_mut, match_sock_option_ref,
     net::socket::{
         options::{Error as SocketError, SocketOption},
         util::{
@@ -31,27 +44,36 @@ use crate::{
 };
 
 mod bound;
+mod multicast;
 mod observer;
+pub mod options;
 mod unbound;
+mod util;
 
 pub(in crate::net) use self::observer::DatagramObserver;
+pub use self::util::IpMembership;
 
 #[derive(Debug, Clone)]
 struct OptionSet {
     socket: SocketOptionSet,
-    // TODO: UDP option set
+    ip: IpOptionSet,
+    udp: UdpOptionSet,
 }
 
 impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_udp();
-        OptionSet { socket }
+        let ip = IpOptionSet::new();
+        let udp = UdpOptionSet::new();
+        OptionSet { socket, ip, udp }
     }
 }
 
 pub struct DatagramSocket {
+    family: IpFamily,
     options: RwLock<OptionSet>,
     inner: RwLock<Takeable<Inner>, PreemptDisabled>,
+    memberships: Mutex<MulticastMemberships>,
     is_nonblocking: AtomicBool,
     pollee: Pollee,
 }
@@ -64,8 +86,9 @@ enum Inner {
 impl Inner {
     fn bind(
         self,
-        endpoint: &IpEndpoint,
+        endpoint: &IpListenEndpoint,
         can_reuse: bool,
+        option: &RawUdpOption,
         observer: DatagramObserver,
     ) -> core::result::Result<BoundDatagram, (Error, Self)> {
         let unbound_datagram = match self {
@@ -78,7 +101,7 @@ impl Inner {
             }
         };
 
-        let bound_datagram = match unbound_datagram.bind(endpoint, can_reuse, observer) {
+        let bound_datagram = match unbound_datagram.bind(endpoint, can_reuse, option, observer) {
             Ok(bound_datagram) => bound_datagram,
             Err((err, unbound_datagram)) => return Err((err, Inner::Unbound(unbound_datagram))),
         };
@@ -88,22 +111,28 @@ impl Inner {
     fn bind_to_ephemeral_endpoint(
         self,
         remote_endpoint: &IpEndpoint,
+        option: &RawUdpOption,
         observer: DatagramObserver,
     ) -> core::result::Result<BoundDatagram, (Error, Self)> {
         if let Inner::Bound(bound_datagram) = self {
             return Ok(bound_datagram);
         }
 
-        let endpoint = get_ephemeral_endpoint(remote_endpoint);
-        self.bind(&endpoint, false, observer)
+        let endpoint = match get_ephemeral_endpoint(remote_endpoint) {
+            Ok(endpoint) => endpoint,
+            Err(err) => return Err((err, self)),
+        };
+        self.bind(&endpoint, false, option, observer)
     }
 }
 
 impl DatagramSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+    pub fn new(family: IpFamily, is_nonblocking: bool) -> Arc<Self> {
         let unbound_datagram = UnboundDatagram::new();
         Arc::new(Self {
+            family,
             inner: RwLock::new(Takeable::new(Inner::Unbound(unbound_datagram))),
+            memberships: Mutex::new(MulticastMemberships::new()),
             is_nonblocking: AtomicBool::new(is_nonblocking),
             pollee: Pollee::new(),
             options: RwLock::new(OptionSet::new()),
@@ -134,10 +163,12 @@ impl DatagramSocket {
         }
 
         // Slow path
+        let raw_option = self.options.read().udp.raw();
         let mut inner = self.inner.write();
         inner.borrow_result(|owned_inner| {
             let bound_datagram = match owned_inner.bind_to_ephemeral_endpoint(
                 remote_endpoint,
+                &raw_option,
                 DatagramObserver::new(self.pollee.clone()),
             ) {
                 Ok(bound_datagram) => bound_datagram,
@@ -160,9 +191,12 @@ impl DatagramSocket {
             return_errno_with_message!(Errno::EAGAIN, "the socket is not bound");
         };
 
-        let recv_bytes = bound_datagram
-            .try_recv(writer, flags)
-            .map(|(recv_bytes, remote_endpoint)| (recv_bytes, remote_endpoint.into()))?;
+        let recv_bytes =
+            bound_datagram
+                .try_recv(writer, flags)
+                .map(|(recv_bytes, remote_endpoint)| {
+                    (recv_bytes, self.family.to_socket_addr(remote_endpoint))
+                })?;
         self.pollee.invalidate();
 
         Ok(recv_bytes)
@@ -173,7 +207,7 @@ impl DatagramSocket {
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, SocketAddr)> {
-        if self.is_nonblocking() {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
             self.try_recv(writer, flags)
         } else {
             self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
@@ -202,6 +236,23 @@ impl DatagramSocket {
         Ok(sent_bytes)
     }
 
+    fn to_remote_endpoint(&self, socket_addr: SocketAddr) -> Result<IpEndpoint> {
+        let options = self.options.read();
+        let endpoint = self
+            .family
+            .to_remote_endpoint(socket_addr, options.ip.v6_only())?;
+
+        // Like Linux, sending to or connecting to a broadcast address requires `SO_BROADCAST`.
+        if !options.socket.broadcast() && is_broadcast_addr(&endpoint.addr) {
+            return_errno_with_message!(
+                Errno::EACCES,
+                "sending to broadcast addresses is not enabled for the socket"
+            );
+        }
+
+        Ok(endpoint)
+    }
+
     fn check_io_events(&self) -> IoEvents {
         let inner = self.inner.read();
 
@@ -277,14 +328,20 @@ impl FileLike for DatagramSocket {
 
 impl Socket for DatagramSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        let options = self.options.read();
+        let endpoint = self
+            .family
+            .to_local_endpoint(socket_addr, options.ip.v6_only())?;
+        let can_reuse = options.socket.reuse_addr();
+        let raw_option = options.udp.raw();
+        drop(options);
 
-        let can_reuse = self.options.read().socket.reuse_addr();
         let mut inner = self.inner.write();
         inner.borrow_result(|owned_inner| {
             let bound_datagram = match owned_inner.bind(
                 &endpoint,
                 can_reuse,
+                &raw_option,
                 DatagramObserver::new(self.pollee.clone()),
             ) {
                 Ok(bound_datagram) => bound_datagram,
@@ -297,7 +354,7 @@ impl Socket for DatagramSocket {
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        let endpoint = self.to_remote_endpoint(socket_addr)?;
 
         self.try_bind_ephemeral(&endpoint)?;
 
@@ -313,14 +370,16 @@ impl Socket for DatagramSocket {
     fn addr(&self) -> Result<SocketAddr> {
         let inner = self.inner.read();
         match inner.as_ref() {
-            Inner::Unbound(_) => Ok(UNSPECIFIED_LOCAL_ENDPOINT.into()),
-            Inner::Bound(bound_datagram) => Ok(bound_datagram.local_endpoint().into()),
+            Inner::Unbound(_) => Ok(self.family.unspecified_socket_addr()),
+            Inner::Bound(bound_datagram) => {
+                Ok(self.family.to_socket_addr(bound_datagram.local_endpoint()))
+            }
         }
     }
 
     fn peer_addr(&self) -> Result<SocketAddr> {
         self.remote_endpoint()
-            .map(|endpoint| endpoint.into())
+            .map(|endpoint| self.family.to_socket_addr(endpoint))
             .ok_or_else(|| Error::with_message(Errno::ENOTCONN, "the socket is not connected"))
     }
 
@@ -337,12 +396,12 @@ impl Socket for DatagramSocket {
 
         let MessageHeader {
             addr,
-            control_message,
+            control_messages,
         } = message_header;
 
         let remote_endpoint = match addr {
             Some(remote_addr) => {
-                let endpoint = remote_addr.try_into()?;
+                let endpoint = self.to_remote_endpoint(remote_addr)?;
                 self.try_bind_ephemeral(&endpoint)?;
                 endpoint
             }
@@ -354,7 +413,7 @@ impl Socket for DatagramSocket {
             })?,
         };
 
-        if control_message.is_some() {
+        if !control_messages.is_empty() {
             // TODO: Support sending control message
             warn!("sending control message is not supported");
         }
@@ -377,7 +436,7 @@ impl Socket for DatagramSocket {
 
         // TODO: Receive control message
 
-        let message_header = MessageHeader::new(Some(peer_addr), None);
+        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
 
         Ok((received_bytes, message_header))
     }
@@ -391,14 +450,65 @@ impl Socket for DatagramSocket {
             _ => ()
         });
 
-        self.options.read().socket.get_option(option)
+        let options = self.options.read();
+
+        match options.socket.get_option(option) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        match options.ip.get_option(option, self.family) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        match_sock_option_mut!(option, {
+            ip_multicast_ttl: MulticastTtl => {
+                let multicast_ttl = options.udp.multicast_ttl();
+                ip_multicast_ttl.set(multicast_ttl as i32);
+            },
+            ip_multicast_loop: MulticastLoop => {
+                let multicast_loop = options.udp.multicast_loop();
+                ip_multicast_loop.set(multicast_loop as i32);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to get is unknown")
+        });
+
+        Ok(())
     }
 
     fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        // Joining or leaving multicast groups involves the iface chosen by the option, not the
+        // iface that the socket is bound to. So these options are handled separately.
+        match_sock_option_ref!(option, {
+            ip_add_membership: AddMembership => {
+                let membership = ip_add_membership.get().unwrap();
+                return self.memberships.lock().join(membership);
+            },
+            ip_drop_membership: DropMembership => {
+                let membership = ip_drop_membership.get().unwrap();
+                return self.memberships.lock().leave(membership);
+            },
+            _ => ()
+        });
+
         let mut options = self.options.write();
         let mut inner = self.inner.write();
 
-        match options.socket.set_option(option, inner.as_mut()) {
+        let result = match options.socket.set_option(option, inner.as_mut()) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                let is_bound = matches!(inner.as_ref(), Inner::Bound(_));
+                match options.ip.set_option(option, self.family, is_bound) {
+                    Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                        do_udp_setsockopt(option, &mut options, inner.as_ref())
+                    }
+                    result => result.map(|_| NeedIfacePoll::FALSE),
+                }
+            }
+            result => result,
+        };
+
+        match result {
             Err(e) => Err(e),
             Ok(need_iface_poll) => {
                 let iface_to_poll = need_iface_poll
@@ -421,4 +531,43 @@ impl Socket for DatagramSocket {
     }
 }
 
+fn do_udp_setsockopt(
+    option: &dyn SocketOption,
+    options: &mut OptionSet,
+    inner: &Inner,
+) -> Result<NeedIfacePoll> {
+    match_sock_option_ref!(option, {
+        ip_multicast_ttl: MulticastTtl => {
+            let multicast_ttl = match *ip_multicast_ttl.get().unwrap() {
+                // Like Linux, -1 means the default value.
+                -1 => util::DEFAULT_MULTICAST_TTL,
+                ttl @ 0..=255 => ttl as u8,
+                _ => return_errno_with_message!(Errno::EINVAL, "the multicast TTL is out of bounds"),
+            };
+            options.udp.set_multicast_ttl(multicast_ttl);
+            inner.set_raw_option(|raw_socket: &dyn RawUdpSetOption| raw_socket.set_multicast_ttl(multicast_ttl));
+        },
+        ip_multicast_loop: MulticastLoop => {
+            let multicast_loop = *ip_multicast_loop.get().unwrap() != 0;
+            options.udp.set_multicast_loop(multicast_loop);
+            inner.set_raw_option(|raw_socket: &dyn RawUdpSetOption| raw_socket.set_multicast_loop(multicast_loop));
+        },
+        _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
+    });
+
+    Ok(NeedIfacePoll::FALSE)
+}
+
+impl Inner {
+    /// Calls `f` to set raw socket option.
+    ///
+    /// Unbound sockets have no raw sockets. The options will be applied when they are bound.
+    fn set_raw_option<R>(&self, set_option: impl FnOnce(&dyn RawUdpSetOption) -> R) -> Option<R> {
+        match self {
+            Inner::Unbound(_) => None,
+            Inner::Bound(bound_datagram) => Some(bound_datagram.set_raw_option(set_option)),
+        }
+    }
+}
+
 impl SetSocketLevelOption for Inner {}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::{IpAddress, Ipv4Address};
+
+use super::IpMembership;
+use crate::{
+    net::iface::{lookup_route, Iface, IFACES},
+    prelude::*,
+};
+
+/// The IPv4 multicast groups joined by a socket.
+///
+/// The groups are left automatically when the socket is closed.
+pub(super) struct MulticastMemberships {
+    groups: Vec<(Arc<Iface>, Ipv4Address)>,
+}
+
+/// The maximum number of the multicast groups that a socket can join.
+///
+/// This is the default value of `/proc/sys/net/ipv4/igmp_max_memberships` in Linux.
+const MAX_MEMBERSHIPS: usize = 20;
+
+impl MulticastMemberships {
+    pub(super) fn new() -> Self {
+        Self { groups: Vec::new() }
+    }
+
+    /// Joins a multicast group (i.e., `IP_ADD_MEMBERSHIP`).
+    pub(super) fn join(&mut self, membership: &IpMembership) -> Result<()> {
+        let group = check_multicast_group(membership)?;
+        let iface = find_iface(membership)?;
+
+        if self.position(&iface, group).is_some() {
+            return_errno_with_message!(Errno::EADDRINUSE, "the multicast group is already joined");
+        }
+        if self.groups.len() >= MAX_MEMBERSHIPS {
+            return_errno_with_message!(Errno::ENOBUFS, "too many multicast groups are joined");
+        }
+
+        let need_iface_poll = iface.join_ipv4_multicast_group(group);
+        self.groups.push((iface.clone(), group));
+
+        if *need_iface_poll {
+            iface.poll();
+        }
+
+        Ok(())
+    }
+
+    /// Leaves a multicast group (i.e., `IP_DROP_MEMBERSHIP`).
+    pub(super) fn leave(&mut self, membership: &IpMembership) -> Result<()> {
+        let group = check_multicast_group(membership)?;
+        let iface = find_iface(membership)?;
+
+        let Some(pos) = self.position(&iface, group) else {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the multicast group is not joined");
+        };
+        self.groups.swap_remove(pos);
+
+        if *iface.leave_ipv4_multicast_group(group) {
+            iface.poll();
+        }
+
+        Ok(())
+    }
+
+    fn position(&self, iface: &Arc<Iface>, group: Ipv4Address) -> Option<usize> {
+        self.groups.iter().position(|(joined_iface, joined_group)| {
+            Arc::ptr_eq(joined_iface, iface) && *joined_group == group
+        })
+    }
+}
+
+impl Drop for MulticastMemberships {
+    fn drop(&mut self) {
+        for (iface, group) in self.groups.drain(..) {
+            if *iface.leave_ipv4_multicast_group(group) {
+                iface.poll();
+            }
+        }
+    }
+}
+
+fn check_multicast_group(membership: &IpMembership) -> Result<Ipv4Address> {
+    if !membership.group.is_multicast() {
+        return_errno_with_message!(Errno::EINVAL, "the address is not a multicast address");
+    }
+
+    Ok(membership.group)
+}
+
+/// Finds the iface on which the multicast group is joined or left.
+///
+/// Like Linux, the iface is specified by its index or by its local address. If neither is
+/// specified, the iface that the routing table chooses for the multicast group is used.
+fn find_iface(membership: &IpMembership) -> Result<Arc<Iface>> {
+    let iface = if membership.iface_index != 0 {
+        IFACES
+            .read()
+            .iter()
+            .find(|iface| iface.index() == membership.iface_index)
+            .cloned()
+    } else if !membership.local_addr.is_unspecified() {
+        IFACES
+            .read()
+            .iter()
+            .find(|iface| iface.ipv4_addr() == Some(membership.local_addr))
+            .cloned()
+    } else {
+        lookup_route(&IpAddress::Ipv4(membership.group)).map(|route| route.iface)
+    };
+
+    iface.ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))
+}
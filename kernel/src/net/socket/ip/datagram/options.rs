@@ -0,0 +1,11 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::IpMembership;
+use crate::impl_socket_options;
+
+impl_socket_options!(
+    pub struct MulticastTtl(i32);
+    pub struct MulticastLoop(i32);
+    pub struct AddMembership(IpMembership);
+    pub struct DropMembership(IpMembership);
+);
@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use aster_bigtcp::{socket::UdpSocket, wire::IpEndpoint};
+use aster_bigtcp::{
+    socket::{RawUdpOption, UdpSocket},
+    wire::IpListenEndpoint,
+};
 
 use super::{bound::BoundDatagram, DatagramObserver};
 use crate::{events::IoEvents, net::socket::ip::common::bind_port, prelude::*};
@@ -16,8 +19,9 @@ impl UnboundDatagram {
 
     pub fn bind(
         self,
-        endpoint: &IpEndpoint,
+        endpoint: &IpListenEndpoint,
         can_reuse: bool,
+        option: &RawUdpOption,
         observer: DatagramObserver,
     ) -> core::result::Result<BoundDatagram, (Error, Self)> {
         let bound_port = match bind_port(endpoint, can_reuse) {
@@ -25,7 +29,7 @@ impl UnboundDatagram {
             Err(err) => return Err((err, self)),
         };
 
-        let bound_socket = match UdpSocket::new_bind(bound_port, observer) {
+        let bound_socket = match UdpSocket::new_bind(bound_port, option, observer) {
             Ok(bound_socket) => bound_socket,
             Err((_, err)) => {
                 unreachable!("`new_bind fails with {:?}, which should not happen", err)
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::{socket::RawUdpOption, wire::Ipv4Address};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, CopyGetters, Setters)]
+#[get_copy = "pub"]
+#[set = "pub"]
+pub struct UdpOptionSet {
+    multicast_ttl: u8,
+    multicast_loop: bool,
+}
+
+/// The default TTL of outgoing multicast packets.
+///
+/// Like Linux, multicast packets do not leave the local network by default.
+pub const DEFAULT_MULTICAST_TTL: u8 = 1;
+
+impl UdpOptionSet {
+    pub fn new() -> Self {
+        Self {
+            multicast_ttl: DEFAULT_MULTICAST_TTL,
+            multicast_loop: true,
+        }
+    }
+
+    pub fn raw(&self) -> RawUdpOption {
+        RawUdpOption {
+            multicast_ttl: self.multicast_ttl,
+            multicast_loop: self.multicast_loop,
+        }
+    }
+}
+
+impl Default for UdpOptionSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A request to join or leave an IPv4 multicast group.
+///
+/// This corresponds to `struct ip_mreqn` (or the older `struct ip_mreq`) in Linux.
+#[derive(Debug, Clone, Copy)]
+pub struct IpMembership {
+    /// The address of the multicast group.
+    pub group: Ipv4Address,
+    /// The local address of the iface, or the unspecified address if it is not specified.
+    pub local_addr: Ipv4Address,
+    /// The index of the iface, or zero if it is not specified.
+    pub iface_index: u32,
+}
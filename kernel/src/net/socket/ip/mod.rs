@@ -3,6 +3,11 @@
 mod addr;
 mod common;
 pub mod datagram;
+pub mod options;
+mod ping;
+mod raw;
 pub mod stream;
 
-use addr::UNSPECIFIED_LOCAL_ENDPOINT;
+pub use addr::IpFamily;
+pub use ping::PingSocket;
+pub use raw::RawSocket;
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::IpFamily;
+use crate::{
+    impl_socket_options, match_sock_option_mut, match_sock_option_ref,
+    net::socket::options::SocketOption, prelude::*,
+};
+
+impl_socket_options!(
+    pub struct V6Only(bool);
+    pub struct Hdrincl(bool);
+);
+
+/// IP-level options shared by TCP and UDP sockets.
+#[derive(Debug, Clone, CopyGetters)]
+#[get_copy = "pub"]
+pub(super) struct IpOptionSet {
+    v6_only: bool,
+}
+
+impl IpOptionSet {
+    pub(super) fn new() -> Self {
+        Self { v6_only: false }
+    }
+
+    pub(super) fn get_option(&self, option: &mut dyn SocketOption, family: IpFamily) -> Result<()> {
+        match_sock_option_mut!(option, {
+            ipv6_v6_only: V6Only => {
+                check_ipv6_option(family)?;
+                ipv6_v6_only.set(self.v6_only);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to get is unknown")
+        });
+
+        Ok(())
+    }
+
+    /// Sets an IP-level option.
+    ///
+    /// The `is_bound` argument indicates whether the socket has been bound to a local address,
+    /// since some options cannot be changed afterwards.
+    pub(super) fn set_option(
+        &mut self,
+        option: &dyn SocketOption,
+        family: IpFamily,
+        is_bound: bool,
+    ) -> Result<()> {
+        match_sock_option_ref!(option, {
+            ipv6_v6_only: V6Only => {
+                check_ipv6_option(family)?;
+                // Linux does not allow changing the option after binding. See
+                // <https://elixir.bootlin.com/linux/v6.0.9/source/net/ipv6/ipv6_sockglue.c#L495>.
+                if is_bound {
+                    return_errno_with_message!(Errno::EINVAL, "the socket is already bound");
+                }
+                self.v6_only = *ipv6_v6_only.get().unwrap();
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
+        });
+
+        Ok(())
+    }
+}
+
+fn check_ipv6_option(family: IpFamily) -> Result<()> {
+    // Linux rejects IPv6-level options on IPv4 sockets with `EOPNOTSUPP`, not `ENOPROTOOPT`.
+    if family != IpFamily::Ipv6 {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "IPv6-level options are not available for IPv4 sockets"
+        );
+    }
+    Ok(())
+}
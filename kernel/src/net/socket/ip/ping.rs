@@ -0,0 +1,454 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! ICMP datagram sockets (a.k.a. ping sockets).
+//!
+//! Ping sockets send ICMP echo requests and receive the corresponding echo replies without the
+//! `CAP_NET_RAW` capability. The identifier of the echo messages is the port that the socket is
+//! bound to, which is filled in by the kernel.
+//!
+//! Unlike Linux, which only allows the groups in `net.ipv4.ping_group_range` to create such
+//! sockets, all users are allowed to create them.
+//!
+//! Currently, only IPv4 is supported.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_bigtcp::{
+    errors::raw::{RecvError, SendError},
+    socket::RawIpHeader,
+    wire::{
+        Icmpv4Message, Icmpv4Packet, IpAddress, IpEndpoint, IpListenEndpoint, IpProtocol,
+        Ipv4Address,
+    },
+};
+
+use super::{
+    common::{bind_port, get_ephemeral_endpoint},
+    options::IpOptionSet,
+    IpFamily,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata, StatusFlags},
+    },
+    match_sock_option_mut,
+    net::{
+        iface::RawSocket as IfaceRawSocket,
+        socket::{
+            ip::datagram::DatagramObserver,
+            options::{Error as SocketError, SocketOption},
+            util::{
+                options::{SetSocketLevelOption, SocketOptionSet},
+                send_recv_flags::SendRecvFlags,
+                socket_addr::SocketAddr,
+                MessageHeader,
+            },
+            Socket,
+        },
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    util::{MultiRead, MultiWrite},
+};
+
+/// The length of ICMP echo headers.
+const ICMP_ECHO_HEADER_LEN: usize = 8;
+
+/// The default time to live of the sent packets.
+const DEFAULT_HOP_LIMIT: u8 = 64;
+
+pub struct PingSocket {
+    inner: Mutex<Inner>,
+    options: RwLock<OptionSet>,
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+}
+
+struct Inner {
+    /// The socket bound to a port of an iface, if any.
+    bound: Option<IfaceRawSocket>,
+    /// The remote address that the socket is connected to, if any.
+    remote_addr: Option<Ipv4Address>,
+}
+
+#[derive(Debug, Clone)]
+struct OptionSet {
+    socket: SocketOptionSet,
+    ip: IpOptionSet,
+}
+
+impl PingSocket {
+    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+        let options = OptionSet {
+            socket: SocketOptionSet::new_raw(),
+            ip: IpOptionSet::new(),
+        };
+
+        Arc::new(Self {
+            inner: Mutex::new(Inner {
+                bound: None,
+                remote_addr: None,
+            }),
+            options: RwLock::new(options),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee: Pollee::new(),
+        })
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+
+    /// Binds the socket to an ephemeral port if it is not bound.
+    fn bind_ephemeral<'a>(
+        &self,
+        inner: &'a mut Inner,
+        remote_addr: Ipv4Address,
+        can_reuse: bool,
+    ) -> Result<&'a IfaceRawSocket> {
+        if inner.bound.is_none() {
+            let endpoint =
+                get_ephemeral_endpoint(&IpEndpoint::new(IpAddress::Ipv4(remote_addr), 0))?;
+            inner.bound = Some(self.new_iface_socket(&endpoint, can_reuse)?);
+        }
+
+        Ok(inner.bound.as_ref().unwrap())
+    }
+
+    fn new_iface_socket(
+        &self,
+        endpoint: &IpListenEndpoint,
+        can_reuse: bool,
+    ) -> Result<IfaceRawSocket> {
+        let bound_port = bind_port(endpoint, can_reuse)?;
+
+        Ok(IfaceRawSocket::new_ping(
+            bound_port,
+            DatagramObserver::new(self.pollee.clone()),
+        ))
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr)> {
+        let inner = self.inner.lock();
+
+        let Some(bound) = inner.bound.as_ref() else {
+            return_errno_with_message!(Errno::EAGAIN, "the socket is not bound");
+        };
+
+        let result = bound.recv(|message, src_addr| {
+            let copied_res = writer.write(&mut VmReader::from(message));
+
+            // Like other datagram sockets, the real length is returned with `MSG_TRUNC`.
+            let res = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+                copied_res.map(|_| message.len())
+            } else {
+                copied_res
+            };
+            res.map(|len| (len, src_addr))
+        });
+
+        drop(inner);
+        self.pollee.invalidate();
+
+        let (len, src_addr) = match result {
+            Ok(result) => result?,
+            Err(RecvError::Exhausted) => {
+                return_errno_with_message!(Errno::EAGAIN, "the receive queue is empty")
+            }
+        };
+
+        Ok((
+            len,
+            IpFamily::Ipv4.to_socket_addr(IpEndpoint::new(src_addr, 0)),
+        ))
+    }
+
+    fn recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr)> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_recv(writer, flags)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
+        }
+    }
+
+    fn send(&self, reader: &mut dyn MultiRead, remote: Option<Ipv4Address>) -> Result<usize> {
+        let len = reader.sum_lens();
+        if len < ICMP_ECHO_HEADER_LEN {
+            return_errno_with_message!(Errno::EINVAL, "the ICMP message is too short");
+        }
+
+        let mut message = vec![0; len];
+        reader.read(&mut VmWriter::from(message.as_mut_slice()))?;
+
+        let mut icmp_packet = Icmpv4Packet::new_unchecked(message.as_mut_slice());
+        if icmp_packet.msg_type() != Icmpv4Message::EchoRequest || icmp_packet.msg_code() != 0 {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "only ICMP echo requests can be sent by ping sockets"
+            );
+        }
+
+        let can_reuse = self.options.read().socket.reuse_addr();
+        let mut inner = self.inner.lock();
+
+        let Some(dst_addr) = remote.or(inner.remote_addr) else {
+            return_errno_with_message!(
+                Errno::EDESTADDRREQ,
+                "the destination address is not specified"
+            );
+        };
+
+        let bound = self.bind_ephemeral(&mut inner, dst_addr, can_reuse)?;
+        let bound_port = bound.bound_port().unwrap();
+
+        // The identifier is always the bound port, so the checksum has to be computed again.
+        icmp_packet.set_echo_ident(bound_port.port());
+        icmp_packet.fill_checksum();
+
+        let src_addr = match bound_port.endpoint().addr {
+            IpAddress::Ipv4(addr) if !addr.is_unspecified() => Some(addr),
+            _ => None,
+        };
+        let header = RawIpHeader {
+            src_addr,
+            dst_addr,
+            protocol: IpProtocol::Icmp,
+            hop_limit: DEFAULT_HOP_LIMIT,
+        };
+
+        let result = bound.send(header, len, |buffer| buffer.copy_from_slice(&message));
+        match result {
+            Ok(()) => (),
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too long")
+            }
+            // TODO: Block if the send buffer is full
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full")
+            }
+        }
+
+        let iface_to_poll = bound.iface().clone();
+
+        drop(inner);
+        self.pollee.invalidate();
+        iface_to_poll.poll();
+
+        Ok(len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+
+        let Some(bound) = inner.bound.as_ref() else {
+            return IoEvents::OUT;
+        };
+
+        let mut events = IoEvents::empty();
+        if bound.can_recv() {
+            events |= IoEvents::IN;
+        }
+        if bound.can_send() {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+}
+
+/// Converts the socket address to an IPv4 address, ignoring the port.
+fn to_remote_addr(socket_addr: SocketAddr) -> Result<Ipv4Address> {
+    let endpoint = IpFamily::Ipv4.to_remote_endpoint(socket_addr, false)?;
+    let IpAddress::Ipv4(addr) = endpoint.addr else {
+        unreachable!("the remote address of an IPv4 socket is an IPv4 address");
+    };
+    Ok(addr)
+}
+
+impl Pollable for PingSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for PingSocket {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.recv(writer, SendRecvFlags::empty())
+            .map(|(len, _)| len)
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        self.send(reader, None)
+    }
+
+    fn as_socket(self: Arc<Self>) -> Option<Arc<dyn Socket>> {
+        Some(self)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        // TODO: when we fully support O_ASYNC, return the flag
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            self.set_nonblocking(true);
+        } else {
+            self.set_nonblocking(false);
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "SockFS" and link `PingSocket` to it.
+        Metadata::new_socket(
+            0,
+            InodeMode::from_bits_truncate(0o140777),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl Socket for PingSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = IpFamily::Ipv4.to_local_endpoint(socket_addr, false)?;
+        let can_reuse = self.options.read().socket.reuse_addr();
+
+        let mut inner = self.inner.lock();
+        if inner.bound.is_some() {
+            return_errno_with_message!(Errno::EINVAL, "the socket is already bound to an address");
+        }
+        inner.bound = Some(self.new_iface_socket(&endpoint, can_reuse)?);
+
+        Ok(())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let remote_addr = to_remote_addr(socket_addr)?;
+        let can_reuse = self.options.read().socket.reuse_addr();
+
+        let mut inner = self.inner.lock();
+        self.bind_ephemeral(&mut inner, remote_addr, can_reuse)?;
+        inner.remote_addr = Some(remote_addr);
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let inner = self.inner.lock();
+
+        // Like other IP sockets, the port is the one that the socket is bound to, which is also
+        // the identifier of the echo messages.
+        match inner.bound.as_ref().and_then(|bound| bound.bound_port()) {
+            Some(bound_port) => Ok(IpFamily::Ipv4.to_socket_addr(bound_port.endpoint())),
+            None => Ok(IpFamily::Ipv4.unspecified_socket_addr()),
+        }
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        self.inner
+            .lock()
+            .remote_addr
+            .map(|addr| SocketAddr::IPv4(addr, 0))
+            .ok_or_else(|| Error::with_message(Errno::ENOTCONN, "the socket is not connected"))
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = addr.map(to_remote_addr).transpose()?;
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        self.send(reader, remote)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_len, remote) = self.recv(writer, flags)?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(remote), Vec::new());
+
+        Ok((received_len, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                self.options.write().socket.get_and_clear_sock_errors(socket_errors);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let options = self.options.read();
+
+        match options.socket.get_option(option) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        options.ip.get_option(option, IpFamily::Ipv4)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let mut options = self.options.write();
+        let mut inner = self.inner.lock();
+
+        match options.socket.set_option(option, &mut *inner) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res.map(|_| ()),
+        }
+
+        let is_bound = inner.bound.is_some();
+        options.ip.set_option(option, IpFamily::Ipv4, is_bound)
+    }
+}
+
+impl SetSocketLevelOption for Inner {}
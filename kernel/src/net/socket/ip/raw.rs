@@ -0,0 +1,532 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Raw IP sockets.
+//!
+//! Raw sockets send and receive IP packets of a protocol directly. The received packets include
+//! the IP headers. By default, the IP headers of the sent packets are generated by the kernel,
+//! unless the `IP_HDRINCL` option is set.
+//!
+//! Currently, only IPv4 is supported.
+//!
+//! See <https://www.man7.org/linux/man-pages/man7/raw.7.html>.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_bigtcp::{
+    errors::raw::{RecvError, SendError},
+    socket::RawIpHeader,
+    wire::{IpAddress, IpProtocol, Ipv4Address, Ipv4Packet},
+};
+
+use super::{
+    common::{get_ephemeral_iface, get_iface_to_bind},
+    options::{Hdrincl, IpOptionSet},
+    IpFamily,
+};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata, StatusFlags},
+    },
+    match_sock_option_mut, match_sock_option_ref,
+    net::{
+        iface::{RawSocket as IfaceRawSocket, IFACES},
+        socket::{
+            ip::datagram::DatagramObserver,
+            options::{Error as SocketError, SocketOption},
+            util::{
+                options::{SetSocketLevelOption, SocketOptionSet},
+                send_recv_flags::SendRecvFlags,
+                socket_addr::SocketAddr,
+                MessageHeader,
+            },
+            Socket,
+        },
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+    util::{MultiRead, MultiWrite},
+};
+
+/// The protocol that allows sending any IP packets (`IPPROTO_RAW`).
+///
+/// Sockets of this protocol always include the IP headers in the sent packets.
+const IPPROTO_RAW: u8 = 255;
+
+/// The minimum length of IPv4 headers.
+const IPV4_HEADER_LEN: usize = 20;
+
+/// The default time to live of the sent packets.
+const DEFAULT_HOP_LIMIT: u8 = 64;
+
+pub struct RawSocket {
+    protocol: u8,
+    inner: Mutex<Inner>,
+    options: RwLock<OptionSet>,
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+}
+
+struct Inner {
+    /// The local address that the socket is bound to, if any.
+    local_addr: Option<Ipv4Address>,
+    /// The remote address that the socket is connected to, if any.
+    remote_addr: Option<Ipv4Address>,
+    /// The sockets that send and receive the packets through the ifaces.
+    iface_sockets: Vec<IfaceRawSocket>,
+}
+
+#[derive(Debug, Clone)]
+struct OptionSet {
+    socket: SocketOptionSet,
+    ip: IpOptionSet,
+    hdrincl: bool,
+}
+
+impl RawSocket {
+    /// Creates a raw socket that sends and receives the packets of the protocol.
+    ///
+    /// Like Linux, this requires the `CAP_NET_RAW` capability.
+    pub fn new(protocol: u8, is_nonblocking: bool) -> Result<Arc<Self>> {
+        let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+        if !credentials.effective_capset().contains(CapSet::NET_RAW) {
+            return_errno_with_message!(Errno::EPERM, "creating raw sockets requires CAP_NET_RAW");
+        }
+
+        let pollee = Pollee::new();
+        let iface_sockets = IFACES
+            .read()
+            .iter()
+            .map(|iface| {
+                IfaceRawSocket::new(
+                    iface.clone(),
+                    IpProtocol::from(protocol),
+                    DatagramObserver::new(pollee.clone()),
+                )
+            })
+            .collect();
+
+        let options = OptionSet {
+            socket: SocketOptionSet::new_raw(),
+            ip: IpOptionSet::new(),
+            hdrincl: protocol == IPPROTO_RAW,
+        };
+
+        Ok(Arc::new(Self {
+            protocol,
+            inner: Mutex::new(Inner {
+                local_addr: None,
+                remote_addr: None,
+                iface_sockets,
+            }),
+            options: RwLock::new(options),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee,
+        }))
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr)> {
+        let inner = self.inner.lock();
+
+        let mut result = None;
+        'outer: for iface_socket in inner.iface_sockets.iter() {
+            loop {
+                let recv_result = iface_socket.recv(|packet, src_addr| {
+                    // Like Linux, the packets that do not match the bound address or the connected
+                    // address are discarded.
+                    if !inner.accepts(packet, &src_addr) {
+                        return None;
+                    }
+
+                    let copied_res = writer.write(&mut VmReader::from(packet));
+
+                    // Like other datagram sockets, the real length is returned with `MSG_TRUNC`.
+                    let res = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+                        copied_res.map(|_| packet.len())
+                    } else {
+                        copied_res
+                    };
+                    Some(res.map(|len| (len, src_addr)))
+                });
+
+                match recv_result {
+                    Ok(Some(res)) => {
+                        result = Some(res);
+                        break 'outer;
+                    }
+                    Ok(None) => continue,
+                    Err(RecvError::Exhausted) => break,
+                }
+            }
+        }
+
+        drop(inner);
+        self.pollee.invalidate();
+
+        let Some(result) = result else {
+            return_errno_with_message!(Errno::EAGAIN, "the receive queue is empty");
+        };
+        let (len, src_addr) = result?;
+
+        Ok((len, to_socket_addr(src_addr)))
+    }
+
+    fn recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr)> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_recv(writer, flags)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
+        }
+    }
+
+    fn send(&self, reader: &mut dyn MultiRead, remote: Option<Ipv4Address>) -> Result<usize> {
+        let hdrincl = self.options.read().hdrincl;
+        let inner = self.inner.lock();
+
+        let Some(dst_addr) = remote.or(inner.remote_addr) else {
+            return_errno_with_message!(
+                Errno::EDESTADDRREQ,
+                "the destination address is not specified"
+            );
+        };
+
+        let iface = get_ephemeral_iface(&IpAddress::Ipv4(dst_addr))?;
+        let iface_socket = inner
+            .iface_sockets
+            .iter()
+            .find(|iface_socket| iface_socket.iface().index() == iface.index())
+            .ok_or_else(|| Error::with_message(Errno::ENETUNREACH, "the iface does not exist"))?;
+
+        let len = reader.sum_lens();
+
+        let result = if hdrincl {
+            let mut packet = vec![0; len];
+            reader.read(&mut VmWriter::from(packet.as_mut_slice()))?;
+
+            // Like Linux, the destination address in the IP header is used as is, while the packet
+            // is routed according to the specified destination address.
+            let (header, header_len) = parse_ip_header(&packet)?;
+            let payload = &packet[header_len..];
+
+            iface_socket.send(header, payload.len(), |buffer| {
+                buffer.copy_from_slice(payload);
+                Ok(len)
+            })
+        } else {
+            let header = RawIpHeader {
+                src_addr: inner.local_addr,
+                dst_addr,
+                protocol: IpProtocol::from(self.protocol),
+                hop_limit: DEFAULT_HOP_LIMIT,
+            };
+
+            iface_socket.send(header, len, |buffer| {
+                // FIXME: If copy failed, we should not send any packet.
+                reader.read(&mut VmWriter::from(buffer))
+            })
+        };
+
+        let sent_len = match result {
+            Ok(result) => result?,
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too long")
+            }
+            // TODO: Block if the send buffer is full
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full")
+            }
+        };
+
+        drop(inner);
+        self.pollee.invalidate();
+        iface.poll();
+
+        Ok(sent_len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+
+        let mut events = IoEvents::empty();
+        if inner
+            .iface_sockets
+            .iter()
+            .any(|iface_socket| iface_socket.can_recv())
+        {
+            events |= IoEvents::IN;
+        }
+        if inner
+            .iface_sockets
+            .iter()
+            .all(|iface_socket| iface_socket.can_send())
+        {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+}
+
+impl Inner {
+    /// Returns whether the received packet matches the bound address and the connected address.
+    fn accepts(&self, packet: &[u8], src_addr: &IpAddress) -> bool {
+        let dst_addr = Ipv4Packet::new_unchecked(packet).dst_addr();
+
+        self.local_addr.is_none_or(|addr| addr == dst_addr)
+            && self
+                .remote_addr
+                .is_none_or(|addr| IpAddress::Ipv4(addr) == *src_addr)
+    }
+}
+
+/// Parses the IP header provided by the user.
+///
+/// Like Linux, the total length and the checksum are always filled in by the kernel, and an
+/// unspecified source address is replaced with the address of the iface. Note that IP options are
+/// not supported and are silently dropped.
+fn parse_ip_header(packet: &[u8]) -> Result<(RawIpHeader, usize)> {
+    if packet.len() < IPV4_HEADER_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the packet is too short");
+    }
+
+    let ip_packet = Ipv4Packet::new_unchecked(packet);
+    let header_len = ip_packet.header_len() as usize;
+    if header_len < IPV4_HEADER_LEN || header_len > packet.len() {
+        return_errno_with_message!(Errno::EINVAL, "the IP header length is invalid");
+    }
+
+    let src_addr = ip_packet.src_addr();
+    let header = RawIpHeader {
+        src_addr: (!src_addr.is_unspecified()).then_some(src_addr),
+        dst_addr: ip_packet.dst_addr(),
+        protocol: ip_packet.next_header(),
+        hop_limit: ip_packet.hop_limit(),
+    };
+
+    Ok((header, header_len))
+}
+
+fn to_socket_addr(addr: IpAddress) -> SocketAddr {
+    match addr {
+        IpAddress::Ipv4(addr) => SocketAddr::IPv4(addr, 0),
+        IpAddress::Ipv6(addr) => SocketAddr::IPv6(addr, 0),
+    }
+}
+
+impl Pollable for RawSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for RawSocket {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.recv(writer, SendRecvFlags::empty())
+            .map(|(len, _)| len)
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        self.send(reader, None)
+    }
+
+    fn as_socket(self: Arc<Self>) -> Option<Arc<dyn Socket>> {
+        Some(self)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        // TODO: when we fully support O_ASYNC, return the flag
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            self.set_nonblocking(true);
+        } else {
+            self.set_nonblocking(false);
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "SockFS" and link `RawSocket` to it.
+        Metadata::new_socket(
+            0,
+            InodeMode::from_bits_truncate(0o140777),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl Socket for RawSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        // The port is ignored, since raw sockets have no ports.
+        let endpoint = IpFamily::Ipv4.to_local_endpoint(socket_addr, false)?;
+        let Some(IpAddress::Ipv4(addr)) = endpoint.addr else {
+            unreachable!("the local address of an IPv4 socket is an IPv4 address");
+        };
+
+        if !addr.is_unspecified() && get_iface_to_bind(endpoint.addr.as_ref()).is_none() {
+            return_errno_with_message!(
+                Errno::EADDRNOTAVAIL,
+                "the address is not available from the local machine"
+            );
+        }
+
+        self.inner.lock().local_addr = (!addr.is_unspecified()).then_some(addr);
+        Ok(())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = IpFamily::Ipv4.to_remote_endpoint(socket_addr, false)?;
+        let IpAddress::Ipv4(addr) = endpoint.addr else {
+            unreachable!("the remote address of an IPv4 socket is an IPv4 address");
+        };
+
+        self.inner.lock().remote_addr = Some(addr);
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let local_addr = self.inner.lock().local_addr;
+
+        // Like Linux, the port of a raw socket is its protocol.
+        Ok(SocketAddr::IPv4(
+            local_addr.unwrap_or(Ipv4Address::UNSPECIFIED),
+            self.protocol as u16,
+        ))
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        self.inner
+            .lock()
+            .remote_addr
+            .map(|addr| SocketAddr::IPv4(addr, 0))
+            .ok_or_else(|| Error::with_message(Errno::ENOTCONN, "the socket is not connected"))
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = match addr {
+            Some(addr) => {
+                let endpoint = IpFamily::Ipv4.to_remote_endpoint(addr, false)?;
+                let IpAddress::Ipv4(addr) = endpoint.addr else {
+                    unreachable!("the remote address of an IPv4 socket is an IPv4 address");
+                };
+                Some(addr)
+            }
+            None => None,
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        self.send(reader, remote)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_len, remote) = self.recv(writer, flags)?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(remote), Vec::new());
+
+        Ok((received_len, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                self.options.write().socket.get_and_clear_sock_errors(socket_errors);
+                return Ok(());
+            },
+            ip_hdrincl: Hdrincl => {
+                ip_hdrincl.set(self.options.read().hdrincl);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        let options = self.options.read();
+
+        match options.socket.get_option(option) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        options.ip.get_option(option, IpFamily::Ipv4)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let mut options = self.options.write();
+
+        match_sock_option_ref!(option, {
+            ip_hdrincl: Hdrincl => {
+                options.hdrincl = *ip_hdrincl.get().unwrap();
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        match options.socket.set_option(option, &mut *self.inner.lock()) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res.map(|_| ()),
+        }
+
+        let is_bound = self.inner.lock().local_addr.is_some();
+        options.ip.set_option(option, IpFamily::Ipv4, is_bound)
+    }
+}
+
+impl SetSocketLevelOption for Inner {}
@@ -8,7 +8,7 @@ use aster_bigtcp::{
     wire::IpEndpoint,
 };
 
-use super::StreamObserver;
+use super::{StreamObserver, TcpInfo};
 use crate::{
     events::IoEvents,
     net::{
@@ -143,7 +143,7 @@ impl ConnectedStream {
     }
 
     pub fn local_endpoint(&self) -> IpEndpoint {
-        self.tcp_conn.local_endpoint().unwrap()
+        self.tcp_conn.local_endpoint()
     }
 
     pub fn remote_endpoint(&self) -> IpEndpoint {
@@ -216,4 +216,11 @@ impl ConnectedStream {
     pub(super) fn raw_with<R>(&self, f: impl FnOnce(&RawTcpSocket) -> R) -> R {
         self.tcp_conn.raw_with(f)
     }
+
+    pub(super) fn tcp_info(&self) -> TcpInfo {
+        TcpInfo {
+            state: self.tcp_conn.raw_with(|socket| socket.state()),
+            congestion: Some(self.tcp_conn.congestion_info()),
+        }
+    }
 }
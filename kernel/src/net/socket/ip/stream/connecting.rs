@@ -5,7 +5,7 @@ use aster_bigtcp::{
     wire::IpEndpoint,
 };
 
-use super::{connected::ConnectedStream, init::InitStream, StreamObserver};
+use super::{connected::ConnectedStream, init::InitStream, StreamObserver, TcpInfo};
 use crate::{
     events::IoEvents,
     net::iface::{BoundPort, Iface, TcpConnection},
@@ -30,15 +30,17 @@ impl ConnectingStream {
         option: &RawTcpOption,
         observer: StreamObserver,
     ) -> core::result::Result<Self, (Error, BoundPort)> {
-        // The only reason this method might fail is because we're trying to connect to an
-        // unspecified address (i.e. 0.0.0.0). We currently have no support for binding to,
-        // listening on, or connecting to the unspecified address.
-        //
+        // This method might fail because we're trying to connect to an unspecified address (i.e.
+        // 0.0.0.0 or ::). We currently have no support for connecting to the unspecified address.
         // We assume the remote will just refuse to connect, so we return `ECONNREFUSED`.
+        //
+        // Otherwise, the local address is not suitable for the remote address. For example, the
+        // socket is bound to an IPv4 address but the remote address is an IPv6 address, or the
+        // socket is bound to a wildcard address but the iface has no address of that IP version.
         let tcp_conn =
             match TcpConnection::new_connect(bound_port, remote_endpoint, option, observer) {
                 Ok(tcp_conn) => tcp_conn,
-                Err((bound_port, _)) => {
+                Err((bound_port, _)) if remote_endpoint.addr.is_unspecified() => {
                     return Err((
                         Error::with_message(
                             Errno::ECONNREFUSED,
@@ -47,6 +49,15 @@ impl ConnectingStream {
                         bound_port,
                     ))
                 }
+                Err((bound_port, _)) => {
+                    return Err((
+                        Error::with_message(
+                            Errno::ENETUNREACH,
+                            "the remote address is not reachable from the local address",
+                        ),
+                        bound_port,
+                    ))
+                }
             };
 
         Ok(Self {
@@ -80,7 +91,7 @@ impl ConnectingStream {
     }
 
     pub fn local_endpoint(&self) -> IpEndpoint {
-        self.tcp_conn.local_endpoint().unwrap()
+        self.tcp_conn.local_endpoint()
     }
 
     pub fn remote_endpoint(&self) -> IpEndpoint {
@@ -101,4 +112,11 @@ impl ConnectingStream {
     ) -> R {
         set_option(&self.tcp_conn)
     }
+
+    pub(super) fn tcp_info(&self) -> TcpInfo {
+        TcpInfo {
+            state: self.tcp_conn.raw_with(|socket| socket.state()),
+            congestion: Some(self.tcp_conn.congestion_info()),
+        }
+    }
 }
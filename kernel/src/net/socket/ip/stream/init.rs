@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use aster_bigtcp::{socket::RawTcpOption, wire::IpEndpoint};
+use aster_bigtcp::{
+    socket::RawTcpOption,
+    wire::{IpEndpoint, IpListenEndpoint},
+};
 
 use super::{connecting::ConnectingStream, listen::ListenStream, StreamObserver};
 use crate::{
@@ -28,7 +31,7 @@ impl InitStream {
 
     pub fn bind(
         self,
-        endpoint: &IpEndpoint,
+        endpoint: &IpListenEndpoint,
         can_reuse: bool,
     ) -> core::result::Result<BoundPort, (Error, Self)> {
         match self {
@@ -53,7 +56,10 @@ impl InitStream {
         self,
         remote_endpoint: &IpEndpoint,
     ) -> core::result::Result<BoundPort, (Error, Self)> {
-        let endpoint = get_ephemeral_endpoint(remote_endpoint);
+        let endpoint = match get_ephemeral_endpoint(remote_endpoint) {
+            Ok(endpoint) => endpoint,
+            Err(err) => return Err((err, self)),
+        };
         self.bind(&endpoint, false)
     }
 
@@ -72,20 +78,20 @@ impl InitStream {
             .map_err(|(err, bound_port)| (err, InitStream::Bound(bound_port)))
     }
 
+    /// Listens for connections.
+    ///
+    /// If the socket is not bound, it will be bound to the wildcard endpoint, which should have an
+    /// unspecified address and an ephemeral port.
     pub fn listen(
         self,
         backlog: usize,
         option: &RawTcpOption,
         observer: StreamObserver,
+        wildcard_endpoint: &IpListenEndpoint,
     ) -> core::result::Result<ListenStream, (Error, Self)> {
-        let InitStream::Bound(bound_port) = self else {
-            // FIXME: The socket should be bound to INADDR_ANY (i.e., 0.0.0.0) with an ephemeral
-            // port. However, INADDR_ANY is not yet supported, so we need to return an error first.
-            debug_assert!(false, "listen() without bind() is not implemented");
-            return Err((
-                Error::with_message(Errno::EINVAL, "listen() without bind() is not implemented"),
-                self,
-            ));
+        let bound_port = match self {
+            InitStream::Bound(bound_port) => bound_port,
+            InitStream::Unbound => self.bind(wildcard_endpoint, false)?,
         };
 
         Ok(ListenStream::new(bound_port, backlog, option, observer))
@@ -94,7 +100,7 @@ impl InitStream {
     pub fn local_endpoint(&self) -> Option<IpEndpoint> {
         match self {
             InitStream::Unbound => None,
-            InitStream::Bound(bound_port) => Some(bound_port.endpoint().unwrap()),
+            InitStream::Bound(bound_port) => Some(bound_port.endpoint()),
         }
     }
 
@@ -45,7 +45,7 @@ impl ListenStream {
     }
 
     pub fn local_endpoint(&self) -> IpEndpoint {
-        self.tcp_listener.local_endpoint().unwrap()
+        self.tcp_listener.local_endpoint()
     }
 
     pub fn iface(&self) -> &Arc<Iface> {
@@ -3,19 +3,19 @@
 use core::sync::atomic::{AtomicBool, Ordering};
 
 use aster_bigtcp::{
-    socket::{NeedIfacePoll, RawTcpOption, RawTcpSetOption},
+    socket::{NeedIfacePoll, RawTcpOption, RawTcpSetOption, TcpState},
     wire::IpEndpoint,
 };
 use connected::ConnectedStream;
 use connecting::{ConnResult, ConnectingStream};
 use init::InitStream;
 use listen::ListenStream;
-use options::{Congestion, KeepIdle, MaxSegment, NoDelay, WindowClamp, KEEPALIVE_INTERVAL};
+use options::{Congestion, Info, KeepIdle, MaxSegment, NoDelay, WindowClamp, KEEPALIVE_INTERVAL};
 use ostd::sync::{PreemptDisabled, RwLockReadGuard, RwLockWriteGuard};
 use takeable::Takeable;
 use util::TcpOptionSet;
 
-use super::UNSPECIFIED_LOCAL_ENDPOINT;
+use super::{options::IpOptionSet, IpFamily};
 use crate::{
     events::IoEvents,
     fs::{
@@ -51,9 +51,10 @@ pub mod options;
 mod util;
 
 pub(in crate::net) use self::observer::StreamObserver;
-pub use self::util::CongestionControl;
+pub use self::util::{CongestionControl, TcpInfo};
 
 pub struct StreamSocket {
+    family: IpFamily,
     options: RwLock<OptionSet>,
     state: RwLock<Takeable<State>, PreemptDisabled>,
     is_nonblocking: AtomicBool,
@@ -74,28 +75,32 @@ enum State {
 #[derive(Debug, Clone)]
 struct OptionSet {
     socket: SocketOptionSet,
+    ip: IpOptionSet,
     tcp: TcpOptionSet,
 }
 
 impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_tcp();
+        let ip = IpOptionSet::new();
         let tcp = TcpOptionSet::new();
-        OptionSet { socket, tcp }
+        OptionSet { socket, ip, tcp }
     }
 
     fn raw(&self) -> RawTcpOption {
         RawTcpOption {
             keep_alive: self.socket.keep_alive().then_some(KEEPALIVE_INTERVAL),
             is_nagle_enabled: !self.tcp.no_delay(),
+            congestion_control: self.tcp.congestion(),
         }
     }
 }
 
 impl StreamSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+    pub fn new(family: IpFamily, is_nonblocking: bool) -> Arc<Self> {
         let init_stream = InitStream::new();
         Arc::new(Self {
+            family,
             options: RwLock::new(OptionSet::new()),
             state: RwLock::new(Takeable::new(State::Init(init_stream))),
             is_nonblocking: AtomicBool::new(is_nonblocking),
@@ -103,9 +108,14 @@ impl StreamSocket {
         })
     }
 
-    fn new_accepted(connected_stream: ConnectedStream) -> Arc<Self> {
-        let options = connected_stream.raw_with(|raw_tcp_socket| {
+    fn new_accepted(
+        family: IpFamily,
+        ip_options: IpOptionSet,
+        connected_stream: ConnectedStream,
+    ) -> Arc<Self> {
+        let mut options = connected_stream.raw_with(|raw_tcp_socket| {
             let mut options = OptionSet::new();
+            options.ip = ip_options;
 
             if raw_tcp_socket.keep_alive().is_some() {
                 options.socket.set_keep_alive(true);
@@ -120,10 +130,16 @@ impl StreamSocket {
             options
         });
 
+        // The congestion control algorithm is inherited from the listening socket.
+        if let Some(congestion) = connected_stream.tcp_info().congestion {
+            options.tcp.set_congestion(congestion.algorithm);
+        }
+
         let pollee = Pollee::new();
         connected_stream.init_observer(StreamObserver::new(pollee.clone()));
 
         Arc::new(Self {
+            family,
             options: RwLock::new(options),
             state: RwLock::new(Takeable::new(State::Connected(connected_stream))),
             is_nonblocking: AtomicBool::new(false),
@@ -305,6 +321,7 @@ impl StreamSocket {
     }
 
     fn try_accept(&self) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
+        let ip_options = self.options.read().ip.clone();
         let state = self.read_updated_state();
 
         let State::Listen(listen_stream) = state.as_ref() else {
@@ -313,8 +330,11 @@ impl StreamSocket {
 
         let accepted = listen_stream.try_accept().map(|connected_stream| {
             let remote_endpoint = connected_stream.remote_endpoint();
-            let accepted_socket = Self::new_accepted(connected_stream);
-            (accepted_socket as _, remote_endpoint.into())
+            let accepted_socket = Self::new_accepted(self.family, ip_options, connected_stream);
+            (
+                accepted_socket as _,
+                self.family.to_socket_addr(remote_endpoint),
+            )
         });
         let iface_to_poll = listen_stream.iface().clone();
 
@@ -352,7 +372,7 @@ impl StreamSocket {
             iface.poll();
         }
 
-        Ok((recv_bytes, remote_endpoint.into()))
+        Ok((recv_bytes, self.family.to_socket_addr(remote_endpoint)))
     }
 
     fn recv(
@@ -360,7 +380,7 @@ impl StreamSocket {
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, SocketAddr)> {
-        if self.is_nonblocking() {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
             self.try_recv(writer, flags)
         } else {
             self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
@@ -396,7 +416,7 @@ impl StreamSocket {
     }
 
     fn send(&self, reader: &mut dyn MultiRead, flags: SendRecvFlags) -> Result<usize> {
-        if self.is_nonblocking() {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
             self.try_send(reader, flags)
         } else {
             self.wait_events(IoEvents::OUT, None, || self.try_send(reader, flags))
@@ -470,9 +490,13 @@ impl FileLike for StreamSocket {
 
 impl Socket for StreamSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        let options = self.options.read();
+        let endpoint = self
+            .family
+            .to_local_endpoint(socket_addr, options.ip.v6_only())?;
+        let can_reuse = options.socket.reuse_addr();
+        drop(options);
 
-        let can_reuse = self.options.read().socket.reuse_addr();
         let mut state = self.write_updated_state();
 
         state.borrow_result(|owned_state| {
@@ -498,7 +522,8 @@ impl Socket for StreamSocket {
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let remote_endpoint = socket_addr.try_into()?;
+        let v6_only = self.options.read().ip.v6_only();
+        let remote_endpoint = self.family.to_remote_endpoint(socket_addr, v6_only)?;
 
         if let Some(result) = self.start_connect(&remote_endpoint) {
             return result;
@@ -511,6 +536,7 @@ impl Socket for StreamSocket {
         let (options, mut state) = self.update_connecting();
 
         let raw_option = options.raw();
+        let wildcard_endpoint = self.family.wildcard_endpoint(options.ip.v6_only());
 
         state.borrow_result(|owned_state| {
             let init_stream = match owned_state {
@@ -533,6 +559,7 @@ impl Socket for StreamSocket {
                 backlog,
                 &raw_option,
                 StreamObserver::new(self.pollee.clone()),
+                &wildcard_endpoint,
             ) {
                 Ok(listen_stream) => listen_stream,
                 Err((err, init_stream)) => {
@@ -553,6 +580,10 @@ impl Socket for StreamSocket {
         }
     }
 
+    fn accept_nonblocking(&self) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
+        self.try_accept()
+    }
+
     fn shutdown(&self, cmd: SockShutdownCmd) -> Result<()> {
         let state = self.read_updated_state();
 
@@ -576,14 +607,17 @@ impl Socket for StreamSocket {
     fn addr(&self) -> Result<SocketAddr> {
         let state = self.read_updated_state();
         let local_endpoint = match state.as_ref() {
-            State::Init(init_stream) => init_stream
-                .local_endpoint()
-                .unwrap_or(UNSPECIFIED_LOCAL_ENDPOINT),
+            State::Init(init_stream) => {
+                let Some(local_endpoint) = init_stream.local_endpoint() else {
+                    return Ok(self.family.unspecified_socket_addr());
+                };
+                local_endpoint
+            }
             State::Connecting(connecting_stream) => connecting_stream.local_endpoint(),
             State::Listen(listen_stream) => listen_stream.local_endpoint(),
             State::Connected(connected_stream) => connected_stream.local_endpoint(),
         };
-        Ok(local_endpoint.into())
+        Ok(self.family.to_socket_addr(local_endpoint))
     }
 
     fn peer_addr(&self) -> Result<SocketAddr> {
@@ -595,7 +629,7 @@ impl Socket for StreamSocket {
             State::Connecting(connecting_stream) => connecting_stream.remote_endpoint(),
             State::Connected(connected_stream) => connected_stream.remote_endpoint(),
         };
-        Ok(remote_endpoint.into())
+        Ok(self.family.to_socket_addr(remote_endpoint))
     }
 
     fn sendmsg(
@@ -610,14 +644,14 @@ impl Socket for StreamSocket {
         }
 
         let MessageHeader {
-            control_message, ..
+            control_messages, ..
         } = message_header;
 
         // According to the Linux man pages, `EISCONN` _may_ be returned when the destination
         // address is specified for a connection-mode socket. In practice, the destination address
         // is simply ignored. We follow the same behavior as the Linux implementation to ignore it.
 
-        if control_message.is_some() {
+        if !control_messages.is_empty() {
             // TODO: Support sending control message
             warn!("sending control message is not supported");
         }
@@ -641,7 +675,7 @@ impl Socket for StreamSocket {
 
         // According to <https://elixir.bootlin.com/linux/v6.0.9/source/net/ipv4/tcp.c#L2645>,
         // peer address is ignored for connected socket.
-        let message_header = MessageHeader::new(None, None);
+        let message_header = MessageHeader::new(None, Vec::new());
 
         Ok((received_bytes, message_header))
     }
@@ -653,6 +687,11 @@ impl Socket for StreamSocket {
                 options.socket.get_and_clear_sock_errors(socket_errors);
                 return Ok(());
             },
+            tcp_info: Info => {
+                let state = self.read_updated_state();
+                tcp_info.set(state.tcp_info());
+                return Ok(());
+            },
             _ => ()
         });
 
@@ -663,6 +702,11 @@ impl Socket for StreamSocket {
             res => return res,
         }
 
+        match options.ip.get_option(option, self.family) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
         // FIXME: Here we only return the previously set values, without actually
         // asking the underlying sockets for the real, effective values.
         match_sock_option_mut!(option, {
@@ -697,7 +741,14 @@ impl Socket for StreamSocket {
 
         let need_iface_poll = match options.socket.set_option(option, state.as_mut()) {
             Err(err) if err.error() == Errno::ENOPROTOOPT => {
-                do_tcp_setsockopt(option, &mut options, state.as_mut())?
+                let is_bound = !matches!(state.as_ref(), State::Init(InitStream::Unbound));
+                match options.ip.set_option(option, self.family, is_bound) {
+                    Err(err) if err.error() == Errno::ENOPROTOOPT => {
+                        do_tcp_setsockopt(option, &mut options, state.as_mut())?
+                    }
+                    Err(err) => return Err(err),
+                    Ok(()) => NeedIfacePoll::FALSE,
+                }
             }
             Err(err) => return Err(err),
             Ok(need_iface_poll) => need_iface_poll,
@@ -761,6 +812,7 @@ fn do_tcp_setsockopt(
         tcp_congestion: Congestion => {
             let congestion = tcp_congestion.get().unwrap();
             options.tcp.set_congestion(*congestion);
+            state.set_raw_option(|raw_socket: &dyn RawTcpSetOption| raw_socket.set_congestion_control(*congestion));
         },
         _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
     });
@@ -784,6 +836,21 @@ impl State {
         }
     }
 
+    fn tcp_info(&self) -> TcpInfo {
+        match self {
+            State::Init(_) => TcpInfo {
+                state: TcpState::Closed,
+                congestion: None,
+            },
+            State::Connecting(connecting_stream) => connecting_stream.tcp_info(),
+            State::Connected(connected_stream) => connected_stream.tcp_info(),
+            State::Listen(_) => TcpInfo {
+                state: TcpState::Listen,
+                congestion: None,
+            },
+        }
+    }
+
     fn iface(&self) -> Option<&Arc<Iface>> {
         match self {
             State::Init(_) => None,
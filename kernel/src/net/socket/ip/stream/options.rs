@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::CongestionControl;
+use super::{CongestionControl, TcpInfo};
 use crate::impl_socket_options;
 
 impl_socket_options!(
@@ -9,6 +9,7 @@ impl_socket_options!(
     pub struct KeepIdle(u32);
     pub struct WindowClamp(u32);
     pub struct Congestion(CongestionControl);
+    pub struct Info(TcpInfo);
 );
 
 /// The keepalive interval.
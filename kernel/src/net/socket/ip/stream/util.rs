@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::prelude::*;
+pub use aster_bigtcp::socket::CongestionControl;
+use aster_bigtcp::socket::{CongestionInfo, TcpState};
 
 #[derive(Debug, Clone, Copy, CopyGetters, Setters)]
 #[get_copy = "pub"]
@@ -24,7 +25,7 @@ impl TcpOptionSet {
             maxseg: DEFAULT_MAXSEG,
             keep_idle: DEFAULT_KEEP_IDLE,
             window_clamp: DEFAULT_WINDOW_CLAMP,
-            congestion: CongestionControl::Reno,
+            congestion: CongestionControl::Cubic,
         }
     }
 }
@@ -35,30 +36,11 @@ impl Default for TcpOptionSet {
     }
 }
 
+/// The information of a TCP socket, which is reported by `TCP_INFO`.
 #[derive(Debug, Clone, Copy)]
-pub enum CongestionControl {
-    Reno,
-    Cubic,
-}
-
-impl CongestionControl {
-    const RENO: &'static str = "reno";
-    const CUBIC: &'static str = "cubic";
-
-    pub fn new(name: &str) -> Result<Self> {
-        let congestion = match name {
-            Self::RENO => Self::Reno,
-            Self::CUBIC => Self::Cubic,
-            _ => return_errno_with_message!(Errno::EINVAL, "unsupported congestion name"),
-        };
-
-        Ok(congestion)
-    }
-
-    pub fn name(&self) -> &'static str {
-        match self {
-            Self::Reno => Self::RENO,
-            Self::Cubic => Self::CUBIC,
-        }
-    }
+pub struct TcpInfo {
+    /// The TCP state.
+    pub state: TcpState,
+    /// The congestion information, or `None` if the socket is not connecting or connected.
+    pub congestion: Option<CongestionInfo>,
 }
@@ -3,7 +3,7 @@
 use self::options::SocketOption;
 pub use self::util::{
     options::LingerOption, send_recv_flags::SendRecvFlags, shutdown_cmd::SockShutdownCmd,
-    socket_addr::SocketAddr, MessageHeader,
+    socket_addr::SocketAddr, ControlMessage, MessageHeader,
 };
 use crate::{
     fs::file_handle::FileLike,
@@ -12,7 +12,9 @@ use crate::{
 };
 
 pub mod ip;
+pub mod netlink;
 pub mod options;
+pub mod packet;
 pub mod unix;
 mod util;
 pub mod vsock;
@@ -39,6 +41,14 @@ pub trait Socket: FileLike + Send + Sync {
         return_errno_with_message!(Errno::EOPNOTSUPP, "accept() is not supported");
     }
 
+    /// Accept a connection on a socket as if the socket were in the non-blocking mode.
+    ///
+    /// If there are no pending connections, this method fails with `EAGAIN` instead of
+    /// waiting, even if `O_NONBLOCK` is not set.
+    fn accept_nonblocking(&self) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "accept() is not supported");
+    }
+
     /// Shut down part of a full-duplex connection
     fn shutdown(&self, _cmd: SockShutdownCmd) -> Result<()> {
         return_errno_with_message!(Errno::EOPNOTSUPP, "shutdown() is not supported");
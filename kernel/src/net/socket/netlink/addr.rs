@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{net::socket::SocketAddr, prelude::*};
+
+/// A netlink socket address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetlinkSocketAddr {
+    /// The port ID, which is zero for the kernel.
+    pub port: u32,
+    /// The mask of the multicast groups.
+    pub groups: u32,
+}
+
+impl NetlinkSocketAddr {
+    pub const fn new(port: u32, groups: u32) -> Self {
+        Self { port, groups }
+    }
+
+    /// Returns the address of the kernel.
+    pub const fn kernel() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl TryFrom<SocketAddr> for NetlinkSocketAddr {
+    type Error = Error;
+
+    fn try_from(value: SocketAddr) -> Result<Self> {
+        let SocketAddr::Netlink(netlink_addr) = value else {
+            return_errno_with_message!(Errno::EINVAL, "invalid netlink socket addr");
+        };
+        Ok(netlink_addr)
+    }
+}
+
+impl From<NetlinkSocketAddr> for SocketAddr {
+    fn from(value: NetlinkSocketAddr) -> Self {
+        SocketAddr::Netlink(value)
+    }
+}
+
+/// The port IDs that are bound by netlink sockets.
+static BOUND_PORTS: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// A port ID bound by a netlink socket.
+///
+/// When dropped, the port ID is automatically released.
+pub(super) struct BoundPort(u32);
+
+impl BoundPort {
+    /// Binds the port ID.
+    ///
+    /// If the port ID is zero, an unused port ID is picked. Like Linux, the process ID is tried
+    /// first, and then negative numbers starting from -4096.
+    pub(super) fn new(port: u32) -> Result<Self> {
+        let mut bound_ports = BOUND_PORTS.lock();
+
+        if port != 0 {
+            if !bound_ports.insert(port) {
+                return_errno_with_message!(Errno::EADDRINUSE, "the port ID is already in use");
+            }
+            return Ok(Self(port));
+        }
+
+        let pid = current!().pid();
+        if bound_ports.insert(pid) {
+            return Ok(Self(pid));
+        }
+
+        let mut port = -4096i32;
+        while !bound_ports.insert(port as u32) {
+            port = port.checked_sub(1).ok_or_else(|| {
+                Error::with_message(Errno::EADDRINUSE, "no port IDs are available")
+            })?;
+        }
+        Ok(Self(port as u32))
+    }
+
+    pub(super) fn port(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Drop for BoundPort {
+    fn drop(&mut self) {
+        BOUND_PORTS.lock().remove(&self.0);
+    }
+}
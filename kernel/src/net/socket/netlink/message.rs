@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netlink messages.
+//!
+//! A netlink message consists of a header ([`CNlMsgHdr`]) and a payload. The payload of most
+//! messages consists of a fixed-size structure followed by attributes, each of which consists of
+//! a header ([`CNlAttr`]) and a value. All the parts are aligned to [`NLMSG_ALIGNTO`] bytes.
+//!
+//! See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/netlink.h>.
+
+use align_ext::AlignExt;
+
+use crate::prelude::*;
+
+/// The alignment of netlink messages and attributes.
+const NLMSG_ALIGNTO: usize = 4;
+
+/// The header of a netlink message.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CNlMsgHdr {
+    /// The length of the message, including the header.
+    pub len: u32,
+    /// The type of the message.
+    pub type_: u16,
+    /// The flags of the message.
+    pub flags: u16,
+    /// The sequence number of the message.
+    pub seq: u32,
+    /// The port ID of the sender.
+    pub pid: u32,
+}
+
+/// The payload of [`NLMSG_ERROR`] messages.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CNlMsgErr {
+    /// The negative error number, or zero for acknowledgments.
+    pub error: i32,
+    /// The header of the message that causes the error.
+    pub msg: CNlMsgHdr,
+}
+
+/// The header of a netlink attribute.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CNlAttr {
+    /// The length of the attribute, including the header.
+    len: u16,
+    /// The type of the attribute.
+    type_: u16,
+}
+
+/// The message type of errors and acknowledgments.
+pub(super) const NLMSG_ERROR: u16 = 2;
+/// The message type that terminates a multipart message.
+pub(super) const NLMSG_DONE: u16 = 3;
+/// The minimum message type that is not reserved for control messages.
+pub(super) const NLMSG_MIN_TYPE: u16 = 0x10;
+
+/// The bits of the attribute type that are used as flags.
+const NLA_TYPE_FLAGS: u16 = 0xc000;
+
+bitflags! {
+    /// The flags of netlink messages.
+    pub(super) struct NlMsgFlags: u16 {
+        /// The message is a request.
+        const REQUEST       = 0x01;
+        /// The message is a part of a multipart message terminated by [`NLMSG_DONE`].
+        const MULTI         = 0x02;
+        /// The request asks for an acknowledgment.
+        const ACK           = 0x04;
+        /// The request asks for being echoed.
+        const ECHO          = 0x08;
+        /// The dump is inconsistent due to changes during the dump.
+        const DUMP_INTR     = 0x10;
+        /// The dump is filtered as requested.
+        const DUMP_FILTERED = 0x20;
+
+        // Modifiers of GET requests.
+        /// Returns the complete table instead of a single entry.
+        const ROOT          = 0x100;
+        /// Returns all the entries matching the criteria.
+        const MATCH         = 0x200;
+        /// Returns an atomic snapshot of the table.
+        const ATOMIC        = 0x400;
+        const DUMP          = Self::ROOT.bits | Self::MATCH.bits;
+
+        // Modifiers of NEW requests.
+        /// Replaces the existing entry.
+        const REPLACE       = 0x100;
+        /// Does not touch the existing entry.
+        const EXCL          = 0x200;
+        /// Creates the entry if it does not exist.
+        const CREATE        = 0x400;
+        /// Adds the entry to the end of the list.
+        const APPEND        = 0x800;
+
+        // Flags of acknowledgments.
+        /// The payload of the request is not included.
+        const CAPPED        = 0x100;
+    }
+}
+
+/// Builds a netlink message with the header fields and the payload.
+pub(super) fn build_message(
+    type_: u16,
+    flags: NlMsgFlags,
+    seq: u32,
+    port: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let len = size_of::<CNlMsgHdr>() + payload.len();
+    let header = CNlMsgHdr {
+        len: len as u32,
+        type_,
+        flags: flags.bits(),
+        seq,
+        pid: port,
+    };
+
+    let mut message = Vec::with_capacity(len.align_up(NLMSG_ALIGNTO));
+    message.extend_from_slice(header.as_bytes());
+    message.extend_from_slice(payload);
+    message.resize(len.align_up(NLMSG_ALIGNTO), 0);
+    message
+}
+
+/// Splits the netlink messages in the bytes.
+///
+/// Like Linux, the splitting stops at the first malformed message, and the bytes after it are
+/// ignored.
+pub(super) fn split_messages(mut bytes: &[u8]) -> Vec<(CNlMsgHdr, &[u8])> {
+    let mut messages = Vec::new();
+
+    while bytes.len() >= size_of::<CNlMsgHdr>() {
+        let header = CNlMsgHdr::from_bytes(bytes);
+        let len = header.len as usize;
+        if len < size_of::<CNlMsgHdr>() || len > bytes.len() {
+            break;
+        }
+
+        messages.push((header, &bytes[size_of::<CNlMsgHdr>()..len]));
+        bytes = &bytes[len.align_up(NLMSG_ALIGNTO).min(bytes.len())..];
+    }
+
+    messages
+}
+
+/// Parses a fixed-size structure at the beginning of a message payload.
+///
+/// This method returns the structure and the attributes that follow it.
+pub(super) fn parse_payload<T: Pod>(payload: &[u8]) -> Result<(T, Attrs)> {
+    if payload.len() < size_of::<T>() {
+        return_errno_with_message!(Errno::EINVAL, "the message payload is too short");
+    }
+
+    let fixed = T::from_bytes(payload);
+    let attrs_offset = size_of::<T>().align_up(NLMSG_ALIGNTO).min(payload.len());
+    let attrs = Attrs::parse(&payload[attrs_offset..])?;
+
+    Ok((fixed, attrs))
+}
+
+/// The attributes in a message payload.
+pub(super) struct Attrs<'a> {
+    attrs: Vec<(u16, &'a [u8])>,
+}
+
+impl<'a> Attrs<'a> {
+    fn parse(mut bytes: &'a [u8]) -> Result<Self> {
+        let mut attrs = Vec::new();
+
+        while bytes.len() >= size_of::<CNlAttr>() {
+            let header = CNlAttr::from_bytes(bytes);
+            let len = header.len as usize;
+            if len < size_of::<CNlAttr>() || len > bytes.len() {
+                return_errno_with_message!(Errno::EINVAL, "the attribute length is invalid");
+            }
+
+            attrs.push((
+                header.type_ & !NLA_TYPE_FLAGS,
+                &bytes[size_of::<CNlAttr>()..len],
+            ));
+            bytes = &bytes[len.align_up(NLMSG_ALIGNTO).min(bytes.len())..];
+        }
+
+        Ok(Self { attrs })
+    }
+
+    /// Returns the value of the attribute.
+    ///
+    /// If the attribute appears multiple times, the last one is returned, as in Linux.
+    pub(super) fn get(&self, type_: u16) -> Option<&'a [u8]> {
+        self.attrs
+            .iter()
+            .rev()
+            .find(|(attr_type, _)| *attr_type == type_)
+            .map(|(_, value)| *value)
+    }
+
+    /// Returns the value of the attribute as a fixed-size value.
+    pub(super) fn get_pod<T: Pod>(&self, type_: u16) -> Result<Option<T>> {
+        let Some(value) = self.get(type_) else {
+            return Ok(None);
+        };
+        if value.len() < size_of::<T>() {
+            return_errno_with_message!(Errno::EINVAL, "the attribute value is too short");
+        }
+        Ok(Some(T::from_bytes(value)))
+    }
+
+    /// Returns the value of the attribute as a string.
+    ///
+    /// The string ends at the first NUL byte, if any.
+    pub(super) fn get_str(&self, type_: u16) -> Result<Option<&'a str>> {
+        let Some(value) = self.get(type_) else {
+            return Ok(None);
+        };
+        let len = value
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(value.len());
+        let str = core::str::from_utf8(&value[..len])
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the string attribute is invalid"))?;
+        Ok(Some(str))
+    }
+}
+
+/// A builder of message payloads that consist of a fixed-size structure and attributes.
+pub(super) struct PayloadBuilder {
+    bytes: Vec<u8>,
+}
+
+impl PayloadBuilder {
+    /// Creates a builder with the fixed-size structure.
+    pub(super) fn new<T: Pod>(fixed: &T) -> Self {
+        let mut bytes = fixed.as_bytes().to_vec();
+        bytes.resize(bytes.len().align_up(NLMSG_ALIGNTO), 0);
+        Self { bytes }
+    }
+
+    /// Appends an attribute.
+    pub(super) fn attr(mut self, type_: u16, value: &[u8]) -> Self {
+        let header = CNlAttr {
+            len: (size_of::<CNlAttr>() + value.len()) as u16,
+            type_,
+        };
+        self.bytes.extend_from_slice(header.as_bytes());
+        self.bytes.extend_from_slice(value);
+        self.bytes
+            .resize(self.bytes.len().align_up(NLMSG_ALIGNTO), 0);
+        self
+    }
+
+    /// Appends an attribute whose value is a fixed-size value.
+    pub(super) fn attr_pod<T: Pod>(self, type_: u16, value: &T) -> Self {
+        self.attr(type_, value.as_bytes())
+    }
+
+    /// Appends an attribute whose value is a NUL-terminated string.
+    pub(super) fn attr_str(self, type_: u16, value: &str) -> Self {
+        let mut bytes = Vec::with_capacity(value.len() + 1);
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.push(0);
+        self.attr(type_, &bytes)
+    }
+
+    pub(super) fn build(self) -> Vec<u8> {
+        self.bytes
+    }
+}
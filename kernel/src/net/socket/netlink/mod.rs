@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netlink sockets.
+//!
+//! Netlink sockets transfer messages between the user space and the kernel. Currently, only the
+//! `NETLINK_ROUTE` protocol (also known as rtnetlink) is supported, with which the user space can
+//! query and modify the links, the addresses, and the routes of the ifaces.
+//!
+//! See <https://www.man7.org/linux/man-pages/man7/netlink.7.html> and
+//! <https://www.man7.org/linux/man-pages/man7/rtnetlink.7.html>.
+
+mod addr;
+mod message;
+mod route;
+
+pub use addr::NetlinkSocketAddr;
+pub use route::NetlinkRouteSocket;
+
+/// The netlink protocol that receives routing and link updates.
+pub const NETLINK_ROUTE: i32 = 0;
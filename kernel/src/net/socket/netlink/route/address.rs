@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The rtnetlink requests about IP addresses.
+
+use aster_bigtcp::{
+    errors::ConfigError,
+    iface::InterfaceType,
+    wire::{Ipv4Address, Ipv4Cidr, Ipv6Cidr},
+};
+
+use super::request::GetResponse;
+use crate::{
+    net::{
+        iface::{iface_by_index, Iface, IFACES},
+        socket::netlink::message::{parse_payload, Attrs, NlMsgFlags, PayloadBuilder},
+    },
+    prelude::*,
+    util::net::CSocketAddrFamily,
+};
+
+/// The fixed-size structure of address messages (`struct ifaddrmsg`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIfAddrMsg {
+    family: u8,
+    prefix_len: u8,
+    flags: u8,
+    scope: u8,
+    index: u32,
+}
+
+// The attribute types of address messages.
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_LABEL: u16 = 3;
+const IFA_BROADCAST: u16 = 4;
+
+/// The address flag that indicates the address is not managed by the kernel.
+const IFA_F_PERMANENT: u8 = 0x80;
+
+// The scopes of addresses and routes.
+pub(super) const RT_SCOPE_UNIVERSE: u8 = 0;
+pub(super) const RT_SCOPE_LINK: u8 = 253;
+pub(super) const RT_SCOPE_HOST: u8 = 254;
+
+pub(super) fn get_addr(payload: &[u8], flags: NlMsgFlags) -> Result<GetResponse> {
+    if !flags.intersects(NlMsgFlags::DUMP) {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "getting a single address is not supported"
+        );
+    }
+
+    // Only the family filter is supported. Some programs (e.g., BusyBox) send a
+    // `struct rtgenmsg`, which only contains the family.
+    let family = payload.first().copied().unwrap_or(0);
+    let (has_ipv4, has_ipv6) = match CSocketAddrFamily::try_from(family as i32) {
+        Ok(CSocketAddrFamily::AF_UNSPEC) => (true, true),
+        Ok(CSocketAddrFamily::AF_INET) => (true, false),
+        Ok(CSocketAddrFamily::AF_INET6) => (false, true),
+        _ => (false, false),
+    };
+
+    let mut ifaces = IFACES.read().clone();
+    ifaces.sort_by_key(|iface| iface.index());
+
+    // Like Linux, the IPv4 addresses of all ifaces are dumped before the IPv6 addresses.
+    let mut entries = Vec::new();
+    if has_ipv4 {
+        entries.extend(ifaces.iter().flat_map(|iface| {
+            iface
+                .ipv4_cidrs()
+                .into_iter()
+                .map(|cidr| build_addr(iface, cidr))
+        }));
+    }
+    if has_ipv6 {
+        entries.extend(ifaces.iter().flat_map(|iface| {
+            iface
+                .ipv6_cidrs()
+                .into_iter()
+                .map(|cidr| build_addr_v6(iface, cidr))
+        }));
+    }
+    Ok(GetResponse::Dump(entries))
+}
+
+pub(super) fn new_addr(payload: &[u8], flags: NlMsgFlags) -> Result<()> {
+    let (msg, attrs) = parse_payload::<CIfAddrMsg>(payload)?;
+    let (iface, addr) = parse_addr(&msg, &attrs)?;
+
+    if msg.prefix_len > 32 {
+        return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+    }
+    if addr.is_loopback() && msg.scope != RT_SCOPE_HOST {
+        return_errno_with_message!(Errno::EINVAL, "loopback addresses must have the host scope");
+    }
+    let cidr = Ipv4Cidr::new(addr, msg.prefix_len);
+
+    match iface.add_ipv4_cidr(cidr) {
+        Ok(()) => Ok(()),
+        Err(ConfigError::Exists) if flags.contains(NlMsgFlags::REPLACE) => {
+            // The address is only replaced if it exists, so the removal cannot fail.
+            iface.remove_ipv4_cidr(addr).unwrap();
+            iface
+                .add_ipv4_cidr(cidr)
+                .map_err(|_| Error::with_message(Errno::ENOSPC, "the address cannot be replaced"))
+        }
+        Err(ConfigError::Exists) => {
+            return_errno_with_message!(Errno::EEXIST, "the address already exists")
+        }
+        Err(ConfigError::Exhausted | ConfigError::NotFound) => {
+            return_errno_with_message!(Errno::ENOSPC, "there are too many addresses")
+        }
+    }
+}
+
+pub(super) fn del_addr(payload: &[u8]) -> Result<()> {
+    let (msg, attrs) = parse_payload::<CIfAddrMsg>(payload)?;
+    let (iface, addr) = parse_addr(&msg, &attrs)?;
+
+    iface
+        .remove_ipv4_cidr(addr)
+        .map_err(|_| Error::with_message(Errno::EADDRNOTAVAIL, "the address does not exist"))?;
+    Ok(())
+}
+
+/// Parses the iface and the IPv4 address in an address message.
+fn parse_addr(msg: &CIfAddrMsg, attrs: &Attrs) -> Result<(Arc<Iface>, Ipv4Address)> {
+    if msg.family != CSocketAddrFamily::AF_INET as u8 {
+        return_errno_with_message!(Errno::EAFNOSUPPORT, "only IPv4 addresses are supported");
+    }
+
+    let iface = iface_by_index(msg.index)
+        .ok_or_else(|| Error::with_message(Errno::ENODEV, "the link does not exist"))?;
+
+    // `IFA_LOCAL` is the address of the iface, while `IFA_ADDRESS` is the address of the peer for
+    // point-to-point links. They are the same for other links, so either of them can be used.
+    let Some(octets) = attrs
+        .get_pod::<[u8; 4]>(IFA_LOCAL)?
+        .or(attrs.get_pod::<[u8; 4]>(IFA_ADDRESS)?)
+    else {
+        return_errno_with_message!(Errno::EINVAL, "the address is not specified");
+    };
+
+    Ok((iface, Ipv4Address::from(octets)))
+}
+
+fn build_addr(iface: &Arc<Iface>, cidr: Ipv4Cidr) -> Vec<u8> {
+    let scope = if iface.type_() == InterfaceType::Loopback {
+        RT_SCOPE_HOST
+    } else {
+        RT_SCOPE_UNIVERSE
+    };
+
+    let msg = CIfAddrMsg {
+        family: CSocketAddrFamily::AF_INET as u8,
+        prefix_len: cidr.prefix_len(),
+        flags: IFA_F_PERMANENT,
+        scope,
+        index: iface.index(),
+    };
+
+    let addr = cidr.address().octets();
+    let mut builder = PayloadBuilder::new(&msg)
+        .attr(IFA_ADDRESS, &addr)
+        .attr(IFA_LOCAL, &addr);
+    if let Some(broadcast) = cidr.broadcast() {
+        builder = builder.attr(IFA_BROADCAST, &broadcast.octets());
+    }
+    builder.attr_str(IFA_LABEL, iface.name()).build()
+}
+
+fn build_addr_v6(iface: &Arc<Iface>, cidr: Ipv6Cidr) -> Vec<u8> {
+    let scope = if cidr.address().is_loopback() {
+        RT_SCOPE_HOST
+    } else if cidr.address().segments()[0] & 0xffc0 == 0xfe80 {
+        // This is a link-local address (i.e., in `fe80::/10`).
+        RT_SCOPE_LINK
+    } else {
+        RT_SCOPE_UNIVERSE
+    };
+
+    let msg = CIfAddrMsg {
+        family: CSocketAddrFamily::AF_INET6 as u8,
+        prefix_len: cidr.prefix_len(),
+        flags: IFA_F_PERMANENT,
+        scope,
+        index: iface.index(),
+    };
+
+    // Only `IFA_ADDRESS` is reported for IPv6 addresses, since there are no point-to-point links
+    // or broadcast addresses.
+    let addr = cidr.address().octets();
+    PayloadBuilder::new(&msg).attr(IFA_ADDRESS, &addr).build()
+}
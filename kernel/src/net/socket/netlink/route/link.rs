@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The rtnetlink requests about links, i.e., ifaces.
+
+use aster_bigtcp::iface::{InterfaceFlags, InterfaceType};
+
+use super::request::GetResponse;
+use crate::{
+    net::{
+        iface::{iface_by_index, iface_by_name, Iface, IFACES},
+        socket::netlink::message::{parse_payload, Attrs, NlMsgFlags, PayloadBuilder},
+    },
+    prelude::*,
+};
+
+/// The fixed-size structure of link messages (`struct ifinfomsg`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIfInfoMsg {
+    family: u8,
+    pad: u8,
+    type_: u16,
+    index: i32,
+    flags: u32,
+    change: u32,
+}
+
+// The attribute types of link messages.
+const IFLA_ADDRESS: u16 = 1;
+const IFLA_BROADCAST: u16 = 2;
+const IFLA_IFNAME: u16 = 3;
+const IFLA_MTU: u16 = 4;
+const IFLA_TXQLEN: u16 = 13;
+const IFLA_OPERSTATE: u16 = 16;
+
+// The operational states of links (RFC 2863).
+const IF_OPER_UNKNOWN: u8 = 0;
+const IF_OPER_DOWN: u8 = 2;
+const IF_OPER_UP: u8 = 6;
+
+/// The length of the transmission queues, which is the default of Linux.
+const TXQLEN: u32 = 1000;
+
+pub(super) fn get_link(payload: &[u8], flags: NlMsgFlags) -> Result<GetResponse> {
+    if flags.intersects(NlMsgFlags::DUMP) {
+        // Filters are not supported, so the payload is ignored. Some programs (e.g., BusyBox)
+        // send a `struct rtgenmsg`, which is shorter than `struct ifinfomsg`.
+        let mut ifaces = IFACES.read().clone();
+        ifaces.sort_by_key(|iface| iface.index());
+
+        let entries = ifaces.iter().map(build_link).collect();
+        return Ok(GetResponse::Dump(entries));
+    }
+
+    let (info, attrs) = parse_payload::<CIfInfoMsg>(payload)?;
+    let iface = find_iface(&info, &attrs)?;
+    Ok(GetResponse::Single(build_link(&iface)))
+}
+
+pub(super) fn change_link(payload: &[u8], flags: NlMsgFlags) -> Result<()> {
+    let (info, attrs) = parse_payload::<CIfInfoMsg>(payload)?;
+
+    let iface = match find_iface(&info, &attrs) {
+        Ok(iface) => iface,
+        Err(err) if err.error() == Errno::ENODEV && flags.contains(NlMsgFlags::CREATE) => {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "creating links is not supported");
+        }
+        Err(err) => return Err(err),
+    };
+    if flags.contains(NlMsgFlags::EXCL) {
+        return_errno_with_message!(Errno::EEXIST, "the link already exists");
+    }
+
+    if attrs
+        .get_pod::<u32>(IFLA_MTU)?
+        .is_some_and(|mtu| mtu as usize != iface.mtu())
+    {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "changing the MTU is not supported");
+    }
+    if info.index > 0
+        && attrs
+            .get_str(IFLA_IFNAME)?
+            .is_some_and(|name| name != iface.name())
+    {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "renaming links is not supported");
+    }
+    if attrs.get(IFLA_ADDRESS).is_some() {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "changing the hardware address is not supported"
+        );
+    }
+
+    // Like Linux, a zero change mask means that all the flags are changed. Only the `UP` flag can
+    // be changed, and the other flags are ignored.
+    let change = if info.change == 0 {
+        InterfaceFlags::all()
+    } else {
+        InterfaceFlags::from_bits_truncate(info.change)
+    };
+    if change.contains(InterfaceFlags::UP) {
+        let is_up = InterfaceFlags::from_bits_truncate(info.flags).contains(InterfaceFlags::UP);
+        if is_up != iface.flags().contains(InterfaceFlags::UP) {
+            iface.set_up(is_up);
+            // Transmit the packets that are queued while the iface is down.
+            iface.poll();
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the iface by the index, or by the name if the index is not specified.
+fn find_iface(info: &CIfInfoMsg, attrs: &Attrs) -> Result<Arc<Iface>> {
+    let iface = if info.index > 0 {
+        iface_by_index(info.index as u32)
+    } else if let Some(name) = attrs.get_str(IFLA_IFNAME)? {
+        iface_by_name(name)
+    } else {
+        None
+    };
+
+    iface.ok_or_else(|| Error::with_message(Errno::ENODEV, "the link does not exist"))
+}
+
+fn build_link(iface: &Arc<Iface>) -> Vec<u8> {
+    let type_ = iface.type_();
+    let flags = iface.flags();
+
+    let info = CIfInfoMsg {
+        family: 0,
+        pad: 0,
+        type_: type_ as u16,
+        index: iface.index() as i32,
+        flags: flags.bits(),
+        change: 0,
+    };
+
+    let oper_state = if type_ == InterfaceType::Loopback {
+        // Like Linux, the operational state of loopback links is unknown.
+        IF_OPER_UNKNOWN
+    } else if flags.contains(InterfaceFlags::RUNNING) {
+        IF_OPER_UP
+    } else {
+        IF_OPER_DOWN
+    };
+
+    let mut builder = PayloadBuilder::new(&info)
+        .attr_str(IFLA_IFNAME, iface.name())
+        .attr_pod(IFLA_MTU, &(iface.mtu() as u32))
+        .attr_pod(IFLA_TXQLEN, &TXQLEN)
+        .attr_pod(IFLA_OPERSTATE, &oper_state);
+
+    match type_ {
+        InterfaceType::Ether => {
+            let hardware_addr = iface.hardware_addr().map(|addr| addr.0).unwrap_or([0; 6]);
+            builder = builder
+                .attr(IFLA_ADDRESS, &hardware_addr)
+                .attr(IFLA_BROADCAST, &[0xff; 6]);
+        }
+        InterfaceType::Loopback => {
+            builder = builder
+                .attr(IFLA_ADDRESS, &[0; 6])
+                .attr(IFLA_BROADCAST, &[0; 6]);
+        }
+        InterfaceType::None => (),
+    }
+
+    builder.build()
+}
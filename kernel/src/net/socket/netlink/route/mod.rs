@@ -0,0 +1,337 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The rtnetlink sockets.
+//!
+//! The requests sent to the kernel are processed synchronously when they are sent, and the
+//! responses are queued in the socket until they are received.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::addr::{BoundPort, NetlinkSocketAddr};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata, StatusFlags},
+    },
+    match_sock_option_mut,
+    net::socket::{
+        options::{Error as SocketError, SocketOption},
+        util::{
+            options::{SetSocketLevelOption, SocketOptionSet},
+            send_recv_flags::SendRecvFlags,
+            socket_addr::SocketAddr,
+            MessageHeader,
+        },
+        Socket,
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    util::{MultiRead, MultiWrite},
+};
+
+mod address;
+mod link;
+mod request;
+mod routing;
+
+/// The maximum length of the requests in a single message.
+const MAX_REQUEST_LEN: usize = 65536;
+
+pub struct NetlinkRouteSocket {
+    inner: Mutex<Inner>,
+    receive_queue: Mutex<ReceiveQueue>,
+    options: RwLock<SocketOptionSet>,
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+}
+
+struct Inner {
+    bound_port: Option<BoundPort>,
+    /// The multicast groups.
+    ///
+    /// TODO: Send notifications to the multicast groups when the links, the addresses, or the
+    /// routes change.
+    groups: u32,
+}
+
+/// The queue of the responses that have not been received.
+struct ReceiveQueue {
+    messages: VecDeque<Vec<u8>>,
+    /// The total length of the messages.
+    len: usize,
+    /// Whether some responses are dropped because the queue is full.
+    is_overrun: bool,
+}
+
+impl NetlinkRouteSocket {
+    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+        Arc::new(Self {
+            inner: Mutex::new(Inner {
+                bound_port: None,
+                groups: 0,
+            }),
+            receive_queue: Mutex::new(ReceiveQueue {
+                messages: VecDeque::new(),
+                len: 0,
+                is_overrun: false,
+            }),
+            options: RwLock::new(SocketOptionSet::new_netlink()),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee: Pollee::new(),
+        })
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+
+    /// Binds the socket to an unused port ID if it is not bound yet.
+    ///
+    /// This method returns the bound port ID.
+    fn bind_ephemeral(&self) -> Result<u32> {
+        let mut inner = self.inner.lock();
+
+        if let Some(bound_port) = inner.bound_port.as_ref() {
+            return Ok(bound_port.port());
+        }
+
+        let bound_port = BoundPort::new(0)?;
+        let port = bound_port.port();
+        inner.bound_port = Some(bound_port);
+        Ok(port)
+    }
+
+    fn try_recv(&self, writer: &mut dyn MultiWrite, flags: SendRecvFlags) -> Result<usize> {
+        let mut receive_queue = self.receive_queue.lock();
+
+        if receive_queue.is_overrun {
+            receive_queue.is_overrun = false;
+            return_errno_with_message!(Errno::ENOBUFS, "some responses are dropped");
+        }
+
+        let Some(message) = receive_queue.messages.front() else {
+            return_errno_with_message!(Errno::EAGAIN, "the receive queue is empty");
+        };
+
+        let message_len = message.len();
+        let copied_len = writer.write(&mut VmReader::from(message.as_slice()))?;
+
+        if !flags.contains(SendRecvFlags::MSG_PEEK) {
+            receive_queue.messages.pop_front();
+            receive_queue.len -= message_len;
+        }
+
+        drop(receive_queue);
+        self.pollee.invalidate();
+
+        // Like other datagram sockets, the real length is returned with `MSG_TRUNC`.
+        if flags.contains(SendRecvFlags::MSG_TRUNC) {
+            Ok(message_len)
+        } else {
+            Ok(copied_len)
+        }
+    }
+
+    fn recv(&self, writer: &mut dyn MultiWrite, flags: SendRecvFlags) -> Result<usize> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_recv(writer, flags)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
+        }
+    }
+
+    fn send(&self, reader: &mut dyn MultiRead, remote: Option<NetlinkSocketAddr>) -> Result<usize> {
+        if remote.is_some_and(|remote| remote != NetlinkSocketAddr::kernel()) {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "sending messages to other sockets or groups is not supported"
+            );
+        }
+
+        let port = self.bind_ephemeral()?;
+
+        let len = reader.sum_lens();
+        if len > MAX_REQUEST_LEN {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+        }
+        let mut requests = vec![0u8; len];
+        reader.read(&mut VmWriter::from(requests.as_mut_slice()))?;
+
+        let responses = request::process_requests(&requests, port);
+
+        let recv_buf = self.options.read().recv_buf() as usize;
+        let mut receive_queue = self.receive_queue.lock();
+        for response in responses {
+            if receive_queue.len + response.len() > recv_buf {
+                receive_queue.is_overrun = true;
+                break;
+            }
+            receive_queue.len += response.len();
+            receive_queue.messages.push_back(response);
+        }
+        drop(receive_queue);
+
+        self.pollee.notify(IoEvents::IN);
+
+        Ok(len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let receive_queue = self.receive_queue.lock();
+
+        if receive_queue.messages.is_empty() && !receive_queue.is_overrun {
+            IoEvents::OUT
+        } else {
+            IoEvents::IN | IoEvents::OUT
+        }
+    }
+}
+
+impl Pollable for NetlinkRouteSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for NetlinkRouteSocket {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.recv(writer, SendRecvFlags::empty())
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        self.send(reader, None)
+    }
+
+    fn as_socket(self: Arc<Self>) -> Option<Arc<dyn Socket>> {
+        Some(self)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        // TODO: when we fully support O_ASYNC, return the flag
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            self.set_nonblocking(true);
+        } else {
+            self.set_nonblocking(false);
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "SockFS" and link `NetlinkRouteSocket` to it.
+        Metadata::new_socket(
+            0,
+            InodeMode::from_bits_truncate(0o140777),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl Socket for NetlinkRouteSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = NetlinkSocketAddr::try_from(socket_addr)?;
+
+        let mut inner = self.inner.lock();
+
+        match inner.bound_port.as_ref() {
+            Some(bound_port) if addr.port != 0 && addr.port != bound_port.port() => {
+                return_errno_with_message!(Errno::EINVAL, "the socket is already bound");
+            }
+            Some(_) => (),
+            None => inner.bound_port = Some(BoundPort::new(addr.port)?),
+        }
+        inner.groups = addr.groups;
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let inner = self.inner.lock();
+
+        let port = inner
+            .bound_port
+            .as_ref()
+            .map(|bound_port| bound_port.port())
+            .unwrap_or(0);
+        Ok(NetlinkSocketAddr::new(port, inner.groups).into())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        Ok(NetlinkSocketAddr::kernel().into())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = addr.map(NetlinkSocketAddr::try_from).transpose()?;
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        self.send(reader, remote)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        let received_len = self.recv(writer, flags)?;
+
+        let message_header =
+            MessageHeader::new(Some(NetlinkSocketAddr::kernel().into()), Vec::new());
+
+        Ok((received_len, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                self.options.write().get_and_clear_sock_errors(socket_errors);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        self.options.read().get_option(option)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let mut options = self.options.write();
+        let mut inner = self.inner.lock();
+
+        options.set_option(option, &mut *inner)?;
+        Ok(())
+    }
+}
+
+impl SetSocketLevelOption for Inner {}
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The processing of rtnetlink requests.
+//!
+//! See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/rtnetlink.h>.
+
+use super::{address, link, routing};
+use crate::{
+    net::{
+        iface::check_config_permission,
+        socket::netlink::message::{
+            build_message, split_messages, CNlMsgErr, CNlMsgHdr, NlMsgFlags, NLMSG_DONE,
+            NLMSG_ERROR, NLMSG_MIN_TYPE,
+        },
+    },
+    prelude::*,
+};
+
+/// The types of rtnetlink messages.
+///
+/// Only the supported types are listed.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+enum RtmType {
+    NewLink = 16,
+    DelLink = 17,
+    GetLink = 18,
+    SetLink = 19,
+    NewAddr = 20,
+    DelAddr = 21,
+    GetAddr = 22,
+    NewRoute = 24,
+    DelRoute = 25,
+    GetRoute = 26,
+}
+
+/// The result of a GET request.
+pub(super) enum GetResponse {
+    /// A single entry.
+    Single(Vec<u8>),
+    /// Multiple entries, which will be sent as a multipart message.
+    Dump(Vec<Vec<u8>>),
+}
+
+/// Processes the requests in the bytes and returns the responses.
+///
+/// `port` is the port ID of the socket that sends the requests.
+pub(super) fn process_requests(bytes: &[u8], port: u32) -> Vec<Vec<u8>> {
+    let mut responses = Vec::new();
+
+    for (header, payload) in split_messages(bytes) {
+        let flags = NlMsgFlags::from_bits_truncate(header.flags);
+
+        // Control messages and messages that are not requests are not processed, but they can
+        // still be acknowledged.
+        let result = if !flags.contains(NlMsgFlags::REQUEST) || header.type_ < NLMSG_MIN_TYPE {
+            Ok(false)
+        } else {
+            process_request(&header, payload, port, &mut responses)
+        };
+
+        match result {
+            // Like Linux, dumps are terminated by `NLMSG_DONE` and are not acknowledged.
+            Ok(is_dump) if flags.contains(NlMsgFlags::ACK) && !is_dump => {
+                responses.push(build_error(&header, 0, &[], port));
+            }
+            Ok(_) => (),
+            Err(err) => {
+                responses.push(build_error(&header, -(err.error() as i32), payload, port));
+            }
+        }
+    }
+
+    responses
+}
+
+/// Processes a request and appends the responses.
+///
+/// This method returns whether the request is a dump request.
+fn process_request(
+    header: &CNlMsgHdr,
+    payload: &[u8],
+    port: u32,
+    responses: &mut Vec<Vec<u8>>,
+) -> Result<bool> {
+    let Ok(type_) = RtmType::try_from(header.type_) else {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the message type is not supported");
+    };
+    let flags = NlMsgFlags::from_bits_truncate(header.flags);
+
+    let get_response = match type_ {
+        RtmType::GetLink => link::get_link(payload, flags)?,
+        RtmType::GetAddr => address::get_addr(payload, flags)?,
+        RtmType::GetRoute => routing::get_route(payload, flags)?,
+        RtmType::NewLink | RtmType::SetLink => {
+            check_config_permission()?;
+            link::change_link(payload, flags)?;
+            return Ok(false);
+        }
+        RtmType::DelLink => {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "deleting links is not supported");
+        }
+        RtmType::NewAddr => {
+            check_config_permission()?;
+            address::new_addr(payload, flags)?;
+            return Ok(false);
+        }
+        RtmType::DelAddr => {
+            check_config_permission()?;
+            address::del_addr(payload)?;
+            return Ok(false);
+        }
+        RtmType::NewRoute => {
+            check_config_permission()?;
+            routing::new_route(payload, flags)?;
+            return Ok(false);
+        }
+        RtmType::DelRoute => {
+            check_config_permission()?;
+            routing::del_route(payload)?;
+            return Ok(false);
+        }
+    };
+
+    // The responses to GET requests have the types of NEW requests.
+    let response_type = header.type_ - 2;
+
+    match get_response {
+        GetResponse::Single(response) => {
+            responses.push(build_message(
+                response_type,
+                NlMsgFlags::empty(),
+                header.seq,
+                port,
+                &response,
+            ));
+            Ok(false)
+        }
+        GetResponse::Dump(entries) => {
+            responses.extend(entries.iter().map(|entry| {
+                build_message(response_type, NlMsgFlags::MULTI, header.seq, port, entry)
+            }));
+            responses.push(build_message(
+                NLMSG_DONE,
+                NlMsgFlags::MULTI,
+                header.seq,
+                port,
+                0i32.as_bytes(),
+            ));
+            Ok(true)
+        }
+    }
+}
+
+/// Builds an error message, or an acknowledgment if the error number is zero.
+///
+/// Like Linux, the payload of the request is included only for errors.
+fn build_error(request: &CNlMsgHdr, error: i32, payload: &[u8], port: u32) -> Vec<u8> {
+    let err = CNlMsgErr {
+        error,
+        msg: *request,
+    };
+
+    let mut bytes = err.as_bytes().to_vec();
+    let flags = if error == 0 {
+        NlMsgFlags::CAPPED
+    } else {
+        bytes.extend_from_slice(payload);
+        NlMsgFlags::empty()
+    };
+
+    build_message(NLMSG_ERROR, flags, request.seq, port, &bytes)
+}
@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The rtnetlink requests about routes.
+//!
+//! There is only one routing table (see [`crate::net::iface::lookup_route`]), which is exposed as
+//! the main table.
+
+use aster_bigtcp::{
+    errors::ConfigError,
+    iface::{InterfaceType, Ipv4Route},
+    wire::{IpAddress, Ipv4Address, Ipv4Cidr},
+};
+
+use super::{
+    address::{RT_SCOPE_HOST, RT_SCOPE_LINK, RT_SCOPE_UNIVERSE},
+    request::GetResponse,
+};
+use crate::{
+    net::{
+        iface::{find_ipv4_route, iface_by_index, lookup_route, Iface, Route, IFACES},
+        socket::netlink::message::{parse_payload, Attrs, NlMsgFlags, PayloadBuilder},
+    },
+    prelude::*,
+    util::net::CSocketAddrFamily,
+};
+
+/// The fixed-size structure of route messages (`struct rtmsg`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CRtMsg {
+    family: u8,
+    dst_len: u8,
+    src_len: u8,
+    tos: u8,
+    table: u8,
+    protocol: u8,
+    scope: u8,
+    type_: u8,
+    flags: u32,
+}
+
+// The attribute types of route messages.
+const RTA_DST: u16 = 1;
+const RTA_OIF: u16 = 4;
+const RTA_GATEWAY: u16 = 5;
+const RTA_PREFSRC: u16 = 7;
+const RTA_TABLE: u16 = 15;
+
+// The routing tables.
+const RT_TABLE_UNSPEC: u32 = 0;
+const RT_TABLE_MAIN: u32 = 254;
+
+// The origins of routes.
+const RTPROT_KERNEL: u8 = 2;
+const RTPROT_BOOT: u8 = 3;
+
+/// The route type of unicast routes.
+const RTN_UNICAST: u8 = 1;
+
+pub(super) fn get_route(payload: &[u8], flags: NlMsgFlags) -> Result<GetResponse> {
+    if flags.intersects(NlMsgFlags::DUMP) {
+        // Only the family filter is supported. Some programs (e.g., BusyBox) send a
+        // `struct rtgenmsg`, which only contains the family.
+        let family = payload.first().copied().unwrap_or(0);
+        if family != CSocketAddrFamily::AF_UNSPEC as u8
+            && family != CSocketAddrFamily::AF_INET as u8
+        {
+            return Ok(GetResponse::Dump(Vec::new()));
+        }
+
+        return Ok(GetResponse::Dump(dump_routes()));
+    }
+
+    let (msg, attrs) = parse_payload::<CRtMsg>(payload)?;
+    if msg.family != CSocketAddrFamily::AF_INET as u8 {
+        return_errno_with_message!(Errno::EAFNOSUPPORT, "only IPv4 routes are supported");
+    }
+    let dst = attrs
+        .get_pod::<[u8; 4]>(RTA_DST)?
+        .map(Ipv4Address::from)
+        .unwrap_or(Ipv4Address::UNSPECIFIED);
+
+    let Some(Route { iface, gateway }) = lookup_route(&IpAddress::Ipv4(dst)) else {
+        return_errno_with_message!(Errno::ENETUNREACH, "there is no route to the destination");
+    };
+
+    let msg = CRtMsg {
+        family: CSocketAddrFamily::AF_INET as u8,
+        dst_len: 32,
+        src_len: 0,
+        tos: 0,
+        table: RT_TABLE_MAIN as u8,
+        protocol: 0,
+        scope: RT_SCOPE_UNIVERSE,
+        type_: RTN_UNICAST,
+        flags: 0,
+    };
+    let mut builder = PayloadBuilder::new(&msg)
+        .attr_pod(RTA_TABLE, &RT_TABLE_MAIN)
+        .attr(RTA_DST, &dst.octets())
+        .attr_pod(RTA_OIF, &iface.index());
+    if let Some(src) = iface.ipv4_addr() {
+        builder = builder.attr(RTA_PREFSRC, &src.octets());
+    }
+    if let Some(IpAddress::Ipv4(gateway)) = gateway {
+        builder = builder.attr(RTA_GATEWAY, &gateway.octets());
+    }
+
+    Ok(GetResponse::Single(builder.build()))
+}
+
+pub(super) fn new_route(payload: &[u8], flags: NlMsgFlags) -> Result<()> {
+    let (msg, attrs) = parse_payload::<CRtMsg>(payload)?;
+    let cidr = parse_route_dst(&msg, &attrs)?;
+
+    if msg.type_ != RTN_UNICAST {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "only unicast routes are supported");
+    }
+    let Some(gateway) = attrs.get_pod::<[u8; 4]>(RTA_GATEWAY)? else {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "only routes through gateways can be added"
+        );
+    };
+    let gateway = Ipv4Address::from(gateway);
+
+    // Like Linux, the gateway must be in a network that the iface is directly connected to.
+    let is_on_link = |iface: &Arc<Iface>| {
+        iface
+            .ipv4_cidrs()
+            .iter()
+            .any(|iface_cidr| iface_cidr.contains_addr(&gateway))
+    };
+    let iface = if let Some(index) = attrs.get_pod::<u32>(RTA_OIF)? {
+        let iface = iface_by_index(index)
+            .ok_or_else(|| Error::with_message(Errno::ENODEV, "the link does not exist"))?;
+        if !is_on_link(&iface) {
+            return_errno_with_message!(Errno::ENETUNREACH, "the gateway is unreachable");
+        }
+        iface
+    } else {
+        IFACES
+            .read()
+            .iter()
+            .find(|iface| is_on_link(iface))
+            .cloned()
+            .ok_or_else(|| Error::with_message(Errno::ENETUNREACH, "the gateway is unreachable"))?
+    };
+
+    match find_ipv4_route(cidr) {
+        Some(_) if flags.contains(NlMsgFlags::EXCL) || !flags.contains(NlMsgFlags::REPLACE) => {
+            return_errno_with_message!(Errno::EEXIST, "the route already exists");
+        }
+        Some((old_iface, _)) => {
+            // The route is found above, so the removal cannot fail.
+            old_iface.remove_ipv4_route(cidr).unwrap();
+        }
+        None if !flags.contains(NlMsgFlags::CREATE) => {
+            return_errno_with_message!(Errno::ENOENT, "the route does not exist");
+        }
+        None => (),
+    }
+
+    iface
+        .add_ipv4_route(Ipv4Route { cidr, gateway })
+        .map_err(|err| match err {
+            ConfigError::Exists => Error::with_message(Errno::EEXIST, "the route already exists"),
+            ConfigError::Exhausted | ConfigError::NotFound => {
+                Error::with_message(Errno::ENOSPC, "there are too many routes")
+            }
+        })
+}
+
+pub(super) fn del_route(payload: &[u8]) -> Result<()> {
+    let (msg, attrs) = parse_payload::<CRtMsg>(payload)?;
+    let cidr = parse_route_dst(&msg, &attrs)?;
+
+    let Some((iface, _)) = find_ipv4_route(cidr) else {
+        return_errno_with_message!(Errno::ESRCH, "the route does not exist");
+    };
+    // The route is found above, so the removal cannot fail.
+    iface.remove_ipv4_route(cidr).unwrap();
+
+    Ok(())
+}
+
+/// Parses the destination network in a route message.
+fn parse_route_dst(msg: &CRtMsg, attrs: &Attrs) -> Result<Ipv4Cidr> {
+    if msg.family != CSocketAddrFamily::AF_INET as u8 {
+        return_errno_with_message!(Errno::EAFNOSUPPORT, "only IPv4 routes are supported");
+    }
+
+    let table = attrs.get_pod::<u32>(RTA_TABLE)?.unwrap_or(msg.table as u32);
+    if table != RT_TABLE_UNSPEC && table != RT_TABLE_MAIN {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "only the main table is supported");
+    }
+
+    if msg.dst_len > 32 {
+        return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+    }
+    let dst = attrs
+        .get_pod::<[u8; 4]>(RTA_DST)?
+        .map(Ipv4Address::from)
+        .unwrap_or(Ipv4Address::UNSPECIFIED);
+    let cidr = Ipv4Cidr::new(dst, msg.dst_len);
+    if cidr.network().address() != dst {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the destination is not the address of a network"
+        );
+    }
+
+    Ok(cidr)
+}
+
+fn dump_routes() -> Vec<Vec<u8>> {
+    let mut ifaces = IFACES.read().clone();
+    ifaces.sort_by_key(|iface| iface.index());
+
+    let mut entries = Vec::new();
+
+    for iface in ifaces.iter() {
+        let scope = if iface.type_() == InterfaceType::Loopback {
+            RT_SCOPE_HOST
+        } else {
+            RT_SCOPE_LINK
+        };
+
+        for cidr in iface.ipv4_cidrs() {
+            let network = cidr.network();
+            let msg = CRtMsg {
+                family: CSocketAddrFamily::AF_INET as u8,
+                dst_len: network.prefix_len(),
+                src_len: 0,
+                tos: 0,
+                table: RT_TABLE_MAIN as u8,
+                protocol: RTPROT_KERNEL,
+                scope,
+                type_: RTN_UNICAST,
+                flags: 0,
+            };
+            let entry = PayloadBuilder::new(&msg)
+                .attr_pod(RTA_TABLE, &RT_TABLE_MAIN)
+                .attr(RTA_DST, &network.address().octets())
+                .attr(RTA_PREFSRC, &cidr.address().octets())
+                .attr_pod(RTA_OIF, &iface.index())
+                .build();
+            entries.push(entry);
+        }
+
+        for route in iface.ipv4_routes() {
+            let msg = CRtMsg {
+                family: CSocketAddrFamily::AF_INET as u8,
+                dst_len: route.cidr.prefix_len(),
+                src_len: 0,
+                tos: 0,
+                table: RT_TABLE_MAIN as u8,
+                protocol: RTPROT_BOOT,
+                scope: RT_SCOPE_UNIVERSE,
+                type_: RTN_UNICAST,
+                flags: 0,
+            };
+            let mut builder = PayloadBuilder::new(&msg).attr_pod(RTA_TABLE, &RT_TABLE_MAIN);
+            // Like Linux, the destination of default routes is omitted.
+            if route.cidr.prefix_len() > 0 {
+                builder = builder.attr(RTA_DST, &route.cidr.address().octets());
+            }
+            let entry = builder
+                .attr(RTA_GATEWAY, &route.gateway.octets())
+                .attr_pod(RTA_OIF, &iface.index())
+                .build();
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
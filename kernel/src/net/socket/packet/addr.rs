@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::EthernetAddress;
+
+use crate::{net::socket::SocketAddr, prelude::*};
+
+/// A packet socket address (i.e., a link-layer address).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketSocketAddr {
+    /// The protocol of the frames, in host byte order.
+    pub protocol: u16,
+    /// The index of the iface, which is zero for all the ifaces.
+    pub ifindex: u32,
+    /// The hardware type of the iface.
+    pub hardware_type: u16,
+    /// The type of the frame.
+    pub packet_type: PacketType,
+    /// The hardware address, if any.
+    ///
+    /// For received frames, this is the source address. For sent frames, this is the destination
+    /// address. For the address of the socket, this is the address of the iface.
+    pub hardware_addr: Option<EthernetAddress>,
+}
+
+/// The type of a frame, from the perspective of the receiver.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+pub enum PacketType {
+    /// The frame is sent to this host.
+    Host = 0,
+    /// The frame is sent to all hosts.
+    Broadcast = 1,
+    /// The frame is sent to a multicast group.
+    Multicast = 2,
+    /// The frame is sent to another host.
+    OtherHost = 3,
+    /// The frame is sent by this host.
+    Outgoing = 4,
+}
+
+impl TryFrom<SocketAddr> for PacketSocketAddr {
+    type Error = Error;
+
+    fn try_from(value: SocketAddr) -> Result<Self> {
+        let SocketAddr::Packet(packet_addr) = value else {
+            return_errno_with_message!(Errno::EINVAL, "invalid packet socket addr");
+        };
+        Ok(packet_addr)
+    }
+}
+
+impl From<PacketSocketAddr> for SocketAddr {
+    fn from(value: PacketSocketAddr) -> Self {
+        SocketAddr::Packet(value)
+    }
+}
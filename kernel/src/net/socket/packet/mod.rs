@@ -0,0 +1,481 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Packet sockets.
+//!
+//! Packet sockets send and receive frames at the link layer. They are used by programs that
+//! implement protocols in the user space, e.g., DHCP clients, which need to talk to the network
+//! before the iface has an IP address.
+//!
+//! Packet sockets that receive the frames of all protocols (`ETH_P_ALL`) also receive the frames
+//! sent by the ifaces, which makes it possible to capture the traffic like `tcpdump`.
+//!
+//! Currently, only ifaces that transmit Ethernet frames are supported. Like Linux, the frames on
+//! the loopback iface also have Ethernet headers, whose addresses are zeros.
+//!
+//! See <https://www.man7.org/linux/man-pages/man7/packet.7.html>.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_bigtcp::{
+    errors::packet::{RecvError, SendError},
+    iface::InterfaceFlags,
+    socket::FrameDirection,
+    wire::{EthernetAddress, EthernetFrame, EthernetProtocol},
+};
+
+pub use self::addr::{PacketSocketAddr, PacketType};
+use super::ip::datagram::DatagramObserver;
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata, StatusFlags},
+    },
+    match_sock_option_mut,
+    net::{
+        iface::{iface_by_index, Iface, PacketSocket as IfacePacketSocket, IFACES},
+        socket::{
+            options::{Error as SocketError, SocketOption},
+            util::{
+                options::{SetSocketLevelOption, SocketOptionSet},
+                send_recv_flags::SendRecvFlags,
+                socket_addr::SocketAddr,
+                MessageHeader,
+            },
+            Socket,
+        },
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+    util::{MultiRead, MultiWrite},
+};
+
+mod addr;
+
+/// The protocol that matches the frames of all protocols (`ETH_P_ALL`).
+const ETH_P_ALL: u16 = 0x0003;
+
+/// The length of Ethernet headers.
+const ETHER_HEADER_LEN: usize = 14;
+
+pub struct PacketSocket {
+    /// Whether the frames are sent and received with the link-layer headers (`SOCK_RAW`) or
+    /// without them (`SOCK_DGRAM`).
+    is_raw: bool,
+    inner: Mutex<Inner>,
+    options: RwLock<SocketOptionSet>,
+    is_nonblocking: AtomicBool,
+    pollee: Pollee,
+}
+
+struct Inner {
+    /// The protocol of the frames to receive, in host byte order.
+    ///
+    /// If the protocol is zero, no frames are received.
+    protocol: u16,
+    /// The index of the bound iface, which is zero for all the ifaces.
+    ifindex: u32,
+    /// The sockets that receive the frames from the ifaces.
+    iface_sockets: Vec<IfacePacketSocket>,
+}
+
+impl PacketSocket {
+    /// Creates a packet socket that receives the frames of the protocol from all the ifaces.
+    ///
+    /// The protocol is in host byte order. Like Linux, this requires the `CAP_NET_RAW`
+    /// capability.
+    pub fn new(is_raw: bool, protocol: u16, is_nonblocking: bool) -> Result<Arc<Self>> {
+        let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+        if !credentials.effective_capset().contains(CapSet::NET_RAW) {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "creating packet sockets requires CAP_NET_RAW"
+            );
+        }
+
+        let socket = Arc::new(Self {
+            is_raw,
+            inner: Mutex::new(Inner {
+                protocol: 0,
+                ifindex: 0,
+                iface_sockets: Vec::new(),
+            }),
+            options: RwLock::new(SocketOptionSet::new_packet()),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            pollee: Pollee::new(),
+        });
+        socket.rebind(&mut socket.inner.lock(), protocol, 0)?;
+
+        Ok(socket)
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nonblocking(&self, is_nonblocking: bool) {
+        self.is_nonblocking.store(is_nonblocking, Ordering::Relaxed);
+    }
+
+    /// Starts to receive the frames of the protocol from the iface with the index.
+    ///
+    /// The frames that have been received but not read are dropped.
+    fn rebind(&self, inner: &mut Inner, protocol: u16, ifindex: u32) -> Result<()> {
+        let ifaces = if ifindex == 0 {
+            IFACES.read().clone()
+        } else {
+            let iface = iface_by_index(ifindex)
+                .ok_or_else(|| Error::with_message(Errno::ENODEV, "the iface does not exist"))?;
+            vec![iface]
+        };
+
+        inner.iface_sockets.clear();
+        inner.protocol = protocol;
+        inner.ifindex = ifindex;
+
+        if protocol == 0 {
+            return Ok(());
+        }
+
+        let ether_protocol = if protocol == ETH_P_ALL {
+            None
+        } else {
+            Some(EthernetProtocol::from(protocol))
+        };
+        inner.iface_sockets = ifaces
+            .into_iter()
+            .map(|iface| {
+                IfacePacketSocket::new(
+                    iface,
+                    ether_protocol,
+                    DatagramObserver::new(self.pollee.clone()),
+                )
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, PacketSocketAddr)> {
+        let inner = self.inner.lock();
+
+        let result = inner.iface_sockets.iter().find_map(|iface_socket| {
+            let result = iface_socket.recv(|frame, direction| {
+                let remote = frame_source(iface_socket.iface(), frame, direction);
+
+                let data = if self.is_raw {
+                    frame
+                } else {
+                    &frame[ETHER_HEADER_LEN..]
+                };
+                let copied_len = writer.write(&mut VmReader::from(data))?;
+
+                // Like other datagram sockets, the real length is returned with `MSG_TRUNC`.
+                if flags.contains(SendRecvFlags::MSG_TRUNC) {
+                    Ok((data.len(), remote))
+                } else {
+                    Ok((copied_len, remote))
+                }
+            });
+
+            match result {
+                Ok(result) => Some(result),
+                Err(RecvError::Exhausted) => None,
+            }
+        });
+
+        drop(inner);
+        self.pollee.invalidate();
+
+        result.unwrap_or_else(|| {
+            Err(Error::with_message(
+                Errno::EAGAIN,
+                "the receive queue is empty",
+            ))
+        })
+    }
+
+    fn recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, PacketSocketAddr)> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_recv(writer, flags)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
+        }
+    }
+
+    fn send(&self, reader: &mut dyn MultiRead, remote: Option<PacketSocketAddr>) -> Result<usize> {
+        let (protocol, ifindex) = {
+            let inner = self.inner.lock();
+            match remote {
+                Some(remote) => (remote.protocol, remote.ifindex),
+                None => (inner.protocol, inner.ifindex),
+            }
+        };
+
+        if ifindex == 0 {
+            return_errno_with_message!(Errno::ENXIO, "the iface is not specified");
+        }
+        let iface = iface_by_index(ifindex)
+            .ok_or_else(|| Error::with_message(Errno::ENXIO, "the iface does not exist"))?;
+        if !iface.flags().contains(InterfaceFlags::UP) {
+            return_errno_with_message!(Errno::ENETDOWN, "the iface is down");
+        }
+
+        let len = reader.sum_lens();
+
+        let result = if self.is_raw {
+            if len < ETHER_HEADER_LEN {
+                return_errno_with_message!(Errno::EINVAL, "the frame is too short");
+            }
+
+            iface.send_frame(len, |frame| {
+                // FIXME: If copy failed, we should not send any frame.
+                reader.read(&mut VmWriter::from(frame))?;
+                Ok(len)
+            })
+        } else {
+            let Some(dst_addr) = remote.and_then(|remote| remote.hardware_addr) else {
+                return_errno_with_message!(
+                    Errno::EDESTADDRREQ,
+                    "the destination address is not specified"
+                );
+            };
+            let src_addr = iface.hardware_addr().unwrap_or(EthernetAddress([0; 6]));
+            if len > iface.mtu() {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+            }
+
+            iface.send_frame(ETHER_HEADER_LEN + len, |frame| {
+                let mut frame = EthernetFrame::new_unchecked(frame);
+                frame.set_dst_addr(dst_addr);
+                frame.set_src_addr(src_addr);
+                frame.set_ethertype(EthernetProtocol::from(protocol));
+                // FIXME: If copy failed, we should not send any frame.
+                reader.read(&mut VmWriter::from(frame.payload_mut()))?;
+                Ok(len)
+            })
+        };
+
+        let sent_len = match result {
+            Ok(result) => result?,
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too long")
+            }
+            Err(SendError::Unsupported) => {
+                return_errno_with_message!(
+                    Errno::EOPNOTSUPP,
+                    "the iface does not support sending frames"
+                )
+            }
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::ENOBUFS, "the transmission queue is full")
+            }
+        };
+
+        iface.poll();
+
+        Ok(sent_len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+
+        if inner
+            .iface_sockets
+            .iter()
+            .any(|iface_socket| iface_socket.can_recv())
+        {
+            IoEvents::IN | IoEvents::OUT
+        } else {
+            IoEvents::OUT
+        }
+    }
+}
+
+/// Returns the address of the source of the received frame.
+fn frame_source(iface: &Arc<Iface>, frame: &[u8], direction: FrameDirection) -> PacketSocketAddr {
+    let frame = EthernetFrame::new_unchecked(frame);
+
+    let dst_addr = frame.dst_addr();
+    let packet_type = if direction == FrameDirection::Outgoing {
+        PacketType::Outgoing
+    } else if dst_addr.is_broadcast() {
+        PacketType::Broadcast
+    } else if dst_addr.is_multicast() {
+        PacketType::Multicast
+    } else if iface.hardware_addr().unwrap_or(EthernetAddress([0; 6])) == dst_addr {
+        PacketType::Host
+    } else {
+        PacketType::OtherHost
+    };
+
+    PacketSocketAddr {
+        protocol: frame.ethertype().into(),
+        ifindex: iface.index(),
+        hardware_type: iface.type_() as u16,
+        packet_type,
+        hardware_addr: Some(frame.src_addr()),
+    }
+}
+
+impl Pollable for PacketSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for PacketSocket {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.recv(writer, SendRecvFlags::empty())
+            .map(|(len, _)| len)
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        self.send(reader, None)
+    }
+
+    fn as_socket(self: Arc<Self>) -> Option<Arc<dyn Socket>> {
+        Some(self)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        // TODO: when we fully support O_ASYNC, return the flag
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        if new_flags.contains(StatusFlags::O_NONBLOCK) {
+            self.set_nonblocking(true);
+        } else {
+            self.set_nonblocking(false);
+        }
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "SockFS" and link `PacketSocket` to it.
+        Metadata::new_socket(
+            0,
+            InodeMode::from_bits_truncate(0o140777),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl Socket for PacketSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = PacketSocketAddr::try_from(socket_addr)?;
+
+        let mut inner = self.inner.lock();
+
+        // Like Linux, a zero protocol means that the protocol is not changed.
+        let protocol = if addr.protocol == 0 {
+            inner.protocol
+        } else {
+            addr.protocol
+        };
+        self.rebind(&mut inner, protocol, addr.ifindex)
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let inner = self.inner.lock();
+
+        let iface = iface_by_index(inner.ifindex);
+        let addr = PacketSocketAddr {
+            protocol: inner.protocol,
+            ifindex: inner.ifindex,
+            hardware_type: iface
+                .as_ref()
+                .map(|iface| iface.type_() as u16)
+                .unwrap_or(0),
+            packet_type: PacketType::Host,
+            hardware_addr: iface.and_then(|iface| iface.hardware_addr()),
+        };
+        Ok(addr.into())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = addr.map(PacketSocketAddr::try_from).transpose()?;
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        self.send(reader, remote)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_len, remote) = self.recv(writer, flags)?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(remote.into()), Vec::new());
+
+        Ok((received_len, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            socket_errors: SocketError => {
+                self.options.write().get_and_clear_sock_errors(socket_errors);
+                return Ok(());
+            },
+            _ => ()
+        });
+
+        self.options.read().get_option(option)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let mut options = self.options.write();
+        let mut inner = self.inner.lock();
+
+        options.set_option(option, &mut *inner)?;
+        Ok(())
+    }
+}
+
+impl SetSocketLevelOption for Inner {}
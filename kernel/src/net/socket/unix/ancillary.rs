@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::UnixCredentials;
+use crate::{fs::file_handle::FileLike, net::socket::ControlMessage, prelude::*};
+
+/// The ancillary data attached to the data sent over a UNIX domain socket.
+///
+/// Note that passing a socket over itself (directly or indirectly) creates a reference cycle.
+/// Unlike Linux, we do not have a garbage collector for such in-flight files.
+#[derive(Clone)]
+pub(super) struct AncillaryData {
+    /// The credentials of the sender.
+    cred: UnixCredentials,
+    /// The files passed with `SCM_RIGHTS`.
+    files: Option<Vec<Arc<dyn FileLike>>>,
+}
+
+impl AncillaryData {
+    pub(super) fn new(cred: UnixCredentials, files: Option<Vec<Arc<dyn FileLike>>>) -> Self {
+        Self { cred, files }
+    }
+
+    /// Creates the ancillary data from the control messages sent by the current process.
+    ///
+    /// If no credentials are specified, the credentials of the current process are used.
+    pub(super) fn from_control_messages(messages: Vec<ControlMessage>) -> Self {
+        let mut cred = None;
+        let mut files = None;
+
+        for message in messages {
+            match message {
+                ControlMessage::Credentials(specified) => cred = Some(specified),
+                ControlMessage::Rights(rights) => files = Some(rights),
+            }
+        }
+
+        Self {
+            cred: cred.unwrap_or_else(UnixCredentials::new_current),
+            files,
+        }
+    }
+
+    pub(super) fn cred(&self) -> UnixCredentials {
+        self.cred
+    }
+
+    pub(super) fn has_files(&self) -> bool {
+        self.files.is_some()
+    }
+
+    /// Takes the files, which can only be received once.
+    pub(super) fn take_files(&mut self) -> Option<Vec<Arc<dyn FileLike>>> {
+        self.files.take()
+    }
+
+    /// Converts the ancillary data into the control messages for the receiver.
+    ///
+    /// Like Linux, the credentials are only reported if the receiver has enabled
+    /// `SO_PASSCRED`.
+    pub(super) fn into_control_messages(self, is_pass_cred: bool) -> Vec<ControlMessage> {
+        let mut messages = Vec::new();
+
+        if is_pass_cred {
+            messages.push(ControlMessage::Credentials(self.cred));
+        }
+        if let Some(files) = self.files {
+            messages.push(ControlMessage::Rights(files));
+        }
+
+        messages
+    }
+}
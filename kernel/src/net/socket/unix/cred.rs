@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet, posix_thread::AsPosixThread, process_table, Gid, Pid,
+        Uid,
+    },
+};
+
+/// The credentials of a process that uses a UNIX domain socket.
+///
+/// For connected sockets, the credentials of the peer are recorded when the connection is
+/// established, which can be retrieved with the `SO_PEERCRED` socket option. The credentials of
+/// the sender are also attached to each message, which can be received as `SCM_CREDENTIALS`
+/// control messages if `SO_PASSCRED` is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnixCredentials {
+    pid: Pid,
+    uid: Uid,
+    gid: Gid,
+}
+
+impl UnixCredentials {
+    /// Creates the credentials of the current process.
+    pub(super) fn new_current() -> Self {
+        let current = current_thread!();
+        let posix_thread = current.as_posix_thread().unwrap();
+        let credentials = posix_thread.credentials();
+
+        Self {
+            pid: posix_thread.process().pid(),
+            uid: credentials.euid(),
+            gid: credentials.egid(),
+        }
+    }
+
+    /// Creates the credentials that are specified by the current process.
+    ///
+    /// This method is used for `SCM_CREDENTIALS` control messages. The PID is in the PID namespace
+    /// of the current process.
+    ///
+    /// Like Linux, unprivileged processes can only specify their own PID and one of their real,
+    /// effective, and saved UIDs (and GIDs).
+    pub fn new_specified(local_pid: Pid, uid: Uid, gid: Gid) -> Result<Self> {
+        let current = current_thread!();
+        let posix_thread = current.as_posix_thread().unwrap();
+        let process = posix_thread.process();
+        let credentials = posix_thread.credentials();
+        let capset = credentials.effective_capset();
+
+        let pid_ns = process.pid_ns();
+        let is_pid_valid =
+            pid_ns.local_id(process.pid()) == Some(local_pid) || capset.contains(CapSet::SYS_ADMIN);
+        let is_uid_valid = [credentials.ruid(), credentials.euid(), credentials.suid()]
+            .contains(&uid)
+            || capset.contains(CapSet::SETUID);
+        let is_gid_valid = [credentials.rgid(), credentials.egid(), credentials.sgid()]
+            .contains(&gid)
+            || capset.contains(CapSet::SETGID);
+        if !is_pid_valid || !is_uid_valid || !is_gid_valid {
+            return_errno_with_message!(Errno::EPERM, "the specified credentials are not allowed");
+        }
+
+        let Some(pid) = pid_ns
+            .global_id(local_pid)
+            .filter(|pid| process_table::get_process(*pid).is_some())
+        else {
+            return_errno_with_message!(Errno::ESRCH, "the specified process does not exist");
+        };
+
+        Ok(Self { pid, uid, gid })
+    }
+
+    /// Creates the credentials that represent an unknown process.
+    ///
+    /// Like Linux, the PID is zero, and the UID and GID are `-1`.
+    pub(super) const fn new_unknown() -> Self {
+        Self {
+            pid: 0,
+            uid: Uid::new(u32::MAX),
+            gid: Gid::new(u32::MAX),
+        }
+    }
+
+    /// Returns the global PID.
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    /// Returns the effective UID.
+    pub fn uid(&self) -> Uid {
+        self.uid
+    }
+
+    /// Returns the effective GID.
+    pub fn gid(&self) -> Gid {
+        self.gid
+    }
+}
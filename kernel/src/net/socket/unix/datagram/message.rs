@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::sync::WaitQueue;
+
+use crate::{
+    events::IoEvents,
+    net::socket::unix::{ancillary::AncillaryData, UnixSocketAddr},
+    prelude::*,
+    process::signal::{PollHandle, Pollee},
+};
+
+/// A datagram sent to a UNIX datagram socket.
+pub(super) struct Message {
+    data: Vec<u8>,
+    /// The address of the sending socket.
+    src_addr: UnixSocketAddr,
+    ancillary: AncillaryData,
+}
+
+impl Message {
+    pub(super) fn new(data: Vec<u8>, src_addr: UnixSocketAddr, ancillary: AncillaryData) -> Self {
+        Self {
+            data,
+            src_addr,
+            ancillary,
+        }
+    }
+
+    pub(super) fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub(super) fn src_addr(&self) -> &UnixSocketAddr {
+        &self.src_addr
+    }
+
+    pub(super) fn into_ancillary(self) -> AncillaryData {
+        self.ancillary
+    }
+}
+
+/// The queue of the datagrams that are waiting to be received by a UNIX datagram socket.
+pub(super) struct MessageQueue {
+    messages: SpinLock<Messages>,
+    /// The queue of the socket that the owner is connected to, if any.
+    ///
+    /// A connected socket only accepts datagrams from its peer.
+    peer: SpinLock<Option<Weak<MessageQueue>>>,
+    pollee: Pollee,
+    /// The wait queue for the senders that are waiting for free space.
+    wait_queue: WaitQueue,
+}
+
+struct Messages {
+    queue: VecDeque<Message>,
+    /// The total length of the queued datagrams.
+    total_len: usize,
+    is_shutdown: bool,
+    is_closed: bool,
+}
+
+/// The maximum total length of the queued datagrams, which is the default receive buffer size in
+/// Linux.
+pub(super) const QUEUE_CAPACITY: usize = 212992;
+
+/// The maximum number of the queued datagrams.
+///
+/// This is the value that most Linux distributions set for `net.unix.max_dgram_qlen`. It prevents
+/// zero-length datagrams from piling up indefinitely.
+const MAX_QUEUED_MESSAGES: usize = 512;
+
+impl MessageQueue {
+    pub(super) fn new() -> Self {
+        Self {
+            messages: SpinLock::new(Messages {
+                queue: VecDeque::new(),
+                total_len: 0,
+                is_shutdown: false,
+                is_closed: false,
+            }),
+            peer: SpinLock::new(None),
+            pollee: Pollee::new(),
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Restricts the queue to only accept datagrams from the peer's socket.
+    pub(super) fn set_peer(&self, peer: &Arc<MessageQueue>) {
+        *self.peer.lock() = Some(Arc::downgrade(peer));
+    }
+
+    /// Tries to push a datagram sent by the socket that owns `sender`.
+    pub(super) fn try_push(
+        &self,
+        message: Message,
+        sender: &Arc<MessageQueue>,
+    ) -> core::result::Result<(), (Error, Message)> {
+        if self
+            .peer
+            .lock()
+            .as_ref()
+            .is_some_and(|peer| !core::ptr::eq(peer.as_ptr(), Arc::as_ptr(sender)))
+        {
+            return Err((
+                Error::with_message(
+                    Errno::EPERM,
+                    "the receiving socket is connected to another socket",
+                ),
+                message,
+            ));
+        }
+
+        let mut messages = self.messages.lock();
+
+        if messages.is_closed {
+            return Err((
+                Error::with_message(Errno::ECONNREFUSED, "the receiving socket is closed"),
+                message,
+            ));
+        }
+        if messages.is_shutdown {
+            return Err((
+                Error::with_message(
+                    Errno::EPIPE,
+                    "the receiving socket is shut down for reading",
+                ),
+                message,
+            ));
+        }
+        if messages.total_len + message.data.len() > QUEUE_CAPACITY
+            || messages.queue.len() >= MAX_QUEUED_MESSAGES
+        {
+            return Err((
+                Error::with_message(Errno::EAGAIN, "the receive queue is full"),
+                message,
+            ));
+        }
+
+        messages.total_len += message.data.len();
+        messages.queue.push_back(message);
+        self.pollee.notify(IoEvents::IN);
+
+        Ok(())
+    }
+
+    /// Tries to pop a datagram.
+    ///
+    /// - Returns `Ok(Some(_))` with the popped datagram if successful.
+    /// - Returns `Ok(None)` if the queue is shut down and there is no datagram left.
+    /// - Returns `Err(EAGAIN)` if the queue is empty.
+    pub(super) fn try_pop(&self) -> Result<Option<Message>> {
+        let mut messages = self.messages.lock();
+
+        let Some(message) = messages.queue.pop_front() else {
+            if messages.is_shutdown {
+                return Ok(None);
+            }
+            return_errno_with_message!(Errno::EAGAIN, "the receive queue is empty");
+        };
+        messages.total_len -= message.data.len();
+
+        drop(messages);
+
+        self.pollee.invalidate();
+        self.wait_queue.wake_all();
+
+        Ok(Some(message))
+    }
+
+    /// Shuts down the queue for reading.
+    ///
+    /// Subsequent attempts to push datagrams will fail with `EPIPE`.
+    pub(super) fn shutdown(&self) {
+        self.messages.lock().is_shutdown = true;
+        self.pollee.notify(IoEvents::IN | IoEvents::RDHUP);
+        self.wait_queue.wake_all();
+    }
+
+    /// Closes the queue because the owner is closed.
+    ///
+    /// Subsequent attempts to push datagrams will fail with `ECONNREFUSED`.
+    pub(super) fn close(&self) {
+        let mut messages = self.messages.lock();
+        messages.is_closed = true;
+        messages.queue.clear();
+        messages.total_len = 0;
+        drop(messages);
+
+        self.wait_queue.wake_all();
+    }
+
+    pub(super) fn is_shutdown(&self) -> bool {
+        self.messages.lock().is_shutdown
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.messages.lock().queue.is_empty()
+    }
+
+    pub(super) fn poll<F>(
+        &self,
+        mask: IoEvents,
+        poller: Option<&mut PollHandle>,
+        check: F,
+    ) -> IoEvents
+    where
+        F: FnOnce() -> IoEvents,
+    {
+        self.pollee.poll_with(mask, poller, check)
+    }
+
+    pub(super) fn notify(&self, events: IoEvents) {
+        self.pollee.notify(events);
+    }
+
+    /// Waits until `cond` does not fail with `EAGAIN`.
+    ///
+    /// The condition is checked again whenever a datagram is popped from the queue.
+    pub(super) fn pause_until<F, R>(&self, mut cond: F) -> Result<R>
+    where
+        F: FnMut() -> Result<R>,
+    {
+        self.wait_queue.pause_until(|| match cond() {
+            Err(err) if err.error() == Errno::EAGAIN => None,
+            result => Some(result),
+        })?
+    }
+}
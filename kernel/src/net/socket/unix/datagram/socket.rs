@@ -0,0 +1,431 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::message::{Message, MessageQueue, QUEUE_CAPACITY};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        utils::{InodeMode, Metadata, StatusFlags},
+    },
+    match_sock_option_mut, match_sock_option_ref,
+    net::socket::{
+        options::{PassCred, PeerCred, SocketOption},
+        unix::{
+            addr::{UnixSocketAddrBound, UnixSocketAddrKey},
+            ancillary::AncillaryData,
+            UnixCredentials, UnixSocketAddr,
+        },
+        util::{send_recv_flags::SendRecvFlags, socket_addr::SocketAddr, MessageHeader},
+        SockShutdownCmd, Socket,
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+    util::{MultiRead, MultiWrite},
+};
+
+/// A UNIX domain socket of the `SOCK_DGRAM` type.
+pub struct UnixDatagramSocket {
+    inner: Mutex<Inner>,
+    queue: Arc<MessageQueue>,
+    is_nonblocking: AtomicBool,
+    is_write_shutdown: AtomicBool,
+    is_pass_cred: AtomicBool,
+}
+
+struct Inner {
+    addr: Option<UnixSocketAddrBound>,
+    peer: Option<Peer>,
+}
+
+/// The socket that a UNIX datagram socket is connected to.
+struct Peer {
+    queue: Weak<MessageQueue>,
+    addr: Option<UnixSocketAddrBound>,
+    cred: UnixCredentials,
+}
+
+impl UnixDatagramSocket {
+    pub fn new(is_nonblocking: bool) -> Arc<Self> {
+        Arc::new(Self {
+            inner: Mutex::new(Inner {
+                addr: None,
+                peer: None,
+            }),
+            queue: Arc::new(MessageQueue::new()),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            is_write_shutdown: AtomicBool::new(false),
+            is_pass_cred: AtomicBool::new(false),
+        })
+    }
+
+    pub fn new_pair(is_nonblocking: bool) -> (Arc<Self>, Arc<Self>) {
+        let socket_a = Self::new(is_nonblocking);
+        let socket_b = Self::new(is_nonblocking);
+
+        let cred = UnixCredentials::new_current();
+        socket_a.connect_to(&socket_b.queue, None, cred);
+        socket_b.connect_to(&socket_a.queue, None, cred);
+
+        (socket_a, socket_b)
+    }
+
+    fn connect_to(
+        &self,
+        peer_queue: &Arc<MessageQueue>,
+        peer_addr: Option<UnixSocketAddrBound>,
+        peer_cred: UnixCredentials,
+    ) {
+        self.queue.set_peer(peer_queue);
+        self.inner.lock().peer = Some(Peer {
+            queue: Arc::downgrade(peer_queue),
+            addr: peer_addr,
+            cred: peer_cred,
+        });
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) {
+        self.is_nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+
+    fn is_pass_cred(&self) -> bool {
+        self.is_pass_cred.load(Ordering::Relaxed)
+    }
+
+    fn send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: Option<UnixSocketAddr>,
+        ancillary: AncillaryData,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        let len = reader.sum_lens();
+        if len > QUEUE_CAPACITY {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+        }
+
+        if self.is_write_shutdown.load(Ordering::Relaxed) {
+            return_errno_with_message!(Errno::EPIPE, "the socket is shut down for writing");
+        }
+
+        let (receiver, src_addr) = {
+            let inner = self.inner.lock();
+
+            let receiver = match remote {
+                Some(remote_addr) => lookup_queue(&remote_addr.connect()?)?,
+                None => {
+                    let Some(peer) = inner.peer.as_ref() else {
+                        return_errno_with_message!(Errno::ENOTCONN, "the socket is not connected");
+                    };
+                    peer.queue.upgrade().ok_or_else(|| {
+                        Error::with_message(Errno::ECONNREFUSED, "the peer socket is closed")
+                    })?
+                }
+            };
+            let src_addr = UnixSocketAddr::from(inner.addr.clone());
+
+            (receiver, src_addr)
+        };
+
+        let mut data = vec![0; len];
+        reader.read(&mut VmWriter::from(data.as_mut_slice()))?;
+        let mut message = Some(Message::new(data, src_addr, ancillary));
+
+        let mut try_push = || {
+            receiver
+                .try_push(message.take().unwrap(), &self.queue)
+                .map_err(|(err, unsent)| {
+                    message = Some(unsent);
+                    err
+                })
+        };
+
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            try_push()?;
+        } else {
+            receiver.pause_until(try_push)?;
+        }
+
+        Ok(len)
+    }
+
+    fn recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr, Option<AncillaryData>)> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_recv(writer, flags)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
+        }
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, SocketAddr, Option<AncillaryData>)> {
+        let Some(message) = self.queue.try_pop()? else {
+            return Ok((0, UnixSocketAddr::Unnamed.into(), None));
+        };
+
+        let data = message.data();
+        let read_len = writer.write(&mut VmReader::from(data))?;
+
+        // Like other datagram sockets, the real length is returned with `MSG_TRUNC`.
+        let len = if flags.contains(SendRecvFlags::MSG_TRUNC) {
+            data.len()
+        } else {
+            read_len
+        };
+
+        let src_addr = message.src_addr().clone().into();
+        Ok((len, src_addr, Some(message.into_ancillary())))
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::OUT;
+
+        let is_read_shutdown = self.queue.is_shutdown();
+        if is_read_shutdown {
+            events |= IoEvents::IN | IoEvents::RDHUP;
+        } else if !self.queue.is_empty() {
+            events |= IoEvents::IN;
+        }
+
+        if is_read_shutdown && self.is_write_shutdown.load(Ordering::Relaxed) {
+            events |= IoEvents::HUP;
+        }
+
+        events
+    }
+}
+
+impl Drop for UnixDatagramSocket {
+    fn drop(&mut self) {
+        if let Some(addr) = self.inner.get_mut().addr.as_ref() {
+            DATAGRAM_TABLE.remove(&addr.to_key());
+        }
+
+        self.queue.close();
+    }
+}
+
+impl Pollable for UnixDatagramSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.queue.poll(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for UnixDatagramSocket {
+    fn as_socket(self: Arc<Self>) -> Option<Arc<dyn Socket>> {
+        Some(self)
+    }
+
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.recv(writer, SendRecvFlags::empty())
+            .map(|(len, _, _)| len)
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        let ancillary = AncillaryData::from_control_messages(Vec::new());
+        self.send(reader, None, ancillary, SendRecvFlags::empty())
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.set_nonblocking(new_flags.contains(StatusFlags::O_NONBLOCK));
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "SockFS" and link `UnixDatagramSocket` to it.
+        Metadata::new_socket(
+            0,
+            InodeMode::from_bits_truncate(0o140777),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+impl Socket for UnixDatagramSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = UnixSocketAddr::try_from(socket_addr)?;
+
+        let mut inner = self.inner.lock();
+        if inner.addr.is_some() {
+            return addr.bind_unnamed();
+        }
+
+        let bound_addr = addr.bind()?;
+        DATAGRAM_TABLE.insert(bound_addr.to_key(), bound_addr.clone(), self.queue.clone());
+        inner.addr = Some(bound_addr);
+
+        Ok(())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let remote_key = UnixSocketAddr::try_from(socket_addr)?.connect()?;
+        let (peer_addr, peer_queue) = DATAGRAM_TABLE.get(&remote_key)?;
+
+        // Linux does not record the credentials of the peer for connected datagram sockets.
+        self.connect_to(&peer_queue, Some(peer_addr), UnixCredentials::new_unknown());
+
+        Ok(())
+    }
+
+    fn shutdown(&self, cmd: SockShutdownCmd) -> Result<()> {
+        if cmd.shut_read() {
+            self.queue.shutdown();
+        }
+
+        if cmd.shut_write() {
+            self.is_write_shutdown.store(true, Ordering::Relaxed);
+            self.queue.notify(IoEvents::HUP);
+        }
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        Ok(self.inner.lock().addr.clone().into())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        let inner = self.inner.lock();
+
+        let Some(peer) = inner.peer.as_ref() else {
+            return_errno_with_message!(Errno::ENOTCONN, "the socket is not connected");
+        };
+
+        Ok(peer.addr.clone().into())
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            peer_cred: PeerCred => {
+                let cred = match self.inner.lock().peer.as_ref() {
+                    Some(peer) => peer.cred,
+                    None => UnixCredentials::new_unknown(),
+                };
+                peer_cred.set(cred);
+            },
+            pass_cred: PassCred => {
+                pass_cred.set(self.is_pass_cred());
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
+        });
+
+        Ok(())
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            pass_cred: PassCred => {
+                let is_pass_cred = *pass_cred.get().unwrap();
+                self.is_pass_cred.store(is_pass_cred, Ordering::Relaxed);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
+        });
+
+        Ok(())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = addr.map(UnixSocketAddr::try_from).transpose()?;
+        let ancillary = AncillaryData::from_control_messages(control_messages);
+
+        self.send(reader, remote, ancillary, flags)
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (received_len, remote, ancillary) = self.recv(writer, flags)?;
+
+        let control_messages = ancillary
+            .map(|ancillary| ancillary.into_control_messages(self.is_pass_cred()))
+            .unwrap_or_default();
+
+        // Like Linux, no address is reported for datagrams sent by unbound sockets.
+        let remote = match remote {
+            SocketAddr::Unix(UnixSocketAddr::Unnamed) => None,
+            remote => Some(remote),
+        };
+        let message_header = MessageHeader::new(remote, control_messages);
+
+        Ok((received_len, message_header))
+    }
+}
+
+static DATAGRAM_TABLE: DatagramTable = DatagramTable::new();
+
+/// The table of the bound UNIX datagram sockets.
+struct DatagramTable {
+    sockets: RwLock<BTreeMap<UnixSocketAddrKey, (UnixSocketAddrBound, Arc<MessageQueue>)>>,
+}
+
+impl DatagramTable {
+    const fn new() -> Self {
+        Self {
+            sockets: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    fn insert(&self, key: UnixSocketAddrKey, addr: UnixSocketAddrBound, queue: Arc<MessageQueue>) {
+        self.sockets.write().insert(key, (addr, queue));
+    }
+
+    fn get(&self, key: &UnixSocketAddrKey) -> Result<(UnixSocketAddrBound, Arc<MessageQueue>)> {
+        self.sockets.read().get(key).cloned().ok_or_else(|| {
+            Error::with_message(
+                Errno::ECONNREFUSED,
+                "no datagram socket is bound to the remote address",
+            )
+        })
+    }
+
+    fn remove(&self, key: &UnixSocketAddrKey) {
+        self.sockets.write().remove(key);
+    }
+}
+
+fn lookup_queue(key: &UnixSocketAddrKey) -> Result<Arc<MessageQueue>> {
+    DATAGRAM_TABLE.get(key).map(|(_, queue)| queue)
+}
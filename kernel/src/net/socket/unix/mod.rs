@@ -1,8 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod addr;
+mod ancillary;
+mod cred;
+mod datagram;
 mod ns;
 mod stream;
 
 pub use addr::UnixSocketAddr;
+pub use cred::UnixCredentials;
+pub use datagram::UnixDatagramSocket;
 pub use stream::UnixStreamSocket;
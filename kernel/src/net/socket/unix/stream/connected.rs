@@ -8,7 +8,10 @@ use crate::{
     events::IoEvents,
     fs::utils::{Channel, Consumer, Producer},
     net::socket::{
-        unix::{addr::UnixSocketAddrBound, UnixSocketAddr},
+        unix::{
+            addr::UnixSocketAddrBound, ancillary::AncillaryData, UnixCredentials, UnixSocketAddr,
+        },
+        util::send_recv_flags::SendRecvFlags,
         SockShutdownCmd,
     },
     prelude::*,
@@ -20,31 +23,54 @@ pub(super) struct Connected {
     addr: AddrView,
     reader: Consumer<u8>,
     writer: Producer<u8>,
+    reader_segments: Arc<Mutex<VecDeque<Segment>>>,
+    writer_segments: Arc<Mutex<VecDeque<Segment>>>,
+    peer_cred: UnixCredentials,
+    is_seqpacket: bool,
 }
 
 impl Connected {
+    /// Creates a pair of connected sockets.
+    ///
+    /// The first socket is bound to `addr` and belongs to the process with `cred`, while the
+    /// second socket is bound to `peer_addr` and belongs to the process with `peer_cred`. If
+    /// `is_seqpacket` is true, the sockets preserve message boundaries.
     pub(super) fn new_pair(
         addr: Option<UnixSocketAddrBound>,
         peer_addr: Option<UnixSocketAddrBound>,
+        cred: UnixCredentials,
+        peer_cred: UnixCredentials,
         reader_pollee: Option<Pollee>,
         writer_pollee: Option<Pollee>,
+        is_seqpacket: bool,
     ) -> (Connected, Connected) {
         let (writer_peer, reader_this) =
             Channel::with_capacity_and_pollees(DEFAULT_BUF_SIZE, None, reader_pollee).split();
         let (writer_this, reader_peer) =
             Channel::with_capacity_and_pollees(DEFAULT_BUF_SIZE, writer_pollee, None).split();
 
+        let segments_this = Arc::new(Mutex::new(VecDeque::new()));
+        let segments_peer = Arc::new(Mutex::new(VecDeque::new()));
+
         let (addr_this, addr_peer) = AddrView::new_pair(addr, peer_addr);
 
         let this = Connected {
             addr: addr_this,
             reader: reader_this,
             writer: writer_this,
+            reader_segments: segments_this.clone(),
+            writer_segments: segments_peer.clone(),
+            peer_cred,
+            is_seqpacket,
         };
         let peer = Connected {
             addr: addr_peer,
             reader: reader_peer,
             writer: writer_peer,
+            reader_segments: segments_peer,
+            writer_segments: segments_this,
+            peer_cred: cred,
+            is_seqpacket,
         };
 
         (this, peer)
@@ -58,6 +84,14 @@ impl Connected {
         self.addr.peer_addr()
     }
 
+    pub(super) fn peer_cred(&self) -> UnixCredentials {
+        self.peer_cred
+    }
+
+    pub(super) fn is_seqpacket(&self) -> bool {
+        self.is_seqpacket
+    }
+
     pub(super) fn bind(&self, addr_to_bind: UnixSocketAddr) -> Result<()> {
         let mut addr = self.addr.addr();
 
@@ -71,12 +105,110 @@ impl Connected {
         Ok(())
     }
 
-    pub(super) fn try_read(&self, writer: &mut dyn MultiWrite) -> Result<usize> {
-        self.reader.try_read(writer)
+    /// Tries to read data and the ancillary data attached to it.
+    ///
+    /// The ancillary data is `None` if no data (or no message for `SOCK_SEQPACKET` sockets) is
+    /// read.
+    pub(super) fn try_read(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+        is_pass_cred: bool,
+    ) -> Result<(usize, Option<AncillaryData>)> {
+        if self.is_seqpacket {
+            return self.try_read_message(writer, flags);
+        }
+
+        let mut segments = self.reader_segments.lock();
+
+        // Like Linux, a read does not go beyond the data that carries files. If `SO_PASSCRED` is
+        // enabled, a read does not mix the data sent with different credentials either.
+        let max_len = if let Some(first) = segments.front() {
+            let mut max_len = 0;
+            for segment in segments.iter() {
+                if is_pass_cred && segment.ancillary.cred() != first.ancillary.cred() {
+                    break;
+                }
+                max_len += segment.len;
+                if segment.ancillary.has_files() {
+                    break;
+                }
+            }
+            max_len
+        } else {
+            usize::MAX
+        };
+
+        let read_len = self.reader.try_read_with_max_len(writer, max_len)?;
+        if read_len == 0 {
+            return Ok((0, None));
+        }
+
+        let cred = segments.front().unwrap().ancillary.cred();
+        let mut files = None;
+
+        let mut remaining_len = read_len;
+        while remaining_len > 0 {
+            let segment = segments.front_mut().unwrap();
+
+            if let Some(segment_files) = segment.ancillary.take_files() {
+                files = Some(segment_files);
+            }
+
+            let consumed_len = segment.len.min(remaining_len);
+            segment.len -= consumed_len;
+            remaining_len -= consumed_len;
+            if segment.len == 0 {
+                segments.pop_front();
+            }
+        }
+
+        Ok((read_len, Some(AncillaryData::new(cred, files))))
+    }
+
+    fn try_read_message(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, Option<AncillaryData>)> {
+        let mut segments = self.reader_segments.lock();
+
+        // Each message has a segment. If there is a segment, there is a message, and reading the
+        // message will consume it even if the read fails.
+        let ancillary = segments.pop_front().map(|segment| segment.ancillary);
+        let (read_len, message_len) = self.reader.try_read_message(writer)?;
+
+        // Like datagram sockets, the real length of the message is returned with `MSG_TRUNC`.
+        if flags.contains(SendRecvFlags::MSG_TRUNC) {
+            Ok((message_len, ancillary))
+        } else {
+            Ok((read_len, ancillary))
+        }
     }
 
-    pub(super) fn try_write(&self, reader: &mut dyn MultiRead) -> Result<usize> {
-        self.writer.try_write(reader)
+    /// Tries to write data with the ancillary data attached to it.
+    pub(super) fn try_write(
+        &self,
+        reader: &mut dyn MultiRead,
+        ancillary: &AncillaryData,
+    ) -> Result<usize> {
+        let mut segments = self.writer_segments.lock();
+
+        let written_len = if self.is_seqpacket {
+            self.writer.try_write_message(reader)?
+        } else {
+            self.writer.try_write(reader)?
+        };
+
+        // Empty messages are still messages, but writing empty bytes to streams is a no-op.
+        if self.is_seqpacket || written_len > 0 {
+            segments.push_back(Segment {
+                len: written_len,
+                ancillary: ancillary.clone(),
+            });
+        }
+
+        Ok(written_len)
     }
 
     pub(super) fn shutdown(&self, cmd: SockShutdownCmd) {
@@ -122,6 +254,15 @@ pub(super) fn combine_io_events(
     events & (mask | IoEvents::ALWAYS_POLL)
 }
 
+/// The data written by one write operation.
+///
+/// The reader uses segments to find the ancillary data attached to the data it reads.
+struct Segment {
+    /// The length of the data that has not been read.
+    len: usize,
+    ancillary: AncillaryData,
+}
+
 struct AddrView {
     addr: Arc<SpinLock<Option<UnixSocketAddrBound>>>,
     peer: Arc<SpinLock<Option<UnixSocketAddrBound>>>,
@@ -13,7 +13,10 @@ use crate::{
     events::IoEvents,
     fs::file_handle::FileLike,
     net::socket::{
-        unix::addr::{UnixSocketAddrBound, UnixSocketAddrKey},
+        unix::{
+            addr::{UnixSocketAddrBound, UnixSocketAddrKey},
+            UnixCredentials,
+        },
         SockShutdownCmd, SocketAddr,
     },
     prelude::*,
@@ -34,9 +37,10 @@ impl Listener {
         backlog: usize,
         is_read_shutdown: bool,
         is_write_shutdown: bool,
+        is_seqpacket: bool,
     ) -> Self {
         let backlog = BACKLOG_TABLE
-            .add_backlog(addr, reader_pollee, backlog, is_read_shutdown)
+            .add_backlog(addr, reader_pollee, backlog, is_read_shutdown, is_seqpacket)
             .unwrap();
         writer_pollee.invalidate();
 
@@ -51,11 +55,18 @@ impl Listener {
         self.backlog.addr()
     }
 
-    pub(super) fn try_accept(&self) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
+    pub(super) fn cred(&self) -> UnixCredentials {
+        self.backlog.cred
+    }
+
+    /// Tries to accept an incoming connection.
+    ///
+    /// Like Linux, the accepted socket inherits `SO_PASSCRED` from the listening socket.
+    pub(super) fn try_accept(&self, is_pass_cred: bool) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
         let connected = self.backlog.pop_incoming()?;
         let peer_addr = connected.peer_addr().into();
 
-        let socket = UnixStreamSocket::new_connected(connected, false);
+        let socket = UnixStreamSocket::new_connected(connected, false, is_pass_cred);
         Ok((socket, peer_addr))
     }
 
@@ -122,6 +133,7 @@ impl BacklogTable {
         pollee: Pollee,
         backlog: usize,
         is_shutdown: bool,
+        is_seqpacket: bool,
     ) -> Option<Arc<Backlog>> {
         let addr_key = addr.to_key();
 
@@ -133,7 +145,13 @@ impl BacklogTable {
 
         // Note that the cached events can be correctly inherited from `Init`, so there is no need
         // to explicitly call `Pollee::invalidate`.
-        let new_backlog = Arc::new(Backlog::new(addr, pollee, backlog, is_shutdown));
+        let new_backlog = Arc::new(Backlog::new(
+            addr,
+            pollee,
+            backlog,
+            is_shutdown,
+            is_seqpacket,
+        ));
         backlog_sockets.insert(addr_key, new_backlog.clone());
 
         Some(new_backlog)
@@ -150,6 +168,9 @@ impl BacklogTable {
 
 pub(super) struct Backlog {
     addr: UnixSocketAddrBound,
+    /// The credentials of the process that starts listening.
+    cred: UnixCredentials,
+    is_seqpacket: bool,
     pollee: Pollee,
     backlog: AtomicUsize,
     incoming_conns: SpinLock<Option<VecDeque<Connected>>>,
@@ -157,7 +178,13 @@ pub(super) struct Backlog {
 }
 
 impl Backlog {
-    fn new(addr: UnixSocketAddrBound, pollee: Pollee, backlog: usize, is_shutdown: bool) -> Self {
+    fn new(
+        addr: UnixSocketAddrBound,
+        pollee: Pollee,
+        backlog: usize,
+        is_shutdown: bool,
+        is_seqpacket: bool,
+    ) -> Self {
         let incoming_sockets = if is_shutdown {
             None
         } else {
@@ -166,6 +193,8 @@ impl Backlog {
 
         Self {
             addr,
+            cred: UnixCredentials::new_current(),
+            is_seqpacket,
             pollee,
             backlog: AtomicUsize::new(backlog),
             incoming_conns: SpinLock::new(incoming_sockets),
@@ -237,7 +266,21 @@ impl Backlog {
     pub(super) fn push_incoming(
         &self,
         init: Init,
+        is_seqpacket: bool,
     ) -> core::result::Result<Connected, (Error, Init)> {
+        if is_seqpacket != self.is_seqpacket {
+            // Linux looks up abstract names per socket type, so a socket of a different type
+            // appears to be missing.
+            let errno = match self.addr {
+                UnixSocketAddrBound::Path(..) => Errno::EPROTOTYPE,
+                UnixSocketAddrBound::Abstract(..) => Errno::ECONNREFUSED,
+            };
+            return Err((
+                Error::with_message(errno, "the listening socket is of a different socket type"),
+                init,
+            ));
+        }
+
         let mut locked_incoming_conns = self.incoming_conns.lock();
 
         let Some(incoming_conns) = &mut *locked_incoming_conns else {
@@ -260,7 +303,8 @@ impl Backlog {
             ));
         }
 
-        let (client_conn, server_conn) = init.into_connected(self.addr.clone());
+        let (client_conn, server_conn) =
+            init.into_connected(self.addr.clone(), self.cred, self.is_seqpacket);
 
         incoming_conns.push_back(server_conn);
         self.pollee.notify(IoEvents::IN);
@@ -15,8 +15,10 @@ use crate::{
         file_handle::FileLike,
         utils::{InodeMode, Metadata, StatusFlags},
     },
+    match_sock_option_mut, match_sock_option_ref,
     net::socket::{
-        unix::UnixSocketAddr,
+        options::{PassCred, PeerCred, SocketOption},
+        unix::{ancillary::AncillaryData, UnixCredentials, UnixSocketAddr},
         util::{send_recv_flags::SendRecvFlags, socket_addr::SocketAddr, MessageHeader},
         SockShutdownCmd, Socket,
     },
@@ -25,23 +27,39 @@ use crate::{
     util::{MultiRead, MultiWrite},
 };
 
+/// A UNIX domain socket of the `SOCK_STREAM` or `SOCK_SEQPACKET` type.
+///
+/// `SOCK_SEQPACKET` sockets are connection-oriented like `SOCK_STREAM` sockets, but they preserve
+/// message boundaries.
 pub struct UnixStreamSocket {
     state: RwMutex<Takeable<State>>,
     is_nonblocking: AtomicBool,
+    is_seqpacket: bool,
+    is_pass_cred: AtomicBool,
 }
 
 impl UnixStreamSocket {
-    pub(super) fn new_init(init: Init, is_nonblocking: bool) -> Arc<Self> {
+    pub(super) fn new_init(init: Init, is_nonblocking: bool, is_seqpacket: bool) -> Arc<Self> {
         Arc::new(Self {
             state: RwMutex::new(Takeable::new(State::Init(init))),
             is_nonblocking: AtomicBool::new(is_nonblocking),
+            is_seqpacket,
+            is_pass_cred: AtomicBool::new(false),
         })
     }
 
-    pub(super) fn new_connected(connected: Connected, is_nonblocking: bool) -> Arc<Self> {
+    pub(super) fn new_connected(
+        connected: Connected,
+        is_nonblocking: bool,
+        is_pass_cred: bool,
+    ) -> Arc<Self> {
+        let is_seqpacket = connected.is_seqpacket();
+
         Arc::new(Self {
             state: RwMutex::new(Takeable::new(State::Connected(connected))),
             is_nonblocking: AtomicBool::new(is_nonblocking),
+            is_seqpacket,
+            is_pass_cred: AtomicBool::new(is_pass_cred),
         })
     }
 }
@@ -53,46 +71,72 @@ enum State {
 }
 
 impl UnixStreamSocket {
-    pub fn new(is_nonblocking: bool) -> Arc<Self> {
-        Self::new_init(Init::new(), is_nonblocking)
+    pub fn new(is_nonblocking: bool, is_seqpacket: bool) -> Arc<Self> {
+        Self::new_init(Init::new(), is_nonblocking, is_seqpacket)
     }
 
-    pub fn new_pair(is_nonblocking: bool) -> (Arc<Self>, Arc<Self>) {
-        let (conn_a, conn_b) = Connected::new_pair(None, None, None, None);
+    pub fn new_pair(is_nonblocking: bool, is_seqpacket: bool) -> (Arc<Self>, Arc<Self>) {
+        let cred = UnixCredentials::new_current();
+        let (conn_a, conn_b) =
+            Connected::new_pair(None, None, cred, cred, None, None, is_seqpacket);
         (
-            Self::new_connected(conn_a, is_nonblocking),
-            Self::new_connected(conn_b, is_nonblocking),
+            Self::new_connected(conn_a, is_nonblocking, false),
+            Self::new_connected(conn_b, is_nonblocking, false),
         )
     }
 
-    fn send(&self, reader: &mut dyn MultiRead, flags: SendRecvFlags) -> Result<usize> {
-        if self.is_nonblocking() {
-            self.try_send(reader, flags)
+    fn send(
+        &self,
+        reader: &mut dyn MultiRead,
+        ancillary: &AncillaryData,
+        flags: SendRecvFlags,
+    ) -> Result<usize> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
+            self.try_send(reader, ancillary, flags)
         } else {
-            self.wait_events(IoEvents::OUT, None, || self.try_send(reader, flags))
+            self.wait_events(IoEvents::OUT, None, || {
+                self.try_send(reader, ancillary, flags)
+            })
         }
     }
 
-    fn try_send(&self, buf: &mut dyn MultiRead, _flags: SendRecvFlags) -> Result<usize> {
+    fn try_send(
+        &self,
+        buf: &mut dyn MultiRead,
+        ancillary: &AncillaryData,
+        _flags: SendRecvFlags,
+    ) -> Result<usize> {
         match self.state.read().as_ref() {
-            State::Connected(connected) => connected.try_write(buf),
+            State::Connected(connected) => connected.try_write(buf, ancillary),
             State::Init(_) | State::Listen(_) => {
                 return_errno_with_message!(Errno::ENOTCONN, "the socket is not connected")
             }
         }
     }
 
-    fn recv(&self, writer: &mut dyn MultiWrite, flags: SendRecvFlags) -> Result<usize> {
-        if self.is_nonblocking() {
+    fn recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, Option<AncillaryData>)> {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
             self.try_recv(writer, flags)
         } else {
             self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
         }
     }
 
-    fn try_recv(&self, buf: &mut dyn MultiWrite, _flags: SendRecvFlags) -> Result<usize> {
+    fn try_recv(
+        &self,
+        buf: &mut dyn MultiWrite,
+        flags: SendRecvFlags,
+    ) -> Result<(usize, Option<AncillaryData>)> {
         match self.state.read().as_ref() {
-            State::Connected(connected) => connected.try_read(buf),
+            State::Connected(connected) => connected.try_read(buf, flags, self.is_pass_cred()),
+            // Linux reports different errors for `SOCK_STREAM` and `SOCK_SEQPACKET` sockets.
+            State::Init(_) | State::Listen(_) if self.is_seqpacket => {
+                return_errno_with_message!(Errno::ENOTCONN, "the socket is not connected")
+            }
             State::Init(_) | State::Listen(_) => {
                 return_errno_with_message!(Errno::EINVAL, "the socket is not connected")
             }
@@ -125,7 +169,7 @@ impl UnixStreamSocket {
                 }
             };
 
-            let connected = match backlog.push_incoming(init) {
+            let connected = match backlog.push_incoming(init, self.is_seqpacket) {
                 Ok(connected) => connected,
                 Err((err, init)) => return (State::Init(init), Err(err)),
             };
@@ -136,7 +180,7 @@ impl UnixStreamSocket {
 
     fn try_accept(&self) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
         match self.state.read().as_ref() {
-            State::Listen(listen) => listen.try_accept() as _,
+            State::Listen(listen) => listen.try_accept(self.is_pass_cred()) as _,
             State::Init(_) | State::Connected(_) => {
                 return_errno_with_message!(Errno::EINVAL, "the socket is not listening")
             }
@@ -150,6 +194,10 @@ impl UnixStreamSocket {
     fn set_nonblocking(&self, nonblocking: bool) {
         self.is_nonblocking.store(nonblocking, Ordering::Relaxed);
     }
+
+    fn is_pass_cred(&self) -> bool {
+        self.is_pass_cred.load(Ordering::Relaxed)
+    }
 }
 
 impl Pollable for UnixStreamSocket {
@@ -171,14 +219,18 @@ impl FileLike for UnixStreamSocket {
     fn read(&self, writer: &mut VmWriter) -> Result<usize> {
         // TODO: Set correct flags
         let flags = SendRecvFlags::empty();
-        let read_len = self.recv(writer, flags)?;
+        let (read_len, _) = self.recv(writer, flags)?;
         Ok(read_len)
     }
 
     fn write(&self, reader: &mut VmReader) -> Result<usize> {
         // TODO: Set correct flags
         let flags = SendRecvFlags::empty();
-        self.send(reader, flags)
+        self.send(
+            reader,
+            &AncillaryData::from_control_messages(Vec::new()),
+            flags,
+        )
     }
 
     fn status_flags(&self) -> StatusFlags {
@@ -258,7 +310,7 @@ impl Socket for UnixStreamSocket {
                 }
             };
 
-            let listener = match init.listen(backlog) {
+            let listener = match init.listen(backlog, self.is_seqpacket) {
                 Ok(listener) => listener,
                 Err((err, init)) => {
                     return (State::Init(init), Err(err));
@@ -277,6 +329,10 @@ impl Socket for UnixStreamSocket {
         }
     }
 
+    fn accept_nonblocking(&self) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
+        self.try_accept()
+    }
+
     fn shutdown(&self, cmd: SockShutdownCmd) -> Result<()> {
         match self.state.read().as_ref() {
             State::Init(init) => init.shutdown(cmd),
@@ -308,6 +364,38 @@ impl Socket for UnixStreamSocket {
         Ok(peer_addr.into())
     }
 
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        match_sock_option_mut!(option, {
+            peer_cred: PeerCred => {
+                // Like Linux, listening sockets report their own credentials.
+                let cred = match self.state.read().as_ref() {
+                    State::Init(_) => UnixCredentials::new_unknown(),
+                    State::Listen(listen) => listen.cred(),
+                    State::Connected(connected) => connected.peer_cred(),
+                };
+                peer_cred.set(cred);
+            },
+            pass_cred: PassCred => {
+                pass_cred.set(self.is_pass_cred());
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
+        });
+
+        Ok(())
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        match_sock_option_ref!(option, {
+            pass_cred: PassCred => {
+                let is_pass_cred = *pass_cred.get().unwrap();
+                self.is_pass_cred.store(is_pass_cred, Ordering::Relaxed);
+            },
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
+        });
+
+        Ok(())
+    }
+
     fn sendmsg(
         &self,
         reader: &mut dyn MultiRead,
@@ -320,15 +408,12 @@ impl Socket for UnixStreamSocket {
         }
 
         let MessageHeader {
-            control_message, ..
+            control_messages, ..
         } = message_header;
 
-        if control_message.is_some() {
-            // TODO: Support sending control message
-            warn!("sending control message is not supported");
-        }
+        let ancillary = AncillaryData::from_control_messages(control_messages);
 
-        self.send(reader, flags)
+        self.send(reader, &ancillary, flags)
     }
 
     fn recvmsg(
@@ -341,11 +426,12 @@ impl Socket for UnixStreamSocket {
             warn!("unsupported flags: {:?}", flags);
         }
 
-        let received_bytes = self.recv(writer, flags)?;
-
-        // TODO: Receive control message
+        let (received_bytes, ancillary) = self.recv(writer, flags)?;
 
-        let message_header = MessageHeader::new(None, None);
+        let control_messages = ancillary
+            .map(|ancillary| ancillary.into_control_messages(self.is_pass_cred()))
+            .unwrap_or_default();
+        let message_header = MessageHeader::new(None, control_messages);
 
         Ok((received_bytes, message_header))
     }
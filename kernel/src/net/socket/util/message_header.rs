@@ -1,21 +1,23 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use core::fmt::Debug;
+
 use super::socket_addr::SocketAddr;
-use crate::prelude::*;
+use crate::{fs::file_handle::FileLike, net::socket::unix::UnixCredentials, prelude::*};
 
 /// Message header used for sendmsg/recvmsg.
 #[derive(Debug)]
 pub struct MessageHeader {
     pub(in crate::net) addr: Option<SocketAddr>,
-    pub(in crate::net) control_message: Option<ControlMessage>,
+    pub(in crate::net) control_messages: Vec<ControlMessage>,
 }
 
 impl MessageHeader {
     /// Creates a new `MessageHeader`.
-    pub const fn new(addr: Option<SocketAddr>, control_message: Option<ControlMessage>) -> Self {
+    pub const fn new(addr: Option<SocketAddr>, control_messages: Vec<ControlMessage>) -> Self {
         Self {
             addr,
-            control_message,
+            control_messages,
         }
     }
 
@@ -23,10 +25,28 @@ impl MessageHeader {
     pub fn addr(&self) -> Option<&SocketAddr> {
         self.addr.as_ref()
     }
+
+    /// Returns the control messages.
+    pub fn control_messages(&self) -> &[ControlMessage] {
+        &self.control_messages
+    }
 }
 
 /// Control message carried by MessageHeader.
-///
-/// TODO: Implement the struct. The struct is empty now.
-#[derive(Debug)]
-pub struct ControlMessage;
+pub enum ControlMessage {
+    /// Files passed with `SCM_RIGHTS`.
+    ///
+    /// The files are referred to by file descriptors in the sending and receiving processes.
+    Rights(Vec<Arc<dyn FileLike>>),
+    /// Credentials passed with `SCM_CREDENTIALS`.
+    Credentials(UnixCredentials),
+}
+
+impl Debug for ControlMessage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Rights(files) => f.debug_tuple("Rights").field(&files.len()).finish(),
+            Self::Credentials(cred) => f.debug_tuple("Credentials").field(cred).finish(),
+        }
+    }
+}
@@ -3,13 +3,14 @@
 use core::time::Duration;
 
 use aster_bigtcp::socket::{
-    NeedIfacePoll, TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
+    NeedIfacePoll, PACKET_RECV_BUF_LEN, RAW_RECV_BUF_LEN, RAW_SEND_BUF_LEN, TCP_RECV_BUF_LEN,
+    TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
 };
 
 use crate::{
     match_sock_option_mut, match_sock_option_ref,
     net::socket::options::{
-        Error as SocketError, KeepAlive, Linger, RecvBuf, ReuseAddr, ReusePort, SendBuf,
+        Broadcast, Error as SocketError, KeepAlive, Linger, RecvBuf, ReuseAddr, ReusePort, SendBuf,
         SocketOption,
     },
     prelude::*,
@@ -26,6 +27,7 @@ pub struct SocketOptionSet {
     recv_buf: u32,
     linger: LingerOption,
     keep_alive: bool,
+    broadcast: bool,
 }
 
 impl SocketOptionSet {
@@ -39,6 +41,7 @@ impl SocketOptionSet {
             recv_buf: TCP_RECV_BUF_LEN as u32,
             linger: LingerOption::default(),
             keep_alive: false,
+            broadcast: false,
         }
     }
 
@@ -52,6 +55,49 @@ impl SocketOptionSet {
             recv_buf: UDP_RECV_PAYLOAD_LEN as u32,
             linger: LingerOption::default(),
             keep_alive: false,
+            broadcast: false,
+        }
+    }
+
+    /// Return the default socket level options for netlink socket.
+    pub fn new_netlink() -> Self {
+        Self {
+            sock_errors: None,
+            reuse_addr: false,
+            reuse_port: false,
+            send_buf: DEFAULT_SEND_BUF_LEN,
+            recv_buf: DEFAULT_RECV_BUF_LEN,
+            linger: LingerOption::default(),
+            keep_alive: false,
+            broadcast: false,
+        }
+    }
+
+    /// Return the default socket level options for packet socket.
+    pub fn new_packet() -> Self {
+        Self {
+            sock_errors: None,
+            reuse_addr: false,
+            reuse_port: false,
+            send_buf: DEFAULT_SEND_BUF_LEN,
+            recv_buf: PACKET_RECV_BUF_LEN as u32,
+            linger: LingerOption::default(),
+            keep_alive: false,
+            broadcast: false,
+        }
+    }
+
+    /// Return the default socket level options for raw socket.
+    pub fn new_raw() -> Self {
+        Self {
+            sock_errors: None,
+            reuse_addr: false,
+            reuse_port: false,
+            send_buf: RAW_SEND_BUF_LEN as u32,
+            recv_buf: RAW_RECV_BUF_LEN as u32,
+            linger: LingerOption::default(),
+            keep_alive: false,
+            broadcast: false,
         }
     }
 
@@ -95,6 +141,10 @@ impl SocketOptionSet {
                 let keep_alive = self.keep_alive();
                 socket_keepalive.set(keep_alive);
             },
+            socket_broadcast: Broadcast => {
+                let broadcast = self.broadcast();
+                socket_broadcast.set(broadcast);
+            },
             _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to get is unknown")
         });
         Ok(())
@@ -140,6 +190,10 @@ impl SocketOptionSet {
                 self.set_keep_alive(*keep_alive);
                 return Ok(socket.set_keep_alive(*keep_alive));
             },
+            socket_broadcast: Broadcast => {
+                let broadcast = socket_broadcast.get().unwrap();
+                self.set_broadcast(*broadcast);
+            },
             _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown")
         });
 
@@ -150,6 +204,10 @@ impl SocketOptionSet {
 pub const MIN_SENDBUF: u32 = 2304;
 pub const MIN_RECVBUF: u32 = 2304;
 
+/// The default buffer lengths of netlink and packet sockets, which are the defaults of Linux.
+const DEFAULT_SEND_BUF_LEN: u32 = 212992;
+const DEFAULT_RECV_BUF_LEN: u32 = 212992;
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct LingerOption {
     is_on: bool,
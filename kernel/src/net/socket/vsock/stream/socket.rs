@@ -120,7 +120,7 @@ impl VsockStreamSocket {
         writer: &mut dyn MultiWrite,
         flags: SendRecvFlags,
     ) -> Result<(usize, SocketAddr)> {
-        if self.is_nonblocking() {
+        if self.is_nonblocking() || flags.contains(SendRecvFlags::MSG_DONTWAIT) {
             self.try_recv(writer, flags)
         } else {
             self.wait_events(IoEvents::IN, None, || self.try_recv(writer, flags))
@@ -287,6 +287,10 @@ impl Socket for VsockStreamSocket {
         }
     }
 
+    fn accept_nonblocking(&self) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
+        self.try_accept()
+    }
+
     fn shutdown(&self, cmd: SockShutdownCmd) -> Result<()> {
         match &*self.status.read() {
             Status::Connected(connected) => connected.shutdown(cmd),
@@ -308,10 +312,10 @@ impl Socket for VsockStreamSocket {
         }
 
         let MessageHeader {
-            control_message, ..
+            control_messages, ..
         } = message_header;
 
-        if control_message.is_some() {
+        if !control_messages.is_empty() {
             // TODO: Support sending control message
             warn!("sending control message is not supported");
         }
@@ -333,7 +337,7 @@ impl Socket for VsockStreamSocket {
 
         // TODO: Receive control message
 
-        let messsge_header = MessageHeader::new(None, None);
+        let messsge_header = MessageHeader::new(None, Vec::new());
 
         Ok((received_bytes, messsge_header))
     }
@@ -12,6 +12,7 @@ use super::{
     posix_thread::{thread_table, AsPosixThread, PosixThread, PosixThreadBuilder, ThreadName},
     process_table,
     process_vm::ProcessVm,
+    seccomp::SeccompState,
     signal::{constants::SIGCHLD, sig_disposition::SigDispositions, sig_num::SigNum},
     Credentials, Process, ProcessBuilder,
 };
@@ -20,7 +21,6 @@ use crate::{
     current_userspace,
     fs::{file_table::FileTable, thread_info::ThreadFsInfo},
     prelude::*,
-    process::posix_thread::allocate_posix_tid,
     thread::{AsThread, Tid},
 };
 
@@ -162,17 +162,32 @@ impl CloneFlags {
             | CloneFlags::CLONE_SETTLS
             | CloneFlags::CLONE_PARENT_SETTID
             | CloneFlags::CLONE_CHILD_SETTID
-            | CloneFlags::CLONE_CHILD_CLEARTID;
+            | CloneFlags::CLONE_CHILD_CLEARTID
+            | CloneFlags::CLONE_NEWNS
+            | CloneFlags::CLONE_NEWUTS
+            | CloneFlags::CLONE_NEWIPC
+            | CloneFlags::CLONE_NEWPID;
         let unsupported_flags = *self - supported_flags;
         if !unsupported_flags.is_empty() {
             warn!("contains unsupported clone flags: {:?}", unsupported_flags);
         }
         Ok(())
     }
+
+    fn check_invalid_flags(&self) -> Result<()> {
+        // A new mount namespace requires the child to have its own root and
+        // working directory.
+        if self.contains(CloneFlags::CLONE_NEWNS | CloneFlags::CLONE_FS) {
+            return_errno_with_message!(Errno::EINVAL, "`CLONE_NEWNS` with `CLONE_FS` is not valid");
+        }
+        Ok(())
+    }
 }
 
 /// Clone a child thread or child process.
 ///
+/// Returns the TID of the child in the PID namespace of the current process.
+///
 /// FIXME: currently, the child process or thread will be scheduled to run at once,
 /// but this may not be the expected behavior.
 pub fn clone_child(
@@ -181,18 +196,23 @@ pub fn clone_child(
     clone_args: CloneArgs,
 ) -> Result<Tid> {
     clone_args.flags.check_unsupported_flags()?;
+    clone_args.flags.check_invalid_flags()?;
+    let pid_ns = ctx.process.pid_ns();
     if clone_args.flags.contains(CloneFlags::CLONE_THREAD) {
         let child_task = clone_child_task(ctx, parent_context, clone_args)?;
         let child_thread = child_task.as_thread().unwrap();
+        // The ID is translated before running the child, which may exit at once.
+        let child_tid = pid_ns
+            .local_id(child_thread.as_posix_thread().unwrap().tid())
+            .unwrap();
         child_thread.run();
 
-        let child_tid = child_thread.as_posix_thread().unwrap().tid();
         Ok(child_tid)
     } else {
         let child_process = clone_child_process(ctx, parent_context, clone_args)?;
+        let child_pid = pid_ns.local_id(child_process.pid()).unwrap();
         child_process.run();
 
-        let child_pid = child_process.pid();
         Ok(child_pid)
     }
 }
@@ -220,6 +240,19 @@ fn clone_child_task(
         task: _,
     } = ctx;
 
+    // The threads in a process must be in the same PID namespace.
+    if clone_flags.contains(CloneFlags::CLONE_NEWPID)
+        || !Arc::ptr_eq(
+            posix_thread.ns_proxy().pid_ns_for_children(),
+            process.pid_ns(),
+        )
+    {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "`CLONE_THREAD` cannot create a thread in another PID namespace"
+        );
+    }
+
     // clone system V semaphore
     clone_sysvsem(clone_flags)?;
 
@@ -229,6 +262,11 @@ fn clone_child_task(
     // clone fs
     let child_fs = clone_fs(posix_thread.fs(), clone_flags);
 
+    // clone namespaces
+    let child_ns_proxy = posix_thread
+        .ns_proxy()
+        .new_with_flags(clone_flags, &child_fs, ctx)?;
+
     let child_root_vmar = process.root_vmar();
     let child_user_space = {
         let child_vm_space = child_root_vmar.vm_space().clone();
@@ -245,7 +283,8 @@ fn clone_child_task(
     // Inherit sigmask from current thread
     let sig_mask = posix_thread.sig_mask().load(Ordering::Relaxed).into();
 
-    let child_tid = allocate_posix_tid();
+    let child_pid_ns = process.pid_ns();
+    let child_tid = child_pid_ns.alloc_tid()?;
     let child_task = {
         let credentials = {
             let credentials = ctx.posix_thread.credentials();
@@ -255,21 +294,33 @@ fn clone_child_task(
         let thread_builder = PosixThreadBuilder::new(child_tid, child_user_space, credentials)
             .process(posix_thread.weak_process())
             .sig_mask(sig_mask)
+            .seccomp(SeccompState::new_from(posix_thread.seccomp()))
+            .no_new_privs(posix_thread.no_new_privs())
             .file_table(child_file_table)
-            .fs(child_fs);
+            .fs(child_fs)
+            .ns_proxy(child_ns_proxy);
         thread_builder.build()
     };
 
-    process
-        .tasks()
-        .lock()
-        .insert(child_task.clone())
-        .map_err(|_| Error::with_message(Errno::EINTR, "the process has exited"))?;
-
+    // The child thread is not visible until it is inserted into the process, so the TID is
+    // released if any of the following steps fails.
     let child_posix_thread = child_task.as_posix_thread().unwrap();
-    clone_parent_settid(child_tid, clone_args.parent_tid, clone_flags)?;
-    clone_child_cleartid(child_posix_thread, clone_args.child_tid, clone_flags)?;
-    clone_child_settid(child_posix_thread, clone_args.child_tid, clone_flags)?;
+    clone_parent_settid(
+        child_pid_ns.local_id(child_tid).unwrap(),
+        clone_args.parent_tid,
+        clone_flags,
+    )
+    .and_then(|_| clone_child_cleartid(child_posix_thread, clone_args.child_tid, clone_flags))
+    .and_then(|_| clone_child_settid(child_posix_thread, clone_args.child_tid, clone_flags))
+    .and_then(|_| {
+        process
+            .tasks()
+            .lock()
+            .insert(child_task.clone())
+            .map_err(|_| Error::with_message(Errno::EINTR, "the process has exited"))
+    })
+    .inspect_err(|_| child_pid_ns.remove_tid(child_tid))?;
+
     Ok(child_task)
 }
 
@@ -315,6 +366,11 @@ fn clone_child_process(
     // clone fs
     let child_fs = clone_fs(posix_thread.fs(), clone_flags);
 
+    // clone namespaces
+    let child_ns_proxy = posix_thread
+        .ns_proxy()
+        .new_with_flags(clone_flags, &child_fs, ctx)?;
+
     // clone sig dispositions
     let child_sig_dispositions = clone_sighand(process.sig_dispositions(), clone_flags);
 
@@ -327,7 +383,9 @@ fn clone_child_process(
     // inherit parent's nice value
     let child_nice = process.nice().load(Ordering::Relaxed);
 
-    let child_tid = allocate_posix_tid();
+    // The child is in the PID namespace for the children of the current thread.
+    let child_pid_ns = child_ns_proxy.pid_ns_for_children().clone();
+    let child_tid = child_pid_ns.alloc_tid()?;
 
     let child = {
         let child_elf_path = process.executable_path();
@@ -342,20 +400,26 @@ fn clone_child_process(
             PosixThreadBuilder::new(child_tid, child_user_space, credentials)
                 .thread_name(Some(child_thread_name))
                 .sig_mask(child_sig_mask)
+                .seccomp(SeccompState::new_from(posix_thread.seccomp()))
+                .no_new_privs(posix_thread.no_new_privs())
                 .file_table(child_file_table)
                 .fs(child_fs)
+                .ns_proxy(child_ns_proxy)
         };
 
         let mut process_builder =
             ProcessBuilder::new(child_tid, &child_elf_path, posix_thread.weak_process());
 
         process_builder
+            .pid_ns(child_pid_ns.clone())
             .main_thread_builder(child_thread_builder)
             .process_vm(child_process_vm)
             .sig_dispositions(child_sig_dispositions)
             .nice(child_nice);
 
-        process_builder.build()?
+        process_builder
+            .build()
+            .inspect_err(|_| child_pid_ns.remove_tid(child_tid))?
     };
 
     if let Some(sig) = clone_args.exit_signal {
@@ -365,7 +429,11 @@ fn clone_child_process(
     // Deals with clone flags
     let child_thread = thread_table::get_thread(child_tid).unwrap();
     let child_posix_thread = child_thread.as_posix_thread().unwrap();
-    clone_parent_settid(child_tid, clone_args.parent_tid, clone_flags)?;
+    clone_parent_settid(
+        process.pid_ns().local_id(child_tid).unwrap(),
+        clone_args.parent_tid,
+        clone_flags,
+    )?;
     clone_child_cleartid(child_posix_thread, clone_args.child_tid, clone_flags)?;
     clone_child_settid(child_posix_thread, clone_args.child_tid, clone_flags)?;
 
@@ -1,7 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::{posix_thread::PosixThread, process_table, Pid, Process};
-use crate::{prelude::*, process::signal::signals::kernel::KernelSignal};
+use super::{
+    posix_thread::{ptrace_detach_all, PosixThread},
+    process_table, Process,
+};
+use crate::{
+    prelude::*,
+    process::signal::{constants::SIGKILL, signals::kernel::KernelSignal},
+};
 
 /// Exits the current POSIX process.
 ///
@@ -19,6 +25,10 @@ pub(super) fn exit_process(current_thread: &PosixThread, current_process: &Proce
 
     send_parent_death_signal(current_process);
 
+    ptrace_detach_all(current_process);
+
+    kill_pid_ns_if_init(current_process);
+
     move_children_to_init(current_process);
 
     send_child_death_signal(current_process);
@@ -41,13 +51,36 @@ fn send_parent_death_signal(current_process: &Process) {
     }
 }
 
-/// Moves the children to the init process.
-fn move_children_to_init(current_process: &Process) {
-    if is_init_process(current_process) {
+/// Kills all the processes in the PID namespace if the process is the init process of it.
+///
+/// A PID namespace cannot live without its init process.
+fn kill_pid_ns_if_init(current_process: &Process) {
+    let pid_ns = current_process.pid_ns();
+    if pid_ns.is_root() || !pid_ns.is_init_process(current_process) {
         return;
     }
 
-    let Some(init_process) = get_init_process() else {
+    pid_ns.set_dead();
+
+    let processes: Vec<_> = process_table::process_table_mut()
+        .iter()
+        .filter(|process| {
+            process.pid() != current_process.pid() && pid_ns.local_id(process.pid()).is_some()
+        })
+        .cloned()
+        .collect();
+    for process in processes {
+        process.enqueue_signal(KernelSignal::new(SIGKILL));
+    }
+}
+
+/// Moves the children to the init process.
+///
+/// The children are adopted by the init process of the PID namespace of the
+/// current process. If the current process is the init process, the init
+/// process of the nearest ancestor namespace adopts them.
+fn move_children_to_init(current_process: &Process) {
+    let Some(init_process) = get_init_process(current_process) else {
         return;
     };
 
@@ -71,13 +104,16 @@ fn send_child_death_signal(current_process: &Process) {
     parent.children_wait_queue().wake_all();
 }
 
-const INIT_PROCESS_PID: Pid = 1;
-
-/// Gets the init process
-fn get_init_process() -> Option<Arc<Process>> {
-    process_table::get_process(INIT_PROCESS_PID)
-}
-
-fn is_init_process(process: &Process) -> bool {
-    process.pid() == INIT_PROCESS_PID
+/// Gets the init process that adopts the children of the current process.
+fn get_init_process(current_process: &Process) -> Option<Arc<Process>> {
+    let mut pid_ns = Some(current_process.pid_ns());
+    while let Some(ns) = pid_ns {
+        if let Some(init_process) = ns.init_process() {
+            if init_process.pid() != current_process.pid() {
+                return Some(init_process);
+            }
+        }
+        pid_ns = ns.parent();
+    }
+    None
 }
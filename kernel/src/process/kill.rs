@@ -27,6 +27,7 @@ pub fn kill(pid: Pid, signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
         };
 
         if !ctx.posix_thread.has_signal_blocked(signal.num()) {
+            let signal = signal.translate_pid(ctx.process.pid_ns());
             ctx.posix_thread.enqueue_signal(Box::new(signal));
             return Ok(());
         }
@@ -92,6 +93,7 @@ pub fn tgkill(tid: Tid, tgid: Pid, signal: Option<UserSignal>, ctx: &Context) ->
     posix_thread.check_signal_perm(signum.as_ref(), &sender)?;
 
     if let Some(signal) = signal {
+        let signal = signal.translate_pid(posix_thread.process().pid_ns());
         posix_thread.enqueue_signal(Box::new(signal));
     }
 
@@ -101,12 +103,18 @@ pub fn tgkill(tid: Tid, tgid: Pid, signal: Option<UserSignal>, ctx: &Context) ->
 /// Sends a signal to all processes except current process and init process, using
 /// the current process as the sender.
 ///
+/// Only the processes visible in the PID namespace of the current process are
+/// signaled, and the init process is the one of that namespace.
+///
 /// The credentials of the current process will be checked to determine
 /// if it is authorized to send the signal to the target group.
 pub fn kill_all(signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
-    let current = current!();
+    let pid_ns = ctx.process.pid_ns();
     for process in process_table::process_table_mut().iter() {
-        if Arc::ptr_eq(&current, process) || process.is_init_process() {
+        if core::ptr::eq(ctx.process, process.as_ref())
+            || pid_ns.local_id(process.pid()).is_none()
+            || pid_ns.is_init_process(process)
+        {
             continue;
         }
 
@@ -119,6 +127,9 @@ pub fn kill_all(signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
 fn kill_process(process: &Process, signal: Option<UserSignal>, ctx: &Context) -> Result<()> {
     let tasks = process.tasks().lock();
 
+    // The sender is identified by its PID in the namespace of the receiver.
+    let signal = signal.map(|signal| signal.translate_pid(process.pid_ns()));
+
     let signum = signal.map(|signal| signal.num());
     let sender_ids = current_thread_sender_ids(signum.as_ref(), ctx);
 
@@ -4,6 +4,7 @@ mod clone;
 pub mod credentials;
 mod exit;
 mod kill;
+pub mod namespace;
 pub mod posix_thread;
 #[allow(clippy::module_inception)]
 mod process;
@@ -12,6 +13,7 @@ pub mod process_table;
 mod process_vm;
 mod program_loader;
 pub mod rlimit;
+pub mod seccomp;
 pub mod signal;
 mod status;
 pub mod sync;
@@ -30,7 +32,7 @@ pub use process_vm::{MAX_ARGV_NUMBER, MAX_ARG_LEN, MAX_ENVP_NUMBER, MAX_ENV_LEN}
 pub use program_loader::{check_executable_file, load_program_to_vm};
 pub use rlimit::ResourceType;
 pub use term_status::TermStatus;
-pub use wait::{wait_child_exit, WaitOptions};
+pub use wait::{wait_child_exit, WaitOptions, WaitStatus};
 
 pub(super) fn init() {
     process::init();
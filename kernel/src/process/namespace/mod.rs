@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Namespaces, which partition the global resources among groups of processes.
+//!
+//! The PID, mount, UTS and IPC namespaces are supported.
+
+use spin::Once;
+
+pub use self::{
+    pid::{init_pid_ns, PidNamespace},
+    uts::{init_uts_ns, UtsName, UtsNamespace, UTS_FIELD_LEN},
+};
+use super::{credentials::capabilities::CapSet, CloneFlags, Process};
+use crate::{
+    fs::{
+        path::{init_mnt_ns, Dentry, MountNamespace},
+        thread_info::ThreadFsInfo,
+    },
+    ipc::{init_ipc_ns, IpcNamespace},
+    prelude::*,
+    process::posix_thread::AsPosixThread,
+};
+
+mod pid;
+mod uts;
+
+/// The namespaces that a POSIX thread is in.
+///
+/// The PID namespace of a thread is the one of its process, which never
+/// changes. So only the PID namespace for the children of the thread is kept
+/// here.
+#[derive(Clone)]
+pub struct NsProxy {
+    uts_ns: Arc<UtsNamespace>,
+    ipc_ns: Arc<IpcNamespace>,
+    mnt_ns: Arc<MountNamespace>,
+    pid_ns_for_children: Arc<PidNamespace>,
+}
+
+impl NsProxy {
+    pub fn uts_ns(&self) -> &Arc<UtsNamespace> {
+        &self.uts_ns
+    }
+
+    pub fn ipc_ns(&self) -> &Arc<IpcNamespace> {
+        &self.ipc_ns
+    }
+
+    pub fn mnt_ns(&self) -> &Arc<MountNamespace> {
+        &self.mnt_ns
+    }
+
+    /// Returns the PID namespace that the children of the thread will be in.
+    pub fn pid_ns_for_children(&self) -> &Arc<PidNamespace> {
+        &self.pid_ns_for_children
+    }
+
+    /// Returns the namespaces after creating the new namespaces specified in `flags`.
+    ///
+    /// If a new mount namespace is created, `fs` is moved to the same
+    /// locations in the new mount namespace.
+    pub(super) fn new_with_flags(
+        self: &Arc<Self>,
+        flags: CloneFlags,
+        fs: &ThreadFsInfo,
+        ctx: &Context,
+    ) -> Result<Arc<Self>> {
+        if !flags.intersects(NS_FLAGS) {
+            return Ok(self.clone());
+        }
+        check_sys_admin(ctx)?;
+
+        let mut new_ns_proxy = NsProxy::clone(self);
+        if flags.contains(CloneFlags::CLONE_NEWUTS) {
+            new_ns_proxy.uts_ns = self.uts_ns.new_copy();
+        }
+        if flags.contains(CloneFlags::CLONE_NEWIPC) {
+            new_ns_proxy.ipc_ns = IpcNamespace::new();
+        }
+        if flags.contains(CloneFlags::CLONE_NEWPID) {
+            new_ns_proxy.pid_ns_for_children = self.pid_ns_for_children.new_child()?;
+        }
+        if flags.contains(CloneFlags::CLONE_NEWNS) {
+            let new_mnt_ns = self.mnt_ns.new_copy();
+            let mut resolver = fs.resolver().write();
+            let root = self.mnt_ns.translate(resolver.root(), &new_mnt_ns);
+            let cwd = self.mnt_ns.translate(resolver.cwd(), &new_mnt_ns);
+            resolver.set_root(root);
+            resolver.set_cwd(cwd);
+            new_ns_proxy.mnt_ns = new_mnt_ns;
+        }
+
+        Ok(Arc::new(new_ns_proxy))
+    }
+}
+
+/// The clone flags that create new namespaces.
+const NS_FLAGS: CloneFlags = CloneFlags::CLONE_NEWNS
+    .union(CloneFlags::CLONE_NEWUTS)
+    .union(CloneFlags::CLONE_NEWIPC)
+    .union(CloneFlags::CLONE_NEWPID);
+
+static INIT_NS_PROXY: Once<Arc<NsProxy>> = Once::new();
+
+/// Returns the initial namespaces, which the init process is in.
+pub fn init_ns_proxy() -> &'static Arc<NsProxy> {
+    INIT_NS_PROXY.call_once(|| {
+        Arc::new(NsProxy {
+            uts_ns: init_uts_ns().clone(),
+            ipc_ns: init_ipc_ns().clone(),
+            mnt_ns: init_mnt_ns().clone(),
+            pid_ns_for_children: init_pid_ns().clone(),
+        })
+    })
+}
+
+/// The kinds of namespaces, which correspond to the files in `/proc/[pid]/ns`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NsKind {
+    Uts,
+    Ipc,
+    Mnt,
+    Pid,
+    PidForChildren,
+}
+
+impl NsKind {
+    pub const ALL: [NsKind; 5] = [
+        NsKind::Uts,
+        NsKind::Ipc,
+        NsKind::Mnt,
+        NsKind::Pid,
+        NsKind::PidForChildren,
+    ];
+
+    /// Returns the name of the file in `/proc/[pid]/ns`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NsKind::Uts => "uts",
+            NsKind::Ipc => "ipc",
+            NsKind::Mnt => "mnt",
+            NsKind::Pid => "pid",
+            NsKind::PidForChildren => "pid_for_children",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Returns the clone flag that creates a namespace of the kind.
+    pub fn clone_flag(&self) -> CloneFlags {
+        match self {
+            NsKind::Uts => CloneFlags::CLONE_NEWUTS,
+            NsKind::Ipc => CloneFlags::CLONE_NEWIPC,
+            NsKind::Mnt => CloneFlags::CLONE_NEWNS,
+            NsKind::Pid | NsKind::PidForChildren => CloneFlags::CLONE_NEWPID,
+        }
+    }
+}
+
+/// Moves the current thread to new namespaces, or stops sharing some
+/// attributes with other threads, as `unshare` does.
+pub fn unshare(flags: CloneFlags, ctx: &Context) -> Result<()> {
+    let supported_flags = NS_FLAGS
+        | CloneFlags::CLONE_FS
+        | CloneFlags::CLONE_FILES
+        | CloneFlags::CLONE_SYSVSEM
+        | CloneFlags::CLONE_THREAD
+        | CloneFlags::CLONE_SIGHAND
+        | CloneFlags::CLONE_VM;
+    let unsupported_flags = flags - supported_flags;
+    if !unsupported_flags.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "the unshare flags are not supported");
+    }
+
+    let posix_thread = ctx.posix_thread;
+
+    // The thread group, the signal handlers and the address space can only be
+    // "unshared" if they are not shared at all.
+    if flags.intersects(CloneFlags::CLONE_THREAD | CloneFlags::CLONE_SIGHAND | CloneFlags::CLONE_VM)
+        && ctx.process.tasks().lock().as_slice().len() > 1
+    {
+        return_errno_with_message!(Errno::EINVAL, "the process has multiple threads");
+    }
+
+    // The file system information and the file table cannot be replaced now.
+    // So unsharing them only succeeds if they are not shared.
+    if flags.intersects(CloneFlags::CLONE_FS | CloneFlags::CLONE_NEWNS)
+        && Arc::strong_count(posix_thread.fs()) > 1
+    {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "unsharing the shared file system information is not supported"
+        );
+    }
+    if flags.contains(CloneFlags::CLONE_FILES) && Arc::strong_count(posix_thread.file_table()) > 1 {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "unsharing the shared file table is not supported"
+        );
+    }
+
+    let ns_proxy = posix_thread.ns_proxy();
+    let new_ns_proxy = ns_proxy.new_with_flags(flags, posix_thread.fs(), ctx)?;
+    if !Arc::ptr_eq(&ns_proxy, &new_ns_proxy) {
+        posix_thread.set_ns_proxy(new_ns_proxy);
+    }
+
+    Ok(())
+}
+
+/// Moves the current thread to a namespace of the target process, as `setns` does.
+///
+/// If `nstype` is not empty, the namespace must be of the type.
+pub fn setns(target: &Process, kind: NsKind, nstype: CloneFlags, ctx: &Context) -> Result<()> {
+    if !nstype.is_empty() && nstype != kind.clone_flag() {
+        return_errno_with_message!(Errno::EINVAL, "the namespace is not of the type");
+    }
+    check_sys_admin(ctx)?;
+
+    let target_ns_proxy = {
+        let main_thread = target.main_thread();
+        main_thread.as_posix_thread().unwrap().ns_proxy()
+    };
+
+    let posix_thread = ctx.posix_thread;
+    let mut new_ns_proxy = NsProxy::clone(&posix_thread.ns_proxy());
+    match kind {
+        NsKind::Uts => new_ns_proxy.uts_ns = target_ns_proxy.uts_ns.clone(),
+        NsKind::Ipc => new_ns_proxy.ipc_ns = target_ns_proxy.ipc_ns.clone(),
+        NsKind::Mnt => {
+            if Arc::strong_count(posix_thread.fs()) > 1 {
+                return_errno_with_message!(Errno::EINVAL, "the file system information is shared");
+            }
+            let mnt_ns = target_ns_proxy.mnt_ns.clone();
+            let mut resolver = posix_thread.fs().resolver().write();
+            resolver.set_root(Dentry::new_fs_root(mnt_ns.root().clone()));
+            resolver.set_cwd(Dentry::new_fs_root(mnt_ns.root().clone()));
+            new_ns_proxy.mnt_ns = mnt_ns;
+        }
+        NsKind::Pid | NsKind::PidForChildren => {
+            let pid_ns = if kind == NsKind::Pid {
+                target.pid_ns().clone()
+            } else {
+                target_ns_proxy.pid_ns_for_children.clone()
+            };
+            // A thread can only move its children to the descendants of its PID namespace.
+            if !ctx.process.pid_ns().is_ancestor_of(&pid_ns) {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the PID namespace is not a descendant of the current one"
+                );
+            }
+            new_ns_proxy.pid_ns_for_children = pid_ns;
+        }
+    }
+    posix_thread.set_ns_proxy(Arc::new(new_ns_proxy));
+
+    Ok(())
+}
+
+fn check_sys_admin(ctx: &Context) -> Result<()> {
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "operating namespaces requires CAP_SYS_ADMIN");
+    }
+    Ok(())
+}
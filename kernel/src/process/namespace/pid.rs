@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use crate::{
+    prelude::*,
+    process::{posix_thread::allocate_posix_tid, process_table, Pid, Process},
+    thread::Tid,
+};
+
+/// A PID namespace.
+///
+/// The kernel identifies threads and processes by their global IDs. A PID
+/// namespace gives the threads created in it, or in its descendants, another
+/// set of IDs that starts from one. The threads are invisible in the other
+/// namespaces.
+///
+/// The IDs in the root namespace are exactly the global IDs.
+pub struct PidNamespace {
+    parent: Option<Arc<PidNamespace>>,
+    /// The nesting level, which is zero for the root namespace.
+    level: u32,
+    inner: Mutex<PidNamespaceInner>,
+}
+
+struct PidNamespaceInner {
+    /// The local ID that will be allocated next.
+    next_id: Tid,
+    /// Maps the global IDs to the local IDs.
+    local_ids: BTreeMap<Tid, Tid>,
+    /// Maps the local IDs to the global IDs.
+    global_ids: BTreeMap<Tid, Tid>,
+    /// The global PID of the init process, which is the first process in the namespace.
+    init_pid: Option<Pid>,
+    /// Whether the init process has exited.
+    ///
+    /// No more threads can be created in the namespace after its init process exits.
+    is_dead: bool,
+}
+
+/// The maximum nesting level of PID namespaces.
+const MAX_PID_NS_LEVEL: u32 = 32;
+
+/// The PID of the init process in the root namespace.
+const INIT_PROCESS_PID: Pid = 1;
+
+impl PidNamespace {
+    fn new(parent: Option<Arc<PidNamespace>>) -> Arc<Self> {
+        let level = parent.as_ref().map_or(0, |parent| parent.level + 1);
+        Arc::new(Self {
+            parent,
+            level,
+            inner: Mutex::new(PidNamespaceInner {
+                next_id: 1,
+                local_ids: BTreeMap::new(),
+                global_ids: BTreeMap::new(),
+                init_pid: None,
+                is_dead: false,
+            }),
+        })
+    }
+
+    /// Creates a child namespace of this namespace.
+    pub fn new_child(self: &Arc<Self>) -> Result<Arc<Self>> {
+        if self.level >= MAX_PID_NS_LEVEL {
+            return_errno_with_message!(Errno::ENOSPC, "too many nested PID namespaces");
+        }
+        Ok(Self::new(Some(self.clone())))
+    }
+
+    /// Returns the parent namespace, or `None` for the root namespace.
+    pub fn parent(&self) -> Option<&Arc<PidNamespace>> {
+        self.parent.as_ref()
+    }
+
+    /// Returns whether this is the root namespace.
+    pub fn is_root(&self) -> bool {
+        self.parent.is_none()
+    }
+
+    /// Returns whether `self` is `other` or one of its ancestors.
+    pub fn is_ancestor_of(&self, other: &PidNamespace) -> bool {
+        let mut ns = Some(other);
+        while let Some(current) = ns {
+            if core::ptr::eq(current, self) {
+                return true;
+            }
+            ns = current.parent.as_deref();
+        }
+        false
+    }
+
+    /// Allocates a global ID for a new thread in this namespace.
+    ///
+    /// The thread is also given a local ID in this namespace and in each of
+    /// its ancestors.
+    pub fn alloc_tid(&self) -> Result<Tid> {
+        if !self.is_root() && self.inner.lock().is_dead {
+            return_errno_with_message!(
+                Errno::ENOMEM,
+                "the init process of the namespace has exited"
+            );
+        }
+
+        let tid = allocate_posix_tid();
+        let mut ns = Some(self);
+        while let Some(current) = ns.filter(|ns| !ns.is_root()) {
+            let mut inner = current.inner.lock();
+            let local_id = inner.next_id;
+            inner.next_id += 1;
+            inner.local_ids.insert(tid, local_id);
+            inner.global_ids.insert(local_id, tid);
+            if inner.init_pid.is_none() {
+                inner.init_pid = Some(tid);
+            }
+            ns = current.parent.as_deref();
+        }
+
+        Ok(tid)
+    }
+
+    /// Removes the local IDs of the thread with the global ID `tid`.
+    pub fn remove_tid(&self, tid: Tid) {
+        let mut ns = Some(self);
+        while let Some(current) = ns.filter(|ns| !ns.is_root()) {
+            let mut inner = current.inner.lock();
+            if let Some(local_id) = inner.local_ids.remove(&tid) {
+                inner.global_ids.remove(&local_id);
+            }
+            ns = current.parent.as_deref();
+        }
+    }
+
+    /// Translates a global ID to the ID in this namespace.
+    ///
+    /// Returns `None` if the thread is not visible in this namespace.
+    pub fn local_id(&self, tid: Tid) -> Option<Tid> {
+        if self.is_root() {
+            return Some(tid);
+        }
+        self.inner.lock().local_ids.get(&tid).copied()
+    }
+
+    /// Translates an ID in this namespace to the global ID.
+    ///
+    /// Returns `None` if no thread has the ID in this namespace.
+    pub fn global_id(&self, local_id: Tid) -> Option<Tid> {
+        if self.is_root() {
+            return Some(local_id);
+        }
+        self.inner.lock().global_ids.get(&local_id).copied()
+    }
+
+    /// Returns the init process of this namespace, if it is alive.
+    pub fn init_process(&self) -> Option<Arc<Process>> {
+        if self.is_root() {
+            return process_table::get_process(INIT_PROCESS_PID);
+        }
+
+        let init_pid = {
+            let inner = self.inner.lock();
+            if inner.is_dead {
+                return None;
+            }
+            inner.init_pid?
+        };
+        process_table::get_process(init_pid)
+    }
+
+    /// Returns whether `process` is the init process of this namespace.
+    pub fn is_init_process(&self, process: &Process) -> bool {
+        if self.is_root() {
+            return process.pid() == INIT_PROCESS_PID;
+        }
+        self.inner.lock().init_pid == Some(process.pid())
+    }
+
+    /// Marks that the init process of the namespace has exited.
+    pub(in crate::process) fn set_dead(&self) {
+        self.inner.lock().is_dead = true;
+    }
+}
+
+static INIT_PID_NS: Once<Arc<PidNamespace>> = Once::new();
+
+/// Returns the root PID namespace.
+pub fn init_pid_ns() -> &'static Arc<PidNamespace> {
+    INIT_PID_NS.call_once(|| PidNamespace::new(None))
+}
@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use crate::prelude::*;
+
+/// A UTS namespace, which isolates the host name and the NIS domain name.
+pub struct UtsNamespace {
+    uts_name: RwLock<UtsName>,
+}
+
+impl UtsNamespace {
+    /// Creates a new namespace with a copy of the names in this namespace.
+    pub fn new_copy(&self) -> Arc<Self> {
+        Arc::new(Self {
+            uts_name: RwLock::new(*self.uts_name.read()),
+        })
+    }
+
+    /// Returns the names in the namespace.
+    pub fn uts_name(&self) -> UtsName {
+        *self.uts_name.read()
+    }
+
+    /// Sets the host name.
+    pub fn set_hostname(&self, hostname: &[u8]) -> Result<()> {
+        copy_name(hostname, &mut self.uts_name.write().nodename)
+    }
+
+    /// Sets the NIS domain name.
+    pub fn set_domainname(&self, domainname: &[u8]) -> Result<()> {
+        copy_name(domainname, &mut self.uts_name.write().domainname)
+    }
+}
+
+pub const UTS_FIELD_LEN: usize = 65;
+
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+pub struct UtsName {
+    sysname: [u8; UTS_FIELD_LEN],
+    nodename: [u8; UTS_FIELD_LEN],
+    release: [u8; UTS_FIELD_LEN],
+    version: [u8; UTS_FIELD_LEN],
+    machine: [u8; UTS_FIELD_LEN],
+    domainname: [u8; UTS_FIELD_LEN],
+}
+
+impl UtsName {
+    const fn new() -> Self {
+        UtsName {
+            sysname: [0; UTS_FIELD_LEN],
+            nodename: [0; UTS_FIELD_LEN],
+            release: [0; UTS_FIELD_LEN],
+            version: [0; UTS_FIELD_LEN],
+            machine: [0; UTS_FIELD_LEN],
+            domainname: [0; UTS_FIELD_LEN],
+        }
+    }
+}
+
+/// Copies `name` to the field, which is always null-terminated.
+fn copy_name(name: &[u8], field: &mut [u8; UTS_FIELD_LEN]) -> Result<()> {
+    if name.len() >= UTS_FIELD_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the name is too long");
+    }
+    field.fill(0);
+    field[..name.len()].copy_from_slice(name);
+    Ok(())
+}
+
+static INIT_UTS_NS: Once<Arc<UtsNamespace>> = Once::new();
+
+/// Returns the initial UTS namespace.
+pub fn init_uts_ns() -> &'static Arc<UtsNamespace> {
+    // We don't use the real name and version of our os here. Instead, we pick up fake values witch is the same as the ones of linux.
+    // The values are used to fool glibc since glibc will check the version and os name.
+    INIT_UTS_NS.call_once(|| {
+        let copy_slice = |src: &[u8], dst: &mut [u8]| {
+            let len = src.len().min(dst.len());
+            dst[..len].copy_from_slice(&src[..len]);
+        };
+
+        let mut uts_name = UtsName::new();
+        copy_slice(b"Linux", &mut uts_name.sysname);
+        copy_slice(b"WHITLEY", &mut uts_name.nodename);
+        copy_slice(b"5.13.0", &mut uts_name.release);
+        copy_slice(b"5.13.0", &mut uts_name.version);
+        copy_slice(b"x86_64", &mut uts_name.machine);
+        copy_slice(b"", &mut uts_name.domainname);
+
+        Arc::new(UtsNamespace {
+            uts_name: RwLock::new(uts_name),
+        })
+    })
+}
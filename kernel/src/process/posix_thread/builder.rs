@@ -2,14 +2,18 @@
 
 #![allow(dead_code)]
 
+use core::sync::atomic::AtomicBool;
+
 use ostd::{cpu::CpuSet, task::Task, user::UserSpace};
 
-use super::{thread_table, PosixThread};
+use super::{thread_table, PosixThread, PtraceState};
 use crate::{
     fs::{file_table::FileTable, thread_info::ThreadFsInfo},
     prelude::*,
     process::{
+        namespace::{init_ns_proxy, NsProxy},
         posix_thread::name::ThreadName,
+        seccomp::SeccompState,
         signal::{sig_mask::AtomicSigMask, sig_queues::SigQueues},
         Credentials, Process,
     },
@@ -32,8 +36,11 @@ pub struct PosixThreadBuilder {
     clear_child_tid: Vaddr,
     file_table: Option<Arc<SpinLock<FileTable>>>,
     fs: Option<Arc<ThreadFsInfo>>,
+    ns_proxy: Option<Arc<NsProxy>>,
     sig_mask: AtomicSigMask,
     sig_queues: SigQueues,
+    seccomp: SeccompState,
+    no_new_privs: bool,
     priority: Priority,
 }
 
@@ -49,8 +56,11 @@ impl PosixThreadBuilder {
             clear_child_tid: 0,
             file_table: None,
             fs: None,
+            ns_proxy: None,
             sig_mask: AtomicSigMask::new_empty(),
             sig_queues: SigQueues::new(),
+            seccomp: SeccompState::new(),
+            no_new_privs: false,
             priority: Priority::default(),
         }
     }
@@ -85,11 +95,26 @@ impl PosixThreadBuilder {
         self
     }
 
+    pub fn ns_proxy(mut self, ns_proxy: Arc<NsProxy>) -> Self {
+        self.ns_proxy = Some(ns_proxy);
+        self
+    }
+
     pub fn sig_mask(mut self, sig_mask: AtomicSigMask) -> Self {
         self.sig_mask = sig_mask;
         self
     }
 
+    pub fn seccomp(mut self, seccomp: SeccompState) -> Self {
+        self.seccomp = seccomp;
+        self
+    }
+
+    pub fn no_new_privs(mut self, no_new_privs: bool) -> Self {
+        self.no_new_privs = no_new_privs;
+        self
+    }
+
     pub fn priority(mut self, priority: Priority) -> Self {
         self.priority = priority;
         self
@@ -106,8 +131,11 @@ impl PosixThreadBuilder {
             clear_child_tid,
             file_table,
             fs,
+            ns_proxy,
             sig_mask,
             sig_queues,
+            seccomp,
+            no_new_privs,
             priority,
         } = self;
 
@@ -116,6 +144,8 @@ impl PosixThreadBuilder {
 
         let fs = fs.unwrap_or_else(|| Arc::new(ThreadFsInfo::default()));
 
+        let ns_proxy = ns_proxy.unwrap_or_else(|| init_ns_proxy().clone());
+
         Arc::new_cyclic(|weak_task| {
             let posix_thread = {
                 let prof_clock = ProfClock::new();
@@ -131,11 +161,15 @@ impl PosixThreadBuilder {
                     credentials,
                     file_table,
                     fs,
+                    ns_proxy: RwLock::new(ns_proxy),
                     sig_mask,
                     sig_queues,
                     sig_context: Mutex::new(None),
                     sig_stack: Mutex::new(None),
                     signalled_waker: SpinLock::new(None),
+                    ptrace: PtraceState::new(),
+                    seccomp,
+                    no_new_privs: AtomicBool::new(no_new_privs),
                     robust_list: Mutex::new(None),
                     prof_clock,
                     virtual_timer_manager,
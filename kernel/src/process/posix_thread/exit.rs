@@ -3,7 +3,8 @@
 use ostd::task::{CurrentTask, Task};
 
 use super::{
-    futex::futex_wake, robust_list::wake_robust_futex, thread_table, AsPosixThread, PosixThread,
+    futex::futex_wake, ptrace::ptrace_exit, robust_list::wake_robust_futex, thread_table,
+    AsPosixThread, PosixThread,
 };
 use crate::{
     current_userspace,
@@ -71,10 +72,13 @@ fn exit_internal(term_status: TermStatus, is_exiting_group: bool) {
 
     wake_robust_list(posix_thread);
 
+    ptrace_exit(posix_thread);
+
     // According to Linux behavior, the main thread shouldn't be removed from the table until the
     // process is reaped by its parent.
     if posix_thread.tid() != posix_process.pid() {
         thread_table::remove_thread(posix_thread.tid());
+        posix_process.pid_ns().remove_tid(posix_thread.tid());
     }
 
     if is_last_thread {
@@ -126,7 +130,7 @@ fn wake_robust_list(current_thread: &PosixThread) {
 
     trace!("exit: wake up the rubust list: {:?}", list_head);
     for futex_addr in list_head.futexes() {
-        let _ = wake_robust_futex(futex_addr, current_thread.tid)
+        let _ = wake_robust_futex(futex_addr, current_thread.ns_tid())
             .inspect_err(|err| debug!("exit: cannot wake up the robust futex: {:?}", err));
     }
 
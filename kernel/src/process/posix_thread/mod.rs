@@ -2,13 +2,15 @@
 
 #![allow(dead_code)]
 
-use core::sync::atomic::{AtomicU32, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 use aster_rights::{ReadOp, WriteOp};
 use ostd::sync::Waker;
 
 use super::{
     kill::SignalSenderIds,
+    namespace::NsProxy,
+    seccomp::SeccompState,
     signal::{
         sig_action::SigAction,
         sig_mask::{AtomicSigMask, SigMask, SigSet},
@@ -33,6 +35,7 @@ mod exit;
 pub mod futex;
 mod name;
 mod posix_thread_ext;
+mod ptrace;
 mod robust_list;
 pub mod thread_table;
 
@@ -40,6 +43,11 @@ pub use builder::PosixThreadBuilder;
 pub use exit::{do_exit, do_exit_group};
 pub use name::{ThreadName, MAX_THREAD_NAME_LEN};
 pub use posix_thread_ext::{create_posix_task_from_executable, AsPosixThread};
+pub(super) use ptrace::ptrace_detach_all;
+pub use ptrace::{
+    ptrace_attach, ptrace_detach, ptrace_exec, ptrace_signal_stop, ptrace_syscall_entry,
+    ptrace_syscall_exit, ptrace_traceme, PtraceOptions, PtraceResumeMode, PtraceState,
+};
 pub use robust_list::RobustListHead;
 
 pub struct PosixThread {
@@ -66,6 +74,9 @@ pub struct PosixThread {
     /// File system
     fs: Arc<ThreadFsInfo>,
 
+    /// The namespaces that the thread is in.
+    ns_proxy: RwLock<Arc<NsProxy>>,
+
     // Signal
     /// Blocked signals
     sig_mask: AtomicSigMask,
@@ -79,6 +90,14 @@ pub struct PosixThread {
     /// when enqueuing a signal.
     signalled_waker: SpinLock<Option<Arc<Waker>>>,
 
+    /// The state of being traced by `ptrace`.
+    ptrace: PtraceState,
+
+    /// The seccomp filters that restrict the syscalls of the thread.
+    seccomp: SeccompState,
+    /// Whether the thread and its descendants cannot gain new privileges by `execve`.
+    no_new_privs: AtomicBool,
+
     /// A profiling clock measures the user CPU time and kernel CPU time in the thread.
     prof_clock: Arc<ProfClock>,
 
@@ -123,6 +142,21 @@ impl PosixThread {
         &self.fs
     }
 
+    /// Returns the namespaces that the thread is in.
+    pub fn ns_proxy(&self) -> Arc<NsProxy> {
+        self.ns_proxy.read().clone()
+    }
+
+    /// Moves the thread to the namespaces, as `unshare` and `setns` do.
+    pub(in crate::process) fn set_ns_proxy(&self, ns_proxy: Arc<NsProxy>) {
+        *self.ns_proxy.write() = ns_proxy;
+    }
+
+    /// Returns the thread ID in the PID namespace of the thread.
+    pub fn ns_tid(&self) -> Tid {
+        self.process().pid_ns().local_id(self.tid).unwrap()
+    }
+
     /// Get the reference to the signal mask of the thread.
     ///
     /// Note that while this function offers mutable access to the signal mask,
@@ -223,7 +257,12 @@ impl PosixThread {
     pub fn enqueue_signal(&self, signal: Box<dyn Signal>) {
         let signal_number = signal.num();
         self.sig_queues.enqueue(signal);
-        if self.process().sig_dispositions().lock().get(signal_number) != SigAction::Ign
+
+        let process = self.process();
+        process
+            .sigqueue_subject()
+            .notify_observers(&SigEvents::new(signal_number));
+        if process.sig_dispositions().lock().get(signal_number) != SigAction::Ign
             && let Some(waker) = &*self.signalled_waker.lock()
         {
             waker.wake_up();
@@ -285,6 +324,28 @@ impl PosixThread {
         &self.robust_list
     }
 
+    /// Returns the state of being traced by `ptrace`.
+    pub fn ptrace(&self) -> &PtraceState {
+        &self.ptrace
+    }
+
+    /// Returns the seccomp state of the thread.
+    pub fn seccomp(&self) -> &SeccompState {
+        &self.seccomp
+    }
+
+    /// Returns whether the thread cannot gain new privileges by `execve`.
+    pub fn no_new_privs(&self) -> bool {
+        self.no_new_privs.load(Ordering::Relaxed)
+    }
+
+    /// Prevents the thread from gaining new privileges by `execve`.
+    ///
+    /// Once set, the attribute cannot be unset.
+    pub fn set_no_new_privs(&self) {
+        self.no_new_privs.store(true, Ordering::Relaxed);
+    }
+
     /// Gets the read-only credentials of the thread.
     pub fn credentials(&self) -> Credentials<ReadOp> {
         self.credentials.dup().restrict()
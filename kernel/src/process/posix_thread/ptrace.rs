@@ -0,0 +1,541 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Process tracing.
+//!
+//! A traced thread (the tracee) enters a ptrace-stop before a signal is
+//! delivered to it (signal-delivery-stop), and, if the tracer asks for it,
+//! when it enters or exits a syscall (syscall-stop). In a ptrace-stop, the
+//! tracee saves its user context so that the tracer can inspect and modify
+//! it, and waits until the tracer resumes it.
+
+use ostd::{
+    cpu::UserContext,
+    sync::{PreemptDisabled, WaitQueue},
+};
+
+use super::{do_exit_group, AsPosixThread, PosixThread};
+use crate::{
+    cpu::LinuxAbi,
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        signal::{
+            constants::{SIGCHLD, SIGKILL, SIGSTOP, SIGTRAP},
+            sig_mask::SigMask,
+            sig_num::SigNum,
+            signals::kernel::KernelSignal,
+            with_signal_blocked,
+        },
+        Process, TermStatus,
+    },
+    thread::{Thread, Tid},
+};
+
+/// The ptrace state of a POSIX thread.
+pub struct PtraceState {
+    inner: SpinLock<PtraceInner>,
+    /// The wait queue on which the thread waits in a ptrace-stop.
+    stop_wait_queue: WaitQueue,
+}
+
+#[derive(Default)]
+struct PtraceInner {
+    tracer: Option<Tracer>,
+    stop: Option<PtraceStop>,
+}
+
+struct Tracer {
+    process: Weak<Process>,
+    options: PtraceOptions,
+    /// Whether the thread is attached with `PTRACE_SEIZE`.
+    is_seized: bool,
+    /// Whether the thread should stop at the entry and exit of syscalls.
+    traces_syscalls: bool,
+}
+
+struct PtraceStop {
+    kind: PtraceStopKind,
+    /// The saved user context, which may be modified by the tracer.
+    user_ctx: UserContext,
+    /// The syscall number if the thread is stopped in a syscall.
+    ///
+    /// The tracer may change the syscall number at the syscall entry. If it
+    /// is changed to `usize::MAX` (i.e., -1), the syscall will be skipped.
+    syscall_num: Option<usize>,
+    /// Whether the stop has been reported to the tracer by `wait`.
+    is_reported: bool,
+    /// The way that the tracer resumes the thread.
+    resume: Option<PtraceResume>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PtraceStopKind {
+    Signal(SigNum),
+    Syscall,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PtraceResume {
+    /// The signal to be delivered to the thread after it is resumed.
+    signal: Option<SigNum>,
+}
+
+/// The way that a tracer resumes a stopped tracee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtraceResumeMode {
+    /// Continues to run until the next signal (`PTRACE_CONT`).
+    Continue,
+    /// Continues to run until the next syscall entry or exit
+    /// (`PTRACE_SYSCALL`).
+    Syscall,
+    /// Continues to run a single instruction (`PTRACE_SINGLESTEP`).
+    SingleStep,
+}
+
+bitflags! {
+    /// The options of a tracee.
+    pub struct PtraceOptions: u32 {
+        const PTRACE_O_TRACESYSGOOD = 1 << 0;
+        const PTRACE_O_TRACEFORK = 1 << 1;
+        const PTRACE_O_TRACEVFORK = 1 << 2;
+        const PTRACE_O_TRACECLONE = 1 << 3;
+        const PTRACE_O_TRACEEXEC = 1 << 4;
+        const PTRACE_O_TRACEVFORKDONE = 1 << 5;
+        const PTRACE_O_TRACEEXIT = 1 << 6;
+        const PTRACE_O_TRACESECCOMP = 1 << 7;
+        const PTRACE_O_EXITKILL = 1 << 20;
+        const PTRACE_O_SUSPEND_SECCOMP = 1 << 21;
+    }
+}
+
+impl PtraceOptions {
+    pub fn supported(&self) -> bool {
+        let supported_options =
+            PtraceOptions::PTRACE_O_TRACESYSGOOD | PtraceOptions::PTRACE_O_EXITKILL;
+        supported_options.contains(*self)
+    }
+}
+
+impl PtraceState {
+    pub(super) fn new() -> Self {
+        Self {
+            inner: SpinLock::new(PtraceInner::default()),
+            stop_wait_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Returns whether the thread is traced.
+    pub fn is_traced(&self) -> bool {
+        self.inner.lock().tracer.is_some()
+    }
+
+    /// Checks whether the thread is traced by `tracer` and is in a
+    /// ptrace-stop.
+    pub fn check_stopped_by(&self, tracer: &Process) -> Result<()> {
+        self.lock_stopped_by(tracer).map(|_| ())
+    }
+
+    /// Sets the options of the tracee.
+    pub fn set_options(&self, tracer: &Process, options: PtraceOptions) -> Result<()> {
+        let mut inner = self.lock_stopped_by(tracer)?;
+        inner.tracer.as_mut().unwrap().options = options;
+        Ok(())
+    }
+
+    /// Resumes the tracee from the ptrace-stop.
+    ///
+    /// If `signal` is not `None` and the tracee is in a
+    /// signal-delivery-stop, the signal will be delivered to the tracee.
+    pub fn resume(
+        &self,
+        tracer: &Process,
+        mode: PtraceResumeMode,
+        signal: Option<SigNum>,
+    ) -> Result<()> {
+        let mut inner = self.lock_stopped_by(tracer)?;
+
+        let stop = inner.stop.as_mut().unwrap();
+        set_single_step(&mut stop.user_ctx, mode == PtraceResumeMode::SingleStep)?;
+        let signal = if let PtraceStopKind::Signal(_) = stop.kind {
+            signal
+        } else {
+            None
+        };
+        stop.resume = Some(PtraceResume { signal });
+
+        inner.tracer.as_mut().unwrap().traces_syscalls = mode == PtraceResumeMode::Syscall;
+
+        drop(inner);
+        self.stop_wait_queue.wake_all();
+        Ok(())
+    }
+
+    /// Calls `f` with the saved user context and the syscall number of the
+    /// tracee in a ptrace-stop.
+    ///
+    /// The changes made by `f` take effect when the tracee is resumed.
+    pub fn with_stopped_context<F, R>(&self, tracer: &Process, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut UserContext, &mut Option<usize>) -> R,
+    {
+        let mut inner = self.lock_stopped_by(tracer)?;
+        let stop = inner.stop.as_mut().unwrap();
+        Ok(f(&mut stop.user_ctx, &mut stop.syscall_num))
+    }
+
+    /// Reports the ptrace-stop that has not been reported to the tracer.
+    ///
+    /// The returned value is the status that should be returned by `wait`.
+    /// If `marks_reported` is false, the stop will be reported again next
+    /// time, as `WNOWAIT` requires.
+    pub(in crate::process) fn report_stop(&self, marks_reported: bool) -> Option<u32> {
+        let mut inner = self.inner.lock();
+        let options = inner.tracer.as_ref()?.options;
+
+        let stop = inner.stop.as_mut()?;
+        if stop.is_reported || stop.resume.is_some() {
+            return None;
+        }
+        if marks_reported {
+            stop.is_reported = true;
+        }
+
+        let sig_num = match stop.kind {
+            PtraceStopKind::Signal(sig_num) => sig_num.as_u8() as u32,
+            PtraceStopKind::Syscall if options.contains(PtraceOptions::PTRACE_O_TRACESYSGOOD) => {
+                SIGTRAP.as_u8() as u32 | 0x80
+            }
+            PtraceStopKind::Syscall => SIGTRAP.as_u8() as u32,
+        };
+        Some((sig_num << 8) | 0x7f)
+    }
+
+    fn lock_stopped_by(
+        &self,
+        tracer: &Process,
+    ) -> Result<SpinLockGuard<PtraceInner, PreemptDisabled>> {
+        let inner = self.inner.lock();
+        let is_traced_by = inner
+            .tracer
+            .as_ref()
+            .is_some_and(|t| core::ptr::eq(t.process.as_ptr(), tracer));
+        if !is_traced_by {
+            return_errno_with_message!(Errno::ESRCH, "the thread is not traced by the process");
+        }
+        if inner.stop.as_ref().is_none_or(|stop| stop.resume.is_some()) {
+            return_errno_with_message!(Errno::ESRCH, "the thread is not in a ptrace-stop");
+        }
+        Ok(inner)
+    }
+
+    /// Detaches the tracer and resumes the tracee if it is in a ptrace-stop.
+    ///
+    /// Returns the old tracer.
+    fn detach(&self, signal: Option<SigNum>) -> Option<Tracer> {
+        let mut inner = self.inner.lock();
+        let tracer = inner.tracer.take()?;
+
+        if let Some(stop) = inner.stop.as_mut()
+            && stop.resume.is_none()
+        {
+            let _ = set_single_step(&mut stop.user_ctx, false);
+            stop.resume = Some(PtraceResume { signal });
+            drop(inner);
+            self.stop_wait_queue.wake_all();
+        }
+
+        Some(tracer)
+    }
+}
+
+/// Makes the parent of the current process the tracer of the current thread.
+pub fn ptrace_traceme(ctx: &Context) -> Result<()> {
+    let Some(parent) = ctx.process.parent().lock().process().upgrade() else {
+        return_errno_with_message!(Errno::EPERM, "the current process has no parent");
+    };
+
+    attach_tracee(&current_thread!(), &parent, false, PtraceOptions::empty())
+}
+
+/// Attaches `tracer` to the `tracee` thread.
+///
+/// If `is_seized` is false, a `SIGSTOP` is sent to the tracee, as
+/// `PTRACE_ATTACH` requires.
+pub fn ptrace_attach(
+    tracee: &Arc<Thread>,
+    tracer: &Arc<Process>,
+    is_seized: bool,
+    options: PtraceOptions,
+    ctx: &Context,
+) -> Result<()> {
+    let tracee_thread = tracee.as_posix_thread().unwrap();
+    if Arc::ptr_eq(&tracee_thread.process(), tracer) {
+        return_errno_with_message!(Errno::EPERM, "a thread cannot trace its own process");
+    }
+    check_attach_perm(tracee_thread, ctx)?;
+
+    attach_tracee(tracee, tracer, is_seized, options)?;
+
+    if !is_seized {
+        tracee_thread.enqueue_signal(Box::new(KernelSignal::new(SIGSTOP)));
+    }
+    Ok(())
+}
+
+/// Detaches `tracer` from the `tracee` thread in a ptrace-stop.
+pub fn ptrace_detach(tracee: &Thread, tracer: &Process, signal: Option<SigNum>) -> Result<()> {
+    let tracee_thread = tracee.as_posix_thread().unwrap();
+
+    let mut tracees = tracer.tracees().lock();
+    tracee_thread.ptrace().check_stopped_by(tracer)?;
+    tracees.remove(&tracee_thread.tid());
+    tracee_thread.ptrace().detach(signal);
+
+    Ok(())
+}
+
+fn attach_tracee(
+    tracee: &Arc<Thread>,
+    tracer: &Arc<Process>,
+    is_seized: bool,
+    options: PtraceOptions,
+) -> Result<()> {
+    let tracee_thread = tracee.as_posix_thread().unwrap();
+
+    let mut tracees = tracer.tracees().lock();
+    let mut inner = tracee_thread.ptrace().inner.lock();
+    if inner.tracer.is_some() {
+        return_errno_with_message!(Errno::EPERM, "the thread is already traced");
+    }
+    inner.tracer = Some(Tracer {
+        process: Arc::downgrade(tracer),
+        options,
+        is_seized,
+        traces_syscalls: false,
+    });
+    tracees.insert(tracee_thread.tid(), tracee.clone());
+
+    Ok(())
+}
+
+/// Checks whether the current thread is allowed to trace `tracee`.
+///
+/// The tracer must have the `CAP_SYS_PTRACE` capability, or its effective
+/// user and group IDs must match all the user and group IDs of the tracee.
+fn check_attach_perm(tracee: &PosixThread, ctx: &Context) -> Result<()> {
+    let credentials = ctx.posix_thread.credentials();
+    if credentials.effective_capset().contains(CapSet::SYS_PTRACE) {
+        return Ok(());
+    }
+
+    let tracee_credentials = tracee.credentials();
+    let euid = credentials.euid();
+    let egid = credentials.egid();
+    if euid == tracee_credentials.ruid()
+        && euid == tracee_credentials.euid()
+        && euid == tracee_credentials.suid()
+        && egid == tracee_credentials.rgid()
+        && egid == tracee_credentials.egid()
+        && egid == tracee_credentials.sgid()
+    {
+        return Ok(());
+    }
+
+    return_errno_with_message!(Errno::EPERM, "the thread is not allowed to be traced");
+}
+
+/// Enters a signal-delivery-stop if the current thread is traced.
+///
+/// Returns the signal that should be delivered, which may be changed or
+/// suppressed by the tracer.
+pub fn ptrace_signal_stop(
+    sig_num: SigNum,
+    user_ctx: &mut UserContext,
+    ctx: &Context,
+) -> Option<SigNum> {
+    if sig_num == SIGKILL {
+        return Some(sig_num);
+    }
+
+    match ptrace_stop(PtraceStopKind::Signal(sig_num), None, user_ctx, ctx) {
+        StopResult::NotTraced => Some(sig_num),
+        StopResult::Killed => None,
+        StopResult::Resumed { resume, .. } => resume.signal,
+    }
+}
+
+/// Enters a syscall-stop at the syscall entry if the tracer asks for it.
+///
+/// Returns the number of the syscall to execute, which may have been changed
+/// by the tracer, or `None` if the syscall should be skipped.
+pub fn ptrace_syscall_entry(user_ctx: &mut UserContext, ctx: &Context) -> Option<usize> {
+    let syscall_num = user_ctx.syscall_num();
+    if !traces_syscalls(ctx.posix_thread) {
+        return Some(syscall_num);
+    }
+
+    // On x86-64, the tracer sees `-ENOSYS` as the return value at the
+    // syscall entry.
+    #[cfg(target_arch = "x86_64")]
+    user_ctx.set_syscall_ret(-(Errno::ENOSYS as i32) as usize);
+
+    let syscall_num = match ptrace_stop(PtraceStopKind::Syscall, Some(syscall_num), user_ctx, ctx) {
+        StopResult::NotTraced => syscall_num,
+        StopResult::Killed => return None,
+        StopResult::Resumed { syscall_num, .. } => syscall_num?,
+    };
+    if syscall_num == usize::MAX {
+        return None;
+    }
+
+    user_ctx.set_syscall_num(syscall_num);
+    Some(syscall_num)
+}
+
+/// Enters a syscall-stop at the syscall exit if the tracer asks for it.
+pub fn ptrace_syscall_exit(syscall_num: usize, user_ctx: &mut UserContext, ctx: &Context) {
+    if ctx.thread.is_exited() || !traces_syscalls(ctx.posix_thread) {
+        return;
+    }
+
+    ptrace_stop(PtraceStopKind::Syscall, Some(syscall_num), user_ctx, ctx);
+}
+
+/// Sends a `SIGTRAP` to the current thread after a successful `execve`, if
+/// the thread is traced but not attached with `PTRACE_SEIZE`.
+pub fn ptrace_exec(ctx: &Context) {
+    let is_traced_legacy = ctx
+        .posix_thread
+        .ptrace()
+        .inner
+        .lock()
+        .tracer
+        .as_ref()
+        .is_some_and(|tracer| !tracer.is_seized);
+
+    if is_traced_legacy {
+        ctx.posix_thread
+            .enqueue_signal(Box::new(KernelSignal::new(SIGTRAP)));
+    }
+}
+
+/// Detaches the exiting thread from its tracer.
+pub(super) fn ptrace_exit(posix_thread: &PosixThread) {
+    let Some(tracer) = posix_thread.ptrace().detach(None) else {
+        return;
+    };
+    let Some(tracer) = tracer.process.upgrade() else {
+        return;
+    };
+
+    tracer.tracees().lock().remove(&posix_thread.tid());
+    tracer.children_wait_queue().wake_all();
+}
+
+/// Detaches the exiting tracer process from all its tracees.
+///
+/// The tracees with `PTRACE_O_EXITKILL` set are killed.
+pub(in crate::process) fn ptrace_detach_all(tracer: &Process) {
+    let tracees: BTreeMap<Tid, Arc<Thread>> = core::mem::take(&mut *tracer.tracees().lock());
+
+    for tracee in tracees.values() {
+        let tracee_thread = tracee.as_posix_thread().unwrap();
+        let Some(old_tracer) = tracee_thread.ptrace().detach(None) else {
+            continue;
+        };
+        if old_tracer
+            .options
+            .contains(PtraceOptions::PTRACE_O_EXITKILL)
+        {
+            tracee_thread
+                .process()
+                .enqueue_signal(KernelSignal::new(SIGKILL));
+        }
+    }
+}
+
+enum StopResult {
+    NotTraced,
+    Killed,
+    Resumed {
+        resume: PtraceResume,
+        syscall_num: Option<usize>,
+    },
+}
+
+fn traces_syscalls(posix_thread: &PosixThread) -> bool {
+    posix_thread
+        .ptrace()
+        .inner
+        .lock()
+        .tracer
+        .as_ref()
+        .is_some_and(|tracer| tracer.traces_syscalls)
+}
+
+fn ptrace_stop(
+    kind: PtraceStopKind,
+    syscall_num: Option<usize>,
+    user_ctx: &mut UserContext,
+    ctx: &Context,
+) -> StopResult {
+    let state = ctx.posix_thread.ptrace();
+
+    let tracer = {
+        let mut inner = state.inner.lock();
+        let Some(tracer) = inner.tracer.as_ref().and_then(|t| t.process.upgrade()) else {
+            return StopResult::NotTraced;
+        };
+        inner.stop = Some(PtraceStop {
+            kind,
+            user_ctx: user_ctx.clone(),
+            syscall_num,
+            is_reported: false,
+            resume: None,
+        });
+        tracer
+    };
+
+    tracer.enqueue_signal(KernelSignal::new(SIGCHLD));
+    tracer.children_wait_queue().wake_all();
+
+    // Only `SIGKILL` can wake up a thread in a ptrace-stop.
+    let res = with_signal_blocked(ctx, SigMask::new_full() - SIGKILL, || {
+        state
+            .stop_wait_queue
+            .pause_until(|| state.inner.lock().stop.as_ref()?.resume)
+    });
+
+    let stop = state.inner.lock().stop.take().unwrap();
+    let Ok(resume) = res else {
+        do_exit_group(TermStatus::Killed(SIGKILL));
+        return StopResult::Killed;
+    };
+
+    *user_ctx = stop.user_ctx;
+    StopResult::Resumed {
+        resume,
+        syscall_num: stop.syscall_num,
+    }
+}
+
+/// Enables or disables single-stepping in the user context.
+fn set_single_step(user_ctx: &mut UserContext, enabled: bool) -> Result<()> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            // Bit 8 is the TF flag.
+            const X86_RFLAGS_TF: usize = 1 << 8;
+            if enabled {
+                user_ctx.general_regs_mut().rflags |= X86_RFLAGS_TF;
+            } else {
+                user_ctx.general_regs_mut().rflags &= !X86_RFLAGS_TF;
+            }
+            Ok(())
+        } else {
+            if enabled {
+                return_errno_with_message!(Errno::EIO, "single-stepping is not supported");
+            }
+            Ok(())
+        }
+    }
+}
@@ -6,6 +6,7 @@ use super::{Pid, Process};
 use crate::{
     prelude::*,
     process::{
+        namespace::{init_pid_ns, PidNamespace},
         posix_thread::{create_posix_task_from_executable, PosixThreadBuilder},
         process_vm::ProcessVm,
         rlimit::ResourceLimits,
@@ -22,6 +23,7 @@ pub struct ProcessBuilder<'a> {
     parent: Weak<Process>,
 
     // Optional parts
+    pid_ns: Option<Arc<PidNamespace>>,
     main_thread_builder: Option<PosixThreadBuilder>,
     argv: Option<Vec<CString>>,
     envp: Option<Vec<CString>>,
@@ -38,6 +40,7 @@ impl<'a> ProcessBuilder<'a> {
             pid,
             executable_path,
             parent,
+            pid_ns: None,
             main_thread_builder: None,
             argv: None,
             envp: None,
@@ -49,6 +52,11 @@ impl<'a> ProcessBuilder<'a> {
         }
     }
 
+    pub fn pid_ns(&mut self, pid_ns: Arc<PidNamespace>) -> &mut Self {
+        self.pid_ns = Some(pid_ns);
+        self
+    }
+
     pub fn main_thread_builder(&mut self, builder: PosixThreadBuilder) -> &mut Self {
         self.main_thread_builder = Some(builder);
         self
@@ -113,6 +121,7 @@ impl<'a> ProcessBuilder<'a> {
             pid,
             executable_path,
             parent,
+            pid_ns,
             main_thread_builder,
             argv,
             envp,
@@ -135,8 +144,11 @@ impl<'a> ProcessBuilder<'a> {
 
         let nice = nice.or_else(|| Some(Nice::default())).unwrap();
 
+        let pid_ns = pid_ns.unwrap_or_else(|| init_pid_ns().clone());
+
         let process = Process::new(
             pid,
+            pid_ns,
             parent,
             executable_path.to_string(),
             process_vm,
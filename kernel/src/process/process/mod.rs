@@ -4,6 +4,7 @@ use core::sync::atomic::{AtomicU32, Ordering};
 
 use self::timer_manager::PosixTimerManager;
 use super::{
+    namespace::PidNamespace,
     posix_thread::{allocate_posix_tid, AsPosixThread},
     process_table,
     process_vm::{Heap, InitStackReader, ProcessVm},
@@ -12,6 +13,7 @@ use super::{
         sig_disposition::SigDispositions,
         sig_num::{AtomicSigNum, SigNum},
         signals::Signal,
+        SigEvents, SigEventsFilter,
     },
     status::ProcessStatus,
     task_set::TaskSet,
@@ -19,9 +21,10 @@ use super::{
 };
 use crate::{
     device::tty::open_ntty_as_controlling_terminal,
+    events::{Observer, Subject},
     prelude::*,
     sched::priority::{AtomicNice, Nice},
-    thread::{AsThread, Thread},
+    thread::{AsThread, Thread, Tid},
     time::clocks::ProfClock,
     vm::vmar::Vmar,
 };
@@ -63,6 +66,8 @@ pub(super) fn init() {
 pub struct Process {
     // Immutable Part
     pid: Pid,
+    /// The PID namespace that the process is in.
+    pid_ns: Arc<PidNamespace>,
 
     process_vm: ProcessVm,
     /// Wait for child status changed
@@ -79,6 +84,8 @@ pub struct Process {
     pub(super) parent: ParentProcess,
     /// Children processes
     children: Mutex<BTreeMap<Pid, Arc<Process>>>,
+    /// Threads traced by this process
+    tracees: Mutex<BTreeMap<Tid, Arc<Thread>>>,
     /// Process group
     pub(super) process_group: Mutex<Weak<ProcessGroup>>,
     /// resource limits
@@ -96,6 +103,8 @@ pub struct Process {
 
     /// The signal that should be sent to the parent when this process exits.
     exit_signal: AtomicSigNum,
+    /// The observers of the signals that are enqueued to the threads of the process.
+    sigqueue_subject: Subject<SigEvents, SigEventsFilter>,
 
     /// A profiling clock measures the user CPU time and kernel CPU time of the current process.
     prof_clock: Arc<ProfClock>,
@@ -174,6 +183,7 @@ impl Process {
     #[allow(clippy::too_many_arguments)]
     fn new(
         pid: Pid,
+        pid_ns: Arc<PidNamespace>,
         parent: Weak<Process>,
         executable_path: String,
         process_vm: ProcessVm,
@@ -190,6 +200,7 @@ impl Process {
 
         Arc::new_cyclic(|process_ref: &Weak<Process>| Self {
             pid,
+            pid_ns,
             tasks: Mutex::new(TaskSet::new()),
             executable_path: RwLock::new(executable_path),
             process_vm,
@@ -197,10 +208,12 @@ impl Process {
             status: ProcessStatus::default(),
             parent: ParentProcess::new(parent),
             children: Mutex::new(BTreeMap::new()),
+            tracees: Mutex::new(BTreeMap::new()),
             process_group: Mutex::new(Weak::new()),
             sig_dispositions,
             parent_death_signal: AtomicSigNum::new_empty(),
             exit_signal: AtomicSigNum::new_empty(),
+            sigqueue_subject: Subject::new(),
             resource_limits: Mutex::new(resource_limits),
             nice: AtomicNice::new(nice),
             timer_manager: PosixTimerManager::new(&prof_clock, process_ref),
@@ -282,6 +295,16 @@ impl Process {
         self.pid
     }
 
+    /// Returns the PID namespace of the process.
+    pub fn pid_ns(&self) -> &Arc<PidNamespace> {
+        &self.pid_ns
+    }
+
+    /// Returns the PID in the PID namespace of the process.
+    pub fn ns_pid(&self) -> Pid {
+        self.pid_ns.local_id(self.pid).unwrap()
+    }
+
     /// Gets the profiling clock of the process.
     pub fn prof_clock(&self) -> &Arc<ProfClock> {
         &self.prof_clock
@@ -337,6 +360,11 @@ impl Process {
         &self.children_wait_queue
     }
 
+    /// Returns the threads traced by this process.
+    pub(super) fn tracees(&self) -> &Mutex<BTreeMap<Tid, Arc<Thread>>> {
+        &self.tracees
+    }
+
     // *********** Process group & Session***********
 
     /// Returns the process group ID of the process.
@@ -636,6 +664,26 @@ impl Process {
         posix_thread.enqueue_signal(Box::new(signal));
     }
 
+    /// Registers an observer of the signals that are enqueued to any thread of the process.
+    ///
+    /// The observer is also notified of the signals that are enqueued to the threads
+    /// created after the registration.
+    pub fn register_sigqueue_observer(
+        &self,
+        observer: Weak<dyn Observer<SigEvents>>,
+        filter: SigEventsFilter,
+    ) {
+        self.sigqueue_subject.register_observer(observer, filter);
+    }
+
+    pub fn unregister_sigqueue_observer(&self, observer: &Weak<dyn Observer<SigEvents>>) {
+        self.sigqueue_subject.unregister_observer(observer);
+    }
+
+    pub(in crate::process) fn sigqueue_subject(&self) -> &Subject<SigEvents, SigEventsFilter> {
+        &self.sigqueue_subject
+    }
+
     /// Clears the parent death signal.
     pub fn clear_parent_death_signal(&self) {
         self.parent_death_signal.clear();
@@ -670,6 +718,14 @@ impl Process {
     }
 }
 
+impl Drop for Process {
+    fn drop(&mut self) {
+        // The PID stays in the PID namespaces until the process is dropped, so
+        // that it can still be translated after the process is reaped.
+        self.pid_ns.remove_tid(self.pid);
+    }
+}
+
 #[cfg(ktest)]
 mod test {
 
@@ -688,6 +744,7 @@ mod test {
         };
         Process::new(
             pid,
+            crate::process::namespace::init_pid_ns().clone(),
             parent,
             String::new(),
             ProcessVm::alloc(),
@@ -19,8 +19,8 @@ impl ProcessFilter {
         // https://elixir.bootlin.com/linux/latest/source/include/uapi/linux/wait.h#L20
         match which {
             0 => Ok(ProcessFilter::Any),
-            1 => Ok(ProcessFilter::WithPid(id as Pid)),
-            2 => Ok(ProcessFilter::WithPgid(id as Pgid)),
+            1 => Ok(ProcessFilter::WithPid(to_global_id(id as Pid))),
+            2 => Ok(ProcessFilter::WithPgid(to_global_id(id as Pgid))),
             3 => todo!(),
             _ => return_errno_with_message!(Errno::EINVAL, "invalid which"),
         }
@@ -32,7 +32,7 @@ impl ProcessFilter {
         // https://man7.org/linux/man-pages/man2/kill.2.html
         if wait_pid < -1 {
             // process group ID is equal to the absolute value of pid.
-            ProcessFilter::WithPgid((-wait_pid) as Pgid)
+            ProcessFilter::WithPgid(to_global_id((-wait_pid) as Pgid))
         } else if wait_pid == -1 {
             // wait for any child process
             ProcessFilter::Any
@@ -42,7 +42,7 @@ impl ProcessFilter {
             ProcessFilter::WithPgid(pgid)
         } else {
             // pid > 0. wait for the child whose process ID is equal to the value of pid.
-            ProcessFilter::WithPid(wait_pid as Pid)
+            ProcessFilter::WithPid(to_global_id(wait_pid as Pid))
         }
     }
 
@@ -54,3 +54,11 @@ impl ProcessFilter {
         }
     }
 }
+
+/// Translates an ID in the PID namespace of the current process to the global ID.
+///
+/// An ID that is invisible in the namespace is translated to zero, which
+/// matches no process.
+fn to_global_id(id: Pid) -> Pid {
+    current!().pid_ns().global_id(id).unwrap_or(0)
+}
@@ -0,0 +1,371 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! An interpreter of the classic Berkeley Packet Filter (cBPF).
+//!
+//! A cBPF program operates on an accumulator `A`, an index register `X` and a
+//! small scratch memory. It is validated when it is loaded, so the interpreter
+//! never jumps out of the program or accesses memory out of bounds.
+//!
+//! Only the instructions that can be used in seccomp filters are supported.
+//! In particular, the input data must be accessed in aligned 32-bit words, and
+//! the words are loaded in the native byte order.
+//!
+//! Reference: <https://www.kernel.org/doc/html/latest/networking/filter.html>.
+
+#![allow(non_camel_case_types)]
+
+use crate::prelude::*;
+
+/// The maximum number of instructions in a cBPF program.
+pub const BPF_MAXINSNS: usize = 4096;
+
+/// The number of words in the scratch memory.
+const BPF_MEMWORDS: usize = 16;
+
+/// A raw cBPF instruction.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+pub struct sock_filter {
+    pub code: u16,
+    pub jt: u8,
+    pub jf: u8,
+    pub k: u32,
+}
+
+/// A raw cBPF program in the user space.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+pub struct sock_fprog {
+    pub len: u16,
+    _padding: [u8; 6],
+    pub filter: Vaddr,
+}
+
+// Instruction classes
+const BPF_LD: u16 = 0x00;
+const BPF_LDX: u16 = 0x01;
+const BPF_ST: u16 = 0x02;
+const BPF_STX: u16 = 0x03;
+const BPF_ALU: u16 = 0x04;
+const BPF_JMP: u16 = 0x05;
+const BPF_RET: u16 = 0x06;
+const BPF_MISC: u16 = 0x07;
+
+// Addressing modes
+const BPF_IMM: u16 = 0x00;
+const BPF_ABS: u16 = 0x20;
+const BPF_MEM: u16 = 0x60;
+const BPF_LEN: u16 = 0x80;
+
+// ALU operations
+const BPF_ADD: u16 = 0x00;
+const BPF_SUB: u16 = 0x10;
+const BPF_MUL: u16 = 0x20;
+const BPF_DIV: u16 = 0x30;
+const BPF_OR: u16 = 0x40;
+const BPF_AND: u16 = 0x50;
+const BPF_LSH: u16 = 0x60;
+const BPF_RSH: u16 = 0x70;
+const BPF_NEG: u16 = 0x80;
+const BPF_MOD: u16 = 0x90;
+const BPF_XOR: u16 = 0xa0;
+
+// Jump operations
+const BPF_JA: u16 = 0x00;
+const BPF_JEQ: u16 = 0x10;
+const BPF_JGT: u16 = 0x20;
+const BPF_JGE: u16 = 0x30;
+const BPF_JSET: u16 = 0x40;
+
+// Operand sources
+const BPF_K: u16 = 0x00;
+const BPF_X: u16 = 0x08;
+const BPF_A: u16 = 0x10;
+
+// Miscellaneous operations
+const BPF_TAX: u16 = 0x00;
+const BPF_TXA: u16 = 0x80;
+
+/// A validated cBPF program.
+#[derive(Debug)]
+pub struct BpfProgram {
+    insns: Vec<Insn>,
+    data_len: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Insn {
+    /// `A = data[k]`
+    LoadAbs(usize),
+    /// `A = k`
+    LoadImm(u32),
+    /// `A = M[k]`
+    LoadMem(usize),
+    /// `X = k`
+    LoadXImm(u32),
+    /// `X = M[k]`
+    LoadXMem(usize),
+    /// `M[k] = A`
+    Store(usize),
+    /// `M[k] = X`
+    StoreX(usize),
+    /// `A = A <op> src`
+    Alu(AluOp, Src),
+    /// `A = -A`
+    Neg,
+    /// `pc += k`
+    Jump(usize),
+    /// `pc += (A <op> src) ? jt : jf`
+    CondJump(JumpOp, Src, usize, usize),
+    /// Returns `k`.
+    RetK(u32),
+    /// Returns `A`.
+    RetA,
+    /// `X = A`
+    Tax,
+    /// `A = X`
+    Txa,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AluOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Or,
+    And,
+    Lsh,
+    Rsh,
+    Mod,
+    Xor,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum JumpOp {
+    Eq,
+    Gt,
+    Ge,
+    Set,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Src {
+    K(u32),
+    X,
+}
+
+impl BpfProgram {
+    /// Validates the raw instructions and creates a program that runs on
+    /// `data_len` bytes of input data.
+    pub fn new(filter: &[sock_filter], data_len: usize) -> Result<Self> {
+        if filter.is_empty() || filter.len() > BPF_MAXINSNS {
+            return_errno_with_message!(Errno::EINVAL, "the BPF program length is invalid");
+        }
+
+        let insns = filter
+            .iter()
+            .enumerate()
+            .map(|(pc, raw)| decode(raw, filter.len() - pc - 1, data_len))
+            .collect::<Result<Vec<_>>>()?;
+
+        if !matches!(insns.last(), Some(Insn::RetK(_) | Insn::RetA)) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the BPF program does not end with a return instruction"
+            );
+        }
+
+        Ok(Self { insns, data_len })
+    }
+
+    /// Returns the number of instructions in the program.
+    pub fn num_insns(&self) -> usize {
+        self.insns.len()
+    }
+
+    /// Runs the program on `data` and returns the result.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the length of `data` is not the one given
+    /// when the program is created.
+    pub fn run(&self, data: &[u8]) -> u32 {
+        assert_eq!(data.len(), self.data_len);
+
+        let mut a: u32 = 0;
+        let mut x: u32 = 0;
+        let mut mem = [0u32; BPF_MEMWORDS];
+        let mut pc = 0;
+
+        loop {
+            let insn = self.insns[pc];
+            pc += 1;
+
+            match insn {
+                Insn::LoadAbs(offset) => {
+                    a = u32::from_ne_bytes(data[offset..offset + 4].try_into().unwrap());
+                }
+                Insn::LoadImm(k) => a = k,
+                Insn::LoadMem(index) => a = mem[index],
+                Insn::LoadXImm(k) => x = k,
+                Insn::LoadXMem(index) => x = mem[index],
+                Insn::Store(index) => mem[index] = a,
+                Insn::StoreX(index) => mem[index] = x,
+                Insn::Alu(op, src) => {
+                    let operand = match src {
+                        Src::K(k) => k,
+                        Src::X => x,
+                    };
+                    a = match op {
+                        AluOp::Add => a.wrapping_add(operand),
+                        AluOp::Sub => a.wrapping_sub(operand),
+                        AluOp::Mul => a.wrapping_mul(operand),
+                        // A division by a zero `X` terminates the program.
+                        AluOp::Div => match a.checked_div(operand) {
+                            Some(result) => result,
+                            None => return 0,
+                        },
+                        AluOp::Mod => match a.checked_rem(operand) {
+                            Some(result) => result,
+                            None => return 0,
+                        },
+                        AluOp::Or => a | operand,
+                        AluOp::And => a & operand,
+                        AluOp::Lsh => a.checked_shl(operand).unwrap_or(0),
+                        AluOp::Rsh => a.checked_shr(operand).unwrap_or(0),
+                        AluOp::Xor => a ^ operand,
+                    };
+                }
+                Insn::Neg => a = a.wrapping_neg(),
+                Insn::Jump(offset) => pc += offset,
+                Insn::CondJump(op, src, jt, jf) => {
+                    let operand = match src {
+                        Src::K(k) => k,
+                        Src::X => x,
+                    };
+                    let is_true = match op {
+                        JumpOp::Eq => a == operand,
+                        JumpOp::Gt => a > operand,
+                        JumpOp::Ge => a >= operand,
+                        JumpOp::Set => a & operand != 0,
+                    };
+                    pc += if is_true { jt } else { jf };
+                }
+                Insn::RetK(k) => return k,
+                Insn::RetA => return a,
+                Insn::Tax => x = a,
+                Insn::Txa => a = x,
+            }
+        }
+    }
+}
+
+/// Decodes a raw instruction, which is followed by `remaining` instructions.
+fn decode(raw: &sock_filter, remaining: usize, data_len: usize) -> Result<Insn> {
+    let code = raw.code;
+    let k = raw.k;
+
+    let mem_index = || {
+        if (k as usize) < BPF_MEMWORDS {
+            Ok(k as usize)
+        } else {
+            Err(Error::with_message(
+                Errno::EINVAL,
+                "the BPF memory index is out of bounds",
+            ))
+        }
+    };
+    let jump_offset = |offset: usize| {
+        if offset < remaining {
+            Ok(offset)
+        } else {
+            Err(Error::with_message(
+                Errno::EINVAL,
+                "the BPF jump target is out of bounds",
+            ))
+        }
+    };
+
+    if code > 0xff {
+        return_errno_with_message!(Errno::EINVAL, "the BPF instruction is invalid");
+    }
+
+    // Only 32-bit words can be loaded, and `BPF_W` is zero, so the size bits
+    // are checked together with the mode bits.
+    let insn = match (code & 0x07, code & 0xf8) {
+        (BPF_LD, BPF_ABS) => {
+            let offset = k as usize;
+            if offset % 4 != 0 || offset + 4 > data_len {
+                return_errno_with_message!(Errno::EINVAL, "the BPF load offset is invalid");
+            }
+            Insn::LoadAbs(offset)
+        }
+        // The input data has a fixed length.
+        (BPF_LD, BPF_LEN) => Insn::LoadImm(data_len as u32),
+        (BPF_LD, BPF_IMM) => Insn::LoadImm(k),
+        (BPF_LD, BPF_MEM) => Insn::LoadMem(mem_index()?),
+        (BPF_LDX, BPF_LEN) => Insn::LoadXImm(data_len as u32),
+        (BPF_LDX, BPF_IMM) => Insn::LoadXImm(k),
+        (BPF_LDX, BPF_MEM) => Insn::LoadXMem(mem_index()?),
+        (BPF_ST, 0) => Insn::Store(mem_index()?),
+        (BPF_STX, 0) => Insn::StoreX(mem_index()?),
+        (BPF_ALU, BPF_NEG) => Insn::Neg,
+        (BPF_ALU, op_and_src) => {
+            let op = match op_and_src & 0xf0 {
+                BPF_ADD => AluOp::Add,
+                BPF_SUB => AluOp::Sub,
+                BPF_MUL => AluOp::Mul,
+                BPF_DIV => AluOp::Div,
+                BPF_OR => AluOp::Or,
+                BPF_AND => AluOp::And,
+                BPF_LSH => AluOp::Lsh,
+                BPF_RSH => AluOp::Rsh,
+                BPF_MOD => AluOp::Mod,
+                BPF_XOR => AluOp::Xor,
+                _ => return_errno_with_message!(Errno::EINVAL, "the BPF ALU operation is invalid"),
+            };
+            let src = decode_src(op_and_src, k);
+            if let Src::K(k) = src {
+                if matches!(op, AluOp::Div | AluOp::Mod) && k == 0 {
+                    return_errno_with_message!(Errno::EINVAL, "the BPF program divides by zero");
+                }
+                if matches!(op, AluOp::Lsh | AluOp::Rsh) && k >= 32 {
+                    return_errno_with_message!(Errno::EINVAL, "the BPF shift is too large");
+                }
+            }
+            Insn::Alu(op, src)
+        }
+        (BPF_JMP, BPF_JA) => Insn::Jump(jump_offset(k as usize)?),
+        (BPF_JMP, op_and_src) => {
+            let op = match op_and_src & 0xf0 {
+                BPF_JEQ => JumpOp::Eq,
+                BPF_JGT => JumpOp::Gt,
+                BPF_JGE => JumpOp::Ge,
+                BPF_JSET => JumpOp::Set,
+                _ => return_errno_with_message!(Errno::EINVAL, "the BPF jump is invalid"),
+            };
+            Insn::CondJump(
+                op,
+                decode_src(op_and_src, k),
+                jump_offset(raw.jt as usize)?,
+                jump_offset(raw.jf as usize)?,
+            )
+        }
+        (BPF_RET, BPF_K) => Insn::RetK(k),
+        (BPF_RET, BPF_A) => Insn::RetA,
+        (BPF_MISC, BPF_TAX) => Insn::Tax,
+        (BPF_MISC, BPF_TXA) => Insn::Txa,
+        _ => return_errno_with_message!(Errno::EINVAL, "the BPF instruction is not supported"),
+    };
+
+    Ok(insn)
+}
+
+fn decode_src(op_and_src: u16, k: u32) -> Src {
+    if op_and_src & BPF_X != 0 {
+        Src::X
+    } else {
+        Src::K(k)
+    }
+}
@@ -0,0 +1,303 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Secure computing mode (seccomp).
+//!
+//! A thread can install filters to restrict the syscalls that it can make.
+//! Each filter is a classic BPF program that runs on the number and the
+//! arguments of a syscall and decides what to do with the syscall. The filters are inherited
+//! by new threads and are kept across `execve`, and they can never be removed.
+//!
+//! Reference: <https://www.kernel.org/doc/html/latest/userspace-api/seccomp_filter.html>.
+
+use core::sync::atomic::Ordering;
+
+use ostd::{cpu::UserContext, user::UserContextApi};
+
+use self::bpf::BpfProgram;
+pub use self::bpf::{sock_filter, sock_fprog, BPF_MAXINSNS};
+use super::{
+    credentials::capabilities::CapSet,
+    posix_thread::{do_exit, do_exit_group, AsPosixThread, PosixThread},
+    signal::{constants::SIGSYS, sig_action::SigAction, signals::seccomp::SeccompSignal},
+    TermStatus,
+};
+use crate::{cpu::LinuxAbi, prelude::*, thread::Tid};
+
+mod bpf;
+
+/// The seccomp state of a POSIX thread.
+pub struct SeccompState {
+    /// The most recently installed filter, which links to the older ones.
+    filter: RwLock<Option<Arc<SeccompFilter>>>,
+}
+
+impl SeccompState {
+    pub(super) fn new() -> Self {
+        Self {
+            filter: RwLock::new(None),
+        }
+    }
+
+    /// Creates the state of a new thread, which inherits the filters of `other`.
+    pub(super) fn new_from(other: &Self) -> Self {
+        Self {
+            filter: RwLock::new(other.filter.read().clone()),
+        }
+    }
+
+    /// Returns whether any filter is installed.
+    pub fn is_filtered(&self) -> bool {
+        self.filter.read().is_some()
+    }
+}
+
+struct SeccompFilter {
+    program: BpfProgram,
+    /// The filter installed before this one.
+    prev: Option<Arc<SeccompFilter>>,
+    /// The total number of instructions of this filter and the previous ones.
+    path_len: usize,
+}
+
+impl SeccompFilter {
+    /// Runs all the filters and returns the result with the highest precedence.
+    fn run(&self, data: &SeccompData) -> u32 {
+        let mut ret = SECCOMP_RET_ALLOW;
+
+        let mut filter = Some(self);
+        while let Some(current) = filter {
+            let current_ret = current.program.run(data.as_bytes());
+            // The action with the lowest signed value has the highest precedence.
+            if ((current_ret & SECCOMP_RET_ACTION_FULL) as i32)
+                < ((ret & SECCOMP_RET_ACTION_FULL) as i32)
+            {
+                ret = current_ret;
+            }
+            filter = current.prev.as_deref();
+        }
+
+        ret
+    }
+
+    /// Returns whether `ancestor` is this filter or one of the previous ones.
+    fn is_descendant_of(&self, ancestor: &SeccompFilter) -> bool {
+        let mut filter = Some(self);
+        while let Some(current) = filter {
+            if core::ptr::eq(current, ancestor) {
+                return true;
+            }
+            filter = current.prev.as_deref();
+        }
+        false
+    }
+}
+
+impl Drop for SeccompFilter {
+    fn drop(&mut self) {
+        // Drops the chain iteratively to avoid deep recursion.
+        let mut prev = self.prev.take();
+        while let Some(filter) = prev {
+            prev = Arc::into_inner(filter).and_then(|mut filter| filter.prev.take());
+        }
+    }
+}
+
+/// The input of seccomp filters.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+struct SeccompData {
+    nr: i32,
+    arch: u32,
+    instruction_pointer: u64,
+    args: [u64; 6],
+}
+
+/// The maximum number of instructions in the filters of a thread.
+///
+/// Each filter is charged four more instructions as the overhead.
+const MAX_INSNS_PER_PATH: usize = 32768;
+
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_KILL_THREAD: u32 = 0x0000_0000;
+const SECCOMP_RET_TRAP: u32 = 0x0003_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+const SECCOMP_RET_TRACE: u32 = 0x7ff0_0000;
+const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+const SECCOMP_RET_ACTION_FULL: u32 = 0xffff_0000;
+const SECCOMP_RET_DATA: u32 = 0x0000_ffff;
+
+/// The maximum error number that a filter can return.
+const MAX_ERRNO: u32 = 4095;
+
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "x86_64")] {
+        /// `AUDIT_ARCH_X86_64`
+        const AUDIT_ARCH: u32 = 0xc000_003e;
+    } else if #[cfg(target_arch = "riscv64")] {
+        /// `AUDIT_ARCH_RISCV64`
+        const AUDIT_ARCH: u32 = 0xc000_00f3;
+    }
+}
+
+/// Installs a filter with the raw instructions for the current thread.
+///
+/// If `syncs_threads` is true, the filter is also installed for the other
+/// threads in the current process. This fails if the filters of some thread
+/// are not the ancestors of the filters of the current thread, in which case
+/// the ID of the thread is returned and no filter is installed.
+pub fn seccomp_set_filter(
+    filter: &[sock_filter],
+    syncs_threads: bool,
+    ctx: &Context,
+) -> Result<Option<Tid>> {
+    let posix_thread = ctx.posix_thread;
+
+    // Without `no_new_privs`, a filter may fool a privileged program executed
+    // later, so only privileged threads can install filters.
+    if !posix_thread.no_new_privs()
+        && !posix_thread
+            .credentials()
+            .effective_capset()
+            .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(
+            Errno::EACCES,
+            "installing a seccomp filter requires no_new_privs or CAP_SYS_ADMIN"
+        );
+    }
+
+    let program = BpfProgram::new(filter, size_of::<SeccompData>())?;
+
+    // Holds the lock so that no thread can be created or exit during the synchronization.
+    let tasks = ctx.process.tasks().lock();
+
+    let prev = posix_thread.seccomp().filter.read().clone();
+    let path_len = prev.as_ref().map_or(0, |prev| prev.path_len) + program.num_insns() + 4;
+    if path_len > MAX_INSNS_PER_PATH {
+        return_errno_with_message!(Errno::ENOMEM, "the seccomp filters are too long");
+    }
+    let filter = Arc::new(SeccompFilter {
+        program,
+        prev,
+        path_len,
+    });
+
+    if !syncs_threads {
+        *posix_thread.seccomp().filter.write() = Some(filter);
+        return Ok(None);
+    }
+
+    let other_threads = || {
+        tasks
+            .as_slice()
+            .iter()
+            .map(|task| task.as_posix_thread().unwrap())
+            .filter(|thread| !core::ptr::eq(*thread, posix_thread))
+    };
+
+    for thread in other_threads() {
+        let can_sync = match thread.seccomp().filter.read().as_deref() {
+            None => true,
+            Some(thread_filter) => filter.is_descendant_of(thread_filter),
+        };
+        if !can_sync {
+            return Ok(Some(thread.tid()));
+        }
+    }
+
+    let no_new_privs = posix_thread.no_new_privs();
+    for thread in other_threads().chain(core::iter::once(posix_thread)) {
+        *thread.seccomp().filter.write() = Some(filter.clone());
+        if no_new_privs {
+            thread.set_no_new_privs();
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns whether the filters can return the action.
+pub fn seccomp_is_action_available(action: u32) -> bool {
+    matches!(
+        action,
+        SECCOMP_RET_KILL_PROCESS
+            | SECCOMP_RET_KILL_THREAD
+            | SECCOMP_RET_TRAP
+            | SECCOMP_RET_ERRNO
+            | SECCOMP_RET_LOG
+            | SECCOMP_RET_ALLOW
+    )
+}
+
+/// Runs the seccomp filters of the current thread on a syscall.
+///
+/// Returns whether the syscall is allowed. If it is not allowed, the return
+/// value of the syscall, if any, has been set in `user_ctx`.
+pub fn seccomp_check_syscall(user_ctx: &mut UserContext, ctx: &Context) -> bool {
+    let Some(filter) = ctx.posix_thread.seccomp().filter.read().clone() else {
+        return true;
+    };
+
+    let syscall_num = user_ctx.syscall_num();
+    let data = SeccompData {
+        nr: syscall_num as i32,
+        arch: AUDIT_ARCH,
+        instruction_pointer: user_ctx.instruction_pointer() as u64,
+        args: user_ctx.syscall_args().map(|arg| arg as u64),
+    };
+    let ret = filter.run(&data);
+    let ret_data = ret & SECCOMP_RET_DATA;
+
+    match ret & SECCOMP_RET_ACTION_FULL {
+        SECCOMP_RET_ALLOW | SECCOMP_RET_LOG => return true,
+        SECCOMP_RET_ERRNO => {
+            let errno = ret_data.min(MAX_ERRNO);
+            user_ctx.set_syscall_ret(-(errno as isize) as usize);
+        }
+        SECCOMP_RET_TRAP => {
+            // The registers are left unchanged, as if the syscall had not been made.
+            let signal = SeccompSignal::new(
+                data.instruction_pointer as Vaddr,
+                data.nr,
+                data.arch,
+                ret_data as i32,
+            );
+            force_sigsys(signal, ctx.posix_thread);
+        }
+        // There is no tracer or supervisor that handles the syscall.
+        SECCOMP_RET_TRACE | SECCOMP_RET_USER_NOTIF => {
+            user_ctx.set_syscall_ret(-(Errno::ENOSYS as isize) as usize);
+        }
+        SECCOMP_RET_KILL_THREAD => {
+            debug!("the thread is killed by seccomp, syscall = {}", syscall_num);
+            do_exit(TermStatus::Killed(SIGSYS));
+        }
+        _ => {
+            debug!(
+                "the process is killed by seccomp, syscall = {}",
+                syscall_num
+            );
+            do_exit_group(TermStatus::Killed(SIGSYS));
+        }
+    }
+
+    false
+}
+
+/// Sends `SIGSYS` to the thread, which cannot be blocked or ignored.
+fn force_sigsys(signal: SeccompSignal, posix_thread: &PosixThread) {
+    let process = posix_thread.process();
+    let mut sig_dispositions = process.sig_dispositions().lock();
+
+    if posix_thread.has_signal_blocked(SIGSYS) || sig_dispositions.get(SIGSYS) == SigAction::Ign {
+        sig_dispositions.set_default(SIGSYS);
+        let sig_mask = posix_thread.sig_mask();
+        sig_mask.store(sig_mask.load(Ordering::Relaxed) - SIGSYS, Ordering::Relaxed);
+    }
+    drop(sig_dispositions);
+
+    posix_thread.enqueue_signal(Box::new(signal));
+}
@@ -58,6 +58,30 @@ impl siginfo_t {
         // let siginfo = *self;
         read_union_fields!(self.siginfo_fields.sigfault.addr)
     }
+
+    pub fn set_si_pid_uid(&mut self, pid: Pid, uid: Uid) {
+        self.siginfo_fields.common.first.piduid = siginfo_piduid_t { pid, uid };
+    }
+
+    pub fn si_pid(&self) -> Pid {
+        read_union_fields!(self.siginfo_fields.common.first.piduid.pid)
+    }
+
+    pub fn si_uid(&self) -> Uid {
+        read_union_fields!(self.siginfo_fields.common.first.piduid.uid)
+    }
+
+    pub fn set_si_value(&mut self, value: sigval_t) {
+        self.siginfo_fields.common.second.value = value;
+    }
+
+    pub fn set_si_sigsys(&mut self, call_addr: Vaddr, syscall: i32, arch: u32) {
+        self.siginfo_fields.sigsys = siginfo_sigsys_t {
+            call_addr,
+            syscall,
+            arch,
+        };
+    }
 }
 
 #[derive(Clone, Copy, Pod)]
@@ -66,6 +90,7 @@ union siginfo_fields_t {
     bytes: [u8; 128 - mem::size_of::<i32>() * 4],
     common: siginfo_common_t,
     sigfault: siginfo_sigfault_t,
+    sigsys: siginfo_sigsys_t,
 }
 
 impl siginfo_fields_t {
@@ -78,7 +103,7 @@ impl siginfo_fields_t {
 
 #[derive(Clone, Copy, Pod)]
 #[repr(C)]
-union siginfo_common_t {
+struct siginfo_common_t {
     first: siginfo_common_first_t,
     second: siginfo_common_second_t,
 }
@@ -158,6 +183,14 @@ union siginfo_addr_bnd_t {
     upper: Vaddr, // *const c_void,
 }
 
+#[derive(Clone, Copy, Pod)]
+#[repr(C)]
+struct siginfo_sigsys_t {
+    call_addr: Vaddr, //*const c_void
+    syscall: i32,
+    arch: u32,
+}
+
 #[derive(Clone, Copy, Debug, Pod)]
 #[repr(C)]
 pub struct ucontext_t {
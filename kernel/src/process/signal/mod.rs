@@ -25,8 +25,9 @@ use sig_action::{SigAction, SigActionFlags, SigDefaultAction};
 use sig_mask::SigMask;
 use sig_num::SigNum;
 pub use sig_stack::{SigStack, SigStackFlags};
+use signals::{kernel::KernelSignal, Signal};
 
-use super::posix_thread::PosixThread;
+use super::posix_thread::{ptrace_signal_stop, PosixThread};
 use crate::{
     cpu::LinuxAbi,
     current_userspace,
@@ -58,6 +59,26 @@ pub fn handle_pending_signal(
         }
     };
 
+    // A traced thread reports the signal to its tracer first, which may change
+    // or suppress the signal.
+    let signal: Box<dyn Signal> = if posix_thread.ptrace().is_traced() {
+        let sig_num = signal.num();
+        match ptrace_signal_stop(sig_num, user_ctx, ctx) {
+            Some(new_sig_num) if new_sig_num == sig_num => signal,
+            Some(new_sig_num) => Box::new(KernelSignal::new(new_sig_num)),
+            None => {
+                if let Some(syscall_number) = syscall_number
+                    && user_ctx.syscall_ret() == -(Errno::ERESTARTSYS as i32) as usize
+                {
+                    restart_syscall(user_ctx, syscall_number);
+                }
+                return Ok(());
+            }
+        }
+    } else {
+        signal
+    };
+
     let sig_num = signal.num();
     trace!("sig_num = {:?}, sig_name = {}", sig_num, sig_num.sig_name());
     let current = posix_thread.process();
@@ -78,8 +99,7 @@ pub fn handle_pending_signal(
                 && user_ctx.syscall_ret() == -(Errno::ERESTARTSYS as i32) as usize
             {
                 if flags.contains(SigActionFlags::SA_RESTART) {
-                    user_ctx.set_syscall_num(syscall_number);
-                    user_ctx.set_instruction_pointer(user_ctx.instruction_pointer() - 2);
+                    restart_syscall(user_ctx, syscall_number);
                 } else {
                     user_ctx.set_syscall_ret(-(Errno::EINTR as i32) as usize);
                 }
@@ -133,6 +153,12 @@ pub fn handle_pending_signal(
     Ok(())
 }
 
+/// Makes the interrupted syscall restart when returning to the user space.
+fn restart_syscall(user_ctx: &mut UserContext, syscall_number: usize) {
+    user_ctx.set_syscall_num(syscall_number);
+    user_ctx.set_instruction_pointer(user_ctx.instruction_pointer() - 2);
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn handle_user_signal(
     ctx: &Context,
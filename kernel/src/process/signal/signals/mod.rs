@@ -2,6 +2,7 @@
 
 pub mod fault;
 pub mod kernel;
+pub mod seccomp;
 pub mod user;
 
 use core::{any::Any, fmt::Debug};
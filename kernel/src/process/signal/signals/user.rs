@@ -4,6 +4,7 @@
 
 use super::Signal;
 use crate::process::{
+    namespace::PidNamespace,
     signal::{
         c_types::siginfo_t,
         constants::{SI_QUEUE, SI_TKILL, SI_USER},
@@ -44,6 +45,14 @@ impl UserSignal {
     pub fn kind(&self) -> UserSignalKind {
         self.kind
     }
+
+    /// Translates the global PID of the sender to the PID in `pid_ns`.
+    ///
+    /// The PID becomes zero if the sender is invisible in the namespace.
+    pub fn translate_pid(mut self, pid_ns: &PidNamespace) -> Self {
+        self.pid = pid_ns.local_id(self.pid).unwrap_or(0);
+        self
+    }
 }
 
 impl Signal for UserSignal {
@@ -58,11 +67,12 @@ impl Signal for UserSignal {
             UserSignalKind::Sigqueue => SI_QUEUE,
         };
 
-        siginfo_t::new(self.num, code)
-        // info.set_si_pid(self.pid);
-        // info.set_si_uid(self.uid);
+        let mut info = siginfo_t::new(self.num, code);
+        info.set_si_pid_uid(self.pid, self.uid);
         // if let UserSignalKind::Sigqueue(val) = self.kind {
         //     info.set_si_value(val);
         // }
+
+        info
     }
 }
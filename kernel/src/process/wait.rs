@@ -10,6 +10,7 @@ use crate::{
         process_table,
         signal::with_signal_blocked,
     },
+    thread::Thread,
 };
 
 // The definition of WaitOptions is from Occlum
@@ -34,13 +35,48 @@ impl WaitOptions {
     }
 }
 
+/// The status of a child that has been waited for.
+pub enum WaitStatus {
+    /// The child process has exited.
+    Zombie(Arc<Process>),
+    /// The traced thread is in a ptrace-stop, with the stop status.
+    PtraceStopped(Arc<Thread>, u32),
+}
+
+impl WaitStatus {
+    /// Returns the process of the child.
+    pub fn process(&self) -> Arc<Process> {
+        match self {
+            WaitStatus::Zombie(process) => process.clone(),
+            WaitStatus::PtraceStopped(thread, _) => thread.as_posix_thread().unwrap().process(),
+        }
+    }
+
+    /// Returns the PID of the exited process, or the TID of the stopped
+    /// thread.
+    pub fn pid(&self) -> Pid {
+        match self {
+            WaitStatus::Zombie(process) => process.pid(),
+            WaitStatus::PtraceStopped(thread, _) => thread.as_posix_thread().unwrap().tid(),
+        }
+    }
+
+    /// Returns the status that is reported to the user space.
+    pub fn status_code(&self) -> u32 {
+        match self {
+            WaitStatus::Zombie(process) => process.status().exit_code(),
+            WaitStatus::PtraceStopped(_, status) => *status,
+        }
+    }
+}
+
 pub fn wait_child_exit(
     child_filter: ProcessFilter,
     wait_options: WaitOptions,
     ctx: &Context,
-) -> Result<Option<Arc<Process>>> {
+) -> Result<Option<WaitStatus>> {
     let current = ctx.process;
-    let zombie_child = with_signal_blocked(ctx, SIGCHLD.into(), || {
+    let wait_status = with_signal_blocked(ctx, SIGCHLD.into(), || {
         current.children_wait_queue().pause_until(|| {
             let unwaited_children = current
                 .children()
@@ -54,7 +90,22 @@ pub fn wait_child_exit(
                 .cloned()
                 .collect::<Vec<_>>();
 
-            if unwaited_children.is_empty() {
+            let tracees = current
+                .tracees()
+                .lock()
+                .values()
+                .filter(|thread| {
+                    let posix_thread = thread.as_posix_thread().unwrap();
+                    match child_filter {
+                        ProcessFilter::Any => true,
+                        ProcessFilter::WithPid(pid) => posix_thread.tid() == pid,
+                        ProcessFilter::WithPgid(pgid) => posix_thread.process().pgid() == pgid,
+                    }
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if unwaited_children.is_empty() && tracees.is_empty() {
                 return Some(Err(Error::with_message(
                     Errno::ECHILD,
                     "the process has no child to wait",
@@ -70,10 +121,19 @@ pub fn wait_child_exit(
                 let zombie_pid = zombie_child.pid();
                 if wait_options.contains(WaitOptions::WNOWAIT) {
                     // does not reap child, directly return
-                    return Some(Ok(Some(zombie_child.clone())));
+                    return Some(Ok(Some(WaitStatus::Zombie(zombie_child.clone()))));
                 } else {
                     reap_zombie_child(current, zombie_pid);
-                    return Some(Ok(Some(zombie_child.clone())));
+                    return Some(Ok(Some(WaitStatus::Zombie(zombie_child.clone()))));
+                }
+            }
+
+            // return immediately if we find a traced thread in a ptrace-stop
+            let marks_reported = !wait_options.contains(WaitOptions::WNOWAIT);
+            for tracee in tracees.iter() {
+                let ptrace = tracee.as_posix_thread().unwrap().ptrace();
+                if let Some(status) = ptrace.report_stop(marks_reported) {
+                    return Some(Ok(Some(WaitStatus::PtraceStopped(tracee.clone(), status))));
                 }
             }
 
@@ -86,7 +146,7 @@ pub fn wait_child_exit(
         })
     })??;
 
-    Ok(zombie_child)
+    Ok(wait_status)
 }
 
 /// Free zombie child with pid, returns the exit code of child process.
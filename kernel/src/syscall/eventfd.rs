@@ -73,7 +73,8 @@ bitflags! {
     }
 }
 
-struct EventFile {
+/// A file that is created by `eventfd`.
+pub struct EventFile {
     counter: Mutex<u64>,
     pollee: Pollee,
     flags: Mutex<Flags>,
@@ -161,24 +162,15 @@ impl EventFile {
 
         return_errno_with_message!(Errno::EINVAL, "new value exceeds MAX_COUNTER_VALUE");
     }
-}
-
-impl Pollable for EventFile {
-    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
-        self.pollee
-            .poll_with(mask, poller, || self.check_io_events())
-    }
-}
 
-impl FileLike for EventFile {
-    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+    fn read_impl(&self, writer: &mut VmWriter, is_nonblocking: bool) -> Result<usize> {
         let read_len = core::mem::size_of::<u64>();
 
         if writer.avail() < read_len {
             return_errno_with_message!(Errno::EINVAL, "buf len is less len u64 size");
         }
 
-        if self.is_nonblocking() {
+        if is_nonblocking {
             self.try_read(writer)?;
         } else {
             self.wait_events(IoEvents::IN, None, || self.try_read(writer))?;
@@ -187,7 +179,7 @@ impl FileLike for EventFile {
         Ok(read_len)
     }
 
-    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+    fn write_impl(&self, reader: &mut VmReader, is_nonblocking: bool) -> Result<usize> {
         let write_len = core::mem::size_of::<u64>();
         if reader.remain() < write_len {
             return_errno_with_message!(Errno::EINVAL, "buf len is less than the size of u64");
@@ -200,7 +192,7 @@ impl FileLike for EventFile {
             return Ok(write_len);
         }
 
-        if self.is_nonblocking() {
+        if is_nonblocking {
             return_errno_with_message!(Errno::EAGAIN, "try writing to event file again");
         }
 
@@ -210,6 +202,31 @@ impl FileLike for EventFile {
 
         Ok(write_len)
     }
+}
+
+impl Pollable for EventFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for EventFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_impl(writer, self.is_nonblocking())
+    }
+
+    fn write(&self, reader: &mut VmReader) -> Result<usize> {
+        self.write_impl(reader, self.is_nonblocking())
+    }
+
+    fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_impl(writer, true)
+    }
+
+    fn write_nonblocking(&self, reader: &mut VmReader) -> Result<usize> {
+        self.write_impl(reader, true)
+    }
 
     fn status_flags(&self) -> StatusFlags {
         if self.is_nonblocking() {
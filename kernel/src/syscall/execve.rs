@@ -16,8 +16,9 @@ use crate::{
     },
     prelude::*,
     process::{
-        check_executable_file, load_program_to_vm, posix_thread::ThreadName, Credentials, Process,
-        MAX_ARGV_NUMBER, MAX_ARG_LEN, MAX_ENVP_NUMBER, MAX_ENV_LEN,
+        check_executable_file, load_program_to_vm,
+        posix_thread::{ptrace_exec, ThreadName},
+        Credentials, Process, MAX_ARGV_NUMBER, MAX_ARG_LEN, MAX_ENVP_NUMBER, MAX_ENV_LEN,
     },
 };
 
@@ -127,8 +128,9 @@ fn do_execve(
     debug!("load elf in execve succeeds");
 
     let credentials = ctx.posix_thread.credentials_mut();
-    set_uid_from_elf(process, &credentials, &elf_file)?;
-    set_gid_from_elf(process, &credentials, &elf_file)?;
+    let no_new_privs = ctx.posix_thread.no_new_privs();
+    set_uid_from_elf(process, &credentials, &elf_file, no_new_privs)?;
+    set_gid_from_elf(process, &credentials, &elf_file, no_new_privs)?;
     credentials.set_keep_capabilities(false);
 
     // set executable path
@@ -150,6 +152,8 @@ fn do_execve(
     // set new user stack top
     user_context.set_stack_pointer(elf_load_info.user_stack_top() as _);
     debug!("user stack top: 0x{:x}", elf_load_info.user_stack_top());
+    // notify the tracer, if any, that the new program is loaded
+    ptrace_exec(ctx);
     Ok(())
 }
 
@@ -199,12 +203,15 @@ fn read_cstring_vec(
 }
 
 /// Sets uid for credentials as the same of uid of elf file if elf file has `set_uid` bit.
+///
+/// The `set_uid` bit is ignored if the thread has `no_new_privs` set.
 fn set_uid_from_elf(
     current: &Process,
     credentials: &Credentials<WriteOp>,
     elf_file: &Dentry,
+    no_new_privs: bool,
 ) -> Result<()> {
-    if elf_file.mode()?.has_set_uid() {
+    if elf_file.mode()?.has_set_uid() && !no_new_privs {
         let uid = elf_file.owner()?;
         credentials.set_euid(uid);
 
@@ -217,12 +224,15 @@ fn set_uid_from_elf(
 }
 
 /// Sets gid for credentials as the same of gid of elf file if elf file has `set_gid` bit.
+///
+/// The `set_gid` bit is ignored if the thread has `no_new_privs` set.
 fn set_gid_from_elf(
     current: &Process,
     credentials: &Credentials<WriteOp>,
     elf_file: &Dentry,
+    no_new_privs: bool,
 ) -> Result<()> {
-    if elf_file.mode()?.has_set_gid() {
+    if elf_file.mode()?.has_set_gid() && !no_new_privs {
         let gid = elf_file.group()?;
         credentials.set_egid(gid);
 
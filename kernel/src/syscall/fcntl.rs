@@ -7,7 +7,8 @@ use crate::{
         file_table::{FdFlags, FileDesc},
         inode_handle::InodeHandle,
         utils::{
-            FileRange, RangeLockItem, RangeLockItemBuilder, RangeLockType, StatusFlags, OFFSET_MAX,
+            FileRange, FileSeals, RangeLockItem, RangeLockItemBuilder, RangeLockType, StatusFlags,
+            OFFSET_MAX,
         },
     },
     prelude::*,
@@ -32,6 +33,8 @@ pub fn sys_fcntl(fd: FileDesc, cmd: i32, arg: u64, ctx: &Context) -> Result<Sysc
         }),
         FcntlCmd::F_GETOWN => handle_getown(fd, ctx),
         FcntlCmd::F_SETOWN => handle_setown(fd, arg, ctx),
+        FcntlCmd::F_ADD_SEALS => handle_addseals(fd, arg, ctx),
+        FcntlCmd::F_GET_SEALS => handle_getseals(fd, ctx),
     }
 }
 
@@ -140,7 +143,10 @@ fn handle_setlk(
 fn handle_getown(fd: FileDesc, ctx: &Context) -> Result<SyscallReturn> {
     let file_table = ctx.posix_thread.file_table().lock();
     let file_entry = file_table.get_entry(fd)?;
-    let pid = file_entry.owner().unwrap_or(0);
+    let pid = file_entry
+        .owner()
+        .and_then(|pid| ctx.process.pid_ns().local_id(pid))
+        .unwrap_or(0);
     Ok(SyscallReturn::Return(pid as _))
 }
 
@@ -156,10 +162,16 @@ fn handle_setown(fd: FileDesc, arg: u64, ctx: &Context) -> Result<SyscallReturn>
     let owner_process = if pid == 0 {
         None
     } else {
-        Some(process_table::get_process(pid).ok_or(Error::with_message(
-            Errno::ESRCH,
-            "cannot set_owner with an invalid pid",
-        ))?)
+        let process = ctx
+            .process
+            .pid_ns()
+            .global_id(pid)
+            .and_then(process_table::get_process)
+            .ok_or(Error::with_message(
+                Errno::ESRCH,
+                "cannot set_owner with an invalid pid",
+            ))?;
+        Some(process)
     };
 
     let mut file_table = ctx.posix_thread.file_table().lock();
@@ -168,6 +180,37 @@ fn handle_setown(fd: FileDesc, arg: u64, ctx: &Context) -> Result<SyscallReturn>
     Ok(SyscallReturn::Return(0))
 }
 
+fn handle_addseals(fd: FileDesc, arg: u64, ctx: &Context) -> Result<SyscallReturn> {
+    let file = {
+        let file_table = ctx.posix_thread.file_table().lock();
+        file_table.get_file(fd)?.clone()
+    };
+    let inode_file = file
+        .downcast_ref::<InodeHandle>()
+        .ok_or(Error::with_message(Errno::EINVAL, "not inode"))?;
+    if !inode_file.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EPERM, "the file is not opened for writing");
+    }
+    let seals = u32::try_from(arg)
+        .ok()
+        .and_then(FileSeals::from_bits)
+        .ok_or(Error::with_message(Errno::EINVAL, "invalid seals"))?;
+    inode_file.dentry().inode().add_seals(seals)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+fn handle_getseals(fd: FileDesc, ctx: &Context) -> Result<SyscallReturn> {
+    let file = {
+        let file_table = ctx.posix_thread.file_table().lock();
+        file_table.get_file(fd)?.clone()
+    };
+    let inode_file = file
+        .downcast_ref::<InodeHandle>()
+        .ok_or(Error::with_message(Errno::EINVAL, "not inode"))?;
+    let seals = inode_file.dentry().inode().seals()?;
+    Ok(SyscallReturn::Return(seals.bits() as _))
+}
+
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, TryFromInt)]
 #[allow(non_camel_case_types)]
@@ -183,6 +226,8 @@ enum FcntlCmd {
     F_SETOWN = 8,
     F_GETOWN = 9,
     F_DUPFD_CLOEXEC = 1030,
+    F_ADD_SEALS = 1033,
+    F_GET_SEALS = 1034,
 }
 
 #[allow(non_camel_case_types)]
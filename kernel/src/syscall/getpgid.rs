@@ -15,10 +15,15 @@ pub fn sys_getpgid(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
 
     // if pid is 0, should return the pgid of current process
     if pid == 0 {
-        return Ok(SyscallReturn::Return(ctx.process.pgid() as _));
+        let pgid = ctx.process.pid_ns().local_id(ctx.process.pgid());
+        return Ok(SyscallReturn::Return(pgid.unwrap_or(0) as _));
     }
 
-    let process = process_table::get_process(pid)
+    let process = ctx
+        .process
+        .pid_ns()
+        .global_id(pid)
+        .and_then(process_table::get_process)
         .ok_or(Error::with_message(Errno::ESRCH, "process does not exist"))?;
 
     if !Arc::ptr_eq(&ctx.process.session().unwrap(), &process.session().unwrap()) {
@@ -28,5 +33,6 @@ pub fn sys_getpgid(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
         );
     }
 
-    Ok(SyscallReturn::Return(process.pgid() as _))
+    let pgid = ctx.process.pid_ns().local_id(process.pgid());
+    Ok(SyscallReturn::Return(pgid.unwrap_or(0) as _))
 }
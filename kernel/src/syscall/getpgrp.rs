@@ -4,5 +4,7 @@ use super::SyscallReturn;
 use crate::prelude::*;
 
 pub fn sys_getpgrp(ctx: &Context) -> Result<SyscallReturn> {
-    Ok(SyscallReturn::Return(ctx.process.pgid() as _))
+    // The process group is invisible if it is created outside the PID namespace.
+    let pgid = ctx.process.pid_ns().local_id(ctx.process.pgid());
+    Ok(SyscallReturn::Return(pgid.unwrap_or(0) as _))
 }
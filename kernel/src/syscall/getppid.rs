@@ -4,5 +4,7 @@ use super::SyscallReturn;
 use crate::prelude::*;
 
 pub fn sys_getppid(ctx: &Context) -> Result<SyscallReturn> {
-    Ok(SyscallReturn::Return(ctx.process.parent().pid() as _))
+    // The parent is invisible if it is outside the PID namespace.
+    let ppid = ctx.process.pid_ns().local_id(ctx.process.parent().pid());
+    Ok(SyscallReturn::Return(ppid.unwrap_or(0) as _))
 }
@@ -10,13 +10,18 @@ pub fn sys_getsid(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
     debug!("pid = {}", pid);
 
     let session = ctx.process.session().unwrap();
-    let sid = session.sid();
+    let sid = ctx.process.pid_ns().local_id(session.sid()).unwrap_or(0);
 
     if pid == 0 {
         return Ok(SyscallReturn::Return(sid as _));
     }
 
-    let Some(process) = process_table::get_process(pid) else {
+    let Some(process) = ctx
+        .process
+        .pid_ns()
+        .global_id(pid)
+        .and_then(process_table::get_process)
+    else {
         return_errno_with_message!(Errno::ESRCH, "the process does not exist")
     };
 
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{
+    setxattr::{check_xattr_permission, lookup_dentry_for_xattr, read_xattr_name, XattrTarget},
+    SyscallReturn,
+};
+use crate::{
+    fs::{
+        file_table::FileDesc,
+        path::Dentry,
+        utils::{XattrName, PATH_MAX},
+    },
+    prelude::*,
+};
+
+pub fn sys_getxattr(
+    path_ptr: Vaddr,
+    name_ptr: Vaddr,
+    value_ptr: Vaddr,
+    value_len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    let dentry = lookup_dentry_for_xattr(XattrTarget::Path(&path), ctx)?;
+
+    do_getxattr(&dentry, name_ptr, value_ptr, value_len, ctx)
+}
+
+pub fn sys_lgetxattr(
+    path_ptr: Vaddr,
+    name_ptr: Vaddr,
+    value_ptr: Vaddr,
+    value_len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    let dentry = lookup_dentry_for_xattr(XattrTarget::PathNoFollow(&path), ctx)?;
+
+    do_getxattr(&dentry, name_ptr, value_ptr, value_len, ctx)
+}
+
+pub fn sys_fgetxattr(
+    fd: FileDesc,
+    name_ptr: Vaddr,
+    value_ptr: Vaddr,
+    value_len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let dentry = lookup_dentry_for_xattr(XattrTarget::FileDesc(fd), ctx)?;
+
+    do_getxattr(&dentry, name_ptr, value_ptr, value_len, ctx)
+}
+
+fn do_getxattr(
+    dentry: &Dentry,
+    name_ptr: Vaddr,
+    value_ptr: Vaddr,
+    value_len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let name = read_xattr_name(name_ptr, ctx)?;
+    let name = XattrName::try_from_full_name(&name)?;
+    debug!("name = {:?}, value_len = {}", name.full_name(), value_len);
+
+    check_xattr_permission(name, dentry, false, ctx)?;
+    let value = dentry.get_xattr(name)?;
+
+    // A zero-sized buffer queries the length of the value.
+    if value_len == 0 {
+        return Ok(SyscallReturn::Return(value.len() as _));
+    }
+    if value.len() > value_len {
+        return_errno_with_message!(Errno::ERANGE, "the buffer is too small for the xattr value");
+    }
+    ctx.user_space()
+        .write_bytes(value_ptr, &mut VmReader::from(value.as_slice()))?;
+
+    Ok(SyscallReturn::Return(value.len() as _))
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{FdFlags, FileDesc},
+        fs_resolver::FsPath,
+        inotify::{InotifyFile, InotifyMask},
+        utils::{CreationFlags, InodeType, Permission, StatusFlags, PATH_MAX},
+    },
+    prelude::*,
+};
+
+pub fn sys_inotify_init(ctx: &Context) -> Result<SyscallReturn> {
+    sys_inotify_init1(0, ctx)
+}
+
+pub fn sys_inotify_init1(flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = InotifyFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid flags"))?;
+    debug!("flags = {:?}", flags);
+
+    let fd_flags = if flags.contains(InotifyFlags::IN_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let inotify_file = InotifyFile::new(flags.contains(InotifyFlags::IN_NONBLOCK));
+
+    let fd = ctx
+        .posix_thread
+        .file_table()
+        .lock()
+        .insert(inotify_file, fd_flags);
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_inotify_add_watch(
+    fd: FileDesc,
+    path_ptr: Vaddr,
+    mask: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let mask = InotifyMask::from_bits_truncate(mask);
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    debug!("fd = {}, path = {:?}, mask = {:?}", fd, path, mask);
+
+    if !mask.intersects(InotifyMask::IN_ALL_EVENTS) {
+        return_errno_with_message!(Errno::EINVAL, "no events are specified");
+    }
+    if mask.contains(InotifyMask::IN_MASK_ADD | InotifyMask::IN_MASK_CREATE) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "IN_MASK_ADD and IN_MASK_CREATE cannot be specified together"
+        );
+    }
+
+    let file = {
+        let file_table = ctx.posix_thread.file_table().lock();
+        file_table.get_file(fd)?.clone()
+    };
+    let inotify_file = file
+        .downcast_ref::<InotifyFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "not an inotify file"))?;
+
+    let dentry = {
+        let path = path.to_string_lossy();
+        let fs_path = FsPath::try_from(path.as_ref())?;
+        let fs = ctx.posix_thread.fs().resolver().read();
+        if mask.contains(InotifyMask::IN_DONT_FOLLOW) {
+            fs.lookup_no_follow(&fs_path)?
+        } else {
+            fs.lookup(&fs_path)?
+        }
+    };
+    if mask.contains(InotifyMask::IN_ONLYDIR) && dentry.type_() != InodeType::Dir {
+        return_errno_with_message!(Errno::ENOTDIR, "the path is not a directory");
+    }
+    dentry.inode().check_permission(Permission::MAY_READ)?;
+
+    let wd = inotify_file.add_watch(dentry.inode().clone(), mask)?;
+    Ok(SyscallReturn::Return(wd as _))
+}
+
+pub fn sys_inotify_rm_watch(fd: FileDesc, wd: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("fd = {}, wd = {}", fd, wd);
+
+    let file = {
+        let file_table = ctx.posix_thread.file_table().lock();
+        file_table.get_file(fd)?.clone()
+    };
+    let inotify_file = file
+        .downcast_ref::<InotifyFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "not an inotify file"))?;
+    inotify_file.remove_watch(wd)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+bitflags! {
+    struct InotifyFlags: u32 {
+        const IN_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+        const IN_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+    }
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::{FdFlags, FileDesc},
+        io_uring::{EnterFlags, IoUringFile, IoUringParams, RegisterOp},
+    },
+    prelude::*,
+};
+
+pub fn sys_io_uring_setup(
+    entries: u32,
+    params_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let user_space = ctx.user_space();
+    let mut params = user_space.read_val::<IoUringParams>(params_addr)?;
+    debug!("entries = {}, params = {:?}", entries, params);
+
+    let io_uring = IoUringFile::new(entries, &mut params)?;
+    user_space.write_val(params_addr, &params)?;
+
+    let fd = {
+        let mut file_table = ctx.posix_thread.file_table().lock();
+        file_table.insert(io_uring, FdFlags::CLOEXEC)
+    };
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_io_uring_enter(
+    fd: FileDesc,
+    to_submit: u32,
+    min_complete: u32,
+    flags: u32,
+    sig: Vaddr,
+    sigsz: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = EnterFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the enter flags are invalid"))?;
+    debug!(
+        "fd = {}, to_submit = {}, min_complete = {}, flags = {:?}, sig = 0x{:x}, sigsz = {}",
+        fd, to_submit, min_complete, flags, sig, sigsz
+    );
+
+    if !EnterFlags::GETEVENTS.contains(flags) {
+        return_errno_with_message!(Errno::EINVAL, "the enter flags are not supported");
+    }
+    if sig != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the signal mask is not supported");
+    }
+
+    let file = {
+        let file_table = ctx.posix_thread.file_table().lock();
+        file_table.get_file(fd)?.clone()
+    };
+    let io_uring = file
+        .downcast_ref::<IoUringFile>()
+        .ok_or_else(|| Error::with_message(Errno::EOPNOTSUPP, "not io_uring file"))?;
+
+    let num_submitted = io_uring.submit(to_submit, ctx)?;
+
+    if flags.contains(EnterFlags::GETEVENTS) {
+        // The submitted SQEs are reported even if the waiting fails.
+        if let Err(err) = io_uring.wait_cqes(min_complete)
+            && num_submitted == 0
+        {
+            return Err(err);
+        }
+    }
+
+    Ok(SyscallReturn::Return(num_submitted as _))
+}
+
+pub fn sys_io_uring_register(
+    fd: FileDesc,
+    opcode: u32,
+    arg: Vaddr,
+    nr_args: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let op = RegisterOp::try_from(opcode)
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the register opcode is invalid"))?;
+    debug!(
+        "fd = {}, op = {:?}, arg = 0x{:x}, nr_args = {}",
+        fd, op, arg, nr_args
+    );
+
+    let file = {
+        let file_table = ctx.posix_thread.file_table().lock();
+        file_table.get_file(fd)?.clone()
+    };
+    let io_uring = file
+        .downcast_ref::<IoUringFile>()
+        .ok_or_else(|| Error::with_message(Errno::EOPNOTSUPP, "not io_uring file"))?;
+
+    io_uring.register(op, arg, nr_args, ctx)?;
+    Ok(SyscallReturn::Return(0))
+}
@@ -6,6 +6,7 @@ use crate::{
         file_table::{FdFlags, FileDesc},
         utils::{IoctlCmd, StatusFlags},
     },
+    net::iface::iface_ioctl,
     prelude::*,
 };
 
@@ -56,6 +57,23 @@ pub fn sys_ioctl(fd: FileDesc, cmd: u32, arg: Vaddr, ctx: &Context) -> Result<Sy
             entry.set_flags(entry.flags() & (!FdFlags::CLOEXEC));
             0
         }
+        // The iface commands can be issued on sockets of any type.
+        IoctlCmd::SIOCGIFNAME
+        | IoctlCmd::SIOCGIFCONF
+        | IoctlCmd::SIOCGIFFLAGS
+        | IoctlCmd::SIOCSIFFLAGS
+        | IoctlCmd::SIOCGIFADDR
+        | IoctlCmd::SIOCSIFADDR
+        | IoctlCmd::SIOCGIFBRDADDR
+        | IoctlCmd::SIOCGIFNETMASK
+        | IoctlCmd::SIOCSIFNETMASK
+        | IoctlCmd::SIOCGIFMTU
+        | IoctlCmd::SIOCGIFHWADDR
+        | IoctlCmd::SIOCGIFINDEX
+            if file.clone().as_socket().is_some() =>
+        {
+            iface_ioctl(ioctl_cmd, arg, ctx)?
+        }
         // FIXME: ioctl operations involving blocking I/O should be able to restart if interrupted
         _ => file.ioctl(ioctl_cmd, arg)?,
     };
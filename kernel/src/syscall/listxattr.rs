@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{
+    setxattr::{has_sys_admin, lookup_dentry_for_xattr, XattrTarget},
+    SyscallReturn,
+};
+use crate::{
+    fs::{
+        file_table::FileDesc,
+        path::Dentry,
+        utils::{XattrName, XattrNamespace, PATH_MAX, XATTR_LIST_MAX_LEN},
+    },
+    prelude::*,
+};
+
+pub fn sys_listxattr(
+    path_ptr: Vaddr,
+    list_ptr: Vaddr,
+    list_len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    let dentry = lookup_dentry_for_xattr(XattrTarget::Path(&path), ctx)?;
+
+    do_listxattr(&dentry, list_ptr, list_len, ctx)
+}
+
+pub fn sys_llistxattr(
+    path_ptr: Vaddr,
+    list_ptr: Vaddr,
+    list_len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    let dentry = lookup_dentry_for_xattr(XattrTarget::PathNoFollow(&path), ctx)?;
+
+    do_listxattr(&dentry, list_ptr, list_len, ctx)
+}
+
+pub fn sys_flistxattr(
+    fd: FileDesc,
+    list_ptr: Vaddr,
+    list_len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let dentry = lookup_dentry_for_xattr(XattrTarget::FileDesc(fd), ctx)?;
+
+    do_listxattr(&dentry, list_ptr, list_len, ctx)
+}
+
+fn do_listxattr(
+    dentry: &Dentry,
+    list_ptr: Vaddr,
+    list_len: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("list_len = {}", list_len);
+
+    let names = match dentry.list_xattr() {
+        Ok(names) => names,
+        // A file system without xattr support has no xattrs to list.
+        Err(err) if err.error() == Errno::EOPNOTSUPP => Vec::new(),
+        Err(err) => return Err(err),
+    };
+
+    let has_sys_admin = has_sys_admin(ctx);
+    let mut list = Vec::new();
+    for full_name in names.iter() {
+        let Ok(name) = XattrName::try_from_full_name(full_name) else {
+            continue;
+        };
+        if name.namespace() == XattrNamespace::Trusted && !has_sys_admin {
+            continue;
+        }
+        list.extend_from_slice(full_name.as_bytes());
+        list.push(0);
+    }
+
+    // A zero-sized buffer queries the length of the list.
+    if list_len == 0 {
+        return Ok(SyscallReturn::Return(list.len() as _));
+    }
+    if list.len() > list_len.min(XATTR_LIST_MAX_LEN) {
+        if list.len() > XATTR_LIST_MAX_LEN {
+            return_errno_with_message!(Errno::E2BIG, "the xattr list is too long");
+        }
+        return_errno_with_message!(Errno::ERANGE, "the buffer is too small for the xattr list");
+    }
+    ctx.user_space()
+        .write_bytes(list_ptr, &mut VmReader::from(list.as_slice()))?;
+
+    Ok(SyscallReturn::Return(list.len() as _))
+}
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::FdFlags,
+        ramfs::new_memfd,
+        utils::{FileSeals, InodeMode, PATH_MAX},
+    },
+    prelude::*,
+};
+
+pub fn sys_memfd_create(name_addr: Vaddr, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = MemfdFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    // The length of the name is checked when the memfd is created.
+    let name = ctx.user_space().read_cstring(name_addr, PATH_MAX)?;
+    let name = name.to_string_lossy();
+    debug!("name = {}, flags = {:?}", name, flags);
+
+    if flags.contains(MemfdFlags::MFD_HUGETLB) {
+        return_errno_with_message!(Errno::EINVAL, "huge pages are not supported");
+    }
+    if flags.contains(MemfdFlags::MFD_EXEC | MemfdFlags::MFD_NOEXEC_SEAL) {
+        return_errno_with_message!(Errno::EINVAL, "MFD_EXEC and MFD_NOEXEC_SEAL are exclusive");
+    }
+
+    let (seals, mode) = if flags.contains(MemfdFlags::MFD_NOEXEC_SEAL) {
+        // `MFD_NOEXEC_SEAL` implies `MFD_ALLOW_SEALING`.
+        (FileSeals::F_SEAL_EXEC, InodeMode::from_bits_truncate(0o666))
+    } else if flags.contains(MemfdFlags::MFD_ALLOW_SEALING) {
+        (FileSeals::empty(), InodeMode::from_bits_truncate(0o777))
+    } else {
+        (FileSeals::F_SEAL_SEAL, InodeMode::from_bits_truncate(0o777))
+    };
+    let memfd = new_memfd(&name, seals, mode)?;
+
+    let fd_flags = if flags.contains(MemfdFlags::MFD_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let fd = ctx
+        .posix_thread
+        .file_table()
+        .lock()
+        .insert(Arc::new(memfd), fd_flags);
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+bitflags! {
+    struct MemfdFlags: u32 {
+        const MFD_CLOEXEC = 0x0001;
+        const MFD_ALLOW_SEALING = 0x0002;
+        const MFD_HUGETLB = 0x0004;
+        const MFD_NOEXEC_SEAL = 0x0008;
+        const MFD_EXEC = 0x0010;
+    }
+}
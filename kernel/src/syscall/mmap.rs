@@ -120,13 +120,12 @@ fn do_sys_mmap(
                 options = options.vmo(shared_vmo);
             }
         } else {
-            let vmo = {
+            let file = {
                 let file_table = ctx.posix_thread.file_table().lock();
-                let file = file_table.get_file(fd)?;
-                let inode_handle = file
-                    .downcast_ref::<InodeHandle>()
-                    .ok_or(Error::with_message(Errno::EINVAL, "no inode"))?;
+                file_table.get_file(fd)?.clone()
+            };
 
+            if let Some(inode_handle) = file.downcast_ref::<InodeHandle>() {
                 let access_mode = inode_handle.access_mode();
                 if vm_perms.contains(VmPerms::READ) && !access_mode.is_readable() {
                     return_errno!(Errno::EACCES);
@@ -139,19 +138,22 @@ fn do_sys_mmap(
                 }
 
                 let inode = inode_handle.dentry().inode();
-                inode
+                let vmo = inode
                     .page_cache()
                     .ok_or(Error::with_message(
                         Errno::EBADF,
                         "File does not have page cache",
                     ))?
-                    .to_dyn()
-            };
-
-            options = options
-                .vmo(vmo)
-                .vmo_offset(offset)
-                .handle_page_faults_around();
+                    .to_dyn();
+
+                options = options
+                    .vmo(vmo)
+                    .vmo_offset(offset)
+                    .handle_page_faults_around();
+            } else {
+                let (vmo, vmo_offset) = file.mmap_vmo(offset)?;
+                options = options.vmo(vmo).vmo_offset(vmo_offset);
+            }
         }
 
         options
@@ -3,9 +3,18 @@
 //! Read the Cpu ctx content then dispatch syscall to corresponding handler
 //! The each sub module contains functions that handle real syscall logic.
 pub use clock_gettime::ClockId;
+pub use eventfd::EventFile;
 use ostd::cpu::UserContext;
 
-use crate::{context::Context, cpu::LinuxAbi, prelude::*};
+use crate::{
+    context::Context,
+    cpu::LinuxAbi,
+    prelude::*,
+    process::{
+        posix_thread::{ptrace_syscall_entry, ptrace_syscall_exit},
+        seccomp::seccomp_check_syscall,
+    },
+};
 
 mod accept;
 mod access;
@@ -58,17 +67,28 @@ mod getsockopt;
 mod gettid;
 mod gettimeofday;
 mod getuid;
+mod getxattr;
+mod inotify;
+mod io_uring;
 mod ioctl;
 mod kill;
 mod link;
 mod listen;
+mod listxattr;
 mod lseek;
 mod madvise;
+mod memfd_create;
 mod mkdir;
 mod mknod;
 mod mmap;
 mod mount;
 mod mprotect;
+mod mqueue;
+mod mremap;
+mod msgctl;
+mod msgget;
+mod msgrcv;
+mod msgsnd;
 mod msync;
 mod munmap;
 mod nanosleep;
@@ -81,12 +101,14 @@ mod pread64;
 mod preadv;
 mod prlimit64;
 mod pselect6;
+mod ptrace;
 mod pwrite64;
 mod pwritev;
 mod read;
 mod readlink;
 mod recvfrom;
 mod recvmsg;
+mod removexattr;
 mod rename;
 mod rmdir;
 mod rt_sigaction;
@@ -96,6 +118,7 @@ mod rt_sigreturn;
 mod rt_sigsuspend;
 mod sched_affinity;
 mod sched_yield;
+mod seccomp;
 mod select;
 mod semctl;
 mod semget;
@@ -111,6 +134,7 @@ mod setfsuid;
 mod setgid;
 mod setgroups;
 mod setitimer;
+mod setns;
 mod setpgid;
 mod setregid;
 mod setresgid;
@@ -119,8 +143,14 @@ mod setreuid;
 mod setsid;
 mod setsockopt;
 mod setuid;
+mod setxattr;
+mod shmat;
+mod shmctl;
+mod shmdt;
+mod shmget;
 mod shutdown;
 mod sigaltstack;
+mod signalfd;
 mod socket;
 mod socketpair;
 mod stat;
@@ -132,11 +162,13 @@ mod tgkill;
 mod time;
 mod timer_create;
 mod timer_settime;
+mod timerfd;
 mod truncate;
 mod umask;
 mod umount;
 mod uname;
 mod unlink;
+mod unshare;
 mod utimens;
 mod wait4;
 mod waitid;
@@ -319,6 +351,20 @@ impl SyscallArgument {
 }
 
 pub fn handle_syscall(ctx: &Context, user_ctx: &mut UserContext) {
+    // The tracer may change the syscall number, or ask to skip the syscall.
+    let syscall_number = ptrace_syscall_entry(user_ctx, ctx);
+    if syscall_number.is_some() {
+        dispatch_syscall(ctx, user_ctx);
+    }
+
+    ptrace_syscall_exit(syscall_number.unwrap_or(usize::MAX), user_ctx, ctx);
+}
+
+fn dispatch_syscall(ctx: &Context, user_ctx: &mut UserContext) {
+    if !seccomp_check_syscall(user_ctx, ctx) {
+        return;
+    }
+
     let syscall_frame = SyscallArgument::new_from_context(user_ctx);
     let syscall_return = arch::syscall_dispatch(
         syscall_frame.syscall_number,
@@ -361,7 +407,3 @@ macro_rules! log_syscall_entry {
         }
     };
 }
-
-pub(super) fn init() {
-    uname::init();
-}
@@ -6,8 +6,11 @@ use crate::{
         exfat::{ExfatFS, ExfatMountOptions},
         ext2::Ext2,
         fs_resolver::{FsPath, AT_FDCWD},
+        mqueue::mqueue_fs,
+        overlayfs::{OverlayFS, OverlayMountOptions},
         path::Dentry,
-        utils::{FileSystem, InodeType},
+        procfs::ProcFS,
+        utils::{FileSystem, Inode, InodeType},
     },
     prelude::*,
     syscall::constants::MAX_FILENAME_LEN,
@@ -15,8 +18,8 @@ use crate::{
 
 /// The `data` argument is interpreted by the different filesystems.
 /// Typically it is a string of comma-separated options understood by
-/// this filesystem. The current implementation only considers the case
-/// where it is `NULL`. Because it should be interpreted by the specific filesystems.
+/// this filesystem. The current implementation only passes it to the
+/// overlay filesystem, and ignores it for the others.
 pub fn sys_mount(
     devname_addr: Vaddr,
     dirname_addr: Vaddr,
@@ -63,7 +66,7 @@ pub fn sys_mount(
     } else if mount_flags.contains(MountFlags::MS_MOVE) {
         do_move_mount_old(devname, dst_dentry, ctx)?;
     } else {
-        do_new_mount(devname, fstype_addr, dst_dentry, ctx)?;
+        do_new_mount(devname, fstype_addr, data, dst_dentry, ctx)?;
     }
 
     Ok(SyscallReturn::Return(0))
@@ -135,6 +138,7 @@ fn do_move_mount_old(src_name: CString, dst_dentry: Dentry, ctx: &Context) -> Re
 fn do_new_mount(
     devname: CString,
     fs_type: Vaddr,
+    data: Vaddr,
     target_dentry: Dentry,
     ctx: &Context,
 ) -> Result<()> {
@@ -146,21 +150,37 @@ fn do_new_mount(
     if fs_type.is_empty() {
         return_errno_with_message!(Errno::EINVAL, "fs_type is empty");
     }
-    let fs = get_fs(fs_type, devname)?;
+    let fs = get_fs(fs_type, devname, data, ctx)?;
     target_dentry.mount(fs)?;
     Ok(())
 }
 
-/// Get the filesystem by fs_type and devname.
-fn get_fs(fs_type: CString, devname: CString) -> Result<Arc<dyn FileSystem>> {
+/// Get the filesystem by fs_type, devname and the filesystem-specific data.
+fn get_fs(
+    fs_type: CString,
+    devname: CString,
+    data: Vaddr,
+    ctx: &Context,
+) -> Result<Arc<dyn FileSystem>> {
+    let fs_type = fs_type.to_str().unwrap();
+    // The file systems that are not backed by devices.
+    match fs_type {
+        "mqueue" => return Ok(mqueue_fs()),
+        // A new procfs shows the processes in the PID namespace of the current process.
+        "proc" => return Ok(ProcFS::new(ctx.process.pid_ns().clone())),
+        "overlay" => return new_overlay_fs(data, ctx),
+        _ => {}
+    }
+
     let devname = devname.to_str().unwrap();
     let device = match aster_block::get_device(devname) {
         Some(device) => device,
         None => return_errno_with_message!(Errno::ENOENT, "Device does not exist"),
     };
-    let fs_type = fs_type.to_str().unwrap();
     match fs_type {
-        "ext2" => {
+        // The Ext2 driver also supports the journal of Ext3 and the Ext4 features used
+        // by default.
+        "ext2" | "ext3" | "ext4" => {
             let ext2_fs = Ext2::open(device)?;
             Ok(ext2_fs)
         }
@@ -172,6 +192,36 @@ fn get_fs(fs_type: CString, devname: CString) -> Result<Arc<dyn FileSystem>> {
     }
 }
 
+/// Creates an overlay filesystem with the `lowerdir=`, `upperdir=` and `workdir=` options.
+fn new_overlay_fs(data: Vaddr, ctx: &Context) -> Result<Arc<dyn FileSystem>> {
+    if data == 0 {
+        return_errno_with_message!(Errno::EINVAL, "overlay options are missing");
+    }
+    let data = ctx.user_space().read_cstring(data, PAGE_SIZE)?;
+    let data = data
+        .to_str()
+        .map_err(|_| Error::with_message(Errno::EINVAL, "overlay options are not UTF-8"))?;
+    let options = OverlayMountOptions::parse(data)?;
+
+    let lookup_dir = |path: &String| -> Result<Arc<dyn Inode>> {
+        let fs_path = FsPath::new(AT_FDCWD, path)?;
+        let dentry = ctx.posix_thread.fs().resolver().read().lookup(&fs_path)?;
+        Ok(dentry.inode().clone())
+    };
+    let lowers = options
+        .lowerdirs
+        .iter()
+        .map(lookup_dir)
+        .collect::<Result<Vec<_>>>()?;
+    let upper_and_work = match (options.upperdir.as_ref(), options.workdir.as_ref()) {
+        (Some(upperdir), Some(workdir)) => Some((lookup_dir(upperdir)?, lookup_dir(workdir)?)),
+        _ => None,
+    };
+
+    let overlay_fs = OverlayFS::new(lowers, upper_and_work)?;
+    Ok(overlay_fs)
+}
+
 bitflags! {
     struct MountFlags: u32 {
         const MS_RDONLY        =   1 << 0;       // Mount read-only.
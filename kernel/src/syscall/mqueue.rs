@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The POSIX message queue syscalls.
+//!
+//! A message queue is opened as a file in the internal mount of the mqueue
+//! file system. The queue is then accessed through its file descriptor.
+
+use core::time::Duration;
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_handle::FileLike,
+        file_table::{FdFlags, FileDesc},
+        inode_handle::InodeHandle,
+        mqueue::{mqueue_fs, mqueue_root, MqueueInode},
+        utils::{AccessMode, CreationFlags, InodeMode, StatusFlags, NAME_MAX},
+    },
+    ipc::msg::posix::{
+        mq_attr, MessageQueue, Notification, DFLT_MSGMAX, DFLT_MSGSIZEMAX, HARD_MSGMAX,
+        HARD_MSGSIZEMAX, MSGMAX, MSGSIZEMAX,
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        signal::{
+            c_types::{sigevent_t, SigNotify},
+            sig_num::SigNum,
+        },
+    },
+    syscall::constants::MAX_FILENAME_LEN,
+    time::{
+        clocks::RealTimeClock,
+        timer::Timeout,
+        timespec_t,
+        wait::{ManagedTimeout, TimeoutExt},
+    },
+};
+
+pub fn sys_mq_open(
+    name_addr: Vaddr,
+    oflag: u32,
+    mode: u16,
+    attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let name = read_queue_name(name_addr, ctx)?;
+    let creation_flags = CreationFlags::from_bits_truncate(oflag);
+    let access_mode = AccessMode::from_u32(oflag)?;
+    let status_flags = StatusFlags::from_bits_truncate(oflag) & StatusFlags::O_NONBLOCK;
+    debug!(
+        "name = {:?}, oflag = {:#o}, mode = {:#o}, attr_addr = {:#x}",
+        name, oflag, mode, attr_addr
+    );
+
+    let root = mqueue_root();
+    let inode_handle = match root.lookup(&name) {
+        Ok(_) if creation_flags.contains(CreationFlags::O_CREAT | CreationFlags::O_EXCL) => {
+            return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+        }
+        Ok(dentry) => InodeHandle::new(dentry, access_mode, status_flags)?,
+        Err(err)
+            if err.error() == Errno::ENOENT && creation_flags.contains(CreationFlags::O_CREAT) =>
+        {
+            let queue = new_queue(attr_addr, ctx)?;
+            let credentials = ctx.posix_thread.credentials();
+            let mode = mode & !ctx.posix_thread.fs().umask().read().get();
+            mqueue_fs().create_queue(
+                &name,
+                InodeMode::from_bits_truncate(mode),
+                credentials.euid(),
+                credentials.egid(),
+                queue,
+            )?;
+            // The creator can access the queue regardless of the permission mode.
+            InodeHandle::new_unchecked_access(root.lookup(&name)?, access_mode, status_flags)?
+        }
+        Err(err) => return Err(err),
+    };
+
+    let fd = ctx
+        .posix_thread
+        .file_table()
+        .lock()
+        .insert(Arc::new(inode_handle), FdFlags::CLOEXEC);
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_mq_unlink(name_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let name = read_queue_name(name_addr, ctx)?;
+    debug!("name = {:?}", name);
+
+    mqueue_root().unlink(&name)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_timedsend(
+    mqdes: FileDesc,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio: u32,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = {:#x}, msg_len = {}, msg_prio = {}, abs_timeout_addr = {:#x}",
+        mqdes, msg_ptr, msg_len, msg_prio, abs_timeout_addr
+    );
+
+    let (file, queue) = get_queue(mqdes, ctx)?;
+    if !file.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for writing");
+    }
+    if msg_len > queue.msg_size() {
+        return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+    }
+    let is_nonblocking = file.status_flags().contains(StatusFlags::O_NONBLOCK);
+    let timeout = read_timeout(abs_timeout_addr, is_nonblocking, ctx)?;
+
+    let mut msg = vec![0u8; msg_len];
+    ctx.user_space()
+        .read_bytes(msg_ptr, &mut VmWriter::from(msg.as_mut_slice()))?;
+
+    let sender = (ctx.process.pid(), ctx.posix_thread.credentials().ruid());
+    queue
+        .send(msg, msg_prio, is_nonblocking, timeout, sender)
+        .map_err(map_timeout_error)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_timedreceive(
+    mqdes: FileDesc,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio_addr: Vaddr,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = {:#x}, msg_len = {}, msg_prio_addr = {:#x}, abs_timeout_addr = {:#x}",
+        mqdes, msg_ptr, msg_len, msg_prio_addr, abs_timeout_addr
+    );
+
+    let (file, queue) = get_queue(mqdes, ctx)?;
+    if !file.access_mode().is_readable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for reading");
+    }
+    let is_nonblocking = file.status_flags().contains(StatusFlags::O_NONBLOCK);
+    let timeout = read_timeout(abs_timeout_addr, is_nonblocking, ctx)?;
+
+    let (msg, prio) = queue
+        .receive(msg_len, is_nonblocking, timeout)
+        .map_err(map_timeout_error)?;
+
+    let user_space = ctx.user_space();
+    user_space.write_bytes(msg_ptr, &mut VmReader::from(msg.as_slice()))?;
+    if msg_prio_addr != 0 {
+        user_space.write_val(msg_prio_addr, &prio)?;
+    }
+    Ok(SyscallReturn::Return(msg.len() as _))
+}
+
+pub fn sys_mq_notify(mqdes: FileDesc, sevp_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("mqdes = {}, sevp_addr = {:#x}", mqdes, sevp_addr);
+
+    let (_, queue) = get_queue(mqdes, ctx)?;
+    if sevp_addr == 0 {
+        queue.unregister_notification(ctx.process.pid());
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let sig_event = ctx.user_space().read_val::<sigevent_t>(sevp_addr)?;
+    let notification = match SigNotify::try_from(sig_event.sigev_notify)? {
+        SigNotify::SIGEV_NONE => Notification::new_none(ctx.process),
+        SigNotify::SIGEV_SIGNAL => {
+            let signo = u8::try_from(sig_event.sigev_signo)
+                .map_err(|_| Error::with_message(Errno::EINVAL, "invalid signal number"))?;
+            Notification::new_signal(ctx.process, SigNum::try_from(signo)?, sig_event.sigev_value)
+        }
+        // TODO: Support `SIGEV_THREAD`, which is implemented by the C library
+        // with a netlink socket.
+        SigNotify::SIGEV_THREAD | SigNotify::SIGEV_THREAD_ID => {
+            return_errno_with_message!(Errno::EINVAL, "the notification method is not supported");
+        }
+    };
+    queue.register_notification(notification)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_mq_getsetattr(
+    mqdes: FileDesc,
+    new_attr_addr: Vaddr,
+    old_attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, new_attr_addr = {:#x}, old_attr_addr = {:#x}",
+        mqdes, new_attr_addr, old_attr_addr
+    );
+
+    let (file, queue) = get_queue(mqdes, ctx)?;
+
+    let new_flags = if new_attr_addr != 0 {
+        let new_attr = ctx.user_space().read_val::<mq_attr>(new_attr_addr)?;
+        let new_flags = u32::try_from(new_attr.mq_flags)
+            .ok()
+            .and_then(StatusFlags::from_bits)
+            .filter(|flags| (*flags - StatusFlags::O_NONBLOCK).is_empty())
+            .ok_or(Error::with_message(Errno::EINVAL, "invalid queue flags"))?;
+        Some(new_flags)
+    } else {
+        None
+    };
+
+    if old_attr_addr != 0 {
+        let old_flags = file.status_flags() & StatusFlags::O_NONBLOCK;
+        let old_attr = mq_attr {
+            mq_flags: old_flags.bits() as i64,
+            mq_maxmsg: queue.max_msgs() as i64,
+            mq_msgsize: queue.msg_size() as i64,
+            mq_curmsgs: queue.nr_msgs() as i64,
+            __reserved: [0; 4],
+        };
+        ctx.user_space().write_val(old_attr_addr, &old_attr)?;
+    }
+
+    if let Some(new_flags) = new_flags {
+        let status_flags = (file.status_flags() - StatusFlags::O_NONBLOCK) | new_flags;
+        file.set_status_flags(status_flags)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Reads the name of a queue, which must be a single path component.
+///
+/// The C library strips the leading slash before invoking the syscalls.
+fn read_queue_name(name_addr: Vaddr, ctx: &Context) -> Result<String> {
+    let name = ctx.user_space().read_cstring(name_addr, MAX_FILENAME_LEN)?;
+    let name = name.to_string_lossy().into_owned();
+
+    if name.is_empty() {
+        return_errno_with_message!(Errno::ENOENT, "the name is empty");
+    }
+    if name.len() > NAME_MAX {
+        return_errno_with_message!(Errno::ENAMETOOLONG, "the name is too long");
+    }
+    if name.contains('/') || name == "." || name == ".." {
+        return_errno_with_message!(Errno::EACCES, "the name is not a valid queue name");
+    }
+    Ok(name)
+}
+
+/// Creates a queue with the attributes at `attr_addr`, or with the default
+/// attributes if `attr_addr` is NULL.
+fn new_queue(attr_addr: Vaddr, ctx: &Context) -> Result<MessageQueue> {
+    if attr_addr == 0 {
+        return Ok(MessageQueue::new(DFLT_MSGMAX, DFLT_MSGSIZEMAX));
+    }
+
+    let attr = ctx.user_space().read_val::<mq_attr>(attr_addr)?;
+    if attr.mq_maxmsg <= 0 || attr.mq_msgsize <= 0 {
+        return_errno_with_message!(Errno::EINVAL, "the queue limits must be positive");
+    }
+    let (max_msgs, msg_size) = (attr.mq_maxmsg as usize, attr.mq_msgsize as usize);
+    if max_msgs > HARD_MSGMAX || msg_size > HARD_MSGSIZEMAX {
+        return_errno_with_message!(Errno::EINVAL, "the queue limits are too large");
+    }
+
+    let is_privileged = ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_RESOURCE);
+    if !is_privileged && (max_msgs > MSGMAX || msg_size > MSGSIZEMAX) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the queue limits are too large for unprivileged processes"
+        );
+    }
+
+    Ok(MessageQueue::new(max_msgs, msg_size))
+}
+
+/// Returns the file of `mqdes` and the queue that the file refers to.
+fn get_queue(mqdes: FileDesc, ctx: &Context) -> Result<(Arc<dyn FileLike>, Arc<MessageQueue>)> {
+    let file = {
+        let file_table = ctx.posix_thread.file_table().lock();
+        file_table.get_file(mqdes)?.clone()
+    };
+    let queue = file
+        .downcast_ref::<InodeHandle>()
+        .and_then(|inode_handle| {
+            inode_handle
+                .dentry()
+                .inode()
+                .downcast_ref::<MqueueInode>()
+                .map(|inode| inode.queue().clone())
+        })
+        .ok_or_else(|| Error::with_message(Errno::EBADF, "not a message queue descriptor"))?;
+    Ok((file, queue))
+}
+
+/// Reads the absolute timeout, which is measured against `CLOCK_REALTIME`.
+fn read_timeout(
+    abs_timeout_addr: Vaddr,
+    is_nonblocking: bool,
+    ctx: &Context,
+) -> Result<TimeoutExt<'static>> {
+    // The timeout is ignored if the operation never blocks.
+    if abs_timeout_addr == 0 || is_nonblocking {
+        return Ok(TimeoutExt::Never);
+    }
+
+    let timespec = ctx.user_space().read_val::<timespec_t>(abs_timeout_addr)?;
+    let abs_timeout = Duration::try_from(timespec)?;
+    Ok(
+        ManagedTimeout::new_with_manager(
+            Timeout::When(abs_timeout),
+            RealTimeClock::timer_manager(),
+        )
+        .into(),
+    )
+}
+
+fn map_timeout_error(err: Error) -> Error {
+    match err.error() {
+        Errno::ETIME => Error::with_message(Errno::ETIMEDOUT, "the timeout expired"),
+        _ => err,
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use align_ext::AlignExt;
+
+use super::SyscallReturn;
+use crate::prelude::*;
+
+pub fn sys_mremap(
+    old_addr: Vaddr,
+    old_size: usize,
+    new_size: usize,
+    flags: i32,
+    new_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MremapFlags::from_bits(flags as u32)
+        .ok_or(Error::with_message(Errno::EINVAL, "unknown mremap flags"))?;
+    debug!(
+        "old_addr = 0x{:x}, old_size = 0x{:x}, new_size = 0x{:x}, flags = {:?}, new_addr = 0x{:x}",
+        old_addr, old_size, new_size, flags, new_addr
+    );
+
+    let new_addr = do_sys_mremap(old_addr, old_size, new_size, flags, new_addr, ctx)?;
+    Ok(SyscallReturn::Return(new_addr as _))
+}
+
+fn do_sys_mremap(
+    old_addr: Vaddr,
+    old_size: usize,
+    new_size: usize,
+    flags: MremapFlags,
+    new_addr: Vaddr,
+    ctx: &Context,
+) -> Result<Vaddr> {
+    if old_addr % PAGE_SIZE != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the old address should be page aligned");
+    }
+    if !flags.contains(MremapFlags::MREMAP_MAYMOVE)
+        && flags.intersects(MremapFlags::MREMAP_FIXED | MremapFlags::MREMAP_DONTUNMAP)
+    {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "MREMAP_FIXED and MREMAP_DONTUNMAP require MREMAP_MAYMOVE"
+        );
+    }
+    if flags.contains(MremapFlags::MREMAP_DONTUNMAP) && old_size != new_size {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "MREMAP_DONTUNMAP requires the size to be unchanged"
+        );
+    }
+    if flags.contains(MremapFlags::MREMAP_FIXED) && new_addr % PAGE_SIZE != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the new address should be page aligned");
+    }
+    if new_size == 0 {
+        return_errno_with_message!(Errno::EINVAL, "the new size cannot be zero");
+    }
+    // TODO: Support duplicating a shared mapping when the old size is zero.
+    if old_size == 0 {
+        return_errno_with_message!(Errno::EINVAL, "the old size cannot be zero");
+    }
+    if old_size > isize::MAX as usize || new_size > isize::MAX as usize {
+        return_errno_with_message!(Errno::ENOMEM, "the size is too large");
+    }
+
+    let mut old_size = old_size.align_up(PAGE_SIZE);
+    let new_size = new_size.align_up(PAGE_SIZE);
+    old_addr
+        .checked_add(old_size.max(new_size))
+        .ok_or(Error::with_message(
+            Errno::EINVAL,
+            "integer overflow when (old_addr + size)",
+        ))?;
+
+    let root_vmar = ctx.process.root_vmar();
+    let keeps_old = flags.contains(MremapFlags::MREMAP_DONTUNMAP);
+
+    if flags.contains(MremapFlags::MREMAP_FIXED) {
+        // The mapping is shrunk in place before being moved.
+        if new_size < old_size {
+            root_vmar.resize_mapping(old_addr, old_size, new_size)?;
+            old_size = new_size;
+        }
+        return root_vmar.remap(old_addr, old_size, Some(new_addr), new_size, keeps_old);
+    }
+
+    if keeps_old {
+        return root_vmar.remap(old_addr, old_size, None, new_size, keeps_old);
+    }
+
+    // Tries to resize the mapping in place first. If the mapping cannot be
+    // expanded in place, it is moved to a free region if allowed.
+    match root_vmar.resize_mapping(old_addr, old_size, new_size) {
+        Ok(()) => Ok(old_addr),
+        Err(err) if err.error() == Errno::ENOMEM && flags.contains(MremapFlags::MREMAP_MAYMOVE) => {
+            root_vmar.remap(old_addr, old_size, None, new_size, false)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+bitflags! {
+    struct MremapFlags: u32 {
+        const MREMAP_MAYMOVE = 1 << 0;
+        const MREMAP_FIXED = 1 << 1;
+        const MREMAP_DONTUNMAP = 1 << 2;
+    }
+}
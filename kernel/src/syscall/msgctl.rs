@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::msg::system_v::{queue::msqid_ds, MsgControlCmd},
+    prelude::*,
+};
+
+pub fn sys_msgctl(msqid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    if msqid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the queue ID is invalid");
+    }
+
+    let cmd = MsgControlCmd::try_from(cmd)?;
+    debug!(
+        "[sys_msgctl] msqid = {}, cmd = {:?}, buf = 0x{:x}",
+        msqid, cmd, buf
+    );
+
+    let credentials = ctx.posix_thread.credentials();
+    let ns_proxy = ctx.posix_thread.ns_proxy();
+    let msg_queues = ns_proxy.ipc_ns().msg_queues();
+    match cmd {
+        MsgControlCmd::IPC_RMID => msg_queues.remove_queue(msqid, &credentials)?,
+        MsgControlCmd::IPC_STAT => {
+            let mut msqid_ds = msg_queues.get_queue(msqid)?.stat(&credentials)?;
+            // The processes may be invisible in the PID namespace of the caller.
+            let pid_ns = ctx.process.pid_ns();
+            msqid_ds.msg_lspid = pid_ns.local_id(msqid_ds.msg_lspid).unwrap_or(0);
+            msqid_ds.msg_lrpid = pid_ns.local_id(msqid_ds.msg_lrpid).unwrap_or(0);
+            ctx.user_space().write_val(buf, &msqid_ds)?;
+        }
+        MsgControlCmd::IPC_SET => {
+            let msqid_ds = ctx.user_space().read_val::<msqid_ds>(buf)?;
+            msg_queues.get_queue(msqid)?.set_attributes(
+                &credentials,
+                msqid_ds.msg_perm.uid.into(),
+                msqid_ds.msg_perm.gid.into(),
+                msqid_ds.msg_perm.mode as u16,
+                msqid_ds.msg_qbytes as usize,
+            )?;
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{ipc::IpcFlags, prelude::*};
+
+pub fn sys_msgget(key: i32, msgflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = IpcFlags::from_bits_truncate(msgflg as u32);
+    let mode = (msgflg as u32 & 0o777) as u16;
+    debug!(
+        "[sys_msgget] key = {}, flags = {:?}, mode = {:o}",
+        key, flags, mode
+    );
+
+    let credentials = ctx.posix_thread.credentials();
+    let id = ctx
+        .posix_thread
+        .ns_proxy()
+        .ipc_ns()
+        .msg_queues()
+        .get_or_create_queue(key, flags, mode, &credentials)?;
+
+    Ok(SyscallReturn::Return(id as _))
+}
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{ipc::msg::system_v::MsgFlags, prelude::*};
+
+pub fn sys_msgrcv(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: isize,
+    msgtyp: i64,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MsgFlags::from_bits_truncate(msgflg as u32);
+    debug!(
+        "[sys_msgrcv] msqid = {}, msgp = 0x{:x}, msgsz = {}, msgtyp = {}, flags = {:?}",
+        msqid, msgp, msgsz, msgtyp, flags
+    );
+
+    if msqid < 0 || msgsz < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the queue ID or the size is invalid");
+    }
+    if flags.contains(MsgFlags::MSG_COPY) {
+        return_errno_with_message!(Errno::ENOSYS, "MSG_COPY is not supported");
+    }
+
+    let queue = ctx
+        .posix_thread
+        .ns_proxy()
+        .ipc_ns()
+        .msg_queues()
+        .get_queue(msqid)?;
+    let msg = queue.receive(
+        msgtyp,
+        msgsz as usize,
+        flags,
+        &ctx.posix_thread.credentials(),
+        ctx.process.pid(),
+    )?;
+
+    let user_space = ctx.user_space();
+    user_space.write_val(msgp, &msg.mtype())?;
+    user_space.write_bytes(msgp + size_of::<i64>(), &mut VmReader::from(msg.data()))?;
+
+    Ok(SyscallReturn::Return(msg.data().len() as _))
+}
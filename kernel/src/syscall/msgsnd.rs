@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::msg::system_v::{
+        queue::{Message, MSGMAX},
+        MsgFlags,
+    },
+    prelude::*,
+};
+
+pub fn sys_msgsnd(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: usize,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = MsgFlags::from_bits_truncate(msgflg as u32);
+    debug!(
+        "[sys_msgsnd] msqid = {}, msgp = 0x{:x}, msgsz = {}, flags = {:?}",
+        msqid, msgp, msgsz, flags
+    );
+
+    if msqid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the queue ID is invalid");
+    }
+    if msgsz > MSGMAX {
+        return_errno_with_message!(Errno::EINVAL, "the message is too long");
+    }
+
+    // The message buffer starts with a `long` that holds the message type,
+    // followed by the message text.
+    let user_space = ctx.user_space();
+    let mtype = user_space.read_val::<i64>(msgp)?;
+    if mtype < 1 {
+        return_errno_with_message!(Errno::EINVAL, "the message type must be positive");
+    }
+    let mut data = vec![0u8; msgsz];
+    user_space.read_bytes(
+        msgp + size_of::<i64>(),
+        &mut VmWriter::from(data.as_mut_slice()),
+    )?;
+
+    let queue = ctx
+        .posix_thread
+        .ns_proxy()
+        .ipc_ns()
+        .msg_queues()
+        .get_queue(msqid)?;
+    queue.send(
+        Message::new(mtype, data),
+        flags,
+        &ctx.posix_thread.credentials(),
+        ctx.process.pid(),
+    )?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use super::SyscallReturn;
+use super::{
+    seccomp::{do_seccomp_set_filter, SeccompFilterFlags},
+    SyscallReturn,
+};
 use crate::{
     prelude::*,
     process::{posix_thread::MAX_THREAD_NAME_LEN, signal::sig_num::SigNum},
@@ -80,6 +83,26 @@ pub fn sys_prctl(
                 thread_name.set_name(&new_thread_name)?;
             }
         }
+        PrctlCmd::PR_GET_SECCOMP => {
+            let mode = if ctx.posix_thread.seccomp().is_filtered() {
+                SECCOMP_MODE_FILTER
+            } else {
+                SECCOMP_MODE_DISABLED
+            };
+            return Ok(SyscallReturn::Return(mode as _));
+        }
+        PrctlCmd::PR_SET_SECCOMP(mode, fprog_addr) => {
+            if mode != SECCOMP_MODE_FILTER {
+                return_errno_with_message!(Errno::EINVAL, "the seccomp mode is not supported");
+            }
+            return do_seccomp_set_filter(SeccompFilterFlags::empty(), fprog_addr, ctx);
+        }
+        PrctlCmd::PR_GET_NO_NEW_PRIVS => {
+            return Ok(SyscallReturn::Return(ctx.posix_thread.no_new_privs() as _));
+        }
+        PrctlCmd::PR_SET_NO_NEW_PRIVS => {
+            ctx.posix_thread.set_no_new_privs();
+        }
         _ => todo!(),
     }
     Ok(SyscallReturn::Return(0))
@@ -93,8 +116,15 @@ const PR_GET_KEEPCAPS: i32 = 7;
 const PR_SET_KEEPCAPS: i32 = 8;
 const PR_SET_NAME: i32 = 15;
 const PR_GET_NAME: i32 = 16;
+const PR_GET_SECCOMP: i32 = 21;
+const PR_SET_SECCOMP: i32 = 22;
 const PR_SET_TIMERSLACK: i32 = 29;
 const PR_GET_TIMERSLACK: i32 = 30;
+const PR_SET_NO_NEW_PRIVS: i32 = 38;
+const PR_GET_NO_NEW_PRIVS: i32 = 39;
+
+const SECCOMP_MODE_DISABLED: u64 = 0;
+const SECCOMP_MODE_FILTER: u64 = 2;
 
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Copy)]
@@ -111,6 +141,10 @@ pub enum PrctlCmd {
     PR_GET_TIMERSLACK,
     PR_SET_DUMPABLE(Dumpable),
     PR_GET_DUMPABLE,
+    PR_GET_SECCOMP,
+    PR_SET_SECCOMP(u64, Vaddr),
+    PR_SET_NO_NEW_PRIVS,
+    PR_GET_NO_NEW_PRIVS,
 }
 
 #[repr(u64)]
@@ -122,7 +156,7 @@ pub enum Dumpable {
 }
 
 impl PrctlCmd {
-    fn from_args(option: i32, arg2: u64, _arg3: u64, _arg4: u64, _arg5: u64) -> Result<PrctlCmd> {
+    fn from_args(option: i32, arg2: u64, arg3: u64, arg4: u64, arg5: u64) -> Result<PrctlCmd> {
         match option {
             PR_SET_PDEATHSIG => {
                 let signum = SigNum::try_from(arg2 as u8)?;
@@ -137,6 +171,20 @@ impl PrctlCmd {
             PR_SET_TIMERSLACK => todo!(),
             PR_GET_KEEPCAPS => Ok(PrctlCmd::PR_GET_KEEPCAPS),
             PR_SET_KEEPCAPS => Ok(PrctlCmd::PR_SET_KEEPCAPS(arg2 as _)),
+            PR_GET_SECCOMP => Ok(PrctlCmd::PR_GET_SECCOMP),
+            PR_SET_SECCOMP => Ok(PrctlCmd::PR_SET_SECCOMP(arg2, arg3 as _)),
+            PR_SET_NO_NEW_PRIVS => {
+                if arg2 != 1 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "invalid arguments");
+                }
+                Ok(PrctlCmd::PR_SET_NO_NEW_PRIVS)
+            }
+            PR_GET_NO_NEW_PRIVS => {
+                if arg2 != 0 || arg3 != 0 || arg4 != 0 || arg5 != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "invalid arguments");
+                }
+                Ok(PrctlCmd::PR_GET_NO_NEW_PRIVS)
+            }
             _ => {
                 debug!("prctl cmd number: {}", option);
                 return_errno_with_message!(Errno::EINVAL, "unsupported prctl command");
@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{rlimit::RLimit64, Pid, ResourceType},
+    process::{process_table, rlimit::RLimit64, Pid, ResourceType},
 };
 
 pub fn sys_getrlimit(resource: u32, rlim_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
@@ -42,7 +42,22 @@ pub fn sys_prlimit64(
         "pid = {}, resource = {:?}, new_rlim_addr = 0x{:x}, old_rlim_addr = 0x{:x}",
         pid, resource, new_rlim_addr, old_rlim_addr
     );
-    let mut resource_limits = ctx.process.resource_limits().lock();
+    let target_process = if pid == 0 {
+        None
+    } else {
+        let process = ctx
+            .process
+            .pid_ns()
+            .global_id(pid)
+            .and_then(process_table::get_process)
+            .ok_or_else(|| {
+                Error::with_message(Errno::ESRCH, "the target process does not exist")
+            })?;
+        Some(process)
+    };
+    let target_process = target_process.as_deref().unwrap_or(ctx.process);
+
+    let mut resource_limits = target_process.resource_limits().lock();
     if old_rlim_addr != 0 {
         let rlimit = resource_limits.get_rlimit(resource);
         ctx.user_space().write_val(old_rlim_addr, rlimit)?;
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{
+        posix_thread::{
+            ptrace_attach, ptrace_detach, ptrace_traceme, thread_table, AsPosixThread, PosixThread,
+            PtraceOptions, PtraceResumeMode,
+        },
+        signal::sig_num::SigNum,
+    },
+    thread::Tid,
+};
+
+pub fn sys_ptrace(
+    request: u64,
+    pid: Tid,
+    addr: Vaddr,
+    data: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let request = PtraceRequest::try_from(request)
+        .map_err(|_| Error::with_message(Errno::EIO, "the ptrace request is not supported"))?;
+    debug!(
+        "request = {:?}, pid = {}, addr = 0x{:x}, data = 0x{:x}",
+        request, pid, addr, data
+    );
+
+    if request == PtraceRequest::PTRACE_TRACEME {
+        ptrace_traceme(ctx)?;
+        return Ok(SyscallReturn::Return(0));
+    }
+
+    let tracee = ctx
+        .process
+        .pid_ns()
+        .global_id(pid)
+        .and_then(thread_table::get_thread)
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the thread does not exist"))?;
+    let tracee_thread = tracee.as_posix_thread().unwrap();
+    let ptrace = tracee_thread.ptrace();
+    let tracer = ctx.process;
+
+    match request {
+        PtraceRequest::PTRACE_ATTACH => {
+            ptrace_attach(&tracee, &current!(), false, PtraceOptions::empty(), ctx)?;
+        }
+        PtraceRequest::PTRACE_SEIZE => {
+            if addr != 0 {
+                return_errno_with_message!(Errno::EIO, "PTRACE_SEIZE flags are not supported");
+            }
+            let options = read_options(data)
+                .map_err(|_| Error::with_message(Errno::EIO, "the ptrace options are invalid"))?;
+            ptrace_attach(&tracee, &current!(), true, options, ctx)?;
+        }
+        PtraceRequest::PTRACE_PEEKTEXT | PtraceRequest::PTRACE_PEEKDATA => {
+            ptrace.check_stopped_by(tracer)?;
+
+            let mut word = [0u8; size_of::<usize>()];
+            tracee_thread
+                .process()
+                .root_vmar()
+                .read_remote(addr, &mut word)
+                .map_err(|_| Error::with_message(Errno::EIO, "the address cannot be read"))?;
+            ctx.user_space()
+                .write_val(data, &usize::from_ne_bytes(word))?;
+        }
+        PtraceRequest::PTRACE_POKETEXT | PtraceRequest::PTRACE_POKEDATA => {
+            ptrace.check_stopped_by(tracer)?;
+
+            tracee_thread
+                .process()
+                .root_vmar()
+                .write_remote(addr, &data.to_ne_bytes())
+                .map_err(|_| Error::with_message(Errno::EIO, "the address cannot be written"))?;
+        }
+        PtraceRequest::PTRACE_CONT => {
+            ptrace.resume(tracer, PtraceResumeMode::Continue, read_signal(data)?)?;
+        }
+        PtraceRequest::PTRACE_SYSCALL => {
+            ptrace.resume(tracer, PtraceResumeMode::Syscall, read_signal(data)?)?;
+        }
+        PtraceRequest::PTRACE_SINGLESTEP => {
+            ptrace.resume(tracer, PtraceResumeMode::SingleStep, read_signal(data)?)?;
+        }
+        PtraceRequest::PTRACE_GETREGS => get_regs(tracee_thread, data, ctx)?,
+        PtraceRequest::PTRACE_SETREGS => set_regs(tracee_thread, data, ctx)?,
+        PtraceRequest::PTRACE_DETACH => {
+            ptrace_detach(&tracee, tracer, read_signal(data)?)?;
+        }
+        PtraceRequest::PTRACE_SETOPTIONS => {
+            ptrace.set_options(tracer, read_options(data)?)?;
+        }
+        PtraceRequest::PTRACE_TRACEME => unreachable!(),
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn read_signal(data: usize) -> Result<Option<SigNum>> {
+    if data == 0 {
+        return Ok(None);
+    }
+
+    let sig_num = u8::try_from(data)
+        .ok()
+        .and_then(|sig_num| SigNum::try_from(sig_num).ok())
+        .ok_or_else(|| Error::with_message(Errno::EIO, "the signal is invalid"))?;
+    Ok(Some(sig_num))
+}
+
+fn read_options(data: usize) -> Result<PtraceOptions> {
+    let options = u32::try_from(data)
+        .ok()
+        .and_then(PtraceOptions::from_bits)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the ptrace options are invalid"))?;
+    if !options.supported() {
+        return_errno_with_message!(Errno::EINVAL, "the ptrace options are not supported");
+    }
+    Ok(options)
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(target_arch = "x86_64")] {
+        use ostd::mm::MAX_USERSPACE_VADDR;
+
+        use crate::arch::cpu::PtraceRegs;
+
+        fn get_regs(tracee_thread: &PosixThread, data: Vaddr, ctx: &Context) -> Result<()> {
+            let regs = tracee_thread.ptrace().with_stopped_context(
+                ctx.process,
+                |user_ctx, syscall_num| {
+                    PtraceRegs::from_user_context(user_ctx, syscall_num.unwrap_or(usize::MAX))
+                },
+            )?;
+            ctx.user_space().write_val(data, &regs)
+        }
+
+        fn set_regs(tracee_thread: &PosixThread, data: Vaddr, ctx: &Context) -> Result<()> {
+            let regs = ctx.user_space().read_val::<PtraceRegs>(data)?;
+            if regs.fs_base >= MAX_USERSPACE_VADDR || regs.gs_base >= MAX_USERSPACE_VADDR {
+                return_errno_with_message!(Errno::EIO, "the segment base is invalid");
+            }
+
+            tracee_thread.ptrace().with_stopped_context(
+                ctx.process,
+                |user_ctx, syscall_num| {
+                    regs.copy_to_user_context(user_ctx);
+                    // The syscall number can only be changed in a syscall-stop.
+                    if syscall_num.is_some() {
+                        *syscall_num = Some(regs.orig_rax);
+                    }
+                },
+            )
+        }
+    } else {
+        fn get_regs(_tracee_thread: &PosixThread, _data: Vaddr, _ctx: &Context) -> Result<()> {
+            return_errno_with_message!(Errno::EIO, "PTRACE_GETREGS is not supported");
+        }
+
+        fn set_regs(_tracee_thread: &PosixThread, _data: Vaddr, _ctx: &Context) -> Result<()> {
+            return_errno_with_message!(Errno::EIO, "PTRACE_SETREGS is not supported");
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+enum PtraceRequest {
+    PTRACE_TRACEME = 0,
+    PTRACE_PEEKTEXT = 1,
+    PTRACE_PEEKDATA = 2,
+    PTRACE_POKETEXT = 4,
+    PTRACE_POKEDATA = 5,
+    PTRACE_CONT = 7,
+    PTRACE_SINGLESTEP = 9,
+    PTRACE_GETREGS = 12,
+    PTRACE_SETREGS = 13,
+    PTRACE_ATTACH = 16,
+    PTRACE_DETACH = 17,
+    PTRACE_SYSCALL = 24,
+    PTRACE_SETOPTIONS = 0x4200,
+    PTRACE_SEIZE = 0x4206,
+}
@@ -2,7 +2,7 @@
 
 use super::SyscallReturn;
 use crate::{
-    fs::file_table::FileDesc,
+    fs::file_table::{FdFlags, FileDesc},
     net::socket::SendRecvFlags,
     prelude::*,
     util::net::{get_socket_from_fd, CUserMsgHdr},
@@ -14,7 +14,7 @@ pub fn sys_recvmsg(
     flags: i32,
     ctx: &Context,
 ) -> Result<SyscallReturn> {
-    let c_user_msghdr: CUserMsgHdr = ctx.user_space().read_val(user_msghdr_ptr)?;
+    let mut c_user_msghdr: CUserMsgHdr = ctx.user_space().read_val(user_msghdr_ptr)?;
     let flags = SendRecvFlags::from_bits_truncate(flags);
 
     debug!(
@@ -34,13 +34,26 @@ pub fn sys_recvmsg(
             })?
     };
 
-    if let Some(addr) = message_header.addr() {
-        c_user_msghdr.write_socket_addr_to_user(addr)?;
-    }
+    c_user_msghdr.write_socket_addr_to_user(message_header.addr())?;
+
+    let fd_flags = if flags.contains(SendRecvFlags::MSG_CMSG_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let is_ctrunc = c_user_msghdr
+        .write_control_messages_to_user(message_header.control_messages(), fd_flags)?;
 
-    if c_user_msghdr.msg_control != 0 {
-        warn!("receiving control message is not supported");
+    // Like Linux, `MSG_CMSG_CLOEXEC` is reported back if it is specified.
+    // TODO: Report other flags (e.g., `MSG_TRUNC`) of the received message.
+    let mut msg_flags = flags & SendRecvFlags::MSG_CMSG_CLOEXEC;
+    if is_ctrunc {
+        msg_flags |= SendRecvFlags::MSG_CTRUNC;
     }
+    c_user_msghdr.msg_flags = msg_flags.bits() as u32;
+
+    ctx.user_space()
+        .write_val(user_msghdr_ptr, &c_user_msghdr)?;
 
     Ok(SyscallReturn::Return(total_bytes as _))
 }
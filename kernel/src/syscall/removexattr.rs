@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{
+    setxattr::{check_xattr_permission, lookup_dentry_for_xattr, read_xattr_name, XattrTarget},
+    SyscallReturn,
+};
+use crate::{
+    fs::{
+        file_table::FileDesc,
+        path::Dentry,
+        utils::{XattrName, PATH_MAX},
+    },
+    prelude::*,
+};
+
+pub fn sys_removexattr(path_ptr: Vaddr, name_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    let dentry = lookup_dentry_for_xattr(XattrTarget::Path(&path), ctx)?;
+
+    do_removexattr(&dentry, name_ptr, ctx)
+}
+
+pub fn sys_lremovexattr(path_ptr: Vaddr, name_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    let dentry = lookup_dentry_for_xattr(XattrTarget::PathNoFollow(&path), ctx)?;
+
+    do_removexattr(&dentry, name_ptr, ctx)
+}
+
+pub fn sys_fremovexattr(fd: FileDesc, name_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let dentry = lookup_dentry_for_xattr(XattrTarget::FileDesc(fd), ctx)?;
+
+    do_removexattr(&dentry, name_ptr, ctx)
+}
+
+fn do_removexattr(dentry: &Dentry, name_ptr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let name = read_xattr_name(name_ptr, ctx)?;
+    let name = XattrName::try_from_full_name(&name)?;
+    debug!("name = {:?}", name.full_name());
+
+    check_xattr_permission(name, dentry, true, ctx)?;
+    dentry.remove_xattr(name)?;
+
+    Ok(SyscallReturn::Return(0))
+}
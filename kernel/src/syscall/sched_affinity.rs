@@ -15,7 +15,12 @@ pub fn sys_sched_getaffinity(
 ) -> Result<SyscallReturn> {
     let cpu_set = match tid {
         0 => ctx.thread.atomic_cpu_affinity().load(),
-        _ => match thread_table::get_thread(tid) {
+        _ => match ctx
+            .process
+            .pid_ns()
+            .global_id(tid)
+            .and_then(thread_table::get_thread)
+        {
             Some(thread) => thread.atomic_cpu_affinity().load(),
             None => return Err(Error::with_message(Errno::ESRCH, "thread does not exist")),
         },
@@ -40,7 +45,12 @@ pub fn sys_sched_setaffinity(
 
     match tid {
         0 => ctx.thread.atomic_cpu_affinity().store(&user_cpu_set),
-        _ => match thread_table::get_thread(tid) {
+        _ => match ctx
+            .process
+            .pid_ns()
+            .global_id(tid)
+            .and_then(thread_table::get_thread)
+        {
             Some(thread) => {
                 thread.atomic_cpu_affinity().store(&user_cpu_set);
             }
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::seccomp::{
+        seccomp_is_action_available, seccomp_set_filter, sock_filter, sock_fprog, BPF_MAXINSNS,
+    },
+};
+
+pub fn sys_seccomp(op: u32, flags: u32, args: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let op = SeccompOp::try_from(op)?;
+    debug!("op = {:?}, flags = 0x{:x}, args = 0x{:x}", op, flags, args);
+
+    match op {
+        SeccompOp::SECCOMP_SET_MODE_STRICT => {
+            return_errno_with_message!(Errno::EINVAL, "the strict mode is not supported")
+        }
+        SeccompOp::SECCOMP_SET_MODE_FILTER => {
+            let flags = SeccompFilterFlags::from_bits(flags)
+                .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid seccomp flags"))?;
+            do_seccomp_set_filter(flags, args, ctx)
+        }
+        SeccompOp::SECCOMP_GET_ACTION_AVAIL => {
+            if flags != 0 {
+                return_errno_with_message!(Errno::EINVAL, "the flags must be zero");
+            }
+            let action = ctx.user_space().read_val::<u32>(args)?;
+            if !seccomp_is_action_available(action) {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "the action is not available");
+            }
+            Ok(SyscallReturn::Return(0))
+        }
+    }
+}
+
+/// Installs the seccomp filter at `fprog_addr` for the current thread.
+pub fn do_seccomp_set_filter(
+    flags: SeccompFilterFlags,
+    fprog_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    if flags.intersects(
+        SeccompFilterFlags::SECCOMP_FILTER_FLAG_NEW_LISTENER
+            | SeccompFilterFlags::SECCOMP_FILTER_FLAG_WAIT_KILLABLE_RECV,
+    ) {
+        return_errno_with_message!(Errno::EINVAL, "user-space notification is not supported");
+    }
+
+    let user_space = ctx.user_space();
+    let fprog = user_space.read_val::<sock_fprog>(fprog_addr)?;
+    let len = fprog.len as usize;
+    if len == 0 || len > BPF_MAXINSNS {
+        return_errno_with_message!(Errno::EINVAL, "the filter length is invalid");
+    }
+    let filter = (0..len)
+        .map(|i| user_space.read_val::<sock_filter>(fprog.filter + i * size_of::<sock_filter>()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let syncs_threads = flags.contains(SeccompFilterFlags::SECCOMP_FILTER_FLAG_TSYNC);
+    match seccomp_set_filter(&filter, syncs_threads, ctx)? {
+        None => Ok(SyscallReturn::Return(0)),
+        Some(_) if flags.contains(SeccompFilterFlags::SECCOMP_FILTER_FLAG_TSYNC_ESRCH) => {
+            return_errno_with_message!(Errno::ESRCH, "some thread cannot be synchronized")
+        }
+        // The ID of the thread that cannot be synchronized is returned.
+        Some(tid) => Ok(SyscallReturn::Return(tid as _)),
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromInt)]
+enum SeccompOp {
+    SECCOMP_SET_MODE_STRICT = 0,
+    SECCOMP_SET_MODE_FILTER = 1,
+    SECCOMP_GET_ACTION_AVAIL = 2,
+}
+
+bitflags! {
+    pub struct SeccompFilterFlags: u32 {
+        const SECCOMP_FILTER_FLAG_TSYNC = 1 << 0;
+        // Logging is not supported, so the flag is ignored.
+        const SECCOMP_FILTER_FLAG_LOG = 1 << 1;
+        // Speculation mitigations are not supported, so the flag is ignored.
+        const SECCOMP_FILTER_FLAG_SPEC_ALLOW = 1 << 2;
+        const SECCOMP_FILTER_FLAG_NEW_LISTENER = 1 << 3;
+        const SECCOMP_FILTER_FLAG_TSYNC_ESRCH = 1 << 4;
+        const SECCOMP_FILTER_FLAG_WAIT_KILLABLE_RECV = 1 << 5;
+    }
+}
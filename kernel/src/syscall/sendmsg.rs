@@ -28,15 +28,9 @@ pub fn sys_sendmsg(
         let addr = c_user_msghdr.read_socket_addr_from_user()?;
         let io_vec_reader = c_user_msghdr.copy_reader_array_from_user(ctx)?;
 
-        let control_message = {
-            if c_user_msghdr.msg_control != 0 {
-                // TODO: support sending control message
-                warn!("control message is not supported now");
-            }
-            None
-        };
-
-        (io_vec_reader, MessageHeader::new(addr, control_message))
+        let control_messages = c_user_msghdr.read_control_messages_from_user()?;
+
+        (io_vec_reader, MessageHeader::new(addr, control_messages))
     };
 
     let total_bytes = socket
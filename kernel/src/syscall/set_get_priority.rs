@@ -102,7 +102,10 @@ impl PriorityTarget {
                 let pid = if who == 0 {
                     ctx.process.pid()
                 } else {
-                    who as Pid
+                    ctx.process
+                        .pid_ns()
+                        .global_id(who as Pid)
+                        .ok_or(Error::new(Errno::ESRCH))?
                 };
                 Self::Process(pid)
             }
@@ -110,7 +113,10 @@ impl PriorityTarget {
                 let pgid = if who == 0 {
                     ctx.process.pgid()
                 } else {
-                    who as Pgid
+                    ctx.process
+                        .pid_ns()
+                        .global_id(who as Pgid)
+                        .ok_or(Error::new(Errno::ESRCH))?
                 };
                 Self::ProcessGroup(pgid)
             }
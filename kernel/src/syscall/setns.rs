@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{file_table::FileDesc, inode_handle::InodeHandle, procfs::ns_file_target},
+    prelude::*,
+    process::{namespace, CloneFlags},
+};
+
+pub fn sys_setns(fd: FileDesc, nstype: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let nstype = CloneFlags::from_bits(nstype as u32)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid namespace type"))?;
+    debug!("fd = {}, nstype = {:?}", fd, nstype);
+
+    let (target, kind) = {
+        let file_table = ctx.posix_thread.file_table().lock();
+        let file = file_table.get_file(fd)?;
+        file.downcast_ref::<InodeHandle>()
+            .and_then(|inode_handle| ns_file_target(inode_handle.dentry().inode()))
+            .ok_or_else(|| {
+                Error::with_message(Errno::EINVAL, "the file does not refer to a namespace")
+            })?
+    };
+
+    namespace::setns(&target, kind, nstype, ctx)?;
+    Ok(SyscallReturn::Return(0))
+}
@@ -8,10 +8,21 @@ use crate::{
 
 pub fn sys_setpgid(pid: Pid, pgid: Pgid, ctx: &Context) -> Result<SyscallReturn> {
     let current = ctx.process;
+    // Translates the IDs in the PID namespace of the current process to the global IDs.
+    let to_global_id = |id| {
+        current
+            .pid_ns()
+            .global_id(id)
+            .ok_or(Error::with_message(Errno::ESRCH, "process does not exist"))
+    };
     // if pid is 0, pid should be the pid of current process
-    let pid = if pid == 0 { current.pid() } else { pid };
+    let pid = if pid == 0 {
+        current.pid()
+    } else {
+        to_global_id(pid)?
+    };
     // if pgid is 0, pgid should be pid
-    let pgid = if pgid == 0 { pid } else { pgid };
+    let pgid = if pgid == 0 { pid } else { to_global_id(pgid)? };
     debug!("pid = {}, pgid = {}", pid, pgid);
 
     if pid != current.pid() && !current.has_child(&pid) {
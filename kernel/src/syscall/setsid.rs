@@ -3,9 +3,10 @@
 use super::SyscallReturn;
 use crate::prelude::*;
 
-pub fn sys_setsid(_ctx: &Context) -> Result<SyscallReturn> {
+pub fn sys_setsid(ctx: &Context) -> Result<SyscallReturn> {
     let current = current!();
     let session = current.to_new_session()?;
 
-    Ok(SyscallReturn::Return(session.sid() as _))
+    let sid = ctx.process.pid_ns().local_id(session.sid()).unwrap_or(0);
+    Ok(SyscallReturn::Return(sid as _))
 }
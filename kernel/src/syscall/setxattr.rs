@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file_table::FileDesc,
+        fs_resolver::{FsPath, AT_FDCWD},
+        inode_handle::InodeHandle,
+        path::Dentry,
+        utils::{
+            InodeType, XattrName, XattrNamespace, XattrSetFlags, PATH_MAX, XATTR_NAME_MAX_LEN,
+            XATTR_VALUE_MAX_LEN,
+        },
+    },
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+};
+
+pub fn sys_setxattr(
+    path_ptr: Vaddr,
+    name_ptr: Vaddr,
+    value_ptr: Vaddr,
+    value_len: usize,
+    flags: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    let dentry = lookup_dentry_for_xattr(XattrTarget::Path(&path), ctx)?;
+
+    do_setxattr(&dentry, name_ptr, value_ptr, value_len, flags, ctx)
+}
+
+pub fn sys_lsetxattr(
+    path_ptr: Vaddr,
+    name_ptr: Vaddr,
+    value_ptr: Vaddr,
+    value_len: usize,
+    flags: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let path = ctx.user_space().read_cstring(path_ptr, PATH_MAX)?;
+    let dentry = lookup_dentry_for_xattr(XattrTarget::PathNoFollow(&path), ctx)?;
+
+    do_setxattr(&dentry, name_ptr, value_ptr, value_len, flags, ctx)
+}
+
+pub fn sys_fsetxattr(
+    fd: FileDesc,
+    name_ptr: Vaddr,
+    value_ptr: Vaddr,
+    value_len: usize,
+    flags: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let dentry = lookup_dentry_for_xattr(XattrTarget::FileDesc(fd), ctx)?;
+
+    do_setxattr(&dentry, name_ptr, value_ptr, value_len, flags, ctx)
+}
+
+fn do_setxattr(
+    dentry: &Dentry,
+    name_ptr: Vaddr,
+    value_ptr: Vaddr,
+    value_len: usize,
+    flags: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = XattrSetFlags::from_bits(flags as _)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid xattr flags"))?;
+    let name = read_xattr_name(name_ptr, ctx)?;
+    let name = XattrName::try_from_full_name(&name)?;
+    debug!(
+        "name = {:?}, value_len = {}, flags = {:?}",
+        name.full_name(),
+        value_len,
+        flags
+    );
+
+    if value_len > XATTR_VALUE_MAX_LEN {
+        return_errno_with_message!(Errno::E2BIG, "the xattr value is too long");
+    }
+    let mut value = vec![0u8; value_len];
+    ctx.user_space()
+        .read_bytes(value_ptr, &mut VmWriter::from(value.as_mut_slice()))?;
+
+    check_xattr_permission(name, dentry, true, ctx)?;
+    dentry.set_xattr(name, &value, flags)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// The file whose extended attributes are operated on.
+pub(super) enum XattrTarget<'a> {
+    /// The path, whose trailing symbolic link is followed.
+    Path(&'a CStr),
+    /// The path, whose trailing symbolic link is not followed.
+    PathNoFollow(&'a CStr),
+    /// The opened file.
+    FileDesc(FileDesc),
+}
+
+pub(super) fn lookup_dentry_for_xattr(target: XattrTarget, ctx: &Context) -> Result<Dentry> {
+    let (path, follow_link) = match target {
+        XattrTarget::Path(path) => (path, true),
+        XattrTarget::PathNoFollow(path) => (path, false),
+        XattrTarget::FileDesc(fd) => {
+            debug!("fd = {}", fd);
+            let file = {
+                let file_table = ctx.posix_thread.file_table().lock();
+                file_table.get_file(fd)?.clone()
+            };
+            let inode_handle = file.downcast_ref::<InodeHandle>().ok_or_else(|| {
+                Error::with_message(Errno::EOPNOTSUPP, "the file does not support xattrs")
+            })?;
+            return Ok(inode_handle.dentry().clone());
+        }
+    };
+    debug!("path = {:?}", path);
+
+    let path = path.to_string_lossy();
+    let fs_path = FsPath::new(AT_FDCWD, path.as_ref())?;
+    let fs = ctx.posix_thread.fs().resolver().read();
+    if follow_link {
+        fs.lookup(&fs_path)
+    } else {
+        fs.lookup_no_follow(&fs_path)
+    }
+}
+
+/// Reads the full name of an extended attribute from the user space.
+pub(super) fn read_xattr_name(name_ptr: Vaddr, ctx: &Context) -> Result<String> {
+    let name = ctx
+        .user_space()
+        .read_cstring(name_ptr, XATTR_NAME_MAX_LEN + 1)
+        .map_err(|err| match err.error() {
+            Errno::E2BIG => Error::with_message(Errno::ERANGE, "the xattr name is too long"),
+            _ => err,
+        })?;
+
+    name.into_string()
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the xattr name is not valid UTF-8"))
+}
+
+/// Checks whether the current thread can access the extended attribute.
+///
+/// The `trusted.` attributes are only accessible with `CAP_SYS_ADMIN`, and so is setting the
+/// `security.` attributes. The `user.` attributes are only allowed on regular files and
+/// directories.
+pub(super) fn check_xattr_permission(
+    name: XattrName,
+    dentry: &Dentry,
+    is_write: bool,
+    ctx: &Context,
+) -> Result<()> {
+    match name.namespace() {
+        XattrNamespace::Trusted => {
+            if !has_sys_admin(ctx) {
+                return_errno_with_message!(
+                    Errno::EPERM,
+                    "accessing trusted xattrs requires CAP_SYS_ADMIN"
+                );
+            }
+        }
+        XattrNamespace::Security => {
+            if is_write && !has_sys_admin(ctx) {
+                return_errno_with_message!(
+                    Errno::EPERM,
+                    "setting security xattrs requires CAP_SYS_ADMIN"
+                );
+            }
+        }
+        XattrNamespace::User => {
+            if !matches!(dentry.type_(), InodeType::File | InodeType::Dir) {
+                if is_write {
+                    return_errno_with_message!(
+                        Errno::EPERM,
+                        "user xattrs are only allowed on regular files and directories"
+                    );
+                }
+                return_errno_with_message!(Errno::ENODATA, "the xattr does not exist");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn has_sys_admin(ctx: &Context) -> bool {
+    ctx.posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+}
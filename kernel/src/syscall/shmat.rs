@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use align_ext::AlignExt;
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{shm::system_v::ShmFlags, PermissionMode},
+    prelude::*,
+    vm::{perms::VmPerms, vmar::is_userspace_vaddr, vmo::VmoRightsOp},
+};
+
+pub fn sys_shmat(shmid: i32, addr: Vaddr, shmflg: i32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = ShmFlags::from_bits_truncate(shmflg as u32);
+    debug!(
+        "[sys_shmat] shmid = {}, addr = 0x{:x}, flags = {:?}",
+        shmid, addr, flags
+    );
+
+    if shmid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the segment ID is invalid");
+    }
+
+    let addr = if addr % PAGE_SIZE == 0 {
+        addr
+    } else if flags.contains(ShmFlags::SHM_RND) {
+        addr.align_down(PAGE_SIZE)
+    } else {
+        return_errno_with_message!(Errno::EINVAL, "the address is not page-aligned");
+    };
+    if addr == 0 && flags.contains(ShmFlags::SHM_REMAP) {
+        return_errno_with_message!(Errno::EINVAL, "SHM_REMAP requires an address");
+    }
+    if addr != 0 && !is_userspace_vaddr(addr) {
+        return_errno_with_message!(Errno::EINVAL, "the address is not in user space");
+    }
+
+    let mut vm_perms = VmPerms::READ;
+    let mut required_perm = PermissionMode::READ;
+    if !flags.contains(ShmFlags::SHM_RDONLY) {
+        vm_perms |= VmPerms::WRITE;
+        required_perm |= PermissionMode::WRITE;
+    }
+    if flags.contains(ShmFlags::SHM_EXEC) {
+        vm_perms |= VmPerms::EXEC;
+        required_perm |= PermissionMode::EXEC;
+    }
+
+    let segment = ctx
+        .posix_thread
+        .ns_proxy()
+        .ipc_ns()
+        .shm_segments()
+        .get_segment(shmid)?;
+    segment.check_perm(&ctx.posix_thread.credentials(), required_perm)?;
+
+    let root_vmar = ctx.process.root_vmar();
+    let vmo = segment.vmo();
+    let mut options = root_vmar
+        .new_map(vmo.size(), vm_perms)?
+        .vmo(vmo.dup()?)
+        .is_shared(true);
+    if addr != 0 {
+        options = options
+            .offset(addr)
+            .can_overwrite(flags.contains(ShmFlags::SHM_REMAP));
+    }
+    let map_addr = options.build().map_err(|err| {
+        // Without `SHM_REMAP`, attaching to an occupied range is invalid.
+        if addr != 0 && err.error() == Errno::EACCES {
+            Error::with_message(Errno::EINVAL, "the address range is occupied")
+        } else {
+            err
+        }
+    })?;
+
+    segment.on_attached(ctx.process.pid());
+
+    Ok(SyscallReturn::Return(map_addr as _))
+}
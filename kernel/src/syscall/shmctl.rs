@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        shm::system_v::{segment::shmid_ds, ShmControlCmd},
+        PermissionMode,
+    },
+    prelude::*,
+};
+
+pub fn sys_shmctl(shmid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    if shmid < 0 {
+        return_errno_with_message!(Errno::EINVAL, "the segment ID is invalid");
+    }
+
+    let cmd = ShmControlCmd::try_from(cmd)?;
+    debug!(
+        "[sys_shmctl] shmid = {}, cmd = {:?}, buf = 0x{:x}",
+        shmid, cmd, buf
+    );
+
+    let credentials = ctx.posix_thread.credentials();
+    let ns_proxy = ctx.posix_thread.ns_proxy();
+    let shm_segments = ns_proxy.ipc_ns().shm_segments();
+    match cmd {
+        ShmControlCmd::IPC_RMID => shm_segments.remove_segment(shmid, &credentials)?,
+        ShmControlCmd::IPC_STAT => {
+            let segment = shm_segments.get_segment(shmid)?;
+            segment.check_perm(&credentials, PermissionMode::READ)?;
+            let mut stat = segment.stat();
+            // The processes may be invisible in the PID namespace of the caller.
+            let pid_ns = ctx.process.pid_ns();
+            stat.shm_cpid = pid_ns.local_id(stat.shm_cpid).unwrap_or(0);
+            stat.shm_lpid = pid_ns.local_id(stat.shm_lpid).unwrap_or(0);
+            ctx.user_space().write_val(buf, &stat)?;
+        }
+        ShmControlCmd::IPC_SET => {
+            let shmid_ds = ctx.user_space().read_val::<shmid_ds>(buf)?;
+            let segment = shm_segments.get_segment(shmid)?;
+            segment.set_owner_and_mode(
+                &credentials,
+                shmid_ds.shm_perm.uid.into(),
+                shmid_ds.shm_perm.gid.into(),
+                shmid_ds.shm_perm.mode as u16,
+            )?;
+        }
+        ShmControlCmd::SHM_LOCK => shm_segments
+            .get_segment(shmid)?
+            .set_locked(&credentials, true)?,
+        ShmControlCmd::SHM_UNLOCK => shm_segments
+            .get_segment(shmid)?
+            .set_locked(&credentials, false)?,
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
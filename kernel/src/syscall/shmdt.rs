@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::prelude::*;
+
+pub fn sys_shmdt(addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("[sys_shmdt] addr = 0x{:x}", addr);
+
+    if addr % PAGE_SIZE != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the address is not page-aligned");
+    }
+
+    let ns_proxy = ctx.posix_thread.ns_proxy();
+    let shm_segments = ns_proxy.ipc_ns().shm_segments();
+    let root_vmar = ctx.process.root_vmar();
+    // The address must be where a segment is attached, i.e., it must be
+    // mapped to the start of the segment.
+    let segment = shm_segments
+        .segments()
+        .into_iter()
+        .find(|segment| root_vmar.vmo_offset_of(addr, segment.vmo()) == Some(0))
+        .ok_or(Error::with_message(
+            Errno::EINVAL,
+            "no segment is attached at the address",
+        ))?;
+
+    let end = addr.saturating_add(segment.vmo().size());
+    root_vmar.remove_vmo_mappings(addr..end, segment.vmo())?;
+    segment.on_detached(ctx.process.pid());
+    shm_segments.destroy_unused_segments();
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,331 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! `signalfd()` creates a file descriptor (we name it as `SignalFile`)
+//! that accepts the signals targeted at the caller.
+//!
+//! The signals in the mask of a `SignalFile` are not delivered through
+//! signal handlers if they are blocked. Instead, they stay pending and
+//! can be dequeued by reading from the `SignalFile`, in the format of
+//! `struct signalfd_siginfo`.
+//!
+//! For more detailed information about this syscall,
+//! refer to the man 2 signalfd documentation.
+//!
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::SyscallReturn;
+use crate::{
+    events::{IoEvents, Observer},
+    fs::{
+        file_handle::FileLike,
+        file_table::{FdFlags, FileDesc},
+        utils::{CreationFlags, InodeMode, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::{
+        posix_thread::{AsPosixThread, PosixThread},
+        signal::{
+            c_types::{siginfo_t, sigset_t},
+            constants::{SIGBUS, SIGFPE, SIGILL, SIGKILL, SIGSEGV, SIGSTOP, SI_USER},
+            sig_mask::{AtomicSigMask, SigMask, SigSet},
+            PollHandle, Pollable, Pollee, SigEvents, SigEventsFilter,
+        },
+        Process,
+    },
+};
+
+pub fn sys_signalfd(
+    fd: FileDesc,
+    mask_ptr: Vaddr,
+    sizemask: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    sys_signalfd4(fd, mask_ptr, sizemask, 0, ctx)
+}
+
+pub fn sys_signalfd4(
+    fd: FileDesc,
+    mask_ptr: Vaddr,
+    sizemask: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = Flags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    if sizemask != size_of::<sigset_t>() {
+        return_errno_with_message!(Errno::EINVAL, "the size of the signal mask is invalid");
+    }
+    let mask = {
+        let mut mask = SigMask::from(ctx.user_space().read_val::<sigset_t>(mask_ptr)?);
+        // SIGKILL and SIGSTOP cannot be received via a signalfd, and they are silently ignored.
+        mask -= SIGKILL;
+        mask -= SIGSTOP;
+        mask
+    };
+    debug!("fd = {}, mask = {:#x}, flags = {:?}", fd, mask, flags);
+
+    if fd >= 0 {
+        let file = {
+            let file_table = ctx.posix_thread.file_table().lock();
+            file_table.get_file(fd)?.clone()
+        };
+        let signal_file = file
+            .downcast_ref::<SignalFile>()
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "not a signalfd file"))?;
+        signal_file.set_mask(mask);
+        return Ok(SyscallReturn::Return(fd as _));
+    }
+    if fd != -1 {
+        return_errno_with_message!(Errno::EBADF, "the file descriptor is invalid");
+    }
+
+    let signal_file = SignalFile::new(mask, flags.contains(Flags::SFD_NONBLOCK), ctx);
+
+    let fd_flags = if flags.contains(Flags::SFD_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let fd = ctx
+        .posix_thread
+        .file_table()
+        .lock()
+        .insert(signal_file, fd_flags);
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+bitflags! {
+    struct Flags: u32 {
+        const SFD_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+        const SFD_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+    }
+}
+
+struct SignalFile {
+    /// The signals that can be read from the file.
+    mask: AtomicSigMask,
+    pollee: Pollee,
+    is_nonblocking: AtomicBool,
+    /// The process whose signals are observed.
+    process: Weak<Process>,
+    this: Weak<SignalFile>,
+}
+
+impl SignalFile {
+    fn new(mask: SigMask, is_nonblocking: bool, ctx: &Context) -> Arc<Self> {
+        let signal_file = Arc::new_cyclic(|weak_self| Self {
+            mask: AtomicSigMask::new(mask),
+            pollee: Pollee::new(),
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+            process: ctx.posix_thread.weak_process(),
+            this: weak_self.clone(),
+        });
+
+        // A signal that is directed to the process can be queued on any of its threads,
+        // including the threads that are created later.
+        ctx.process.register_sigqueue_observer(
+            signal_file.this.clone(),
+            SigEventsFilter::new(SigSet::new_empty()),
+        );
+
+        signal_file
+    }
+
+    fn mask(&self) -> SigMask {
+        self.mask.load(Ordering::Relaxed)
+    }
+
+    fn set_mask(&self, mask: SigMask) {
+        self.mask.store(mask, Ordering::Relaxed);
+        // The signals that are newly added to the mask may be pending already.
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    /// Calls `f` on the threads whose signal queues can be read by the current thread
+    /// until `f` returns `Some`.
+    ///
+    /// The threads are the current thread itself and then the other threads of the current
+    /// process, since a signal that is directed to the process can be queued on any of them.
+    fn find_map_posix_threads<T>(&self, mut f: impl FnMut(&PosixThread) -> Option<T>) -> Option<T> {
+        let current = current_thread!();
+        let current = current.as_posix_thread().unwrap();
+        if let Some(res) = f(current) {
+            return Some(res);
+        }
+
+        let process = current.process();
+        let tasks = process.tasks().lock();
+        tasks.as_slice().iter().find_map(|task| {
+            let posix_thread = task.as_posix_thread().unwrap();
+            if core::ptr::eq(posix_thread, current) {
+                return None;
+            }
+            f(posix_thread)
+        })
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let blocked = SigSet::new_full() - self.mask();
+
+        let mut read_len = 0;
+        while writer.avail() >= size_of::<signalfd_siginfo>() {
+            let Some(signal) =
+                self.find_map_posix_threads(|posix_thread| posix_thread.dequeue_signal(&blocked))
+            else {
+                break;
+            };
+            writer.write_val(&signalfd_siginfo::from(signal.to_info()))?;
+            read_len += size_of::<signalfd_siginfo>();
+        }
+
+        self.pollee.invalidate();
+
+        if read_len == 0 {
+            return_errno_with_message!(Errno::EAGAIN, "no signals are pending");
+        }
+
+        Ok(read_len)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mask = self.mask();
+        let has_pending = self
+            .find_map_posix_threads(|posix_thread| {
+                (!(posix_thread.sig_pending() & mask).is_empty()).then_some(())
+            })
+            .is_some();
+
+        if has_pending {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn read_impl(&self, writer: &mut VmWriter, is_nonblocking: bool) -> Result<usize> {
+        if writer.avail() < size_of::<signalfd_siginfo>() {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is too small");
+        }
+
+        if is_nonblocking {
+            self.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+        }
+    }
+}
+
+impl Drop for SignalFile {
+    fn drop(&mut self) {
+        if let Some(process) = self.process.upgrade() {
+            let observer = self.this.clone() as Weak<dyn Observer<SigEvents>>;
+            process.unregister_sigqueue_observer(&observer);
+        }
+    }
+}
+
+impl Observer<SigEvents> for SignalFile {
+    fn on_events(&self, _events: &SigEvents) {
+        self.pollee.notify(IoEvents::IN);
+    }
+}
+
+impl Pollable for SignalFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for SignalFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_impl(writer, self.is_nonblocking())
+    }
+
+    fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_impl(writer, true)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.is_nonblocking.store(
+            new_flags.contains(StatusFlags::O_NONBLOCK),
+            Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `SignalFile` to it.
+        Metadata::new_file(
+            0,
+            InodeMode::from_bits_truncate(0o600),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
+
+/// The signal information that is read from a signalfd file.
+#[derive(Debug, Clone, Copy, Pod)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct signalfd_siginfo {
+    ssi_signo: u32,
+    ssi_errno: i32,
+    ssi_code: i32,
+    ssi_pid: u32,
+    ssi_uid: u32,
+    ssi_fd: i32,
+    ssi_tid: u32,
+    ssi_band: u32,
+    ssi_overrun: u32,
+    ssi_trapno: u32,
+    ssi_status: i32,
+    ssi_int: i32,
+    ssi_ptr: u64,
+    ssi_utime: u64,
+    ssi_stime: u64,
+    ssi_addr: u64,
+    ssi_addr_lsb: u16,
+    __pad2: u16,
+    ssi_syscall: i32,
+    ssi_call_addr: u64,
+    ssi_arch: u32,
+    __pad: [u8; 28],
+}
+
+impl From<siginfo_t> for signalfd_siginfo {
+    fn from(info: siginfo_t) -> Self {
+        let mut ssi = Self::new_zeroed();
+        ssi.ssi_signo = info.si_signo as u32;
+        ssi.ssi_errno = info.si_errno;
+        ssi.ssi_code = info.si_code;
+
+        let signo = info.si_signo as u8;
+        if [SIGSEGV, SIGBUS, SIGILL, SIGFPE]
+            .iter()
+            .any(|num| num.as_u8() == signo)
+        {
+            ssi.ssi_addr = info.si_addr() as u64;
+        } else if info.si_code <= SI_USER {
+            // The signal is sent by `kill`, `tkill`, `sigqueue` and so on.
+            ssi.ssi_pid = info.si_pid();
+            ssi.ssi_uid = info.si_uid().into();
+        }
+
+        ssi
+    }
+}
@@ -4,8 +4,10 @@ use super::SyscallReturn;
 use crate::{
     fs::{file_handle::FileLike, file_table::FdFlags},
     net::socket::{
-        ip::{datagram::DatagramSocket, stream::StreamSocket},
-        unix::UnixStreamSocket,
+        ip::{datagram::DatagramSocket, stream::StreamSocket, IpFamily, PingSocket, RawSocket},
+        netlink::{NetlinkRouteSocket, NETLINK_ROUTE},
+        packet::PacketSocket,
+        unix::{UnixDatagramSocket, UnixStreamSocket},
         vsock::VsockStreamSocket,
     },
     prelude::*,
@@ -16,32 +18,104 @@ pub fn sys_socket(domain: i32, type_: i32, protocol: i32, ctx: &Context) -> Resu
     let domain = CSocketAddrFamily::try_from(domain)?;
     let sock_type = SockType::try_from(type_ & SOCK_TYPE_MASK)?;
     let sock_flags = SockFlags::from_bits_truncate(type_ & !SOCK_TYPE_MASK);
+    let nonblocking = sock_flags.contains(SockFlags::SOCK_NONBLOCK);
+
+    // The protocols of packet sockets are Ethernet protocols in network byte order, which cannot
+    // be parsed as IP protocols.
+    if domain == CSocketAddrFamily::AF_PACKET {
+        debug!(
+            "domain = {:?}, sock_type = {:?}, sock_flags = {:?}, protocol = {:#x}",
+            domain, sock_type, sock_flags, protocol
+        );
+        let is_raw = match sock_type {
+            SockType::SOCK_RAW => true,
+            SockType::SOCK_DGRAM => false,
+            _ => return_errno_with_message!(
+                Errno::ESOCKTNOSUPPORT,
+                "the socket type is not supported by packet sockets"
+            ),
+        };
+        let file_like = PacketSocket::new(is_raw, u16::from_be(protocol as u16), nonblocking)?;
+        return insert_socket(file_like, sock_flags, ctx);
+    }
+
+    // Raw IP sockets accept any IP protocol, including the ones that are not listed in
+    // `Protocol`.
+    if domain == CSocketAddrFamily::AF_INET && matches!(sock_type, SockType::SOCK_RAW) {
+        debug!(
+            "domain = {:?}, sock_type = {:?}, sock_flags = {:?}, protocol = {}",
+            domain, sock_type, sock_flags, protocol
+        );
+        let protocol = match protocol {
+            0 => return_errno_with_message!(
+                Errno::EPROTONOSUPPORT,
+                "raw sockets cannot be created for IPPROTO_IP"
+            ),
+            1..=255 => protocol as u8,
+            _ => return_errno_with_message!(Errno::EINVAL, "the IP protocol is invalid"),
+        };
+        let file_like = RawSocket::new(protocol, nonblocking)?;
+        return insert_socket(file_like, sock_flags, ctx);
+    }
+
     let protocol = Protocol::try_from(protocol)?;
     debug!(
         "domain = {:?}, sock_type = {:?}, sock_flags = {:?}, protocol = {:?}",
         domain, sock_type, sock_flags, protocol
     );
-    let nonblocking = sock_flags.contains(SockFlags::SOCK_NONBLOCK);
     let file_like = match (domain, sock_type, protocol) {
-        // FIXME: SOCK_SEQPACKET is added to run fcntl_test, not supported yet.
-        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM | SockType::SOCK_SEQPACKET, _) => {
-            UnixStreamSocket::new(nonblocking) as Arc<dyn FileLike>
+        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM, _) => {
+            UnixStreamSocket::new(nonblocking, false) as Arc<dyn FileLike>
+        }
+        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_SEQPACKET, _) => {
+            UnixStreamSocket::new(nonblocking, true) as Arc<dyn FileLike>
+        }
+        (CSocketAddrFamily::AF_UNIX, SockType::SOCK_DGRAM, _) => {
+            UnixDatagramSocket::new(nonblocking) as Arc<dyn FileLike>
         }
         (
             CSocketAddrFamily::AF_INET,
             SockType::SOCK_STREAM,
             Protocol::IPPROTO_IP | Protocol::IPPROTO_TCP,
-        ) => StreamSocket::new(nonblocking) as Arc<dyn FileLike>,
+        ) => StreamSocket::new(IpFamily::Ipv4, nonblocking) as Arc<dyn FileLike>,
         (
             CSocketAddrFamily::AF_INET,
             SockType::SOCK_DGRAM,
             Protocol::IPPROTO_IP | Protocol::IPPROTO_UDP,
-        ) => DatagramSocket::new(nonblocking) as Arc<dyn FileLike>,
+        ) => DatagramSocket::new(IpFamily::Ipv4, nonblocking) as Arc<dyn FileLike>,
+        (CSocketAddrFamily::AF_INET, SockType::SOCK_DGRAM, Protocol::IPPROTO_ICMP) => {
+            PingSocket::new(nonblocking) as Arc<dyn FileLike>
+        }
+        (
+            CSocketAddrFamily::AF_INET6,
+            SockType::SOCK_STREAM,
+            Protocol::IPPROTO_IP | Protocol::IPPROTO_TCP,
+        ) => StreamSocket::new(IpFamily::Ipv6, nonblocking) as Arc<dyn FileLike>,
+        (
+            CSocketAddrFamily::AF_INET6,
+            SockType::SOCK_DGRAM,
+            Protocol::IPPROTO_IP | Protocol::IPPROTO_UDP,
+        ) => DatagramSocket::new(IpFamily::Ipv6, nonblocking) as Arc<dyn FileLike>,
+        // Netlink protocols are parsed as IP protocols above, which is fine as long as their
+        // values are valid IP protocols.
+        (CSocketAddrFamily::AF_NETLINK, SockType::SOCK_RAW | SockType::SOCK_DGRAM, protocol)
+            if protocol as i32 == NETLINK_ROUTE =>
+        {
+            NetlinkRouteSocket::new(nonblocking) as Arc<dyn FileLike>
+        }
         (CSocketAddrFamily::AF_VSOCK, SockType::SOCK_STREAM, _) => {
             Arc::new(VsockStreamSocket::new(nonblocking)) as Arc<dyn FileLike>
         }
         _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported domain"),
     };
+    insert_socket(file_like, sock_flags, ctx)
+}
+
+fn insert_socket(
+    file_like: Arc<dyn FileLike>,
+    sock_flags: SockFlags,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
     let fd = {
         let mut file_table = ctx.posix_thread.file_table().lock();
         let fd_flags = if sock_flags.contains(SockFlags::SOCK_CLOEXEC) {
@@ -28,6 +28,11 @@ pub fn sys_tgkill(tgid: Pid, tid: Tid, sig_num: u8, ctx: &Context) -> Result<Sys
         let uid = ctx.posix_thread.credentials().ruid();
         UserSignal::new(sig_num, UserSignalKind::Tkill, pid, uid)
     });
+    // Translates the IDs in the PID namespace of the current process to the global IDs.
+    let pid_ns = ctx.process.pid_ns();
+    let (Some(tid), Some(tgid)) = (pid_ns.global_id(tid), pid_ns.global_id(tgid)) else {
+        return_errno_with_message!(Errno::ESRCH, "target thread does not exist");
+    };
     tgkill(tid, tgid, signal, ctx)?;
     Ok(SyscallReturn::Return(0))
 }
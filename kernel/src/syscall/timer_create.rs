@@ -73,9 +73,14 @@ pub fn sys_timer_create(
                 // Send a signal to the specified thread when the timer is expired.
                 SigNotify::SIGEV_THREAD_ID => {
                     let tid = sig_event.sigev_un.read_tid() as u32;
-                    let thread = thread_table::get_thread(tid).ok_or_else(|| {
-                        Error::with_message(Errno::EINVAL, "target thread does not exist")
-                    })?;
+                    let thread = ctx
+                        .process
+                        .pid_ns()
+                        .global_id(tid)
+                        .and_then(thread_table::get_thread)
+                        .ok_or_else(|| {
+                            Error::with_message(Errno::EINVAL, "target thread does not exist")
+                        })?;
                     let posix_thread = thread.as_posix_thread().unwrap();
                     if posix_thread.process().pid() != current_process.pid() {
                         return_errno_with_message!(
@@ -118,7 +123,11 @@ pub fn sys_timer_create(
         let dynamic_clockid_info = DynamicClockIdInfo::try_from(clockid)?;
         match dynamic_clockid_info {
             DynamicClockIdInfo::Pid(pid, clock_type) => {
-                let process = process_table::get_process(pid)
+                let process = ctx
+                    .process
+                    .pid_ns()
+                    .global_id(pid)
+                    .and_then(process_table::get_process)
                     .ok_or_else(|| crate::Error::with_message(Errno::EINVAL, "invalid clock id"))?;
                 let process_timer_manager = process.timer_manager();
                 match clock_type {
@@ -129,7 +138,11 @@ pub fn sys_timer_create(
                 }
             }
             DynamicClockIdInfo::Tid(tid, clock_type) => {
-                let thread = thread_table::get_thread(tid)
+                let thread = ctx
+                    .process
+                    .pid_ns()
+                    .global_id(tid)
+                    .and_then(thread_table::get_thread)
                     .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid clock id"))?;
                 let posix_thread = thread.as_posix_thread().unwrap();
                 match clock_type {
@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! `timerfd_create()` creates a timer (we name it as `TimerFile`)
+//! that delivers timer expiration notifications via a file descriptor.
+//!
+//! `TimerFile` counts the number of expirations that have occurred since
+//! the timer was last set or read. Reading from `TimerFile` returns the
+//! count as a u64 integer and resets it.
+//! The read operation may be blocked based on file flags.
+//!
+//! For more detailed information about this syscall,
+//! refer to the man 2 timerfd_create documentation.
+//!
+
+use core::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use super::{ClockId, SyscallReturn};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file_handle::FileLike,
+        file_table::{FdFlags, FileDesc},
+        utils::{CreationFlags, InodeMode, Metadata, StatusFlags},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable, Pollee},
+    time::{
+        clockid_t,
+        clocks::{BootTimeClock, MonotonicClock, RealTimeClock},
+        itimerspec_t,
+        timer::{Timeout, Timer},
+        timespec_t,
+    },
+};
+
+pub fn sys_timerfd_create(clockid: clockid_t, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = Flags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("clockid = {}, flags = {:?}", clockid, flags);
+
+    let clock_id = ClockId::try_from(clockid)
+        .map_err(|_| Error::with_message(Errno::EINVAL, "invalid clock ID"))?;
+    let timer_file = TimerFile::new(clock_id, flags.contains(Flags::TFD_NONBLOCK))?;
+
+    let fd_flags = if flags.contains(Flags::TFD_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+    let fd = ctx
+        .posix_thread
+        .file_table()
+        .lock()
+        .insert(timer_file, fd_flags);
+    Ok(SyscallReturn::Return(fd as _))
+}
+
+pub fn sys_timerfd_settime(
+    fd: FileDesc,
+    flags: u32,
+    new_itimerspec_addr: Vaddr,
+    old_itimerspec_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = TimerFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "unknown flags"))?;
+    debug!("fd = {}, flags = {:?}", fd, flags);
+
+    let user_space = ctx.user_space();
+    let new_itimerspec = user_space.read_val::<itimerspec_t>(new_itimerspec_addr)?;
+    let interval = Duration::try_from(new_itimerspec.it_interval)?;
+    let expire_time = Duration::try_from(new_itimerspec.it_value)?;
+
+    let file = get_file(fd, ctx)?;
+    let timer_file = file
+        .downcast_ref::<TimerFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "not a timerfd file"))?;
+
+    if old_itimerspec_addr != 0 {
+        user_space.write_val(old_itimerspec_addr, &timer_file.itimerspec())?;
+    }
+
+    let timeout = if expire_time == Duration::ZERO {
+        None
+    } else if flags.contains(TimerFlags::TFD_TIMER_ABSTIME) {
+        Some(Timeout::When(expire_time))
+    } else {
+        Some(Timeout::After(expire_time))
+    };
+    // TODO: Support `TFD_TIMER_CANCEL_ON_SET`. Currently, the realtime clock
+    // cannot be set, so the timer will never be canceled.
+    timer_file.set_time(interval, timeout);
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub fn sys_timerfd_gettime(
+    fd: FileDesc,
+    itimerspec_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("fd = {}", fd);
+
+    let file = get_file(fd, ctx)?;
+    let timer_file = file
+        .downcast_ref::<TimerFile>()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "not a timerfd file"))?;
+
+    ctx.user_space()
+        .write_val(itimerspec_addr, &timer_file.itimerspec())?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn get_file(fd: FileDesc, ctx: &Context) -> Result<Arc<dyn FileLike>> {
+    let file_table = ctx.posix_thread.file_table().lock();
+    Ok(file_table.get_file(fd)?.clone())
+}
+
+bitflags! {
+    struct Flags: u32 {
+        const TFD_CLOEXEC = CreationFlags::O_CLOEXEC.bits();
+        const TFD_NONBLOCK = StatusFlags::O_NONBLOCK.bits();
+    }
+}
+
+bitflags! {
+    struct TimerFlags: u32 {
+        const TFD_TIMER_ABSTIME = 1 << 0;
+        const TFD_TIMER_CANCEL_ON_SET = 1 << 1;
+    }
+}
+
+struct TimerFile {
+    timer: Arc<Timer>,
+    /// The number of expirations since the timer was last set or read.
+    ticks: AtomicU64,
+    pollee: Pollee,
+    is_nonblocking: AtomicBool,
+}
+
+impl TimerFile {
+    fn new(clock_id: ClockId, is_nonblocking: bool) -> Result<Arc<Self>> {
+        let timer_manager = match clock_id {
+            ClockId::CLOCK_REALTIME => RealTimeClock::timer_manager(),
+            ClockId::CLOCK_MONOTONIC => MonotonicClock::timer_manager(),
+            ClockId::CLOCK_BOOTTIME => BootTimeClock::timer_manager(),
+            _ => return_errno_with_message!(Errno::EINVAL, "the clock is not supported"),
+        };
+
+        Ok(Arc::new_cyclic(|weak_self: &Weak<Self>| {
+            let weak_self = weak_self.clone();
+            // The callback is invoked in the timer softirq, where it is fine to
+            // notify the pollee since it does not sleep.
+            let timer = timer_manager.create_timer(move || {
+                if let Some(timer_file) = weak_self.upgrade() {
+                    timer_file.on_expired();
+                }
+            });
+
+            Self {
+                timer,
+                ticks: AtomicU64::new(0),
+                pollee: Pollee::new(),
+                is_nonblocking: AtomicBool::new(is_nonblocking),
+            }
+        }))
+    }
+
+    fn on_expired(&self) {
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    /// Arms the timer, or disarms it if `timeout` is `None`.
+    fn set_time(&self, interval: Duration, timeout: Option<Timeout>) {
+        self.timer.cancel();
+        self.ticks.store(0, Ordering::Relaxed);
+        self.pollee.invalidate();
+
+        self.timer.set_interval(interval);
+        if let Some(timeout) = timeout {
+            self.timer.set_timeout(timeout);
+        }
+    }
+
+    fn itimerspec(&self) -> itimerspec_t {
+        itimerspec_t {
+            it_interval: timespec_t::from(self.timer.interval()),
+            it_value: timespec_t::from(self.timer.remain()),
+        }
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn try_read(&self, writer: &mut VmWriter) -> Result<()> {
+        let ticks = self.ticks.swap(0, Ordering::Relaxed);
+        if ticks == 0 {
+            return_errno_with_message!(Errno::EAGAIN, "the timer has not expired");
+        }
+        self.pollee.invalidate();
+
+        if let Err(err) = writer.write_val(&ticks) {
+            // Do not lose the expirations if they cannot be read by the user.
+            self.ticks.fetch_add(ticks, Ordering::Relaxed);
+            self.pollee.notify(IoEvents::IN);
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if self.ticks.load(Ordering::Relaxed) != 0 {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn read_impl(&self, writer: &mut VmWriter, is_nonblocking: bool) -> Result<usize> {
+        let read_len = size_of::<u64>();
+
+        if writer.avail() < read_len {
+            return_errno_with_message!(Errno::EINVAL, "buf len is less than the size of u64");
+        }
+
+        if is_nonblocking {
+            self.try_read(writer)?;
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))?;
+        }
+
+        Ok(read_len)
+    }
+}
+
+impl Drop for TimerFile {
+    fn drop(&mut self) {
+        self.timer.cancel();
+    }
+}
+
+impl Pollable for TimerFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for TimerFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_impl(writer, self.is_nonblocking())
+    }
+
+    fn read_nonblocking(&self, writer: &mut VmWriter) -> Result<usize> {
+        self.read_impl(writer, true)
+    }
+
+    fn status_flags(&self) -> StatusFlags {
+        if self.is_nonblocking() {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        }
+    }
+
+    fn set_status_flags(&self, new_flags: StatusFlags) -> Result<()> {
+        self.is_nonblocking.store(
+            new_flags.contains(StatusFlags::O_NONBLOCK),
+            Ordering::Relaxed,
+        );
+        Ok(())
+    }
+
+    fn metadata(&self) -> Metadata {
+        // This is a dummy implementation.
+        // TODO: Add "anonymous inode fs" and link `TimerFile` to it.
+        Metadata::new_file(
+            0,
+            InodeMode::from_bits_truncate(0o600),
+            aster_block::BLOCK_SIZE,
+        )
+    }
+}
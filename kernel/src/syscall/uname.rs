@@ -1,62 +1,55 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use spin::Once;
-
 use super::SyscallReturn;
-use crate::prelude::*;
-
-// We don't use the real name and version of our os here. Instead, we pick up fake values witch is the same as the ones of linux.
-// The values are used to fool glibc since glibc will check the version and os name.
-static UTS_NAME: Once<UtsName> = Once::new();
+use crate::{
+    prelude::*,
+    process::{credentials::capabilities::CapSet, namespace::UTS_FIELD_LEN},
+};
 
-const UTS_FIELD_LEN: usize = 65;
-
-#[derive(Debug, Clone, Copy, Pod)]
-#[repr(C)]
-struct UtsName {
-    sysname: [u8; UTS_FIELD_LEN],
-    nodename: [u8; UTS_FIELD_LEN],
-    release: [u8; UTS_FIELD_LEN],
-    version: [u8; UTS_FIELD_LEN],
-    machine: [u8; UTS_FIELD_LEN],
-    domainname: [u8; UTS_FIELD_LEN],
+pub fn sys_uname(old_uname_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("old uname addr = 0x{:x}", old_uname_addr);
+    let uts_name = ctx.posix_thread.ns_proxy().uts_ns().uts_name();
+    ctx.user_space().write_val(old_uname_addr, &uts_name)?;
+    Ok(SyscallReturn::Return(0))
 }
 
-impl UtsName {
-    const fn new() -> Self {
-        UtsName {
-            sysname: [0; UTS_FIELD_LEN],
-            nodename: [0; UTS_FIELD_LEN],
-            release: [0; UTS_FIELD_LEN],
-            version: [0; UTS_FIELD_LEN],
-            machine: [0; UTS_FIELD_LEN],
-            domainname: [0; UTS_FIELD_LEN],
-        }
-    }
+pub fn sys_sethostname(name_addr: Vaddr, len: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("name addr = 0x{:x}, len = {}", name_addr, len);
+    let hostname = read_name(name_addr, len, ctx)?;
+    ctx.posix_thread
+        .ns_proxy()
+        .uts_ns()
+        .set_hostname(&hostname)?;
+    Ok(SyscallReturn::Return(0))
 }
 
-pub(super) fn init() {
-    UTS_NAME.call_once(|| {
-        let copy_slice = |src: &[u8], dst: &mut [u8]| {
-            let len = src.len().min(dst.len());
-            dst[..len].copy_from_slice(&src[..len]);
-        };
+pub fn sys_setdomainname(name_addr: Vaddr, len: i32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("name addr = 0x{:x}, len = {}", name_addr, len);
+    let domainname = read_name(name_addr, len, ctx)?;
+    ctx.posix_thread
+        .ns_proxy()
+        .uts_ns()
+        .set_domainname(&domainname)?;
+    Ok(SyscallReturn::Return(0))
+}
 
-        let mut uts_name = UtsName::new();
-        copy_slice(b"Linux", &mut uts_name.sysname);
-        copy_slice(b"WHITLEY", &mut uts_name.nodename);
-        copy_slice(b"5.13.0", &mut uts_name.release);
-        copy_slice(b"5.13.0", &mut uts_name.version);
-        copy_slice(b"x86_64", &mut uts_name.machine);
-        copy_slice(b"", &mut uts_name.domainname);
+/// Reads a name to be set in the UTS namespace, which requires `CAP_SYS_ADMIN`.
+fn read_name(name_addr: Vaddr, len: i32, ctx: &Context) -> Result<Vec<u8>> {
+    if !ctx
+        .posix_thread
+        .credentials()
+        .effective_capset()
+        .contains(CapSet::SYS_ADMIN)
+    {
+        return_errno_with_message!(Errno::EPERM, "setting the name requires CAP_SYS_ADMIN");
+    }
 
-        uts_name
-    });
-}
+    if len < 0 || len as usize >= UTS_FIELD_LEN {
+        return_errno_with_message!(Errno::EINVAL, "the name length is invalid");
+    }
 
-pub fn sys_uname(old_uname_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
-    debug!("old uname addr = 0x{:x}", old_uname_addr);
+    let mut name = vec![0u8; len as usize];
     ctx.user_space()
-        .write_val(old_uname_addr, UTS_NAME.get().unwrap())?;
-    Ok(SyscallReturn::Return(0))
+        .read_bytes(name_addr, &mut VmWriter::from(name.as_mut_slice()))?;
+    Ok(name)
 }
@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{namespace, CloneFlags},
+};
+
+pub fn sys_unshare(flags: u64, ctx: &Context) -> Result<SyscallReturn> {
+    let flags = u32::try_from(flags)
+        .ok()
+        .and_then(CloneFlags::from_bits)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid flags"))?;
+    debug!("flags = {:?}", flags);
+
+    namespace::unshare(flags, ctx)?;
+    Ok(SyscallReturn::Return(0))
+}
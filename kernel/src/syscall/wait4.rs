@@ -22,22 +22,28 @@ pub fn sys_wait4(
     debug!("wait4 current pid = {}", ctx.process.pid());
     let process_filter = ProcessFilter::from_id(wait_pid as _);
 
-    let waited_process =
+    let wait_status =
         wait_child_exit(process_filter, wait_options, ctx).map_err(|err| match err.error() {
             Errno::EINTR => Error::new(Errno::ERESTARTSYS),
             _ => err,
         })?;
-    let Some(process) = waited_process else {
+    let Some(wait_status) = wait_status else {
         return Ok(SyscallReturn::Return(0 as _));
     };
 
-    let (return_pid, exit_code) = (process.pid(), process.status().exit_code());
+    let return_pid = ctx
+        .process
+        .pid_ns()
+        .local_id(wait_status.pid())
+        .unwrap_or(0);
+    let status_code = wait_status.status_code();
     if exit_status_ptr != 0 {
         ctx.user_space()
-            .write_val(exit_status_ptr as _, &exit_code)?;
+            .write_val(exit_status_ptr as _, &status_code)?;
     }
 
     if rusage_addr != 0 {
+        let process = wait_status.process();
         let rusage = rusage_t {
             ru_utime: process.prof_clock().user_clock().read_time().into(),
             ru_stime: process.prof_clock().kernel_clock().read_time().into(),
@@ -18,11 +18,16 @@ pub fn sys_waitid(
     let process_filter = ProcessFilter::from_which_and_id(which, upid)?;
     let wait_options = WaitOptions::from_bits(options as u32)
         .ok_or(Error::with_message(Errno::EINVAL, "invalid options"))?;
-    let waited_process =
+    let wait_status =
         wait_child_exit(process_filter, wait_options, ctx).map_err(|err| match err.error() {
             Errno::EINTR => Error::new(Errno::ERESTARTSYS),
             _ => err,
         })?;
-    let pid = waited_process.map_or(0, |process| process.pid());
+    let pid = wait_status.map_or(0, |wait_status| {
+        ctx.process
+            .pid_ns()
+            .local_id(wait_status.pid())
+            .unwrap_or(0)
+    });
     Ok(SyscallReturn::Return(pid as _))
 }
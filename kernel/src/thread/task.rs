@@ -48,7 +48,7 @@ pub fn create_new_user_task(user_space: Arc<UserSpace>, thread_ref: Arc<Thread>)
         // in the child process.
         if is_userspace_vaddr(child_tid_ptr) {
             current_userspace!()
-                .write_val(child_tid_ptr, &current_posix_thread.tid())
+                .write_val(child_tid_ptr, &current_posix_thread.ns_tid())
                 .unwrap();
         }
 
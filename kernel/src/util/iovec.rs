@@ -83,6 +83,18 @@ fn copy_iovs_and_convert<'a, T: 'a>(
     Ok(v.into_boxed_slice())
 }
 
+/// Copies the IO vectors from the user space.
+///
+/// The base address and the length of each non-empty buffer are returned, so that the buffers
+/// can be accessed later, e.g., by kernel workers on behalf of the user.
+pub fn copy_io_vecs_from_user(
+    ctx: &Context,
+    start_addr: Vaddr,
+    count: usize,
+) -> Result<Box<[(Vaddr, usize)]>> {
+    copy_iovs_and_convert(ctx, start_addr, count, |iov, _| Ok((iov.base, iov.len)))
+}
+
 /// A collection of [`VmReader`]s.
 ///
 /// Such readers are built from user-provided buffer, so it's always fallible.
@@ -4,7 +4,13 @@ use core::cmp::min;
 
 use ostd::task::Task;
 
-use super::{ip::CSocketAddrInet, unix, vsock::CSocketAddrVm};
+use super::{
+    ip::{CSocketAddrInet, CSocketAddrInet6},
+    netlink::CSocketAddrNetlink,
+    packet::CSocketAddrPacket,
+    unix,
+    vsock::CSocketAddrVm,
+};
 use crate::{current_userspace, net::socket::SocketAddr, prelude::*};
 
 /// Address family.
@@ -159,6 +165,13 @@ pub fn read_socket_addr_from_user(addr: Vaddr, addr_len: usize) -> Result<Socket
             let (addr, port) = CSocketAddrInet::from_bytes(storage.as_bytes()).into();
             SocketAddr::IPv4(addr, port)
         }
+        Ok(CSocketAddrFamily::AF_INET6) => {
+            if addr_len < CSocketAddrInet6::MIN_LEN {
+                return_errno_with_message!(Errno::EINVAL, "the socket address length is too small");
+            }
+            let (addr, port) = CSocketAddrInet6::from_bytes(storage.as_bytes()).into();
+            SocketAddr::IPv6(addr, port)
+        }
         Ok(CSocketAddrFamily::AF_UNIX) => {
             let addr = unix::from_c_bytes(&storage.as_bytes()[..addr_len])?;
             SocketAddr::Unix(addr)
@@ -170,6 +183,23 @@ pub fn read_socket_addr_from_user(addr: Vaddr, addr_len: usize) -> Result<Socket
             let addr = CSocketAddrVm::from_bytes(storage.as_bytes());
             SocketAddr::Vsock(addr.into())
         }
+        Ok(CSocketAddrFamily::AF_NETLINK) => {
+            if addr_len < size_of::<CSocketAddrNetlink>() {
+                return_errno_with_message!(Errno::EINVAL, "the socket address length is too small");
+            }
+            let addr = CSocketAddrNetlink::from_bytes(storage.as_bytes());
+            SocketAddr::Netlink(addr.into())
+        }
+        Ok(CSocketAddrFamily::AF_PACKET) => {
+            if addr_len < CSocketAddrPacket::ADDR_OFFSET {
+                return_errno_with_message!(Errno::EINVAL, "the socket address length is too small");
+            }
+            let addr = CSocketAddrPacket::from_bytes(storage.as_bytes());
+            if addr_len < addr.len() {
+                return_errno_with_message!(Errno::EINVAL, "the socket address length is too small");
+            }
+            SocketAddr::Packet(addr.into())
+        }
         _ => {
             return_errno_with_message!(
                 Errno::EAFNOSUPPORT,
@@ -242,33 +272,34 @@ pub fn write_socket_addr_with_max_len(
     let current_task = Task::current().unwrap();
     let user_space = CurrentUserSpace::new(&current_task);
 
-    let actual_len = match socket_addr {
-        SocketAddr::IPv4(addr, port) => {
-            let socket_addr = CSocketAddrInet::from((*addr, *port));
-            let actual_len = size_of::<CSocketAddrInet>();
-            let written_len = min(actual_len, max_len as _);
-            user_space.write_bytes(
-                dest,
-                &mut VmReader::from(&socket_addr.as_bytes()[..written_len]),
-            )?;
-            actual_len
-        }
-        SocketAddr::Unix(addr) => unix::into_c_bytes_and(addr, |bytes| {
-            let written_len = min(bytes.len(), max_len as _);
-            user_space.write_bytes(dest, &mut VmReader::from(&bytes[..written_len]))?;
-            Ok::<usize, Error>(bytes.len())
-        })?,
-        SocketAddr::Vsock(addr) => {
-            let socket_addr = CSocketAddrVm::from(*addr);
-            let actual_len = size_of::<CSocketAddrVm>();
-            let written_len = min(actual_len, max_len as _);
-            user_space.write_bytes(
-                dest,
-                &mut VmReader::from(&socket_addr.as_bytes()[..written_len]),
-            )?;
-            actual_len
-        }
-    };
+    let actual_len = socket_addr_into_c_bytes_and(socket_addr, |bytes| {
+        let written_len = min(bytes.len(), max_len as _);
+        user_space.write_bytes(dest, &mut VmReader::from(&bytes[..written_len]))?;
+        Ok::<usize, Error>(bytes.len())
+    })?;
 
     Ok(actual_len as i32)
 }
+
+/// Converts a socket address to the bytes of the corresponding Linux C structure and calls `f`
+/// with the bytes.
+///
+/// # Panics
+///
+/// This method will panic in the same cases as [`write_socket_addr_with_max_len`].
+pub fn socket_addr_into_c_bytes_and<R, F>(socket_addr: &SocketAddr, f: F) -> R
+where
+    F: FnOnce(&[u8]) -> R,
+{
+    match socket_addr {
+        SocketAddr::IPv4(addr, port) => f(CSocketAddrInet::from((*addr, *port)).as_bytes()),
+        SocketAddr::IPv6(addr, port) => f(CSocketAddrInet6::from((*addr, *port)).as_bytes()),
+        SocketAddr::Unix(addr) => unix::into_c_bytes_and(addr, f),
+        SocketAddr::Vsock(addr) => f(CSocketAddrVm::from(*addr).as_bytes()),
+        SocketAddr::Netlink(addr) => f(CSocketAddrNetlink::from(*addr).as_bytes()),
+        SocketAddr::Packet(addr) => {
+            let c_addr = CSocketAddrPacket::from(*addr);
+            f(&c_addr.as_bytes()[..c_addr.len()])
+        }
+    }
+}
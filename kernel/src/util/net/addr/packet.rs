@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::wire::EthernetAddress;
+
+use super::family::CSocketAddrFamily;
+use crate::{
+    net::socket::packet::{PacketSocketAddr, PacketType},
+    prelude::*,
+};
+
+/// Packet socket address.
+///
+/// See <https://www.man7.org/linux/man-pages/man7/packet.7.html>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CSocketAddrPacket {
+    /// Address family (AF_PACKET).
+    sll_family: u16,
+    /// Physical-layer protocol, in network byte order.
+    sll_protocol: u16,
+    /// Interface number.
+    sll_ifindex: i32,
+    /// ARP hardware type.
+    sll_hatype: u16,
+    /// Packet type.
+    sll_pkttype: u8,
+    /// Length of address.
+    sll_halen: u8,
+    /// Physical-layer address.
+    sll_addr: [u8; 8],
+}
+
+impl CSocketAddrPacket {
+    /// The offset of the physical-layer address.
+    ///
+    /// The length of the socket address is this offset plus the length of the physical-layer
+    /// address.
+    pub(super) const ADDR_OFFSET: usize = 12;
+
+    /// Returns the length of the socket address.
+    pub(super) fn len(&self) -> usize {
+        Self::ADDR_OFFSET + (self.sll_halen as usize).min(self.sll_addr.len())
+    }
+}
+
+impl From<PacketSocketAddr> for CSocketAddrPacket {
+    fn from(value: PacketSocketAddr) -> Self {
+        let mut sll_addr = [0; 8];
+        let sll_halen = if let Some(hardware_addr) = value.hardware_addr {
+            sll_addr[..6].copy_from_slice(&hardware_addr.0);
+            6
+        } else {
+            0
+        };
+
+        Self {
+            sll_family: CSocketAddrFamily::AF_PACKET as u16,
+            sll_protocol: value.protocol.to_be(),
+            sll_ifindex: value.ifindex as i32,
+            sll_hatype: value.hardware_type,
+            sll_pkttype: value.packet_type as u8,
+            sll_halen,
+            sll_addr,
+        }
+    }
+}
+
+impl From<CSocketAddrPacket> for PacketSocketAddr {
+    fn from(value: CSocketAddrPacket) -> Self {
+        // Only Ethernet addresses are supported.
+        let hardware_addr = if value.sll_halen >= 6 {
+            Some(EthernetAddress::from_bytes(&value.sll_addr[..6]))
+        } else {
+            None
+        };
+
+        Self {
+            protocol: u16::from_be(value.sll_protocol),
+            ifindex: value.sll_ifindex as u32,
+            hardware_type: value.sll_hatype,
+            packet_type: PacketType::try_from(value.sll_pkttype).unwrap_or(PacketType::Host),
+            hardware_addr,
+        }
+    }
+}
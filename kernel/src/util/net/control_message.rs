@@ -0,0 +1,247 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Control messages (i.e., ancillary data) in `sendmsg` and `recvmsg`.
+
+use super::CSocketOptionLevel;
+use crate::{
+    current_userspace,
+    fs::{
+        file_handle::FileLike,
+        file_table::{FdFlags, FileDesc},
+    },
+    net::socket::{unix::UnixCredentials, ControlMessage},
+    prelude::*,
+    process::{posix_thread::AsPosixThread, Gid, Uid},
+};
+
+/// The `cmsghdr` structure in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CControlMessageHeader {
+    cmsg_len: usize,
+    cmsg_level: i32,
+    cmsg_type: i32,
+}
+
+const HEADER_LEN: usize = size_of::<CControlMessageHeader>();
+
+/// Control message types at the `SOL_SOCKET` level.
+///
+/// The definition is from https://elixir.bootlin.com/linux/v6.0.9/source/include/linux/socket.h.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+enum CSocketControlMessageType {
+    SCM_RIGHTS = 1,
+    SCM_CREDENTIALS = 2,
+}
+
+/// The maximum number of files that can be passed in one message, which is `SCM_MAX_FD` in Linux.
+const MAX_FILES: usize = 253;
+
+/// The `ucred` structure in Linux.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+pub(super) struct CUserCred {
+    pid: i32,
+    uid: u32,
+    gid: u32,
+}
+
+impl From<UnixCredentials> for CUserCred {
+    fn from(value: UnixCredentials) -> Self {
+        // The PID is translated into the PID namespace of the current process. Zero is reported
+        // if the process is not visible in the namespace.
+        let pid = current!().pid_ns().local_id(value.pid()).unwrap_or(0);
+
+        Self {
+            pid: pid as i32,
+            uid: value.uid().into(),
+            gid: value.gid().into(),
+        }
+    }
+}
+
+/// Reads the control messages in the buffer of `len` bytes at `addr`.
+pub(super) fn read_control_messages_from_user(
+    addr: Vaddr,
+    len: usize,
+) -> Result<Vec<ControlMessage>> {
+    let user_space = current_userspace!();
+
+    let mut files = Vec::new();
+    let mut cred = None;
+
+    let mut offset = 0;
+    while len - offset >= HEADER_LEN {
+        let header = user_space.read_val::<CControlMessageHeader>(addr + offset)?;
+        if header.cmsg_len < HEADER_LEN || header.cmsg_len > len - offset {
+            return_errno_with_message!(Errno::EINVAL, "the control message length is invalid");
+        }
+
+        let data_addr = addr + offset + HEADER_LEN;
+        let data_len = header.cmsg_len - HEADER_LEN;
+        offset = (offset + align_up(header.cmsg_len)).min(len);
+
+        // Like Linux, control messages at other levels are ignored.
+        if header.cmsg_level != CSocketOptionLevel::SOL_SOCKET as i32 {
+            continue;
+        }
+
+        match CSocketControlMessageType::try_from(header.cmsg_type)? {
+            CSocketControlMessageType::SCM_RIGHTS => {
+                let num_fds = data_len / size_of::<FileDesc>();
+                if files.len() + num_fds > MAX_FILES {
+                    return_errno_with_message!(Errno::EINVAL, "too many files are passed");
+                }
+
+                let current = current_thread!();
+                let file_table = current.as_posix_thread().unwrap().file_table().lock();
+                for i in 0..num_fds {
+                    let fd =
+                        user_space.read_val::<FileDesc>(data_addr + i * size_of::<FileDesc>())?;
+                    files.push(file_table.get_file(fd)?.clone());
+                }
+            }
+            CSocketControlMessageType::SCM_CREDENTIALS => {
+                if data_len != size_of::<CUserCred>() {
+                    return_errno_with_message!(Errno::EINVAL, "the credentials are invalid");
+                }
+
+                let ucred = user_space.read_val::<CUserCred>(data_addr)?;
+                cred = Some(UnixCredentials::new_specified(
+                    ucred.pid as _,
+                    Uid::new(ucred.uid),
+                    Gid::new(ucred.gid),
+                )?);
+            }
+        }
+    }
+
+    let mut messages = Vec::new();
+    if let Some(cred) = cred {
+        messages.push(ControlMessage::Credentials(cred));
+    }
+    if !files.is_empty() {
+        messages.push(ControlMessage::Rights(files));
+    }
+
+    Ok(messages)
+}
+
+/// Writes the control messages to the buffer of `len` bytes at `addr`.
+///
+/// The files in [`ControlMessage::Rights`] are installed in the file table of the current
+/// process with `fd_flags`.
+///
+/// This method returns the number of bytes written and whether the control messages are
+/// truncated because the buffer is too small.
+pub(super) fn write_control_messages_to_user(
+    messages: &[ControlMessage],
+    addr: Vaddr,
+    len: usize,
+    fd_flags: FdFlags,
+) -> Result<(usize, bool)> {
+    let mut writer = ControlMessageWriter {
+        addr,
+        len,
+        offset: 0,
+        is_truncated: false,
+    };
+
+    for message in messages {
+        match message {
+            ControlMessage::Credentials(cred) => writer.write(
+                CSocketControlMessageType::SCM_CREDENTIALS,
+                CUserCred::from(*cred).as_bytes(),
+            )?,
+            ControlMessage::Rights(files) => writer.write_files(files, fd_flags)?,
+        }
+    }
+
+    Ok((writer.offset, writer.is_truncated))
+}
+
+struct ControlMessageWriter {
+    addr: Vaddr,
+    len: usize,
+    offset: usize,
+    is_truncated: bool,
+}
+
+impl ControlMessageWriter {
+    /// Writes a control message, truncating it if there is not enough space.
+    fn write(&mut self, type_: CSocketControlMessageType, data: &[u8]) -> Result<()> {
+        let avail_len = self.len - self.offset;
+        if avail_len < HEADER_LEN {
+            self.is_truncated = true;
+            return Ok(());
+        }
+
+        let mut message_len = HEADER_LEN + data.len();
+        if message_len > avail_len {
+            self.is_truncated = true;
+            message_len = avail_len;
+        }
+
+        self.write_header(type_, message_len)?;
+        current_userspace!().write_bytes(
+            self.addr + self.offset + HEADER_LEN,
+            &mut VmReader::from(&data[..message_len - HEADER_LEN]),
+        )?;
+
+        self.offset += align_up(HEADER_LEN + data.len()).min(avail_len);
+        Ok(())
+    }
+
+    /// Installs the files and writes their file descriptors in a `SCM_RIGHTS` control message.
+    ///
+    /// The files that cannot be installed due to the lack of space are dropped.
+    fn write_files(&mut self, files: &[Arc<dyn FileLike>], fd_flags: FdFlags) -> Result<()> {
+        let avail_len = self.len - self.offset;
+        let max_fds = avail_len.saturating_sub(HEADER_LEN) / size_of::<FileDesc>();
+        let num_fds = files.len().min(max_fds);
+        if num_fds < files.len() {
+            self.is_truncated = true;
+        }
+        if num_fds == 0 {
+            return Ok(());
+        }
+
+        let fds = {
+            let current = current_thread!();
+            let mut file_table = current.as_posix_thread().unwrap().file_table().lock();
+            files[..num_fds]
+                .iter()
+                .map(|file| file_table.insert(file.clone(), fd_flags))
+                .collect::<Vec<_>>()
+        };
+
+        let data_len = num_fds * size_of::<FileDesc>();
+        self.write_header(CSocketControlMessageType::SCM_RIGHTS, HEADER_LEN + data_len)?;
+        let user_space = current_userspace!();
+        for (i, fd) in fds.iter().enumerate() {
+            user_space.write_val(
+                self.addr + self.offset + HEADER_LEN + i * size_of::<FileDesc>(),
+                fd,
+            )?;
+        }
+
+        self.offset += align_up(HEADER_LEN + data_len).min(avail_len);
+        Ok(())
+    }
+
+    fn write_header(&self, type_: CSocketControlMessageType, message_len: usize) -> Result<()> {
+        let header = CControlMessageHeader {
+            cmsg_len: message_len,
+            cmsg_level: CSocketOptionLevel::SOL_SOCKET as i32,
+            cmsg_type: type_ as i32,
+        };
+        current_userspace!().write_val(self.addr + self.offset, &header)
+    }
+}
+
+/// Aligns the length of a control message like `CMSG_ALIGN` in Linux.
+const fn align_up(len: usize) -> usize {
+    len.next_multiple_of(size_of::<usize>())
+}
@@ -1,12 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod addr;
+mod control_message;
 mod options;
 mod socket;
 
 pub use addr::{
-    read_socket_addr_from_user, write_socket_addr_to_user, write_socket_addr_with_max_len,
-    CSocketAddrFamily,
+    read_socket_addr_from_user, socket_addr_into_c_bytes_and, write_socket_addr_to_user,
+    write_socket_addr_with_max_len, CSocketAddrFamily, CSocketAddrInet,
 };
 pub use options::{new_raw_socket_option, CSocketOptionLevel};
 pub use socket::{CUserMsgHdr, Protocol, SockFlags, SockType, SOCK_TYPE_MASK};
@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::RawSocketOption;
+use crate::{
+    impl_raw_sock_option_set_only, impl_raw_socket_option,
+    net::socket::ip::{
+        datagram::options::{AddMembership, DropMembership, MulticastLoop, MulticastTtl},
+        options::Hdrincl,
+    },
+    prelude::*,
+    util::net::options::SocketOption,
+};
+
+/// Sock options for IPv4 sockets.
+///
+/// The raw definition is from https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/in.h#L94
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, TryFromInt)]
+#[allow(non_camel_case_types)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum CIpOptionName {
+    HDRINCL = 3,          /* The IP header is included in the data of raw sockets */
+    MULTICAST_TTL = 33,   /* The TTL of outgoing multicast packets */
+    MULTICAST_LOOP = 34,  /* Whether outgoing multicast packets are looped back */
+    ADD_MEMBERSHIP = 35,  /* Join a multicast group */
+    DROP_MEMBERSHIP = 36, /* Leave a multicast group */
+}
+
+pub fn new_ip_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
+    let name = CIpOptionName::try_from(name).map_err(|_| Errno::ENOPROTOOPT)?;
+    match name {
+        CIpOptionName::HDRINCL => Ok(Box::new(Hdrincl::new())),
+        CIpOptionName::MULTICAST_TTL => Ok(Box::new(MulticastTtl::new())),
+        CIpOptionName::MULTICAST_LOOP => Ok(Box::new(MulticastLoop::new())),
+        CIpOptionName::ADD_MEMBERSHIP => Ok(Box::new(AddMembership::new())),
+        CIpOptionName::DROP_MEMBERSHIP => Ok(Box::new(DropMembership::new())),
+    }
+}
+
+impl_raw_socket_option!(Hdrincl);
+impl_raw_socket_option!(MulticastTtl);
+impl_raw_socket_option!(MulticastLoop);
+impl_raw_sock_option_set_only!(AddMembership);
+impl_raw_sock_option_set_only!(DropMembership);
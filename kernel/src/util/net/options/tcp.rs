@@ -2,8 +2,10 @@
 
 use super::RawSocketOption;
 use crate::{
-    impl_raw_socket_option,
-    net::socket::ip::stream::options::{Congestion, KeepIdle, MaxSegment, NoDelay, WindowClamp},
+    impl_raw_sock_option_get_only, impl_raw_socket_option,
+    net::socket::ip::stream::options::{
+        Congestion, Info, KeepIdle, MaxSegment, NoDelay, WindowClamp,
+    },
     prelude::*,
     util::net::options::SocketOption,
 };
@@ -22,6 +24,7 @@ pub enum CTcpOptionName {
     KEEPIDLE = 4,      /* Start keeplives after this period */
     KEEPALIVE = 5,     /* Interval between keepalives */
     WINDOW_CLAMP = 10, /* Bound advertised window */
+    INFO = 11,         /* Information about this connection. */
     CONGESTION = 13,   /* Congestion control algorithm */
 }
 
@@ -32,6 +35,7 @@ pub fn new_tcp_option(name: i32) -> Result<Box<dyn RawSocketOption>> {
         CTcpOptionName::MAXSEG => Ok(Box::new(MaxSegment::new())),
         CTcpOptionName::KEEPIDLE => Ok(Box::new(KeepIdle::new())),
         CTcpOptionName::WINDOW_CLAMP => Ok(Box::new(WindowClamp::new())),
+        CTcpOptionName::INFO => Ok(Box::new(Info::new())),
         CTcpOptionName::CONGESTION => Ok(Box::new(Congestion::new())),
         _ => return_errno_with_message!(Errno::ENOPROTOOPT, "unsupported tcp-level option"),
     }
@@ -41,4 +45,5 @@ impl_raw_socket_option!(NoDelay);
 impl_raw_socket_option!(MaxSegment);
 impl_raw_socket_option!(KeepIdle);
 impl_raw_socket_option!(WindowClamp);
+impl_raw_sock_option_get_only!(Info);
 impl_raw_socket_option!(Congestion);
@@ -2,10 +2,23 @@
 
 use core::time::Duration;
 
+use aster_bigtcp::{
+    socket::{CongestionState, TcpState},
+    wire::Ipv4Address,
+};
+
 use crate::{
     current_userspace,
-    net::socket::{ip::stream::CongestionControl, LingerOption},
+    net::socket::{
+        ip::{
+            datagram::IpMembership,
+            stream::{CongestionControl, TcpInfo},
+        },
+        unix::UnixCredentials,
+        LingerOption,
+    },
     prelude::*,
+    util::net::control_message::CUserCred,
 };
 
 /// Create an object by reading its C counterpart from the user space.
@@ -94,6 +107,37 @@ impl WriteToUser for bool {
     }
 }
 
+/// Reads an `int`, or an `unsigned char` if the buffer is too short.
+///
+/// Like Linux, many IP-level options (e.g., `IP_MULTICAST_TTL`) accept either of the two types.
+impl ReadFromUser for i32 {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) >= core::mem::size_of::<i32>() {
+            return current_userspace!().read_val::<i32>(addr);
+        }
+        if max_len == 0 {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        let val = current_userspace!().read_val::<u8>(addr)?;
+
+        Ok(val as i32)
+    }
+}
+
+impl WriteToUser for i32 {
+    fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
+        let write_len = core::mem::size_of::<i32>();
+
+        if (max_len as usize) < write_len {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        current_userspace!().write_val(addr, self)?;
+        Ok(write_len)
+    }
+}
+
 impl WriteToUser for Option<Error> {
     fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
         let write_len = core::mem::size_of::<i32>();
@@ -138,26 +182,96 @@ impl WriteToUser for LingerOption {
     }
 }
 
+/// The maximum length of the name of a congestion control algorithm, including the null byte.
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/net/tcp.h#L1060>.
+const TCP_CA_NAME_MAX: usize = 16;
+
 impl ReadFromUser for CongestionControl {
     fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
-        let mut bytes = vec![0; max_len as usize];
-        current_userspace!().read_bytes(addr, &mut VmWriter::from(bytes.as_mut_slice()))?;
-        let name = String::from_utf8(bytes).unwrap();
-        CongestionControl::new(&name)
+        if max_len == 0 {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        let mut bytes = [0u8; TCP_CA_NAME_MAX - 1];
+        let bytes = &mut bytes[..(max_len as usize).min(TCP_CA_NAME_MAX - 1)];
+        current_userspace!().read_bytes(addr, &mut VmWriter::from(&mut *bytes))?;
+
+        // Like Linux, the name ends at the first null byte, if any.
+        let len = bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(bytes.len());
+        core::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(CongestionControl::from_name)
+            .ok_or_else(|| {
+                Error::with_message(
+                    Errno::ENOENT,
+                    "the congestion control algorithm does not exist",
+                )
+            })
     }
 }
 
+/// Writes the name padded with null bytes, which is truncated if the buffer is too small.
 impl WriteToUser for CongestionControl {
     fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
-        let name = self.name().as_bytes();
+        let mut name = [0u8; TCP_CA_NAME_MAX];
+        name[..self.name().len()].copy_from_slice(self.name().as_bytes());
 
-        let write_len = name.len();
-        if write_len > max_len as usize {
+        let write_len = TCP_CA_NAME_MAX.min(max_len as usize);
+        current_userspace!().write_bytes(addr, &mut VmReader::from(&name[..write_len]))?;
+
+        Ok(write_len)
+    }
+}
+
+impl WriteToUser for TcpInfo {
+    fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
+        let tcp_info = CTcpInfo::from(*self);
+        let write_len = core::mem::size_of::<CTcpInfo>().min(max_len as usize);
+
+        // Like Linux, the information is truncated if the buffer is too small.
+        current_userspace!()
+            .write_bytes(addr, &mut VmReader::from(&tcp_info.as_bytes()[..write_len]))?;
+        Ok(write_len)
+    }
+}
+
+/// Reads a `struct ip_mreqn`, or a `struct ip_mreq` if the buffer is too short.
+impl ReadFromUser for IpMembership {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) >= core::mem::size_of::<CIpMreqn>() {
+            let mreqn = current_userspace!().read_val::<CIpMreqn>(addr)?;
+            return Ok(IpMembership {
+                group: Ipv4Address::from(mreqn.imr_multiaddr),
+                local_addr: Ipv4Address::from(mreqn.imr_address),
+                iface_index: mreqn.imr_ifindex as u32,
+            });
+        }
+
+        if (max_len as usize) < core::mem::size_of::<CIpMreq>() {
             return_errno_with_message!(Errno::EINVAL, "max_len is too short");
         }
 
-        current_userspace!().write_bytes(addr, &mut VmReader::from(name))?;
+        let mreq = current_userspace!().read_val::<CIpMreq>(addr)?;
+        Ok(IpMembership {
+            group: Ipv4Address::from(mreq.imr_multiaddr),
+            local_addr: Ipv4Address::from(mreq.imr_interface),
+            iface_index: 0,
+        })
+    }
+}
+
+impl WriteToUser for UnixCredentials {
+    fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
+        let ucred = CUserCred::from(*self);
+        let write_len = core::mem::size_of::<CUserCred>().min(max_len as usize);
 
+        // Like Linux, the credentials are truncated if the buffer is too small.
+        current_userspace!()
+            .write_bytes(addr, &mut VmReader::from(&ucred.as_bytes()[..write_len]))?;
         Ok(write_len)
     }
 }
@@ -169,6 +283,27 @@ struct CLinger {
     l_linger: i32, // how many seconds to linger for
 }
 
+/// The request to join or leave a multicast group, with the iface specified by its index.
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/in.h#L178>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIpMreqn {
+    imr_multiaddr: [u8; 4], // IP multicast address of group
+    imr_address: [u8; 4],   // local IP address of interface
+    imr_ifindex: i32,       // Interface index
+}
+
+/// The request to join or leave a multicast group.
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/in.h#L171>.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod)]
+struct CIpMreq {
+    imr_multiaddr: [u8; 4], // IP multicast address of group
+    imr_interface: [u8; 4], // local IP address of interface
+}
+
 impl From<LingerOption> for CLinger {
     fn from(value: LingerOption) -> Self {
         let l_onoff = if value.is_on() { 1 } else { 0 };
@@ -186,3 +321,149 @@ impl From<CLinger> for LingerOption {
         LingerOption::new(is_on, timeout)
     }
 }
+
+/// The information of a TCP socket.
+///
+/// See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/tcp.h#L214>.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Pod)]
+struct CTcpInfo {
+    tcpi_state: u8,
+    tcpi_ca_state: u8,
+    tcpi_retransmits: u8,
+    tcpi_probes: u8,
+    tcpi_backoff: u8,
+    tcpi_options: u8,
+    tcpi_wscale: u8, // tcpi_snd_wscale : 4, tcpi_rcv_wscale : 4
+    tcpi_flags: u8,  // tcpi_delivery_rate_app_limited : 1, tcpi_fastopen_client_fail : 2
+
+    tcpi_rto: u32,
+    tcpi_ato: u32,
+    tcpi_snd_mss: u32,
+    tcpi_rcv_mss: u32,
+
+    tcpi_unacked: u32,
+    tcpi_sacked: u32,
+    tcpi_lost: u32,
+    tcpi_retrans: u32,
+    tcpi_fackets: u32,
+
+    // Times
+    tcpi_last_data_sent: u32,
+    tcpi_last_ack_sent: u32,
+    tcpi_last_data_recv: u32,
+    tcpi_last_ack_recv: u32,
+
+    // Metrics
+    tcpi_pmtu: u32,
+    tcpi_rcv_ssthresh: u32,
+    tcpi_rtt: u32,
+    tcpi_rttvar: u32,
+    tcpi_snd_ssthresh: u32,
+    tcpi_snd_cwnd: u32,
+    tcpi_advmss: u32,
+    tcpi_reordering: u32,
+
+    tcpi_rcv_rtt: u32,
+    tcpi_rcv_space: u32,
+
+    tcpi_total_retrans: u32,
+
+    tcpi_pacing_rate: u64,
+    tcpi_max_pacing_rate: u64,
+    tcpi_bytes_acked: u64,
+    tcpi_bytes_received: u64,
+    tcpi_segs_out: u32,
+    tcpi_segs_in: u32,
+
+    tcpi_notsent_bytes: u32,
+    tcpi_min_rtt: u32,
+    tcpi_data_segs_in: u32,
+    tcpi_data_segs_out: u32,
+
+    tcpi_delivery_rate: u64,
+
+    tcpi_busy_time: u64,
+    tcpi_rwnd_limited: u64,
+    tcpi_sndbuf_limited: u64,
+
+    tcpi_delivered: u32,
+    tcpi_delivered_ce: u32,
+
+    tcpi_bytes_sent: u64,
+    tcpi_bytes_retrans: u64,
+    tcpi_dsack_dups: u32,
+    tcpi_reord_seen: u32,
+
+    tcpi_rcv_ooopack: u32,
+
+    tcpi_snd_wnd: u32,
+}
+
+impl From<TcpInfo> for CTcpInfo {
+    fn from(value: TcpInfo) -> Self {
+        // The TCP states in Linux.
+        //
+        // See <https://elixir.bootlin.com/linux/v6.0.9/source/include/net/tcp_states.h#L12>.
+        let tcpi_state = match value.state {
+            TcpState::Established => 1,
+            TcpState::SynSent => 2,
+            TcpState::SynReceived => 3,
+            TcpState::FinWait1 => 4,
+            TcpState::FinWait2 => 5,
+            TcpState::TimeWait => 6,
+            TcpState::Closed => 7,
+            TcpState::CloseWait => 8,
+            TcpState::LastAck => 9,
+            TcpState::Listen => 10,
+            TcpState::Closing => 11,
+        };
+
+        let Some(congestion) = value.congestion else {
+            return Self {
+                tcpi_state,
+                ..Default::default()
+            };
+        };
+
+        // The congestion control states in Linux.
+        //
+        // See <https://elixir.bootlin.com/linux/v6.0.9/source/include/uapi/linux/tcp.h#L179>.
+        let tcpi_ca_state = match congestion.state {
+            CongestionState::Open => 0,
+            CongestionState::Disorder => 1,
+            CongestionState::Recovery => 3,
+            CongestionState::Loss => 4,
+        };
+
+        /// The slow start threshold that is reported before the first reduction.
+        const TCP_INFINITE_SSTHRESH: u32 = 0x7fffffff;
+
+        let mss = congestion.mss;
+        let to_micros = |duration: aster_bigtcp::time::Duration| {
+            duration.total_micros().min(u32::MAX as u64) as u32
+        };
+
+        Self {
+            tcpi_state,
+            tcpi_ca_state,
+            tcpi_retransmits: congestion.retransmits.min(u8::MAX as u32) as u8,
+            tcpi_wscale: congestion.remote_win_shift & 0xf,
+            tcpi_rto: to_micros(congestion.rto),
+            tcpi_snd_mss: mss as u32,
+            tcpi_unacked: congestion.unacked.div_ceil(mss) as u32,
+            tcpi_rtt: congestion.srtt.map_or(0, to_micros),
+            tcpi_rttvar: to_micros(congestion.rttvar),
+            tcpi_snd_ssthresh: if congestion.ssthresh == usize::MAX {
+                TCP_INFINITE_SSTHRESH
+            } else {
+                (congestion.ssthresh / mss) as u32
+            },
+            tcpi_snd_cwnd: (congestion.cwnd / mss) as u32,
+            tcpi_total_retrans: congestion.total_retrans,
+            tcpi_bytes_acked: congestion.bytes_acked,
+            tcpi_min_rtt: congestion.min_rtt.map_or(u32::MAX, to_micros),
+            ..Default::default()
+        }
+    }
+}
@@ -237,11 +237,22 @@ impl RingBuffer<u8> {
     ///
     /// Returns the number of bytes read.
     pub fn read_fallible(&mut self, writer: &mut dyn MultiWrite) -> Result<usize> {
+        self.read_fallible_with_max_len(writer, usize::MAX)
+    }
+
+    /// Reads at most `max_len` bytes from the `RingBuffer` to the `writer`.
+    ///
+    /// Returns the number of bytes read.
+    pub fn read_fallible_with_max_len(
+        &mut self,
+        writer: &mut dyn MultiWrite,
+        max_len: usize,
+    ) -> Result<usize> {
         let mut consumer = Consumer {
             rb: self,
             phantom: PhantomData,
         };
-        consumer.read_fallible(writer)
+        consumer.read_fallible_with_max_len(writer, max_len)
     }
 }
 
@@ -403,12 +414,23 @@ impl<R: Deref<Target = RingBuffer<u8>>> Consumer<u8, R> {
     ///
     /// Returns the number of bytes read.
     pub fn read_fallible(&mut self, writer: &mut dyn MultiWrite) -> Result<usize> {
+        self.read_fallible_with_max_len(writer, usize::MAX)
+    }
+
+    /// Reads at most `max_len` bytes from the `RingBuffer` to the `writer`.
+    ///
+    /// Returns the number of bytes read.
+    pub fn read_fallible_with_max_len(
+        &mut self,
+        writer: &mut dyn MultiWrite,
+        max_len: usize,
+    ) -> Result<usize> {
         let rb = &self.rb;
         let len = rb.len();
         if len == 0 {
             return Ok(0);
         }
-        let read_len = writer.sum_lens().min(len);
+        let read_len = writer.sum_lens().min(len).min(max_len);
 
         let head = rb.head();
         let read_len = if head + read_len > rb.capacity {
@@ -13,7 +13,10 @@ use align_ext::AlignExt;
 use aster_rights::Rights;
 use ostd::{
     cpu::CpuExceptionInfo,
-    mm::{tlb::TlbFlushOp, PageFlags, PageProperty, VmSpace, MAX_USERSPACE_VADDR},
+    mm::{
+        tlb::TlbFlushOp, vm_space::VmItem, Frame, PageFlags, PageProperty, VmIo, VmSpace,
+        MAX_USERSPACE_VADDR,
+    },
 };
 
 use self::{
@@ -99,11 +102,105 @@ impl<R> Vmar<R> {
     ///
     /// If the new mapping size is smaller than the original mapping size, the
     /// extra part will be unmapped. If the new mapping is larger than the old
-    /// mapping and the extra part overlaps with existing mapping, resizing
-    /// will fail and return `Err`.
+    /// mapping, the original range must end at the end of a [`VmMapping`],
+    /// which will be enlarged. If the extra part overlaps with existing
+    /// mapping, resizing will fail and return `Err`.
     pub fn resize_mapping(&self, map_addr: Vaddr, old_size: usize, new_size: usize) -> Result<()> {
         self.0.resize_mapping(map_addr, old_size, new_size)
     }
+
+    /// Moves the original mapping to a new range and resizes it.
+    ///
+    /// The range of the mapping goes from `old_addr..old_addr + old_size` to
+    /// `new_addr..new_addr + new_size`, where `new_size` must not be smaller
+    /// than `old_size`. The original range must lie in a single
+    /// [`VmMapping`]. If `new_addr` is `None`, a free region will be chosen.
+    /// Otherwise, existing mappings in the new range will be unmapped.
+    ///
+    /// The pages that have been mapped are moved to the new range without
+    /// being copied. If `keeps_old` is true, the original range remains
+    /// mapped, but without any pages, as `MREMAP_DONTUNMAP` requires.
+    ///
+    /// On success, the start address of the new range is returned.
+    pub fn remap(
+        &self,
+        old_addr: Vaddr,
+        old_size: usize,
+        new_addr: Option<Vaddr>,
+        new_size: usize,
+        keeps_old: bool,
+    ) -> Result<Vaddr> {
+        self.0
+            .remap(old_addr, old_size, new_addr, new_size, keeps_old)
+    }
+
+    /// Reads the memory at `addr` from outside the address space.
+    ///
+    /// The pages are committed if needed, regardless of the permissions of
+    /// the mappings. This is how a tracer reads the memory of its tracee.
+    pub fn read_remote(&self, addr: Vaddr, buf: &mut [u8]) -> Result<()> {
+        self.0
+            .access_remote(addr, buf.len(), false, false, |frame, offset, range| {
+                frame.read_bytes(offset, &mut buf[range])
+            })
+    }
+
+    /// Writes the memory at `addr` from outside the address space.
+    ///
+    /// The pages are committed if needed, regardless of the permissions of
+    /// the mappings. Private pages are copied before being written, so that
+    /// a tracer can set breakpoints in read-only code of its tracee.
+    pub fn write_remote(&self, addr: Vaddr, buf: &[u8]) -> Result<()> {
+        self.0
+            .access_remote(addr, buf.len(), true, false, |frame, offset, range| {
+                frame.write_bytes(offset, &buf[range])
+            })
+    }
+
+    /// Reads the memory at `addr` from outside the address space on behalf
+    /// of its user.
+    ///
+    /// Unlike [`Self::read_remote`], the permissions of the mappings are
+    /// respected, so this method fails with `EFAULT` if the memory is not
+    /// readable by the user. This is how kernel workers access the user
+    /// buffers of asynchronous I/O.
+    pub fn read_for_user(&self, addr: Vaddr, buf: &mut [u8]) -> Result<()> {
+        self.0
+            .access_remote(addr, buf.len(), false, true, |frame, offset, range| {
+                frame.read_bytes(offset, &mut buf[range])
+            })
+    }
+
+    /// Writes the memory at `addr` from outside the address space on behalf
+    /// of its user.
+    ///
+    /// Unlike [`Self::write_remote`], the permissions of the mappings are
+    /// respected, so this method fails with `EFAULT` if the memory is not
+    /// writable by the user.
+    pub fn write_for_user(&self, addr: Vaddr, buf: &[u8]) -> Result<()> {
+        self.0
+            .access_remote(addr, buf.len(), true, true, |frame, offset, range| {
+                frame.write_bytes(offset, &buf[range])
+            })
+    }
+
+    /// Returns the offset in `vmo` that `addr` is mapped to.
+    ///
+    /// If `addr` is not mapped, or is not mapped to `vmo`, this method will
+    /// return `None`.
+    pub fn vmo_offset_of<R2>(&self, addr: Vaddr, vmo: &Vmo<R2>) -> Option<usize> {
+        let inner = self.0.inner.read();
+        inner.vm_mappings.find_one(&addr)?.vmo_offset_of(addr, vmo)
+    }
+
+    /// Removes the mappings of `vmo` in the range.
+    ///
+    /// Mappings may fall partially within the range; only the overlapped
+    /// portions of the mappings are unmapped. Unlike `remove_mapping`, the
+    /// mappings in the range that are not backed by `vmo` are left untouched.
+    pub fn remove_vmo_mappings<R2>(&self, range: Range<usize>, vmo: &Vmo<R2>) -> Result<()> {
+        self.0.remove_vmo_mappings(range, vmo)
+    }
 }
 
 pub(super) struct Vmar_ {
@@ -129,6 +226,19 @@ impl VmarInner {
         }
     }
 
+    /// Checks whether the range lies in a single mapping.
+    ///
+    /// Returns the start address of the mapping if so.
+    fn check_lies_in_single_mapping(&self, range: &Range<Vaddr>) -> Result<Vaddr> {
+        match self.vm_mappings.find_one(&range.start) {
+            Some(vm_mapping) if range.end <= vm_mapping.map_end() => Ok(vm_mapping.map_to_addr()),
+            _ => return_errno_with_message!(
+                Errno::EFAULT,
+                "the range does not lie in a single mapping"
+            ),
+        }
+    }
+
     /// Allocates a free region for mapping with a specific offset and size.
     ///
     /// If the provided range is already occupied, return an error.
@@ -279,7 +389,11 @@ impl Vmar_ {
             if perms == vm_mapping_perms {
                 continue;
             }
-            let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            let mut vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            if let Err(err) = vm_mapping.prepare_protect(perms) {
+                inner.vm_mappings.insert(vm_mapping);
+                return Err(err);
+            }
             let vm_mapping_range = vm_mapping.range();
             let intersected_range = get_intersected_range(&range, &vm_mapping_range);
 
@@ -318,6 +432,67 @@ impl Vmar_ {
         return_errno_with_message!(Errno::EACCES, "page fault addr is not in current vmar");
     }
 
+    /// Returns the frame of the page at `address` for accessing it from
+    /// outside the address space.
+    fn get_frame_for_remote_access(
+        &self,
+        address: Vaddr,
+        is_write: bool,
+        checks_perms: bool,
+    ) -> Result<Frame> {
+        let inner = self.inner.read();
+
+        let Some(vm_mapping) = inner.vm_mappings.find_one(&address) else {
+            return_errno_with_message!(Errno::EFAULT, "the address is not mapped");
+        };
+        if checks_perms {
+            let required_perms = if is_write {
+                VmPerms::WRITE
+            } else {
+                VmPerms::READ
+            };
+            if !vm_mapping.perms().contains(required_perms) {
+                return_errno_with_message!(Errno::EFAULT, "the mapping is not accessible");
+            }
+        }
+        vm_mapping.get_frame_for_remote_access(&self.vm_space, address, is_write)
+    }
+
+    /// Accesses `len` bytes at `addr` page by page from outside the address
+    /// space.
+    ///
+    /// For each page, `access` is called with its frame, the offset in the
+    /// frame, and the range in the buffer. If `checks_perms` is true, the
+    /// access fails if the mappings do not permit it.
+    fn access_remote<F>(
+        &self,
+        addr: Vaddr,
+        len: usize,
+        is_write: bool,
+        checks_perms: bool,
+        mut access: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&Frame, usize, Range<usize>) -> ostd::Result<()>,
+    {
+        if addr.checked_add(len).is_none() {
+            return_errno_with_message!(Errno::EFAULT, "the address range overflows");
+        }
+
+        let mut copied = 0;
+        while copied < len {
+            let page_offset = (addr + copied) % PAGE_SIZE;
+            let copy_len = (PAGE_SIZE - page_offset).min(len - copied);
+
+            let frame = self.get_frame_for_remote_access(addr + copied, is_write, checks_perms)?;
+            access(&frame, page_offset, copied..copied + copy_len)?;
+
+            copied += copy_len;
+        }
+
+        Ok(())
+    }
+
     /// Clears all content of the root VMAR.
     fn clear_root_vmar(&self) -> Result<()> {
         self.vm_space.clear().unwrap();
@@ -332,6 +507,23 @@ impl Vmar_ {
         Ok(())
     }
 
+    fn remove_vmo_mappings<R>(&self, range: Range<usize>, vmo: &Vmo<R>) -> Result<()> {
+        let mut inner = self.inner.write();
+
+        let mut ranges_to_remove = Vec::new();
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            let map_to_addr = vm_mapping.map_to_addr();
+            if vm_mapping.vmo_offset_of(map_to_addr, vmo).is_some() {
+                ranges_to_remove.push(get_intersected_range(&range, &vm_mapping.range()));
+            }
+        }
+
+        for range in ranges_to_remove {
+            inner.alloc_free_region_exact_truncate(&self.vm_space, range.start, range.len())?;
+        }
+        Ok(())
+    }
+
     // Split and unmap the found mapping if resize smaller.
     // Enlarge the last mapping if resize larger.
     fn resize_mapping(&self, map_addr: Vaddr, old_size: usize, new_size: usize) -> Result<()> {
@@ -356,17 +548,135 @@ impl Vmar_ {
         }
 
         let mut inner = self.inner.write();
-        let last_mapping = inner.vm_mappings.find_one(&(old_map_end - 1)).unwrap();
+        let Some(last_mapping) = inner.vm_mappings.find_one(&(old_map_end - 1)) else {
+            return_errno_with_message!(Errno::EFAULT, "the range to resize is not mapped");
+        };
+        if last_mapping.map_end() != old_map_end {
+            return_errno_with_message!(
+                Errno::ENOMEM,
+                "the range to resize does not end at the end of a mapping"
+            );
+        }
+        if new_map_end > ROOT_VMAR_CAP_ADDR
+            || inner
+                .vm_mappings
+                .find(&(old_map_end..new_map_end))
+                .next()
+                .is_some()
+        {
+            return_errno_with_message!(Errno::ENOMEM, "the range to enlarge is occupied");
+        }
+
         let last_mapping_addr = last_mapping.map_to_addr();
         let last_mapping = inner.vm_mappings.remove(&last_mapping_addr).unwrap();
-
-        let extra_mapping_start = last_mapping.map_end();
-        inner.alloc_free_region_exact(extra_mapping_start, new_map_end - extra_mapping_start)?;
-        let last_mapping = last_mapping.enlarge(new_map_end - extra_mapping_start);
+        let last_mapping = last_mapping.enlarge(new_map_end - old_map_end);
         inner.vm_mappings.insert(last_mapping);
         Ok(())
     }
 
+    fn remap(
+        &self,
+        old_addr: Vaddr,
+        old_size: usize,
+        new_addr: Option<Vaddr>,
+        new_size: usize,
+        keeps_old: bool,
+    ) -> Result<Vaddr> {
+        debug_assert!(old_addr % PAGE_SIZE == 0);
+        debug_assert!(old_size % PAGE_SIZE == 0);
+        debug_assert!(new_size % PAGE_SIZE == 0);
+        debug_assert!(new_size >= old_size);
+
+        if old_size == 0 {
+            return_errno_with_message!(Errno::EINVAL, "can not remap a mapping of 0 size");
+        }
+
+        let mut inner = self.inner.write();
+        let old_range = old_addr..old_addr + old_size;
+        inner.check_lies_in_single_mapping(&old_range)?;
+
+        // Allocates the new range.
+        let new_range = if let Some(new_addr) = new_addr {
+            debug_assert!(new_addr % PAGE_SIZE == 0);
+            let new_range = new_addr
+                ..new_addr.checked_add(new_size).ok_or(Error::with_message(
+                    Errno::EINVAL,
+                    "the new range overflows",
+                ))?;
+            if !is_userspace_vaddr(new_addr) || new_range.end > ROOT_VMAR_CAP_ADDR {
+                return_errno_with_message!(Errno::EINVAL, "the new range is not in user space");
+            }
+            if is_intersected(&old_range, &new_range) {
+                return_errno_with_message!(Errno::EINVAL, "the new range overlaps the old one");
+            }
+            inner.alloc_free_region_exact_truncate(&self.vm_space, new_addr, new_size)?
+        } else {
+            inner.alloc_free_region(new_size, PAGE_SIZE)?
+        };
+
+        // Takes the original range out of its mapping. The mapping may have
+        // been split when allocating the new range, so it is looked up again.
+        let old_mapping = {
+            let vm_mapping_addr = inner.check_lies_in_single_mapping(&old_range)?;
+            let vm_mapping = inner.vm_mappings.remove(&vm_mapping_addr).unwrap();
+            let (left, taken, right) = vm_mapping.split_range(&old_range)?;
+            if let Some(left) = left {
+                inner.vm_mappings.insert(left);
+            }
+            if let Some(right) = right {
+                inner.vm_mappings.insert(right);
+            }
+            taken
+        };
+        if keeps_old {
+            inner.vm_mappings.insert(old_mapping.new_fork()?);
+        }
+
+        self.move_pages(&old_range, new_range.start)?;
+
+        let new_mapping = old_mapping.relocate(new_range.start);
+        let new_mapping = if new_size > old_size {
+            new_mapping.enlarge(new_size - old_size)
+        } else {
+            new_mapping
+        };
+        inner.vm_mappings.insert(new_mapping);
+
+        Ok(new_range.start)
+    }
+
+    /// Moves the pages mapped in `old_range` to the range starting at
+    /// `new_addr` in the VM space.
+    ///
+    /// The frames are remapped at the new addresses with their properties
+    /// unchanged, so no page is copied. The new range must not be mapped.
+    fn move_pages(&self, old_range: &Range<Vaddr>, new_addr: Vaddr) -> Result<()> {
+        let new_end = new_addr + old_range.len();
+        let range = old_range.start.min(new_addr)..old_range.end.max(new_end);
+        let mut cursor = self.vm_space.cursor_mut(&range)?;
+
+        let mut offset = 0;
+        while offset < old_range.len() {
+            cursor.jump(old_range.start + offset)?;
+            match cursor.query()? {
+                VmItem::Mapped { frame, prop, .. } => {
+                    cursor.jump(new_addr + offset)?;
+                    cursor.map(frame, prop);
+                    offset += PAGE_SIZE;
+                }
+                VmItem::NotMapped { va, len } => {
+                    offset = va.align_down(len) + len - old_range.start;
+                }
+            }
+        }
+
+        // Unmaps the original range at once to flush the TLB only once.
+        cursor.jump(old_range.start)?;
+        cursor.unmap(old_range.len());
+
+        Ok(())
+    }
+
     /// Returns the attached `VmSpace`.
     fn vm_space(&self) -> &Arc<VmSpace> {
         &self.vm_space
@@ -587,6 +897,12 @@ impl<R1, R2> VmarMapOptions<R1, R2> {
             handle_page_faults_around,
         } = self;
 
+        // Checks whether the VMO can be mapped before modifying the VMAR.
+        let is_writable_shared = is_shared && perms.contains(VmPerms::WRITE);
+        let vmo = vmo
+            .map(|vmo| MappedVmo::new(vmo.to_dyn(), vmo_offset..vmo_limit, is_writable_shared))
+            .transpose()?;
+
         // Allocates a free region.
         trace!("allocate free region, map_size = 0x{:x}, offset = {:x?}, align = 0x{:x}, can_overwrite = {}", map_size, offset, align, can_overwrite);
         let mut inner = parent.0.inner.write();
@@ -607,7 +923,6 @@ impl<R1, R2> VmarMapOptions<R1, R2> {
         };
 
         // Build the mapping.
-        let vmo = vmo.map(|vmo| MappedVmo::new(vmo.to_dyn(), vmo_offset..vmo_limit));
         let vm_mapping = VmMapping::new(
             NonZeroUsize::new(map_size).unwrap(),
             map_to_addr,
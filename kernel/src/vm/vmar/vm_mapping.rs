@@ -216,6 +216,55 @@ impl VmMapping {
         Ok(())
     }
 
+    /// Returns the frame of the page at `address`, committing it if needed.
+    ///
+    /// This is used to access the mapping from outside its address space,
+    /// e.g., by `ptrace`, so the permissions of the mapping are not checked.
+    /// If `is_write` is true, a private page is copied before being returned
+    /// even if the mapping is not writable, so that the writes will never be
+    /// visible to other address spaces or to the underlying file.
+    pub fn get_frame_for_remote_access(
+        &self,
+        vm_space: &VmSpace,
+        address: Vaddr,
+        is_write: bool,
+    ) -> Result<Frame> {
+        if is_write && self.is_shared && !self.perms.contains(VmPerms::WRITE) {
+            return_errno_with_message!(Errno::EACCES, "the shared mapping is not writable");
+        }
+
+        let page_aligned_addr = address.align_down(PAGE_SIZE);
+        let mut cursor =
+            vm_space.cursor_mut(&(page_aligned_addr..page_aligned_addr + PAGE_SIZE))?;
+
+        match cursor.query().unwrap() {
+            VmItem::Mapped { frame, prop, .. } => {
+                if !is_write || self.is_shared || prop.flags.contains(PageFlags::W) {
+                    return Ok(frame);
+                }
+
+                // The page may be shared with other address spaces or with the
+                // page cache. Perform COW but keep the original permissions.
+                let new_frame = duplicate_frame(&frame)?;
+                cursor.map(new_frame.clone(), prop);
+                Ok(new_frame)
+            }
+            VmItem::NotMapped { .. } => {
+                let (frame, is_readonly) = self.prepare_page(address, is_write)?;
+
+                let mut vm_perms = self.perms;
+                if is_readonly {
+                    vm_perms -= VmPerms::WRITE;
+                }
+                let page_flags = PageFlags::from(vm_perms) | PageFlags::ACCESSED;
+                let map_prop = PageProperty::new(page_flags, CachePolicy::Writeback);
+
+                cursor.map(frame.clone(), map_prop);
+                Ok(frame)
+            }
+        }
+    }
+
     fn prepare_page(&self, page_fault_addr: Vaddr, write: bool) -> Result<(Frame, bool)> {
         let mut is_readonly = false;
         let Some(vmo) = &self.vmo else {
@@ -301,6 +350,17 @@ impl VmMapping {
         }
     }
 
+    /// Relocates the mapping to start at `map_to_addr`.
+    ///
+    /// This only changes the range of the mapping. The pages that have been
+    /// mapped should be moved to the new range in the VM space by the caller.
+    pub fn relocate(self, map_to_addr: Vaddr) -> Self {
+        Self {
+            map_to_addr,
+            ..self
+        }
+    }
+
     /// Splits the mapping at the specified address.
     ///
     /// The address must be within the mapping and page-aligned. The address
@@ -314,11 +374,14 @@ impl VmMapping {
         if let Some(vmo) = self.vmo {
             let at_offset = vmo.range.start + at - self.map_to_addr;
 
-            let l_range = vmo.range.start..at_offset;
+            // The left part keeps the whole accessible range, so that it can
+            // be enlarged later (e.g., by `mremap`). Accesses beyond the end
+            // of the mapping are not possible anyway.
+            let l_range = vmo.range.clone();
             let r_range = at_offset..vmo.range.end;
 
-            l_vmo = Some(MappedVmo::new(vmo.vmo.dup()?, l_range));
-            r_vmo = Some(MappedVmo::new(vmo.vmo.dup()?, r_range));
+            l_vmo = Some(vmo.dup_with_range(l_range)?);
+            r_vmo = Some(vmo.dup_with_range(r_range)?);
         }
 
         let left_size = at - self.map_to_addr;
@@ -389,6 +452,33 @@ impl VmMapping {
         Ok(())
     }
 
+    /// Returns the offset in the VMO that `addr` is mapped to, if the mapping
+    /// is backed by `vmo`.
+    pub(super) fn vmo_offset_of<R>(&self, addr: Vaddr, vmo: &Vmo<R>) -> Option<usize> {
+        debug_assert!(self.range().contains(&addr));
+        let mapped_vmo = self.vmo.as_ref()?;
+        if !mapped_vmo.vmo.is_same(vmo) {
+            return None;
+        }
+        Some(mapped_vmo.range.start + (addr - self.map_to_addr))
+    }
+
+    /// Checks whether the perms of the mapping can be changed to `perms`.
+    ///
+    /// If a shared mapping becomes writable, it is recorded as a writable
+    /// shared mapping of the VMO. This fails with `EACCES` if the VMO denies
+    /// writable shared mappings.
+    pub(super) fn prepare_protect(&mut self, perms: VmPerms) -> Result<()> {
+        if !self.is_shared || !perms.contains(VmPerms::WRITE) {
+            return Ok(());
+        }
+        let Some(vmo) = self.vmo.as_mut() else {
+            return Ok(());
+        };
+        vmo.set_writable_shared()
+            .map_err(|_| Error::with_message(Errno::EACCES, "the mapping cannot be writable"))
+    }
+
     /// Change the perms of the mapping.
     pub(super) fn protect(self, vm_space: &VmSpace, perms: VmPerms) -> Self {
         let range = self.range();
@@ -416,12 +506,38 @@ pub(super) struct MappedVmo {
     vmo: Vmo,
     /// Represents the accessible range in the VMO for mappings.
     range: Range<usize>,
+    /// Whether the mapping is a shared mapping that may write to the VMO.
+    ///
+    /// Such a mapping is recorded in the [`WritableMappingStatus`] of the VMO.
+    ///
+    /// [`WritableMappingStatus`]: crate::vm::vmo::WritableMappingStatus
+    is_writable_shared: bool,
 }
 
 impl MappedVmo {
     /// Creates a `MappedVmo` used for mapping.
-    pub(super) fn new(vmo: Vmo, range: Range<usize>) -> Self {
-        Self { vmo, range }
+    ///
+    /// If `is_writable_shared` is true, this method fails if the VMO denies
+    /// writable shared mappings.
+    pub(super) fn new(vmo: Vmo, range: Range<usize>, is_writable_shared: bool) -> Result<Self> {
+        if is_writable_shared {
+            vmo.writable_mapping_status().map()?;
+        }
+        vmo.inc_mappings();
+        Ok(Self {
+            vmo,
+            range,
+            is_writable_shared,
+        })
+    }
+
+    /// Makes the mapping a writable shared mapping, if it is not already one.
+    fn set_writable_shared(&mut self) -> Result<()> {
+        if !self.is_writable_shared {
+            self.vmo.writable_mapping_status().map()?;
+            self.is_writable_shared = true;
+        }
+        Ok(())
     }
 
     fn size(&self) -> usize {
@@ -456,9 +572,29 @@ impl MappedVmo {
 
     /// Duplicates the capability.
     pub fn dup(&self) -> Result<Self> {
+        self.dup_with_range(self.range.clone())
+    }
+
+    /// Duplicates the capability with a new accessible range.
+    fn dup_with_range(&self, range: Range<usize>) -> Result<Self> {
+        let vmo = self.vmo.dup()?;
+        if self.is_writable_shared {
+            vmo.writable_mapping_status().map_dup();
+        }
+        vmo.inc_mappings();
         Ok(Self {
-            vmo: self.vmo.dup()?,
-            range: self.range.clone(),
+            vmo,
+            range,
+            is_writable_shared: self.is_writable_shared,
         })
     }
 }
+
+impl Drop for MappedVmo {
+    fn drop(&mut self) {
+        if self.is_writable_shared {
+            self.vmo.writable_mapping_status().unmap();
+        }
+        self.vmo.dec_mappings();
+    }
+}
@@ -5,7 +5,10 @@
 
 //! Virtual Memory Objects (VMOs).
 
-use core::ops::Range;
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use align_ext::AlignExt;
 use aster_rights::Rights;
@@ -167,13 +170,99 @@ impl Pages {
 /// 1. File-backed VMO: the VMO backed by a file and resides in the `PageCache`,
 ///    which includes a pager to provide it with actual pages.
 /// 2. Anonymous VMO: the VMO without a file backup, which does not have a pager.
-#[derive(Clone)]
 pub(super) struct Vmo_ {
     pager: Option<Arc<dyn Pager>>,
     /// Flags
     flags: VmoFlags,
     /// The virtual pages where the VMO resides.
     pages: Pages,
+    /// The number of mappings of the VMO.
+    nr_mappings: AtomicUsize,
+    /// The status of the writable shared mappings of the VMO.
+    writable_mapping_status: WritableMappingStatus,
+}
+
+impl Clone for Vmo_ {
+    fn clone(&self) -> Self {
+        Self {
+            pager: self.pager.clone(),
+            flags: self.flags,
+            pages: self.pages.clone(),
+            // The new VMO is not mapped anywhere yet.
+            nr_mappings: AtomicUsize::new(0),
+            writable_mapping_status: WritableMappingStatus::default(),
+        }
+    }
+}
+
+/// The status of the writable shared mappings of a VMO.
+///
+/// Writes to a writable shared mapping go directly to the VMO. So if the
+/// content of a VMO should not be modified (e.g., the VMO is the page cache of
+/// a sealed memfd), the writable shared mappings must be denied.
+///
+/// A mapping that may write to the VMO should call [`map`] when it is created
+/// and [`unmap`] when it is destroyed.
+///
+/// [`map`]: Self::map
+/// [`unmap`]: Self::unmap
+#[derive(Debug, Default)]
+pub struct WritableMappingStatus {
+    inner: SpinLock<WritableMappingStatusInner>,
+}
+
+#[derive(Debug, Default)]
+struct WritableMappingStatusInner {
+    nr_mappings: usize,
+    is_denied: bool,
+}
+
+impl WritableMappingStatus {
+    /// Records a new writable shared mapping.
+    ///
+    /// Returns `EPERM` if the writable shared mappings are denied.
+    pub fn map(&self) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.is_denied {
+            return_errno_with_message!(Errno::EPERM, "the VMO cannot be mapped as writable");
+        }
+        inner.nr_mappings += 1;
+        Ok(())
+    }
+
+    /// Records a writable shared mapping that is duplicated from an existing one
+    /// (e.g., during `fork` or when a mapping is split).
+    ///
+    /// Unlike [`Self::map`], this never fails because the existing mappings
+    /// are not affected by [`Self::deny_new`].
+    pub fn map_dup(&self) {
+        self.inner.lock().nr_mappings += 1;
+    }
+
+    /// Removes a writable shared mapping that is recorded by [`Self::map`]
+    /// or [`Self::map_dup`].
+    pub fn unmap(&self) {
+        let mut inner = self.inner.lock();
+        debug_assert!(inner.nr_mappings > 0);
+        inner.nr_mappings -= 1;
+    }
+
+    /// Denies new writable shared mappings.
+    ///
+    /// Returns `EBUSY` if there are writable shared mappings.
+    pub fn deny(&self) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.nr_mappings > 0 {
+            return_errno_with_message!(Errno::EBUSY, "the VMO is mapped as writable");
+        }
+        inner.is_denied = true;
+        Ok(())
+    }
+
+    /// Denies new writable shared mappings, leaving the existing ones untouched.
+    pub fn deny_new(&self) {
+        self.inner.lock().is_denied = true;
+    }
 }
 
 impl Debug for Vmo_ {
@@ -447,6 +536,35 @@ impl<R> Vmo<R> {
     pub fn flags(&self) -> VmoFlags {
         self.0.flags()
     }
+
+    /// Returns the number of mappings of the VMO.
+    ///
+    /// A mapping that is split into multiple parts (e.g., by `mprotect`)
+    /// counts as multiple mappings.
+    pub fn nr_mappings(&self) -> usize {
+        self.0.nr_mappings.load(Ordering::Relaxed)
+    }
+
+    /// Records that a new mapping of the VMO is created.
+    pub(in crate::vm) fn inc_mappings(&self) {
+        self.0.nr_mappings.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a mapping of the VMO is destroyed.
+    pub(in crate::vm) fn dec_mappings(&self) {
+        let old_nr_mappings = self.0.nr_mappings.fetch_sub(1, Ordering::Relaxed);
+        debug_assert!(old_nr_mappings > 0);
+    }
+
+    /// Checks whether the two VMOs are the same one.
+    pub fn is_same<R2>(&self, other: &Vmo<R2>) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns the status of the writable shared mappings of a VMO.
+    pub fn writable_mapping_status(&self) -> &WritableMappingStatus {
+        &self.0.writable_mapping_status
+    }
 }
 
 /// Gets the page index range that contains the offset range of VMO.
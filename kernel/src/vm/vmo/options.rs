@@ -4,6 +4,8 @@
 
 //! Options for allocating root and child VMOs.
 
+use core::sync::atomic::AtomicUsize;
+
 use align_ext::AlignExt;
 use aster_rights::{Rights, TRightSet, TRights};
 use ostd::{
@@ -11,7 +13,7 @@ use ostd::{
     mm::{Frame, FrameAllocOptions},
 };
 
-use super::{Pager, Pages, Vmo, VmoFlags};
+use super::{Pager, Pages, Vmo, VmoFlags, WritableMappingStatus};
 use crate::{prelude::*, vm::vmo::Vmo_};
 
 /// Options for allocating a root VMO.
@@ -134,6 +136,8 @@ fn alloc_vmo_(size: usize, flags: VmoFlags, pager: Option<Arc<dyn Pager>>) -> Re
         pager,
         flags,
         pages,
+        nr_mappings: AtomicUsize::new(0),
+        writable_mapping_status: WritableMappingStatus::default(),
     })
 }
 
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <dirent.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#ifndef DIR_PATH
+#define DIR_PATH "/ext2/dir_index"
+#endif
+
+// Enough entries to fill many blocks, so that the directory becomes hash-indexed
+#define NR_FILES 3000
+
+static char path[PATH_MAX];
+static char new_path[PATH_MAX];
+
+static const char *file_path(const char *prefix, int idx)
+{
+	snprintf(path, sizeof(path), "%s/%s_%d_with_a_longer_name", DIR_PATH,
+		 prefix, idx);
+	return path;
+}
+
+static int count_entries(void)
+{
+	DIR *dir;
+	struct dirent *entry;
+	int count = 0;
+
+	dir = opendir(DIR_PATH);
+	if (dir == NULL)
+		return -1;
+	while ((entry = readdir(dir)) != NULL)
+		if (strcmp(entry->d_name, ".") != 0 &&
+		    strcmp(entry->d_name, "..") != 0)
+			count++;
+	closedir(dir);
+	return count;
+}
+
+static int create_files(int start, int step)
+{
+	int i, fd;
+
+	for (i = start; i < NR_FILES; i += step) {
+		fd = open(file_path("file", i), O_CREAT | O_EXCL | O_WRONLY,
+			  0644);
+		if (fd < 0)
+			return -1;
+		close(fd);
+	}
+	return 0;
+}
+
+static int lookup_files(const char *prefix, int start, int step)
+{
+	struct stat stat_buf;
+	int i;
+
+	for (i = start; i < NR_FILES; i += step)
+		if (stat(file_path(prefix, i), &stat_buf) < 0 ||
+		    !S_ISREG(stat_buf.st_mode))
+			return -1;
+	return 0;
+}
+
+static int lookup_none(const char *prefix, int start, int step)
+{
+	struct stat stat_buf;
+	int i;
+
+	for (i = start; i < NR_FILES; i += step)
+		if (stat(file_path(prefix, i), &stat_buf) != -1 ||
+		    errno != ENOENT)
+			return -1;
+	errno = 0;
+	return 0;
+}
+
+static int rename_files(int start, int step)
+{
+	int i;
+
+	for (i = start; i < NR_FILES; i += step) {
+		file_path("renamed", i);
+		strcpy(new_path, path);
+		if (rename(file_path("file", i), new_path) < 0)
+			return -1;
+	}
+	return 0;
+}
+
+static int unlink_files(const char *prefix, int start, int step)
+{
+	int i;
+
+	for (i = start; i < NR_FILES; i += step)
+		if (unlink(file_path(prefix, i)) < 0)
+			return -1;
+	return 0;
+}
+
+FN_SETUP(mkdir)
+{
+	CHECK(mkdir(DIR_PATH, 0755));
+}
+END_SETUP()
+
+FN_TEST(create_and_lookup)
+{
+	TEST_SUCC(create_files(0, 1));
+	TEST_RES(lookup_files("file", 0, 1), _ret == 0);
+	TEST_RES(lookup_none("missing", 0, 1), _ret == 0);
+	TEST_ERRNO(open(file_path("file", 0), O_CREAT | O_EXCL | O_WRONLY,
+			0644),
+		   EEXIST);
+	TEST_RES(count_entries(), _ret == NR_FILES);
+}
+END_TEST()
+
+FN_TEST(rename)
+{
+	TEST_SUCC(rename_files(0, 3));
+	TEST_RES(lookup_files("renamed", 0, 3), _ret == 0);
+	TEST_RES(lookup_none("file", 0, 3), _ret == 0);
+	TEST_RES(lookup_files("file", 1, 3), _ret == 0);
+	TEST_RES(lookup_files("file", 2, 3), _ret == 0);
+	TEST_RES(count_entries(), _ret == NR_FILES);
+}
+END_TEST()
+
+FN_TEST(unlink)
+{
+	TEST_SUCC(unlink_files("file", 1, 3));
+	TEST_RES(lookup_none("file", 1, 3), _ret == 0);
+	TEST_RES(lookup_files("renamed", 0, 3), _ret == 0);
+	TEST_RES(lookup_files("file", 2, 3), _ret == 0);
+	TEST_RES(count_entries(), _ret == NR_FILES - (NR_FILES + 1) / 3);
+
+	// The freed space is reused by the new entries
+	TEST_SUCC(create_files(1, 3));
+	TEST_RES(lookup_files("file", 1, 3), _ret == 0);
+	TEST_RES(count_entries(), _ret == NR_FILES);
+}
+END_TEST()
+
+FN_TEST(cleanup)
+{
+	TEST_SUCC(unlink_files("renamed", 0, 3));
+	TEST_SUCC(unlink_files("file", 1, 3));
+	TEST_SUCC(unlink_files("file", 2, 3));
+	TEST_RES(count_entries(), _ret == 0);
+	TEST_SUCC(rmdir(DIR_PATH));
+}
+END_TEST()
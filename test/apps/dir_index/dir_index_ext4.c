@@ -0,0 +1,6 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// Runs the same tests on the Ext4 image, whose directories use the metadata checksums
+#define DIR_PATH "/ext4/dir_index"
+
+#include "dir_index.c"
@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <fcntl.h>
+#include <limits.h>
+#include <poll.h>
+#include <stdio.h>
+#include <sys/inotify.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#define EVENT_BUF_LEN (16 * (sizeof(struct inotify_event) + NAME_MAX + 1))
+
+static char buf[EVENT_BUF_LEN]
+	__attribute__((aligned(__alignof__(struct inotify_event))));
+
+/*
+ * Reads all the pending events and checks that the events of `masks` with the
+ * names of `names` arrive in order.
+ */
+static int read_events(int fd, const uint32_t *masks, const char **names,
+		       int nr_events)
+{
+	int i = 0;
+	ssize_t len;
+	char *ptr;
+	struct inotify_event *event;
+
+	while (i < nr_events) {
+		len = read(fd, buf, sizeof(buf));
+		if (len <= 0)
+			return -1;
+
+		for (ptr = buf; ptr < buf + len;
+		     ptr += sizeof(struct inotify_event) + event->len) {
+			event = (struct inotify_event *)ptr;
+			if (i >= nr_events || event->mask != masks[i])
+				return -1;
+			if (names[i] == NULL ? event->len != 0 :
+					       strcmp(event->name, names[i]) != 0)
+				return -1;
+			i++;
+		}
+	}
+
+	return 0;
+}
+
+static int test_dir_events(const char *dir)
+{
+	int fd, wd, file_fd;
+	char path[PATH_MAX], new_path[PATH_MAX];
+	struct pollfd pfd;
+
+	uint32_t masks[] = { IN_CREATE, IN_OPEN,	 IN_MODIFY,
+			     IN_CLOSE_WRITE, IN_MOVED_FROM, IN_MOVED_TO,
+			     IN_DELETE };
+	const char *names[] = { "inotify_file", "inotify_file",
+				"inotify_file", "inotify_file",
+				"inotify_file", "inotify_file_new",
+				"inotify_file_new" };
+
+	snprintf(path, sizeof(path), "%s/inotify_file", dir);
+	snprintf(new_path, sizeof(new_path), "%s/inotify_file_new", dir);
+
+	fd = inotify_init1(IN_NONBLOCK | IN_CLOEXEC);
+	if (fd < 0)
+		return -1;
+	wd = inotify_add_watch(fd, dir, IN_ALL_EVENTS & ~IN_ACCESS);
+	if (wd < 0)
+		return -1;
+
+	pfd.fd = fd;
+	pfd.events = POLLIN;
+	if (poll(&pfd, 1, 0) != 0)
+		return -1;
+
+	file_fd = open(path, O_CREAT | O_WRONLY, 0644);
+	if (file_fd < 0 || write(file_fd, "hello", 5) != 5)
+		return -1;
+	close(file_fd);
+	if (rename(path, new_path) < 0 || unlink(new_path) < 0)
+		return -1;
+
+	if (poll(&pfd, 1, 0) != 1 || !(pfd.revents & POLLIN))
+		return -1;
+	if (read_events(fd, masks, names, 7) < 0)
+		return -1;
+
+	if (inotify_rm_watch(fd, wd) < 0)
+		return -1;
+	close(fd);
+
+	return 0;
+}
+
+static int test_self_events(const char *dir)
+{
+	int fd, wd, file_fd;
+	char path[PATH_MAX];
+
+	uint32_t masks[] = { IN_ATTRIB, IN_DELETE_SELF, IN_IGNORED };
+	const char *names[] = { NULL, NULL, NULL };
+
+	snprintf(path, sizeof(path), "%s/inotify_self", dir);
+	file_fd = open(path, O_CREAT | O_WRONLY, 0644);
+	if (file_fd < 0)
+		return -1;
+	close(file_fd);
+
+	fd = inotify_init1(IN_NONBLOCK);
+	if (fd < 0)
+		return -1;
+	wd = inotify_add_watch(fd, path, IN_ATTRIB | IN_DELETE_SELF);
+	if (wd < 0)
+		return -1;
+
+	if (chmod(path, 0600) < 0 || unlink(path) < 0)
+		return -1;
+	if (read_events(fd, masks, names, 3) < 0)
+		return -1;
+
+	// The watch has been removed with the file
+	if (inotify_rm_watch(fd, wd) != -1 || errno != EINVAL)
+		return -1;
+	errno = 0;
+	close(fd);
+
+	return 0;
+}
+
+FN_TEST(invalid_args)
+{
+	int fd;
+
+	TEST_ERRNO(inotify_init1(-1), EINVAL);
+
+	fd = TEST_SUCC(inotify_init());
+	TEST_ERRNO(inotify_add_watch(fd, "/", 0), EINVAL);
+	TEST_ERRNO(inotify_add_watch(fd, "/nonexistent", IN_ALL_EVENTS),
+		   ENOENT);
+	TEST_ERRNO(inotify_add_watch(fd, "/proc/self/exe",
+				     IN_ALL_EVENTS | IN_ONLYDIR),
+		   ENOTDIR);
+	TEST_ERRNO(inotify_rm_watch(fd, 1000), EINVAL);
+	TEST_ERRNO(inotify_add_watch(0, "/", IN_ALL_EVENTS), EINVAL);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(ramfs)
+{
+	TEST_SUCC(test_dir_events("/tmp"));
+	TEST_SUCC(test_self_events("/tmp"));
+}
+END_TEST()
+
+FN_TEST(ext2)
+{
+	TEST_SUCC(test_dir_events("/ext2"));
+	TEST_SUCC(test_self_events("/ext2"));
+}
+END_TEST()
+
+FN_TEST(exfat)
+{
+	TEST_SUCC(test_dir_events("/exfat"));
+	TEST_SUCC(test_self_events("/exfat"));
+}
+END_TEST()
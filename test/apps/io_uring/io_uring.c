@@ -0,0 +1,475 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <linux/io_uring.h>
+#include <linux/time_types.h>
+#include <poll.h>
+#include <stdint.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/eventfd.h>
+#include <sys/mman.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <sys/uio.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define RING_ENTRIES 8
+#define FILE_NAME "/tmp/io_uring_test_file"
+#define SOCK_NAME "/tmp/io_uring_test_sock"
+
+static int io_uring_setup(unsigned int entries, struct io_uring_params *p)
+{
+	return syscall(SYS_io_uring_setup, entries, p);
+}
+
+static int io_uring_enter(int fd, unsigned int to_submit,
+			  unsigned int min_complete, unsigned int flags)
+{
+	return syscall(SYS_io_uring_enter, fd, to_submit, min_complete, flags,
+		       NULL, 0);
+}
+
+static int io_uring_register(int fd, unsigned int opcode, void *arg,
+			     unsigned int nr_args)
+{
+	return syscall(SYS_io_uring_register, fd, opcode, arg, nr_args);
+}
+
+static int ring_fd;
+static struct io_uring_params params;
+static unsigned int *sq_tail, *sq_mask, *sq_array;
+static unsigned int *cq_head, *cq_tail, *cq_mask;
+static struct io_uring_sqe *sqes;
+static struct io_uring_cqe *cqes;
+static unsigned int sqe_tail, num_pending;
+
+FN_SETUP(ring)
+{
+	size_t sq_size, cq_size, ring_size;
+	char *ring;
+
+	ring_fd = CHECK(io_uring_setup(RING_ENTRIES, &params));
+
+	sq_size = params.sq_off.array +
+		  params.sq_entries * sizeof(unsigned int);
+	cq_size = params.cq_off.cqes +
+		  params.cq_entries * sizeof(struct io_uring_cqe);
+	ring_size = sq_size > cq_size ? sq_size : cq_size;
+
+	ring = (char *)CHECK_WITH(
+		(long)mmap(NULL, ring_size, PROT_READ | PROT_WRITE, MAP_SHARED,
+			   ring_fd, IORING_OFF_SQ_RING),
+		_ret != (long)MAP_FAILED);
+	sqes = (struct io_uring_sqe *)CHECK_WITH(
+		(long)mmap(NULL,
+			   params.sq_entries * sizeof(struct io_uring_sqe),
+			   PROT_READ | PROT_WRITE, MAP_SHARED, ring_fd,
+			   IORING_OFF_SQES),
+		_ret != (long)MAP_FAILED);
+
+	sq_tail = (unsigned int *)(ring + params.sq_off.tail);
+	sq_mask = (unsigned int *)(ring + params.sq_off.ring_mask);
+	sq_array = (unsigned int *)(ring + params.sq_off.array);
+	cq_head = (unsigned int *)(ring + params.cq_off.head);
+	cq_tail = (unsigned int *)(ring + params.cq_off.tail);
+	cq_mask = (unsigned int *)(ring + params.cq_off.ring_mask);
+	cqes = (struct io_uring_cqe *)(ring + params.cq_off.cqes);
+
+	sqe_tail = *sq_tail;
+}
+END_SETUP()
+
+// Returns a zeroed SQE, which will be submitted by the next `submit`.
+static struct io_uring_sqe *next_sqe(int opcode, int fd, __u64 user_data)
+{
+	unsigned int index = sqe_tail & *sq_mask;
+	struct io_uring_sqe *sqe = &sqes[index];
+
+	memset(sqe, 0, sizeof(*sqe));
+	sqe->opcode = opcode;
+	sqe->fd = fd;
+	sqe->user_data = user_data;
+	sq_array[index] = index;
+
+	sqe_tail++;
+	num_pending++;
+	return sqe;
+}
+
+// Submits the pending SQEs and waits for `min_complete` CQEs.
+static int submit(unsigned int min_complete)
+{
+	unsigned int to_submit = num_pending;
+
+	num_pending = 0;
+	__atomic_store_n(sq_tail, sqe_tail, __ATOMIC_RELEASE);
+	return io_uring_enter(ring_fd, to_submit, min_complete,
+			      IORING_ENTER_GETEVENTS);
+}
+
+// Consumes a CQE. Returns -1 if there are no CQEs.
+static int reap(struct io_uring_cqe *cqe)
+{
+	unsigned int head = *cq_head;
+
+	if (head == __atomic_load_n(cq_tail, __ATOMIC_ACQUIRE))
+		return -1;
+
+	*cqe = cqes[head & *cq_mask];
+	__atomic_store_n(cq_head, head + 1, __ATOMIC_RELEASE);
+	return 0;
+}
+
+FN_TEST(setup)
+{
+	TEST_SUCC(params.sq_entries == RING_ENTRIES ? 0 : -1);
+	TEST_SUCC(params.cq_entries == 2 * RING_ENTRIES ? 0 : -1);
+	TEST_SUCC(params.features & IORING_FEAT_SINGLE_MMAP ? 0 : -1);
+	TEST_SUCC(params.features & IORING_FEAT_RW_CUR_POS ? 0 : -1);
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	struct io_uring_params p;
+	int fds[2];
+
+	memset(&p, 0, sizeof(p));
+	TEST_ERRNO(io_uring_setup(0, &p), EINVAL);
+
+	p.flags = 1U << 31;
+	TEST_ERRNO(io_uring_setup(RING_ENTRIES, &p), EINVAL);
+
+	memset(&p, 0, sizeof(p));
+	p.flags = IORING_SETUP_CQSIZE;
+	p.cq_entries = RING_ENTRIES / 2;
+	TEST_ERRNO(io_uring_setup(RING_ENTRIES, &p), EINVAL);
+
+	TEST_SUCC(pipe(fds));
+	TEST_ERRNO(io_uring_enter(fds[0], 0, 0, 0), EOPNOTSUPP);
+	TEST_ERRNO(io_uring_enter(ring_fd, 0, 0, 1U << 31), EINVAL);
+	TEST_ERRNO(io_uring_register(ring_fd, 0xff, NULL, 0), EINVAL);
+	TEST_ERRNO(io_uring_register(ring_fd, IORING_UNREGISTER_FILES, NULL, 0),
+		   ENXIO);
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+}
+END_TEST()
+
+FN_TEST(nop)
+{
+	struct io_uring_cqe cqe;
+
+	next_sqe(IORING_OP_NOP, -1, 0x1234);
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 0x1234 &&
+				     cqe.res == 0);
+	TEST_RES(reap(&cqe), _ret == -1);
+
+	// The errors of the invalid SQEs are reported in the CQEs.
+	next_sqe(0xff, -1, 1);
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 1 &&
+				     cqe.res == -EINVAL);
+	next_sqe(IORING_OP_READ, -1, 2);
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 2 &&
+				     cqe.res == -EBADF);
+}
+END_TEST()
+
+FN_TEST(read_write)
+{
+	struct io_uring_cqe cqe;
+	struct io_uring_sqe *sqe;
+	char buf[16] = {};
+	int fd;
+
+	fd = TEST_SUCC(open(FILE_NAME, O_RDWR | O_CREAT | O_TRUNC, 0644));
+
+	sqe = next_sqe(IORING_OP_WRITE, fd, 1);
+	sqe->addr = (__u64)"hello io_uring";
+	sqe->len = 14;
+	sqe->off = 0;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 1 &&
+				     cqe.res == 14);
+
+	sqe = next_sqe(IORING_OP_READ, fd, 2);
+	sqe->addr = (__u64)buf;
+	sqe->len = sizeof(buf);
+	sqe->off = 6;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 2 && cqe.res == 8 &&
+				     memcmp(buf, "io_uring", 8) == 0);
+
+	// An offset of -1 means the current file position.
+	sqe = next_sqe(IORING_OP_READ, fd, 3);
+	sqe->addr = (__u64)buf;
+	sqe->len = 5;
+	sqe->off = -1;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 3 && cqe.res == 5 &&
+				     memcmp(buf, "hello", 5) == 0);
+	TEST_RES(lseek(fd, 0, SEEK_CUR), _ret == 5);
+
+	sqe = next_sqe(IORING_OP_FSYNC, fd, 4);
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 4 && cqe.res == 0);
+
+	// Writing to a read-only buffer fails.
+	sqe = next_sqe(IORING_OP_READ, fd, 5);
+	sqe->addr = (__u64)"read-only";
+	sqe->len = 4;
+	sqe->off = 0;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 5 &&
+				     cqe.res == -EFAULT);
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(FILE_NAME));
+}
+END_TEST()
+
+FN_TEST(readv_writev)
+{
+	struct io_uring_cqe cqe;
+	struct io_uring_sqe *sqe;
+	char buf1[4], buf2[8];
+	struct iovec iov[2];
+	int fd;
+
+	fd = TEST_SUCC(open(FILE_NAME, O_RDWR | O_CREAT | O_TRUNC, 0644));
+
+	iov[0].iov_base = "abc";
+	iov[0].iov_len = 3;
+	iov[1].iov_base = "defgh";
+	iov[1].iov_len = 5;
+	sqe = next_sqe(IORING_OP_WRITEV, fd, 1);
+	sqe->addr = (__u64)iov;
+	sqe->len = 2;
+	sqe->off = 0;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 1 && cqe.res == 8);
+
+	iov[0].iov_base = buf1;
+	iov[0].iov_len = sizeof(buf1);
+	iov[1].iov_base = buf2;
+	iov[1].iov_len = sizeof(buf2);
+	sqe = next_sqe(IORING_OP_READV, fd, 2);
+	sqe->addr = (__u64)iov;
+	sqe->len = 2;
+	sqe->off = 0;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 2 && cqe.res == 8 &&
+				     memcmp(buf1, "abcd", 4) == 0 &&
+				     memcmp(buf2, "efgh", 4) == 0);
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(FILE_NAME));
+}
+END_TEST()
+
+FN_TEST(pipe)
+{
+	struct io_uring_cqe cqe;
+	struct io_uring_sqe *sqe;
+	char buf[8] = {};
+	int fds[2];
+
+	TEST_SUCC(pipe(fds));
+
+	// The read waits for the data without blocking the submitter.
+	sqe = next_sqe(IORING_OP_READ, fds[0], 1);
+	sqe->addr = (__u64)buf;
+	sqe->len = sizeof(buf);
+	TEST_RES(submit(0), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == -1);
+
+	TEST_RES(write(fds[1], "pipe", 4), _ret == 4);
+	TEST_RES(io_uring_enter(ring_fd, 0, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 0);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 1 && cqe.res == 4 &&
+				     memcmp(buf, "pipe", 4) == 0);
+
+	sqe = next_sqe(IORING_OP_POLL_ADD, fds[0], 2);
+	sqe->poll32_events = POLLIN;
+	TEST_RES(submit(0), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == -1);
+
+	TEST_RES(write(fds[1], "x", 1), _ret == 1);
+	TEST_RES(io_uring_enter(ring_fd, 0, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 0);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 2 &&
+				     cqe.res == POLLIN);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+}
+END_TEST()
+
+FN_TEST(timeout)
+{
+	struct __kernel_timespec short_ts = { .tv_nsec = 10 * 1000 * 1000 };
+	struct __kernel_timespec long_ts = { .tv_sec = 100 };
+	struct io_uring_cqe cqe1, cqe2;
+	struct io_uring_sqe *sqe;
+
+	sqe = next_sqe(IORING_OP_TIMEOUT, -1, 1);
+	sqe->addr = (__u64)&short_ts;
+	sqe->len = 1;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe1), _ret == 0 && cqe1.user_data == 1 &&
+				      cqe1.res == -ETIME);
+
+	// The timeout completes after one completion of other requests.
+	sqe = next_sqe(IORING_OP_TIMEOUT, -1, 2);
+	sqe->addr = (__u64)&long_ts;
+	sqe->len = 1;
+	sqe->off = 1;
+	next_sqe(IORING_OP_NOP, -1, 3);
+	TEST_RES(submit(2), _ret == 2);
+	TEST_RES(reap(&cqe1), _ret == 0 && cqe1.user_data == 3 &&
+				      cqe1.res == 0);
+	TEST_RES(reap(&cqe2), _ret == 0 && cqe2.user_data == 2 &&
+				      cqe2.res == 0);
+
+	sqe = next_sqe(IORING_OP_TIMEOUT, -1, 4);
+	sqe->addr = (__u64)&short_ts;
+	sqe->len = 2;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe1), _ret == 0 && cqe1.user_data == 4 &&
+				      cqe1.res == -EINVAL);
+}
+END_TEST()
+
+FN_TEST(epoll)
+{
+	struct epoll_event event = { .events = EPOLLIN };
+	struct io_uring_cqe cqe;
+	int epfd;
+
+	epfd = TEST_SUCC(epoll_create1(0));
+	TEST_SUCC(epoll_ctl(epfd, EPOLL_CTL_ADD, ring_fd, &event));
+	TEST_RES(epoll_wait(epfd, &event, 1, 0), _ret == 0);
+
+	next_sqe(IORING_OP_NOP, -1, 1);
+	TEST_RES(submit(0), _ret == 1);
+	TEST_RES(epoll_wait(epfd, &event, 1, 1000),
+		 _ret == 1 && event.events == EPOLLIN);
+
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 1);
+	TEST_RES(epoll_wait(epfd, &event, 1, 0), _ret == 0);
+
+	TEST_SUCC(close(epfd));
+}
+END_TEST()
+
+FN_TEST(register)
+{
+	struct io_uring_probe *probe;
+	struct io_uring_cqe cqe;
+	struct io_uring_sqe *sqe;
+	int fds[2], efd;
+	uint64_t count;
+	char buf[4];
+
+	TEST_SUCC(pipe(fds));
+	efd = TEST_SUCC(eventfd(0, EFD_NONBLOCK));
+
+	TEST_SUCC(io_uring_register(ring_fd, IORING_REGISTER_FILES, fds, 2));
+	TEST_ERRNO(io_uring_register(ring_fd, IORING_REGISTER_FILES, fds, 2),
+		   EBUSY);
+	TEST_ERRNO(io_uring_register(ring_fd, IORING_REGISTER_EVENTFD, &fds[0],
+				     1),
+		   EINVAL);
+	TEST_SUCC(io_uring_register(ring_fd, IORING_REGISTER_EVENTFD, &efd, 1));
+
+	// Write to the registered file at index 1.
+	sqe = next_sqe(IORING_OP_WRITE, 1, 1);
+	sqe->flags = IOSQE_FIXED_FILE;
+	sqe->addr = (__u64)"reg";
+	sqe->len = 3;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 1 && cqe.res == 3);
+	TEST_RES(read(fds[0], buf, sizeof(buf)),
+		 _ret == 3 && memcmp(buf, "reg", 3) == 0);
+	TEST_RES(read(efd, &count, sizeof(count)), _ret == 8 && count == 1);
+
+	sqe = next_sqe(IORING_OP_WRITE, 2, 2);
+	sqe->flags = IOSQE_FIXED_FILE;
+	TEST_RES(submit(1), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 2 &&
+				     cqe.res == -EBADF);
+
+	TEST_SUCC(io_uring_register(ring_fd, IORING_UNREGISTER_EVENTFD, NULL,
+				    0));
+	TEST_SUCC(io_uring_register(ring_fd, IORING_UNREGISTER_FILES, NULL, 0));
+
+	probe = calloc(1, sizeof(*probe) + 256 * sizeof(probe->ops[0]));
+	TEST_SUCC(io_uring_register(ring_fd, IORING_REGISTER_PROBE, probe,
+				    256));
+	TEST_RES(probe->ops_len,
+		 _ret > IORING_OP_RECV &&
+			 (probe->ops[IORING_OP_READ].flags &
+			  IO_URING_OP_SUPPORTED));
+	free(probe);
+
+	TEST_SUCC(close(efd));
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+}
+END_TEST()
+
+FN_TEST(socket)
+{
+	struct sockaddr_un addr = { .sun_family = AF_UNIX };
+	struct sockaddr_un peer_addr;
+	socklen_t peer_addrlen = sizeof(peer_addr);
+	struct io_uring_cqe cqe;
+	struct io_uring_sqe *sqe;
+	int listener, client;
+	char buf[8] = {};
+
+	strcpy(addr.sun_path, SOCK_NAME);
+	listener = TEST_SUCC(socket(AF_UNIX, SOCK_STREAM, 0));
+	TEST_SUCC(bind(listener, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(listen(listener, 1));
+
+	sqe = next_sqe(IORING_OP_ACCEPT, listener, 1);
+	sqe->addr = (__u64)&peer_addr;
+	sqe->addr2 = (__u64)&peer_addrlen;
+	sqe->accept_flags = SOCK_CLOEXEC;
+	TEST_RES(submit(0), _ret == 1);
+	TEST_RES(reap(&cqe), _ret == -1);
+
+	client = TEST_SUCC(socket(AF_UNIX, SOCK_STREAM, 0));
+	TEST_SUCC(connect(client, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_RES(io_uring_enter(ring_fd, 0, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 0);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.user_data == 1 && cqe.res >= 0 &&
+				     peer_addr.sun_family == AF_UNIX);
+	TEST_RES(fcntl(cqe.res, F_GETFD), _ret == FD_CLOEXEC);
+
+	sqe = next_sqe(IORING_OP_RECV, cqe.res, 2);
+	sqe->addr = (__u64)buf;
+	sqe->len = sizeof(buf);
+	sqe = next_sqe(IORING_OP_SEND, client, 3);
+	sqe->addr = (__u64)"socket";
+	sqe->len = 6;
+	TEST_RES(submit(2), _ret == 2);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.res == 6);
+	TEST_RES(reap(&cqe), _ret == 0 && cqe.res == 6 &&
+				     memcmp(buf, "socket", 6) == 0);
+
+	TEST_SUCC(close(client));
+	TEST_SUCC(close(listener));
+	TEST_SUCC(unlink(SOCK_NAME));
+}
+END_TEST()
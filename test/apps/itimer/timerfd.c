@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <poll.h>
+#include <stdint.h>
+#include <sys/timerfd.h>
+#include <time.h>
+#include <unistd.h>
+
+FN_TEST(invalid_args)
+{
+	int fd;
+	struct itimerspec its = { 0 };
+
+	TEST_ERRNO(timerfd_create(CLOCK_MONOTONIC, -1), EINVAL);
+	TEST_ERRNO(timerfd_create(-1, 0), EINVAL);
+
+	fd = TEST_SUCC(timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK));
+	its.it_value.tv_nsec = 1000000000;
+	TEST_ERRNO(timerfd_settime(fd, 0, &its, NULL), EINVAL);
+	TEST_ERRNO(timerfd_settime(0, 0, &its, NULL), EINVAL);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(oneshot)
+{
+	int fd;
+	uint64_t ticks;
+	char small_buf[4];
+	struct itimerspec its = { 0 };
+	struct itimerspec cur;
+	struct pollfd pfd;
+
+	fd = TEST_SUCC(timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK));
+	TEST_ERRNO(read(fd, &ticks, sizeof(ticks)), EAGAIN);
+
+	its.it_value.tv_nsec = 50 * 1000 * 1000;
+	TEST_SUCC(timerfd_settime(fd, 0, &its, NULL));
+	TEST_RES(timerfd_gettime(fd, &cur),
+		 cur.it_value.tv_sec == 0 && cur.it_value.tv_nsec > 0 &&
+			 cur.it_interval.tv_sec == 0 &&
+			 cur.it_interval.tv_nsec == 0);
+
+	pfd.fd = fd;
+	pfd.events = POLLIN;
+	TEST_RES(poll(&pfd, 1, 1000), _ret == 1 && pfd.revents == POLLIN);
+	TEST_ERRNO(read(fd, small_buf, sizeof(small_buf)), EINVAL);
+	TEST_RES(read(fd, &ticks, sizeof(ticks)),
+		 _ret == sizeof(ticks) && ticks == 1);
+	TEST_ERRNO(read(fd, &ticks, sizeof(ticks)), EAGAIN);
+
+	TEST_RES(timerfd_gettime(fd, &cur),
+		 cur.it_value.tv_sec == 0 && cur.it_value.tv_nsec == 0);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(periodic)
+{
+	int fd;
+	uint64_t ticks;
+	struct itimerspec its;
+	struct itimerspec old;
+
+	fd = TEST_SUCC(timerfd_create(CLOCK_REALTIME, 0));
+
+	its.it_value.tv_sec = 0;
+	its.it_value.tv_nsec = 10 * 1000 * 1000;
+	its.it_interval.tv_sec = 0;
+	its.it_interval.tv_nsec = 10 * 1000 * 1000;
+	TEST_SUCC(timerfd_settime(fd, 0, &its, NULL));
+
+	usleep(100 * 1000);
+	TEST_RES(read(fd, &ticks, sizeof(ticks)),
+		 _ret == sizeof(ticks) && ticks >= 2);
+
+	// Disarm the timer.
+	its.it_value.tv_nsec = 0;
+	TEST_RES(timerfd_settime(fd, 0, &its, &old),
+		 old.it_interval.tv_nsec == 10 * 1000 * 1000);
+	TEST_RES(timerfd_gettime(fd, &old),
+		 old.it_value.tv_sec == 0 && old.it_value.tv_nsec == 0);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(abstime)
+{
+	int fd;
+	uint64_t ticks;
+	struct itimerspec its = { 0 };
+
+	fd = TEST_SUCC(timerfd_create(CLOCK_MONOTONIC, TFD_CLOEXEC));
+	TEST_SUCC(clock_gettime(CLOCK_MONOTONIC, &its.it_value));
+	its.it_value.tv_nsec += 20 * 1000 * 1000;
+	if (its.it_value.tv_nsec >= 1000000000) {
+		its.it_value.tv_sec += 1;
+		its.it_value.tv_nsec -= 1000000000;
+	}
+	TEST_SUCC(timerfd_settime(fd, TFD_TIMER_ABSTIME, &its, NULL));
+
+	// The read is blocked until the timer expires.
+	TEST_RES(read(fd, &ticks, sizeof(ticks)),
+		 _ret == sizeof(ticks) && ticks == 1);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define PAGE_SIZE 4096
+
+static long remap(void *old_addr, size_t old_size, size_t new_size, int flags,
+		  void *new_addr)
+{
+	return (long)mremap(old_addr, old_size, new_size, flags, new_addr);
+}
+
+static long map_anon(void *addr, size_t size, int flags)
+{
+	return (long)mmap(addr, size, PROT_READ | PROT_WRITE,
+			  MAP_PRIVATE | MAP_ANONYMOUS | flags, -1, 0);
+}
+
+static int pipe_fds[2];
+
+// Checks whether the page at `addr` is mapped by passing it to a syscall,
+// which fails with `EFAULT` if the page is not mapped.
+static long probe(void *addr)
+{
+	char buf;
+
+	if (write(pipe_fds[1], addr, 1) < 0)
+		return -1;
+	return read(pipe_fds[0], &buf, 1);
+}
+
+FN_SETUP(pipe)
+{
+	CHECK(pipe(pipe_fds));
+}
+END_SETUP()
+
+FN_TEST(invalid_arguments)
+{
+	char *addr;
+
+	addr = (char *)TEST_SUCC(map_anon(NULL, PAGE_SIZE * 2, 0));
+
+	TEST_ERRNO(remap(addr + 1, PAGE_SIZE, PAGE_SIZE, 0, NULL), EINVAL);
+	TEST_ERRNO(remap(addr, PAGE_SIZE, 0, 0, NULL), EINVAL);
+	TEST_ERRNO(remap(addr, PAGE_SIZE, PAGE_SIZE, 0x80, NULL), EINVAL);
+	TEST_ERRNO(remap(addr, PAGE_SIZE, PAGE_SIZE, MREMAP_FIXED, NULL),
+		   EINVAL);
+	TEST_ERRNO(remap(addr, PAGE_SIZE, PAGE_SIZE * 2,
+			 MREMAP_MAYMOVE | MREMAP_DONTUNMAP, NULL),
+		   EINVAL);
+	TEST_ERRNO(remap(addr, PAGE_SIZE, PAGE_SIZE,
+			 MREMAP_MAYMOVE | MREMAP_FIXED, addr + 1),
+		   EINVAL);
+	// The new range overlaps the old one.
+	TEST_ERRNO(remap(addr, PAGE_SIZE, PAGE_SIZE,
+			 MREMAP_MAYMOVE | MREMAP_FIXED, addr),
+		   EINVAL);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 2));
+	// The old range is not mapped.
+	TEST_ERRNO(remap(addr, PAGE_SIZE, PAGE_SIZE * 2, MREMAP_MAYMOVE, NULL),
+		   EFAULT);
+}
+END_TEST()
+
+FN_TEST(shrink_in_place)
+{
+	char *addr;
+
+	addr = (char *)TEST_SUCC(map_anon(NULL, PAGE_SIZE * 4, 0));
+	addr[0] = 'a';
+
+	TEST_RES(remap(addr, PAGE_SIZE * 4, PAGE_SIZE, 0, NULL),
+		 _ret == (long)addr && addr[0] == 'a');
+	TEST_ERRNO(probe(addr + PAGE_SIZE), EFAULT);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+}
+END_TEST()
+
+FN_TEST(grow_in_place)
+{
+	char *addr;
+
+	addr = (char *)TEST_SUCC(map_anon(NULL, PAGE_SIZE * 4, 0));
+	TEST_SUCC(munmap(addr + PAGE_SIZE * 2, PAGE_SIZE * 2));
+	addr[0] = 'a';
+
+	TEST_RES(remap(addr, PAGE_SIZE * 2, PAGE_SIZE * 4, 0, NULL),
+		 _ret == (long)addr && addr[0] == 'a');
+	addr[PAGE_SIZE * 3] = 'b';
+
+	// The range after the mapping is occupied.
+	TEST_SUCC(munmap(addr + PAGE_SIZE * 3, PAGE_SIZE));
+	TEST_ERRNO(remap(addr, PAGE_SIZE * 2, PAGE_SIZE * 4, 0, NULL), ENOMEM);
+	TEST_RES(addr[0], _ret == 'a');
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 3));
+}
+END_TEST()
+
+FN_TEST(grow_and_move)
+{
+	char *addr, *blocker, *new_addr;
+
+	addr = (char *)TEST_SUCC(map_anon(NULL, PAGE_SIZE * 3, 0));
+	blocker = addr + PAGE_SIZE * 2;
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 2));
+	addr = (char *)TEST_SUCC(map_anon(blocker - PAGE_SIZE * 2,
+					  PAGE_SIZE * 2, MAP_FIXED));
+	strcpy(addr, "first");
+	strcpy(addr + PAGE_SIZE, "second");
+
+	new_addr = (char *)TEST_RES(
+		remap(addr, PAGE_SIZE * 2, PAGE_SIZE * 4, MREMAP_MAYMOVE, NULL),
+		_ret != (long)addr);
+	TEST_RES(strcmp(new_addr, "first"), _ret == 0);
+	TEST_RES(strcmp(new_addr + PAGE_SIZE, "second"), _ret == 0);
+	TEST_RES(new_addr[PAGE_SIZE * 3], _ret == 0);
+	TEST_ERRNO(probe(addr), EFAULT);
+	TEST_SUCC(probe(blocker));
+
+	TEST_SUCC(munmap(new_addr, PAGE_SIZE * 4));
+	TEST_SUCC(munmap(blocker, PAGE_SIZE));
+}
+END_TEST()
+
+FN_TEST(move_to_fixed_address)
+{
+	char *addr, *target;
+
+	target = (char *)TEST_SUCC(map_anon(NULL, PAGE_SIZE * 2, 0));
+	target[0] = 'x';
+	addr = (char *)TEST_SUCC(map_anon(NULL, PAGE_SIZE * 4, 0));
+	strcpy(addr, "moved");
+	strcpy(addr + PAGE_SIZE * 3, "dropped");
+
+	// The mapping is shrunk and replaces the existing mapping.
+	TEST_RES(remap(addr, PAGE_SIZE * 4, PAGE_SIZE * 2,
+		       MREMAP_MAYMOVE | MREMAP_FIXED, target),
+		 _ret == (long)target);
+	TEST_RES(strcmp(target, "moved"), _ret == 0);
+	TEST_ERRNO(probe(addr), EFAULT);
+	TEST_ERRNO(probe(addr + PAGE_SIZE * 3), EFAULT);
+
+	TEST_SUCC(munmap(target, PAGE_SIZE * 2));
+}
+END_TEST()
+
+FN_TEST(move_without_unmap)
+{
+	char *addr, *new_addr;
+
+	addr = (char *)TEST_SUCC(map_anon(NULL, PAGE_SIZE * 2, 0));
+	strcpy(addr, "hello");
+
+	new_addr = (char *)TEST_RES(remap(addr, PAGE_SIZE * 2, PAGE_SIZE * 2,
+					  MREMAP_MAYMOVE | MREMAP_DONTUNMAP,
+					  NULL),
+				    _ret != (long)addr);
+	TEST_RES(strcmp(new_addr, "hello"), _ret == 0);
+	// The old range is still mapped, but the pages are gone.
+	TEST_SUCC(probe(addr));
+	TEST_RES(addr[0], _ret == 0);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * 2));
+	TEST_SUCC(munmap(new_addr, PAGE_SIZE * 2));
+}
+END_TEST()
+
+FN_TEST(move_shared_mapping)
+{
+	char *addr, *target;
+
+	addr = (char *)TEST_SUCC((long)mmap(NULL, PAGE_SIZE * 2,
+					    PROT_READ | PROT_WRITE,
+					    MAP_SHARED | MAP_ANONYMOUS, -1, 0));
+	target = (char *)TEST_SUCC(map_anon(NULL, PAGE_SIZE, 0));
+	strcpy(addr + PAGE_SIZE, "shared");
+
+	TEST_RES(remap(addr + PAGE_SIZE, PAGE_SIZE, PAGE_SIZE,
+		       MREMAP_MAYMOVE | MREMAP_FIXED, target),
+		 _ret == (long)target);
+	TEST_RES(strcmp(target, "shared"), _ret == 0);
+	TEST_SUCC(probe(addr));
+	TEST_ERRNO(probe(addr + PAGE_SIZE), EFAULT);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+	TEST_SUCC(munmap(target, PAGE_SIZE));
+}
+END_TEST()
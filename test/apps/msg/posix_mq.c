@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <fcntl.h>
+#include <mqueue.h>
+#include <poll.h>
+#include <signal.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#define QUEUE_NAME "/posix_mq_test"
+
+static mqd_t open_queue(int oflag, long maxmsg, long msgsize)
+{
+	struct mq_attr attr = { .mq_maxmsg = maxmsg, .mq_msgsize = msgsize };
+
+	return mq_open(QUEUE_NAME, oflag, 0600, &attr);
+}
+
+FN_TEST(open_and_unlink)
+{
+	mqd_t mqd;
+	struct mq_attr attr;
+
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR), ENOENT);
+	TEST_ERRNO(open_queue(O_RDWR | O_CREAT, 0, 8), EINVAL);
+	TEST_ERRNO(open_queue(O_RDWR | O_CREAT, 8, 0), EINVAL);
+
+	mqd = TEST_SUCC(open_queue(O_RDWR | O_CREAT | O_EXCL, 4, 16));
+	TEST_ERRNO(open_queue(O_RDWR | O_CREAT | O_EXCL, 4, 16), EEXIST);
+	TEST_RES(mq_getattr(mqd, &attr),
+		 attr.mq_flags == 0 && attr.mq_maxmsg == 4 &&
+			 attr.mq_msgsize == 16 && attr.mq_curmsgs == 0);
+	TEST_RES(fcntl(mqd, F_GETFD), _ret == FD_CLOEXEC);
+
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+	TEST_ERRNO(mq_unlink(QUEUE_NAME), ENOENT);
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR), ENOENT);
+	TEST_SUCC(mq_close(mqd));
+}
+END_TEST()
+
+FN_TEST(send_and_receive)
+{
+	mqd_t mqd;
+	char buf[16];
+	unsigned int prio;
+
+	mqd = TEST_SUCC(open_queue(O_RDWR | O_CREAT | O_NONBLOCK, 4, 16));
+
+	TEST_ERRNO(mq_send(mqd, buf, 17, 0), EMSGSIZE);
+	TEST_SUCC(mq_send(mqd, "low", 4, 1));
+	TEST_SUCC(mq_send(mqd, "high", 5, 9));
+	TEST_SUCC(mq_send(mqd, "low2", 5, 1));
+	TEST_SUCC(mq_send(mqd, "mid", 4, 5));
+	TEST_ERRNO(mq_send(mqd, "full", 5, 0), EAGAIN);
+
+	TEST_ERRNO(mq_receive(mqd, buf, 15, &prio), EMSGSIZE);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 5 && prio == 9 && strcmp(buf, "high") == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 4 && prio == 5 && strcmp(buf, "mid") == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 4 && prio == 1 && strcmp(buf, "low") == 0);
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), &prio),
+		 _ret == 5 && prio == 1 && strcmp(buf, "low2") == 0);
+	TEST_ERRNO(mq_receive(mqd, buf, sizeof(buf), &prio), EAGAIN);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(access_mode)
+{
+	mqd_t rd, wr;
+	char buf[16];
+
+	wr = TEST_SUCC(open_queue(O_WRONLY | O_CREAT, 4, 16));
+	rd = TEST_SUCC(mq_open(QUEUE_NAME, O_RDONLY));
+
+	TEST_ERRNO(mq_receive(wr, buf, sizeof(buf), NULL), EBADF);
+	TEST_ERRNO(mq_send(rd, "x", 2, 0), EBADF);
+	TEST_SUCC(mq_send(wr, "x", 2, 0));
+	TEST_RES(mq_receive(rd, buf, sizeof(buf), NULL), _ret == 2);
+
+	TEST_SUCC(mq_close(rd));
+	TEST_SUCC(mq_close(wr));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(set_attributes)
+{
+	mqd_t mqd;
+	char buf[16];
+	struct mq_attr attr = { .mq_flags = O_NONBLOCK };
+	struct mq_attr old_attr;
+
+	mqd = TEST_SUCC(open_queue(O_RDWR | O_CREAT, 4, 16));
+
+	TEST_RES(mq_setattr(mqd, &attr, &old_attr), old_attr.mq_flags == 0);
+	TEST_ERRNO(mq_receive(mqd, buf, sizeof(buf), NULL), EAGAIN);
+	TEST_RES(mq_getattr(mqd, &attr), attr.mq_flags == O_NONBLOCK);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(timed_receive)
+{
+	mqd_t mqd;
+	char buf[16];
+	struct timespec timeout;
+
+	mqd = TEST_SUCC(open_queue(O_RDWR | O_CREAT, 4, 16));
+
+	TEST_SUCC(clock_gettime(CLOCK_REALTIME, &timeout));
+	timeout.tv_nsec += 100 * 1000 * 1000;
+	if (timeout.tv_nsec >= 1000 * 1000 * 1000) {
+		timeout.tv_sec += 1;
+		timeout.tv_nsec -= 1000 * 1000 * 1000;
+	}
+	TEST_ERRNO(mq_timedreceive(mqd, buf, sizeof(buf), NULL, &timeout),
+		   ETIMEDOUT);
+
+	timeout.tv_nsec = -1;
+	TEST_ERRNO(mq_timedreceive(mqd, buf, sizeof(buf), NULL, &timeout),
+		   EINVAL);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(blocking_receive)
+{
+	mqd_t mqd;
+	pid_t pid;
+	char buf[16];
+
+	mqd = TEST_SUCC(open_queue(O_RDWR | O_CREAT, 4, 16));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		usleep(100 * 1000);
+		_exit(mq_send(mqd, "wake", 5, 0) < 0);
+	}
+	TEST_RES(mq_receive(mqd, buf, sizeof(buf), NULL),
+		 _ret == 5 && strcmp(buf, "wake") == 0);
+	TEST_RES(wait(NULL), _ret == pid);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(poll_queue)
+{
+	mqd_t mqd;
+	char buf[16];
+	struct pollfd pfd;
+
+	mqd = TEST_SUCC(open_queue(O_RDWR | O_CREAT, 1, 16));
+	pfd.fd = mqd;
+	pfd.events = POLLIN | POLLOUT;
+
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLOUT);
+	TEST_SUCC(mq_send(mqd, "x", 2, 0));
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLIN);
+	TEST_SUCC(mq_receive(mqd, buf, sizeof(buf), NULL));
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLOUT);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+static volatile siginfo_t received_info;
+
+static void handle_signal(int signo, siginfo_t *info, void *ucontext)
+{
+	received_info = *info;
+}
+
+FN_TEST(notify_signal)
+{
+	mqd_t mqd;
+	char buf[16];
+	struct sigaction sa = { .sa_sigaction = handle_signal,
+				.sa_flags = SA_SIGINFO };
+	struct sigevent sev = { .sigev_notify = SIGEV_SIGNAL,
+				.sigev_signo = SIGUSR1,
+				.sigev_value.sival_int = 42 };
+
+	TEST_SUCC(sigaction(SIGUSR1, &sa, NULL));
+	mqd = TEST_SUCC(open_queue(O_RDWR | O_CREAT, 4, 16));
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_ERRNO(mq_notify(mqd, &sev), EBUSY);
+
+	TEST_SUCC(mq_send(mqd, "x", 2, 0));
+	TEST_RES(received_info.si_signo,
+		 _ret == SIGUSR1 && received_info.si_code == SI_MESGQ &&
+			 received_info.si_value.sival_int == 42 &&
+			 received_info.si_pid == getpid());
+
+	// The registration is removed after the notification.
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+
+	TEST_SUCC(mq_receive(mqd, buf, sizeof(buf), NULL));
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+	sa.sa_handler = SIG_DFL;
+	sa.sa_flags = 0;
+	TEST_SUCC(sigaction(SIGUSR1, &sa, NULL));
+}
+END_TEST()
+
+FN_TEST(mqueue_fs)
+{
+	mqd_t mqd;
+	int fd;
+	char buf[128];
+
+	TEST_SUCC(mkdir("/tmp/mqueue", 0755));
+	TEST_SUCC(mount("mqueue", "/tmp/mqueue", "mqueue", 0, NULL));
+
+	mqd = TEST_SUCC(open_queue(O_RDWR | O_CREAT, 4, 16));
+	TEST_SUCC(mq_send(mqd, "hello", 6, 0));
+
+	fd = TEST_SUCC(open("/tmp/mqueue" QUEUE_NAME, O_RDONLY));
+	TEST_RES(read(fd, buf, sizeof(buf)),
+		 _ret > 0 && strncmp(buf, "QSIZE:6 ", 8) == 0);
+	TEST_SUCC(close(fd));
+
+	TEST_SUCC(unlink("/tmp/mqueue" QUEUE_NAME));
+	TEST_ERRNO(mq_unlink(QUEUE_NAME), ENOENT);
+	TEST_ERRNO(open("/tmp/mqueue" QUEUE_NAME, O_RDONLY), ENOENT);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(umount("/tmp/mqueue"));
+	TEST_SUCC(rmdir("/tmp/mqueue"));
+}
+END_TEST()
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <string.h>
+#include <sys/ipc.h>
+#include <sys/msg.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+struct message {
+	long mtype;
+	char mtext[64];
+};
+
+static long send_msg(int msqid, long mtype, const char *text, int flags)
+{
+	struct message msg = { .mtype = mtype };
+
+	strcpy(msg.mtext, text);
+	return msgsnd(msqid, &msg, strlen(text) + 1, flags);
+}
+
+static long recv_type(int msqid, long msgtyp, int flags)
+{
+	struct message msg;
+
+	if (msgrcv(msqid, &msg, sizeof(msg.mtext), msgtyp, flags) < 0)
+		return -1;
+	return msg.mtype;
+}
+
+FN_TEST(get_by_key)
+{
+	key_t key = 0x4d53;
+	int msqid;
+	struct msqid_ds ds;
+
+	TEST_ERRNO(msgget(key, 0600), ENOENT);
+
+	msqid = TEST_SUCC(msgget(key, IPC_CREAT | 0600));
+	TEST_RES(msgget(key, 0600), _ret == msqid);
+	TEST_ERRNO(msgget(key, IPC_CREAT | IPC_EXCL | 0600), EEXIST);
+
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 ds.msg_qnum == 0 && ds.msg_cbytes == 0 &&
+			 ds.msg_qbytes == 16384 &&
+			 (ds.msg_perm.mode & 0777) == 0600 &&
+			 ds.msg_perm.__key == key);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+	TEST_ERRNO(msgget(key, 0600), ENOENT);
+	TEST_ERRNO(msgctl(msqid, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(send_and_receive)
+{
+	int msqid;
+	struct message msg;
+	struct msqid_ds ds;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, 0600));
+
+	TEST_ERRNO(send_msg(msqid, 0, "zero", 0), EINVAL);
+	TEST_SUCC(send_msg(msqid, 1, "hello", 0));
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 ds.msg_qnum == 1 && ds.msg_cbytes == 6 &&
+			 ds.msg_lspid == getpid());
+
+	TEST_ERRNO(msgrcv(msqid, &msg, 2, 0, 0), E2BIG);
+	TEST_RES(msgrcv(msqid, &msg, 2, 0, MSG_NOERROR),
+		 _ret == 2 && msg.mtype == 1 && memcmp(msg.mtext, "he", 2) == 0);
+	TEST_ERRNO(msgrcv(msqid, &msg, sizeof(msg.mtext), 0, IPC_NOWAIT),
+		   ENOMSG);
+
+	TEST_SUCC(send_msg(msqid, 2, "world", 0));
+	TEST_RES(msgrcv(msqid, &msg, sizeof(msg.mtext), 0, 0),
+		 _ret == 6 && msg.mtype == 2 &&
+			 strcmp(msg.mtext, "world") == 0);
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 ds.msg_qnum == 0 && ds.msg_lrpid == getpid());
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(receive_by_type)
+{
+	int msqid;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, 0600));
+	TEST_SUCC(send_msg(msqid, 3, "c", 0));
+	TEST_SUCC(send_msg(msqid, 1, "a", 0));
+	TEST_SUCC(send_msg(msqid, 2, "b", 0));
+	TEST_SUCC(send_msg(msqid, 3, "c", 0));
+
+	// The first message with the type.
+	TEST_RES(recv_type(msqid, 2, IPC_NOWAIT), _ret == 2);
+	// The first message with a type other than the type.
+	TEST_RES(recv_type(msqid, 3, IPC_NOWAIT | MSG_EXCEPT), _ret == 1);
+	TEST_ERRNO(recv_type(msqid, 2, IPC_NOWAIT), ENOMSG);
+	// The message with the lowest type that is no more than the absolute
+	// value of the type.
+	TEST_ERRNO(recv_type(msqid, -2, IPC_NOWAIT), ENOMSG);
+	TEST_RES(recv_type(msqid, -3, IPC_NOWAIT), _ret == 3);
+	// The first message.
+	TEST_RES(recv_type(msqid, 0, IPC_NOWAIT), _ret == 3);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(queue_limit)
+{
+	int msqid;
+	struct msqid_ds ds;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, 0600));
+
+	TEST_SUCC(msgctl(msqid, IPC_STAT, &ds));
+	ds.msg_qbytes = 8;
+	TEST_SUCC(msgctl(msqid, IPC_SET, &ds));
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds), ds.msg_qbytes == 8);
+
+	TEST_SUCC(send_msg(msqid, 1, "abcdef", 0));
+	TEST_ERRNO(send_msg(msqid, 1, "abcdef", IPC_NOWAIT), EAGAIN);
+	TEST_RES(recv_type(msqid, 0, 0), _ret == 1);
+	TEST_SUCC(send_msg(msqid, 1, "abcdef", IPC_NOWAIT));
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(blocking_receive)
+{
+	int msqid;
+	pid_t pid;
+	struct message msg;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, 0600));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		usleep(100 * 1000);
+		_exit(send_msg(msqid, 5, "wake", 0) < 0);
+	}
+	TEST_RES(msgrcv(msqid, &msg, sizeof(msg.mtext), 5, 0),
+		 _ret == 5 && strcmp(msg.mtext, "wake") == 0);
+	TEST_RES(wait(NULL), _ret == pid);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(remove_wakes_up_receivers)
+{
+	int msqid;
+	pid_t pid;
+	int status;
+
+	msqid = TEST_SUCC(msgget(IPC_PRIVATE, 0600));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(recv_type(msqid, 0, 0) < 0 && errno == EIDRM ? 0 : 1);
+	usleep(100 * 1000);
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_ERRNO(send_msg(msqid, 1, "gone", 0), EINVAL);
+}
+END_TEST()
@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <sched.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/msg.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/utsname.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#define MSG_KEY 0x6e73
+#define MNT_DIR "/tmp/namespace_test"
+
+// Runs `fn` in a child process and returns the wait status.
+static int run_in_child(int (*fn)(void))
+{
+	pid_t pid;
+	int status;
+
+	pid = fork();
+	if (pid < 0)
+		return -1;
+	if (pid == 0)
+		_exit(fn());
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	return status;
+}
+
+#define EXITED_WITH_ZERO(status) (WIFEXITED(status) && WEXITSTATUS(status) == 0)
+
+static int hostname_is(const char *hostname)
+{
+	struct utsname uts;
+
+	if (uname(&uts) < 0)
+		return 0;
+	return strcmp(uts.nodename, hostname) == 0;
+}
+
+static char orig_hostname[65];
+
+FN_SETUP(hostname)
+{
+	struct utsname uts;
+
+	CHECK(uname(&uts));
+	strcpy(orig_hostname, uts.nodename);
+}
+END_SETUP()
+
+FN_TEST(invalid_args)
+{
+	char long_name[80];
+
+	memset(long_name, 'a', sizeof(long_name));
+
+	TEST_ERRNO(unshare(CLONE_VFORK), EINVAL);
+	TEST_ERRNO(unshare(1 << 2), EINVAL);
+	TEST_ERRNO(sethostname(long_name, sizeof(long_name)), EINVAL);
+	TEST_ERRNO(setns(-1, 0), EBADF);
+	TEST_ERRNO(setns(STDIN_FILENO, 0), EINVAL);
+}
+END_TEST()
+
+static int uts_child(void)
+{
+	if (unshare(CLONE_NEWUTS) < 0)
+		return 1;
+	if (!hostname_is(orig_hostname))
+		return 2;
+
+	if (sethostname("ns-test", 7) < 0)
+		return 3;
+	if (!hostname_is("ns-test"))
+		return 4;
+
+	if (setdomainname("ns-domain", 9) < 0)
+		return 5;
+	return 0;
+}
+
+FN_TEST(uts_ns)
+{
+	TEST_RES(run_in_child(uts_child), EXITED_WITH_ZERO(_ret));
+	TEST_RES(hostname_is(orig_hostname), _ret);
+}
+END_TEST()
+
+static int setns_child(void)
+{
+	int fd;
+
+	fd = open("/proc/self/ns/uts", O_RDONLY);
+	if (fd < 0)
+		return 1;
+
+	if (unshare(CLONE_NEWUTS) < 0)
+		return 2;
+	if (sethostname("ns-other", 8) < 0)
+		return 3;
+
+	if (setns(fd, CLONE_NEWIPC) != -1 || errno != EINVAL)
+		return 4;
+	if (setns(fd, CLONE_NEWUTS) < 0)
+		return 5;
+	if (!hostname_is(orig_hostname))
+		return 6;
+
+	close(fd);
+	return 0;
+}
+
+FN_TEST(setns)
+{
+	TEST_RES(run_in_child(setns_child), EXITED_WITH_ZERO(_ret));
+	TEST_RES(hostname_is(orig_hostname), _ret);
+}
+END_TEST()
+
+static int ipc_child(void)
+{
+	if (msgget(MSG_KEY, 0) < 0)
+		return 1;
+
+	if (unshare(CLONE_NEWIPC) < 0)
+		return 2;
+	if (msgget(MSG_KEY, 0) != -1 || errno != ENOENT)
+		return 3;
+
+	if (msgget(MSG_KEY, IPC_CREAT | 0600) < 0)
+		return 4;
+	return 0;
+}
+
+FN_TEST(ipc_ns)
+{
+	int msqid;
+
+	msqid = TEST_SUCC(msgget(MSG_KEY, IPC_CREAT | IPC_EXCL | 0600));
+	TEST_RES(run_in_child(ipc_child), EXITED_WITH_ZERO(_ret));
+	TEST_RES(msgget(MSG_KEY, 0), _ret == msqid);
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+static int pid_grandchild(void)
+{
+	char link[16];
+	ssize_t len;
+	int status;
+	pid_t pid;
+
+	if (getpid() != 1 || syscall(SYS_gettid) != 1)
+		return 1;
+	// The parent is outside the PID namespace.
+	if (getppid() != 0)
+		return 2;
+
+	// The procfs mounted outside still shows the outer PID namespace.
+	len = readlink("/proc/self", link, sizeof(link) - 1);
+	if (len <= 0)
+		return 3;
+	link[len] = '\0';
+	if (strcmp(link, "1") == 0)
+		return 4;
+
+	pid = fork();
+	if (pid < 0)
+		return 5;
+	if (pid == 0)
+		_exit(getpid() == 2 && getppid() == 1 ? 0 : 1);
+	if (pid != 2)
+		return 6;
+	if (waitpid(pid, &status, 0) != pid || !EXITED_WITH_ZERO(status))
+		return 7;
+	return 0;
+}
+
+static int pid_child(void)
+{
+	int status;
+	pid_t pid;
+
+	if (unshare(CLONE_NEWPID) < 0)
+		return 1;
+	// The PID namespace of the current process is not changed.
+	if (getpid() == 1)
+		return 2;
+
+	pid = fork();
+	if (pid < 0)
+		return 3;
+	if (pid == 0)
+		_exit(pid_grandchild());
+	if (pid == 1)
+		return 4;
+
+	if (waitpid(pid, &status, 0) != pid || !EXITED_WITH_ZERO(status))
+		return 5;
+
+	// No process can be created after the init process exits.
+	if (fork() != -1 || errno != ENOMEM)
+		return 6;
+	return 0;
+}
+
+FN_TEST(pid_ns)
+{
+	TEST_RES(run_in_child(pid_child), EXITED_WITH_ZERO(_ret));
+}
+END_TEST()
+
+static int mnt_child(void)
+{
+	if (unshare(CLONE_NEWNS) < 0)
+		return 1;
+	if (mount("proc", MNT_DIR, "proc", 0, NULL) < 0)
+		return 2;
+	if (access(MNT_DIR "/self", F_OK) < 0)
+		return 3;
+	return 0;
+}
+
+FN_TEST(mnt_ns)
+{
+	TEST_SUCC(mkdir(MNT_DIR, 0755));
+	TEST_RES(run_in_child(mnt_child), EXITED_WITH_ZERO(_ret));
+	// The mount is invisible outside the mount namespace.
+	TEST_ERRNO(access(MNT_DIR "/self", F_OK), ENOENT);
+	TEST_SUCC(rmdir(MNT_DIR));
+}
+END_TEST()
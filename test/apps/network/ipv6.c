@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+
+#include "test.h"
+
+#define S_PORT htons(0x1235)
+#define M_PORT htons(0x1236)
+
+static struct sockaddr_in6 lo_addr;
+static struct sockaddr_in6 mapped_addr;
+static struct sockaddr_in lo4_addr;
+
+FN_SETUP(general)
+{
+	lo_addr.sin6_family = AF_INET6;
+	lo_addr.sin6_port = S_PORT;
+	lo_addr.sin6_addr = in6addr_loopback;
+
+	mapped_addr.sin6_family = AF_INET6;
+	mapped_addr.sin6_port = M_PORT;
+	CHECK_WITH(inet_pton(AF_INET6, "::ffff:127.0.0.1",
+			     &mapped_addr.sin6_addr),
+		   _ret == 1);
+
+	lo4_addr.sin_family = AF_INET;
+	lo4_addr.sin_port = M_PORT;
+	CHECK_WITH(inet_pton(AF_INET, "127.0.0.1", &lo4_addr.sin_addr),
+		   _ret == 1);
+}
+END_SETUP()
+
+static int sk_listen;
+static int sk_mapped_listen;
+
+FN_SETUP(listen)
+{
+	sk_listen = CHECK(socket(AF_INET6, SOCK_STREAM, 0));
+	CHECK(bind(sk_listen, (struct sockaddr *)&lo_addr, sizeof(lo_addr)));
+	CHECK(listen(sk_listen, 2));
+
+	sk_mapped_listen = CHECK(socket(AF_INET6, SOCK_STREAM, 0));
+	CHECK(bind(sk_mapped_listen, (struct sockaddr *)&mapped_addr,
+		   sizeof(mapped_addr)));
+	CHECK(listen(sk_mapped_listen, 2));
+}
+END_SETUP()
+
+FN_TEST(loopback_stream)
+{
+	struct sockaddr_in6 saddr;
+	socklen_t addrlen;
+	int sk_client, sk_accepted;
+	char buf[4];
+
+	sk_client = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&lo_addr,
+			  sizeof(lo_addr)));
+
+	addrlen = sizeof(saddr);
+	sk_accepted = TEST_RES(accept(sk_listen, (struct sockaddr *)&saddr,
+				      &addrlen),
+			       addrlen == sizeof(saddr) &&
+				       saddr.sin6_family == AF_INET6 &&
+				       IN6_IS_ADDR_LOOPBACK(&saddr.sin6_addr));
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk_accepted, (struct sockaddr *)&saddr, &addrlen),
+		 addrlen == sizeof(saddr) && saddr.sin6_port == S_PORT &&
+			 IN6_IS_ADDR_LOOPBACK(&saddr.sin6_addr));
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getpeername(sk_client, (struct sockaddr *)&saddr, &addrlen),
+		 addrlen == sizeof(saddr) && saddr.sin6_port == S_PORT &&
+			 IN6_IS_ADDR_LOOPBACK(&saddr.sin6_addr));
+
+	TEST_RES(write(sk_client, "ping", 4), _ret == 4);
+	TEST_RES(read(sk_accepted, buf, sizeof(buf)),
+		 _ret == 4 && memcmp(buf, "ping", 4) == 0);
+
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+}
+END_TEST()
+
+FN_TEST(loopback_dgram)
+{
+	struct sockaddr_in6 saddr;
+	socklen_t addrlen;
+	int sk_recv, sk_send;
+	char buf[4];
+
+	sk_recv = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	TEST_SUCC(bind(sk_recv, (struct sockaddr *)&lo_addr, sizeof(lo_addr)));
+
+	sk_send = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	TEST_RES(sendto(sk_send, "pong", 4, 0, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr)),
+		 _ret == 4);
+
+	addrlen = sizeof(saddr);
+	TEST_RES(recvfrom(sk_recv, buf, sizeof(buf), 0,
+			  (struct sockaddr *)&saddr, &addrlen),
+		 _ret == 4 && memcmp(buf, "pong", 4) == 0 &&
+			 addrlen == sizeof(saddr) &&
+			 IN6_IS_ADDR_LOOPBACK(&saddr.sin6_addr));
+
+	TEST_SUCC(close(sk_send));
+	TEST_SUCC(close(sk_recv));
+}
+END_TEST()
+
+FN_TEST(ipv4_mapped)
+{
+	struct sockaddr_in6 saddr;
+	socklen_t addrlen;
+	int sk_client, sk_accepted;
+
+	sk_client = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_client, (struct sockaddr *)&lo4_addr,
+			  sizeof(lo4_addr)));
+
+	addrlen = sizeof(saddr);
+	sk_accepted = TEST_RES(
+		accept(sk_mapped_listen, (struct sockaddr *)&saddr, &addrlen),
+		addrlen == sizeof(saddr) && saddr.sin6_family == AF_INET6 &&
+			IN6_IS_ADDR_V4MAPPED(&saddr.sin6_addr));
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk_accepted, (struct sockaddr *)&saddr, &addrlen),
+		 addrlen == sizeof(saddr) && saddr.sin6_port == M_PORT &&
+			 memcmp(&saddr.sin6_addr, &mapped_addr.sin6_addr,
+				sizeof(saddr.sin6_addr)) == 0);
+
+	TEST_SUCC(close(sk_accepted));
+	TEST_SUCC(close(sk_client));
+}
+END_TEST()
+
+FN_TEST(v6only)
+{
+	int sk, sk_inet, opt;
+	socklen_t optlen;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+
+	optlen = sizeof(opt);
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, &optlen),
+		 optlen == sizeof(opt) && opt == 0);
+
+	opt = 1;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, sizeof(opt)));
+
+	optlen = sizeof(opt);
+	TEST_RES(getsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, &optlen),
+		 optlen == sizeof(opt) && opt == 1);
+
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&mapped_addr,
+			sizeof(mapped_addr)),
+		   EINVAL);
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&mapped_addr,
+			   sizeof(mapped_addr)),
+		   ENETUNREACH);
+
+	TEST_SUCC(close(sk));
+
+	sk_inet = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	optlen = sizeof(opt);
+	TEST_ERRNO(getsockopt(sk_inet, IPPROTO_IPV6, IPV6_V6ONLY, &opt,
+			      &optlen),
+		   EOPNOTSUPP);
+	TEST_SUCC(close(sk_inet));
+}
+END_TEST()
+
+FN_TEST(v6only_after_bind)
+{
+	struct sockaddr_in6 saddr = lo_addr;
+	int sk, opt = 1;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+
+	saddr.sin6_port = 0;
+	TEST_SUCC(bind(sk, (struct sockaddr *)&saddr, sizeof(saddr)));
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IPV6, IPV6_V6ONLY, &opt, sizeof(opt)),
+		   EINVAL);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(wildcard)
+{
+	struct sockaddr_in6 saddr = { .sin6_family = AF_INET6 };
+	socklen_t addrlen;
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk, (struct sockaddr *)&saddr, &addrlen),
+		 addrlen == sizeof(saddr) && saddr.sin6_family == AF_INET6 &&
+			 saddr.sin6_port == 0 &&
+			 IN6_IS_ADDR_UNSPECIFIED(&saddr.sin6_addr));
+
+	saddr.sin6_addr = in6addr_any;
+	saddr.sin6_port = 0;
+	TEST_SUCC(bind(sk, (struct sockaddr *)&saddr, sizeof(saddr)));
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk, (struct sockaddr *)&saddr, &addrlen),
+		 addrlen == sizeof(saddr) && saddr.sin6_port != 0 &&
+			 IN6_IS_ADDR_UNSPECIFIED(&saddr.sin6_addr));
+
+	TEST_SUCC(listen(sk, 1));
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(bad_family)
+{
+	struct sockaddr_in6 saddr = lo_addr;
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+
+	saddr.sin6_family = AF_INET;
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&saddr, sizeof(saddr)),
+		   EAFNOSUPPORT);
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&lo_addr, sizeof(lo_addr) - 5),
+		   EINVAL);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_mapped_listen));
+	CHECK(close(sk_listen));
+}
+END_SETUP()
@@ -0,0 +1,330 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <sys/socket.h>
+#include <sys/ioctl.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+
+#include "test.h"
+
+#define TEST_ADDR "127.1.2.3"
+
+static int sk_nl;
+static int sk_inet;
+static unsigned int lo_index;
+static unsigned int seq;
+
+static char buffer[8192];
+
+FN_SETUP(sockets)
+{
+	struct sockaddr_nl addr = { .nl_family = AF_NETLINK };
+
+	sk_nl = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	CHECK(bind(sk_nl, (struct sockaddr *)&addr, sizeof(addr)));
+
+	sk_inet = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+}
+END_SETUP()
+
+FN_SETUP(lo_index)
+{
+	struct ifreq ifr = { .ifr_name = "lo" };
+
+	CHECK(ioctl(sk_inet, SIOCGIFINDEX, &ifr));
+	lo_index = ifr.ifr_ifindex;
+}
+END_SETUP()
+
+static int send_request(unsigned short type, unsigned short flags,
+			const void *payload, size_t len)
+{
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buffer;
+
+	hdr->nlmsg_len = NLMSG_LENGTH(len);
+	hdr->nlmsg_type = type;
+	hdr->nlmsg_flags = NLM_F_REQUEST | flags;
+	hdr->nlmsg_seq = ++seq;
+	hdr->nlmsg_pid = 0;
+	memcpy(NLMSG_DATA(hdr), payload, len);
+
+	return send(sk_nl, buffer, hdr->nlmsg_len, 0);
+}
+
+/*
+ * Receives the acknowledgment of the last request, and returns the error
+ * number in it.
+ */
+static int recv_ack(void)
+{
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buffer;
+	struct nlmsgerr *err = NLMSG_DATA(hdr);
+	int len;
+
+	len = recv(sk_nl, buffer, sizeof(buffer), 0);
+	if (len < 0)
+		return -1;
+	if (!NLMSG_OK(hdr, len) || hdr->nlmsg_type != NLMSG_ERROR ||
+	    hdr->nlmsg_seq != seq || err->msg.nlmsg_seq != seq) {
+		errno = EPROTO;
+		return -1;
+	}
+
+	return -err->error;
+}
+
+/*
+ * Receives the multipart responses of the last dump request, and calls the
+ * callback with each of them. Returns the number of the responses.
+ */
+static int recv_dump(void (*callback)(struct nlmsghdr *hdr, void *data),
+		     void *data)
+{
+	struct nlmsghdr *hdr;
+	int len, count = 0;
+
+	for (;;) {
+		len = recv(sk_nl, buffer, sizeof(buffer), 0);
+		if (len < 0)
+			return -1;
+
+		for (hdr = (struct nlmsghdr *)buffer; NLMSG_OK(hdr, len);
+		     hdr = NLMSG_NEXT(hdr, len)) {
+			if (hdr->nlmsg_seq != seq ||
+			    !(hdr->nlmsg_flags & NLM_F_MULTI)) {
+				errno = EPROTO;
+				return -1;
+			}
+			if (hdr->nlmsg_type == NLMSG_DONE)
+				return count;
+
+			callback(hdr, data);
+			count++;
+		}
+	}
+}
+
+static struct rtattr *find_attr(struct rtattr *rta, int len,
+				unsigned short type)
+{
+	for (; RTA_OK(rta, len); rta = RTA_NEXT(rta, len))
+		if (rta->rta_type == type)
+			return rta;
+	return NULL;
+}
+
+FN_TEST(getsockname)
+{
+	struct sockaddr_nl addr;
+	socklen_t addrlen = sizeof(addr);
+
+	TEST_RES(getsockname(sk_nl, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == sizeof(addr) && addr.nl_family == AF_NETLINK &&
+			 addr.nl_pid != 0 && addr.nl_groups == 0);
+}
+END_TEST()
+
+struct link_info {
+	int found_lo;
+	int lo_flags;
+	int lo_mtu;
+};
+
+static void check_link(struct nlmsghdr *hdr, void *data)
+{
+	struct link_info *info = data;
+	struct ifinfomsg *ifi = NLMSG_DATA(hdr);
+	struct rtattr *name, *mtu;
+
+	if (hdr->nlmsg_type != RTM_NEWLINK || ifi->ifi_index != (int)lo_index)
+		return;
+
+	name = find_attr(IFLA_RTA(ifi), IFLA_PAYLOAD(hdr), IFLA_IFNAME);
+	mtu = find_attr(IFLA_RTA(ifi), IFLA_PAYLOAD(hdr), IFLA_MTU);
+	if (name == NULL || strcmp(RTA_DATA(name), "lo") != 0 || mtu == NULL)
+		return;
+
+	info->found_lo = 1;
+	info->lo_flags = ifi->ifi_flags;
+	info->lo_mtu = *(int *)RTA_DATA(mtu);
+}
+
+FN_TEST(dump_links)
+{
+	struct rtgenmsg gen = { .rtgen_family = AF_UNSPEC };
+	struct link_info info = { 0 };
+	struct ifreq ifr = { .ifr_name = "lo" };
+
+	TEST_SUCC(send_request(RTM_GETLINK, NLM_F_DUMP, &gen, sizeof(gen)));
+	TEST_RES(recv_dump(check_link, &info),
+		 _ret >= 2 && info.found_lo &&
+			 (info.lo_flags & (IFF_UP | IFF_LOOPBACK)) ==
+				 (IFF_UP | IFF_LOOPBACK));
+
+	TEST_RES(ioctl(sk_inet, SIOCGIFMTU, &ifr), ifr.ifr_mtu == info.lo_mtu);
+}
+END_TEST()
+
+FN_TEST(get_link_by_name)
+{
+	struct {
+		struct ifinfomsg ifi;
+		struct rtattr rta;
+		char name[4];
+	} req = {
+		.ifi = { .ifi_family = AF_UNSPEC },
+		.rta = { .rta_len = RTA_LENGTH(3), .rta_type = IFLA_IFNAME },
+		.name = "lo",
+	};
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buffer;
+	struct ifinfomsg *ifi = NLMSG_DATA(hdr);
+
+	TEST_SUCC(send_request(RTM_GETLINK, 0, &req, sizeof(req)));
+	TEST_RES(recv(sk_nl, buffer, sizeof(buffer), 0),
+		 NLMSG_OK(hdr, _ret) && hdr->nlmsg_type == RTM_NEWLINK &&
+			 hdr->nlmsg_seq == seq && ifi->ifi_index == (int)lo_index);
+
+	strcpy(req.name, "xx");
+	TEST_SUCC(send_request(RTM_GETLINK, 0, &req, sizeof(req)));
+	TEST_RES(recv_ack(), _ret == ENODEV);
+}
+END_TEST()
+
+struct addr_info {
+	int found_lo;
+	int found_test;
+};
+
+static void check_addr(struct nlmsghdr *hdr, void *data)
+{
+	struct addr_info *info = data;
+	struct ifaddrmsg *ifa = NLMSG_DATA(hdr);
+	struct rtattr *local;
+	struct in_addr addr;
+
+	if (hdr->nlmsg_type != RTM_NEWADDR || ifa->ifa_family != AF_INET ||
+	    ifa->ifa_index != lo_index)
+		return;
+
+	local = find_attr(IFA_RTA(ifa), IFA_PAYLOAD(hdr), IFA_LOCAL);
+	if (local == NULL)
+		return;
+	memcpy(&addr, RTA_DATA(local), sizeof(addr));
+
+	if (addr.s_addr == htonl(INADDR_LOOPBACK) && ifa->ifa_prefixlen == 8)
+		info->found_lo = 1;
+	if (addr.s_addr == inet_addr(TEST_ADDR) && ifa->ifa_prefixlen == 8)
+		info->found_test = 1;
+}
+
+static int dump_addrs(struct addr_info *info)
+{
+	struct rtgenmsg gen = { .rtgen_family = AF_INET };
+
+	memset(info, 0, sizeof(*info));
+	if (send_request(RTM_GETADDR, NLM_F_DUMP, &gen, sizeof(gen)) < 0)
+		return -1;
+	return recv_dump(check_addr, info);
+}
+
+static int change_addr(unsigned short type, unsigned short flags,
+		       unsigned char scope)
+{
+	struct {
+		struct ifaddrmsg ifa;
+		struct rtattr rta;
+		struct in_addr addr;
+	} req = {
+		.ifa = { .ifa_family = AF_INET,
+			 .ifa_prefixlen = 8,
+			 .ifa_scope = scope,
+			 .ifa_index = lo_index },
+		.rta = { .rta_len = RTA_LENGTH(sizeof(struct in_addr)),
+			 .rta_type = IFA_LOCAL },
+		.addr = { .s_addr = inet_addr(TEST_ADDR) },
+	};
+
+	if (send_request(type, NLM_F_ACK | flags, &req, sizeof(req)) < 0)
+		return -1;
+	return recv_ack();
+}
+
+FN_TEST(add_and_del_addr)
+{
+	struct addr_info info;
+
+	TEST_RES(dump_addrs(&info), info.found_lo && !info.found_test);
+
+	// Loopback addresses must have the host scope
+	TEST_RES(change_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+			     RT_SCOPE_UNIVERSE),
+		 _ret == EINVAL);
+
+	TEST_RES(change_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+			     RT_SCOPE_HOST),
+		 _ret == 0);
+	TEST_RES(dump_addrs(&info), info.found_lo && info.found_test);
+	TEST_RES(change_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+			     RT_SCOPE_HOST),
+		 _ret == EEXIST);
+
+	TEST_RES(change_addr(RTM_DELADDR, 0, RT_SCOPE_HOST), _ret == 0);
+	TEST_RES(dump_addrs(&info), info.found_lo && !info.found_test);
+	TEST_RES(change_addr(RTM_DELADDR, 0, RT_SCOPE_HOST),
+		 _ret == EADDRNOTAVAIL);
+}
+END_TEST()
+
+FN_TEST(get_route)
+{
+	struct {
+		struct rtmsg rtm;
+		struct rtattr rta;
+		struct in_addr addr;
+	} req = {
+		.rtm = { .rtm_family = AF_INET, .rtm_dst_len = 32 },
+		.rta = { .rta_len = RTA_LENGTH(sizeof(struct in_addr)),
+			 .rta_type = RTA_DST },
+		.addr = { .s_addr = htonl(INADDR_LOOPBACK) },
+	};
+	struct nlmsghdr *hdr = (struct nlmsghdr *)buffer;
+	struct rtmsg *rtm = NLMSG_DATA(hdr);
+	struct rtattr *oif;
+
+	TEST_SUCC(send_request(RTM_GETROUTE, 0, &req, sizeof(req)));
+	TEST_RES(recv(sk_nl, buffer, sizeof(buffer), 0),
+		 NLMSG_OK(hdr, _ret) && hdr->nlmsg_type == RTM_NEWROUTE &&
+			 (oif = find_attr(RTM_RTA(rtm), RTM_PAYLOAD(hdr),
+					  RTA_OIF)) != NULL &&
+			 *(unsigned int *)RTA_DATA(oif) == lo_index);
+}
+END_TEST()
+
+FN_TEST(ioctl_lo)
+{
+	struct ifreq ifr = { .ifr_ifindex = lo_index };
+	struct sockaddr_in *addr = (struct sockaddr_in *)&ifr.ifr_addr;
+
+	TEST_RES(ioctl(sk_inet, SIOCGIFNAME, &ifr),
+		 strcmp(ifr.ifr_name, "lo") == 0);
+
+	TEST_RES(ioctl(sk_inet, SIOCGIFFLAGS, &ifr),
+		 (ifr.ifr_flags & (IFF_UP | IFF_LOOPBACK)) ==
+			 (IFF_UP | IFF_LOOPBACK));
+
+	TEST_RES(ioctl(sk_inet, SIOCGIFADDR, &ifr),
+		 addr->sin_family == AF_INET &&
+			 addr->sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+
+	TEST_RES(ioctl(sk_inet, SIOCGIFNETMASK, &ifr),
+		 addr->sin_family == AF_INET &&
+			 addr->sin_addr.s_addr == htonl(0xff000000));
+
+	strcpy(ifr.ifr_name, "xx");
+	TEST_ERRNO(ioctl(sk_inet, SIOCGIFFLAGS, &ifr), ENODEV);
+}
+END_TEST()
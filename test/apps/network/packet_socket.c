@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <unistd.h>
+#include <sys/socket.h>
+#include <net/if.h>
+#include <arpa/inet.h>
+#include <linux/if_ether.h>
+#include <linux/if_packet.h>
+
+#include "test.h"
+
+// The protocol reserved for local experiments
+#define TEST_PROTO 0x88b5
+#define PAYLOAD "hello packet"
+#define FRAME_LEN (ETH_HLEN + sizeof(PAYLOAD))
+
+static int lo_index;
+static char frame[FRAME_LEN];
+static char buf[256];
+
+static struct sockaddr_ll lo_addr(int protocol)
+{
+	struct sockaddr_ll addr;
+
+	memset(&addr, 0, sizeof(addr));
+	addr.sll_family = AF_PACKET;
+	addr.sll_protocol = htons(protocol);
+	addr.sll_ifindex = lo_index;
+	// Like Linux, the hardware address of the loopback iface is zeros
+	addr.sll_halen = ETH_ALEN;
+
+	return addr;
+}
+
+static int new_packet_socket(int type, int protocol)
+{
+	struct sockaddr_ll addr = lo_addr(protocol);
+	int sk;
+
+	sk = socket(AF_PACKET, type, htons(protocol));
+	if (sk < 0)
+		return -1;
+
+	if (bind(sk, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+		close(sk);
+		return -1;
+	}
+
+	return sk;
+}
+
+// Receives the next test frame, skipping the frames of other protocols
+static int recv_test_frame(int sk, struct sockaddr_ll *addr)
+{
+	socklen_t addrlen;
+	ssize_t len;
+
+	for (;;) {
+		addrlen = sizeof(*addr);
+		len = recvfrom(sk, buf, sizeof(buf), MSG_DONTWAIT,
+			       (struct sockaddr *)addr, &addrlen);
+		if (len < 0 || addr->sll_protocol == htons(TEST_PROTO))
+			return len;
+	}
+}
+
+FN_SETUP(general)
+{
+	struct ethhdr *eth = (struct ethhdr *)frame;
+
+	lo_index = CHECK(if_nametoindex("lo"));
+
+	memset(eth, 0, sizeof(*eth));
+	eth->h_proto = htons(TEST_PROTO);
+	memcpy(frame + ETH_HLEN, PAYLOAD, sizeof(PAYLOAD));
+}
+END_SETUP()
+
+FN_TEST(invalid)
+{
+	struct sockaddr_ll addr = lo_addr(TEST_PROTO);
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_PACKET, SOCK_RAW, htons(TEST_PROTO)));
+
+	// The iface is not specified
+	TEST_ERRNO(send(sk, frame, FRAME_LEN, 0), ENXIO);
+
+	addr.sll_ifindex = 0x7fff;
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&addr, sizeof(addr)), ENODEV);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw)
+{
+	struct sockaddr_ll addr;
+	socklen_t addrlen;
+	int sk;
+
+	sk = TEST_SUCC(new_packet_socket(SOCK_RAW, TEST_PROTO));
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(sk, (struct sockaddr *)&addr, &addrlen),
+		 addr.sll_family == AF_PACKET &&
+			 addr.sll_protocol == htons(TEST_PROTO) &&
+			 addr.sll_ifindex == lo_index);
+
+	TEST_RES(send(sk, frame, FRAME_LEN, 0), _ret == FRAME_LEN);
+
+	// Only the received frame matches the protocol, the sent frame does not
+	TEST_RES(recv_test_frame(sk, &addr),
+		 _ret == FRAME_LEN && memcmp(buf, frame, FRAME_LEN) == 0 &&
+			 addr.sll_ifindex == lo_index &&
+			 addr.sll_pkttype == PACKET_HOST);
+	TEST_ERRNO(recv_test_frame(sk, &addr), EAGAIN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(dgram)
+{
+	struct sockaddr_ll addr = lo_addr(TEST_PROTO);
+	int sk;
+
+	sk = TEST_SUCC(new_packet_socket(SOCK_DGRAM, TEST_PROTO));
+
+	TEST_RES(sendto(sk, PAYLOAD, sizeof(PAYLOAD), 0,
+			(struct sockaddr *)&addr, sizeof(addr)),
+		 _ret == sizeof(PAYLOAD));
+
+	// The frame is received without the Ethernet header
+	TEST_RES(recv_test_frame(sk, &addr),
+		 _ret == sizeof(PAYLOAD) &&
+			 memcmp(buf, PAYLOAD, sizeof(PAYLOAD)) == 0 &&
+			 addr.sll_ifindex == lo_index &&
+			 addr.sll_pkttype == PACKET_HOST &&
+			 addr.sll_halen == ETH_ALEN);
+	TEST_ERRNO(recv_test_frame(sk, &addr), EAGAIN);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(all_protocols)
+{
+	struct sockaddr_ll addr;
+	int sk_send, sk_all;
+
+	// A socket with a zero protocol receives nothing
+	sk_send = TEST_SUCC(new_packet_socket(SOCK_RAW, 0));
+	sk_all = TEST_SUCC(new_packet_socket(SOCK_RAW, ETH_P_ALL));
+
+	TEST_RES(send(sk_send, frame, FRAME_LEN, 0), _ret == FRAME_LEN);
+
+	// Both the sent frame and the received frame are captured
+	TEST_RES(recv_test_frame(sk_all, &addr),
+		 _ret == FRAME_LEN && memcmp(buf, frame, FRAME_LEN) == 0 &&
+			 addr.sll_pkttype == PACKET_OUTGOING);
+	TEST_RES(recv_test_frame(sk_all, &addr),
+		 _ret == FRAME_LEN && memcmp(buf, frame, FRAME_LEN) == 0 &&
+			 addr.sll_pkttype == PACKET_HOST);
+	TEST_ERRNO(recv_test_frame(sk_all, &addr), EAGAIN);
+
+	TEST_ERRNO(recv(sk_send, buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+
+	TEST_SUCC(close(sk_send));
+	TEST_SUCC(close(sk_all));
+}
+END_TEST()
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/ip_icmp.h>
+#include <arpa/inet.h>
+
+#include "test.h"
+
+#define ECHO_SEQ 0x4321
+
+static struct sockaddr_in lo_addr;
+
+FN_SETUP(general)
+{
+	lo_addr.sin_family = AF_INET;
+	CHECK_WITH(inet_pton(AF_INET, "127.0.0.1", &lo_addr.sin_addr),
+		   _ret == 1);
+}
+END_SETUP()
+
+static unsigned short checksum(const void *data, size_t len)
+{
+	const unsigned short *words = data;
+	unsigned int sum = 0;
+
+	for (; len > 1; len -= 2)
+		sum += *words++;
+	if (len == 1)
+		sum += *(const unsigned char *)words;
+
+	while (sum >> 16)
+		sum = (sum & 0xffff) + (sum >> 16);
+
+	return ~sum;
+}
+
+static void fill_echo(struct icmphdr *icmp, int type)
+{
+	memset(icmp, 0, sizeof(*icmp));
+	icmp->type = type;
+	icmp->un.echo.sequence = htons(ECHO_SEQ);
+}
+
+FN_TEST(ping_loopback)
+{
+	struct sockaddr_in saddr;
+	socklen_t addrlen;
+	struct icmphdr icmp;
+	char buf[64];
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+
+	fill_echo(&icmp, ICMP_ECHO);
+	TEST_RES(sendto(sk, &icmp, sizeof(icmp), 0, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr)),
+		 _ret == sizeof(icmp));
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk, (struct sockaddr *)&saddr, &addrlen),
+		 addrlen == sizeof(saddr) && saddr.sin_port != 0);
+
+	addrlen = sizeof(saddr);
+	TEST_RES(recvfrom(sk, buf, sizeof(buf), 0, (struct sockaddr *)&saddr,
+			  &addrlen),
+		 _ret == sizeof(icmp) && addrlen == sizeof(saddr) &&
+			 saddr.sin_addr.s_addr == lo_addr.sin_addr.s_addr);
+	memcpy(&icmp, buf, sizeof(icmp));
+	TEST_RES(icmp.type, _ret == ICMP_ECHOREPLY);
+	TEST_RES(ntohs(icmp.un.echo.sequence), _ret == ECHO_SEQ);
+
+	addrlen = sizeof(saddr);
+	TEST_RES(getsockname(sk, (struct sockaddr *)&saddr, &addrlen),
+		 saddr.sin_port == icmp.un.echo.id);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(ping_bad_message)
+{
+	struct icmphdr icmp;
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+
+	fill_echo(&icmp, ICMP_ECHOREPLY);
+	TEST_ERRNO(sendto(sk, &icmp, sizeof(icmp), 0,
+			  (struct sockaddr *)&lo_addr, sizeof(lo_addr)),
+		   EINVAL);
+
+	fill_echo(&icmp, ICMP_ECHO);
+	TEST_ERRNO(sendto(sk, &icmp, 4, 0, (struct sockaddr *)&lo_addr,
+			  sizeof(lo_addr)),
+		   EINVAL);
+
+	TEST_ERRNO(send(sk, &icmp, sizeof(icmp), 0), EDESTADDRREQ);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw_icmp)
+{
+	struct sockaddr_in saddr;
+	socklen_t addrlen;
+	struct icmphdr icmp;
+	struct iphdr *ip;
+	char buf[128];
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_ICMP));
+
+	fill_echo(&icmp, ICMP_ECHO);
+	icmp.un.echo.id = htons(0x1234);
+	icmp.checksum = checksum(&icmp, sizeof(icmp));
+	TEST_RES(sendto(sk, &icmp, sizeof(icmp), 0, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr)),
+		 _ret == sizeof(icmp));
+
+	// The raw socket receives the echo request that it sent.
+	addrlen = sizeof(saddr);
+	TEST_RES(recvfrom(sk, buf, sizeof(buf), 0, (struct sockaddr *)&saddr,
+			  &addrlen),
+		 _ret == sizeof(struct iphdr) + sizeof(icmp) &&
+			 addrlen == sizeof(saddr) &&
+			 saddr.sin_addr.s_addr == lo_addr.sin_addr.s_addr);
+	ip = (struct iphdr *)buf;
+	TEST_RES(ip->version, _ret == 4);
+	TEST_RES(ip->protocol, _ret == IPPROTO_ICMP);
+	TEST_RES(ip->daddr, _ret == lo_addr.sin_addr.s_addr);
+	memcpy(&icmp, buf + sizeof(struct iphdr), sizeof(icmp));
+	TEST_RES(icmp.type, _ret == ICMP_ECHO);
+
+	// The raw socket also receives the echo reply from the kernel.
+	TEST_RES(recv(sk, buf, sizeof(buf), 0),
+		 _ret == sizeof(struct iphdr) + sizeof(icmp));
+	memcpy(&icmp, buf + sizeof(struct iphdr), sizeof(icmp));
+	TEST_RES(icmp.type, _ret == ICMP_ECHOREPLY);
+	TEST_RES(ntohs(icmp.un.echo.id), _ret == 0x1234);
+	TEST_RES(ntohs(icmp.un.echo.sequence), _ret == ECHO_SEQ);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw_hdrincl)
+{
+	int sk, opt;
+	socklen_t optlen;
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_ICMP));
+
+	optlen = sizeof(opt);
+	TEST_RES(getsockopt(sk, IPPROTO_IP, IP_HDRINCL, &opt, &optlen),
+		 optlen == sizeof(opt) && opt == 0);
+
+	opt = 1;
+	TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_HDRINCL, &opt, sizeof(opt)));
+
+	optlen = sizeof(opt);
+	TEST_RES(getsockopt(sk, IPPROTO_IP, IP_HDRINCL, &opt, &optlen),
+		 optlen == sizeof(opt) && opt == 1);
+
+	TEST_SUCC(close(sk));
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_RAW));
+
+	optlen = sizeof(opt);
+	TEST_RES(getsockopt(sk, IPPROTO_IP, IP_HDRINCL, &opt, &optlen),
+		 optlen == sizeof(opt) && opt == 1);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(raw_bad_protocol)
+{
+	TEST_ERRNO(socket(AF_INET, SOCK_RAW, 0), EPROTONOSUPPORT);
+	TEST_ERRNO(socket(AF_INET, SOCK_RAW, -1), EINVAL);
+}
+END_TEST()
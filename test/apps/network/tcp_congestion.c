@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netinet/tcp.h>
+#include <arpa/inet.h>
+
+#include "test.h"
+
+#define S_PORT htons(0x1242)
+
+static struct sockaddr_in sk_addr;
+
+static int sk_unbound;
+static int sk_listen;
+static int sk_connect;
+static int sk_accept;
+
+static char buf[65536];
+static char default_name[16];
+
+FN_SETUP(general)
+{
+	sk_addr.sin_family = AF_INET;
+	sk_addr.sin_port = S_PORT;
+	CHECK(inet_aton("127.0.0.1", &sk_addr.sin_addr));
+
+	sk_unbound = CHECK(socket(PF_INET, SOCK_STREAM, 0));
+
+	sk_listen = CHECK(socket(PF_INET, SOCK_STREAM, 0));
+	CHECK(bind(sk_listen, (struct sockaddr *)&sk_addr, sizeof(sk_addr)));
+	CHECK(listen(sk_listen, 2));
+}
+END_SETUP()
+
+FN_TEST(congestion_default)
+{
+	char name[16];
+	socklen_t len;
+
+	len = sizeof(default_name);
+	TEST_RES(getsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION,
+			    default_name, &len),
+		 len == sizeof(default_name) && strlen(default_name) > 0);
+
+	// The name is truncated if the buffer is too small.
+	len = 2;
+	TEST_RES(getsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION, name,
+			    &len),
+		 len == 2 && memcmp(name, default_name, 2) == 0);
+}
+END_TEST()
+
+FN_TEST(congestion_set)
+{
+	char name[16];
+	socklen_t len;
+
+	TEST_SUCC(setsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION, "reno",
+			     4));
+	len = sizeof(name);
+	TEST_RES(getsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION, name,
+			    &len),
+		 len == sizeof(name) && strcmp(name, "reno") == 0);
+
+	// The name can be terminated by a null byte.
+	TEST_SUCC(setsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION,
+			     "cubic\0reno", 10));
+	len = sizeof(name);
+	TEST_RES(getsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION, name,
+			    &len),
+		 len == sizeof(name) && strcmp(name, "cubic") == 0);
+
+	TEST_ERRNO(setsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION,
+			      "unknown", 7),
+		   ENOENT);
+	TEST_ERRNO(setsockopt(sk_unbound, IPPROTO_TCP, TCP_CONGESTION, "reno",
+			      0),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(congestion_inherit)
+{
+	char name[16];
+	socklen_t len;
+
+	TEST_SUCC(setsockopt(sk_listen, IPPROTO_TCP, TCP_CONGESTION, "reno",
+			     4));
+
+	sk_connect = TEST_SUCC(socket(PF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_connect, (struct sockaddr *)&sk_addr,
+			  sizeof(sk_addr)));
+	sk_accept = TEST_SUCC(accept(sk_listen, NULL, NULL));
+
+	len = sizeof(name);
+	TEST_RES(getsockopt(sk_accept, IPPROTO_TCP, TCP_CONGESTION, name,
+			    &len),
+		 len == sizeof(name) && strcmp(name, "reno") == 0);
+
+	len = sizeof(name);
+	TEST_RES(getsockopt(sk_connect, IPPROTO_TCP, TCP_CONGESTION, name,
+			    &len),
+		 len == sizeof(name) && strcmp(name, default_name) == 0);
+}
+END_TEST()
+
+FN_TEST(info_unconnected)
+{
+	struct tcp_info info;
+	socklen_t len;
+
+	len = sizeof(info);
+	TEST_RES(getsockopt(sk_unbound, IPPROTO_TCP, TCP_INFO, &info, &len),
+		 len == sizeof(info) && info.tcpi_state == TCP_CLOSE);
+
+	len = sizeof(info);
+	TEST_RES(getsockopt(sk_listen, IPPROTO_TCP, TCP_INFO, &info, &len),
+		 len == sizeof(info) && info.tcpi_state == TCP_LISTEN);
+
+	// The information is truncated if the buffer is too small.
+	len = 1;
+	TEST_RES(getsockopt(sk_listen, IPPROTO_TCP, TCP_INFO, &info, &len),
+		 len == 1);
+
+	TEST_ERRNO(setsockopt(sk_listen, IPPROTO_TCP, TCP_INFO, &info,
+			      sizeof(info)),
+		   ENOPROTOOPT);
+}
+END_TEST()
+
+FN_TEST(info_connected)
+{
+	struct tcp_info info;
+	socklen_t len;
+
+	TEST_RES(send(sk_connect, buf, sizeof(buf), 0), _ret == sizeof(buf));
+	TEST_RES(recv(sk_accept, buf, sizeof(buf), MSG_WAITALL),
+		 _ret == sizeof(buf));
+	usleep(10000);
+
+	len = sizeof(info);
+	TEST_RES(getsockopt(sk_connect, IPPROTO_TCP, TCP_INFO, &info, &len),
+		 len == sizeof(info) && info.tcpi_state == TCP_ESTABLISHED &&
+			 info.tcpi_ca_state == TCP_CA_Open &&
+			 info.tcpi_snd_mss > 0 && info.tcpi_snd_cwnd >= 4 &&
+			 info.tcpi_unacked == 0);
+}
+END_TEST()
+
+FN_TEST(congestion_switch)
+{
+	static const char *const names[] = { "reno", "cubic", "bbr" };
+	char name[16];
+	socklen_t len;
+	int i;
+
+	for (i = 0; i < sizeof(names) / sizeof(names[0]); ++i) {
+		TEST_SUCC(setsockopt(sk_connect, IPPROTO_TCP, TCP_CONGESTION,
+				     names[i], strlen(names[i])));
+		len = sizeof(name);
+		TEST_RES(getsockopt(sk_connect, IPPROTO_TCP, TCP_CONGESTION,
+				    name, &len),
+			 len == sizeof(name) && strcmp(name, names[i]) == 0);
+
+		TEST_RES(send(sk_connect, buf, sizeof(buf), 0),
+			 _ret == sizeof(buf));
+		TEST_RES(recv(sk_accept, buf, sizeof(buf), MSG_WAITALL),
+			 _ret == sizeof(buf));
+	}
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_unbound));
+	CHECK(close(sk_listen));
+	CHECK(close(sk_connect));
+	CHECK(close(sk_accept));
+}
+END_SETUP()
@@ -0,0 +1,403 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <unistd.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <net/if.h>
+#include <net/if_arp.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/ip_icmp.h>
+#include <netinet/udp.h>
+#include <arpa/inet.h>
+#include <linux/if_tun.h>
+#include <linux/if_ether.h>
+
+#include "test.h"
+
+#define TUN_NAME "tuntest0"
+#define LOCAL_ADDR "10.0.7.1"
+#define PEER_ADDR "10.0.7.2"
+#define PEER_PORT 4321
+#define ECHO_SEQ 0x1234
+
+static int tun_fd;
+static int sk_ctl;
+
+static unsigned short checksum(const void *data, size_t len, unsigned int sum)
+{
+	const unsigned short *words = data;
+
+	for (; len > 1; len -= 2)
+		sum += *words++;
+	if (len == 1)
+		sum += *(const unsigned char *)words;
+
+	while (sum >> 16)
+		sum = (sum & 0xffff) + (sum >> 16);
+
+	return ~sum;
+}
+
+static void fill_ip(struct iphdr *ip, int protocol, size_t payload_len)
+{
+	memset(ip, 0, sizeof(*ip));
+	ip->version = 4;
+	ip->ihl = sizeof(*ip) / 4;
+	ip->tot_len = htons(sizeof(*ip) + payload_len);
+	ip->ttl = 64;
+	ip->protocol = protocol;
+	ip->saddr = inet_addr(PEER_ADDR);
+	ip->daddr = inet_addr(LOCAL_ADDR);
+	ip->check = checksum(ip, sizeof(*ip), 0);
+}
+
+static struct ifreq new_ifreq(const char *name, short flags)
+{
+	struct ifreq ifr;
+
+	memset(&ifr, 0, sizeof(ifr));
+	strncpy(ifr.ifr_name, name, IFNAMSIZ - 1);
+	ifr.ifr_flags = flags;
+
+	return ifr;
+}
+
+FN_SETUP(open)
+{
+	tun_fd = CHECK(open("/dev/net/tun", O_RDWR));
+	sk_ctl = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+}
+END_SETUP()
+
+FN_TEST(unattached)
+{
+	struct ifreq ifr;
+	char buf[16];
+
+	TEST_ERRNO(read(tun_fd, buf, sizeof(buf)), EBADFD);
+	TEST_ERRNO(write(tun_fd, buf, sizeof(buf)), EBADFD);
+	TEST_ERRNO(ioctl(tun_fd, TUNGETIFF, &ifr), EBADFD);
+
+	ifr = new_ifreq("", 0);
+	TEST_ERRNO(ioctl(tun_fd, TUNSETIFF, &ifr), EINVAL);
+	ifr = new_ifreq("tuntest%d%d", IFF_TUN);
+	TEST_ERRNO(ioctl(tun_fd, TUNSETIFF, &ifr), EINVAL);
+}
+END_TEST()
+
+FN_TEST(attach)
+{
+	struct ifreq ifr;
+	int fd;
+
+	ifr = new_ifreq("tuntest%d", IFF_TUN | IFF_NO_PI);
+	TEST_RES(ioctl(tun_fd, TUNSETIFF, &ifr),
+		 strcmp(ifr.ifr_name, TUN_NAME) == 0);
+
+	ifr = new_ifreq(TUN_NAME, IFF_TUN | IFF_NO_PI);
+	TEST_ERRNO(ioctl(tun_fd, TUNSETIFF, &ifr), EEXIST);
+
+	memset(&ifr, 0, sizeof(ifr));
+	TEST_RES(ioctl(tun_fd, TUNGETIFF, &ifr),
+		 strcmp(ifr.ifr_name, TUN_NAME) == 0 &&
+			 (ifr.ifr_flags & (IFF_TUN | IFF_TAP | IFF_NO_PI)) ==
+				 (IFF_TUN | IFF_NO_PI));
+
+	// The iface is in use.
+	fd = TEST_SUCC(open("/dev/net/tun", O_RDWR));
+	ifr = new_ifreq(TUN_NAME, IFF_TUN | IFF_NO_PI);
+	TEST_ERRNO(ioctl(fd, TUNSETIFF, &ifr), EBUSY);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(configure)
+{
+	struct ifreq ifr;
+	struct sockaddr_in *addr = (struct sockaddr_in *)&ifr.ifr_addr;
+	char buf[sizeof(struct iphdr)];
+
+	ifr = new_ifreq(TUN_NAME, 0);
+	TEST_RES(ioctl(sk_ctl, SIOCGIFFLAGS, &ifr),
+		 (ifr.ifr_flags & IFF_UP) == 0 &&
+			 (ifr.ifr_flags & IFF_POINTOPOINT) &&
+			 (ifr.ifr_flags & IFF_NOARP));
+
+	ifr = new_ifreq(TUN_NAME, 0);
+	TEST_RES(ioctl(sk_ctl, SIOCGIFHWADDR, &ifr),
+		 ifr.ifr_hwaddr.sa_family == ARPHRD_NONE);
+
+	// Packets cannot be written if the iface is down.
+	fill_ip((struct iphdr *)buf, IPPROTO_ICMP, 0);
+	TEST_ERRNO(write(tun_fd, buf, sizeof(buf)), EIO);
+
+	ifr = new_ifreq(TUN_NAME, 0);
+	addr->sin_family = AF_INET;
+	addr->sin_addr.s_addr = inet_addr(LOCAL_ADDR);
+	TEST_SUCC(ioctl(sk_ctl, SIOCSIFADDR, &ifr));
+
+	ifr = new_ifreq(TUN_NAME, 0);
+	addr->sin_family = AF_INET;
+	addr->sin_addr.s_addr = inet_addr("255.255.255.0");
+	TEST_SUCC(ioctl(sk_ctl, SIOCSIFNETMASK, &ifr));
+
+	ifr = new_ifreq(TUN_NAME, IFF_UP);
+	TEST_SUCC(ioctl(sk_ctl, SIOCSIFFLAGS, &ifr));
+
+	// Only IP packets can be written to TUN ifaces.
+	memset(buf, 0, sizeof(buf));
+	TEST_ERRNO(write(tun_fd, buf, sizeof(buf)), EINVAL);
+}
+END_TEST()
+
+// Reads the next IPv4 packet, skipping packets of other versions (e.g., IPv6 router
+// solicitations).
+static ssize_t read_ipv4(int fd, void *buf, size_t len, size_t offset)
+{
+	struct pollfd pfd = { .fd = fd, .events = POLLIN };
+	ssize_t ret;
+
+	for (;;) {
+		if (poll(&pfd, 1, 1000) != 1) {
+			errno = ETIMEDOUT;
+			return -1;
+		}
+
+		ret = read(fd, buf, len);
+		if (ret <= 0 || ((unsigned char *)buf)[offset] >> 4 == 4)
+			return ret;
+	}
+}
+
+FN_TEST(ping)
+{
+	struct {
+		struct iphdr ip;
+		struct icmphdr icmp;
+	} pkt;
+	char buf[128];
+	struct iphdr *ip = (struct iphdr *)buf;
+	struct icmphdr *icmp = (struct icmphdr *)(buf + sizeof(*ip));
+
+	fill_ip(&pkt.ip, IPPROTO_ICMP, sizeof(pkt.icmp));
+	memset(&pkt.icmp, 0, sizeof(pkt.icmp));
+	pkt.icmp.type = ICMP_ECHO;
+	pkt.icmp.un.echo.id = htons(1);
+	pkt.icmp.un.echo.sequence = htons(ECHO_SEQ);
+	pkt.icmp.checksum = checksum(&pkt.icmp, sizeof(pkt.icmp), 0);
+
+	TEST_RES(write(tun_fd, &pkt, sizeof(pkt)), _ret == sizeof(pkt));
+
+	TEST_RES(read_ipv4(tun_fd, buf, sizeof(buf), 0),
+		 _ret == sizeof(pkt) && ip->protocol == IPPROTO_ICMP &&
+			 ip->saddr == inet_addr(LOCAL_ADDR) &&
+			 ip->daddr == inet_addr(PEER_ADDR) &&
+			 icmp->type == ICMP_ECHOREPLY &&
+			 ntohs(icmp->un.echo.sequence) == ECHO_SEQ);
+}
+END_TEST()
+
+static unsigned short udp_checksum(const struct iphdr *ip,
+				   const struct udphdr *udp, size_t len)
+{
+	unsigned int sum = 0;
+
+	sum += (ip->saddr & 0xffff) + (ip->saddr >> 16);
+	sum += (ip->daddr & 0xffff) + (ip->daddr >> 16);
+	sum += htons(IPPROTO_UDP);
+	sum += htons(len);
+
+	return checksum(udp, len, sum);
+}
+
+FN_TEST(udp)
+{
+	struct {
+		struct iphdr ip;
+		struct udphdr udp;
+		char data[5];
+	} pkt;
+#define PKT_LEN (sizeof(pkt.ip) + sizeof(pkt.udp) + sizeof(pkt.data))
+	struct sockaddr_in addr = { .sin_family = AF_INET };
+	socklen_t addrlen;
+	char buf[128];
+	struct iphdr *ip = (struct iphdr *)buf;
+	struct udphdr *udp = (struct udphdr *)(buf + sizeof(*ip));
+	int sk;
+
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	addr.sin_addr.s_addr = inet_addr(LOCAL_ADDR);
+	TEST_SUCC(bind(sk, (struct sockaddr *)&addr, sizeof(addr)));
+	addrlen = sizeof(addr);
+	TEST_SUCC(getsockname(sk, (struct sockaddr *)&addr, &addrlen));
+
+	// Receive a datagram from the peer.
+	fill_ip(&pkt.ip, IPPROTO_UDP, sizeof(pkt.udp) + sizeof(pkt.data));
+	pkt.udp.source = htons(PEER_PORT);
+	pkt.udp.dest = addr.sin_port;
+	pkt.udp.len = htons(sizeof(pkt.udp) + sizeof(pkt.data));
+	pkt.udp.check = 0;
+	memcpy(pkt.data, "hello", sizeof(pkt.data));
+	pkt.udp.check = udp_checksum(&pkt.ip, &pkt.udp,
+				     sizeof(pkt.udp) + sizeof(pkt.data));
+
+	TEST_RES(write(tun_fd, &pkt, PKT_LEN), _ret == PKT_LEN);
+
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0 &&
+			 addr.sin_addr.s_addr == inet_addr(PEER_ADDR) &&
+			 addr.sin_port == htons(PEER_PORT));
+
+	// Send a datagram to the peer.
+	TEST_RES(sendto(sk, "world", 5, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 5);
+
+	TEST_RES(read_ipv4(tun_fd, buf, sizeof(buf), 0),
+		 _ret == sizeof(*ip) + sizeof(*udp) + 5 &&
+			 ip->protocol == IPPROTO_UDP &&
+			 ip->saddr == inet_addr(LOCAL_ADDR) &&
+			 ip->daddr == inet_addr(PEER_ADDR) &&
+			 udp->dest == htons(PEER_PORT) &&
+			 memcmp(udp + 1, "world", 5) == 0);
+
+	TEST_SUCC(close(sk));
+#undef PKT_LEN
+}
+END_TEST()
+
+FN_TEST(route)
+{
+	struct sockaddr_in addr = { .sin_family = AF_INET };
+	socklen_t addrlen;
+	char buf[128];
+	struct iphdr *ip = (struct iphdr *)buf;
+	struct udphdr *udp = (struct udphdr *)(buf + sizeof(*ip));
+	int sk;
+
+	// The iface and the local address are chosen by the routing table.
+	sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	addr.sin_addr.s_addr = inet_addr(PEER_ADDR);
+	addr.sin_port = htons(PEER_PORT);
+	TEST_SUCC(connect(sk, (struct sockaddr *)&addr, sizeof(addr)));
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(sk, (struct sockaddr *)&addr, &addrlen),
+		 addr.sin_addr.s_addr == inet_addr(LOCAL_ADDR));
+
+	TEST_RES(send(sk, "route", 5, 0), _ret == 5);
+	TEST_RES(read_ipv4(tun_fd, buf, sizeof(buf), 0),
+		 _ret == sizeof(*ip) + sizeof(*udp) + 5 &&
+			 ip->protocol == IPPROTO_UDP &&
+			 ip->saddr == inet_addr(LOCAL_ADDR) &&
+			 ip->daddr == inet_addr(PEER_ADDR) &&
+			 memcmp(udp + 1, "route", 5) == 0);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(packet_info)
+{
+	struct {
+		struct tun_pi pi;
+		struct iphdr ip;
+		struct icmphdr icmp;
+	} pkt;
+	struct ifreq ifr;
+	struct sockaddr_in *addr = (struct sockaddr_in *)&ifr.ifr_addr;
+	char buf[128];
+	struct tun_pi *pi = (struct tun_pi *)buf;
+	struct iphdr *ip = (struct iphdr *)(buf + sizeof(*pi));
+	int fd;
+
+	fd = TEST_SUCC(open("/dev/net/tun", O_RDWR));
+	ifr = new_ifreq("tuntest%d", IFF_TUN);
+	TEST_RES(ioctl(fd, TUNSETIFF, &ifr),
+		 strcmp(ifr.ifr_name, "tuntest1") == 0);
+
+	ifr = new_ifreq("tuntest1", 0);
+	addr->sin_family = AF_INET;
+	addr->sin_addr.s_addr = inet_addr("10.0.8.1");
+	TEST_SUCC(ioctl(sk_ctl, SIOCSIFADDR, &ifr));
+	ifr = new_ifreq("tuntest1", 0);
+	addr->sin_family = AF_INET;
+	addr->sin_addr.s_addr = inet_addr("255.255.255.0");
+	TEST_SUCC(ioctl(sk_ctl, SIOCSIFNETMASK, &ifr));
+	ifr = new_ifreq("tuntest1", IFF_UP);
+	TEST_SUCC(ioctl(sk_ctl, SIOCSIFFLAGS, &ifr));
+
+	// The packet information is required.
+	TEST_ERRNO(write(fd, buf, 2), EINVAL);
+
+	pkt.pi.flags = 0;
+	pkt.pi.proto = htons(ETH_P_IP);
+	fill_ip(&pkt.ip, IPPROTO_ICMP, sizeof(pkt.icmp));
+	pkt.ip.saddr = inet_addr("10.0.8.2");
+	pkt.ip.daddr = inet_addr("10.0.8.1");
+	pkt.ip.check = 0;
+	pkt.ip.check = checksum(&pkt.ip, sizeof(pkt.ip), 0);
+	memset(&pkt.icmp, 0, sizeof(pkt.icmp));
+	pkt.icmp.type = ICMP_ECHO;
+	pkt.icmp.un.echo.sequence = htons(ECHO_SEQ);
+	pkt.icmp.checksum = checksum(&pkt.icmp, sizeof(pkt.icmp), 0);
+	TEST_RES(write(fd, &pkt, sizeof(pkt)), _ret == sizeof(pkt));
+
+	// The buffer must have space for the packet information. The packet
+	// is dropped if it does not.
+	TEST_RES(poll(&(struct pollfd){ .fd = fd, .events = POLLIN }, 1, 1000),
+		 _ret == 1);
+	TEST_ERRNO(read(fd, buf, sizeof(*pi) - 1), EINVAL);
+
+	TEST_RES(write(fd, &pkt, sizeof(pkt)), _ret == sizeof(pkt));
+	TEST_RES(read_ipv4(fd, buf, sizeof(buf), sizeof(*pi)),
+		 _ret > sizeof(*pi));
+	TEST_RES(pi->proto, _ret == htons(ETH_P_IP) && pi->flags == 0 &&
+				    ip->saddr == inet_addr("10.0.8.1") &&
+				    ip->protocol == IPPROTO_ICMP);
+
+	TEST_SUCC(close(fd));
+
+	// The iface is destroyed when the file is closed.
+	TEST_ERRNO(if_nametoindex("tuntest1"), ENODEV);
+}
+END_TEST()
+
+FN_TEST(tap)
+{
+	struct ifreq ifr;
+	int fd;
+
+	fd = TEST_SUCC(open("/dev/net/tun", O_RDWR));
+	ifr = new_ifreq("taptest%d", IFF_TAP | IFF_NO_PI);
+	TEST_RES(ioctl(fd, TUNSETIFF, &ifr),
+		 strcmp(ifr.ifr_name, "taptest0") == 0);
+
+	ifr = new_ifreq("taptest0", 0);
+	TEST_RES(ioctl(sk_ctl, SIOCGIFHWADDR, &ifr),
+		 ifr.ifr_hwaddr.sa_family == ARPHRD_ETHER &&
+			 (ifr.ifr_hwaddr.sa_data[0] & 0x03) == 0x02);
+
+	ifr = new_ifreq("taptest0", 0);
+	TEST_RES(ioctl(sk_ctl, SIOCGIFFLAGS, &ifr),
+		 (ifr.ifr_flags & IFF_UP) == 0 &&
+			 (ifr.ifr_flags & IFF_BROADCAST));
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(tun_fd));
+	CHECK_WITH(if_nametoindex(TUN_NAME), _ret == 0);
+	CHECK(close(sk_ctl));
+}
+END_SETUP()
@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <unistd.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+
+#include "test.h"
+
+#define MC_PORT htons(0x4321)
+
+static struct sockaddr_in mc_addr;
+static struct sockaddr_in bc_addr;
+
+static int sk_recv;
+static int sk_send;
+
+FN_SETUP(general)
+{
+	mc_addr.sin_family = AF_INET;
+	mc_addr.sin_port = MC_PORT;
+	CHECK(inet_aton("239.1.2.3", &mc_addr.sin_addr));
+
+	bc_addr.sin_family = AF_INET;
+	bc_addr.sin_port = MC_PORT;
+	bc_addr.sin_addr.s_addr = htonl(INADDR_BROADCAST);
+
+	sk_recv = CHECK(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	sk_send = CHECK(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+}
+END_SETUP()
+
+FN_SETUP(bind)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = MC_PORT,
+		.sin_addr = { .s_addr = htonl(INADDR_ANY) },
+	};
+
+	CHECK(bind(sk_recv, (struct sockaddr *)&addr, sizeof(addr)));
+}
+END_SETUP()
+
+FN_TEST(default_options)
+{
+	int val;
+	socklen_t len;
+
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val, &len),
+		 len == sizeof(val) && val == 1);
+
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_LOOP, &val, &len),
+		 len == sizeof(val) && val == 1);
+
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, SOL_SOCKET, SO_BROADCAST, &val, &len),
+		 len == sizeof(val) && val == 0);
+}
+END_TEST()
+
+FN_TEST(multicast_ttl)
+{
+	int val;
+	unsigned char ch;
+	socklen_t len;
+
+	val = 5;
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			     sizeof(val)));
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val, &len),
+		 len == sizeof(val) && val == 5);
+
+	ch = 7;
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &ch,
+			     sizeof(ch)));
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val, &len),
+		 len == sizeof(val) && val == 7);
+
+	val = -1;
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			     sizeof(val)));
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val, &len),
+		 len == sizeof(val) && val == 1);
+
+	val = 256;
+	TEST_ERRNO(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			      sizeof(val)),
+		   EINVAL);
+	val = -2;
+	TEST_ERRNO(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_TTL, &val,
+			      sizeof(val)),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(membership)
+{
+	struct ip_mreq mreq = {
+		.imr_multiaddr = mc_addr.sin_addr,
+		.imr_interface = { .s_addr = htonl(INADDR_ANY) },
+	};
+	struct ip_mreqn mreqn = {
+		.imr_multiaddr = mc_addr.sin_addr,
+		.imr_address = { .s_addr = htonl(INADDR_ANY) },
+		.imr_ifindex = 0,
+	};
+	socklen_t len = sizeof(mreq);
+
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRNOTAVAIL);
+
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreqn,
+			      sizeof(mreqn)),
+		   EADDRINUSE);
+
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreqn,
+			     sizeof(mreqn)));
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRNOTAVAIL);
+
+	mreq.imr_multiaddr.s_addr = htonl(INADDR_LOOPBACK);
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EINVAL);
+
+	mreqn.imr_ifindex = 0x7fffffff;
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreqn,
+			      sizeof(mreqn)),
+		   ENODEV);
+
+	TEST_ERRNO(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq) - 1),
+		   EINVAL);
+
+	TEST_ERRNO(getsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      &len),
+		   ENOPROTOOPT);
+}
+END_TEST()
+
+FN_TEST(max_memberships)
+{
+	struct ip_mreq mreq = {
+		.imr_interface = { .s_addr = htonl(INADDR_ANY) },
+	};
+	int sk, i;
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM, 0));
+
+	for (i = 0; i < 20; ++i) {
+		mreq.imr_multiaddr.s_addr = htonl(0xef010200 + i);
+		TEST_SUCC(setsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+				     sizeof(mreq)));
+	}
+
+	mreq.imr_multiaddr.s_addr = htonl(0xef010200 + i);
+	TEST_ERRNO(setsockopt(sk, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   ENOBUFS);
+
+	// The memberships are dropped when the socket is closed.
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(multicast_loop)
+{
+	struct ip_mreq mreq = {
+		.imr_multiaddr = mc_addr.sin_addr,
+		.imr_interface = { .s_addr = htonl(INADDR_ANY) },
+	};
+	unsigned char ch;
+	int val;
+	socklen_t len;
+	char buf[8];
+
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&mc_addr,
+			sizeof(mc_addr)),
+		 _ret == 5);
+	usleep(10000);
+	TEST_RES(recv(sk_recv, buf, sizeof(buf), 0),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+
+	ch = 0;
+	TEST_SUCC(setsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_LOOP, &ch,
+			     sizeof(ch)));
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk_send, IPPROTO_IP, IP_MULTICAST_LOOP, &val, &len),
+		 len == sizeof(val) && val == 0);
+
+	TEST_RES(sendto(sk_send, "world", 5, 0, (struct sockaddr *)&mc_addr,
+			sizeof(mc_addr)),
+		 _ret == 5);
+	usleep(10000);
+	TEST_ERRNO(recv(sk_recv, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(setsockopt(sk_recv, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+}
+END_TEST()
+
+FN_TEST(broadcast)
+{
+	int sk, val;
+	socklen_t len;
+
+	sk = TEST_SUCC(socket(PF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+
+	TEST_ERRNO(sendto(sk, "hello", 5, 0, (struct sockaddr *)&bc_addr,
+			  sizeof(bc_addr)),
+		   EACCES);
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&bc_addr, sizeof(bc_addr)),
+		   EACCES);
+
+	val = 1;
+	TEST_SUCC(setsockopt(sk, SOL_SOCKET, SO_BROADCAST, &val, sizeof(val)));
+	len = sizeof(val);
+	TEST_RES(getsockopt(sk, SOL_SOCKET, SO_BROADCAST, &val, &len),
+		 len == sizeof(val) && val == 1);
+
+	TEST_SUCC(connect(sk, (struct sockaddr *)&bc_addr, sizeof(bc_addr)));
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_recv));
+	CHECK(close(sk_send));
+}
+END_SETUP()
@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <sys/poll.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <stddef.h>
+
+#include "test.h"
+
+#define PATH_OFFSET offsetof(struct sockaddr_un, sun_path)
+
+static struct sockaddr_un recv_addr = { .sun_family = AF_UNIX,
+					.sun_path = "/tmp/dgram_recv" };
+static struct sockaddr_un send_addr = { .sun_family = AF_UNIX,
+					.sun_path = "\0dgram_send" };
+#define SEND_ADDRLEN (PATH_OFFSET + 11)
+
+static int sk_recv;
+static int sk_send;
+
+FN_SETUP(bind)
+{
+	sk_recv = CHECK(socket(PF_UNIX, SOCK_DGRAM | SOCK_NONBLOCK, 0));
+	CHECK(bind(sk_recv, (struct sockaddr *)&recv_addr, sizeof(recv_addr)));
+
+	sk_send = CHECK(socket(PF_UNIX, SOCK_DGRAM, 0));
+	CHECK(bind(sk_send, (struct sockaddr *)&send_addr, SEND_ADDRLEN));
+}
+END_SETUP()
+
+FN_TEST(message_boundaries)
+{
+	struct sockaddr_un addr;
+	socklen_t addrlen;
+	char buf[16];
+
+	TEST_RES(sendto(sk_send, "hello", 5, 0, (struct sockaddr *)&recv_addr,
+			sizeof(recv_addr)),
+		 _ret == 5);
+	TEST_RES(sendto(sk_send, "", 0, 0, (struct sockaddr *)&recv_addr,
+			sizeof(recv_addr)),
+		 _ret == 0);
+	TEST_RES(sendto(sk_send, "world!", 6, 0, (struct sockaddr *)&recv_addr,
+			sizeof(recv_addr)),
+		 _ret == 6);
+
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_recv, buf, sizeof(buf), 0,
+			  (struct sockaddr *)&addr, &addrlen),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0 &&
+			 addrlen == SEND_ADDRLEN &&
+			 memcmp(addr.sun_path, send_addr.sun_path, 11) == 0);
+
+	TEST_RES(recv(sk_recv, buf, sizeof(buf), 0), _ret == 0);
+
+	// The rest of a truncated datagram is discarded.
+	TEST_RES(recv(sk_recv, buf, 3, MSG_TRUNC),
+		 _ret == 6 && memcmp(buf, "wor", 3) == 0);
+	TEST_ERRNO(recv(sk_recv, buf, sizeof(buf), 0), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(unbound_sender)
+{
+	char buf[16];
+	int sk;
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM, 0));
+	TEST_RES(sendto(sk, "abc", 3, 0, (struct sockaddr *)&recv_addr,
+			sizeof(recv_addr)),
+		 _ret == 3);
+
+	TEST_RES(recv(sk_recv, buf, sizeof(buf), 0),
+		 _ret == 3 && memcmp(buf, "abc", 3) == 0);
+
+	TEST_ERRNO(send(sk, "abc", 3, 0), ENOTCONN);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(connected)
+{
+	struct sockaddr_un addr;
+	socklen_t addrlen;
+	char buf[16];
+	int sk;
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM, 0));
+	TEST_SUCC(connect(sk, (struct sockaddr *)&recv_addr,
+			  sizeof(recv_addr)));
+
+	addrlen = sizeof(addr);
+	TEST_RES(getpeername(sk, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == PATH_OFFSET + sizeof("/tmp/dgram_recv") &&
+			 strcmp(addr.sun_path, recv_addr.sun_path) == 0);
+
+	TEST_RES(send(sk, "xyz", 3, 0), _ret == 3);
+	TEST_RES(recv(sk_recv, buf, sizeof(buf), 0),
+		 _ret == 3 && memcmp(buf, "xyz", 3) == 0);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(connected_receiver)
+{
+	int sk;
+
+	// A connected socket only accepts datagrams from its peer.
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_DGRAM, 0));
+	TEST_SUCC(connect(sk_recv, (struct sockaddr *)&send_addr,
+			  SEND_ADDRLEN));
+	TEST_ERRNO(sendto(sk, "abc", 3, 0, (struct sockaddr *)&recv_addr,
+			  sizeof(recv_addr)),
+		   EPERM);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(bad_addresses)
+{
+	struct sockaddr_un addr = { .sun_family = AF_UNIX,
+				    .sun_path = "\0dgram_none" };
+
+	TEST_ERRNO(sendto(sk_send, "abc", 3, 0, (struct sockaddr *)&addr,
+			  PATH_OFFSET + 11),
+		   ECONNREFUSED);
+	TEST_ERRNO(connect(sk_send, (struct sockaddr *)&addr, PATH_OFFSET + 11),
+		   ECONNREFUSED);
+	addr.sun_path[1] = 'D';
+	TEST_ERRNO(bind(sk_send, (struct sockaddr *)&addr, PATH_OFFSET + 11),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(socketpair)
+{
+	int sv[2];
+	char buf[16];
+	struct ucred cred;
+	socklen_t len;
+	struct pollfd pfd;
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_DGRAM, 0, sv));
+
+	TEST_RES(write(sv[0], "ab", 2), _ret == 2);
+	TEST_RES(write(sv[0], "cde", 3), _ret == 3);
+
+	pfd.fd = sv[1];
+	pfd.events = POLLIN | POLLOUT;
+	TEST_RES(poll(&pfd, 1, 0),
+		 _ret == 1 && pfd.revents == (POLLIN | POLLOUT));
+
+	TEST_RES(read(sv[1], buf, sizeof(buf)),
+		 _ret == 2 && memcmp(buf, "ab", 2) == 0);
+	TEST_RES(read(sv[1], buf, sizeof(buf)),
+		 _ret == 3 && memcmp(buf, "cde", 3) == 0);
+
+	len = sizeof(cred);
+	TEST_RES(getsockopt(sv[0], SOL_SOCKET, SO_PEERCRED, &cred, &len),
+		 len == sizeof(cred) && cred.pid == getpid() &&
+			 cred.uid == geteuid() && cred.gid == getegid());
+
+	TEST_SUCC(close(sv[1]));
+	TEST_ERRNO(write(sv[0], "ab", 2), ECONNREFUSED);
+	TEST_SUCC(close(sv[0]));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_send));
+	CHECK(close(sk_recv));
+	CHECK(unlink(recv_addr.sun_path));
+}
+END_SETUP()
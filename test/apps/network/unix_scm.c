@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <limits.h>
+
+#include "test.h"
+
+static int send_fds(int sk, const char *data, size_t len, const int *fds,
+		    int num_fds)
+{
+	char control[CMSG_SPACE(sizeof(int) * 4)];
+	struct iovec iov = { .iov_base = (void *)data, .iov_len = len };
+	struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+	struct cmsghdr *cmsg;
+
+	if (num_fds > 0) {
+		memset(control, 0, sizeof(control));
+		msg.msg_control = control;
+		msg.msg_controllen = CMSG_SPACE(sizeof(int) * num_fds);
+
+		cmsg = CMSG_FIRSTHDR(&msg);
+		cmsg->cmsg_level = SOL_SOCKET;
+		cmsg->cmsg_type = SCM_RIGHTS;
+		cmsg->cmsg_len = CMSG_LEN(sizeof(int) * num_fds);
+		memcpy(CMSG_DATA(cmsg), fds, sizeof(int) * num_fds);
+	}
+
+	return sendmsg(sk, &msg, 0);
+}
+
+static int send_cred(int sk, const char *data, size_t len, pid_t pid,
+		     uid_t uid, gid_t gid)
+{
+	char control[CMSG_SPACE(sizeof(struct ucred))];
+	struct iovec iov = { .iov_base = (void *)data, .iov_len = len };
+	struct msghdr msg = { .msg_iov = &iov,
+			      .msg_iovlen = 1,
+			      .msg_control = control,
+			      .msg_controllen = sizeof(control) };
+	struct cmsghdr *cmsg;
+	struct ucred cred = { .pid = pid, .uid = uid, .gid = gid };
+
+	cmsg = CMSG_FIRSTHDR(&msg);
+	cmsg->cmsg_level = SOL_SOCKET;
+	cmsg->cmsg_type = SCM_CREDENTIALS;
+	cmsg->cmsg_len = CMSG_LEN(sizeof(cred));
+	memcpy(CMSG_DATA(cmsg), &cred, sizeof(cred));
+
+	return sendmsg(sk, &msg, 0);
+}
+
+static char recv_buf[16];
+static char recv_control[256];
+static struct msghdr recv_msg;
+
+static int recv_with_control(int sk, size_t controllen, int flags)
+{
+	static struct iovec iov;
+
+	iov.iov_base = recv_buf;
+	iov.iov_len = sizeof(recv_buf);
+
+	memset(recv_control, 0, sizeof(recv_control));
+	memset(&recv_msg, 0, sizeof(recv_msg));
+	recv_msg.msg_iov = &iov;
+	recv_msg.msg_iovlen = 1;
+	recv_msg.msg_control = recv_control;
+	recv_msg.msg_controllen = controllen;
+
+	return recvmsg(sk, &recv_msg, flags);
+}
+
+static int sk_pair[2];
+static int pipe_fds[2];
+
+FN_SETUP(pair)
+{
+	CHECK(socketpair(PF_UNIX, SOCK_STREAM, 0, sk_pair));
+	CHECK(pipe(pipe_fds));
+}
+END_SETUP()
+
+FN_TEST(pass_fds)
+{
+	struct cmsghdr *cmsg;
+	int fds[2];
+	char buf[4];
+
+	TEST_RES(send_fds(sk_pair[0], "ab", 2, pipe_fds, 2), _ret == 2);
+	TEST_RES(write(pipe_fds[1], "xyz", 3), _ret == 3);
+
+	TEST_RES(recv_with_control(sk_pair[1], sizeof(recv_control),
+				   MSG_CMSG_CLOEXEC),
+		 _ret == 2 && recv_msg.msg_flags == MSG_CMSG_CLOEXEC &&
+			 recv_msg.msg_controllen ==
+				 CMSG_SPACE(sizeof(int) * 2));
+
+	cmsg = CMSG_FIRSTHDR(&recv_msg);
+	TEST_RES(cmsg->cmsg_len, _ret == CMSG_LEN(sizeof(int) * 2));
+	TEST_RES(cmsg->cmsg_type, _ret == SCM_RIGHTS);
+	memcpy(fds, CMSG_DATA(cmsg), sizeof(fds));
+
+	TEST_RES(read(fds[0], buf, sizeof(buf)),
+		 _ret == 3 && memcmp(buf, "xyz", 3) == 0);
+	TEST_RES(fcntl(fds[1], F_GETFD), _ret == FD_CLOEXEC);
+
+	TEST_SUCC(close(fds[0]));
+	TEST_SUCC(close(fds[1]));
+}
+END_TEST()
+
+FN_TEST(truncated_fds)
+{
+	struct cmsghdr *cmsg;
+	int fd;
+
+	TEST_RES(send_fds(sk_pair[0], "ab", 2, pipe_fds, 2), _ret == 2);
+
+	// There is only space for one file descriptor.
+	TEST_RES(recv_with_control(sk_pair[1], CMSG_LEN(sizeof(int)), 0),
+		 _ret == 2 && recv_msg.msg_flags == MSG_CTRUNC &&
+			 recv_msg.msg_controllen == CMSG_LEN(sizeof(int)));
+
+	cmsg = CMSG_FIRSTHDR(&recv_msg);
+	TEST_RES(cmsg->cmsg_len, _ret == CMSG_LEN(sizeof(int)));
+	memcpy(&fd, CMSG_DATA(cmsg), sizeof(fd));
+	TEST_RES(fcntl(fd, F_GETFD), _ret == 0);
+	TEST_SUCC(close(fd));
+
+	TEST_RES(send_fds(sk_pair[0], "ab", 2, pipe_fds, 1), _ret == 2);
+	TEST_RES(recv(sk_pair[1], recv_buf, sizeof(recv_buf), 0), _ret == 2);
+}
+END_TEST()
+
+FN_TEST(stream_boundaries)
+{
+	TEST_RES(write(sk_pair[0], "ab", 2), _ret == 2);
+	TEST_RES(send_fds(sk_pair[0], "cd", 2, pipe_fds, 1), _ret == 2);
+	TEST_RES(write(sk_pair[0], "ef", 2), _ret == 2);
+
+	// A read stops after the data that carries files.
+	TEST_RES(recv_with_control(sk_pair[1], 0, 0),
+		 _ret == 4 && memcmp(recv_buf, "abcd", 4) == 0 &&
+			 recv_msg.msg_flags == MSG_CTRUNC &&
+			 recv_msg.msg_controllen == 0);
+	TEST_RES(recv_with_control(sk_pair[1], sizeof(recv_control), 0),
+		 _ret == 2 && memcmp(recv_buf, "ef", 2) == 0 &&
+			 recv_msg.msg_flags == 0 &&
+			 recv_msg.msg_controllen == 0);
+}
+END_TEST()
+
+FN_TEST(bad_fds)
+{
+	int fds[2] = { pipe_fds[0], -1 };
+
+	TEST_ERRNO(send_fds(sk_pair[0], "ab", 2, fds, 2), EBADF);
+	TEST_ERRNO(recv(sk_pair[1], recv_buf, sizeof(recv_buf), MSG_DONTWAIT),
+		   EAGAIN);
+}
+END_TEST()
+
+FN_TEST(pass_cred)
+{
+	struct cmsghdr *cmsg;
+	struct ucred cred;
+	int opt;
+	socklen_t optlen;
+
+	optlen = sizeof(opt);
+	TEST_RES(getsockopt(sk_pair[1], SOL_SOCKET, SO_PASSCRED, &opt, &optlen),
+		 optlen == sizeof(opt) && opt == 0);
+
+	// Credentials are not received without `SO_PASSCRED`.
+	TEST_RES(write(sk_pair[0], "ab", 2), _ret == 2);
+	TEST_RES(recv_with_control(sk_pair[1], sizeof(recv_control), 0),
+		 _ret == 2 && recv_msg.msg_controllen == 0);
+
+	opt = 1;
+	TEST_SUCC(setsockopt(sk_pair[1], SOL_SOCKET, SO_PASSCRED, &opt,
+			     sizeof(opt)));
+
+	TEST_RES(write(sk_pair[0], "ab", 2), _ret == 2);
+	TEST_RES(recv_with_control(sk_pair[1], sizeof(recv_control), 0),
+		 _ret == 2 && recv_msg.msg_controllen ==
+				      CMSG_SPACE(sizeof(struct ucred)));
+
+	cmsg = CMSG_FIRSTHDR(&recv_msg);
+	TEST_RES(cmsg->cmsg_type, _ret == SCM_CREDENTIALS);
+	memcpy(&cred, CMSG_DATA(cmsg), sizeof(cred));
+	TEST_RES(cred.pid, _ret == getpid());
+	TEST_RES(cred.uid, _ret == geteuid());
+	TEST_RES(cred.gid, _ret == getegid());
+
+	TEST_RES(send_cred(sk_pair[0], "cd", 2, getpid(), getuid(), getgid()),
+		 _ret == 2);
+	TEST_RES(recv_with_control(sk_pair[1], sizeof(recv_control), 0),
+		 _ret == 2 && recv_msg.msg_controllen ==
+				      CMSG_SPACE(sizeof(struct ucred)));
+
+	opt = 0;
+	TEST_SUCC(setsockopt(sk_pair[1], SOL_SOCKET, SO_PASSCRED, &opt,
+			     sizeof(opt)));
+}
+END_TEST()
+
+FN_TEST(bad_cred)
+{
+	TEST_ERRNO(send_cred(sk_pair[0], "ab", 2, INT_MAX, getuid(), getgid()),
+		   geteuid() == 0 ? ESRCH : EPERM);
+	TEST_ERRNO(recv(sk_pair[1], recv_buf, sizeof(recv_buf), MSG_DONTWAIT),
+		   EAGAIN);
+}
+END_TEST()
+
+FN_TEST(datagram)
+{
+	struct cmsghdr *cmsg;
+	int sv[2];
+	int fd;
+	char buf[4];
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_DGRAM, 0, sv));
+
+	TEST_RES(send_fds(sv[0], "ab", 2, &pipe_fds[0], 1), _ret == 2);
+	TEST_RES(send_fds(sv[0], "cd", 2, &pipe_fds[1], 1), _ret == 2);
+
+	// Closing the socket drops the file in the queued message.
+	TEST_SUCC(close(sv[0]));
+
+	TEST_RES(recv_with_control(sv[1], sizeof(recv_control), 0),
+		 _ret == 2 && memcmp(recv_buf, "ab", 2) == 0 &&
+			 recv_msg.msg_controllen == CMSG_SPACE(sizeof(int)));
+	cmsg = CMSG_FIRSTHDR(&recv_msg);
+	memcpy(&fd, CMSG_DATA(cmsg), sizeof(fd));
+
+	TEST_RES(write(pipe_fds[1], "x", 1), _ret == 1);
+	TEST_RES(read(fd, buf, sizeof(buf)), _ret == 1 && buf[0] == 'x');
+	TEST_SUCC(close(fd));
+
+	TEST_RES(recv_with_control(sv[1], sizeof(recv_control), 0),
+		 _ret == 2 && memcmp(recv_buf, "cd", 2) == 0 &&
+			 recv_msg.msg_controllen == CMSG_SPACE(sizeof(int)));
+	cmsg = CMSG_FIRSTHDR(&recv_msg);
+	memcpy(&fd, CMSG_DATA(cmsg), sizeof(fd));
+	TEST_SUCC(close(fd));
+
+	TEST_SUCC(close(sv[1]));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_pair[0]));
+	CHECK(close(sk_pair[1]));
+	CHECK(close(pipe_fds[0]));
+	CHECK(close(pipe_fds[1]));
+}
+END_SETUP()
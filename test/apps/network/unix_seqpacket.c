@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <stddef.h>
+
+#include "test.h"
+
+#define PATH_OFFSET offsetof(struct sockaddr_un, sun_path)
+
+static struct sockaddr_un listen_addr = { .sun_family = AF_UNIX,
+					  .sun_path = "\0seqpacket_listen" };
+#define LISTEN_ADDRLEN (PATH_OFFSET + 17)
+
+static int sk_listen;
+static int sk_connected;
+static int sk_accepted;
+
+FN_SETUP(connect)
+{
+	sk_listen = CHECK(socket(PF_UNIX, SOCK_SEQPACKET, 0));
+	CHECK(bind(sk_listen, (struct sockaddr *)&listen_addr, LISTEN_ADDRLEN));
+	CHECK(listen(sk_listen, 2));
+
+	sk_connected = CHECK(socket(PF_UNIX, SOCK_SEQPACKET | SOCK_NONBLOCK, 0));
+	CHECK(connect(sk_connected, (struct sockaddr *)&listen_addr,
+		      LISTEN_ADDRLEN));
+
+	sk_accepted = CHECK(accept4(sk_listen, NULL, NULL, SOCK_NONBLOCK));
+}
+END_SETUP()
+
+FN_TEST(message_boundaries)
+{
+	char buf[16];
+
+	TEST_RES(send(sk_connected, "hello", 5, 0), _ret == 5);
+	TEST_RES(send(sk_connected, "world!", 6, 0), _ret == 6);
+	TEST_RES(send(sk_connected, "abc", 3, 0), _ret == 3);
+
+	TEST_RES(recv(sk_accepted, buf, sizeof(buf), 0),
+		 _ret == 5 && memcmp(buf, "hello", 5) == 0);
+
+	// The rest of a truncated message is discarded.
+	TEST_RES(recv(sk_accepted, buf, 3, 0),
+		 _ret == 3 && memcmp(buf, "wor", 3) == 0);
+	TEST_RES(recv(sk_accepted, buf, 1, MSG_TRUNC),
+		 _ret == 3 && buf[0] == 'a');
+
+	TEST_ERRNO(recv(sk_accepted, buf, sizeof(buf), 0), EAGAIN);
+}
+END_TEST()
+
+FN_TEST(peer_credentials)
+{
+	struct ucred cred;
+	socklen_t len;
+
+#define CHECK_CRED(sk)                                                        \
+	len = sizeof(cred);                                                   \
+	TEST_RES(getsockopt(sk, SOL_SOCKET, SO_PEERCRED, &cred, &len),        \
+		 len == sizeof(cred) && cred.pid == getpid() &&               \
+			 cred.uid == geteuid() && cred.gid == getegid());
+
+	CHECK_CRED(sk_listen);
+	CHECK_CRED(sk_connected);
+	CHECK_CRED(sk_accepted);
+
+#undef CHECK_CRED
+}
+END_TEST()
+
+FN_TEST(wrong_type)
+{
+	struct sockaddr_un addr = { .sun_family = AF_UNIX,
+				    .sun_path = "/tmp/seqpacket_listen" };
+	int sk, sk_path;
+
+	sk_path = TEST_SUCC(socket(PF_UNIX, SOCK_SEQPACKET, 0));
+	TEST_SUCC(bind(sk_path, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(listen(sk_path, 2));
+
+	// Linux reports different errors for path and abstract names.
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_STREAM, 0));
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&listen_addr, LISTEN_ADDRLEN),
+		   ECONNREFUSED);
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&addr, sizeof(addr)),
+		   EPROTOTYPE);
+	TEST_SUCC(close(sk));
+
+	TEST_SUCC(close(sk_path));
+	TEST_SUCC(unlink(addr.sun_path));
+}
+END_TEST()
+
+FN_TEST(unconnected)
+{
+	char buf[16];
+	int sk;
+
+	sk = TEST_SUCC(socket(PF_UNIX, SOCK_SEQPACKET, 0));
+	TEST_ERRNO(recv(sk, buf, sizeof(buf), 0), ENOTCONN);
+	TEST_ERRNO(send(sk, buf, sizeof(buf), 0), ENOTCONN);
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(socketpair)
+{
+	int sv[2];
+	char buf[16];
+
+	TEST_SUCC(socketpair(PF_UNIX, SOCK_SEQPACKET, 0, sv));
+
+	TEST_RES(write(sv[0], "ab", 2), _ret == 2);
+	TEST_RES(write(sv[0], "cde", 3), _ret == 3);
+	TEST_RES(read(sv[1], buf, sizeof(buf)),
+		 _ret == 2 && memcmp(buf, "ab", 2) == 0);
+	TEST_RES(read(sv[1], buf, sizeof(buf)),
+		 _ret == 3 && memcmp(buf, "cde", 3) == 0);
+
+	TEST_SUCC(close(sv[0]));
+	TEST_RES(read(sv[1], buf, sizeof(buf)), _ret == 0);
+	TEST_SUCC(close(sv[1]));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_accepted));
+	CHECK(close(sk_connected));
+	CHECK(close(sk_listen));
+}
+END_SETUP()
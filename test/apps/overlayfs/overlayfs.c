@@ -0,0 +1,243 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <dirent.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/sysmacros.h>
+#include <sys/xattr.h>
+#include <unistd.h>
+
+#define BASE_DIR "/tmp/overlay"
+#define LOWER_DIR BASE_DIR "/lower"
+#define UPPER_DIR BASE_DIR "/upper"
+#define WORK_DIR BASE_DIR "/work"
+#define MERGED_DIR BASE_DIR "/merged"
+#define RDONLY_DIR BASE_DIR "/rdonly"
+// The bottom layer lives on another file system
+#define BOTTOM_DIR "/ext2/overlay_lower"
+
+#define OPTIONS                                                \
+	"lowerdir=" LOWER_DIR ":" BOTTOM_DIR ",upperdir=" UPPER_DIR \
+	",workdir=" WORK_DIR
+
+static char buf[256];
+
+static int write_file(const char *path, const char *content)
+{
+	int fd;
+
+	fd = open(path, O_CREAT | O_TRUNC | O_WRONLY, 0644);
+	if (fd < 0)
+		return -1;
+	if (write(fd, content, strlen(content)) != strlen(content)) {
+		close(fd);
+		return -1;
+	}
+	return close(fd);
+}
+
+static int check_file(const char *path, const char *content)
+{
+	int fd;
+	ssize_t len;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	len = read(fd, buf, sizeof(buf) - 1);
+	close(fd);
+	if (len != strlen(content) || memcmp(buf, content, len) != 0) {
+		errno = EIO;
+		return -1;
+	}
+	return 0;
+}
+
+static int count_entries(const char *path)
+{
+	DIR *dir;
+	struct dirent *entry;
+	int count = 0;
+
+	dir = opendir(path);
+	if (dir == NULL)
+		return -1;
+	while ((entry = readdir(dir)) != NULL)
+		if (strcmp(entry->d_name, ".") != 0 &&
+		    strcmp(entry->d_name, "..") != 0)
+			count++;
+	closedir(dir);
+	return count;
+}
+
+static int is_whiteout(const char *path)
+{
+	struct stat stat_buf;
+
+	if (lstat(path, &stat_buf) < 0)
+		return 0;
+	return S_ISCHR(stat_buf.st_mode) && stat_buf.st_rdev == makedev(0, 0);
+}
+
+FN_SETUP(layers)
+{
+	CHECK(mkdir(BASE_DIR, 0755));
+	CHECK(mkdir(LOWER_DIR, 0755));
+	CHECK(mkdir(UPPER_DIR, 0755));
+	CHECK(mkdir(WORK_DIR, 0755));
+	CHECK(mkdir(MERGED_DIR, 0755));
+	CHECK(mkdir(RDONLY_DIR, 0755));
+	CHECK(mkdir(BOTTOM_DIR, 0755));
+
+	CHECK(write_file(LOWER_DIR "/file", "lower"));
+	CHECK(write_file(LOWER_DIR "/removed", "removed"));
+	CHECK(mkdir(LOWER_DIR "/dir", 0755));
+	CHECK(write_file(LOWER_DIR "/dir/a", "a"));
+	CHECK(write_file(LOWER_DIR "/dir/b", "b"));
+	CHECK(mkdir(LOWER_DIR "/moved_dir", 0755));
+
+	CHECK(write_file(BOTTOM_DIR "/file", "bottom"));
+	CHECK(write_file(BOTTOM_DIR "/bottom_only", "bottom only"));
+	CHECK(mkdir(BOTTOM_DIR "/dir", 0755));
+	CHECK(write_file(BOTTOM_DIR "/dir/c", "c"));
+
+	CHECK(mount("overlay", MERGED_DIR, "overlay", 0, OPTIONS));
+}
+END_SETUP()
+
+FN_TEST(invalid_options)
+{
+	TEST_ERRNO(mount("overlay", RDONLY_DIR, "overlay", 0, NULL), EINVAL);
+	TEST_ERRNO(mount("overlay", RDONLY_DIR, "overlay", 0,
+			 "upperdir=" UPPER_DIR ",workdir=" WORK_DIR),
+		   EINVAL);
+	TEST_ERRNO(mount("overlay", RDONLY_DIR, "overlay", 0,
+			 "lowerdir=" LOWER_DIR ",upperdir=" UPPER_DIR),
+		   EINVAL);
+	TEST_ERRNO(mount("overlay", RDONLY_DIR, "overlay", 0,
+			 "lowerdir=" BASE_DIR "/nonexistent"),
+		   ENOENT);
+}
+END_TEST()
+
+FN_TEST(merged_view)
+{
+	// The upper lower layer hides the files of the same name below it
+	TEST_SUCC(check_file(MERGED_DIR "/file", "lower"));
+	TEST_SUCC(check_file(MERGED_DIR "/bottom_only", "bottom only"));
+
+	// The directories are merged
+	TEST_RES(count_entries(MERGED_DIR), _ret == 5);
+	TEST_RES(count_entries(MERGED_DIR "/dir"), _ret == 3);
+	TEST_SUCC(check_file(MERGED_DIR "/dir/c", "c"));
+}
+END_TEST()
+
+FN_TEST(copy_up)
+{
+	struct stat stat_buf;
+	int fd;
+
+	fd = TEST_SUCC(open(MERGED_DIR "/file", O_WRONLY | O_APPEND));
+	TEST_RES(write(fd, "+upper", 6), _ret == 6);
+	TEST_SUCC(close(fd));
+
+	TEST_SUCC(check_file(MERGED_DIR "/file", "lower+upper"));
+	TEST_SUCC(check_file(UPPER_DIR "/file", "lower+upper"));
+	TEST_SUCC(check_file(LOWER_DIR "/file", "lower"));
+
+	// Changing the attributes also copies up the file and its parents
+	TEST_SUCC(chmod(MERGED_DIR "/dir/c", 0600));
+	TEST_RES(stat(UPPER_DIR "/dir/c", &stat_buf),
+		 (stat_buf.st_mode & 0777) == 0600);
+	TEST_RES(stat(BOTTOM_DIR "/dir/c", &stat_buf),
+		 (stat_buf.st_mode & 0777) == 0644);
+	TEST_RES(count_entries(UPPER_DIR "/dir"), _ret == 1);
+	TEST_RES(count_entries(MERGED_DIR "/dir"), _ret == 3);
+}
+END_TEST()
+
+FN_TEST(whiteout)
+{
+	struct stat stat_buf;
+
+	TEST_SUCC(unlink(MERGED_DIR "/removed"));
+	TEST_ERRNO(stat(MERGED_DIR "/removed", &stat_buf), ENOENT);
+	TEST_RES(is_whiteout(UPPER_DIR "/removed"), _ret == 1);
+	TEST_SUCC(check_file(LOWER_DIR "/removed", "removed"));
+	TEST_RES(count_entries(MERGED_DIR), _ret == 4);
+
+	// A new file replaces the whiteout
+	TEST_SUCC(write_file(MERGED_DIR "/removed", "recreated"));
+	TEST_SUCC(check_file(MERGED_DIR "/removed", "recreated"));
+	TEST_RES(is_whiteout(UPPER_DIR "/removed"), _ret == 0);
+	TEST_RES(count_entries(MERGED_DIR), _ret == 5);
+}
+END_TEST()
+
+FN_TEST(opaque)
+{
+	TEST_ERRNO(rmdir(MERGED_DIR "/dir"), ENOTEMPTY);
+	TEST_SUCC(unlink(MERGED_DIR "/dir/a"));
+	TEST_SUCC(unlink(MERGED_DIR "/dir/b"));
+	TEST_SUCC(unlink(MERGED_DIR "/dir/c"));
+	TEST_RES(count_entries(MERGED_DIR "/dir"), _ret == 0);
+	TEST_SUCC(rmdir(MERGED_DIR "/dir"));
+	TEST_RES(is_whiteout(UPPER_DIR "/dir"), _ret == 1);
+
+	// The new directory hides the directories in the lower layers
+	TEST_SUCC(mkdir(MERGED_DIR "/dir", 0755));
+	TEST_RES(count_entries(MERGED_DIR "/dir"), _ret == 0);
+	TEST_RES(getxattr(UPPER_DIR "/dir", "trusted.overlay.opaque", buf,
+			  sizeof(buf)),
+		 _ret == 1 && buf[0] == 'y');
+	TEST_ERRNO(getxattr(MERGED_DIR "/dir", "trusted.overlay.opaque", buf,
+			    sizeof(buf)),
+		   ENODATA);
+	TEST_RES(count_entries(LOWER_DIR "/dir"), _ret == 2);
+}
+END_TEST()
+
+FN_TEST(rename)
+{
+	struct stat stat_buf;
+
+	TEST_SUCC(rename(MERGED_DIR "/file", MERGED_DIR "/dir/file"));
+	TEST_SUCC(check_file(MERGED_DIR "/dir/file", "lower+upper"));
+	TEST_ERRNO(stat(MERGED_DIR "/file", &stat_buf), ENOENT);
+	TEST_RES(is_whiteout(UPPER_DIR "/file"), _ret == 1);
+
+	// The file below the whiteout is hidden even if it is in the bottom layer
+	TEST_SUCC(rename(MERGED_DIR "/bottom_only", MERGED_DIR "/renamed"));
+	TEST_SUCC(check_file(MERGED_DIR "/renamed", "bottom only"));
+	TEST_ERRNO(stat(MERGED_DIR "/bottom_only", &stat_buf), ENOENT);
+
+	// The directories in the lower layers cannot be moved
+	TEST_ERRNO(rename(MERGED_DIR "/moved_dir", MERGED_DIR "/dir/moved_dir"),
+		   EXDEV);
+}
+END_TEST()
+
+FN_TEST(read_only)
+{
+	TEST_SUCC(mount("overlay", RDONLY_DIR, "overlay", 0,
+			"lowerdir=" LOWER_DIR ":" BOTTOM_DIR));
+	TEST_SUCC(check_file(RDONLY_DIR "/bottom_only", "bottom only"));
+	TEST_ERRNO(open(RDONLY_DIR "/file", O_WRONLY), EROFS);
+	TEST_ERRNO(mkdir(RDONLY_DIR "/new_dir", 0755), EROFS);
+	TEST_ERRNO(unlink(RDONLY_DIR "/removed"), EROFS);
+	TEST_SUCC(umount(RDONLY_DIR));
+}
+END_TEST()
+
+FN_TEST(unmount)
+{
+	TEST_SUCC(umount(MERGED_DIR));
+	TEST_RES(count_entries(MERGED_DIR), _ret == 0);
+}
+END_TEST()
@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <signal.h>
+#include <stddef.h>
+#include <sys/ptrace.h>
+#include <sys/syscall.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+static volatile long value = 1;
+
+static __attribute__((noinline)) int target(void)
+{
+	return value == 2 ? 0 : 1;
+}
+
+// Forks a child that is traced by the current process. The child stops with
+// `SIGSTOP` before running `fn` and exits with its return value.
+static pid_t fork_tracee(int (*fn)(void))
+{
+	pid_t pid;
+
+	pid = fork();
+	if (pid != 0)
+		return pid;
+
+	if (ptrace(PTRACE_TRACEME, 0, NULL, NULL) < 0)
+		_exit(-1);
+	raise(SIGSTOP);
+	_exit(fn());
+}
+
+// Waits for the child to stop and returns the stop signal.
+static int wait_stop(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	if (!WIFSTOPPED(status))
+		return -1;
+	return WSTOPSIG(status);
+}
+
+// Waits for the child to exit and returns the exit code.
+static int wait_exit(pid_t pid)
+{
+	int status;
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	if (!WIFEXITED(status))
+		return -1;
+	return WEXITSTATUS(status);
+}
+
+static int nothing(void)
+{
+	return 0;
+}
+
+FN_TEST(traceme)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork_tracee(nothing));
+	TEST_RES(wait_stop(pid), _ret == SIGSTOP);
+
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, pid, NULL, NULL), EPERM);
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, getpid(), NULL, NULL), EPERM);
+	TEST_ERRNO(ptrace(PTRACE_SETOPTIONS, pid, NULL, (void *)0x80000000),
+		   EINVAL);
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_exit(pid), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(peek_and_poke)
+{
+	struct user_regs_struct regs;
+	pid_t pid;
+	long word;
+
+	pid = TEST_SUCC(fork_tracee(target));
+	TEST_RES(wait_stop(pid), _ret == SIGSTOP);
+
+	TEST_RES(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), _ret == 1);
+	TEST_SUCC(ptrace(PTRACE_POKEDATA, pid, &value, (void *)2));
+	TEST_RES(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), _ret == 2);
+	TEST_RES(value, _ret == 1);
+
+	TEST_ERRNO(ptrace(PTRACE_PEEKDATA, pid, NULL, NULL), EIO);
+	TEST_ERRNO(ptrace(PTRACE_POKEDATA, pid, NULL, NULL), EIO);
+
+	// Set a breakpoint in the code, which is read-only.
+	word = TEST_SUCC(ptrace(PTRACE_PEEKTEXT, pid, target, NULL));
+	TEST_SUCC(ptrace(PTRACE_POKETEXT, pid, target,
+			 (void *)((word & ~0xffL) | 0xcc)));
+	TEST_RES(*(unsigned char *)target, _ret != 0xcc);
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_stop(pid), _ret == SIGTRAP);
+
+	TEST_SUCC(ptrace(PTRACE_POKETEXT, pid, target, (void *)word));
+	TEST_SUCC(ptrace(PTRACE_GETREGS, pid, NULL, &regs));
+	TEST_RES(regs.rip, _ret == (long)target + 1);
+	regs.rip = (long)target;
+	TEST_SUCC(ptrace(PTRACE_SETREGS, pid, NULL, &regs));
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_exit(pid), _ret == 0);
+}
+END_TEST()
+
+static int call_getpid(void)
+{
+	return syscall(SYS_getpid) == 42 ? 0 : 1;
+}
+
+FN_TEST(syscall_stop)
+{
+	struct user_regs_struct regs;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork_tracee(call_getpid));
+	TEST_RES(wait_stop(pid), _ret == SIGSTOP);
+	TEST_SUCC(ptrace(PTRACE_SETOPTIONS, pid, NULL,
+			 (void *)PTRACE_O_TRACESYSGOOD));
+
+	// Skip the stops until the entry of `getpid`.
+	do {
+		TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+		TEST_RES(wait_stop(pid), _ret == (SIGTRAP | 0x80));
+		TEST_SUCC(ptrace(PTRACE_GETREGS, pid, NULL, &regs));
+	} while (regs.orig_rax != SYS_getpid);
+	TEST_RES(regs.rax, _ret == -ENOSYS);
+
+	TEST_SUCC(ptrace(PTRACE_SYSCALL, pid, NULL, NULL));
+	TEST_RES(wait_stop(pid), _ret == (SIGTRAP | 0x80));
+	TEST_SUCC(ptrace(PTRACE_GETREGS, pid, NULL, &regs));
+	TEST_RES(regs.orig_rax, _ret == SYS_getpid);
+	TEST_RES(regs.rax, _ret == pid);
+	regs.rax = 42;
+	TEST_SUCC(ptrace(PTRACE_SETREGS, pid, NULL, &regs));
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_exit(pid), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(single_step)
+{
+	struct user_regs_struct regs;
+	unsigned long long rip;
+	pid_t pid;
+	int i;
+
+	pid = TEST_SUCC(fork_tracee(nothing));
+	TEST_RES(wait_stop(pid), _ret == SIGSTOP);
+	TEST_SUCC(ptrace(PTRACE_GETREGS, pid, NULL, &regs));
+
+	for (i = 0; i < 3; i++) {
+		rip = regs.rip;
+		TEST_SUCC(ptrace(PTRACE_SINGLESTEP, pid, NULL, NULL));
+		TEST_RES(wait_stop(pid), _ret == SIGTRAP);
+		TEST_SUCC(ptrace(PTRACE_GETREGS, pid, NULL, &regs));
+		TEST_RES(regs.rip, _ret != rip);
+	}
+
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(wait_exit(pid), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(attach_and_seize)
+{
+	pid_t pid;
+	int status;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		for (;;)
+			pause();
+	}
+
+	TEST_ERRNO(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), ESRCH);
+	TEST_SUCC(ptrace(PTRACE_ATTACH, pid, NULL, NULL));
+	TEST_RES(wait_stop(pid), _ret == SIGSTOP);
+	TEST_ERRNO(ptrace(PTRACE_ATTACH, pid, NULL, NULL), EPERM);
+	TEST_RES(ptrace(PTRACE_PEEKDATA, pid, &value, NULL), _ret == 1);
+	TEST_SUCC(ptrace(PTRACE_DETACH, pid, NULL, NULL));
+	TEST_ERRNO(ptrace(PTRACE_CONT, pid, NULL, NULL), ESRCH);
+
+	TEST_ERRNO(ptrace(PTRACE_SEIZE, pid, NULL, (void *)0x80000000),
+		   EIO);
+	TEST_SUCC(ptrace(PTRACE_SEIZE, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, WNOHANG), _ret == 0);
+	TEST_ERRNO(ptrace(PTRACE_CONT, pid, NULL, NULL), ESRCH);
+
+	// The signal is suppressed by the tracer.
+	TEST_SUCC(kill(pid, SIGUSR1));
+	TEST_RES(wait_stop(pid), _ret == SIGUSR1);
+	TEST_SUCC(ptrace(PTRACE_CONT, pid, NULL, NULL));
+	TEST_RES(waitpid(pid, &status, WNOHANG), _ret == 0);
+
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+}
+END_TEST()
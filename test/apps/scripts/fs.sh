@@ -49,6 +49,8 @@ test_fdatasync() {
     rm -f /test_fdatasync.txt
     fdatasync/fdatasync /ext2
     rm -f /ext2/test_fdatasync.txt
+    fdatasync/fdatasync /ext4
+    rm -f /ext4/test_fdatasync.txt
     fdatasync/fdatasync /exfat
     rm -f /exfat/test_fdatasync.txt
 }
@@ -57,10 +59,32 @@ echo "Start ext2 fs test......"
 test_ext2 "/ext2" "test_file.txt"
 echo "All ext2 fs test passed."
 
+echo "Start ext4 fs test......"
+test_ext2 "/ext4" "test_file.txt"
+echo "All ext4 fs test passed."
+
 echo "Start fdatasync test......"
 test_fdatasync
 echo "All fdatasync test passed."
 
+echo "Start inotify test......"
+inotify/inotify
+echo "All inotify test passed."
+
+echo "Start xattr test......"
+xattr/xattr
+echo "All xattr test passed."
+
+echo "Start dir_index test......"
+dir_index/dir_index
+dir_index/dir_index_ext4
+echo "All dir_index test passed."
+
+echo "Start overlayfs test......"
+overlayfs/overlayfs
+rm -rf /tmp/overlay /ext2/overlay_lower
+echo "All overlayfs test passed."
+
 pipe/pipe_err
 pipe/short_rw
 epoll/epoll_err
@@ -28,10 +28,20 @@ sleep 0.2
 ./socketpair
 ./sockoption
 ./listen_backlog
+./netlink_route
+./ipv6
+./raw_socket
+./packet_socket
 ./send_buf_full
+./tcp_congestion
 ./tcp_err
 ./tcp_poll
 ./udp_err
+./udp_multicast
+./unix_dgram
 ./unix_err
+./unix_seqpacket
+./unix_scm
+./tun
 
 echo "All network test passed"
@@ -23,16 +23,27 @@ fork_c/fork
 getpid/getpid
 hello_pie/hello
 hello_world/hello_world
+io_uring/io_uring
 itimer/setitimer
 itimer/timer_create
+itimer/timerfd
 mmap/mmap_and_fork
 mmap/mmap_shared_filebacked
 mmap/mmap_readahead
+mmap/mremap
+msg/posix_mq
+msg/sysv_msg
+namespace/namespace
 pthread/pthread_test
+ptrace/ptrace
 pty/open_pty
+seccomp/seccomp
+shm/memfd
 shm/posix_shm
+shm/sysv_shm
 signal_c/parent_death_signal
 signal_c/signal_test
+signal_c/signalfd
 "
 
 for testcase in ${tests}
@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <linux/audit.h>
+#include <linux/filter.h>
+#include <linux/seccomp.h>
+#include <signal.h>
+#include <stddef.h>
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../network/test.h"
+
+#ifndef SYS_SECCOMP
+#define SYS_SECCOMP 1
+#endif
+
+#define LOAD_NR \
+	BPF_STMT(BPF_LD | BPF_W | BPF_ABS, offsetof(struct seccomp_data, nr))
+#define LOAD_ARG0 \
+	BPF_STMT(BPF_LD | BPF_W | BPF_ABS, offsetof(struct seccomp_data, args[0]))
+#define RET(action) BPF_STMT(BPF_RET | BPF_K, (action))
+#define JEQ(value, jt, jf) \
+	BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, (value), (jt), (jf))
+
+static int set_filter(unsigned int flags, struct sock_filter *filter,
+		      unsigned short len)
+{
+	struct sock_fprog prog = { .len = len, .filter = filter };
+
+	return syscall(SYS_seccomp, SECCOMP_SET_MODE_FILTER, flags, &prog);
+}
+
+#define SET_FILTER(flags, filter) \
+	set_filter((flags), (filter), sizeof(filter) / sizeof((filter)[0]))
+
+// Runs `fn` in a child process and returns the wait status.
+static int run_in_child(int (*fn)(void))
+{
+	pid_t pid;
+	int status;
+
+	pid = fork();
+	if (pid < 0)
+		return -1;
+	if (pid == 0)
+		_exit(fn());
+
+	if (waitpid(pid, &status, 0) != pid)
+		return -1;
+	return status;
+}
+
+FN_TEST(no_new_privs)
+{
+	TEST_RES(prctl(PR_GET_NO_NEW_PRIVS, 0, 0, 0, 0), _ret == 0);
+	TEST_ERRNO(prctl(PR_SET_NO_NEW_PRIVS, 0, 0, 0, 0), EINVAL);
+	TEST_ERRNO(prctl(PR_SET_NO_NEW_PRIVS, 1, 1, 0, 0), EINVAL);
+	TEST_ERRNO(prctl(PR_GET_NO_NEW_PRIVS, 1, 0, 0, 0), EINVAL);
+
+	TEST_SUCC(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+	TEST_RES(prctl(PR_GET_NO_NEW_PRIVS, 0, 0, 0, 0), _ret == 1);
+}
+END_TEST()
+
+static struct sock_filter no_ret_filter[] = {
+	LOAD_NR,
+};
+
+static struct sock_filter bad_jump_filter[] = {
+	LOAD_NR,
+	JEQ(0, 0, 1),
+	RET(SECCOMP_RET_ALLOW),
+};
+
+static struct sock_filter bad_load_filter[] = {
+	BPF_STMT(BPF_LD | BPF_W | BPF_ABS, 1),
+	RET(SECCOMP_RET_ALLOW),
+};
+
+static struct sock_filter big_load_filter[] = {
+	BPF_STMT(BPF_LD | BPF_W | BPF_ABS, sizeof(struct seccomp_data)),
+	RET(SECCOMP_RET_ALLOW),
+};
+
+static struct sock_filter byte_load_filter[] = {
+	BPF_STMT(BPF_LD | BPF_B | BPF_ABS, 0),
+	RET(SECCOMP_RET_ALLOW),
+};
+
+static struct sock_filter div_zero_filter[] = {
+	BPF_STMT(BPF_ALU | BPF_DIV | BPF_K, 0),
+	RET(SECCOMP_RET_ALLOW),
+};
+
+static struct sock_filter bad_mem_filter[] = {
+	BPF_STMT(BPF_ST, BPF_MEMWORDS),
+	RET(SECCOMP_RET_ALLOW),
+};
+
+FN_TEST(invalid_filter)
+{
+	unsigned int action;
+
+	TEST_ERRNO(set_filter(0, no_ret_filter, 0), EINVAL);
+	TEST_ERRNO(SET_FILTER(0, no_ret_filter), EINVAL);
+	TEST_ERRNO(SET_FILTER(0, bad_jump_filter), EINVAL);
+	TEST_ERRNO(SET_FILTER(0, bad_load_filter), EINVAL);
+	TEST_ERRNO(SET_FILTER(0, big_load_filter), EINVAL);
+	TEST_ERRNO(SET_FILTER(0, byte_load_filter), EINVAL);
+	TEST_ERRNO(SET_FILTER(0, div_zero_filter), EINVAL);
+	TEST_ERRNO(SET_FILTER(0, bad_mem_filter), EINVAL);
+	TEST_ERRNO(SET_FILTER(1 << 31, no_ret_filter), EINVAL);
+
+	TEST_RES(prctl(PR_GET_SECCOMP, 0, 0, 0, 0), _ret == 0);
+
+	action = SECCOMP_RET_ERRNO;
+	TEST_SUCC(syscall(SYS_seccomp, SECCOMP_GET_ACTION_AVAIL, 0, &action));
+	action = 0x12340000;
+	TEST_ERRNO(syscall(SYS_seccomp, SECCOMP_GET_ACTION_AVAIL, 0, &action),
+		   EOPNOTSUPP);
+}
+END_TEST()
+
+static struct sock_filter filter[] = {
+	BPF_STMT(BPF_LD | BPF_W | BPF_ABS, offsetof(struct seccomp_data, arch)),
+	JEQ(AUDIT_ARCH_X86_64, 1, 0),
+	RET(SECCOMP_RET_KILL_PROCESS),
+	LOAD_NR,
+	JEQ(__NR_getppid, 0, 1),
+	RET(SECCOMP_RET_ERRNO | EPERM),
+	JEQ(__NR_getpgid, 0, 1),
+	RET(SECCOMP_RET_TRAP | 42),
+	JEQ(__NR_getsid, 0, 1),
+	RET(SECCOMP_RET_KILL_PROCESS),
+	JEQ(__NR_dup, 0, 3),
+	LOAD_ARG0,
+	JEQ(100, 0, 1),
+	RET(SECCOMP_RET_ERRNO | ENOTTY),
+	RET(SECCOMP_RET_ALLOW),
+};
+
+// Uses the scratch memory and the ALU to compute `nr * 2 + 1`.
+static struct sock_filter later_filter[] = {
+	LOAD_NR,
+	BPF_STMT(BPF_ST, 0),
+	BPF_STMT(BPF_LDX | BPF_W | BPF_MEM, 0),
+	BPF_STMT(BPF_ALU | BPF_ADD | BPF_X, 0),
+	BPF_STMT(BPF_ALU | BPF_OR | BPF_K, 1),
+	JEQ(__NR_getppid * 2 + 1, 0, 1),
+	RET(SECCOMP_RET_ERRNO | EACCES),
+	RET(SECCOMP_RET_ALLOW),
+};
+
+static volatile int sigsys_code, sigsys_syscall, sigsys_errno;
+
+static void handle_sigsys(int sig, siginfo_t *info, void *ucontext)
+{
+	sigsys_code = info->si_code;
+	sigsys_syscall = info->si_syscall;
+	sigsys_errno = info->si_errno;
+}
+
+static int child_getppid(void)
+{
+	return syscall(SYS_getppid) == -1 && errno == EPERM ? 0 : 1;
+}
+
+static int filtered_child(void)
+{
+	struct sigaction sa = { .sa_sigaction = handle_sigsys,
+				.sa_flags = SA_SIGINFO };
+	int status;
+
+	if (SET_FILTER(0, filter) < 0)
+		return 1;
+	if (prctl(PR_GET_SECCOMP, 0, 0, 0, 0) != 2)
+		return 2;
+
+	if (getpid() <= 0)
+		return 3;
+	if (syscall(SYS_getppid) != -1 || errno != EPERM)
+		return 4;
+	if (dup(100) != -1 || errno != ENOTTY)
+		return 5;
+	if (dup(101) != -1 || errno != EBADF)
+		return 6;
+
+	// The filter is inherited by the child process.
+	status = run_in_child(child_getppid);
+	if (!WIFEXITED(status) || WEXITSTATUS(status) != 0)
+		return 7;
+
+	if (sigaction(SIGSYS, &sa, NULL) < 0)
+		return 8;
+	syscall(SYS_getpgid, 0);
+	if (sigsys_code != SYS_SECCOMP || sigsys_syscall != __NR_getpgid ||
+	    sigsys_errno != 42)
+		return 9;
+
+	// The filter installed later takes precedence.
+	if (SET_FILTER(SECCOMP_FILTER_FLAG_TSYNC, later_filter) != 0)
+		return 10;
+	if (syscall(SYS_getppid) != -1 || errno != EACCES)
+		return 11;
+
+	syscall(SYS_getsid, 0);
+	return 12;
+}
+
+FN_TEST(filter)
+{
+	TEST_RES(run_in_child(filtered_child),
+		 WIFSIGNALED(_ret) && WTERMSIG(_ret) == SIGSYS);
+	TEST_RES(prctl(PR_GET_SECCOMP, 0, 0, 0, 0), _ret == 0);
+}
+END_TEST()
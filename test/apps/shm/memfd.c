@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <fcntl.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define PAGE_SIZE 4096
+
+#ifndef MFD_NOEXEC_SEAL
+#define MFD_NOEXEC_SEAL 0x0008U
+#endif
+#ifndef F_SEAL_EXEC
+#define F_SEAL_EXEC 0x0020
+#endif
+
+static long map_page(int fd, int prot, int flags)
+{
+	return (long)mmap(NULL, PAGE_SIZE, prot, flags, fd, 0);
+}
+
+FN_TEST(invalid_args)
+{
+	char long_name[256];
+
+	memset(long_name, 'a', sizeof(long_name) - 1);
+	long_name[sizeof(long_name) - 1] = '\0';
+
+	TEST_ERRNO(memfd_create("test", 0x1000), EINVAL);
+	TEST_ERRNO(memfd_create(long_name, 0), EINVAL);
+	TEST_ERRNO(memfd_create(NULL, 0), EFAULT);
+}
+END_TEST()
+
+FN_TEST(read_write)
+{
+	int fd;
+	char buf[6];
+	struct stat st;
+
+	fd = TEST_SUCC(memfd_create("test", MFD_CLOEXEC));
+	TEST_RES(fcntl(fd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_RES(fstat(fd, &st), st.st_size == 0 && S_ISREG(st.st_mode));
+
+	TEST_RES(write(fd, "hello", 6), _ret == 6);
+	TEST_RES(pread(fd, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+	TEST_SUCC(ftruncate(fd, PAGE_SIZE));
+	TEST_RES(fstat(fd, &st), st.st_size == PAGE_SIZE);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(shared_mapping)
+{
+	int fd;
+	char *addr;
+	char buf[6];
+	pid_t pid;
+
+	fd = TEST_SUCC(memfd_create("test", 0));
+	TEST_SUCC(ftruncate(fd, PAGE_SIZE));
+	addr = (char *)TEST_SUCC(
+		map_page(fd, PROT_READ | PROT_WRITE, MAP_SHARED));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		strcpy(addr, "hello");
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, NULL, 0), _ret == pid);
+
+	TEST_RES(strcmp(addr, "hello"), _ret == 0);
+	TEST_RES(pread(fd, buf, sizeof(buf), 0),
+		 _ret == 6 && strcmp(buf, "hello") == 0);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(no_sealing)
+{
+	int fd;
+
+	fd = TEST_SUCC(memfd_create("test", 0));
+	TEST_RES(fcntl(fd, F_GET_SEALS), _ret == F_SEAL_SEAL);
+	TEST_ERRNO(fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE), EPERM);
+	TEST_SUCC(close(fd));
+
+	TEST_ERRNO(fcntl(STDIN_FILENO, F_GET_SEALS), EINVAL);
+}
+END_TEST()
+
+FN_TEST(seal_size)
+{
+	int fd;
+
+	fd = TEST_SUCC(memfd_create("test", MFD_ALLOW_SEALING));
+	TEST_RES(fcntl(fd, F_GET_SEALS), _ret == 0);
+	TEST_SUCC(ftruncate(fd, PAGE_SIZE));
+
+	TEST_ERRNO(fcntl(fd, F_ADD_SEALS, 0x1000), EINVAL);
+	TEST_SUCC(fcntl(fd, F_ADD_SEALS, F_SEAL_SHRINK | F_SEAL_GROW));
+	TEST_RES(fcntl(fd, F_GET_SEALS),
+		 _ret == (F_SEAL_SHRINK | F_SEAL_GROW));
+
+	TEST_ERRNO(ftruncate(fd, PAGE_SIZE / 2), EPERM);
+	TEST_ERRNO(ftruncate(fd, PAGE_SIZE * 2), EPERM);
+	TEST_ERRNO(pwrite(fd, "x", 1, PAGE_SIZE), EPERM);
+	TEST_RES(pwrite(fd, "x", 1, PAGE_SIZE - 1), _ret == 1);
+
+	TEST_SUCC(fcntl(fd, F_ADD_SEALS, F_SEAL_SEAL));
+	TEST_ERRNO(fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE), EPERM);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(seal_write)
+{
+	int fd;
+	char *addr;
+
+	fd = TEST_SUCC(memfd_create("test", MFD_ALLOW_SEALING));
+	TEST_SUCC(ftruncate(fd, PAGE_SIZE));
+
+	addr = (char *)TEST_SUCC(
+		map_page(fd, PROT_READ | PROT_WRITE, MAP_SHARED));
+	TEST_ERRNO(fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE), EBUSY);
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+
+	TEST_SUCC(fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE));
+	TEST_ERRNO(write(fd, "x", 1), EPERM);
+	TEST_ERRNO(map_page(fd, PROT_READ | PROT_WRITE, MAP_SHARED), EPERM);
+
+	// Read-only and private mappings are still allowed.
+	addr = (char *)TEST_SUCC(
+		map_page(fd, PROT_READ | PROT_WRITE, MAP_PRIVATE));
+	addr[0] = 'x';
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+	addr = (char *)TEST_SUCC(map_page(fd, PROT_READ, MAP_SHARED));
+	TEST_RES(addr[0], _ret == 0);
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(seal_future_write)
+{
+	int fd;
+	char *addr;
+
+	fd = TEST_SUCC(memfd_create("test", MFD_ALLOW_SEALING));
+	TEST_SUCC(ftruncate(fd, PAGE_SIZE));
+
+	addr = (char *)TEST_SUCC(
+		map_page(fd, PROT_READ | PROT_WRITE, MAP_SHARED));
+	TEST_SUCC(fcntl(fd, F_ADD_SEALS, F_SEAL_FUTURE_WRITE));
+
+	// The existing mapping is still writable.
+	addr[0] = 'x';
+	TEST_ERRNO(pwrite(fd, "y", 1, 0), EPERM);
+	TEST_ERRNO(map_page(fd, PROT_READ | PROT_WRITE, MAP_SHARED), EPERM);
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(noexec_seal)
+{
+	int fd;
+	struct stat st;
+
+	fd = TEST_SUCC(memfd_create("test", MFD_NOEXEC_SEAL));
+	TEST_RES(fstat(fd, &st), (st.st_mode & 0777) == 0666);
+	TEST_RES(fcntl(fd, F_GET_SEALS), _ret == F_SEAL_EXEC);
+	TEST_ERRNO(fchmod(fd, 0777), EPERM);
+	TEST_SUCC(fchmod(fd, 0644));
+	TEST_SUCC(close(fd));
+}
+END_TEST()
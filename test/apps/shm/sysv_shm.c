@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <string.h>
+#include <sys/ipc.h>
+#include <sys/mman.h>
+#include <sys/shm.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define PAGE_SIZE 4096
+
+static long attach(int shmid, const void *addr, int flags)
+{
+	return (long)shmat(shmid, addr, flags);
+}
+
+static long nattch(int shmid)
+{
+	struct shmid_ds ds;
+
+	if (shmctl(shmid, IPC_STAT, &ds) < 0)
+		return -1;
+	return ds.shm_nattch;
+}
+
+FN_TEST(get_by_key)
+{
+	key_t key = 0x5157;
+	int shmid;
+	struct shmid_ds ds;
+
+	TEST_ERRNO(shmget(key, PAGE_SIZE, 0600), ENOENT);
+	TEST_ERRNO(shmget(key, 0, IPC_CREAT | 0600), EINVAL);
+
+	shmid = TEST_SUCC(shmget(key, 100, IPC_CREAT | 0600));
+	TEST_RES(shmget(key, 100, 0600), _ret == shmid);
+	TEST_RES(shmget(key, 0, IPC_CREAT | 0600), _ret == shmid);
+	TEST_ERRNO(shmget(key, 100, IPC_CREAT | IPC_EXCL | 0600), EEXIST);
+	TEST_ERRNO(shmget(key, PAGE_SIZE, 0600), EINVAL);
+
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 ds.shm_segsz == 100 && ds.shm_nattch == 0 &&
+			 ds.shm_cpid == getpid() &&
+			 (ds.shm_perm.mode & 0777) == 0600 &&
+			 ds.shm_perm.__key == key);
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+	TEST_ERRNO(shmget(key, 100, 0600), ENOENT);
+	TEST_ERRNO(shmctl(shmid, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(private_segments)
+{
+	int shmid1, shmid2;
+
+	shmid1 = TEST_SUCC(shmget(IPC_PRIVATE, PAGE_SIZE, 0600));
+	shmid2 = TEST_RES(shmget(IPC_PRIVATE, PAGE_SIZE, 0600),
+			  _ret != shmid1);
+
+	TEST_SUCC(shmctl(shmid1, IPC_RMID, NULL));
+	TEST_SUCC(shmctl(shmid2, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(attach_and_share)
+{
+	int shmid;
+	char *addr;
+	pid_t pid;
+
+	shmid = TEST_SUCC(shmget(IPC_PRIVATE, PAGE_SIZE, 0600));
+	addr = (char *)TEST_SUCC(attach(shmid, NULL, 0));
+	TEST_RES(nattch(shmid), _ret == 1);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		strcpy(addr, "hello");
+		_exit(nattch(shmid) == 2 ? 0 : 1);
+	}
+	TEST_RES(wait(NULL), _ret == pid);
+
+	TEST_RES(strcmp(addr, "hello"), _ret == 0);
+	TEST_RES(nattch(shmid), _ret == 1);
+
+	TEST_ERRNO(shmdt(addr + 1), EINVAL);
+	TEST_ERRNO(shmdt(addr + PAGE_SIZE), EINVAL);
+	TEST_SUCC(shmdt(addr));
+	TEST_RES(nattch(shmid), _ret == 0);
+	TEST_ERRNO(shmdt(addr), EINVAL);
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(attach_at_address)
+{
+	int shmid;
+	char *addr, *addr2;
+
+	shmid = TEST_SUCC(shmget(IPC_PRIVATE, PAGE_SIZE, 0600));
+	addr = (char *)TEST_SUCC(attach(shmid, NULL, 0));
+	strcpy(addr, "hello");
+
+	// Attach to an occupied address.
+	TEST_ERRNO(attach(shmid, addr, 0), EINVAL);
+	TEST_ERRNO(attach(shmid, addr + 1, 0), EINVAL);
+	TEST_ERRNO(attach(shmid, NULL, SHM_REMAP), EINVAL);
+	TEST_RES(attach(shmid, addr + 1, SHM_RND | SHM_REMAP),
+		 _ret == (long)addr);
+	TEST_RES(nattch(shmid), _ret == 1);
+	TEST_SUCC(shmdt(addr));
+
+	// Attach to a free address.
+	addr2 = (char *)TEST_SUCC(attach(shmid, addr, SHM_RDONLY));
+	TEST_RES(strcmp(addr2, "hello"), addr2 == addr && _ret == 0);
+	TEST_SUCC(shmdt(addr2));
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(remove_attached)
+{
+	key_t key = 0x5158;
+	int shmid;
+	char *addr;
+	struct shmid_ds ds;
+
+	shmid = TEST_SUCC(shmget(key, PAGE_SIZE, IPC_CREAT | 0600));
+	addr = (char *)TEST_SUCC(attach(shmid, NULL, 0));
+	strcpy(addr, "hello");
+
+	// The segment is destroyed after the last detach.
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+	TEST_ERRNO(shmget(key, PAGE_SIZE, 0600), ENOENT);
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 ds.shm_nattch == 1 && (ds.shm_perm.mode & SHM_DEST) &&
+			 ds.shm_perm.__key == IPC_PRIVATE);
+	TEST_RES(strcmp(addr, "hello"), _ret == 0);
+
+	TEST_SUCC(shmdt(addr));
+	TEST_ERRNO(shmctl(shmid, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(remove_by_munmap)
+{
+	int shmid;
+	char *addr;
+	struct shmid_ds ds;
+
+	shmid = TEST_SUCC(shmget(IPC_PRIVATE, PAGE_SIZE, 0600));
+	addr = (char *)TEST_SUCC(attach(shmid, NULL, 0));
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+	TEST_ERRNO(shmdt(addr), EINVAL);
+	TEST_ERRNO(shmctl(shmid, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(set_mode)
+{
+	int shmid;
+	char *addr;
+	struct shmid_ds ds;
+
+	shmid = TEST_SUCC(shmget(IPC_PRIVATE, PAGE_SIZE, 0600));
+	TEST_SUCC(shmctl(shmid, IPC_STAT, &ds));
+	ds.shm_perm.mode = 0400;
+	TEST_SUCC(shmctl(shmid, IPC_SET, &ds));
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 (ds.shm_perm.mode & 0777) == 0400);
+
+	addr = (char *)TEST_SUCC(attach(shmid, NULL, SHM_RDONLY));
+	TEST_SUCC(shmdt(addr));
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(invalid_args)
+{
+	TEST_ERRNO(shmctl(-1, IPC_STAT, NULL), EINVAL);
+	TEST_ERRNO(shmctl(0x7fffffff, IPC_RMID, NULL), EINVAL);
+	TEST_ERRNO(attach(-1, NULL, 0), EINVAL);
+	TEST_ERRNO(attach(0x7fffffff, NULL, 0), EINVAL);
+}
+END_TEST()
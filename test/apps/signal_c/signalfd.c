@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <poll.h>
+#include <signal.h>
+#include <sys/signalfd.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static sigset_t mask;
+
+FN_SETUP(block_signals)
+{
+	sigemptyset(&mask);
+	sigaddset(&mask, SIGUSR1);
+	sigaddset(&mask, SIGUSR2);
+	CHECK(sigprocmask(SIG_BLOCK, &mask, NULL));
+}
+END_SETUP()
+
+FN_TEST(invalid_args)
+{
+	int fd;
+
+	TEST_ERRNO(signalfd(-1, &mask, -1), EINVAL);
+	TEST_ERRNO(syscall(SYS_signalfd4, -1, &mask, 4, 0), EINVAL);
+	TEST_ERRNO(signalfd(-2, &mask, 0), EBADF);
+	TEST_ERRNO(signalfd(STDIN_FILENO, &mask, 0), EINVAL);
+
+	fd = TEST_SUCC(signalfd(-1, &mask, SFD_CLOEXEC));
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(read_signals)
+{
+	int fd;
+	struct signalfd_siginfo info[2];
+	char small_buf[sizeof(struct signalfd_siginfo) - 1];
+	struct pollfd pfd;
+
+	fd = TEST_SUCC(signalfd(-1, &mask, SFD_NONBLOCK));
+	TEST_ERRNO(read(fd, info, sizeof(info)), EAGAIN);
+
+	pfd.fd = fd;
+	pfd.events = POLLIN;
+	TEST_RES(poll(&pfd, 1, 0), _ret == 0);
+
+	TEST_SUCC(kill(getpid(), SIGUSR1));
+	TEST_SUCC(kill(getpid(), SIGUSR2));
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLIN);
+
+	TEST_ERRNO(read(fd, small_buf, sizeof(small_buf)), EINVAL);
+	TEST_RES(read(fd, info, sizeof(info)),
+		 _ret == sizeof(info) && info[0].ssi_signo == SIGUSR1 &&
+			 info[0].ssi_pid == getpid() &&
+			 info[0].ssi_uid == getuid() &&
+			 info[1].ssi_signo == SIGUSR2);
+	TEST_ERRNO(read(fd, info, sizeof(info)), EAGAIN);
+	TEST_RES(poll(&pfd, 1, 0), _ret == 0);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(update_mask)
+{
+	int fd;
+	sigset_t usr1_mask;
+	struct signalfd_siginfo info;
+
+	sigemptyset(&usr1_mask);
+	sigaddset(&usr1_mask, SIGUSR1);
+
+	fd = TEST_SUCC(signalfd(-1, &usr1_mask, SFD_NONBLOCK));
+	TEST_SUCC(kill(getpid(), SIGUSR2));
+	TEST_ERRNO(read(fd, &info, sizeof(info)), EAGAIN);
+
+	TEST_RES(signalfd(fd, &mask, 0), _ret == fd);
+	TEST_RES(read(fd, &info, sizeof(info)),
+		 _ret == sizeof(info) && info.ssi_signo == SIGUSR2);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(blocking_read)
+{
+	int fd;
+	pid_t pid;
+	struct signalfd_siginfo info;
+
+	fd = TEST_SUCC(signalfd(-1, &mask, 0));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		usleep(100 * 1000);
+		kill(getppid(), SIGUSR1);
+		_exit(0);
+	}
+
+	TEST_RES(read(fd, &info, sizeof(info)),
+		 _ret == sizeof(info) && info.ssi_signo == SIGUSR1 &&
+			 info.ssi_pid == pid);
+	TEST_RES(waitpid(pid, NULL, 0), _ret == pid);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
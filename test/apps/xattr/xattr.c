@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../network/test.h"
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <sys/xattr.h>
+#include <unistd.h>
+
+static char buf[4096];
+static char big_value[2048];
+
+/*
+ * Returns whether the NUL-separated list of `len` bytes contains `name`.
+ */
+static int list_contains(const char *list, ssize_t len, const char *name)
+{
+	const char *ptr;
+
+	for (ptr = list; ptr < list + len; ptr += strlen(ptr) + 1)
+		if (strcmp(ptr, name) == 0)
+			return 1;
+	return 0;
+}
+
+static int test_set_get(const char *path, const char *link_path)
+{
+	if (getxattr(path, "user.foo", buf, sizeof(buf)) != -1 ||
+	    errno != ENODATA)
+		return -1;
+	if (setxattr(path, "user.foo", "bar", 3, XATTR_REPLACE) != -1 ||
+	    errno != ENODATA)
+		return -1;
+	if (setxattr(path, "user.foo", "bar", 3, XATTR_CREATE) < 0)
+		return -1;
+	if (setxattr(path, "user.foo", "baz", 3, XATTR_CREATE) != -1 ||
+	    errno != EEXIST)
+		return -1;
+
+	if (getxattr(path, "user.foo", NULL, 0) != 3)
+		return -1;
+	if (getxattr(path, "user.foo", buf, sizeof(buf)) != 3 ||
+	    memcmp(buf, "bar", 3) != 0)
+		return -1;
+	if (getxattr(path, "user.foo", buf, 2) != -1 || errno != ERANGE)
+		return -1;
+
+	// The symbolic link is followed unless the `l*` variants are used
+	if (setxattr(link_path, "user.foo", "hello", 5, XATTR_REPLACE) < 0)
+		return -1;
+	if (getxattr(link_path, "user.foo", buf, sizeof(buf)) != 5 ||
+	    memcmp(buf, "hello", 5) != 0)
+		return -1;
+	if (lgetxattr(link_path, "user.foo", buf, sizeof(buf)) != -1 ||
+	    errno != ENODATA)
+		return -1;
+	if (lsetxattr(link_path, "user.foo", "bar", 3, 0) != -1 ||
+	    errno != EPERM)
+		return -1;
+
+	// The value is too large to be stored in the inode
+	memset(big_value, 'x', sizeof(big_value));
+	if (setxattr(path, "user.big", big_value, sizeof(big_value), 0) < 0)
+		return -1;
+	if (getxattr(path, "user.big", buf, sizeof(buf)) != sizeof(big_value) ||
+	    memcmp(buf, big_value, sizeof(big_value)) != 0)
+		return -1;
+
+	if (setxattr(path, "user.empty", "", 0, 0) < 0)
+		return -1;
+	if (getxattr(path, "user.empty", buf, sizeof(buf)) != 0)
+		return -1;
+
+	errno = 0;
+	return 0;
+}
+
+static int test_list_remove(const char *path, const char *link_path)
+{
+	ssize_t len;
+	int fd;
+
+	len = listxattr(path, NULL, 0);
+	if (len <= 0)
+		return -1;
+	if (listxattr(path, buf, 1) != -1 || errno != ERANGE)
+		return -1;
+	if (listxattr(path, buf, sizeof(buf)) != len ||
+	    !list_contains(buf, len, "user.foo") ||
+	    !list_contains(buf, len, "user.big") ||
+	    !list_contains(buf, len, "user.empty"))
+		return -1;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	if (fsetxattr(fd, "user.fd", "1", 1, 0) < 0)
+		return -1;
+	if (fgetxattr(fd, "user.fd", buf, sizeof(buf)) != 1 || buf[0] != '1')
+		return -1;
+	if (fremovexattr(fd, "user.fd") < 0)
+		return -1;
+	if (fremovexattr(fd, "user.fd") != -1 || errno != ENODATA)
+		return -1;
+	close(fd);
+
+	if (removexattr(path, "user.foo") < 0 ||
+	    removexattr(link_path, "user.big") < 0 ||
+	    lremovexattr(path, "user.empty") < 0)
+		return -1;
+	if (removexattr(path, "user.foo") != -1 || errno != ENODATA)
+		return -1;
+
+	len = listxattr(path, buf, sizeof(buf));
+	if (len < 0 || list_contains(buf, len, "user.foo") ||
+	    list_contains(buf, len, "user.big") ||
+	    list_contains(buf, len, "user.empty"))
+		return -1;
+
+	errno = 0;
+	return 0;
+}
+
+static int test_xattr(const char *dir)
+{
+	char path[PATH_MAX], link_path[PATH_MAX];
+	int fd, ret;
+
+	snprintf(path, sizeof(path), "%s/xattr_file", dir);
+	snprintf(link_path, sizeof(link_path), "%s/xattr_link", dir);
+
+	fd = open(path, O_CREAT | O_WRONLY, 0644);
+	if (fd < 0)
+		return -1;
+	close(fd);
+	if (symlink(path, link_path) < 0)
+		return -1;
+
+	ret = test_set_get(path, link_path);
+	if (ret == 0)
+		ret = test_list_remove(path, link_path);
+
+	unlink(link_path);
+	unlink(path);
+
+	return ret;
+}
+
+FN_TEST(invalid_args)
+{
+	static char long_name[XATTR_NAME_MAX + 16];
+
+	memcpy(long_name, "user.", 5);
+	memset(long_name + 5, 'a', sizeof(long_name) - 6);
+
+	TEST_ERRNO(setxattr("/tmp", "user.foo", "bar", 3, 4), EINVAL);
+	TEST_ERRNO(setxattr("/tmp", "foo", "bar", 3, 0), EOPNOTSUPP);
+	TEST_ERRNO(getxattr("/tmp", "unknown.foo", buf, sizeof(buf)),
+		   EOPNOTSUPP);
+	TEST_ERRNO(setxattr("/tmp", "", "bar", 3, 0), ERANGE);
+	TEST_ERRNO(setxattr("/tmp", long_name, "bar", 3, 0), ERANGE);
+	TEST_ERRNO(getxattr("/tmp", long_name, buf, sizeof(buf)), ERANGE);
+	TEST_ERRNO(setxattr("/nonexistent", "user.foo", "bar", 3, 0), ENOENT);
+	TEST_ERRNO(fsetxattr(-1, "user.foo", "bar", 3, 0), EBADF);
+}
+END_TEST()
+
+FN_TEST(ramfs)
+{
+	TEST_SUCC(test_xattr("/tmp"));
+}
+END_TEST()
+
+FN_TEST(ext2)
+{
+	TEST_SUCC(test_xattr("/ext2"));
+}
+END_TEST()
+
+FN_TEST(ext4)
+{
+	TEST_SUCC(test_xattr("/ext4"));
+}
+END_TEST()